@@ -68,6 +68,8 @@ impl VkXml {
     fn add_externs() {
         Self::add_extern("xcb_connection_t");
         Self::add_extern("xcb_window_t");
+        Self::add_extern("wl_display");
+        Self::add_extern("wl_surface");
     }
 
     fn add_extern(name: &str) {
@@ -654,7 +654,7 @@ impl VkFuncDeclMember {
             static ref RE_ALL_TAGS: regex::Regex = regex::Regex::new(r"</?[^>]*>").expect("regex");
             static ref RE_ALL_SPACES: regex::Regex = regex::Regex::new(r"\s\s+").expect("regex");
             static ref RE_TYPE_NAME_MEMBERS: regex::Regex = regex::Regex::new(
-                r"typedef\s(.*?)\s\(VKAPI_PTR\s\*\s(.*?)\s\)\((?:\s|void)(.*?)\);"
+                r"typedef\s(.*?)\s\(VKAPI_PTR\s\*\s(.*?)\s\)\((?:\s|void)(.*?\))\;"
             )
             .expect("regex");
             static ref RE_MEMBER: regex::Regex =
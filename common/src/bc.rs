@@ -0,0 +1,179 @@
+//! Decompression of BC1, BC2 and BC3 blocks, which encode 4×4 texels each.
+// NOTE: https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html#S3TC
+
+use crate::format;
+use crate::math::Format;
+
+/// Width and height of block in texels.
+pub const BLOCK_SIZE: u32 = 4;
+
+/// Reads texel at `x` and `y` within block of format from bytes, decoding sRGB color
+/// components.
+pub fn read_texel(format: Format, block: &[u8], x: u32, y: u32) -> [f32; 4] {
+    let texels = decode_block(format, block);
+    let decoded_format = if format.is_srgb() {
+        Format::R8G8B8A8Srgb
+    } else {
+        Format::R8G8B8A8Unorm
+    };
+    format::read_texel(decoded_format, &texels[(y * BLOCK_SIZE + x) as usize])
+}
+
+/// Decodes block of format to RGBA8 texels in row-major order.
+pub fn decode_block(format: Format, block: &[u8]) -> [[u8; 4]; 16] {
+    match format {
+        Format::Bc1RgbUnormBlock | Format::Bc1RgbSrgbBlock => decode_color(block, Some(false)),
+        Format::Bc1RgbaUnormBlock | Format::Bc1RgbaSrgbBlock => decode_color(block, Some(true)),
+        Format::Bc2UnormBlock | Format::Bc2SrgbBlock => {
+            let mut texels = decode_color(&block[8..], None);
+            let alphas = read_u64(&block[..8]);
+            for (i, texel) in texels.iter_mut().enumerate() {
+                texel[3] = ((alphas >> (4 * i)) & 0xf) as u8 * 17;
+            }
+            texels
+        }
+        Format::Bc3UnormBlock | Format::Bc3SrgbBlock => {
+            let mut texels = decode_color(&block[8..], None);
+            let alphas = decode_alpha(&block[..8]);
+            for (texel, alpha) in texels.iter_mut().zip(alphas) {
+                texel[3] = alpha;
+            }
+            texels
+        }
+        _ => unreachable!("{format:?} is not BC1, BC2 or BC3"),
+    }
+}
+
+/// Decodes color block made of two RGB565 endpoints and 2 bit palette index per texel.
+///
+/// BC1 blocks pass whether format has alpha and switch to three colors plus black when the first
+/// endpoint isn't greater than the second one, which is transparent if format has alpha. BC2 and
+/// BC3 blocks pass `None` and always use four colors.
+fn decode_color(block: &[u8], bc1_alpha: Option<bool>) -> [[u8; 4]; 16] {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let [c0, c1] = [color0, color1].map(expand_rgb565);
+    let mix = |w0: u32, w1: u32| -> [u8; 4] {
+        let mut color = [0, 0, 0, 255];
+        for (component, (&a, &b)) in color.iter_mut().zip(c0.iter().zip(&c1)) {
+            let sum = w0 * a as u32 + w1 * b as u32;
+            *component = ((sum + (w0 + w1) / 2) / (w0 + w1)) as u8;
+        }
+        color
+    };
+    let palette = match bc1_alpha {
+        Some(has_alpha) if color0 <= color1 => {
+            let black = [0, 0, 0, if has_alpha { 0 } else { 255 }];
+            [mix(1, 0), mix(0, 1), mix(1, 1), black]
+        }
+        _ => [mix(1, 0), mix(0, 1), mix(2, 1), mix(1, 2)],
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    std::array::from_fn(|i| palette[((indices >> (2 * i)) & 0x3) as usize])
+}
+
+/// Decodes BC3 alpha block made of two 8 bit endpoints and 3 bit palette index per texel.
+fn decode_alpha(block: &[u8]) -> [u8; 16] {
+    let (alpha0, alpha1) = (block[0] as u32, block[1] as u32);
+    let mix = |w0: u32, w1: u32| ((w0 * alpha0 + w1 * alpha1 + (w0 + w1) / 2) / (w0 + w1)) as u8;
+    let palette = if alpha0 > alpha1 {
+        [
+            mix(1, 0),
+            mix(0, 1),
+            mix(6, 1),
+            mix(5, 2),
+            mix(4, 3),
+            mix(3, 4),
+            mix(2, 5),
+            mix(1, 6),
+        ]
+    } else {
+        [
+            mix(1, 0),
+            mix(0, 1),
+            mix(4, 1),
+            mix(3, 2),
+            mix(2, 3),
+            mix(1, 4),
+            0,
+            255,
+        ]
+    };
+    let indices = read_u64(block) >> 16;
+    std::array::from_fn(|i| palette[((indices >> (3 * i)) & 0x7) as usize])
+}
+
+/// Expands RGB565 color to 8 bits per component by replicating the high bits into the low ones.
+const fn expand_rgb565(color: u16) -> [u8; 3] {
+    let r = ((color >> 11) & 0x1f) as u8;
+    let g = ((color >> 5) & 0x3f) as u8;
+    let b = (color & 0x1f) as u8;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut le_bytes = [0u8; 8];
+    le_bytes.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(le_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Color block with given endpoints and texel `i` using palette index `i % 4`.
+    fn color_block(color0: u16, color1: u16) -> [u8; 8] {
+        let [a, b] = color0.to_le_bytes();
+        let [c, d] = color1.to_le_bytes();
+        [a, b, c, d, 0xe4, 0xe4, 0xe4, 0xe4]
+    }
+
+    #[test]
+    fn bc1_transparent_black_only_with_alpha() {
+        let block = color_block(0x001f, 0xf800);
+        let rgba = decode_block(Format::Bc1RgbaUnormBlock, &block);
+        assert_eq!(
+            rgba[..4],
+            [
+                [0, 0, 255, 255],
+                [255, 0, 0, 255],
+                [128, 0, 128, 255],
+                [0, 0, 0, 0]
+            ]
+        );
+        let rgb = decode_block(Format::Bc1RgbUnormBlock, &block);
+        assert_eq!(rgb[3], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn bc2_explicit_alpha() {
+        let mut block = [0u8; 16];
+        block[..8].copy_from_slice(&0xfedc_ba98_7654_3210_u64.to_le_bytes());
+        // Four color mode is used although the first endpoint isn't greater.
+        block[8..].copy_from_slice(&color_block(0x001f, 0xf800));
+        let texels = decode_block(Format::Bc2UnormBlock, &block);
+        for (i, texel) in texels.iter().enumerate() {
+            assert_eq!(texel[3], i as u8 * 17);
+        }
+        assert_eq!(texels[2], [85, 0, 170, 34]);
+    }
+
+    #[test]
+    fn bc3_interpolated_alpha() {
+        let mut block = [0u8; 16];
+        // Texel `i` uses alpha palette index `i % 8`.
+        let indices = (0..16_u64).fold(0, |indices, i| indices | ((i % 8) << (3 * i)));
+        block[..8].copy_from_slice(&((indices << 16) | 0x00ff).to_le_bytes());
+        block[8..].copy_from_slice(&color_block(0xf800, 0x001f));
+        let alphas = decode_block(Format::Bc3UnormBlock, &block).map(|texel| texel[3]);
+        assert_eq!(alphas[..8], [255, 0, 219, 182, 146, 109, 73, 36]);
+
+        block[..2].copy_from_slice(&[0x00, 0xff]);
+        let alphas = decode_block(Format::Bc3UnormBlock, &block).map(|texel| texel[3]);
+        assert_eq!(alphas[..8], [0, 255, 51, 102, 153, 204, 0, 255]);
+    }
+}
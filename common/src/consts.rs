@@ -2,7 +2,7 @@ pub const MAX_VERTEX_ATTRIBUTES: u32 = 16;
 pub const MAX_VERTEX_BINDINGS: u32 = 16;
 pub const MAX_VERTEX_ATTRIBUTE_OFFSET: u32 = 2047;
 pub const MAX_VERTEX_BINDING_STRIDE: u32 = 2048;
-pub const MAX_VIEWPORTS: u32 = 15;
+pub const MAX_VIEWPORTS: u32 = 1;
 pub const MAX_VIEWPORT_DIMENSIONS: (u32, u32) = (16384, 16384);
 pub const VIEWPORT_BOUNDS_RANGE: (f32, f32) = (
     -2.0 * MAX_VIEWPORT_DIMENSIONS.0 as f32,
@@ -10,3 +10,19 @@ pub const VIEWPORT_BOUNDS_RANGE: (f32, f32) = (
 );
 pub const MAX_CLIP_DISTANCES: u32 = 4;
 pub const MAX_CULL_DISTANCES: u32 = 4;
+pub const MAX_COLOR_ATTACHMENTS: u32 = 8;
+pub const MAX_BOUND_DESCRIPTOR_SETS: u32 = 4;
+pub const MAX_SAMPLER_LOD_BIAS: f32 = 2.0;
+pub const MAX_PUSH_CONSTANTS_SIZE: u32 = 128;
+pub const MIN_UNIFORM_BUFFER_OFFSET_ALIGNMENT: u64 = 16;
+pub const MIN_STORAGE_BUFFER_OFFSET_ALIGNMENT: u64 = 16;
+pub const LINE_WIDTH_RANGE: (f32, f32) = (1.0, 8.0);
+pub const LINE_WIDTH_GRANULARITY: f32 = 1.0;
+pub const POINT_SIZE_RANGE: (f32, f32) = (1.0, 64.0);
+pub const POINT_SIZE_GRANULARITY: f32 = 1.0;
+/// Supported sample counts, each count being its own bit as in `VkSampleCountFlagBits`.
+pub const SAMPLE_COUNTS: u32 = 1 | 2 | 4;
+pub const MAX_COMPUTE_SHARED_MEMORY_SIZE: u32 = 16384;
+pub const MAX_COMPUTE_WORK_GROUP_COUNT: [u32; 3] = [65535, 65535, 65535];
+pub const MAX_COMPUTE_WORK_GROUP_INVOCATIONS: u32 = 128;
+pub const MAX_COMPUTE_WORK_GROUP_SIZE: [u32; 3] = [128, 128, 64];
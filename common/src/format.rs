@@ -0,0 +1,484 @@
+//! Conversion between texels stored in memory and float components.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fundamentals-numerics
+
+use crate::math::{linear_to_srgb, srgb_to_linear, Format};
+
+/// Interpretation of bits of format components.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NumericFormat {
+    Unorm,
+    Snorm,
+    Uint,
+    Sint,
+    Sfloat,
+    Ufloat,
+    /// Unsigned normalized color components encoded with sRGB transfer function.
+    Srgb,
+}
+
+/// Reads texel of format from bytes, substituting components missing from format with
+/// (0, 0, 0, 1).
+pub fn read_texel(format: Format, bytes: &[u8]) -> [f32; 4] {
+    let info = format.info();
+    let mut texel = [0.0f32, 0.0f32, 0.0f32, 1.0f32];
+    if let Some(fields) = packed_fields(format) {
+        let packed = read_uint(&bytes[..info.bytes_per_pixel as usize]);
+        for (component, &(offset, bits)) in texel.iter_mut().zip(fields) {
+            let raw = (packed >> offset) & mask(bits);
+            *component = decode_component(raw, bits, info.numeric_format);
+        }
+    } else {
+        let size = (info.bytes_per_pixel / info.component_count) as usize;
+        let components = bytes.chunks_exact(size).take(info.component_count as usize);
+        for (component, bytes) in texel.iter_mut().zip(components) {
+            *component = decode_component(read_uint(bytes), size as u32 * 8, info.numeric_format);
+        }
+    }
+    if format.is_bgr() {
+        texel.swap(0, 2);
+    }
+    if format.is_srgb() {
+        for component in &mut texel[..3] {
+            *component = srgb_to_linear(*component);
+        }
+    }
+    texel
+}
+
+/// Writes texel of format to bytes, ignoring components missing from format.
+pub fn write_texel(format: Format, texel: [f32; 4], bytes: &mut [u8]) {
+    let info = format.info();
+    let mut texel = texel;
+    if format.is_srgb() {
+        for component in &mut texel[..3] {
+            *component = linear_to_srgb(*component);
+        }
+    }
+    if format.is_bgr() {
+        texel.swap(0, 2);
+    }
+    if let Some(fields) = packed_fields(format) {
+        // Bits not covered by components, such as stencil of depth/stencil formats, are kept.
+        let bytes = &mut bytes[..info.bytes_per_pixel as usize];
+        let mut packed = read_uint(bytes);
+        for (&component, &(offset, bits)) in texel.iter().zip(fields) {
+            packed &= !(mask(bits) << offset);
+            packed |= encode_component(component, bits, info.numeric_format) << offset;
+        }
+        write_uint(packed, bytes);
+    } else {
+        let size = (info.bytes_per_pixel / info.component_count) as usize;
+        let components = bytes
+            .chunks_exact_mut(size)
+            .take(info.component_count as usize);
+        for (&component, bytes) in texel.iter().zip(components) {
+            let raw = encode_component(component, size as u32 * 8, info.numeric_format);
+            write_uint(raw, bytes);
+        }
+    }
+}
+
+/// Reads raw bits of every component of texel of format from bytes in RGBA order, substituting
+/// zero for components missing from format.
+pub fn read_raw_texel(format: Format, bytes: &[u8]) -> [u64; 4] {
+    let info = format.info();
+    let mut texel = [0u64; 4];
+    if let Some(fields) = packed_fields(format) {
+        let packed = read_uint(&bytes[..info.bytes_per_pixel as usize]);
+        for (component, &(offset, bits)) in texel.iter_mut().zip(fields) {
+            *component = (packed >> offset) & mask(bits);
+        }
+    } else {
+        let size = (info.bytes_per_pixel / info.component_count) as usize;
+        let components = bytes.chunks_exact(size).take(info.component_count as usize);
+        for (component, bytes) in texel.iter_mut().zip(components) {
+            *component = read_uint(bytes);
+        }
+    }
+    if format.is_bgr() {
+        texel.swap(0, 2);
+    }
+    texel
+}
+
+/// Writes raw bits of texel components of format to bytes, truncating them to component size.
+pub fn write_raw_texel(format: Format, texel: [u64; 4], bytes: &mut [u8]) {
+    let info = format.info();
+    let mut texel = texel;
+    if format.is_bgr() {
+        texel.swap(0, 2);
+    }
+    if let Some(fields) = packed_fields(format) {
+        let bytes = &mut bytes[..info.bytes_per_pixel as usize];
+        let mut packed = read_uint(bytes);
+        for (&component, &(offset, bits)) in texel.iter().zip(fields) {
+            packed &= !(mask(bits) << offset);
+            packed |= (component & mask(bits)) << offset;
+        }
+        write_uint(packed, bytes);
+    } else {
+        let size = (info.bytes_per_pixel / info.component_count) as usize;
+        let components = bytes
+            .chunks_exact_mut(size)
+            .take(info.component_count as usize);
+        for (&component, bytes) in texel.iter().zip(components) {
+            write_uint(component, bytes);
+        }
+    }
+}
+
+/// Reads components of texel of integer format from bytes as 32-bit integers, sign-extending
+/// components of signed formats and substituting components missing from format with
+/// (0, 0, 0, 1).
+pub fn read_integer_texel(format: Format, bytes: &[u8]) -> [u32; 4] {
+    let info = format.info();
+    let decode = |raw: u64, bits: u32| match info.numeric_format {
+        NumericFormat::Sint => sign_extend(raw, bits) as u32,
+        _ => raw as u32,
+    };
+    let mut texel = [0, 0, 0, 1];
+    if let Some(fields) = packed_fields(format) {
+        let packed = read_uint(&bytes[..info.bytes_per_pixel as usize]);
+        for (component, &(offset, bits)) in texel.iter_mut().zip(fields) {
+            *component = decode((packed >> offset) & mask(bits), bits);
+        }
+    } else {
+        let size = (info.bytes_per_pixel / info.component_count) as usize;
+        let components = bytes.chunks_exact(size).take(info.component_count as usize);
+        for (component, bytes) in texel.iter_mut().zip(components) {
+            *component = decode(read_uint(bytes), size as u32 * 8);
+        }
+    }
+    if format.is_bgr() {
+        texel.swap(0, 2);
+    }
+    texel
+}
+
+/// Reads stencil value of texel of format with stencil aspect from bytes.
+pub fn read_stencil(format: Format, bytes: &[u8]) -> u8 {
+    let offset = stencil_offset(format);
+    let packed = read_uint(&bytes[..format.info().bytes_per_pixel as usize]);
+    (packed >> offset) as u8
+}
+
+/// Writes stencil value of texel of format with stencil aspect to bytes, keeping depth.
+pub fn write_stencil(format: Format, stencil: u8, bytes: &mut [u8]) {
+    let offset = stencil_offset(format);
+    let bytes = &mut bytes[..format.info().bytes_per_pixel as usize];
+    let packed = read_uint(bytes) & !(mask(8) << offset);
+    write_uint(packed | (stencil as u64) << offset, bytes);
+}
+
+/// Returns bit offset of 8 bit stencil component of format.
+fn stencil_offset(format: Format) -> u32 {
+    match format {
+        Format::S8Uint => 0,
+        Format::D24UnormS8Uint => 24,
+        _ => unreachable!("{format:?} has no stencil aspect"),
+    }
+}
+
+/// Returns bit offset and bit count of every component of packed format, in RGBA order.
+const fn packed_fields(format: Format) -> Option<&'static [(u32, u32)]> {
+    match format {
+        Format::A2b10g10r10UnormPack32 | Format::A2b10g10r10UintPack32 => {
+            Some(&[(0, 10), (10, 10), (20, 10), (30, 2)])
+        }
+        Format::B10g11r11UfloatPack32 => Some(&[(0, 11), (11, 11), (22, 10)]),
+        Format::D24UnormS8Uint => Some(&[(0, 24)]),
+        _ => None,
+    }
+}
+
+const fn mask(bits: u32) -> u64 {
+    if bits >= u64::BITS {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+const fn sign_extend(raw: u64, bits: u32) -> i64 {
+    let shift = u64::BITS - bits;
+    ((raw << shift) as i64) >> shift
+}
+
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fundamentals-fixedfpconv
+fn decode_component(raw: u64, bits: u32, numeric_format: NumericFormat) -> f32 {
+    match numeric_format {
+        NumericFormat::Unorm | NumericFormat::Srgb => (raw as f64 / mask(bits) as f64) as f32,
+        NumericFormat::Snorm => {
+            let max = mask(bits - 1) as f64;
+            (sign_extend(raw, bits) as f64 / max).max(-1.0f64) as f32
+        }
+        NumericFormat::Uint => raw as f32,
+        NumericFormat::Sint => sign_extend(raw, bits) as f32,
+        NumericFormat::Sfloat => match bits {
+            16 => {
+                let value = unpack_float(raw as u32 & 0x7fff, 5, 10);
+                if raw & 0x8000 != 0 {
+                    -value
+                } else {
+                    value
+                }
+            }
+            32 => f32::from_bits(raw as u32),
+            64 => f64::from_bits(raw) as f32,
+            _ => unreachable!("{bits} bit float"),
+        },
+        NumericFormat::Ufloat => unpack_float(raw as u32, 5, bits - 5),
+    }
+}
+
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fundamentals-fpfixedconv
+fn encode_component(value: f32, bits: u32, numeric_format: NumericFormat) -> u64 {
+    match numeric_format {
+        NumericFormat::Unorm | NumericFormat::Srgb => {
+            (value.clamp(0.0f32, 1.0f32) as f64 * mask(bits) as f64).round() as u64
+        }
+        NumericFormat::Snorm => {
+            let max = mask(bits - 1) as f64;
+            let value = (value.clamp(-1.0f32, 1.0f32) as f64 * max).round() as i64;
+            value as u64 & mask(bits)
+        }
+        NumericFormat::Uint => (value.round() as f64).clamp(0.0f64, mask(bits) as f64) as u64,
+        NumericFormat::Sint => {
+            let max = mask(bits - 1) as f64;
+            let value = (value.round() as f64).clamp(-max - 1.0f64, max) as i64;
+            value as u64 & mask(bits)
+        }
+        NumericFormat::Sfloat => match bits {
+            16 => {
+                let sign = if value.is_sign_negative() { 0x8000 } else { 0 };
+                sign | pack_float(value.abs(), 5, 10) as u64
+            }
+            32 => value.to_bits() as u64,
+            64 => (value as f64).to_bits(),
+            _ => unreachable!("{bits} bit float"),
+        },
+        NumericFormat::Ufloat => {
+            // Negative values are converted to zero.
+            if value < 0.0f32 {
+                0
+            } else {
+                pack_float(value, 5, bits - 5) as u64
+            }
+        }
+    }
+}
+
+/// Converts unsigned float with given exponent and mantissa bits to `f32`.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fundamentals-fp11
+fn unpack_float(raw: u32, exponent_bits: u32, mantissa_bits: u32) -> f32 {
+    let exponent_max = (1 << exponent_bits) - 1;
+    let bias = (exponent_max >> 1) as i32;
+    let exponent = (raw >> mantissa_bits) & exponent_max;
+    let mantissa = raw & ((1 << mantissa_bits) - 1);
+    if exponent == 0 {
+        mantissa as f32 * 2.0f32.powi(1 - bias - mantissa_bits as i32)
+    } else if exponent == exponent_max {
+        if mantissa == 0 {
+            f32::INFINITY
+        } else {
+            f32::NAN
+        }
+    } else {
+        let mantissa = (1 << mantissa_bits) | mantissa;
+        mantissa as f32 * 2.0f32.powi(exponent as i32 - bias - mantissa_bits as i32)
+    }
+}
+
+/// Converts non-negative `f32` to unsigned float with given exponent and mantissa bits, rounding
+/// to nearest even.
+const fn pack_float(value: f32, exponent_bits: u32, mantissa_bits: u32) -> u32 {
+    let exponent_max = (1 << exponent_bits) - 1;
+    if value.is_nan() {
+        return (exponent_max << mantissa_bits) | 1;
+    }
+    let bias = (exponent_max >> 1) as i32;
+    let bits = value.to_bits();
+    let exponent = (bits >> 23) as i32 - 127 + bias;
+    let mantissa = bits & 0x7f_ffff;
+    let shift = 23 - mantissa_bits;
+    if exponent >= exponent_max as i32 {
+        exponent_max << mantissa_bits
+    } else if exponent > 0 {
+        // Rounding may carry into exponent, overflowing to infinity.
+        let packed = ((exponent as u32) << 23) | mantissa;
+        round_shift_right(packed, shift)
+    } else {
+        // Denormalized value including implicit leading one.
+        let shift = shift + (1 - exponent) as u32;
+        if shift > 24 {
+            0
+        } else {
+            round_shift_right(mantissa | 0x80_0000, shift)
+        }
+    }
+}
+
+const fn round_shift_right(value: u32, shift: u32) -> u32 {
+    let result = value >> shift;
+    let rest = value & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    if rest > half || (rest == half && result & 1 == 1) {
+        result + 1
+    } else {
+        result
+    }
+}
+
+fn read_uint(bytes: &[u8]) -> u64 {
+    fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+        bytes.try_into().unwrap_or_else(|_| unreachable!())
+    }
+    match bytes.len() {
+        1 => bytes[0] as u64,
+        2 => u16::from_ne_bytes(array(bytes)) as u64,
+        4 => u32::from_ne_bytes(array(bytes)) as u64,
+        8 => u64::from_ne_bytes(array(bytes)),
+        size => unreachable!("{size} byte component"),
+    }
+}
+
+fn write_uint(value: u64, bytes: &mut [u8]) {
+    match bytes.len() {
+        1 => bytes[0] = value as u8,
+        2 => bytes.copy_from_slice(&(value as u16).to_ne_bytes()),
+        4 => bytes.copy_from_slice(&(value as u32).to_ne_bytes()),
+        8 => bytes.copy_from_slice(&value.to_ne_bytes()),
+        size => unreachable!("{size} byte component"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(format: Format, texel: [f32; 4]) -> (Vec<u8>, [f32; 4]) {
+        let mut bytes = vec![0u8; format.info().bytes_per_pixel as usize];
+        write_texel(format, texel, &mut bytes);
+        let result = read_texel(format, &bytes);
+        (bytes, result)
+    }
+
+    #[test]
+    fn unorm_round_trip() {
+        let (bytes, texel) = round_trip(Format::R8G8B8A8Unorm, [0.2, 0.4, 1.5, -1.0]);
+        assert_eq!(bytes, [51, 102, 255, 0]);
+        assert_eq!(texel, [0.2, 0.4, 1.0, 0.0]);
+
+        let (bytes, texel) = round_trip(Format::B8G8R8A8Unorm, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(bytes, [0, 0, 255, 255]);
+        assert_eq!(texel, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn snorm_round_trip() {
+        let (bytes, texel) = round_trip(Format::R8G8Snorm, [-1.0, 0.5, 0.0, 0.0]);
+        assert_eq!(bytes, [0x81, 64]);
+        assert_eq!(texel, [-1.0, 64.0 / 127.0, 0.0, 1.0]);
+
+        // Both -128 and -127 represent -1.
+        assert_eq!(read_texel(Format::R8Snorm, &[0x80]), [-1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn integer_round_trip() {
+        let (bytes, texel) = round_trip(Format::R16G16Uint, [7.0, 70000.0, 0.0, 0.0]);
+        assert_eq!(bytes, [7, 0, 255, 255]);
+        assert_eq!(texel, [7.0, 65535.0, 0.0, 1.0]);
+
+        let (bytes, texel) = round_trip(Format::R32Sint, [-5.0, 0.0, 0.0, 0.0]);
+        assert_eq!(bytes, (-5i32).to_ne_bytes());
+        assert_eq!(texel, [-5.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn sfloat_round_trip() {
+        let (bytes, texel) = round_trip(Format::R16G16Sfloat, [1.5, -2.0, 0.0, 0.0]);
+        assert_eq!(bytes, [0x00, 0x3e, 0x00, 0xc0]);
+        assert_eq!(texel, [1.5, -2.0, 0.0, 1.0]);
+
+        // Smallest half float denormal and overflow to infinity.
+        let (_, texel) = round_trip(Format::R16G16Sfloat, [2.0f32.powi(-24), 1.0e6, 0.0, 0.0]);
+        assert_eq!(texel, [2.0f32.powi(-24), f32::INFINITY, 0.0, 1.0]);
+
+        let texel = [0.1, -2.5, 1.0e10, 0.5];
+        assert_eq!(round_trip(Format::R32G32B32A32Sfloat, texel).1, texel);
+    }
+
+    #[test]
+    fn packed_round_trip() {
+        let (bytes, texel) = round_trip(Format::A2b10g10r10UnormPack32, [1.0, 0.0, 1.0, 1.0 / 3.0]);
+        assert_eq!(bytes, (0x3ffu32 | 0x3ff << 20 | 1 << 30).to_ne_bytes());
+        assert_eq!(texel, [1.0, 0.0, 1.0, 1.0 / 3.0]);
+
+        let (bytes, texel) = round_trip(Format::B10g11r11UfloatPack32, [1.0, 0.5, 2.0, 1.0]);
+        assert_eq!(bytes, (0x3c0u32 | 0x380 << 11 | 0x200 << 22).to_ne_bytes());
+        assert_eq!(texel, [1.0, 0.5, 2.0, 1.0]);
+
+        // Negative values of unsigned floats are clamped to zero.
+        assert_eq!(
+            round_trip(Format::B10g11r11UfloatPack32, [-1.0; 4]).1,
+            [0.0, 0.0, 0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn depth_stencil_aspects_are_independent() {
+        let format = Format::D24UnormS8Uint;
+        let mut bytes = vec![0u8; 4];
+        write_stencil(format, 0xa5, &mut bytes);
+        write_texel(format, [1.0, 0.0, 0.0, 0.0], &mut bytes);
+        assert_eq!(
+            u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            0xa5ff_ffff
+        );
+        assert_eq!(read_stencil(format, &bytes), 0xa5);
+        write_stencil(format, 0x01, &mut bytes);
+        assert_eq!(read_texel(format, &bytes), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(read_stencil(format, &bytes), 0x01);
+
+        let mut bytes = [0u8];
+        write_stencil(Format::S8Uint, 0x7f, &mut bytes);
+        assert_eq!(bytes, [0x7f]);
+    }
+
+    #[test]
+    fn raw_texel_truncates_components() {
+        let mut bytes = vec![0u8; 4];
+        write_raw_texel(Format::B8G8R8A8Unorm, [0x1ff, 0x02, 0x03, 0x04], &mut bytes);
+        assert_eq!(bytes, [0x03, 0x02, 0xff, 0x04]);
+        assert_eq!(
+            read_raw_texel(Format::B8G8R8A8Unorm, &bytes),
+            [0xff, 0x02, 0x03, 0x04]
+        );
+
+        let format = Format::A2b10g10r10UintPack32;
+        write_raw_texel(format, [0x7ff, 0, 0x155, 0x7], &mut bytes);
+        assert_eq!(bytes, (0x3ffu32 | 0x155 << 20 | 0x3 << 30).to_ne_bytes());
+        assert_eq!(read_raw_texel(format, &bytes), [0x3ff, 0, 0x155, 0x3]);
+    }
+
+    #[test]
+    fn integer_texel_keeps_values() {
+        let bytes = 0x8000_0001u32.to_ne_bytes();
+        assert_eq!(
+            read_integer_texel(Format::R32Uint, &bytes),
+            [0x8000_0001, 0, 0, 1]
+        );
+        assert_eq!(
+            read_integer_texel(Format::R8G8B8A8Sint, &[0xff, 0x80, 0x7f, 0x00]),
+            [-1i32 as u32, -128i32 as u32, 127, 0]
+        );
+        assert_eq!(
+            read_integer_texel(
+                Format::A2b10g10r10UintPack32,
+                &(0x3ffu32 | 0x3 << 30).to_ne_bytes()
+            ),
+            [0x3ff, 0, 0, 0x3]
+        );
+    }
+}
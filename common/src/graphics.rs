@@ -3,7 +3,8 @@ use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 
 use crate::consts::{MAX_VERTEX_ATTRIBUTES, MAX_VERTEX_BINDINGS};
-use crate::math::{Extent3, Format};
+use crate::math::{Extent3, Format, Texel};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Default)]
 pub struct VertexInputState {
@@ -63,6 +64,19 @@ pub enum VertexInputRate {
     Instance,
 }
 
+/// Interpolation of fragment shader input across primitive, as decorated in the shader.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#shaders-interpolation-decorations
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Perspective-correct interpolation.
+    #[default]
+    Smooth,
+    /// Value of provoking vertex, without interpolation.
+    Flat,
+    /// Linear interpolation in framebuffer space.
+    NoPerspective,
+}
+
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub enum PolygonMode {
     #[default]
@@ -88,6 +102,166 @@ pub enum FrontFace {
     Clockwise,
 }
 
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum CompareOp {
+    #[default]
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum StencilOp {
+    #[default]
+    Keep,
+    Zero,
+    Replace,
+    IncrementAndClamp,
+    DecrementAndClamp,
+    Invert,
+    IncrementAndWrap,
+    DecrementAndWrap,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct StencilOpState {
+    pub fail_op: StencilOp,
+    pub pass_op: StencilOp,
+    pub depth_fail_op: StencilOp,
+    pub compare_op: CompareOp,
+    pub compare_mask: u32,
+    pub write_mask: u32,
+    pub reference: u32,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Filter {
+    #[default]
+    Nearest,
+    Linear,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SamplerMipmapMode {
+    #[default]
+    Nearest,
+    Linear,
+}
+
+/// Handling of texel coordinates outside of image.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SamplerAddressMode {
+    #[default]
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+    MirrorClampToEdge,
+}
+
+/// Color returned for texels outside of image when clamping to border.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BorderColor {
+    #[default]
+    TransparentBlack,
+    OpaqueBlack,
+    OpaqueWhite,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct SamplerState {
+    pub mag_filter: Filter,
+    pub min_filter: Filter,
+    pub mipmap_mode: SamplerMipmapMode,
+    pub address_mode_u: SamplerAddressMode,
+    pub address_mode_v: SamplerAddressMode,
+    pub address_mode_w: SamplerAddressMode,
+    pub mip_lod_bias: f32,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    pub border_color: BorderColor,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BlendFactor {
+    #[default]
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+    Src1Color,
+    OneMinusSrc1Color,
+    Src1Alpha,
+    OneMinusSrc1Alpha,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BlendOp {
+    #[default]
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+/// Bitwise operation combining fragment color `s` with color attachment value `d`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum LogicOp {
+    Clear,
+    And,
+    AndReverse,
+    #[default]
+    Copy,
+    AndInverted,
+    NoOp,
+    Xor,
+    Or,
+    Nor,
+    Equivalent,
+    Invert,
+    OrReverse,
+    CopyInverted,
+    OrInverted,
+    Nand,
+    Set,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ColorComponentFlags {
+    pub r: bool,
+    pub g: bool,
+    pub b: bool,
+    pub a: bool,
+}
+
+impl Default for ColorComponentFlags {
+    fn default() -> Self {
+        Self {
+            r: true,
+            g: true,
+            b: true,
+            a: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct VertexBuffer {
     pub binding_number: VertexBindingNumber,
@@ -107,10 +281,90 @@ pub struct DescriptorBuffer {
     pub binding: MemoryBinding,
 }
 
+/// Descriptor set bound for use by shader resource variables.
+#[derive(Debug, Clone, Default)]
+pub struct DescriptorSet {
+    pub bindings: Vec<DescriptorSetBinding>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DescriptorSetBinding {
+    pub binding: u32,
+    /// Whether buffer descriptors are offset by dynamic offsets when descriptor set is bound.
+    pub dynamic: bool,
+    /// Descriptor of every array element, `None` if it is not written.
+    pub descriptors: Vec<Option<Descriptor>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Descriptor {
+    Buffer {
+        buffer: DescriptorBuffer,
+        offset: u64,
+        range: u64,
+    },
+}
+
 #[derive(Debug, Clone)]
 pub struct DescriptorImage {
     pub binding: MemoryBinding,
     pub extent: Extent3<u32>,
+    /// Swizzle applied to texels read through image view.
+    pub components: ComponentMapping,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ComponentSwizzle {
+    #[default]
+    Identity,
+    Zero,
+    One,
+    R,
+    G,
+    B,
+    A,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ComponentMapping {
+    pub r: ComponentSwizzle,
+    pub g: ComponentSwizzle,
+    pub b: ComponentSwizzle,
+    pub a: ComponentSwizzle,
+}
+
+impl ComponentMapping {
+    /// Remaps components of texel read from image of given format.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#textures-component-substitution
+    pub fn apply(&self, texel: Texel, format: Format) -> Texel {
+        let format_info = format.info();
+        let mut components = texel.get_as_f32_array();
+        // Components missing from format are substituted with (0, 0, 0, 1).
+        if format_info.component_count < 2 {
+            components[1] = 0.0f32;
+        }
+        if format_info.component_count < 3 {
+            components[2] = 0.0f32;
+        }
+        if format_info.component_count < 4 {
+            components[3] = 1.0f32;
+        }
+        let swizzle = |swizzle: ComponentSwizzle, identity: usize| match swizzle {
+            ComponentSwizzle::Identity => components[identity],
+            ComponentSwizzle::Zero => 0.0f32,
+            ComponentSwizzle::One => 1.0f32,
+            ComponentSwizzle::R => components[0],
+            ComponentSwizzle::G => components[1],
+            ComponentSwizzle::B => components[2],
+            ComponentSwizzle::A => components[3],
+        };
+        Texel::from_sfloat32_raw(
+            swizzle(self.r, 0),
+            swizzle(self.g, 1),
+            swizzle(self.b, 2),
+            swizzle(self.a, 3),
+        )
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -1,3 +1,5 @@
+pub mod bc;
 pub mod consts;
+pub mod format;
 pub mod graphics;
 pub mod math;
@@ -1,86 +1,218 @@
+use crate::bc;
+use crate::format::{read_texel, write_texel, NumericFormat};
 use std::fmt::Formatter;
-use std::ops::Range;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Format {
     R8Unorm,
+    R8Snorm,
+    R8Uint,
+    R8Sint,
     R8G8Unorm,
+    R8G8Snorm,
+    R8G8Uint,
+    R8G8Sint,
     R8G8B8A8Unorm,
+    R8G8B8A8Snorm,
+    R8G8B8A8Uint,
+    R8G8B8A8Sint,
+    R8G8B8A8Srgb,
+    B8G8R8A8Unorm,
+    B8G8R8A8Srgb,
+    R16Unorm,
+    R16Snorm,
+    R16Uint,
+    R16Sint,
+    R16Sfloat,
+    R16G16Unorm,
+    R16G16Snorm,
+    R16G16Uint,
+    R16G16Sint,
+    R16G16Sfloat,
+    R16G16B16A16Unorm,
+    R16G16B16A16Snorm,
+    R16G16B16A16Uint,
+    R16G16B16A16Sint,
+    R16G16B16A16Sfloat,
+    R32Uint,
+    R32Sint,
+    R32Sfloat,
+    R32G32Uint,
+    R32G32Sint,
+    R32G32Sfloat,
+    R32G32B32Uint,
+    R32G32B32Sint,
+    R32G32B32Sfloat,
+    R32G32B32A32Uint,
+    R32G32B32A32Sint,
     R32G32B32A32Sfloat,
     A2b10g10r10UnormPack32,
+    A2b10g10r10UintPack32,
+    B10g11r11UfloatPack32,
     D16Unorm,
+    D32Sfloat,
+    S8Uint,
+    /// Depth stored in lower 24 bits and stencil in upper 8 bits of 32 bit word.
+    D24UnormS8Uint,
+    Bc1RgbUnormBlock,
+    Bc1RgbSrgbBlock,
+    Bc1RgbaUnormBlock,
+    Bc1RgbaSrgbBlock,
+    Bc2UnormBlock,
+    Bc2SrgbBlock,
+    Bc3UnormBlock,
+    Bc3SrgbBlock,
 }
 
 pub struct FormatInfo {
+    /// Size of texel, or of whole block for block-compressed formats.
     pub bytes_per_pixel: u8,
-    pub bytes_per_component: Option<u8>,
-    pub bytes0: Option<Range<usize>>,
-    pub bytes1: Option<Range<usize>>,
-    pub bytes2: Option<Range<usize>>,
-    pub bytes3: Option<Range<usize>>,
-    pub is_unorm: bool,
+    /// Number of components stored in texel.
+    pub component_count: u8,
+    pub numeric_format: NumericFormat,
+}
+
+impl FormatInfo {
+    const fn new(bytes_per_pixel: u8, component_count: u8, numeric_format: NumericFormat) -> Self {
+        Self {
+            bytes_per_pixel,
+            component_count,
+            numeric_format,
+        }
+    }
 }
 
 impl Format {
-    #[allow(unreachable_code)]
     pub const fn info(&self) -> FormatInfo {
         match *self {
-            Self::R8Unorm => FormatInfo {
-                bytes_per_pixel: 1,
-                bytes_per_component: Some(1),
-                bytes0: Some(0..1),
-                bytes1: None,
-                bytes2: None,
-                bytes3: None,
-                is_unorm: true,
-            },
-            Self::R8G8Unorm => FormatInfo {
-                bytes_per_pixel: 2,
-                bytes_per_component: Some(1),
-                bytes0: Some(0..1),
-                bytes1: Some(1..2),
-                bytes2: None,
-                bytes3: None,
-                is_unorm: true,
-            },
-            Self::R8G8B8A8Unorm => FormatInfo {
-                bytes_per_pixel: 4,
-                bytes_per_component: Some(1),
-                bytes0: Some(0..1),
-                bytes1: Some(1..2),
-                bytes2: Some(2..3),
-                bytes3: Some(3..4),
-                is_unorm: true,
-            },
-            Self::R32G32B32A32Sfloat => FormatInfo {
-                bytes_per_pixel: 16,
-                bytes_per_component: Some(4),
-                bytes0: Some(0..4),
-                bytes1: Some(4..8),
-                bytes2: Some(8..12),
-                bytes3: Some(12..16),
-                is_unorm: false,
-            },
-            Self::A2b10g10r10UnormPack32 => FormatInfo {
-                bytes_per_pixel: 4,
-                bytes_per_component: None,
-                bytes0: todo!(),
-                bytes1: todo!(),
-                bytes2: todo!(),
-                bytes3: todo!(),
-                is_unorm: false,
-            },
-            Self::D16Unorm => FormatInfo {
-                bytes_per_pixel: 2,
-                bytes_per_component: Some(2),
-                bytes0: Some(0..2),
-                bytes1: None,
-                bytes2: None,
-                bytes3: None,
-                is_unorm: false,
-            },
+            Self::R8Unorm => FormatInfo::new(1, 1, NumericFormat::Unorm),
+            Self::R8Snorm => FormatInfo::new(1, 1, NumericFormat::Snorm),
+            Self::R8Uint => FormatInfo::new(1, 1, NumericFormat::Uint),
+            Self::R8Sint => FormatInfo::new(1, 1, NumericFormat::Sint),
+            Self::R8G8Unorm => FormatInfo::new(2, 2, NumericFormat::Unorm),
+            Self::R8G8Snorm => FormatInfo::new(2, 2, NumericFormat::Snorm),
+            Self::R8G8Uint => FormatInfo::new(2, 2, NumericFormat::Uint),
+            Self::R8G8Sint => FormatInfo::new(2, 2, NumericFormat::Sint),
+            Self::R8G8B8A8Unorm => FormatInfo::new(4, 4, NumericFormat::Unorm),
+            Self::R8G8B8A8Snorm => FormatInfo::new(4, 4, NumericFormat::Snorm),
+            Self::R8G8B8A8Uint => FormatInfo::new(4, 4, NumericFormat::Uint),
+            Self::R8G8B8A8Sint => FormatInfo::new(4, 4, NumericFormat::Sint),
+            Self::R8G8B8A8Srgb => FormatInfo::new(4, 4, NumericFormat::Srgb),
+            Self::B8G8R8A8Unorm => FormatInfo::new(4, 4, NumericFormat::Unorm),
+            Self::B8G8R8A8Srgb => FormatInfo::new(4, 4, NumericFormat::Srgb),
+            Self::R16Unorm => FormatInfo::new(2, 1, NumericFormat::Unorm),
+            Self::R16Snorm => FormatInfo::new(2, 1, NumericFormat::Snorm),
+            Self::R16Uint => FormatInfo::new(2, 1, NumericFormat::Uint),
+            Self::R16Sint => FormatInfo::new(2, 1, NumericFormat::Sint),
+            Self::R16Sfloat => FormatInfo::new(2, 1, NumericFormat::Sfloat),
+            Self::R16G16Unorm => FormatInfo::new(4, 2, NumericFormat::Unorm),
+            Self::R16G16Snorm => FormatInfo::new(4, 2, NumericFormat::Snorm),
+            Self::R16G16Uint => FormatInfo::new(4, 2, NumericFormat::Uint),
+            Self::R16G16Sint => FormatInfo::new(4, 2, NumericFormat::Sint),
+            Self::R16G16Sfloat => FormatInfo::new(4, 2, NumericFormat::Sfloat),
+            Self::R16G16B16A16Unorm => FormatInfo::new(8, 4, NumericFormat::Unorm),
+            Self::R16G16B16A16Snorm => FormatInfo::new(8, 4, NumericFormat::Snorm),
+            Self::R16G16B16A16Uint => FormatInfo::new(8, 4, NumericFormat::Uint),
+            Self::R16G16B16A16Sint => FormatInfo::new(8, 4, NumericFormat::Sint),
+            Self::R16G16B16A16Sfloat => FormatInfo::new(8, 4, NumericFormat::Sfloat),
+            Self::R32Uint => FormatInfo::new(4, 1, NumericFormat::Uint),
+            Self::R32Sint => FormatInfo::new(4, 1, NumericFormat::Sint),
+            Self::R32Sfloat => FormatInfo::new(4, 1, NumericFormat::Sfloat),
+            Self::R32G32Uint => FormatInfo::new(8, 2, NumericFormat::Uint),
+            Self::R32G32Sint => FormatInfo::new(8, 2, NumericFormat::Sint),
+            Self::R32G32Sfloat => FormatInfo::new(8, 2, NumericFormat::Sfloat),
+            Self::R32G32B32Uint => FormatInfo::new(12, 3, NumericFormat::Uint),
+            Self::R32G32B32Sint => FormatInfo::new(12, 3, NumericFormat::Sint),
+            Self::R32G32B32Sfloat => FormatInfo::new(12, 3, NumericFormat::Sfloat),
+            Self::R32G32B32A32Uint => FormatInfo::new(16, 4, NumericFormat::Uint),
+            Self::R32G32B32A32Sint => FormatInfo::new(16, 4, NumericFormat::Sint),
+            Self::R32G32B32A32Sfloat => FormatInfo::new(16, 4, NumericFormat::Sfloat),
+            Self::A2b10g10r10UnormPack32 => FormatInfo::new(4, 4, NumericFormat::Unorm),
+            Self::A2b10g10r10UintPack32 => FormatInfo::new(4, 4, NumericFormat::Uint),
+            Self::B10g11r11UfloatPack32 => FormatInfo::new(4, 3, NumericFormat::Ufloat),
+            Self::D16Unorm => FormatInfo::new(2, 1, NumericFormat::Unorm),
+            Self::D32Sfloat => FormatInfo::new(4, 1, NumericFormat::Sfloat),
+            Self::S8Uint => FormatInfo::new(1, 1, NumericFormat::Uint),
+            // Stencil is not read or written as texel component.
+            Self::D24UnormS8Uint => FormatInfo::new(4, 1, NumericFormat::Unorm),
+            Self::Bc1RgbUnormBlock => FormatInfo::new(8, 3, NumericFormat::Unorm),
+            Self::Bc1RgbSrgbBlock => FormatInfo::new(8, 3, NumericFormat::Srgb),
+            Self::Bc1RgbaUnormBlock => FormatInfo::new(8, 4, NumericFormat::Unorm),
+            Self::Bc1RgbaSrgbBlock => FormatInfo::new(8, 4, NumericFormat::Srgb),
+            Self::Bc2UnormBlock => FormatInfo::new(16, 4, NumericFormat::Unorm),
+            Self::Bc2SrgbBlock => FormatInfo::new(16, 4, NumericFormat::Srgb),
+            Self::Bc3UnormBlock => FormatInfo::new(16, 4, NumericFormat::Unorm),
+            Self::Bc3SrgbBlock => FormatInfo::new(16, 4, NumericFormat::Srgb),
         }
     }
+
+    /// Returns whether color components other than alpha are stored sRGB encoded.
+    pub const fn is_srgb(&self) -> bool {
+        matches!(self.info().numeric_format, NumericFormat::Srgb)
+    }
+
+    /// Returns whether red and blue components are stored swapped.
+    pub const fn is_bgr(&self) -> bool {
+        matches!(*self, Self::B8G8R8A8Unorm | Self::B8G8R8A8Srgb)
+    }
+
+    /// Returns whether texels are stored compressed in blocks of [`bc::BLOCK_SIZE`] squared
+    /// texels.
+    pub const fn is_block_compressed(&self) -> bool {
+        matches!(
+            *self,
+            Self::Bc1RgbUnormBlock
+                | Self::Bc1RgbSrgbBlock
+                | Self::Bc1RgbaUnormBlock
+                | Self::Bc1RgbaSrgbBlock
+                | Self::Bc2UnormBlock
+                | Self::Bc2SrgbBlock
+                | Self::Bc3UnormBlock
+                | Self::Bc3SrgbBlock
+        )
+    }
+
+    /// Returns width and height of blocks texels are stored in, which is 1×1 for uncompressed
+    /// formats.
+    pub const fn block_extent(&self) -> (u32, u32) {
+        if self.is_block_compressed() {
+            (bc::BLOCK_SIZE, bc::BLOCK_SIZE)
+        } else {
+            (1, 1)
+        }
+    }
+
+    /// Returns whether format has depth aspect.
+    pub const fn has_depth(&self) -> bool {
+        matches!(
+            *self,
+            Self::D16Unorm | Self::D32Sfloat | Self::D24UnormS8Uint
+        )
+    }
+
+    /// Returns whether format has stencil aspect.
+    pub const fn has_stencil(&self) -> bool {
+        matches!(*self, Self::S8Uint | Self::D24UnormS8Uint)
+    }
+}
+
+/// Decodes sRGB encoded color component to linear.
+// NOTE: https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html#TRANSFER_SRGB
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045f32 {
+        value / 12.92f32
+    } else {
+        ((value + 0.055f32) / 1.055f32).powf(2.4f32)
+    }
+}
+
+/// Encodes linear color component to sRGB.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308f32 {
+        value * 12.92f32
+    } else {
+        value.powf(1.0f32 / 2.4f32).mul_add(1.055f32, -0.055f32)
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Default)]
@@ -178,119 +310,15 @@ impl Vector4 {
         [x, y, z, w]
     }
 
-    fn to_sfloat32_bytes(self, index: impl std::slice::SliceIndex<[u64], Output = u64>) -> [u8; 4] {
-        f32::from_bits(self.components[index] as u32).to_ne_bytes()
-    }
-
-    fn to_unorm8_byte(self, index: impl std::slice::SliceIndex<[u64], Output = u64>) -> u8 {
-        let value = f32::from_bits(self.components[index] as u32);
-        (value * 255.0f32).round() as u8
-    }
-
-    fn to_unorm16_bytes(self, index: impl std::slice::SliceIndex<[u64], Output = u64>) -> [u8; 2] {
-        let value = f32::from_bits(self.components[index] as u32);
-        let value = (value * 65535.0f32).round() as u16;
-        value.to_ne_bytes()
-    }
-
-    #[allow(dead_code)]
-    fn to_unorm32_bytes(self, index: impl std::slice::SliceIndex<[u64], Output = u64>) -> [u8; 4] {
-        let value = f32::from_bits(self.components[index] as u32);
-        let value = (value * 4294967295.0f32).round() as u32;
-        value.to_ne_bytes()
-    }
-
     pub fn to_bytes(&self, format: Format) -> Vec<u8> {
         let mut result = vec![0u8; format.info().bytes_per_pixel as usize];
-        match format {
-            Format::R8Unorm => {
-                result[0] = self.to_unorm8_byte(0);
-            }
-            Format::R8G8Unorm => {
-                result[0] = self.to_unorm8_byte(0);
-                result[1] = self.to_unorm8_byte(1);
-            }
-            Format::R8G8B8A8Unorm => {
-                result[0] = self.to_unorm8_byte(0);
-                result[1] = self.to_unorm8_byte(1);
-                result[2] = self.to_unorm8_byte(2);
-                result[3] = self.to_unorm8_byte(3);
-            }
-            Format::R32G32B32A32Sfloat => {
-                result[0..4].copy_from_slice(&self.to_sfloat32_bytes(0));
-                result[4..8].copy_from_slice(&self.to_sfloat32_bytes(1));
-                result[8..12].copy_from_slice(&self.to_sfloat32_bytes(2));
-                result[12..16].copy_from_slice(&self.to_sfloat32_bytes(3));
-            }
-            Format::A2b10g10r10UnormPack32 => {
-                unimplemented!()
-            }
-            Format::D16Unorm => {
-                result[0..2].copy_from_slice(&self.to_unorm16_bytes(0));
-            }
-        }
+        write_texel(format, self.get_as_f32_array(), &mut result);
         result
     }
 
     pub fn from_vertex_buffer_bytes(format: Format, bytes: &[u8]) -> Self {
-        let (s0, s1, s2, s3) = (
-            format.info().bytes0,
-            format.info().bytes1,
-            format.info().bytes2,
-            format.info().bytes3,
-        );
-        let f = |s: Range<usize>| {
-            let range_len = s.len();
-            let (bytes_start, bytes_end) = (s.start.min(bytes.len()), s.end.min(bytes.len()));
-            let bytes_len = bytes_end - bytes_start;
-            let mut raw = [0_u8; std::mem::size_of::<u64>()];
-            if cfg!(target_endian = "big") {
-                let size = raw.len();
-                raw[size - bytes_len..].copy_from_slice(&bytes[bytes_start..bytes_end]);
-            } else {
-                raw[..bytes_len].copy_from_slice(&bytes[bytes_start..bytes_end]);
-            }
-            match range_len {
-                1 => u8::from_ne_bytes(
-                    raw[..range_len]
-                        .try_into()
-                        .unwrap_or_else(|_| unreachable!()),
-                ) as u64,
-                2 => u16::from_ne_bytes(
-                    raw[..range_len]
-                        .try_into()
-                        .unwrap_or_else(|_| unreachable!()),
-                ) as u64,
-                4 => u32::from_ne_bytes(
-                    raw[..range_len]
-                        .try_into()
-                        .unwrap_or_else(|_| unreachable!()),
-                ) as u64,
-                8 => u64::from_ne_bytes(
-                    raw[..range_len]
-                        .try_into()
-                        .unwrap_or_else(|_| unreachable!()),
-                ),
-                _ => unreachable!("{:#?}", &raw[..range_len]),
-            }
-        };
-        let result = Self {
-            components: [
-                s0.map_or_else(|| 0, f),
-                s1.map_or_else(|| 0, f),
-                s2.map_or_else(|| 0, f),
-                s3.map_or_else(|| 0, f),
-            ],
-        };
-        if format.info().is_unorm {
-            match format.info().bytes_per_component {
-                Some(1) => result.to_unorm8(),
-                Some(4) => result.to_unorm32(),
-                _ => unimplemented!(),
-            }
-        } else {
-            result
-        }
+        let [r, g, b, a] = read_texel(format, bytes);
+        Self::from_sfloat32_raw(r, g, b, a)
     }
 
     pub fn to_unorm8(self) -> Self {
@@ -332,13 +360,24 @@ pub struct Vertex {
     pub position: Position,
     pub point_size: f32,
     pub index: u32,
+    pub instance_index: u32,
     pub clip_distances: [f32; crate::consts::MAX_CLIP_DISTANCES as usize],
+    /// Vertex attributes indexed by vertex shader input location, replaced by its outputs
+    /// indexed by output location once it runs.
+    pub attributes: [Vector4; crate::consts::MAX_VERTEX_ATTRIBUTES as usize],
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct Fragment {
     pub position: Position,
     pub color: Color,
+    /// Whether fragment belongs to front-facing polygon. Points and lines are always front-facing.
+    pub front_facing: bool,
+    /// Samples of pixel covered by fragment, bit `i` standing for sample `i`. Bits beyond sample
+    /// count of render target are ignored.
+    pub coverage: u32,
+    /// Fragment shader inputs indexed by location, interpolated from vertex shader outputs.
+    pub attributes: [Vector4; crate::consts::MAX_VERTEX_ATTRIBUTES as usize],
 }
 
 #[derive(Debug, Copy, Clone, Default)]
@@ -361,7 +400,7 @@ pub struct Extent3<T> {
     pub depth: T,
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub struct Extent2<T> {
     pub width: T,
     pub height: T,
@@ -0,0 +1,102 @@
+use crate::Memory;
+use common::consts::{MAX_BOUND_DESCRIPTOR_SETS, MAX_PUSH_CONSTANTS_SIZE};
+use common::graphics::{Descriptor, DescriptorBuffer, DescriptorSet};
+use shader::glsl::ShaderResources;
+
+/// Descriptor sets and push constants bound for shaders of a pipeline bind point.
+#[derive(Debug, Clone)]
+pub struct ResourceBindings {
+    descriptor_sets: [Option<DescriptorSet>; MAX_BOUND_DESCRIPTOR_SETS as usize],
+    push_constants: Vec<u8>,
+}
+
+impl Default for ResourceBindings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceBindings {
+    pub fn new() -> Self {
+        Self {
+            descriptor_sets: Default::default(),
+            push_constants: vec![0; MAX_PUSH_CONSTANTS_SIZE as usize],
+        }
+    }
+
+    pub fn bind_descriptor_sets(
+        &mut self,
+        first_set: u32,
+        sets: Vec<DescriptorSet>,
+        dynamic_offsets: &[u32],
+    ) {
+        let mut dynamic_offsets = dynamic_offsets.iter();
+        for (i, mut set) in sets.into_iter().enumerate() {
+            // Dynamic offsets are consumed in order of binding numbers, then array elements.
+            set.bindings.sort_by_key(|binding| binding.binding);
+            for binding in set.bindings.iter_mut().filter(|binding| binding.dynamic) {
+                for descriptor in binding.descriptors.iter_mut() {
+                    let dynamic_offset = dynamic_offsets.next().copied().unwrap_or_default();
+                    if let Some(Descriptor::Buffer { offset, .. }) = descriptor {
+                        *offset += dynamic_offset as u64;
+                    }
+                }
+            }
+            self.descriptor_sets[first_set as usize + i] = Some(set);
+        }
+    }
+
+    pub fn push_constants(&mut self, offset: u32, values: &[u8]) {
+        let offset = offset as usize;
+        self.push_constants[offset..offset + values.len()].copy_from_slice(values);
+    }
+
+    /// Reads contents of buffers described by first array element of bound descriptor set
+    /// bindings.
+    pub fn shader_resources(&self, memory: &Memory) -> ShaderResources {
+        let mut resources = ShaderResources {
+            push_constants: self.push_constants.clone(),
+            ..Default::default()
+        };
+        for (set, binding, buffer, offset, range) in self.buffers() {
+            let bytes = memory.read_bytes(&buffer.binding, buffer.binding.offset + offset, range);
+            resources.buffers.insert((set, binding), bytes.to_vec());
+        }
+        resources
+    }
+
+    /// Writes contents of buffers changed by shader in `resources` back to memory.
+    pub fn store_shader_resources(&self, memory: &mut Memory, resources: &ShaderResources) {
+        for (set, binding, buffer, offset, range) in self.buffers() {
+            let Some(bytes) = resources.buffers.get(&(set, binding)) else {
+                continue;
+            };
+            let offset = buffer.binding.offset + offset;
+            if memory.read_bytes(&buffer.binding, offset, range) != bytes.as_slice() {
+                memory.write_bytes(bytes, &buffer.binding, offset);
+            }
+        }
+    }
+
+    /// Returns set and binding numbers of bound buffer descriptors along with their buffers,
+    /// offsets and ranges.
+    fn buffers(&self) -> impl Iterator<Item = (u32, u32, &DescriptorBuffer, u64, u64)> {
+        self.descriptor_sets
+            .iter()
+            .enumerate()
+            .filter_map(|(set, descriptor_set)| Some((set as u32, descriptor_set.as_ref()?)))
+            .flat_map(|(set, descriptor_set)| {
+                descriptor_set.bindings.iter().filter_map(move |binding| {
+                    let Some(Some(Descriptor::Buffer {
+                        buffer,
+                        offset,
+                        range,
+                    })) = binding.descriptors.first()
+                    else {
+                        return None;
+                    };
+                    Some((set, binding.binding, buffer, *offset, *range))
+                })
+            })
+    }
+}
@@ -0,0 +1,263 @@
+use crate::ColorBlendAttachmentState;
+use common::format::{read_raw_texel, write_raw_texel, NumericFormat};
+use common::graphics::{BlendFactor, BlendOp, LogicOp};
+use common::math::{Color, Format};
+
+/// Combines fragment color with color already stored in color attachment.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#framebuffer-blending
+pub fn blend(
+    src: Color,
+    dst: Color,
+    state: &ColorBlendAttachmentState,
+    blend_constants: Color,
+    format: Format,
+) -> Color {
+    let format_info = format.info();
+    let clamp = |x: [f32; 4]| match format_info.numeric_format {
+        NumericFormat::Unorm | NumericFormat::Srgb => x.map(|x| x.clamp(0.0f32, 1.0f32)),
+        NumericFormat::Snorm => x.map(|x| x.clamp(-1.0f32, 1.0f32)),
+        _ => x,
+    };
+
+    let dst = dst.get_as_f32_array();
+    let s = clamp(src.get_as_f32_array());
+    let mut d = clamp(dst);
+    if format_info.component_count < 4 {
+        // Attachment without alpha component behaves as if alpha was 1.
+        d[3] = 1.0f32;
+    }
+    let c = clamp(blend_constants.get_as_f32_array());
+
+    let result = if state.blend_enable {
+        let mut result = [0.0f32; 4];
+        for (i, value) in result.iter_mut().enumerate() {
+            let (src_factor, dst_factor, op) = if i < 3 {
+                (
+                    state.src_color_blend_factor,
+                    state.dst_color_blend_factor,
+                    state.color_blend_op,
+                )
+            } else {
+                (
+                    state.src_alpha_blend_factor,
+                    state.dst_alpha_blend_factor,
+                    state.alpha_blend_op,
+                )
+            };
+            let src_factor = blend_factor(src_factor, &s, &d, &c, i);
+            let dst_factor = blend_factor(dst_factor, &s, &d, &c, i);
+            *value = match op {
+                BlendOp::Add => s[i].mul_add(src_factor, d[i] * dst_factor),
+                BlendOp::Subtract => s[i].mul_add(src_factor, -d[i] * dst_factor),
+                BlendOp::ReverseSubtract => d[i].mul_add(dst_factor, -s[i] * src_factor),
+                BlendOp::Min => s[i].min(d[i]),
+                BlendOp::Max => s[i].max(d[i]),
+            };
+        }
+        clamp(result)
+    } else {
+        s
+    };
+
+    let mask = state.color_write_mask;
+    let [r, g, b, a] = [mask.r, mask.g, mask.b, mask.a];
+    let pick = |write: bool, i: usize| if write { result[i] } else { dst[i] };
+    Color::from_sfloat32_raw(pick(r, 0), pick(g, 1), pick(b, 2), pick(a, 3))
+}
+
+/// Returns whether logical operations apply to color attachment of format, which holds for
+/// integer and normalized integer formats.
+pub const fn supports_logic_op(format: Format) -> bool {
+    matches!(
+        format.info().numeric_format,
+        NumericFormat::Unorm | NumericFormat::Snorm | NumericFormat::Uint | NumericFormat::Sint
+    )
+}
+
+/// Combines fragment color with texel `dst` stored in color attachment using bitwise `op`,
+/// returning bytes of texel to store. Components excluded by color write mask keep `dst` value.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#framebuffer-logicop
+pub fn logic_op(
+    src: Color,
+    dst: &[u8],
+    op: LogicOp,
+    state: &ColorBlendAttachmentState,
+    format: Format,
+) -> Vec<u8> {
+    let s = read_raw_texel(format, &src.to_bytes(format));
+    let d = read_raw_texel(format, dst);
+    let mask = state.color_write_mask;
+    let writes = [mask.r, mask.g, mask.b, mask.a];
+    let texel = std::array::from_fn(|i| {
+        let (s, d) = (s[i], d[i]);
+        if !writes[i] {
+            return d;
+        }
+        match op {
+            LogicOp::Clear => 0,
+            LogicOp::And => s & d,
+            LogicOp::AndReverse => s & !d,
+            LogicOp::Copy => s,
+            LogicOp::AndInverted => !s & d,
+            LogicOp::NoOp => d,
+            LogicOp::Xor => s ^ d,
+            LogicOp::Or => s | d,
+            LogicOp::Nor => !(s | d),
+            LogicOp::Equivalent => !(s ^ d),
+            LogicOp::Invert => !d,
+            LogicOp::OrReverse => s | !d,
+            LogicOp::CopyInverted => !s,
+            LogicOp::OrInverted => !s | d,
+            LogicOp::Nand => !(s & d),
+            LogicOp::Set => !0,
+        }
+    });
+    let mut result = dst.to_vec();
+    write_raw_texel(format, texel, &mut result);
+    result
+}
+
+fn blend_factor(
+    factor: BlendFactor,
+    s: &[f32; 4],
+    d: &[f32; 4],
+    c: &[f32; 4],
+    component: usize,
+) -> f32 {
+    let is_alpha = component == 3;
+    match factor {
+        BlendFactor::Zero => 0.0f32,
+        BlendFactor::One => 1.0f32,
+        BlendFactor::SrcColor => s[component],
+        BlendFactor::OneMinusSrcColor => 1.0f32 - s[component],
+        BlendFactor::DstColor => d[component],
+        BlendFactor::OneMinusDstColor => 1.0f32 - d[component],
+        BlendFactor::SrcAlpha => s[3],
+        BlendFactor::OneMinusSrcAlpha => 1.0f32 - s[3],
+        BlendFactor::DstAlpha => d[3],
+        BlendFactor::OneMinusDstAlpha => 1.0f32 - d[3],
+        BlendFactor::ConstantColor => c[component],
+        BlendFactor::OneMinusConstantColor => 1.0f32 - c[component],
+        BlendFactor::ConstantAlpha => c[3],
+        BlendFactor::OneMinusConstantAlpha => 1.0f32 - c[3],
+        BlendFactor::SrcAlphaSaturate => {
+            if is_alpha {
+                1.0f32
+            } else {
+                s[3].min(1.0f32 - d[3])
+            }
+        }
+        BlendFactor::Src1Color
+        | BlendFactor::OneMinusSrc1Color
+        | BlendFactor::Src1Alpha
+        | BlendFactor::OneMinusSrc1Alpha => {
+            // dualSrcBlend feature is not supported.
+            unreachable!()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::graphics::ColorComponentFlags;
+
+    fn assert_color_eq(color: Color, expected: [f32; 4]) {
+        for (actual, expected) in color.get_as_f32_array().iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() < 1e-6,
+                "{color:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn blend_over() {
+        let state = ColorBlendAttachmentState {
+            blend_enable: true,
+            src_color_blend_factor: BlendFactor::SrcAlpha,
+            dst_color_blend_factor: BlendFactor::OneMinusSrcAlpha,
+            color_blend_op: BlendOp::Add,
+            src_alpha_blend_factor: BlendFactor::One,
+            dst_alpha_blend_factor: BlendFactor::OneMinusSrcAlpha,
+            alpha_blend_op: BlendOp::Add,
+            color_write_mask: ColorComponentFlags::default(),
+        };
+        let src = Color::from_sfloat32_raw(1.0, 0.0, 0.0, 0.25);
+        let dst = Color::from_sfloat32_raw(0.0, 0.0, 1.0, 1.0);
+        let result = blend(src, dst, &state, Color::default(), Format::R8G8B8A8Unorm);
+        assert_color_eq(result, [0.25, 0.0, 0.75, 1.0]);
+    }
+
+    #[test]
+    fn blend_additive() {
+        let state = ColorBlendAttachmentState {
+            blend_enable: true,
+            src_color_blend_factor: BlendFactor::One,
+            dst_color_blend_factor: BlendFactor::One,
+            color_blend_op: BlendOp::Add,
+            src_alpha_blend_factor: BlendFactor::One,
+            dst_alpha_blend_factor: BlendFactor::One,
+            alpha_blend_op: BlendOp::Add,
+            color_write_mask: ColorComponentFlags::default(),
+        };
+        let src = Color::from_sfloat32_raw(0.5, 0.25, 0.75, 0.5);
+        let dst = Color::from_sfloat32_raw(0.25, 0.25, 0.5, 0.25);
+        let result = blend(src, dst, &state, Color::default(), Format::R8G8B8A8Unorm);
+        assert_color_eq(result, [0.75, 0.5, 1.0, 0.75]);
+        let result = blend(
+            src,
+            dst,
+            &state,
+            Color::default(),
+            Format::R32G32B32A32Sfloat,
+        );
+        assert_color_eq(result, [0.75, 0.5, 1.25, 0.75]);
+    }
+
+    #[test]
+    fn blend_disabled_with_write_mask() {
+        let state = ColorBlendAttachmentState {
+            blend_enable: false,
+            color_write_mask: ColorComponentFlags {
+                r: true,
+                g: false,
+                b: true,
+                a: false,
+            },
+            ..Default::default()
+        };
+        let src = Color::from_sfloat32_raw(1.0, 1.0, 1.0, 1.0);
+        let dst = Color::from_sfloat32_raw(0.0, 0.5, 0.0, 0.5);
+        let result = blend(src, dst, &state, Color::default(), Format::R8G8B8A8Unorm);
+        assert_color_eq(result, [1.0, 0.5, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn logic_op_xor_with_write_mask() {
+        let state = ColorBlendAttachmentState {
+            color_write_mask: ColorComponentFlags {
+                r: true,
+                g: true,
+                b: true,
+                a: false,
+            },
+            ..Default::default()
+        };
+        let src = Color::from_sfloat32_raw(0.0, 255.0, 0x0f as f32, 0x0f as f32);
+        let dst = [0x5a, 0x5a, 0x5a, 0x5a];
+        let format = Format::R8G8B8A8Uint;
+        assert!(supports_logic_op(format));
+        assert_eq!(
+            logic_op(src, &dst, LogicOp::Xor, &state, format),
+            [0x5a, 0xa5, 0x55, 0x5a]
+        );
+        // Inverted bits are truncated to component size.
+        assert_eq!(
+            logic_op(src, &dst, LogicOp::Invert, &state, format),
+            [0xa5, 0xa5, 0xa5, 0x5a]
+        );
+        assert!(!supports_logic_op(Format::R8G8B8A8Srgb));
+        assert!(!supports_logic_op(Format::R32G32B32A32Sfloat));
+    }
+}
@@ -0,0 +1,178 @@
+use common::math::{Position, Vector4, Vertex};
+
+/// Planes of clip volume bounding depth, as coefficients of clip coordinates whose dot product with
+/// position is non-negative inside the volume. Together they keep clip `w` non-negative, so
+/// clipped primitives never cross `w = 0`. Side planes are left to scissoring of fragments, as
+/// vertices within depth range map to finite framebuffer coordinates.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vertexpostproc-clipping
+const CLIP_PLANES: [[f32; 4]; 2] = [
+    // Near plane, z >= 0.
+    [0.0f32, 0.0f32, 1.0f32, 0.0f32],
+    // Far plane, z <= w.
+    [0.0f32, 0.0f32, -1.0f32, 1.0f32],
+];
+
+/// Returns whether vertex in clip coordinates lies inside clip volume.
+pub fn is_vertex_inside(vertex: &Vertex) -> bool {
+    CLIP_PLANES
+        .iter()
+        .all(|plane| plane_distance(plane, vertex) >= 0.0f32)
+}
+
+/// Clips line segment in clip coordinates against clip volume, returning `None` if it lies
+/// outside. Endpoints inside the volume are kept as they are.
+pub fn clip_line(v0: Vertex, v1: Vertex) -> Option<[Vertex; 2]> {
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for plane in &CLIP_PLANES {
+        let (d0, d1) = (plane_distance(plane, &v0), plane_distance(plane, &v1));
+        match (d0 >= 0.0f32, d1 >= 0.0f32) {
+            (true, true) => {}
+            (false, false) => return None,
+            (false, true) => t0 = t0.max(d0 / (d0 - d1)),
+            (true, false) => t1 = t1.min(d0 / (d0 - d1)),
+        }
+    }
+    (t0 < t1).then(|| [interpolate(v0, v1, t0), interpolate(v0, v1, t1)])
+}
+
+/// Clips convex polygon in clip coordinates against clip volume, keeping its winding order.
+///
+/// Vertices inside the volume are kept as they are, and new ones are generated where edges cross
+/// its planes. Returns fewer than 3 vertices if polygon lies outside.
+// NOTE: Sutherland-Hodgman algorithm.
+pub fn clip_polygon(vertices: &[Vertex]) -> Vec<Vertex> {
+    let mut polygon = vertices.to_vec();
+    for plane in &CLIP_PLANES {
+        let distances = polygon
+            .iter()
+            .map(|vertex| plane_distance(plane, vertex))
+            .collect::<Vec<_>>();
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for i in 0..polygon.len() {
+            let j = (i + 1) % polygon.len();
+            let (d0, d1) = (distances[i], distances[j]);
+            if d0 >= 0.0f32 {
+                clipped.push(polygon[i]);
+            }
+            if (d0 >= 0.0f32) != (d1 >= 0.0f32) {
+                clipped.push(interpolate(polygon[i], polygon[j], d0 / (d0 - d1)));
+            }
+        }
+        polygon = clipped;
+    }
+    polygon
+}
+
+fn plane_distance(plane: &[f32; 4], vertex: &Vertex) -> f32 {
+    let [x, y, z, w] = vertex.position.get_as_f32_array();
+    plane[3].mul_add(w, plane[2].mul_add(z, plane[1].mul_add(y, plane[0] * x)))
+}
+
+/// Interpolates vertex linearly in clip space, exactly returning `v0` and `v1` at `t` of 0 and 1.
+/// Attributes are interpolated as floats, while indices are taken from `v0`.
+fn interpolate(v0: Vertex, v1: Vertex, t: f32) -> Vertex {
+    let lerp = |a: f32, b: f32| b.mul_add(t, a * (1.0f32 - t));
+    let lerp_vector = |a: Vector4, b: Vector4| {
+        let [a, b] = [a, b].map(|v| v.get_as_f32_array());
+        let [x, y, z, w] = [0, 1, 2, 3].map(|i| lerp(a[i], b[i]));
+        Position::from_sfloat32_raw(x, y, z, w)
+    };
+    Vertex {
+        position: lerp_vector(v0.position, v1.position),
+        point_size: lerp(v0.point_size, v1.point_size),
+        index: v0.index,
+        instance_index: v0.instance_index,
+        clip_distances: std::array::from_fn(|i| lerp(v0.clip_distances[i], v1.clip_distances[i])),
+        attributes: std::array::from_fn(|i| lerp_vector(v0.attributes[i], v1.attributes[i])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(position: [f32; 4], attribute: f32) -> Vertex {
+        let [x, y, z, w] = position;
+        let mut vertex = Vertex {
+            position: Position::from_sfloat32_raw(x, y, z, w),
+            point_size: 1.0f32,
+            index: 0,
+            instance_index: 0,
+            clip_distances: Default::default(),
+            attributes: Default::default(),
+        };
+        vertex.attributes[0] = Vector4::from_sfloat32_raw(attribute, 0.0f32, 0.0f32, 0.0f32);
+        vertex
+    }
+
+    fn positions_and_attributes(vertices: &[Vertex]) -> Vec<([f32; 4], f32)> {
+        vertices
+            .iter()
+            .map(|vertex| {
+                (
+                    vertex.position.get_as_f32_array(),
+                    vertex.attributes[0].get_as_sfloat32(0),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clip_polygon_inside_is_unchanged() {
+        let triangle = [
+            vertex([0.0, 0.0, 0.5, 1.0], 0.0),
+            vertex([1.0, 0.0, 0.5, 1.0], 1.0),
+            vertex([0.0, 1.0, 0.5, 1.0], 2.0),
+        ];
+        assert_eq!(
+            positions_and_attributes(&clip_polygon(&triangle)),
+            positions_and_attributes(&triangle)
+        );
+    }
+
+    #[test]
+    fn clip_polygon_straddling_near_plane() {
+        // First vertex lies behind near plane, even behind the eye with negative w.
+        let triangle = [
+            vertex([0.0, 0.0, -3.0, -1.0], 0.0),
+            vertex([1.0, 0.0, 1.0, 1.0], 4.0),
+            vertex([0.0, 1.0, 1.0, 3.0], 8.0),
+        ];
+        // New vertices lie on near plane, replacing the one behind it.
+        assert_eq!(
+            positions_and_attributes(&clip_polygon(&triangle)),
+            [
+                ([0.75, 0.0, 0.0, 0.5], 3.0),
+                ([1.0, 0.0, 1.0, 1.0], 4.0),
+                ([0.0, 1.0, 1.0, 3.0], 8.0),
+                ([0.0, 0.75, 0.0, 2.0], 6.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn clip_polygon_behind_near_plane_is_removed() {
+        let triangle = [
+            vertex([0.0, 0.0, -0.5, 1.0], 0.0),
+            vertex([1.0, 0.0, -0.5, 1.0], 0.0),
+            vertex([0.0, 1.0, -0.5, 1.0], 0.0),
+        ];
+        assert!(clip_polygon(&triangle).is_empty());
+        assert!(!is_vertex_inside(&triangle[0]));
+    }
+
+    #[test]
+    fn clip_line_against_near_and_far_planes() {
+        let v0 = vertex([0.0, 0.0, -1.0, 1.0], 0.0);
+        let v1 = vertex([1.0, 0.0, 3.0, 1.0], 4.0);
+        let Some(clipped) = clip_line(v0, v1) else {
+            unreachable!()
+        };
+        assert_eq!(
+            positions_and_attributes(&clipped),
+            [([0.25, 0.0, 0.0, 1.0], 1.0), ([0.5, 0.0, 1.0, 1.0], 2.0)]
+        );
+        let behind = vertex([1.0, 0.0, -2.0, 1.0], 0.0);
+        assert!(clip_line(v0, behind).is_none());
+    }
+}
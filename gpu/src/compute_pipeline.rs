@@ -0,0 +1,47 @@
+use crate::{Memory, ResourceBindings};
+use common::graphics::DescriptorSet;
+use log::warn;
+use shader::glsl::Shader;
+
+/// State of pipeline bound to compute bind point, dispatching workgroups of its shader.
+#[derive(Debug, Clone, Default)]
+pub struct ComputePipeline {
+    bindings: ResourceBindings,
+    shader: Option<Shader>,
+}
+
+impl ComputePipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_shader(&mut self, shader: Shader) {
+        self.shader = Some(shader);
+    }
+
+    pub fn bind_descriptor_sets(
+        &mut self,
+        first_set: u32,
+        sets: Vec<DescriptorSet>,
+        dynamic_offsets: &[u32],
+    ) {
+        self.bindings
+            .bind_descriptor_sets(first_set, sets, dynamic_offsets);
+    }
+
+    pub fn push_constants(&mut self, offset: u32, values: &[u8]) {
+        self.bindings.push_constants(offset, values);
+    }
+
+    /// Runs shader for all invocations of `workgroup_count` workgroups, storing contents of
+    /// buffers it writes back to memory.
+    pub fn dispatch(&self, memory: &mut Memory, workgroup_count: [u32; 3]) {
+        let Some(shader) = &self.shader else {
+            warn!("No compute pipeline bound for dispatch");
+            return;
+        };
+        let mut resources = self.bindings.shader_resources(memory);
+        shader.execute_compute_shader(&mut resources, workgroup_count);
+        self.bindings.store_shader_resources(memory, &resources);
+    }
+}
@@ -0,0 +1,182 @@
+use common::graphics::{CompareOp, StencilOp, StencilOpState};
+use common::math::Format;
+
+/// Performs depth test of fragment `depth` against `stored` depth value.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-depth
+pub fn depth_test(op: CompareOp, depth: f32, stored: f32) -> bool {
+    compare(op, depth, stored)
+}
+
+/// Returns depth bias of polygon whose depth changes by at most `max_slope` per pixel.
+///
+/// `r` is minimum resolvable difference of depth attachment values, and non-zero `clamp` bounds
+/// bias magnitude in its direction.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-depthbias-computation
+pub fn depth_bias(
+    constant_factor: f32,
+    slope_factor: f32,
+    clamp: f32,
+    max_slope: f32,
+    r: f32,
+) -> f32 {
+    let bias = max_slope.mul_add(slope_factor, r * constant_factor);
+    if clamp > 0.0f32 {
+        bias.min(clamp)
+    } else if clamp < 0.0f32 {
+        bias.max(clamp)
+    } else {
+        bias
+    }
+}
+
+/// Returns minimum resolvable difference of depth attachment values of format, which for floating
+/// point formats depends on exponent of `max_depth` of primitive.
+pub fn min_resolvable_depth_difference(format: Format, max_depth: f32) -> f32 {
+    match format {
+        Format::D16Unorm => 1.0f32 / (1u32 << 16) as f32,
+        Format::D24UnormS8Uint => 1.0f32 / (1u32 << 24) as f32,
+        Format::D32Sfloat => {
+            let exponent = ((max_depth.abs().to_bits() >> 23) & 0xff) as i32 - 127;
+            2.0f32.powi(exponent - 23)
+        }
+        _ => unreachable!("{format:?} has no depth aspect"),
+    }
+}
+
+/// Performs stencil test of fragment against `stored` stencil value, returning whether fragment
+/// passes and stencil value to store afterwards.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-stencil
+pub fn stencil_test(state: &StencilOpState, stored: u8, depth_passed: bool) -> (bool, u8) {
+    let reference = state.reference as u8;
+    let compare_mask = state.compare_mask as u8;
+    let passed = compare(
+        state.compare_op,
+        reference & compare_mask,
+        stored & compare_mask,
+    );
+    let op = match (passed, depth_passed) {
+        (false, _) => state.fail_op,
+        (true, false) => state.depth_fail_op,
+        (true, true) => state.pass_op,
+    };
+    let write_mask = state.write_mask as u8;
+    let value = apply_stencil_op(op, stored, reference);
+    (
+        passed && depth_passed,
+        (stored & !write_mask) | (value & write_mask),
+    )
+}
+
+/// Compares `reference` against `value` using `op`.
+fn compare<T: PartialOrd>(op: CompareOp, reference: T, value: T) -> bool {
+    match op {
+        CompareOp::Never => false,
+        CompareOp::Less => reference < value,
+        CompareOp::Equal => reference == value,
+        CompareOp::LessOrEqual => reference <= value,
+        CompareOp::Greater => reference > value,
+        CompareOp::NotEqual => reference != value,
+        CompareOp::GreaterOrEqual => reference >= value,
+        CompareOp::Always => true,
+    }
+}
+
+const fn apply_stencil_op(op: StencilOp, stored: u8, reference: u8) -> u8 {
+    match op {
+        StencilOp::Keep => stored,
+        StencilOp::Zero => 0,
+        StencilOp::Replace => reference,
+        StencilOp::IncrementAndClamp => stored.saturating_add(1),
+        StencilOp::DecrementAndClamp => stored.saturating_sub(1),
+        StencilOp::Invert => !stored,
+        StencilOp::IncrementAndWrap => stored.wrapping_add(1),
+        StencilOp::DecrementAndWrap => stored.wrapping_sub(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(compare_op: CompareOp, pass_op: StencilOp) -> StencilOpState {
+        StencilOpState {
+            fail_op: StencilOp::Keep,
+            pass_op,
+            depth_fail_op: StencilOp::Zero,
+            compare_op,
+            compare_mask: 0xff,
+            write_mask: 0xff,
+            reference: 0x12,
+        }
+    }
+
+    #[test]
+    fn stencil_test_compares_masked_reference() {
+        let equal = state(CompareOp::Equal, StencilOp::Keep);
+        assert_eq!(stencil_test(&equal, 0x12, true), (true, 0x12));
+        assert_eq!(stencil_test(&equal, 0x13, true), (false, 0x13));
+        let masked = StencilOpState {
+            compare_mask: 0xf0,
+            ..equal
+        };
+        assert_eq!(stencil_test(&masked, 0x1f, true), (true, 0x1f));
+        // Reference is compared on the left hand side.
+        let less = state(CompareOp::Less, StencilOp::Keep);
+        assert_eq!(stencil_test(&less, 0x13, true), (true, 0x13));
+        assert_eq!(stencil_test(&less, 0x12, true), (false, 0x12));
+    }
+
+    #[test]
+    fn stencil_test_applies_ops_through_write_mask() {
+        let always = |pass_op| state(CompareOp::Always, pass_op);
+        assert_eq!(stencil_test(&always(StencilOp::Replace), 0, true).1, 0x12);
+        assert_eq!(stencil_test(&always(StencilOp::Zero), 0x34, true).1, 0);
+        assert_eq!(
+            stencil_test(&always(StencilOp::IncrementAndClamp), 0xff, true).1,
+            0xff
+        );
+        assert_eq!(
+            stencil_test(&always(StencilOp::IncrementAndWrap), 0xff, true).1,
+            0
+        );
+        assert_eq!(
+            stencil_test(&always(StencilOp::DecrementAndClamp), 0, true).1,
+            0
+        );
+        assert_eq!(
+            stencil_test(&always(StencilOp::DecrementAndWrap), 0, true).1,
+            0xff
+        );
+        assert_eq!(stencil_test(&always(StencilOp::Invert), 0x0f, true).1, 0xf0);
+        // Failing depth test applies depth fail op and discards fragment.
+        assert_eq!(
+            stencil_test(&always(StencilOp::Replace), 0x34, false),
+            (false, 0)
+        );
+        let masked = StencilOpState {
+            write_mask: 0x0f,
+            ..always(StencilOp::Invert)
+        };
+        assert_eq!(stencil_test(&masked, 0x00, true).1, 0x0f);
+    }
+
+    #[test]
+    fn depth_bias_scales_slope_and_resolvable_difference() {
+        let r = min_resolvable_depth_difference(Format::D24UnormS8Uint, 0.5);
+        assert_eq!(r, 1.0 / 16777216.0);
+        assert_eq!(depth_bias(2.0, 0.5, 0.0, 0.25, r), 2.0f32.mul_add(r, 0.125));
+        // Clamp bounds bias in its own direction only.
+        assert_eq!(depth_bias(0.0, 1.0, 0.0625, 0.25, r), 0.0625);
+        assert_eq!(depth_bias(0.0, -1.0, 0.0625, 0.25, r), -0.25);
+        assert_eq!(depth_bias(0.0, -1.0, -0.0625, 0.25, r), -0.0625);
+        // Floating point difference follows exponent of maximum depth.
+        assert_eq!(
+            min_resolvable_depth_difference(Format::D32Sfloat, 0.75),
+            1.0 / 16777216.0
+        );
+        assert_eq!(
+            min_resolvable_depth_difference(Format::D32Sfloat, 1.0),
+            1.0 / 8388608.0
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{Memory, MemoryAllocation};
+
+/// Size in bytes of event status.
+pub const EVENT_SIZE: u64 = 4;
+
+/// Event whose status is kept in device memory, where both commands and the host set and reset
+/// it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub memory: MemoryAllocation,
+}
+
+impl Event {
+    /// Makes event signaled.
+    pub fn set(&self, memory: &mut Memory) {
+        memory.write_bytes(&1u32.to_le_bytes(), &self.memory, 0);
+    }
+
+    /// Makes event unsignaled.
+    pub fn reset(&self, memory: &mut Memory) {
+        memory.write_bytes(&[0; EVENT_SIZE as usize], &self.memory, 0);
+    }
+
+    /// Returns whether event is signaled.
+    pub fn is_set(&self, memory: &Memory) -> bool {
+        memory
+            .read_bytes(&self.memory, 0, EVENT_SIZE)
+            .iter()
+            .any(|&byte| byte != 0)
+    }
+}
@@ -1,19 +1,37 @@
 use crate::{
-    GraphicsPipeline, InputAssemblyState, Memory, RasterizationState, RenderArea, RenderTarget,
-    RenderTargetIndex, ViewportState,
+    read_texel_filtered, ColorBlendState, ComputePipeline, DepthStencilState, DynamicState, Event,
+    GraphicsPipeline, InputAssemblyState, Memory, Query, RasterizationState, RenderArea,
+    RenderTarget, RenderTargetIndex, Scissor, Viewport, ViewportState,
 };
 use common::{
-    graphics::{DescriptorBuffer, DescriptorImage, IndexBuffer, VertexBuffer, VertexInputState},
-    math::{Color, Extent3, Format, Offset3},
+    consts::MAX_COLOR_ATTACHMENTS,
+    format::{read_texel, write_stencil, write_texel, NumericFormat},
+    graphics::{
+        DescriptorBuffer, DescriptorImage, DescriptorSet, Filter, IndexBuffer, VertexBuffer,
+        VertexInputState,
+    },
+    math::{Color, Extent2, Extent3, Format, Offset2, Offset3, Range2},
 };
 use log::warn;
-use shader::glsl::ShaderState;
+use shader::glsl::{Shader, ShaderState};
 use std::fmt::{Debug, Formatter};
+use std::time::Instant;
 
-#[derive(Default)]
 pub struct Gpu {
     pub memory: Memory,
     pub graphics_pipeline: GraphicsPipeline,
+    pub compute_pipeline: ComputePipeline,
+    /// Occlusion queries begun and not yet ended, along with number of samples passed when they
+    /// began.
+    active_queries: Vec<(Query, u64)>,
+    /// Time timestamps are counted from.
+    clock_origin: Instant,
+}
+
+impl Default for Gpu {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Gpu {
@@ -21,12 +39,18 @@ impl Gpu {
         Self {
             memory: Default::default(),
             graphics_pipeline: Default::default(),
+            compute_pipeline: Default::default(),
+            active_queries: Default::default(),
+            clock_origin: Instant::now(),
         }
     }
 
-    pub fn submit(&mut self, command_buffer: CommandBuffer) {
+    /// Executes commands of `command_buffer` in order. Returns commands left to execute once events
+    /// waited for by the first of them are set, if any of them isn't set yet.
+    pub fn submit(&mut self, command_buffer: CommandBuffer) -> Option<CommandBuffer> {
         warn!("TODO: Just submit, mpsc event loop on other thread?");
-        for command in command_buffer.commands {
+        let mut commands = command_buffer.commands.into_iter();
+        while let Some(command) = commands.next() {
             match command {
                 Command::CopyBufferToImage {
                     src_buffer,
@@ -49,9 +73,106 @@ impl Gpu {
                 } => {
                     self.copy_buffer_to_buffer(src_buffer, dst_buffer, region);
                 }
-                Command::ExecuteCommands { command_buffer } => {
+                Command::UpdateBuffer {
+                    buffer,
+                    offset,
+                    data,
+                } => {
+                    self.memory
+                        .write_bytes(&data, &buffer.binding, buffer.binding.offset + offset);
+                }
+                Command::FillBuffer {
+                    buffer,
+                    offset,
+                    size,
+                    value,
+                } => {
+                    self.fill_buffer(&buffer, offset, size, value);
+                }
+                Command::BlitImage {
+                    src_image,
+                    dst_image,
+                    region,
+                    filter,
+                } => {
+                    self.blit_image(src_image, dst_image, region, filter);
+                }
+                Command::ResolveImage {
+                    src_image,
+                    dst_image,
+                    region,
+                } => {
+                    self.resolve_image(src_image, dst_image, region);
+                }
+                Command::ClearColorImage {
+                    image,
+                    format,
+                    color,
+                } => {
+                    self.fill_image(&image, &color.to_bytes(format));
+                }
+                Command::ClearDepthStencilImage {
+                    image,
+                    format,
+                    depth,
+                    stencil,
+                } => {
+                    let mut texel = vec![0; format.info().bytes_per_pixel as usize];
+                    if format.has_depth() {
+                        write_texel(format, [depth, 0.0f32, 0.0f32, 0.0f32], &mut texel);
+                    }
+                    if format.has_stencil() {
+                        write_stencil(format, stencil as u8, &mut texel);
+                    }
+                    self.fill_image(&image, &texel);
+                }
+                Command::ResetQuery { query } => {
+                    query.reset(&mut self.memory);
+                }
+                Command::BeginQuery { query } => {
+                    let samples_passed = self.graphics_pipeline.samples_passed();
+                    self.active_queries.push((query, samples_passed));
+                }
+                Command::EndQuery { query } => {
+                    let Some(index) = self.active_queries.iter().position(|(q, _)| *q == query)
+                    else {
+                        warn!("Ended query {:?} is not active", query);
+                        continue;
+                    };
+                    let (_, begin_samples_passed) = self.active_queries.swap_remove(index);
+                    let samples_passed = self.graphics_pipeline.samples_passed();
+                    query.write_result(&mut self.memory, samples_passed - begin_samples_passed);
+                }
+                Command::WriteTimestamp { query } => {
+                    // Commands execute on host, so timestamps are taken from its monotonic clock.
+                    let timestamp = self.clock_origin.elapsed().as_nanos() as u64;
+                    query.write_result(&mut self.memory, timestamp);
+                }
+                Command::SetEvent { event } => {
+                    event.set(&mut self.memory);
+                }
+                Command::ResetEvent { event } => {
+                    event.reset(&mut self.memory);
+                }
+                Command::WaitEvents { ref events } => {
+                    if !events.iter().all(|event| event.is_set(&self.memory)) {
+                        let commands = std::iter::once(command).chain(commands).collect();
+                        return Some(CommandBuffer { commands });
+                    }
+                }
+                Command::ExecuteCommands { buffers } => {
                     warn!("TODO: Avoid submit recursion.");
-                    self.submit(command_buffer);
+                    let mut buffers = buffers.into_iter();
+                    while let Some(command_buffer) = buffers.next() {
+                        if let Some(remaining) = self.submit(command_buffer) {
+                            // Resumes with the rest of secondary command buffers, then of this one.
+                            let buffers = std::iter::once(remaining).chain(buffers).collect();
+                            let commands = std::iter::once(Command::ExecuteCommands { buffers })
+                                .chain(commands)
+                                .collect();
+                            return Some(CommandBuffer { commands });
+                        }
+                    }
                 }
                 Command::BindRenderTarget { render_target } => {
                     self.graphics_pipeline.bind_render_target(render_target);
@@ -59,6 +180,14 @@ impl Gpu {
                 Command::UnbindRenderTarget { index } => {
                     self.graphics_pipeline.unbind_render_target(index);
                 }
+                Command::SetColorAttachments { color_attachments } => {
+                    self.graphics_pipeline
+                        .set_color_attachments(color_attachments);
+                }
+                Command::SetInputAttachments { input_attachments } => {
+                    self.graphics_pipeline
+                        .set_input_attachments(input_attachments);
+                }
                 Command::ClearRenderTarget {
                     index,
                     render_area,
@@ -71,6 +200,18 @@ impl Gpu {
                         color,
                     );
                 }
+                Command::ClearRenderTargetStencil {
+                    index,
+                    render_area,
+                    stencil,
+                } => {
+                    self.graphics_pipeline.clear_render_target_stencil(
+                        &mut self.memory,
+                        index,
+                        render_area,
+                        stencil,
+                    );
+                }
 
                 Command::SetShaderState { shader_state } => {
                     self.graphics_pipeline.set_shader_state(shader_state);
@@ -88,18 +229,84 @@ impl Gpu {
                 Command::SetViewportState { viewport_state } => {
                     self.graphics_pipeline.set_viewport_state(viewport_state);
                 }
+                Command::SetViewport {
+                    first_viewport,
+                    viewports,
+                } => {
+                    self.graphics_pipeline
+                        .set_viewports(first_viewport, viewports);
+                }
+                Command::SetScissor {
+                    first_scissor,
+                    scissors,
+                } => {
+                    self.graphics_pipeline.set_scissors(first_scissor, scissors);
+                }
+                Command::SetDepthBias {
+                    constant_factor,
+                    clamp,
+                    slope_factor,
+                } => {
+                    self.graphics_pipeline
+                        .set_depth_bias(constant_factor, clamp, slope_factor);
+                }
+                Command::SetDepthBounds { depth_bounds } => {
+                    self.graphics_pipeline.set_depth_bounds(depth_bounds);
+                }
                 Command::SetRasterizationState {
                     rasterization_state,
                 } => {
                     self.graphics_pipeline
                         .set_rasterization_state(rasterization_state);
                 }
+                Command::SetDepthStencilState {
+                    depth_stencil_state,
+                } => {
+                    self.graphics_pipeline
+                        .set_depth_stencil_state(depth_stencil_state);
+                }
+                Command::SetColorBlendState { color_blend_state } => {
+                    self.graphics_pipeline
+                        .set_color_blend_state(color_blend_state);
+                }
+                Command::SetDynamicState { dynamic_state } => {
+                    self.graphics_pipeline.set_dynamic_state(dynamic_state);
+                }
                 Command::BindVertexBuffer { vertex_buffer } => {
                     self.graphics_pipeline.bind_vertex_buffer(vertex_buffer);
                 }
                 Command::BindIndexBuffer { index_buffer } => {
                     self.graphics_pipeline.bind_index_buffer(index_buffer);
                 }
+                Command::BindDescriptorSets {
+                    bind_point: PipelineBindPoint::Graphics,
+                    first_set,
+                    sets,
+                    dynamic_offsets,
+                } => {
+                    self.graphics_pipeline
+                        .bind_descriptor_sets(first_set, sets, &dynamic_offsets);
+                }
+                Command::BindDescriptorSets {
+                    bind_point: PipelineBindPoint::Compute,
+                    first_set,
+                    sets,
+                    dynamic_offsets,
+                } => {
+                    self.compute_pipeline
+                        .bind_descriptor_sets(first_set, sets, &dynamic_offsets);
+                }
+                Command::PushConstants { offset, values } => {
+                    // Push constants are shared by pipelines of all bind points.
+                    self.graphics_pipeline.push_constants(offset, &values);
+                    self.compute_pipeline.push_constants(offset, &values);
+                }
+                Command::SetComputeShader { shader } => {
+                    self.compute_pipeline.set_shader(shader);
+                }
+                Command::Dispatch { x, y, z } => {
+                    self.compute_pipeline.dispatch(&mut self.memory, [x, y, z]);
+                }
                 Command::DrawPrimitive {
                     vertex_count,
                     instance_count,
@@ -132,6 +339,7 @@ impl Gpu {
                 }
             }
         }
+        None
     }
 }
 
@@ -142,36 +350,16 @@ impl Gpu {
         dst_image: DescriptorImage,
         region: RegionCopyBufferImage,
     ) {
-        warn!("TODO: Complete buffer to image copy algorithm");
-        let buffer_image_height = if region.buffer_image_height == 0 {
-            region.image_extent.height
-        } else {
-            region.buffer_image_height
-        };
-        let buffer_row_len = if region.buffer_row_len == 0 {
-            region.image_extent.width
-        } else {
-            region.buffer_row_len
-        };
-        assert_eq!(region.image_offset.x, 0);
-        assert_eq!(region.image_offset.y, 0);
-        assert_eq!(region.image_offset.z, 0);
-        assert_eq!(region.image_mip_level, 0);
-        assert_eq!(region.image_base_array_level, 0);
-        assert_eq!(region.image_array_level_count, 1);
-        assert_eq!(region.image_extent.depth, 1);
-        let size = buffer_row_len
-            * buffer_image_height
-            * region.image_format.info().bytes_per_pixel as u32;
-        let src_offset = region.buffer_offset;
-        let dst_offset = 0;
-        self.memory.copy_bytes(
-            &src_buffer.binding,
-            &dst_image.binding,
-            src_offset,
-            dst_offset,
-            size as u64,
-        );
+        for (buffer_offset, image_offset, size) in buffer_image_copy_rows(region, dst_image.extent)
+        {
+            self.memory.copy_bytes(
+                &src_buffer.binding,
+                &dst_image.binding,
+                src_buffer.binding.offset + buffer_offset,
+                dst_image.binding.offset + image_offset,
+                size,
+            );
+        }
     }
 
     fn copy_image_to_buffer(
@@ -213,6 +401,102 @@ impl Gpu {
         );
     }
 
+    /// Samples source region at each destination texel center, scaling and converting format.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#copies-imagescaling
+    fn blit_image(
+        &mut self,
+        src_image: DescriptorImage,
+        dst_image: DescriptorImage,
+        region: RegionBlitImage,
+        filter: Filter,
+    ) {
+        let [src0, src1] = region.src_offsets;
+        let [dst0, dst1] = region.dst_offsets;
+        // Scale is negative when exactly one of regions is mirrored.
+        let scale_u = (src1.x - src0.x) as f32 / (dst1.x - dst0.x) as f32;
+        let scale_v = (src1.y - src0.y) as f32 / (dst1.y - dst0.y) as f32;
+        let bytes_per_pixel = region.dst_format.info().bytes_per_pixel as u64;
+        for y in dst0.y.min(dst1.y)..dst0.y.max(dst1.y) {
+            for x in dst0.x.min(dst1.x)..dst0.x.max(dst1.x) {
+                let u = (x as f32 + 0.5f32 - dst0.x as f32).mul_add(scale_u, src0.x as f32);
+                let v = (y as f32 + 0.5f32 - dst0.y as f32).mul_add(scale_v, src0.y as f32);
+                let texel =
+                    read_texel_filtered(&self.memory, &src_image, region.src_format, u, v, filter);
+                let offset = dst_image.binding.offset
+                    + (y as u64 * dst_image.extent.width as u64 + x as u64) * bytes_per_pixel;
+                self.memory.write_bytes(
+                    &texel.to_bytes(region.dst_format),
+                    &dst_image.binding,
+                    offset,
+                );
+            }
+        }
+    }
+
+    /// Averages samples of each source texel into destination texel. Integer texels take value of
+    /// their first sample instead.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#copies-resolve
+    fn resolve_image(
+        &mut self,
+        src_image: DescriptorImage,
+        dst_image: DescriptorImage,
+        region: RegionResolveImage,
+    ) {
+        let bytes_per_pixel = region.format.info().bytes_per_pixel as u64;
+        let samples = region.samples as u64;
+        let resolved_samples = match region.format.info().numeric_format {
+            NumericFormat::Uint | NumericFormat::Sint => 1,
+            _ => samples,
+        };
+        let texel_index = |image: &DescriptorImage, offset: Offset2<i32>, x: u32, y: u32| {
+            (offset.y as u64 + y as u64) * image.extent.width as u64 + offset.x as u64 + x as u64
+        };
+        for y in 0..region.extent.height {
+            for x in 0..region.extent.width {
+                let src_offset = src_image.binding.offset
+                    + texel_index(&src_image, region.src_offset, x, y) * samples * bytes_per_pixel;
+                let bytes = self.memory.read_bytes(
+                    &src_image.binding,
+                    src_offset,
+                    resolved_samples * bytes_per_pixel,
+                );
+                let mut texel = [0.0f32; 4];
+                for sample in bytes.chunks_exact(bytes_per_pixel as usize) {
+                    let sample = read_texel(region.format, sample);
+                    for (component, value) in texel.iter_mut().zip(sample) {
+                        *component += value / resolved_samples as f32;
+                    }
+                }
+                let mut bytes = vec![0; bytes_per_pixel as usize];
+                write_texel(region.format, texel, &mut bytes);
+                let dst_offset = dst_image.binding.offset
+                    + texel_index(&dst_image, region.dst_offset, x, y) * bytes_per_pixel;
+                self.memory
+                    .write_bytes(&bytes, &dst_image.binding, dst_offset);
+            }
+        }
+    }
+
+    /// Writes texel bytes into every texel of image.
+    fn fill_image(&mut self, image: &DescriptorImage, texel: &[u8]) {
+        let extent = image.extent;
+        let texel_count = extent.width as usize * extent.height as usize * extent.depth as usize;
+        let offset = image.binding.offset as usize;
+        let memory = self.memory.get_memory_mut(&image.binding);
+        let texels = &mut memory[offset..offset + texel_count * texel.len()];
+        for dst in texels.chunks_exact_mut(texel.len()) {
+            dst.copy_from_slice(texel);
+        }
+    }
+
+    fn fill_buffer(&mut self, buffer: &DescriptorBuffer, offset: u64, size: u64, value: u32) {
+        let offset = (buffer.binding.offset + offset) as usize;
+        let memory = self.memory.get_memory_mut(&buffer.binding);
+        for dst in memory[offset..offset + size as usize].chunks_exact_mut(4) {
+            dst.copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
     fn copy_buffer_to_buffer(
         &mut self,
         src_buffer: DescriptorBuffer,
@@ -222,13 +506,63 @@ impl Gpu {
         self.memory.copy_bytes(
             &src_buffer.binding,
             &dst_buffer.binding,
-            region.src_offset,
-            region.dst_offset,
+            src_buffer.binding.offset + region.src_offset,
+            dst_buffer.binding.offset + region.dst_offset,
             region.size,
         );
     }
 }
 
+/// Returns buffer offset, image offset and size in bytes of each texel row copied by region.
+/// Image offsets are relative to base array layer of region's mip level of given extent.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#copies-buffers-images-addressing
+fn buffer_image_copy_rows(
+    region: RegionCopyBufferImage,
+    image_extent: Extent3<u32>,
+) -> impl Iterator<Item = (u64, u64, u64)> {
+    // Block-compressed images are copied in rows of whole blocks.
+    let bytes_per_pixel = region.image_format.info().bytes_per_pixel as u64;
+    let (block_width, block_height) = region.image_format.block_extent();
+    let extent = region.image_extent;
+    let buffer_row_len = if region.buffer_row_len == 0 {
+        extent.width
+    } else {
+        region.buffer_row_len
+    }
+    .div_ceil(block_width) as u64;
+    let buffer_image_height = if region.buffer_image_height == 0 {
+        extent.height
+    } else {
+        region.buffer_image_height
+    }
+    .div_ceil(block_height) as u64;
+    let [x, y, z] = [
+        region.image_offset.x as u32 / block_width,
+        region.image_offset.y as u32 / block_height,
+        region.image_offset.z as u32,
+    ]
+    .map(|offset| offset as u64);
+    let (width, height) = (
+        extent.width.div_ceil(block_width) as u64,
+        extent.height.div_ceil(block_height) as u64,
+    );
+    let image_width = image_extent.width.div_ceil(block_width) as u64;
+    let image_height = image_extent.height.div_ceil(block_height) as u64;
+    // Array layers are addressed in buffer as consecutive depth slices.
+    let slices = region.image_array_level_count as u64 * extent.depth as u64;
+    (0..slices).flat_map(move |slice| {
+        (0..height).map(move |row| {
+            let buffer_row = slice * buffer_image_height + row;
+            let image_row = (slice + z) * image_height + y + row;
+            (
+                region.buffer_offset + buffer_row * buffer_row_len * bytes_per_pixel,
+                (image_row * image_width + x) * bytes_per_pixel,
+                width * bytes_per_pixel,
+            )
+        })
+    })
+}
+
 impl Debug for Gpu {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Gpu").finish()
@@ -248,10 +582,15 @@ impl CommandBuffer {
     pub fn record(&mut self, command: Command) {
         self.commands.push(command);
     }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Command {
+    /// Copies region into `dst_image` describing base array layer of region's mip level.
     CopyBufferToImage {
         src_buffer: DescriptorBuffer,
         dst_image: DescriptorImage,
@@ -267,8 +606,77 @@ pub enum Command {
         dst_buffer: DescriptorBuffer,
         region: RegionCopyBufferBuffer,
     },
+    /// Writes `data` recorded with command into `buffer` at `offset`.
+    UpdateBuffer {
+        buffer: DescriptorBuffer,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    /// Fills `size` bytes of `buffer` at `offset` with repeated 32-bit `value`.
+    FillBuffer {
+        buffer: DescriptorBuffer,
+        offset: u64,
+        size: u64,
+        value: u32,
+    },
+    /// Blits region between 2D images described by single array layer of their mip levels.
+    BlitImage {
+        src_image: DescriptorImage,
+        dst_image: DescriptorImage,
+        region: RegionBlitImage,
+        filter: Filter,
+    },
+    /// Resolves region of multisampled `src_image` into `dst_image`, both describing single array
+    /// layer.
+    ResolveImage {
+        src_image: DescriptorImage,
+        dst_image: DescriptorImage,
+        region: RegionResolveImage,
+    },
+    /// Fills `image` describing single array layer of mip level with color.
+    ClearColorImage {
+        image: DescriptorImage,
+        format: Format,
+        color: Color,
+    },
+    /// Fills `image` describing single array layer of mip level with depth and stencil.
+    ClearDepthStencilImage {
+        image: DescriptorImage,
+        format: Format,
+        depth: f32,
+        stencil: u32,
+    },
+    /// Makes `query` unavailable.
+    ResetQuery {
+        query: Query,
+    },
+    /// Starts counting samples passing depth and stencil tests for `query`.
+    BeginQuery {
+        query: Query,
+    },
+    /// Stores number of samples passed since `query` began as its result, making it available.
+    EndQuery {
+        query: Query,
+    },
+    /// Stores nanoseconds elapsed since creation of GPU as result of `query`, making it available.
+    WriteTimestamp {
+        query: Query,
+    },
+    /// Makes `event` signaled.
+    SetEvent {
+        event: Event,
+    },
+    /// Makes `event` unsignaled.
+    ResetEvent {
+        event: Event,
+    },
+    /// Stops execution of following commands until every one of `events` is signaled.
+    WaitEvents {
+        events: Vec<Event>,
+    },
+    /// Executes commands of `buffers` in order.
     ExecuteCommands {
-        command_buffer: CommandBuffer,
+        buffers: Vec<CommandBuffer>,
     },
     BindRenderTarget {
         render_target: RenderTarget,
@@ -276,11 +684,25 @@ pub enum Command {
     UnbindRenderTarget {
         index: RenderTargetIndex,
     },
+    /// Sets render targets written by fragment shader outputs, indexed by their location.
+    SetColorAttachments {
+        color_attachments: [Option<RenderTargetIndex>; MAX_COLOR_ATTACHMENTS as usize],
+    },
+    /// Sets render targets read by fragment shader, indexed by their input attachment index.
+    SetInputAttachments {
+        input_attachments: Vec<Option<RenderTargetIndex>>,
+    },
     ClearRenderTarget {
         index: RenderTargetIndex,
         render_area: RenderArea,
         color: Color,
     },
+    /// Fills stencil aspect of `render_area` of render target, keeping its depth.
+    ClearRenderTargetStencil {
+        index: RenderTargetIndex,
+        render_area: RenderArea,
+        stencil: u8,
+    },
     SetShaderState {
         shader_state: ShaderState,
     },
@@ -293,15 +715,55 @@ pub enum Command {
     SetViewportState {
         viewport_state: ViewportState,
     },
+    SetViewport {
+        first_viewport: u32,
+        viewports: Vec<Viewport>,
+    },
+    SetScissor {
+        first_scissor: u32,
+        scissors: Vec<Scissor>,
+    },
+    /// Sets depth bias factors used by pipelines with dynamic depth bias.
+    SetDepthBias {
+        constant_factor: f32,
+        clamp: f32,
+        slope_factor: f32,
+    },
+    /// Sets depth bounds used by pipelines with dynamic depth bounds.
+    SetDepthBounds {
+        depth_bounds: Range2<f32>,
+    },
     SetRasterizationState {
         rasterization_state: RasterizationState,
     },
+    SetDepthStencilState {
+        depth_stencil_state: DepthStencilState,
+    },
+    SetColorBlendState {
+        color_blend_state: ColorBlendState,
+    },
+    SetDynamicState {
+        dynamic_state: DynamicState,
+    },
     BindVertexBuffer {
         vertex_buffer: VertexBuffer,
     },
     BindIndexBuffer {
         index_buffer: IndexBuffer,
     },
+    /// Binds descriptor sets for pipeline of `bind_point` starting at `first_set`, offsetting
+    /// dynamic buffer descriptors by consecutive `dynamic_offsets`.
+    BindDescriptorSets {
+        bind_point: PipelineBindPoint,
+        first_set: u32,
+        sets: Vec<DescriptorSet>,
+        dynamic_offsets: Vec<u32>,
+    },
+    /// Updates push constant bytes starting at `offset`.
+    PushConstants {
+        offset: u32,
+        values: Vec<u8>,
+    },
     DrawPrimitive {
         vertex_count: u32,
         instance_count: u32,
@@ -315,6 +777,22 @@ pub enum Command {
         vertex_offset: i32,
         first_instance: u32,
     },
+    SetComputeShader {
+        shader: Shader,
+    },
+    /// Runs compute shader for `x` by `y` by `z` workgroups.
+    Dispatch {
+        x: u32,
+        y: u32,
+        z: u32,
+    },
+}
+
+/// Pipeline using state set by commands binding resources.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PipelineBindPoint {
+    Graphics,
+    Compute,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -331,6 +809,26 @@ pub struct RegionCopyBufferImage {
     pub image_format: Format,
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct RegionBlitImage {
+    /// Corners of source region, which is mirrored if they are not ordered.
+    pub src_offsets: [Offset2<i32>; 2],
+    pub src_format: Format,
+    /// Corners of destination region, which is mirrored if they are not ordered.
+    pub dst_offsets: [Offset2<i32>; 2],
+    pub dst_format: Format,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct RegionResolveImage {
+    pub src_offset: Offset2<i32>,
+    pub dst_offset: Offset2<i32>,
+    pub extent: Extent2<u32>,
+    pub format: Format,
+    /// Sample count of source image.
+    pub samples: u32,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct RegionCopyBufferBuffer {
     pub src_offset: u64,
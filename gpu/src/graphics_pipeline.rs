@@ -1,49 +1,112 @@
+use std::num::NonZeroUsize;
 use std::ops::{Index, IndexMut};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
-use crate::{draw_line_bresenham, draw_points, Memory};
+use crate::{
+    assemble_primitives, blend, clip_line, clip_polygon, depth_bias, depth_test, draw_triangle,
+    is_triangle_culled, is_triangle_front_facing, is_vertex_inside, logic_op, max_depth_slope,
+    min_resolvable_depth_difference, overlapping_tiles, rasterize_line, rasterize_point,
+    stencil_test, supports_logic_op, tiles, viewport, Interpolations, Memory, Primitive,
+    RasterizerBackend, ResourceBindings, SampleStore, TileSamples,
+};
 use byteorder::ByteOrder;
 use common::{
     consts::{
-        MAX_VERTEX_ATTRIBUTE_OFFSET, MAX_VERTEX_BINDINGS, MAX_VERTEX_BINDING_STRIDE, MAX_VIEWPORTS,
+        LINE_WIDTH_RANGE, MAX_COLOR_ATTACHMENTS, MAX_VERTEX_ATTRIBUTE_OFFSET, MAX_VERTEX_BINDINGS,
+        MAX_VERTEX_BINDING_STRIDE, MAX_VIEWPORTS, POINT_SIZE_RANGE,
+    },
+    format::{
+        read_integer_texel, read_stencil, read_texel, write_stencil, write_texel, NumericFormat,
     },
     graphics::{
-        CullMode, DescriptorImage, FrontFace, IndexBuffer, PolygonMode, VertexBuffer,
-        VertexInputRate, VertexInputState,
+        BlendFactor, BlendOp, ColorComponentFlags, CompareOp, CullMode, DescriptorImage,
+        DescriptorSet, FrontFace, IndexBuffer, Interpolation, LogicOp, PolygonMode, StencilOpState,
+        VertexBuffer, VertexInputRate, VertexInputState,
     },
-    math::{Color, Extent2, Format, Fragment, Offset2, Position, Range2, Vertex},
+    math::{Color, Extent2, Extent3, Format, Fragment, Offset2, Range2, Vector4, Vertex},
 };
 use hashbrown::HashMap;
 
 use log::warn;
-use shader::glsl::{FragmentShaderOutput, ShaderState, VertexShaderOutput};
+use shader::glsl::{
+    FragmentShaderOutput, InputAttachment, Shader, ShaderResources, ShaderState, VertexShaderOutput,
+};
+use tracing::debug_span;
 
-#[derive(Default)]
 pub struct GraphicsPipeline {
     render_targets: HashMap<RenderTargetIndex, RenderTarget>,
+    /// Render targets written by fragment shader outputs, indexed by their location.
+    color_attachments: [Option<RenderTargetIndex>; MAX_COLOR_ATTACHMENTS as usize],
+    /// Render targets read by fragment shader, indexed by their input attachment index.
+    input_attachments: Vec<Option<RenderTargetIndex>>,
     vertex_buffers: [Option<VertexBuffer>; MAX_VERTEX_BINDINGS as usize],
     index_buffer: Option<IndexBuffer>,
+    bindings: ResourceBindings,
 
     shader_state: ShaderState,
+    /// Interpolation of vertex shader outputs, as decorated on fragment shader inputs.
+    interpolations: Interpolations,
     vertex_input_state: VertexInputState,
     input_assembly_state: InputAssemblyState,
     viewport_state: ViewportState,
     rasterization_state: RasterizationState,
+    depth_stencil_state: DepthStencilState,
+    color_blend_state: ColorBlendState,
+
+    dynamic_state: DynamicState,
+    /// Values set by dynamic state commands, used instead of static pipeline state declared
+    /// dynamic.
+    dynamic_viewport_state: ViewportState,
+    dynamic_rasterization_state: RasterizationState,
+    dynamic_depth_stencil_state: DepthStencilState,
+
+    rasterizer_backend: RasterizerBackend,
+    /// Number of samples that passed depth and stencil tests of all draws, counted by occlusion
+    /// queries.
+    samples_passed: AtomicU64,
+}
+
+impl Default for GraphicsPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GraphicsPipeline {
     pub fn new() -> Self {
         Self {
             render_targets: HashMap::default(),
+            color_attachments: std::array::from_fn(|location| Some(RenderTargetIndex(location))),
+            input_attachments: Default::default(),
             vertex_buffers: Default::default(),
             index_buffer: Default::default(),
+            bindings: Default::default(),
             shader_state: Default::default(),
+            interpolations: Default::default(),
             vertex_input_state: Default::default(),
             input_assembly_state: Default::default(),
             viewport_state: Default::default(),
             rasterization_state: Default::default(),
+            depth_stencil_state: Default::default(),
+            color_blend_state: Default::default(),
+            dynamic_state: Default::default(),
+            dynamic_viewport_state: Default::default(),
+            dynamic_rasterization_state: Default::default(),
+            dynamic_depth_stencil_state: Default::default(),
+            rasterizer_backend: Default::default(),
+            samples_passed: Default::default(),
         }
     }
 
+    pub const fn set_rasterizer_backend(&mut self, rasterizer_backend: RasterizerBackend) {
+        self.rasterizer_backend = rasterizer_backend;
+    }
+
+    /// Returns number of samples that passed depth and stencil tests so far.
+    pub fn samples_passed(&self) -> u64 {
+        self.samples_passed.load(Ordering::Relaxed)
+    }
+
     pub fn bind_render_target(&mut self, rt: RenderTarget) {
         self.render_targets.insert(rt.index, rt);
     }
@@ -52,6 +115,20 @@ impl GraphicsPipeline {
         self.render_targets.remove(&index);
     }
 
+    /// Sets render targets written by fragment shader outputs at each location, which by default
+    /// write render target of the same index.
+    pub const fn set_color_attachments(
+        &mut self,
+        color_attachments: [Option<RenderTargetIndex>; MAX_COLOR_ATTACHMENTS as usize],
+    ) {
+        self.color_attachments = color_attachments;
+    }
+
+    /// Sets render targets read by fragment shader at each input attachment index.
+    pub fn set_input_attachments(&mut self, input_attachments: Vec<Option<RenderTargetIndex>>) {
+        self.input_attachments = input_attachments;
+    }
+
     pub fn clear_render_target(
         &self,
         memory: &mut Memory,
@@ -63,27 +140,40 @@ impl GraphicsPipeline {
             .render_targets
             .get(&index)
             .unwrap_or_else(|| unreachable!());
-        assert_eq!(rt.samples, 1);
         assert!(area.offset.x >= 0);
         assert!(area.offset.y >= 0);
 
-        let bytes_per_pixel = rt.format.info().bytes_per_pixel;
-        let dst_offset = rt.image.extent.width * area.offset.y as u32 * bytes_per_pixel as u32;
-        let mut dst = memory.get_memory_mut(&rt.image.binding);
-        dst = &mut dst[dst_offset as usize..];
         let src = color.to_bytes(rt.format);
         let src = src.as_slice();
+        rt.for_each_sample_in_area(memory, area, |sample| sample.copy_from_slice(src));
+    }
 
-        for _y in 0..area.extent.height {
-            for _x in 0..area.extent.width {
-                let dst_offset = area.offset.x as usize * bytes_per_pixel as usize;
-                dst[dst_offset..dst_offset + bytes_per_pixel as usize].copy_from_slice(src);
-                dst = &mut dst[bytes_per_pixel as usize..];
-            }
-        }
+    pub fn clear_render_target_stencil(
+        &self,
+        memory: &mut Memory,
+        index: RenderTargetIndex,
+        area: RenderArea,
+        stencil: u8,
+    ) {
+        let rt = self
+            .render_targets
+            .get(&index)
+            .unwrap_or_else(|| unreachable!());
+        assert!(rt.format.has_stencil());
+        assert!(area.offset.x >= 0);
+        assert!(area.offset.y >= 0);
+
+        rt.for_each_sample_in_area(memory, area, |sample| {
+            write_stencil(rt.format, stencil, sample);
+        });
     }
 
     pub fn set_shader_state(&mut self, shader_state: ShaderState) {
+        self.interpolations = shader_state
+            .fragment_shader
+            .as_ref()
+            .map(|shader| *shader.input_interpolations())
+            .unwrap_or_default();
         self.shader_state = shader_state;
     }
 
@@ -99,10 +189,44 @@ impl GraphicsPipeline {
         self.viewport_state = viewport_state;
     }
 
+    pub fn set_viewports(&mut self, first_viewport: u32, viewports: Vec<Viewport>) {
+        for (i, viewport) in (first_viewport..).zip(viewports) {
+            self.dynamic_viewport_state.viewports[ViewportIndex(i)] = Some(viewport);
+        }
+    }
+
+    pub fn set_scissors(&mut self, first_scissor: u32, scissors: Vec<Scissor>) {
+        for (i, scissor) in (first_scissor..).zip(scissors) {
+            self.dynamic_viewport_state.scissors[ViewportIndex(i)] = Some(scissor);
+        }
+    }
+
+    pub const fn set_depth_bias(&mut self, constant_factor: f32, clamp: f32, slope_factor: f32) {
+        self.dynamic_rasterization_state.depth_bias_constant_factor = constant_factor;
+        self.dynamic_rasterization_state.depth_bias_clamp = clamp;
+        self.dynamic_rasterization_state.depth_bias_slope_factor = slope_factor;
+    }
+
+    pub const fn set_depth_bounds(&mut self, depth_bounds: Range2<f32>) {
+        self.dynamic_depth_stencil_state.depth_bounds = depth_bounds;
+    }
+
+    pub fn set_dynamic_state(&mut self, dynamic_state: DynamicState) {
+        self.dynamic_state = dynamic_state;
+    }
+
     pub fn set_rasterization_state(&mut self, rasterization_state: RasterizationState) {
         self.rasterization_state = rasterization_state;
     }
 
+    pub const fn set_depth_stencil_state(&mut self, depth_stencil_state: DepthStencilState) {
+        self.depth_stencil_state = depth_stencil_state;
+    }
+
+    pub fn set_color_blend_state(&mut self, color_blend_state: ColorBlendState) {
+        self.color_blend_state = color_blend_state;
+    }
+
     pub fn bind_vertex_buffer(&mut self, vertex_buffer: VertexBuffer) {
         let index = vertex_buffer.binding_number;
         self.vertex_buffers[index] = Some(vertex_buffer);
@@ -112,6 +236,20 @@ impl GraphicsPipeline {
         self.index_buffer = Some(index_buffer);
     }
 
+    pub fn bind_descriptor_sets(
+        &mut self,
+        first_set: u32,
+        sets: Vec<DescriptorSet>,
+        dynamic_offsets: &[u32],
+    ) {
+        self.bindings
+            .bind_descriptor_sets(first_set, sets, dynamic_offsets);
+    }
+
+    pub fn push_constants(&mut self, offset: u32, values: &[u8]) {
+        self.bindings.push_constants(offset, values);
+    }
+
     pub fn draw_primitive(
         &mut self,
         memory: &mut Memory,
@@ -120,16 +258,14 @@ impl GraphicsPipeline {
         first_vertex: u32,
         first_instance: u32,
     ) {
-        // Fetch vertices from vertex buffer using bindings.
-        let vertices = self.fetch_vertex_input(
-            memory,
-            vertex_count,
-            instance_count,
-            first_vertex,
-            first_instance,
-        );
+        let _span = debug_span!("draw", vertex_count, instance_count).entered();
+        let indices = (first_vertex..first_vertex + vertex_count).collect::<Vec<_>>();
+        for instance in first_instance..first_instance + instance_count {
+            // Fetch vertices from vertex buffer using bindings.
+            let vertices = self.fetch_vertex_input(memory, &indices, instance);
 
-        self.draw_primitive_rest(memory, vertices)
+            self.draw_primitive_rest(memory, vertices)
+        }
     }
 
     pub fn draw_primitive_indexed(
@@ -141,21 +277,38 @@ impl GraphicsPipeline {
         vertex_offset: i32,
         first_instance: u32,
     ) {
-        let vertices = self.fetch_vertex_input_indexed(
-            memory,
-            index_count,
-            instance_count,
-            first_index,
-            vertex_offset,
-            first_instance,
-        );
+        let _span = debug_span!("draw_indexed", index_count, instance_count).entered();
+        let (indices, restart_index) = self.fetch_indices(memory, index_count, first_index);
+        for instance in first_instance..first_instance + instance_count {
+            // Restart index ends current primitives and starts assembling new ones.
+            for indices in indices.split(|&index| Some(index) == restart_index) {
+                if indices.is_empty() {
+                    continue;
+                }
+                let indices = indices
+                    .iter()
+                    .map(|&index| index.wrapping_add_signed(vertex_offset))
+                    .collect::<Vec<_>>();
+                let vertices = self.fetch_vertex_input(memory, &indices, instance);
 
-        self.draw_primitive_rest(memory, vertices)
+                self.draw_primitive_rest(memory, vertices)
+            }
+        }
     }
 
-    fn draw_primitive_rest(&mut self, memory: &mut Memory, vertices: Vec<Vertex>) {
+    fn draw_primitive_rest(&self, memory: &mut Memory, vertices: Vec<Vertex>) {
+        let mut resources = self.bindings.shader_resources(memory);
+        for (index, rt) in self.input_attachments.iter().enumerate() {
+            let Some(rt) = rt.and_then(|index| self.render_targets.get(&index)) else {
+                continue;
+            };
+            resources
+                .input_attachments
+                .insert(index as u32, rt.input_attachment(memory));
+        }
+
         // Vertex shader.
-        let vertices = self.execute_vertex_shader(&self.vertex_input_state, vertices);
+        let vertices = self.execute_vertex_shader(&self.vertex_input_state, &resources, vertices);
 
         warn!("TODO: tesselation assembler");
         warn!("TODO: tesselation control shader");
@@ -165,256 +318,607 @@ impl GraphicsPipeline {
         warn!("TODO: geometry shader");
 
         // Primitive assembler.
-        let Some(viewport) = self.viewport_state.viewports[ViewportIndex(0)].as_ref() else {
+        let Some(viewport) = self.viewport(ViewportIndex(0)) else {
             warn!("TODO: Use all set viewports");
             unreachable!();
         };
-        assert_eq!(viewport.offset.x, 0.0f32);
-        assert_eq!(viewport.offset.y, 0.0f32);
         let primitive_vertices = vertices
             .iter()
-            .map(|vertex_shader_output| {
-                let v = vertex_shader_output.position;
-
-                let x = v.get_as_sfloat32(0);
-                let y = v.get_as_sfloat32(1);
-                let z = v.get_as_sfloat32(2);
-                let w = v.get_as_sfloat32(3);
-                // Perspective division.
-                let x_ndc = x / w;
-                let y_ndc = y / w;
-                let z_ndc = z / w;
-                warn!("TODO: Depth test.");
-                warn!("TODO: Back-face culling.");
-                warn!("TODO: Clipping.");
-                // Viewport transformation
-                // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vertexpostproc-viewport\
-                assert_eq!(viewport.offset.x, 0.0);
-                assert_eq!(viewport.offset.y, 0.0);
-                let (p_x, p_y, p_z) = (
-                    viewport.extent.width,
-                    viewport.extent.height,
-                    viewport.depth.max - viewport.depth.min,
-                );
-                let (o_x, o_y, o_z) = (
-                    viewport.offset.x + viewport.extent.width / 2.0,
-                    viewport.offset.y + viewport.extent.height / 2.0,
-                    viewport.depth.min,
-                );
-                let (x_screen, y_screen, z_screen) = (
-                    (p_x / 2.0).mul_add(x_ndc, o_x),
-                    (p_y / 2.0).mul_add(y_ndc, o_y),
-                    p_z.mul_add(z_ndc, o_z),
-                );
-                Vertex {
-                    position: Position::from_sfloat32_raw(x_screen, y_screen, z_screen, 1.0),
-                    point_size: 1.0f32,
-                    index: vertex_shader_output.vertex_index,
-                    clip_distances: [0.0f32, 0.0f32, 0.0f32, 0.0f32],
-                }
+            .map(|vertex_shader_output| Vertex {
+                position: vertex_shader_output.position,
+                point_size: vertex_shader_output.point_size,
+                index: vertex_shader_output.vertex_index,
+                instance_index: 0,
+                clip_distances: [0.0f32, 0.0f32, 0.0f32, 0.0f32],
+                attributes: vertex_shader_output.attributes,
             })
             .collect::<Vec<_>>();
+        // Maps vertex left by clipping into framebuffer coordinates, which fails for zero `w` only
+        // on the boundary of clip volume.
+        let to_framebuffer = |vertex: Vertex| {
+            Some(Vertex {
+                position: viewport::transform(vertex.position, viewport)?,
+                ..vertex
+            })
+        };
 
         // Rasterization.
-        let Some(rt) = self.render_targets.get_mut(&RenderTargetIndex(0)).cloned() else {
+        let Some(rt) = self.render_targets.get(&RenderTargetIndex(0)).cloned() else {
             warn!("TODO: Determine used RenderTarget from fragment shader");
             unreachable!()
         };
 
         warn!("TODO: Determine color in vertex shader");
         warn!("TODO: Color interpolation");
+
+        let scissor = self.scissor_rect(Extent2 {
+            width: rt.image.extent.width,
+            height: rt.image.extent.height,
+        });
+        // Flat attributes of vertices left by clipping take values of provoking vertex, which is the
+        // first vertex of primitive.
+        let interpolations = self.interpolations;
+        let provoke = |vertices: &mut [Vertex], provoking_vertex: &Vertex| {
+            for vertex in vertices {
+                for (location, interpolation) in interpolations.iter().enumerate() {
+                    if *interpolation == Interpolation::Flat {
+                        vertex.attributes[location] = provoking_vertex.attributes[location];
+                    }
+                }
+            }
+        };
+        let mut raster_primitives = vec![];
+        let primitives =
+            assemble_primitives(self.input_assembly_state.topology, primitive_vertices.len());
+        for primitive in primitives {
+            match primitive {
+                Primitive::Point(i) => {
+                    let vertex = primitive_vertices[i];
+                    if !is_vertex_inside(&vertex) {
+                        continue;
+                    }
+                    let Some(vertex) = to_framebuffer(vertex) else {
+                        continue;
+                    };
+                    raster_primitives.push(RasterPrimitive::Point(Box::new(vertex)));
+                }
+                Primitive::Line([i0, i1]) => {
+                    let Some(mut vertices) =
+                        clip_line(primitive_vertices[i0], primitive_vertices[i1])
+                    else {
+                        continue;
+                    };
+                    provoke(&mut vertices, &primitive_vertices[i0]);
+                    let [Some(v0), Some(v1)] = vertices.map(to_framebuffer) else {
+                        continue;
+                    };
+                    raster_primitives.push(RasterPrimitive::Line(Box::new([v0, v1])));
+                }
+                Primitive::Triangle(indices) => {
+                    let mut polygon = clip_polygon(&indices.map(|i| primitive_vertices[i]));
+                    provoke(&mut polygon, &primitive_vertices[indices[0]]);
+                    let Some(polygon) = polygon
+                        .into_iter()
+                        .map(to_framebuffer)
+                        .collect::<Option<Vec<_>>>()
+                    else {
+                        continue;
+                    };
+                    // Clipped polygon is convex, so triangles fanning out of its first vertex
+                    // share its facing unless they are degenerate.
+                    let front_face = self.rasterization_state.front_face;
+                    let Some(triangle) = fan_triangles(&polygon)
+                        .find(|&triangle| is_triangle_front_facing(triangle, front_face).is_some())
+                    else {
+                        // Degenerate polygon, or one clipped away, is always culled.
+                        continue;
+                    };
+                    if is_triangle_culled(triangle, self.rasterization_state.cull_mode, front_face)
+                    {
+                        continue;
+                    }
+                    let front_facing = is_triangle_front_facing(triangle, front_face) == Some(true);
+                    let bias = self.depth_bias(&polygon, triangle);
+                    raster_primitives.push(RasterPrimitive::Polygon {
+                        vertices: polygon,
+                        front_facing,
+                        bias,
+                    });
+                }
+            }
+        }
+
+        match self.rasterizer_backend {
+            RasterizerBackend::Simple => {
+                let mut fragments = vec![];
+                for primitive in &raster_primitives {
+                    self.rasterize(primitive, scissor, rt.samples, &mut fragments);
+                }
+                self.process_fragments(memory, &resources, scissor, fragments);
+            }
+            RasterizerBackend::Tiled => {
+                self.process_tiles(memory, &resources, scissor, rt.samples, &raster_primitives);
+            }
+        }
+    }
+
+    /// Rasterizes primitive into fragments within `area`.
+    fn rasterize(
+        &self,
+        primitive: &RasterPrimitive,
+        area: RenderArea,
+        samples: u32,
+        fragments: &mut Vec<Fragment>,
+    ) {
         let color = Color::from_sfloat32_raw(1.0f32, 1.0f32, 1.0f32, 1.0f32);
 
-        let mut fragments = vec![];
-        match self.input_assembly_state.topology {
-            PrimitiveTopology::PointList => draw_points(primitive_vertices, &mut fragments, color),
-            PrimitiveTopology::LineList => unimplemented!(),
-            PrimitiveTopology::LineStrip => unimplemented!(),
-            PrimitiveTopology::TriangleList => {
-                assert_eq!(primitive_vertices.len() % 3, 0);
-                for triangle in primitive_vertices.chunks_exact(3) {
-                    let vertices: [Vertex; 3] =
-                        triangle.try_into().unwrap_or_else(|_| unreachable!());
-                    match self.rasterization_state.polygon_mode {
-                        PolygonMode::Fill | PolygonMode::Line => {
-                            warn!("TODO: Implement PolygonMode::Fill");
-                            for i in 0..3 {
-                                draw_line_bresenham(
-                                    vertices[i],
-                                    vertices[(i + 1) % 3],
-                                    &mut fragments,
-                                    color,
-                                );
-                            }
+        let line_width = self.line_width();
+        let interpolations = &self.interpolations;
+        match primitive {
+            RasterPrimitive::Point(vertex) => {
+                let vertex = **vertex;
+                rasterize_point(vertex, point_size(vertex), area, fragments, color);
+            }
+            RasterPrimitive::Line(vertices) => {
+                let [v0, v1] = **vertices;
+                rasterize_line(v0, v1, line_width, area, fragments, color, interpolations);
+            }
+            RasterPrimitive::Polygon {
+                vertices: polygon,
+                front_facing,
+                bias,
+            } => {
+                let first_fragment = fragments.len();
+                match self.rasterization_state.polygon_mode {
+                    PolygonMode::Fill => {
+                        for triangle in fan_triangles(polygon) {
+                            draw_triangle(
+                                triangle,
+                                area,
+                                samples,
+                                fragments,
+                                color,
+                                interpolations,
+                            );
                         }
-                        PolygonMode::Point => {
-                            draw_points(vertices, &mut fragments, color);
+                    }
+                    PolygonMode::Line => {
+                        for i in 0..polygon.len() {
+                            rasterize_line(
+                                polygon[i],
+                                polygon[(i + 1) % polygon.len()],
+                                line_width,
+                                area,
+                                fragments,
+                                color,
+                                interpolations,
+                            );
                         }
-                        PolygonMode::FillRectangle => unimplemented!(),
-                    };
+                    }
+                    PolygonMode::Point => {
+                        for &vertex in polygon {
+                            rasterize_point(vertex, point_size(vertex), area, fragments, color);
+                        }
+                    }
+                    PolygonMode::FillRectangle => unimplemented!(),
+                };
+                // Fragments of triangle take its facing and depth bias in any polygon mode.
+                for fragment in &mut fragments[first_fragment..] {
+                    fragment.front_facing = *front_facing;
+                    let [x, y, z, w] = fragment.position.get_as_f32_array();
+                    fragment.position = Vector4::from_sfloat32_raw(x, y, z + bias, w);
                 }
             }
-            PrimitiveTopology::TriangleStrip => unimplemented!(),
-            PrimitiveTopology::TriangleFan => unimplemented!(),
-            PrimitiveTopology::LineListWithAdjacency => unimplemented!(),
-            PrimitiveTopology::LineStripWithAdjacency => unimplemented!(),
-            PrimitiveTopology::TriangleListWithAdjacency => unimplemented!(),
-            PrimitiveTopology::TriangleStripWithAdjacency => unimplemented!(),
-            PrimitiveTopology::PatchList => unimplemented!(),
-        };
+        }
+    }
+
+    /// Bins primitives into tiles of `scissor` they overlap, then rasterizes and processes
+    /// fragments of tiles in parallel. Tiles keep order of their primitives and never share
+    /// pixels, so results match rasterizing all primitives at once.
+    fn process_tiles(
+        &self,
+        memory: &mut Memory,
+        resources: &ShaderResources,
+        scissor: RenderArea,
+        samples: u32,
+        primitives: &[RasterPrimitive],
+    ) {
+        let tiles = tiles(scissor);
+        let mut bins = vec![vec![]; tiles.len()];
+        for primitive in primitives {
+            for tile in overlapping_tiles(scissor, primitive.bounds(self.line_width())) {
+                bins[tile].push(primitive);
+            }
+        }
+        let work = std::iter::zip(tiles, bins)
+            .filter(|(_, bin)| !bin.is_empty())
+            .collect::<Vec<_>>();
 
-        warn!("TODO: early per-fragment operations");
+        // Each thread takes next tile left until none is, copying its samples out of memory.
+        let next_tile = AtomicUsize::new(0);
+        let threads = std::thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .min(work.len());
+        let shared_memory = &*memory;
+        let tile_samples = std::thread::scope(|scope| {
+            let threads = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = vec![];
+                        while let Some((tile, bin)) =
+                            work.get(next_tile.fetch_add(1, Ordering::Relaxed))
+                        {
+                            let mut tile_samples = TileSamples::new(
+                                shared_memory,
+                                *tile,
+                                self.render_targets.values(),
+                            );
+                            let mut fragments = vec![];
+                            for primitive in bin {
+                                self.rasterize(primitive, *tile, samples, &mut fragments);
+                            }
+                            self.process_fragments(
+                                &mut tile_samples,
+                                resources,
+                                scissor,
+                                fragments,
+                            );
+                            done.push(tile_samples);
+                        }
+                        done
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads
+                .into_iter()
+                .flat_map(|thread| {
+                    thread
+                        .join()
+                        .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+                })
+                .collect::<Vec<_>>()
+        });
+        for tile_samples in tile_samples {
+            tile_samples.store(memory);
+        }
+    }
 
-        // Fragment shader.
-        let fragments = self.execute_fragment_shader(fragments);
+    /// Runs per-fragment operations and fragment shader on fragments, writing results into
+    /// render target samples.
+    fn process_fragments(
+        &self,
+        samples: &mut impl SampleStore,
+        resources: &ShaderResources,
+        scissor: RenderArea,
+        mut fragments: Vec<Fragment>,
+    ) {
+        // Scissor test.
+        fragments.retain(|fragment| {
+            let position = fragment.position;
+            scissor.contains(position.get_as_sfloat32(0), position.get_as_sfloat32(1))
+        });
+
+        // Fragment shader does not discard fragments, so depth and stencil tests can run before
+        // it, unless it writes depth they have to use.
+        let writes_frag_depth = self
+            .shader_state
+            .fragment_shader
+            .as_ref()
+            .is_some_and(Shader::writes_frag_depth);
+        if !writes_frag_depth {
+            self.apply_depth_stencil_test(samples, &mut fragments);
+            fragments.retain(|fragment| fragment.coverage != 0);
+        }
+
+        // Fragment shader. Its outputs match input fragments one to one, which keep their facing
+        // and coverage.
+        let outputs = self.execute_fragment_shader(resources, fragments.clone());
+        for (fragment, output) in std::iter::zip(&mut fragments, &outputs) {
+            fragment.position = output.position;
+        }
+        if writes_frag_depth {
+            self.apply_depth_stencil_test(samples, &mut fragments);
+        }
+        let samples_passed = fragments
+            .iter()
+            .map(|fragment| fragment.coverage.count_ones() as u64)
+            .sum();
+        self.samples_passed
+            .fetch_add(samples_passed, Ordering::Relaxed);
 
         warn!("TODO: late per-fragment operations");
-        warn!("TODO: color/blending operations");
 
-        // Color attachment output
-        warn!("TODO: Fragment shader should write directly to render target");
-        for fragment in fragments {
-            let position = fragment.position;
-            let color = fragment.color.to_bytes(rt.format);
+        // Color attachment output. Each output of fragment shader is written to color attachment
+        // at its location, blending with attachment state at the same index. Attachments without
+        // output at their location are left as they are.
+        // Logical operation disables blending, even for formats it does not apply to.
+        let logic_op_enable = self.color_blend_state.logic_op_enable;
+        for (location, index) in self.color_attachments.iter().enumerate() {
+            let Some(rt) = index.and_then(|index| self.render_targets.get(&index)) else {
+                continue;
+            };
+            let mut blend_attachment =
+                self.color_blend_state.attachments[location].unwrap_or_default();
+            blend_attachment.blend_enable &= !logic_op_enable;
+            for (fragment, output) in std::iter::zip(&fragments, &outputs) {
+                let Some(fragment_color) = output.colors[location] else {
+                    continue;
+                };
+                let position = fragment.position;
+                let x = position.get_as_sfloat32(0) as u32;
+                let y = position.get_as_sfloat32(1) as u32;
+                assert!(x < rt.image.extent.width);
+                assert!(y < rt.image.extent.height);
 
-            let framebuffer_width = rt.image.extent.width as u64;
-            let framebuffer_height = rt.image.extent.height as u64;
-            let framebuffer_x = position.get_as_sfloat32(0) as u64;
-            let framebuffer_y = position.get_as_sfloat32(1) as u64;
-            assert!(framebuffer_x < framebuffer_width);
-            assert!(framebuffer_y < framebuffer_height);
-            let dst_offset = (framebuffer_x + framebuffer_y * framebuffer_width)
-                * rt.format.info().bytes_per_pixel as u64;
-            warn!("TODO: Write texel to image function");
-            memory.write_bytes(&color, &rt.image.binding, dst_offset);
+                // Every covered sample takes color of the fragment.
+                for sample in (0..rt.samples).filter(|sample| fragment.coverage & 1 << sample != 0)
+                {
+                    // Logical operation or blending.
+                    let dst = samples.sample(rt, x, y, sample);
+                    let color = if logic_op_enable && supports_logic_op(rt.format) {
+                        logic_op(
+                            fragment_color,
+                            dst,
+                            self.color_blend_state.logic_op,
+                            &blend_attachment,
+                            rt.format,
+                        )
+                    } else {
+                        let dst_color = Color::from_vertex_buffer_bytes(rt.format, dst);
+                        let color = blend(
+                            fragment_color,
+                            dst_color,
+                            &blend_attachment,
+                            self.color_blend_state.blend_constants,
+                            rt.format,
+                        );
+                        color.to_bytes(rt.format)
+                    };
+                    warn!("TODO: Write texel to image function");
+                    samples.set_sample(rt, x, y, sample, &color);
+                }
+            }
         }
     }
 }
 
 impl GraphicsPipeline {
-    fn fetch_vertex_input(
-        &mut self,
-        memory: &Memory,
-        vertex_count: u32,
-        instance_count: u32,
-        first_vertex: u32,
-        first_instance: u32,
-    ) -> Vec<Vertex> {
-        assert_eq!(instance_count, 1);
-        assert_eq!(first_vertex, 0);
-        assert_eq!(first_instance, 0);
-
-        if self.vertex_input_state.bindings.iter().all(|x| x.is_none()) {
-            return (first_vertex..vertex_count)
-                .map(|index| Vertex {
-                    position: Default::default(),
-                    point_size: 1.0f32,
-                    index,
-                    clip_distances: Default::default(),
-                })
-                .collect();
+    /// Clears coverage of fragment samples failing depth bounds, depth or stencil test, updating
+    /// depth and stencil values of bound depth/stencil render target.
+    fn apply_depth_stencil_test(&self, samples: &mut impl SampleStore, fragments: &mut [Fragment]) {
+        let state = &self.depth_stencil_state;
+        let Some(rt) = self
+            .render_targets
+            .values()
+            .find(|rt| rt.format.has_depth() || rt.format.has_stencil())
+        else {
+            // Depth and stencil tests always pass without depth/stencil attachment.
+            return;
+        };
+        let depth_test_enable = state.depth_test_enable && rt.format.has_depth();
+        let stencil_test_enable = state.stencil_test_enable && rt.format.has_stencil();
+        let depth_bounds_test_enable = state.depth_bounds_test_enable && rt.format.has_depth();
+        if !depth_test_enable && !stencil_test_enable && !depth_bounds_test_enable {
+            return;
         }
-
-        let Some(binding) = self.vertex_input_state.bindings[0].as_ref() else {
-            warn!("TODO: Determine used VertexBindings from vertex shader (if any)");
-            unreachable!("{:#?}", self.vertex_input_state.bindings)
+        let depth_bounds = if self.dynamic_state.depth_bounds {
+            self.dynamic_depth_stencil_state.depth_bounds
+        } else {
+            state.depth_bounds
+        };
+        let bytes_per_pixel = rt.format.info().bytes_per_pixel as u64;
+        let stencil_state = |fragment: &Fragment| {
+            if fragment.front_facing {
+                &state.front
+            } else {
+                &state.back
+            }
         };
-        assert!(binding.stride < MAX_VERTEX_BINDING_STRIDE);
-        assert_eq!(binding.input_rate, VertexInputRate::Vertex);
+        // Each covered sample is tested on its own, fragment is discarded once none passes.
+        for fragment in fragments.iter_mut() {
+            let x = fragment.position.get_as_sfloat32(0) as u32;
+            let y = fragment.position.get_as_sfloat32(1) as u32;
+            // Fragment depth is converted to format of attachment before comparison.
+            let mut depth = vec![0; bytes_per_pixel as usize];
+            if depth_test_enable {
+                let z = fragment.position.get_as_sfloat32(2);
+                write_texel(rt.format, [z, 0.0f32, 0.0f32, 0.0f32], &mut depth);
+            }
+            let mut coverage = 0;
+            for sample in (0..rt.samples).filter(|sample| fragment.coverage & 1 << sample != 0) {
+                let mut texel = samples.sample(rt, x, y, sample).to_vec();
+                // Sample outside depth bounds is discarded before stencil test, leaving stencil
+                // value as it is.
+                // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-dbt
+                if depth_bounds_test_enable {
+                    let stored = read_texel(rt.format, &texel)[0];
+                    if !(depth_bounds.min..=depth_bounds.max).contains(&stored) {
+                        continue;
+                    }
+                }
+                let depth_passed = !depth_test_enable
+                    || depth_test(
+                        state.depth_compare_op,
+                        read_texel(rt.format, &depth)[0],
+                        read_texel(rt.format, &texel)[0],
+                    );
+                let passed = if stencil_test_enable {
+                    let stored = read_stencil(rt.format, &texel);
+                    let (passed, stencil) =
+                        stencil_test(stencil_state(fragment), stored, depth_passed);
+                    write_stencil(rt.format, stencil, &mut texel);
+                    passed
+                } else {
+                    depth_passed
+                };
+                if passed && depth_test_enable && state.depth_write_enable {
+                    write_texel(rt.format, read_texel(rt.format, &depth), &mut texel);
+                }
+                samples.set_sample(rt, x, y, sample, &texel);
+                coverage |= u32::from(passed) << sample;
+            }
+            fragment.coverage = coverage;
+        }
+    }
 
-        let Some(attribute) = self.vertex_input_state.attributes[0].as_ref() else {
-            warn!("TODO: Determine used VertexAttributes from vertex shader (if any)");
-            unreachable!()
+    /// Returns depth bias of polygon in framebuffer coordinates, using its non-degenerate
+    /// `triangle` for depth slope. Bias is zero when disabled or without depth attachment.
+    fn depth_bias(&self, polygon: &[Vertex], triangle: [Vertex; 3]) -> f32 {
+        let Some(format) = self
+            .render_targets
+            .values()
+            .map(|rt| rt.format)
+            .find(Format::has_depth)
+        else {
+            return 0.0f32;
+        };
+        if !self.rasterization_state.depth_bias_enable {
+            return 0.0f32;
+        }
+        let factors = if self.dynamic_state.depth_bias {
+            &self.dynamic_rasterization_state
+        } else {
+            &self.rasterization_state
         };
-        assert_eq!(attribute.binding, binding.number);
-        assert_eq!(attribute.location, 0);
-        assert!(attribute.offset < MAX_VERTEX_ATTRIBUTE_OFFSET);
+        let max_depth = polygon
+            .iter()
+            .map(|vertex| vertex.position.get_as_sfloat32(2))
+            .fold(0.0f32, f32::max);
+        depth_bias(
+            factors.depth_bias_constant_factor,
+            factors.depth_bias_slope_factor,
+            factors.depth_bias_clamp,
+            max_depth_slope(triangle).unwrap_or_default(),
+            min_resolvable_depth_difference(format, max_depth),
+        )
+    }
 
-        // Create elements from vertex buffer using attributes.
-        let Some(vertex_buffer) = self.vertex_buffers[binding.number].as_ref() else {
-            unreachable!()
+    fn viewport(&self, index: ViewportIndex) -> Option<&Viewport> {
+        let viewport_state = if self.dynamic_state.viewport {
+            &self.dynamic_viewport_state
+        } else {
+            &self.viewport_state
         };
-        let element_format = attribute.format;
-        let element_size = element_format.info().bytes_per_pixel as u32;
-        let element_stride = if binding.stride == 0 {
-            element_size
+        viewport_state.viewports[index].as_ref()
+    }
+
+    fn scissor(&self, index: ViewportIndex) -> Option<&Scissor> {
+        let viewport_state = if self.dynamic_state.scissor {
+            &self.dynamic_viewport_state
         } else {
-            binding.stride
-        };
-        let vertex_buffer_size = vertex_buffer.buffer.binding.size - vertex_buffer.offset;
-        assert_eq!(vertex_buffer_size % element_stride as u64, 0);
-
-        let bytes = memory
-            .read_bytes(
-                &vertex_buffer.buffer.binding,
-                vertex_buffer.offset,
-                vertex_buffer_size,
-            )
-            .to_vec();
-        warn!("TODO: Stream vertex buffer bytes instead of reading all of them?");
-
-        warn!("TODO: Determine vertex element components in shader?");
-        let vertices = bytes
-            .chunks_exact(element_stride as usize)
-            .take(vertex_count as usize)
-            .map(|element| Vertex {
-                position: Position::from_vertex_buffer_bytes(element_format, element),
+            &self.viewport_state
+        };
+        viewport_state.scissors[index].as_ref()
+    }
+
+    /// Returns width of rasterized lines, clamped to supported range.
+    const fn line_width(&self) -> f32 {
+        self.rasterization_state
+            .line_width
+            .clamp(LINE_WIDTH_RANGE.0, LINE_WIDTH_RANGE.1)
+    }
+
+    /// Returns framebuffer area passing scissor test.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-scissor
+    fn scissor_rect(&self, framebuffer_extent: Extent2<u32>) -> RenderArea {
+        let framebuffer = RenderArea {
+            extent: framebuffer_extent,
+            offset: Offset2 { x: 0, y: 0 },
+        };
+        self.scissor(ViewportIndex(0))
+            .map_or(framebuffer, |scissor| {
+                scissor.render_area.intersection(&framebuffer)
+            })
+    }
+
+    /// Fetches attributes of vertices from bound vertex buffers, indexing bindings with per-vertex
+    /// input rate by vertex index and bindings with per-instance input rate by instance index.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fxvertex-input-address-calculation
+    fn fetch_vertex_input(&self, memory: &Memory, indices: &[u32], instance: u32) -> Vec<Vertex> {
+        let mut vertices = indices
+            .iter()
+            .map(|&index| Vertex {
+                position: Default::default(),
                 point_size: 1.0f32,
-                index: 0,
+                index,
+                instance_index: instance,
                 clip_distances: Default::default(),
+                attributes: Default::default(),
             })
-            .collect();
+            .collect::<Vec<_>>();
+
+        for attribute in self.vertex_input_state.attributes.iter().flatten() {
+            assert!(attribute.offset <= MAX_VERTEX_ATTRIBUTE_OFFSET);
+            let binding = self
+                .vertex_input_state
+                .bindings
+                .get(attribute.binding.0 as usize)
+                .and_then(Option::as_ref);
+            let Some(binding) = binding else {
+                warn!(
+                    "Vertex attribute at location {} uses binding {} without description",
+                    attribute.location, attribute.binding.0
+                );
+                continue;
+            };
+            assert!(binding.stride <= MAX_VERTEX_BINDING_STRIDE);
+            let Some(vertex_buffer) = self.vertex_buffers[binding.number].as_ref() else {
+                warn!("Vertex buffer is not bound to binding {}", binding.number.0);
+                continue;
+            };
+            let size = attribute.format.info().bytes_per_pixel as u64;
+            for vertex in vertices.iter_mut() {
+                let element = match binding.input_rate {
+                    VertexInputRate::Vertex => vertex.index,
+                    VertexInputRate::Instance => instance,
+                };
+                let offset = vertex_buffer.offset
+                    + element as u64 * binding.stride as u64
+                    + attribute.offset as u64;
+                let bytes = memory.read_bytes(&vertex_buffer.buffer.binding, offset, size);
+                vertex.attributes[attribute.location as usize] =
+                    Vector4::from_vertex_buffer_bytes(attribute.format, bytes);
+            }
+        }
         vertices
     }
 
-    fn fetch_vertex_input_indexed(
+    /// Reads indices from bound index buffer, returning them with primitive restart index if
+    /// enabled.
+    fn fetch_indices(
         &self,
         memory: &Memory,
         index_count: u32,
-        instance_count: u32,
         first_index: u32,
-        _vertex_offset: i32,
-        first_instance: u32,
-    ) -> Vec<Vertex> {
-        assert_eq!(instance_count, 1);
-        assert_eq!(first_index, 0);
-        assert_eq!(first_instance, 0);
-
+    ) -> (Vec<u32>, Option<u32>) {
         let Some(index_buffer) = self.index_buffer.as_ref() else {
             unreachable!()
         };
+        let index_size = index_buffer.index_size as usize;
+        assert!(matches!(index_size, 1 | 2 | 4));
 
-        self.vertex_input_state.bindings[0].as_ref().map_or_else(
-            || {
-                let mut vertices = vec![];
-                for index in first_index..first_index + index_count {
-                    let bytes = memory.read_bytes(
-                        &index_buffer.buffer.binding,
-                        index_buffer.offset + index as u64 * index_buffer.index_size as u64,
-                        index_buffer.index_size as u64,
-                    );
-                    let index =
-                        byteorder::NativeEndian::read_uint(bytes, index_buffer.index_size as usize)
-                            as u32;
-                    vertices.push(Vertex {
-                        position: Default::default(),
-                        point_size: 1.0f32,
-                        index,
-                        clip_distances: Default::default(),
-                    });
-                }
-                vertices
-            },
-            |_binding| {
-                warn!("TODO: Determine used VertexBindings from vertex shader (if any)");
-                todo!();
-            },
-        )
+        let bytes = memory.read_bytes(
+            &index_buffer.buffer.binding,
+            index_buffer.offset + first_index as u64 * index_size as u64,
+            index_count as u64 * index_size as u64,
+        );
+        let indices = bytes
+            .chunks_exact(index_size)
+            .map(|bytes| byteorder::NativeEndian::read_uint(bytes, index_size) as u32)
+            .collect();
+
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#drawing-primitive-topologies
+        let restart_index = self
+            .input_assembly_state
+            .primitive_restart
+            .then_some(u32::MAX >> (32 - 8 * index_size));
+        (indices, restart_index)
     }
 
     fn execute_vertex_shader(
         &self,
         vertex_input_state: &VertexInputState,
+        resources: &ShaderResources,
         vertices: Vec<Vertex>,
     ) -> Vec<VertexShaderOutput> {
         let shader = self
@@ -423,16 +927,20 @@ impl GraphicsPipeline {
             .as_ref()
             .unwrap_or_else(|| unreachable!());
 
-        shader.execute_vertex_shader(vertex_input_state, vertices)
+        shader.execute_vertex_shader(vertex_input_state, resources, vertices)
     }
 
-    fn execute_fragment_shader(&self, fragments: Vec<Fragment>) -> Vec<FragmentShaderOutput> {
+    fn execute_fragment_shader(
+        &self,
+        resources: &ShaderResources,
+        fragments: Vec<Fragment>,
+    ) -> Vec<FragmentShaderOutput> {
         let shader = self
             .shader_state
             .fragment_shader
             .as_ref()
             .unwrap_or_else(|| unreachable!());
-        shader.execute_fragment_shader(fragments)
+        shader.execute_fragment_shader(resources, fragments)
     }
 }
 
@@ -442,6 +950,96 @@ pub struct RenderArea {
     pub offset: Offset2<i32>,
 }
 
+impl RenderArea {
+    /// Returns common part of both areas, which is empty if they do not overlap.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let range = |offset: i32, size: u32| (offset as i64, offset as i64 + size as i64);
+        let (x0, x1) = range(self.offset.x, self.extent.width);
+        let (y0, y1) = range(self.offset.y, self.extent.height);
+        let (other_x0, other_x1) = range(other.offset.x, other.extent.width);
+        let (other_y0, other_y1) = range(other.offset.y, other.extent.height);
+        let (x0, x1) = (x0.max(other_x0), x1.min(other_x1));
+        let (y0, y1) = (y0.max(other_y0), y1.min(other_y1));
+        Self {
+            extent: Extent2 {
+                width: (x1 - x0).max(0) as u32,
+                height: (y1 - y0).max(0) as u32,
+            },
+            offset: Offset2 {
+                x: x0 as i32,
+                y: y0 as i32,
+            },
+        }
+    }
+
+    /// Returns whether framebuffer coordinates lie inside area.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let (x0, y0) = (self.offset.x as f32, self.offset.y as f32);
+        let (x1, y1) = (
+            x0 + self.extent.width as f32,
+            y0 + self.extent.height as f32,
+        );
+        (x0..x1).contains(&x) && (y0..y1).contains(&y)
+    }
+}
+
+/// Primitive in framebuffer coordinates left by clipping and culling, ready for rasterization.
+#[derive(Debug, Clone)]
+enum RasterPrimitive {
+    Point(Box<Vertex>),
+    Line(Box<[Vertex; 2]>),
+    /// Convex polygon with facing and depth bias shared by its fragments.
+    Polygon {
+        vertices: Vec<Vertex>,
+        front_facing: bool,
+        bias: f32,
+    },
+}
+
+impl RasterPrimitive {
+    /// Returns area containing every pixel primitive may cover when rasterized with lines
+    /// `line_width` pixels wide, in any polygon mode.
+    fn bounds(&self, line_width: f32) -> RenderArea {
+        let vertices = match self {
+            Self::Point(vertex) => std::slice::from_ref(vertex.as_ref()),
+            Self::Line(vertices) => vertices.as_slice(),
+            Self::Polygon { vertices, .. } => vertices.as_slice(),
+        };
+        let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+        for vertex in vertices {
+            // Wide lines and points extend beyond their vertices by half their size.
+            let half_size = line_width.max(point_size(*vertex)) / 2.0f32 + 1.0f32;
+            for i in 0..2 {
+                let x = vertex.position.get_as_sfloat32(i);
+                min[i] = min[i].min(x - half_size);
+                max[i] = max[i].max(x + half_size);
+            }
+        }
+        // Bounds saturate at framebuffer limits, including non-finite coordinates.
+        let [x0, y0] = min.map(|x| x.floor().clamp(0.0f32, i32::MAX as f32) as i32);
+        let [x1, y1] = max.map(|x| x.ceil().clamp(0.0f32, i32::MAX as f32) as i32);
+        RenderArea {
+            extent: Extent2 {
+                width: (x1 - x0).max(0) as u32,
+                height: (y1 - y0).max(0) as u32,
+            },
+            offset: Offset2 { x: x0, y: y0 },
+        }
+    }
+}
+
+/// Returns triangles fanning out of first vertex of convex polygon.
+fn fan_triangles(polygon: &[Vertex]) -> impl Iterator<Item = [Vertex; 3]> + '_ {
+    (2..polygon.len()).map(|i| [polygon[0], polygon[i - 1], polygon[i]])
+}
+
+/// Returns size of rasterized point, clamped to supported range.
+const fn point_size(vertex: Vertex) -> f32 {
+    vertex
+        .point_size
+        .clamp(POINT_SIZE_RANGE.0, POINT_SIZE_RANGE.1)
+}
+
 #[derive(Debug, Clone)]
 pub struct RenderTarget {
     pub index: RenderTargetIndex,
@@ -450,6 +1048,63 @@ pub struct RenderTarget {
     pub image: DescriptorImage,
 }
 
+impl RenderTarget {
+    /// Returns offset of sample within memory bound to image. Samples of each pixel are stored one
+    /// after another.
+    pub const fn sample_offset(&self, x: u32, y: u32, sample: u32) -> u64 {
+        let pixel = y as u64 * self.image.extent.width as u64 + x as u64;
+        let sample = pixel * self.samples as u64 + sample as u64;
+        self.image.binding.offset + sample * self.format.info().bytes_per_pixel as u64
+    }
+
+    /// Reads first sample of every pixel for fragment shader to read as input attachment, keeping
+    /// integers of integer formats and converting others to floats.
+    fn input_attachment(&self, memory: &Memory) -> InputAttachment {
+        let Extent3 { width, height, .. } = self.image.extent;
+        let info = self.format.info();
+        let bytes_per_pixel = info.bytes_per_pixel as u64;
+        let texels = itertools::iproduct!(0..height, 0..width)
+            .map(|(y, x)| {
+                let bytes = memory.read_bytes(
+                    &self.image.binding,
+                    self.sample_offset(x, y, 0),
+                    bytes_per_pixel,
+                );
+                match info.numeric_format {
+                    NumericFormat::Uint | NumericFormat::Sint => {
+                        read_integer_texel(self.format, bytes)
+                    }
+                    _ => read_texel(self.format, bytes).map(f32::to_bits),
+                }
+            })
+            .collect();
+        InputAttachment {
+            width,
+            height,
+            texels,
+        }
+    }
+
+    /// Calls `f` with bytes of every sample of pixels within `area`.
+    fn for_each_sample_in_area(
+        &self,
+        memory: &mut Memory,
+        area: RenderArea,
+        mut f: impl FnMut(&mut [u8]),
+    ) {
+        let bytes_per_pixel = self.format.info().bytes_per_pixel as usize;
+        let row_size = (area.extent.width * self.samples) as usize * bytes_per_pixel;
+        let dst = memory.get_memory_mut(&self.image.binding);
+        let (x, y) = (area.offset.x as u32, area.offset.y as u32);
+        for y in y..y + area.extent.height {
+            let offset = self.sample_offset(x, y, 0) as usize;
+            for sample in dst[offset..offset + row_size].chunks_exact_mut(bytes_per_pixel) {
+                f(sample);
+            }
+        }
+    }
+}
+
 #[derive(Eq, Hash, PartialEq, Debug, Copy, Clone)]
 pub struct RenderTargetIndex(pub usize);
 
@@ -496,6 +1151,15 @@ pub struct Scissor {
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct ViewportIndex(pub u32);
 
+/// Pipeline states taken from dynamic state commands instead of pipeline.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DynamicState {
+    pub viewport: bool,
+    pub scissor: bool,
+    pub depth_bias: bool,
+    pub depth_bounds: bool,
+}
+
 // TODO: impl_index_trait!()
 impl Index<ViewportIndex> for [Option<Viewport>] {
     type Output = Option<Viewport>;
@@ -550,3 +1214,1566 @@ pub struct RasterizationState {
     pub depth_bias_slope_factor: f32,
     pub line_width: f32,
 }
+
+#[derive(Debug, Clone, Default)]
+pub struct DepthStencilState {
+    pub depth_test_enable: bool,
+    pub depth_write_enable: bool,
+    pub depth_compare_op: CompareOp,
+    pub depth_bounds_test_enable: bool,
+    pub stencil_test_enable: bool,
+    pub front: StencilOpState,
+    pub back: StencilOpState,
+    pub depth_bounds: Range2<f32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ColorBlendState {
+    pub logic_op_enable: bool,
+    pub logic_op: LogicOp,
+    pub attachments: [Option<ColorBlendAttachmentState>; MAX_COLOR_ATTACHMENTS as usize],
+    pub blend_constants: Color,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ColorBlendAttachmentState {
+    pub blend_enable: bool,
+    pub src_color_blend_factor: BlendFactor,
+    pub dst_color_blend_factor: BlendFactor,
+    pub color_blend_op: BlendOp,
+    pub src_alpha_blend_factor: BlendFactor,
+    pub dst_alpha_blend_factor: BlendFactor,
+    pub alpha_blend_op: BlendOp,
+    pub color_write_mask: ColorComponentFlags,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryHandleStore;
+    use common::graphics::{
+        BlendFactor, ComponentMapping, DescriptorBuffer, IndexBuffer, MemoryBinding, StencilOp,
+        VertexAttribute, VertexBinding, VertexBindingNumber,
+    };
+    use common::math::{linear_to_srgb, Extent3};
+    use shader::glsl::{Shader, SpecializationInfo};
+
+    // #version 450
+    // layout(location = 0) in vec4 position;
+    // void main() { gl_Position = position; }
+    const VERTEX_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000015, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0007000f, 0x00000000, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000003, 0x00000004, 0x00050048, 0x0000000c, 0x00000000, 0x0000000b,
+        0x00000000, 0x00050048, 0x0000000c, 0x00000001, 0x0000000b, 0x00000001, 0x00050048,
+        0x0000000c, 0x00000002, 0x0000000b, 0x00000003, 0x00050048, 0x0000000c, 0x00000003,
+        0x0000000b, 0x00000004, 0x00030047, 0x0000000c, 0x00000002, 0x00040047, 0x00000004,
+        0x0000001e, 0x00000000, 0x00020013, 0x00000005, 0x00030021, 0x00000006, 0x00000005,
+        0x00030016, 0x00000007, 0x00000020, 0x00040017, 0x00000008, 0x00000007, 0x00000004,
+        0x00040015, 0x00000009, 0x00000020, 0x00000000, 0x0004002b, 0x00000009, 0x0000000a,
+        0x00000001, 0x0004001c, 0x0000000b, 0x00000007, 0x0000000a, 0x0006001e, 0x0000000c,
+        0x00000008, 0x00000007, 0x0000000b, 0x0000000b, 0x00040020, 0x0000000d, 0x00000003,
+        0x0000000c, 0x0004003b, 0x0000000d, 0x00000003, 0x00000003, 0x00040015, 0x0000000e,
+        0x00000020, 0x00000001, 0x0004002b, 0x0000000e, 0x0000000f, 0x00000000, 0x00040020,
+        0x00000010, 0x00000001, 0x00000008, 0x0004003b, 0x00000010, 0x00000004, 0x00000001,
+        0x00040020, 0x00000011, 0x00000003, 0x00000008, 0x00050036, 0x00000005, 0x00000001,
+        0x00000000, 0x00000006, 0x000200f8, 0x00000012, 0x0004003d, 0x00000008, 0x00000013,
+        0x00000004, 0x00050041, 0x00000011, 0x00000014, 0x00000003, 0x0000000f, 0x0003003e,
+        0x00000014, 0x00000013, 0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(location = 0) in vec4 position;
+    // void main() { gl_Position = position; gl_PointSize = 4.0; }
+    const POINT_SIZE_VERTEX_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000019, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0007000f, 0x00000000, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000003, 0x00000004, 0x00050048, 0x0000000c, 0x00000000, 0x0000000b,
+        0x00000000, 0x00050048, 0x0000000c, 0x00000001, 0x0000000b, 0x00000001, 0x00050048,
+        0x0000000c, 0x00000002, 0x0000000b, 0x00000003, 0x00050048, 0x0000000c, 0x00000003,
+        0x0000000b, 0x00000004, 0x00030047, 0x0000000c, 0x00000002, 0x00040047, 0x00000004,
+        0x0000001e, 0x00000000, 0x00020013, 0x00000005, 0x00030021, 0x00000006, 0x00000005,
+        0x00030016, 0x00000007, 0x00000020, 0x00040017, 0x00000008, 0x00000007, 0x00000004,
+        0x00040015, 0x00000009, 0x00000020, 0x00000000, 0x0004002b, 0x00000009, 0x0000000a,
+        0x00000001, 0x0004001c, 0x0000000b, 0x00000007, 0x0000000a, 0x0006001e, 0x0000000c,
+        0x00000008, 0x00000007, 0x0000000b, 0x0000000b, 0x00040020, 0x0000000d, 0x00000003,
+        0x0000000c, 0x0004003b, 0x0000000d, 0x00000003, 0x00000003, 0x00040015, 0x0000000e,
+        0x00000020, 0x00000001, 0x0004002b, 0x0000000e, 0x0000000f, 0x00000000, 0x00040020,
+        0x00000010, 0x00000001, 0x00000008, 0x0004003b, 0x00000010, 0x00000004, 0x00000001,
+        0x00040020, 0x00000011, 0x00000003, 0x00000008, 0x0004002b, 0x0000000e, 0x00000015,
+        0x00000001, 0x00040020, 0x00000016, 0x00000003, 0x00000007, 0x0004002b, 0x00000007,
+        0x00000017, 0x40800000, 0x00050036, 0x00000005, 0x00000001, 0x00000000, 0x00000006,
+        0x000200f8, 0x00000012, 0x0004003d, 0x00000008, 0x00000013, 0x00000004, 0x00050041,
+        0x00000011, 0x00000014, 0x00000003, 0x0000000f, 0x0003003e, 0x00000014, 0x00000013,
+        0x00050041, 0x00000016, 0x00000018, 0x00000003, 0x00000015, 0x0003003e, 0x00000018,
+        0x00000017, 0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(location = 0) out vec4 color;
+    // void main() { color = vec4(1.0, 0.0, 0.0, 1.0); }
+    const FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x0000000e, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0006000f, 0x00000004, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00030010, 0x00000001, 0x00000007, 0x00040047, 0x00000002,
+        0x0000001e, 0x00000000, 0x00020013, 0x00000003, 0x00030021, 0x00000004, 0x00000003,
+        0x00030016, 0x00000005, 0x00000020, 0x00040017, 0x00000006, 0x00000005, 0x00000004,
+        0x00040020, 0x00000007, 0x00000003, 0x00000006, 0x0004003b, 0x00000007, 0x00000002,
+        0x00000003, 0x0004002b, 0x00000005, 0x00000008, 0x3f800000, 0x0004002b, 0x00000005,
+        0x00000009, 0x00000000, 0x0004002b, 0x00000005, 0x0000000a, 0x00000000, 0x0004002b,
+        0x00000005, 0x0000000b, 0x3f800000, 0x0007002c, 0x00000006, 0x0000000c, 0x00000008,
+        0x00000009, 0x0000000a, 0x0000000b, 0x00050036, 0x00000003, 0x00000001, 0x00000000,
+        0x00000004, 0x000200f8, 0x0000000d, 0x0003003e, 0x00000002, 0x0000000c, 0x000100fd,
+        0x00010038,
+    ];
+
+    // #version 450
+    // layout(constant_id = 0) const float red = 1.0;
+    // layout(location = 0) out vec4 color;
+    // void main() { color = vec4(red, 0.0, 0.0, 1.0); }
+    const SPEC_CONSTANT_FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x0000000e, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0006000f, 0x00000004, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00030010, 0x00000001, 0x00000007, 0x00040047, 0x00000002,
+        0x0000001e, 0x00000000, 0x00040047, 0x00000008, 0x00000001, 0x00000000, 0x00020013,
+        0x00000003, 0x00030021, 0x00000004, 0x00000003, 0x00030016, 0x00000005, 0x00000020,
+        0x00040017, 0x00000006, 0x00000005, 0x00000004, 0x00040020, 0x00000007, 0x00000003,
+        0x00000006, 0x0004003b, 0x00000007, 0x00000002, 0x00000003, 0x00040032, 0x00000005,
+        0x00000008, 0x3f800000, 0x0004002b, 0x00000005, 0x00000009, 0x00000000, 0x0004002b,
+        0x00000005, 0x0000000a, 0x00000000, 0x0004002b, 0x00000005, 0x0000000b, 0x3f800000,
+        0x00070033, 0x00000006, 0x0000000c, 0x00000008, 0x00000009, 0x0000000a, 0x0000000b,
+        0x00050036, 0x00000003, 0x00000001, 0x00000000, 0x00000004, 0x000200f8, 0x0000000d,
+        0x0003003e, 0x00000002, 0x0000000c, 0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(location = 0) out vec4 color;
+    // void main() { color = vec4(gl_FragCoord.x / 8.0, 0.0, 0.0, 1.0); }
+    const FRAG_COORD_FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000015, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0007000f, 0x00000004, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00000003, 0x00030010, 0x00000001, 0x00000007, 0x00040047,
+        0x00000002, 0x0000001e, 0x00000000, 0x00040047, 0x00000003, 0x0000000b, 0x0000000f,
+        0x00020013, 0x00000004, 0x00030021, 0x00000005, 0x00000004, 0x00030016, 0x00000006,
+        0x00000020, 0x00040017, 0x00000007, 0x00000006, 0x00000004, 0x00040020, 0x00000008,
+        0x00000003, 0x00000007, 0x0004003b, 0x00000008, 0x00000002, 0x00000003, 0x00040020,
+        0x00000009, 0x00000001, 0x00000007, 0x0004003b, 0x00000009, 0x00000003, 0x00000001,
+        0x00040015, 0x0000000a, 0x00000020, 0x00000000, 0x0004002b, 0x0000000a, 0x0000000b,
+        0x00000000, 0x00040020, 0x0000000c, 0x00000001, 0x00000006, 0x0004002b, 0x00000006,
+        0x0000000d, 0x41000000, 0x0004002b, 0x00000006, 0x0000000e, 0x00000000, 0x0004002b,
+        0x00000006, 0x0000000f, 0x3f800000, 0x00050036, 0x00000004, 0x00000001, 0x00000000,
+        0x00000005, 0x000200f8, 0x00000010, 0x00050041, 0x0000000c, 0x00000011, 0x00000003,
+        0x0000000b, 0x0004003d, 0x00000006, 0x00000012, 0x00000011, 0x00050088, 0x00000006,
+        0x00000013, 0x00000012, 0x0000000d, 0x00070050, 0x00000007, 0x00000014, 0x00000013,
+        0x0000000e, 0x0000000e, 0x0000000f, 0x0003003e, 0x00000002, 0x00000014, 0x000100fd,
+        0x00010038,
+    ];
+
+    // #version 450
+    // layout(location = 0) out vec4 color;
+    // void main() { color = vec4(1.0, 0.0, 0.0, 1.0); gl_FragDepth = gl_FragCoord.x / 8.0; }
+    const FRAG_DEPTH_FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000017, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0008000f, 0x00000004, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00000003, 0x00000004, 0x00030010, 0x00000001, 0x00000007,
+        0x00030010, 0x00000001, 0x0000000c, 0x00040047, 0x00000002, 0x0000001e, 0x00000000,
+        0x00040047, 0x00000003, 0x0000000b, 0x0000000f, 0x00040047, 0x00000004, 0x0000000b,
+        0x00000016, 0x00020013, 0x00000005, 0x00030021, 0x00000006, 0x00000005, 0x00030016,
+        0x00000007, 0x00000020, 0x00040017, 0x00000008, 0x00000007, 0x00000004, 0x00040020,
+        0x00000009, 0x00000003, 0x00000008, 0x0004003b, 0x00000009, 0x00000002, 0x00000003,
+        0x00040020, 0x0000000a, 0x00000001, 0x00000008, 0x0004003b, 0x0000000a, 0x00000003,
+        0x00000001, 0x00040015, 0x0000000b, 0x00000020, 0x00000000, 0x0004002b, 0x0000000b,
+        0x0000000c, 0x00000000, 0x00040020, 0x0000000d, 0x00000001, 0x00000007, 0x00040020,
+        0x0000000e, 0x00000003, 0x00000007, 0x0004003b, 0x0000000e, 0x00000004, 0x00000003,
+        0x0004002b, 0x00000007, 0x0000000f, 0x41000000, 0x0004002b, 0x00000007, 0x00000010,
+        0x00000000, 0x0004002b, 0x00000007, 0x00000011, 0x3f800000, 0x0007002c, 0x00000008,
+        0x00000012, 0x00000011, 0x00000010, 0x00000010, 0x00000011, 0x00050036, 0x00000005,
+        0x00000001, 0x00000000, 0x00000006, 0x000200f8, 0x00000013, 0x0003003e, 0x00000002,
+        0x00000012, 0x00050041, 0x0000000d, 0x00000014, 0x00000003, 0x0000000c, 0x0004003d,
+        0x00000007, 0x00000015, 0x00000014, 0x00050088, 0x00000007, 0x00000016, 0x00000015,
+        0x0000000f, 0x0003003e, 0x00000004, 0x00000016, 0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(location = 0) in vec4 position;
+    // layout(location = 1) in float value;
+    // layout(location = 0) flat out int index;
+    // layout(location = 1) out float interpolated_value;
+    // void main() {
+    //     gl_Position = position;
+    //     index = gl_VertexIndex;
+    //     interpolated_value = value;
+    // }
+    const FLAT_VERTEX_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000017, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x000b000f, 0x00000000, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00000003, 0x00000004, 0x00000005, 0x00000006, 0x00000007,
+        0x00040047, 0x00000002, 0x0000000b, 0x00000000, 0x00040047, 0x00000003, 0x0000001e,
+        0x00000000, 0x00040047, 0x00000004, 0x0000001e, 0x00000001, 0x00030047, 0x00000005,
+        0x0000000e, 0x00040047, 0x00000005, 0x0000001e, 0x00000000, 0x00040047, 0x00000006,
+        0x0000001e, 0x00000001, 0x00040047, 0x00000007, 0x0000000b, 0x0000002a, 0x00020013,
+        0x00000008, 0x00030021, 0x00000009, 0x00000008, 0x00030016, 0x0000000a, 0x00000020,
+        0x00040017, 0x0000000b, 0x0000000a, 0x00000004, 0x00040015, 0x0000000c, 0x00000020,
+        0x00000001, 0x00040020, 0x0000000d, 0x00000003, 0x0000000b, 0x0004003b, 0x0000000d,
+        0x00000002, 0x00000003, 0x00040020, 0x0000000e, 0x00000001, 0x0000000b, 0x0004003b,
+        0x0000000e, 0x00000003, 0x00000001, 0x00040020, 0x0000000f, 0x00000001, 0x0000000a,
+        0x0004003b, 0x0000000f, 0x00000004, 0x00000001, 0x00040020, 0x00000010, 0x00000003,
+        0x0000000c, 0x0004003b, 0x00000010, 0x00000005, 0x00000003, 0x00040020, 0x00000011,
+        0x00000003, 0x0000000a, 0x0004003b, 0x00000011, 0x00000006, 0x00000003, 0x00040020,
+        0x00000012, 0x00000001, 0x0000000c, 0x0004003b, 0x00000012, 0x00000007, 0x00000001,
+        0x00050036, 0x00000008, 0x00000001, 0x00000000, 0x00000009, 0x000200f8, 0x00000013,
+        0x0004003d, 0x0000000b, 0x00000014, 0x00000003, 0x0003003e, 0x00000002, 0x00000014,
+        0x0004003d, 0x0000000c, 0x00000015, 0x00000007, 0x0003003e, 0x00000005, 0x00000015,
+        0x0004003d, 0x0000000a, 0x00000016, 0x00000004, 0x0003003e, 0x00000006, 0x00000016,
+        0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(location = 0) flat in int index;
+    // layout(location = 1) in float value;
+    // layout(location = 0) out vec4 color;
+    // void main() { color = vec4(value, float(index) / 255.0, 0.0, 1.0); }
+    const FLAT_FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000016, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0008000f, 0x00000004, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00000003, 0x00000004, 0x00030010, 0x00000001, 0x00000007,
+        0x00040047, 0x00000002, 0x0000001e, 0x00000000, 0x00030047, 0x00000003, 0x0000000e,
+        0x00040047, 0x00000003, 0x0000001e, 0x00000000, 0x00040047, 0x00000004, 0x0000001e,
+        0x00000001, 0x00020013, 0x00000005, 0x00030021, 0x00000006, 0x00000005, 0x00030016,
+        0x00000007, 0x00000020, 0x00040017, 0x00000008, 0x00000007, 0x00000004, 0x00040015,
+        0x00000009, 0x00000020, 0x00000001, 0x00040020, 0x0000000a, 0x00000003, 0x00000008,
+        0x0004003b, 0x0000000a, 0x00000002, 0x00000003, 0x00040020, 0x0000000b, 0x00000001,
+        0x00000009, 0x0004003b, 0x0000000b, 0x00000003, 0x00000001, 0x00040020, 0x0000000c,
+        0x00000001, 0x00000007, 0x0004003b, 0x0000000c, 0x00000004, 0x00000001, 0x0004002b,
+        0x00000007, 0x0000000d, 0x437f0000, 0x0004002b, 0x00000007, 0x0000000e, 0x00000000,
+        0x0004002b, 0x00000007, 0x0000000f, 0x3f800000, 0x00050036, 0x00000005, 0x00000001,
+        0x00000000, 0x00000006, 0x000200f8, 0x00000010, 0x0004003d, 0x00000007, 0x00000011,
+        0x00000004, 0x0004003d, 0x00000009, 0x00000012, 0x00000003, 0x0004006f, 0x00000007,
+        0x00000013, 0x00000012, 0x00050088, 0x00000007, 0x00000014, 0x00000013, 0x0000000d,
+        0x00070050, 0x00000008, 0x00000015, 0x00000011, 0x00000014, 0x0000000e, 0x0000000f,
+        0x0003003e, 0x00000002, 0x00000015, 0x000100fd, 0x00010038,
+    ];
+
+    const WIDTH: u32 = 8;
+    const HEIGHT: u32 = 8;
+
+    fn bind_memory(memory: &mut Memory, bytes: &[u8]) -> MemoryBinding {
+        let allocation = memory.allocate_memory(bytes.len() as u64);
+        memory.write_bytes(bytes, &allocation, 0);
+        let mut binding = MemoryBinding::new();
+        binding.store(allocation, 0, bytes.len() as u64);
+        binding
+    }
+
+    /// Creates pipeline drawing triangles with given vertex positions into 8x8 render target.
+    // #version 450
+    // layout(location = 0) out vec4 color0;
+    // layout(location = 1) out vec4 color1;
+    // void main() {
+    //     color0 = vec4(1.0, 0.0, 0.0, 1.0);
+    //     color1 = vec4(0.0, 1.0, 0.0, 1.0);
+    // }
+    const MULTIPLE_OUTPUTS_FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x0000000e, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0007000f, 0x00000004, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00000003, 0x00030010, 0x00000001, 0x00000007, 0x00040047,
+        0x00000002, 0x0000001e, 0x00000000, 0x00040047, 0x00000003, 0x0000001e, 0x00000001,
+        0x00020013, 0x00000004, 0x00030021, 0x00000005, 0x00000004, 0x00030016, 0x00000006,
+        0x00000020, 0x00040017, 0x00000007, 0x00000006, 0x00000004, 0x00040020, 0x00000008,
+        0x00000003, 0x00000007, 0x0004003b, 0x00000008, 0x00000002, 0x00000003, 0x0004003b,
+        0x00000008, 0x00000003, 0x00000003, 0x0004002b, 0x00000006, 0x00000009, 0x00000000,
+        0x0004002b, 0x00000006, 0x0000000a, 0x3f800000, 0x0007002c, 0x00000007, 0x0000000b,
+        0x0000000a, 0x00000009, 0x00000009, 0x0000000a, 0x0007002c, 0x00000007, 0x0000000c,
+        0x00000009, 0x0000000a, 0x00000009, 0x0000000a, 0x00050036, 0x00000004, 0x00000001,
+        0x00000000, 0x00000005, 0x000200f8, 0x0000000d, 0x0003003e, 0x00000002, 0x0000000b,
+        0x0003003e, 0x00000003, 0x0000000c, 0x000100fd, 0x00010038,
+    ];
+
+    fn triangle_pipeline(
+        memory: &mut Memory,
+        positions: &[[f32; 4]],
+    ) -> (GraphicsPipeline, RenderTarget) {
+        let mut pipeline = GraphicsPipeline::new();
+        pipeline.set_shader_state(ShaderState {
+            vertex_shader: Shader::new("main", VERTEX_SHADER.to_vec()).ok(),
+            fragment_shader: Shader::new("main", FRAGMENT_SHADER.to_vec()).ok(),
+        });
+        let mut vertex_input_state = VertexInputState::default();
+        vertex_input_state.bindings[0] = Some(VertexBinding {
+            number: VertexBindingNumber(0),
+            stride: 16,
+            input_rate: VertexInputRate::Vertex,
+        });
+        vertex_input_state.attributes[0] = Some(VertexAttribute {
+            location: 0,
+            binding: VertexBindingNumber(0),
+            format: Format::R32G32B32A32Sfloat,
+            offset: 0,
+        });
+        pipeline.set_vertex_input_state(vertex_input_state);
+        pipeline.set_input_assembly_state(InputAssemblyState {
+            topology: PrimitiveTopology::TriangleList,
+            primitive_restart: false,
+        });
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(Viewport {
+            offset: Offset2 { x: 0.0, y: 0.0 },
+            extent: Extent2 {
+                width: WIDTH as f32,
+                height: HEIGHT as f32,
+            },
+            depth: Range2 { min: 0.0, max: 1.0 },
+        });
+        pipeline.set_viewport_state(viewport_state);
+
+        let bytes = positions
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        pipeline.bind_vertex_buffer(VertexBuffer {
+            binding_number: VertexBindingNumber(0),
+            buffer: DescriptorBuffer {
+                binding: bind_memory(memory, &bytes),
+            },
+            offset: 0,
+        });
+        let rt = RenderTarget {
+            index: RenderTargetIndex(0),
+            format: Format::R8G8B8A8Unorm,
+            samples: 1,
+            image: DescriptorImage {
+                binding: bind_memory(memory, &[0; (WIDTH * HEIGHT * 4) as usize]),
+                extent: Extent3 {
+                    width: WIDTH,
+                    height: HEIGHT,
+                    depth: 1,
+                },
+                components: ComponentMapping::default(),
+            },
+        };
+        pipeline.bind_render_target(rt.clone());
+        (pipeline, rt)
+    }
+
+    /// Draws `triangles` overlapping triangles of varying depth and attributes, blended with
+    /// constant color, returning contents of color and depth render targets.
+    fn render_scene(
+        backend: RasterizerBackend,
+        samples: u32,
+        extent: Extent2<u32>,
+        triangles: u32,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let mut memory = Memory::new();
+        let mut pipeline = GraphicsPipeline::new();
+        pipeline.set_rasterizer_backend(backend);
+        pipeline.set_shader_state(ShaderState {
+            vertex_shader: Shader::new("main", FLAT_VERTEX_SHADER.to_vec()).ok(),
+            fragment_shader: Shader::new("main", FLAT_FRAGMENT_SHADER.to_vec()).ok(),
+        });
+        let mut vertex_input_state = VertexInputState::default();
+        vertex_input_state.bindings[0] = Some(VertexBinding {
+            number: VertexBindingNumber(0),
+            stride: 20,
+            input_rate: VertexInputRate::Vertex,
+        });
+        vertex_input_state.attributes[0] = Some(VertexAttribute {
+            location: 0,
+            binding: VertexBindingNumber(0),
+            format: Format::R32G32B32A32Sfloat,
+            offset: 0,
+        });
+        vertex_input_state.attributes[1] = Some(VertexAttribute {
+            location: 1,
+            binding: VertexBindingNumber(0),
+            format: Format::R32Sfloat,
+            offset: 16,
+        });
+        pipeline.set_vertex_input_state(vertex_input_state);
+        pipeline.set_input_assembly_state(InputAssemblyState {
+            topology: PrimitiveTopology::TriangleList,
+            primitive_restart: false,
+        });
+        // Scissor is not aligned to tiles.
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(Viewport {
+            offset: Offset2 { x: 0.0, y: 0.0 },
+            extent: Extent2 {
+                width: extent.width as f32,
+                height: extent.height as f32,
+            },
+            depth: Range2 { min: 0.0, max: 1.0 },
+        });
+        viewport_state.scissors[0] = Some(Scissor {
+            render_area: RenderArea {
+                extent: Extent2 {
+                    width: extent.width - 10,
+                    height: extent.height - 6,
+                },
+                offset: Offset2 { x: 5, y: 3 },
+            },
+        });
+        pipeline.set_viewport_state(viewport_state);
+        pipeline.set_depth_stencil_state(DepthStencilState {
+            depth_test_enable: true,
+            depth_write_enable: true,
+            depth_compare_op: CompareOp::LessOrEqual,
+            ..Default::default()
+        });
+        let mut color_blend_state = ColorBlendState {
+            blend_constants: Color::from_sfloat32_raw(0.5, 0.5, 0.5, 0.5),
+            ..Default::default()
+        };
+        color_blend_state.attachments[0] = Some(ColorBlendAttachmentState {
+            blend_enable: true,
+            src_color_blend_factor: BlendFactor::ConstantColor,
+            dst_color_blend_factor: BlendFactor::OneMinusConstantColor,
+            src_alpha_blend_factor: BlendFactor::One,
+            ..Default::default()
+        });
+        pipeline.set_color_blend_state(color_blend_state);
+
+        // Vertices are scattered over and around framebuffer by linear congruential generator.
+        let mut state = 1u32;
+        let mut random = || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 24) as f32
+        };
+        let vertices = (0..triangles * 3)
+            .flat_map(|_| {
+                let x = random().mul_add(2.4, -1.2);
+                let y = random().mul_add(2.4, -1.2);
+                [x, y, random(), 1.0, random()]
+            })
+            .flat_map(f32::to_le_bytes)
+            .collect::<Vec<_>>();
+        pipeline.bind_vertex_buffer(VertexBuffer {
+            binding_number: VertexBindingNumber(0),
+            buffer: DescriptorBuffer {
+                binding: bind_memory(&mut memory, &vertices),
+            },
+            offset: 0,
+        });
+        let sample_count = (extent.width * extent.height * samples) as usize;
+        let image = |memory: &mut Memory, bytes: &[u8]| DescriptorImage {
+            binding: bind_memory(memory, bytes),
+            extent: Extent3 {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            },
+            components: ComponentMapping::default(),
+        };
+        let rt = RenderTarget {
+            index: RenderTargetIndex(0),
+            format: Format::R8G8B8A8Unorm,
+            samples,
+            image: image(&mut memory, &vec![0; sample_count * 4]),
+        };
+        let depth_rt = RenderTarget {
+            index: RenderTargetIndex(1),
+            format: Format::D32Sfloat,
+            samples,
+            image: image(&mut memory, &1.0f32.to_ne_bytes().repeat(sample_count)),
+        };
+        pipeline.bind_render_target(rt.clone());
+        pipeline.bind_render_target(depth_rt.clone());
+        pipeline.draw_primitive(&mut memory, triangles * 3, 1, 0, 0);
+
+        let size = sample_count as u64 * 4;
+        (
+            memory.read_bytes(&rt.image.binding, 0, size).to_vec(),
+            memory.read_bytes(&depth_rt.image.binding, 0, size).to_vec(),
+        )
+    }
+
+    fn covered_pixels(memory: &Memory, rt: &RenderTarget) -> Vec<(u32, u32)> {
+        let pixels = memory.read_bytes(&rt.image.binding, 0, (WIDTH * HEIGHT * 4) as u64);
+        let mut covered = vec![];
+        for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+            match pixel {
+                [255, 0, 0, 255] => covered.push((i as u32 % WIDTH, i as u32 / WIDTH)),
+                [0, 0, 0, 0] => {}
+                _ => panic!("unexpected pixel {pixel:?}"),
+            }
+        }
+        covered
+    }
+
+    #[test]
+    fn draw_triangle_fill() {
+        let mut memory = Memory::new();
+        // Covers upper left half of framebuffer.
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+        ];
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+
+        // Pixel centers on diagonal edge belong to bottom right edge and are not covered.
+        let expected = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|(x, y)| x + y < WIDTH - 1)
+            .collect::<Vec<_>>();
+        assert_eq!(covered_pixels(&memory, &rt), expected);
+    }
+
+    #[test]
+    fn draw_point_list_point_size() {
+        let mut memory = Memory::new();
+        let positions = [[0.0, 0.0, 0.0, 1.0], [-0.75, -0.75, 0.0, 1.0]];
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.set_shader_state(ShaderState {
+            vertex_shader: Shader::new("main", POINT_SIZE_VERTEX_SHADER.to_vec()).ok(),
+            fragment_shader: Shader::new("main", FRAGMENT_SHADER.to_vec()).ok(),
+        });
+        pipeline.set_input_assembly_state(InputAssemblyState {
+            topology: PrimitiveTopology::PointList,
+            primitive_restart: false,
+        });
+        pipeline.draw_primitive(&mut memory, 2, 1, 0, 0);
+
+        // Points cover 4x4 pixels centered on framebuffer center and clipped to its corner.
+        let expected = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|&(x, y)| (2..6).contains(&x) && (2..6).contains(&y) || x < 3 && y < 3)
+            .collect::<Vec<_>>();
+        assert_eq!(covered_pixels(&memory, &rt), expected);
+    }
+
+    #[test]
+    fn draw_triangle_first_vertex_and_instances() {
+        let mut memory = Memory::new();
+        // Second triangle covers the lower right half of framebuffer, with clockwise winding.
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+        ];
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.draw_primitive(&mut memory, 3, 2, 3, 1);
+
+        let expected = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|(x, y)| x + y >= WIDTH - 1)
+            .collect::<Vec<_>>();
+        assert_eq!(covered_pixels(&memory, &rt), expected);
+    }
+
+    const QUAD: [[f32; 4]; 4] = [
+        [-1.0, -1.0, 0.0, 1.0],
+        [1.0, -1.0, 0.0, 1.0],
+        [-1.0, 1.0, 0.0, 1.0],
+        [1.0, 1.0, 0.0, 1.0],
+    ];
+
+    fn draw_indexed(
+        topology: PrimitiveTopology,
+        indices: &[u32],
+        index_size: u8,
+        primitive_restart: bool,
+        vertex_offset: i32,
+    ) -> Vec<u8> {
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &QUAD);
+        pipeline.set_input_assembly_state(InputAssemblyState {
+            topology,
+            primitive_restart,
+        });
+        let bytes = indices
+            .iter()
+            .flat_map(|&index| match index_size {
+                2 => (index as u16).to_ne_bytes().to_vec(),
+                4 => index.to_ne_bytes().to_vec(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        pipeline.bind_index_buffer(IndexBuffer {
+            buffer: DescriptorBuffer {
+                binding: bind_memory(&mut memory, &bytes),
+            },
+            offset: 0,
+            index_size,
+        });
+        pipeline.draw_primitive_indexed(&mut memory, indices.len() as u32, 1, 0, vertex_offset, 0);
+        memory
+            .read_bytes(&rt.image.binding, 0, (WIDTH * HEIGHT * 4) as u64)
+            .to_vec()
+    }
+
+    #[test]
+    fn draw_indexed_quad_uint16_uint32() {
+        let indices = [0, 1, 2, 2, 1, 3];
+        let pixels = draw_indexed(PrimitiveTopology::TriangleList, &indices, 2, false, 0);
+        assert!(pixels
+            .chunks_exact(4)
+            .all(|pixel| pixel == [255, 0, 0, 255]));
+        assert_eq!(
+            draw_indexed(PrimitiveTopology::TriangleList, &indices, 4, false, 0),
+            pixels
+        );
+    }
+
+    #[test]
+    fn draw_indexed_vertex_offset_and_primitive_restart() {
+        let quad = draw_indexed(
+            PrimitiveTopology::TriangleList,
+            &[0, 1, 2, 2, 1, 3],
+            2,
+            false,
+            0,
+        );
+        // Restart index is compared before vertex offset is applied.
+        let indices = [1, 2, 3, 0xffff, 3, 2, 4];
+        assert_eq!(
+            draw_indexed(PrimitiveTopology::TriangleList, &indices, 2, true, -1),
+            quad
+        );
+        let indices = [1, 2, 3, 0xffff_ffff, 3, 2, 4];
+        assert_eq!(
+            draw_indexed(PrimitiveTopology::TriangleList, &indices, 4, true, -1),
+            quad
+        );
+    }
+
+    #[test]
+    fn draw_indexed_triangle_strip_primitive_restart() {
+        let quad = draw_indexed(
+            PrimitiveTopology::TriangleList,
+            &[0, 1, 2, 2, 1, 3],
+            2,
+            false,
+            0,
+        );
+        assert_eq!(
+            draw_indexed(PrimitiveTopology::TriangleStrip, &[0, 1, 2, 3], 2, false, 0),
+            quad
+        );
+        // Restart ends strip, so no triangle is assembled across it.
+        let indices = [0, 1, 2, 0xffff, 2, 1, 3];
+        assert_eq!(
+            draw_indexed(PrimitiveTopology::TriangleStrip, &indices, 2, true, 0),
+            quad
+        );
+        let pixels = draw_indexed(
+            PrimitiveTopology::TriangleStrip,
+            &[0, 1, 0xffff, 2, 3],
+            2,
+            true,
+            0,
+        );
+        assert!(pixels.iter().all(|&x| x == 0));
+    }
+
+    #[test]
+    fn draw_indexed_triangle_fan() {
+        let quad = draw_indexed(
+            PrimitiveTopology::TriangleList,
+            &[0, 1, 2, 2, 1, 3],
+            2,
+            false,
+            0,
+        );
+        assert_eq!(
+            draw_indexed(PrimitiveTopology::TriangleFan, &[0, 1, 3, 2], 2, false, 0),
+            quad
+        );
+    }
+
+    #[test]
+    fn draw_triangle_cull_mode() {
+        // Upper left triangle is clockwise and lower right one is counter-clockwise in framebuffer.
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+        ];
+        let draw = |cull_mode, front_face| {
+            let mut memory = Memory::new();
+            let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+            pipeline.set_rasterization_state(RasterizationState {
+                cull_mode,
+                front_face,
+                ..Default::default()
+            });
+            pipeline.draw_primitive(&mut memory, 6, 1, 0, 0);
+            covered_pixels(&memory, &rt)
+        };
+        let upper_left = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|(x, y)| x + y < WIDTH - 1)
+            .collect::<Vec<_>>();
+        let lower_right = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|(x, y)| x + y >= WIDTH - 1)
+            .collect::<Vec<_>>();
+
+        assert_eq!(draw(CullMode::Back, FrontFace::Clockwise), upper_left);
+        assert_eq!(draw(CullMode::Front, FrontFace::Clockwise), lower_right);
+        assert_eq!(
+            draw(CullMode::Back, FrontFace::CounterClockwise),
+            lower_right
+        );
+        assert_eq!(draw(CullMode::FrontAndBack, FrontFace::Clockwise), vec![]);
+        assert_eq!(draw(CullMode::None, FrontFace::Clockwise).len(), 64);
+    }
+
+    #[test]
+    fn draw_triangle_degenerate_is_culled() {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.set_rasterization_state(RasterizationState {
+            polygon_mode: PolygonMode::Line,
+            ..Default::default()
+        });
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+        assert_eq!(covered_pixels(&memory, &rt), vec![]);
+    }
+
+    #[test]
+    fn draw_triangle_straddling_near_plane_is_clipped() {
+        // Depth equals x, so left half of upper left triangle lies behind near plane.
+        let positions = [
+            [-1.0, -1.0, -1.0, 1.0],
+            [1.0, -1.0, 1.0, 1.0],
+            [-1.0, 1.0, -1.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+
+        let expected = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|&(x, y)| x >= WIDTH / 2 && x + y < WIDTH - 1)
+            .collect::<Vec<_>>();
+        assert_eq!(covered_pixels(&memory, &rt), expected);
+    }
+
+    /// Draws triangle with corners at centers of pixels (0, 0), (6, 0) and (0, 6) in given
+    /// polygon mode.
+    fn draw_triangle_polygon_mode(polygon_mode: PolygonMode) -> Vec<(u32, u32)> {
+        let positions = [
+            [-0.875, -0.875, 0.0, 1.0],
+            [0.625, -0.875, 0.0, 1.0],
+            [-0.875, 0.625, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.set_rasterization_state(RasterizationState {
+            polygon_mode,
+            ..Default::default()
+        });
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+        covered_pixels(&memory, &rt)
+    }
+
+    #[test]
+    fn draw_triangle_polygon_mode_line() {
+        // Only edges are drawn, leaving interior pixels untouched.
+        let expected = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|&(x, y)| x + y <= 6 && (x == 0 || y == 0 || x + y == 6))
+            .collect::<Vec<_>>();
+        assert_eq!(draw_triangle_polygon_mode(PolygonMode::Line), expected);
+    }
+
+    #[test]
+    fn draw_triangle_polygon_mode_point() {
+        assert_eq!(
+            draw_triangle_polygon_mode(PolygonMode::Point),
+            [(0, 0), (6, 0), (0, 6)]
+        );
+    }
+
+    fn scissor(x: i32, y: i32, width: u32, height: u32) -> Scissor {
+        Scissor {
+            render_area: RenderArea {
+                extent: Extent2 { width, height },
+                offset: Offset2 { x, y },
+            },
+        }
+    }
+
+    /// Draws triangle covering whole framebuffer with given scissors set.
+    fn draw_full_screen_triangle(scissors: Vec<Scissor>) -> Vec<(u32, u32)> {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.set_dynamic_state(DynamicState {
+            scissor: true,
+            ..Default::default()
+        });
+        pipeline.set_scissors(0, scissors);
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+        covered_pixels(&memory, &rt)
+    }
+
+    #[test]
+    fn draw_triangle_scissor() {
+        let expected = (0..HEIGHT / 2)
+            .flat_map(|y| (0..WIDTH / 2).map(move |x| (x, y)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            draw_full_screen_triangle(vec![scissor(0, 0, WIDTH / 2, HEIGHT / 2)]),
+            expected
+        );
+
+        // Scissor partially outside of framebuffer is clipped to it.
+        let expected = (HEIGHT - 2..HEIGHT)
+            .flat_map(|y| (WIDTH - 3..WIDTH).map(move |x| (x, y)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            draw_full_screen_triangle(vec![scissor(WIDTH as i32 - 3, HEIGHT as i32 - 2, 16, 16)]),
+            expected
+        );
+    }
+
+    #[test]
+    fn draw_triangle_empty_scissor() {
+        assert_eq!(draw_full_screen_triangle(vec![scissor(2, 2, 0, 4)]), vec![]);
+        assert_eq!(
+            draw_full_screen_triangle(vec![scissor(WIDTH as i32, 0, 4, 4)]),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn draw_triangle_dynamic_viewport() {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.set_viewports(
+            0,
+            vec![Viewport {
+                offset: Offset2 { x: 2.0, y: 0.0 },
+                extent: Extent2 {
+                    width: 4.0,
+                    height: 2.0,
+                },
+                depth: Range2 { min: 0.0, max: 1.0 },
+            }],
+        );
+        pipeline.set_dynamic_state(DynamicState {
+            viewport: true,
+            ..Default::default()
+        });
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+
+        // Upper left half of 4x2 dynamic viewport is covered instead of static 8x8 one.
+        assert_eq!(
+            covered_pixels(&memory, &rt),
+            vec![(2, 0), (3, 0), (4, 0), (2, 1)]
+        );
+    }
+
+    #[test]
+    fn draw_triangle_stencil_replace_then_equal() {
+        // Upper left half of framebuffer, then the whole of it.
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        let stencil_rt = RenderTarget {
+            index: RenderTargetIndex(1),
+            format: Format::S8Uint,
+            samples: 1,
+            image: DescriptorImage {
+                binding: bind_memory(&mut memory, &[0; (WIDTH * HEIGHT) as usize]),
+                extent: rt.image.extent,
+                components: ComponentMapping::default(),
+            },
+        };
+        pipeline.bind_render_target(stencil_rt.clone());
+        let render_area = RenderArea {
+            offset: Offset2 { x: 0, y: 0 },
+            extent: Extent2 {
+                width: WIDTH,
+                height: HEIGHT,
+            },
+        };
+        pipeline.clear_render_target_stencil(&mut memory, stencil_rt.index, render_area, 0);
+
+        let stencil_state = |compare_op, pass_op| StencilOpState {
+            fail_op: StencilOp::Keep,
+            pass_op,
+            depth_fail_op: StencilOp::Keep,
+            compare_op,
+            compare_mask: 0xff,
+            write_mask: 0xff,
+            reference: 1,
+        };
+        let replace = stencil_state(CompareOp::Always, StencilOp::Replace);
+        pipeline.set_depth_stencil_state(DepthStencilState {
+            stencil_test_enable: true,
+            front: replace,
+            back: replace,
+            ..Default::default()
+        });
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+        pipeline.clear_render_target(
+            &mut memory,
+            rt.index,
+            render_area,
+            Color::from_sfloat32_raw(0.0, 0.0, 0.0, 0.0),
+        );
+
+        // Second draw covers only pixels whose stencil was replaced by the first one.
+        let equal = stencil_state(CompareOp::Equal, StencilOp::Keep);
+        pipeline.set_depth_stencil_state(DepthStencilState {
+            stencil_test_enable: true,
+            front: equal,
+            back: equal,
+            ..Default::default()
+        });
+        pipeline.draw_primitive(&mut memory, 3, 1, 3, 0);
+
+        let expected = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|(x, y)| x + y < WIDTH - 1)
+            .collect::<Vec<_>>();
+        assert_eq!(covered_pixels(&memory, &rt), expected);
+        let stencil = memory.read_bytes(&stencil_rt.image.binding, 0, (WIDTH * HEIGHT) as u64);
+        assert_eq!(
+            stencil.iter().filter(|&&value| value == 1).count(),
+            expected.len()
+        );
+    }
+
+    #[test]
+    fn draw_triangle_depth_bias_wins_less_depth_test() {
+        // Upper left half of framebuffer, then the whole of it, on the same plane.
+        let positions = [
+            [-1.0, -1.0, 0.25, 1.0],
+            [1.0, -1.0, 0.25, 1.0],
+            [-1.0, 1.0, 0.25, 1.0],
+            [-1.0, -1.0, 0.25, 1.0],
+            [3.0, -1.0, 0.25, 1.0],
+            [-1.0, 3.0, 0.25, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        let depth_rt = RenderTarget {
+            index: RenderTargetIndex(1),
+            format: Format::D16Unorm,
+            samples: 1,
+            image: DescriptorImage {
+                binding: bind_memory(&mut memory, &[0xff; (WIDTH * HEIGHT * 2) as usize]),
+                extent: rt.image.extent,
+                components: ComponentMapping::default(),
+            },
+        };
+        pipeline.bind_render_target(depth_rt);
+        pipeline.set_depth_stencil_state(DepthStencilState {
+            depth_test_enable: true,
+            depth_write_enable: true,
+            depth_compare_op: CompareOp::Less,
+            ..Default::default()
+        });
+        let render_area = RenderArea {
+            offset: Offset2 { x: 0, y: 0 },
+            extent: Extent2 {
+                width: WIDTH,
+                height: HEIGHT,
+            },
+        };
+        let draw_upper_left_half =
+            |pipeline: &mut GraphicsPipeline, memory: &mut Memory, rasterization_state| {
+                pipeline.clear_render_target(
+                    memory,
+                    rt.index,
+                    render_area,
+                    Color::from_sfloat32_raw(0.0, 0.0, 0.0, 0.0),
+                );
+                pipeline.set_rasterization_state(rasterization_state);
+                pipeline.draw_primitive(memory, 3, 1, 0, 0);
+                covered_pixels(memory, &rt)
+            };
+        let upper_left_half = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+            .filter(|(x, y)| x + y < WIDTH - 1)
+            .collect::<Vec<_>>();
+
+        pipeline.draw_primitive(&mut memory, 3, 1, 3, 0);
+        // Coplanar triangle fails depth test unless biased towards viewer.
+        let biased = RasterizationState {
+            depth_bias_enable: true,
+            depth_bias_constant_factor: -1.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            draw_upper_left_half(
+                &mut pipeline,
+                &mut memory,
+                RasterizationState {
+                    depth_bias_enable: false,
+                    ..biased
+                }
+            ),
+            vec![]
+        );
+        assert_eq!(
+            draw_upper_left_half(&mut pipeline, &mut memory, biased.clone()),
+            upper_left_half
+        );
+
+        // Dynamic depth bias replaces factors of pipeline, and has to get past depth written by
+        // previous biased draw.
+        pipeline.set_dynamic_state(DynamicState {
+            depth_bias: true,
+            ..Default::default()
+        });
+        pipeline.set_depth_bias(-2.0, 0.0, 0.0);
+        assert_eq!(
+            draw_upper_left_half(&mut pipeline, &mut memory, biased.clone()),
+            upper_left_half
+        );
+        pipeline.set_depth_bias(0.0, 0.0, 0.0);
+        assert_eq!(
+            draw_upper_left_half(&mut pipeline, &mut memory, biased),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn draw_triangle_depth_bounds_test() {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        // Stored depth ramps up from left to right, reaching 0.5 between its halves.
+        let ramp = (0..HEIGHT)
+            .flat_map(|_| (0..WIDTH).map(|x| (x as f32 + 0.5) / WIDTH as f32))
+            .flat_map(f32::to_ne_bytes)
+            .collect::<Vec<_>>();
+        let depth_rt = RenderTarget {
+            index: RenderTargetIndex(1),
+            format: Format::D32Sfloat,
+            samples: 1,
+            image: DescriptorImage {
+                binding: bind_memory(&mut memory, &ramp),
+                extent: rt.image.extent,
+                components: ComponentMapping::default(),
+            },
+        };
+        pipeline.bind_render_target(depth_rt.clone());
+        pipeline.set_depth_stencil_state(DepthStencilState {
+            depth_bounds_test_enable: true,
+            depth_bounds: Range2 { min: 0.0, max: 0.5 },
+            ..Default::default()
+        });
+        let render_area = RenderArea {
+            offset: Offset2 { x: 0, y: 0 },
+            extent: Extent2 {
+                width: WIDTH,
+                height: HEIGHT,
+            },
+        };
+        let mut draw = |pipeline: &mut GraphicsPipeline| {
+            pipeline.clear_render_target(
+                &mut memory,
+                rt.index,
+                render_area,
+                Color::from_sfloat32_raw(0.0, 0.0, 0.0, 0.0),
+            );
+            pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+            covered_pixels(&memory, &rt)
+        };
+        let half = |near: bool| {
+            (0..HEIGHT)
+                .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+                .filter(|&(x, _)| (x < WIDTH / 2) == near)
+                .collect::<Vec<_>>()
+        };
+
+        // Only fragments over near half of stored depth survive.
+        assert_eq!(draw(&mut pipeline), half(true));
+
+        // Dynamic depth bounds replace those of pipeline.
+        pipeline.set_dynamic_state(DynamicState {
+            depth_bounds: true,
+            ..Default::default()
+        });
+        pipeline.set_depth_bounds(Range2 { min: 0.5, max: 1.0 });
+        assert_eq!(draw(&mut pipeline), half(false));
+
+        // Depth bounds test leaves stored depth unchanged.
+        let depth = memory.read_bytes(&depth_rt.image.binding, 0, ramp.len() as u64);
+        assert_eq!(depth, ramp);
+    }
+
+    #[test]
+    fn draw_triangle_srgb_attachment_encodes_blended_color() {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, mut rt) = triangle_pipeline(&mut memory, &positions);
+        rt.format = Format::R8G8B8A8Srgb;
+        pipeline.bind_render_target(rt.clone());
+        // Scales red fragment color to mid-gray linear intensity.
+        let mut color_blend_state = ColorBlendState {
+            blend_constants: Color::from_sfloat32_raw(0.5, 0.5, 0.5, 0.5),
+            ..Default::default()
+        };
+        color_blend_state.attachments[0] = Some(ColorBlendAttachmentState {
+            blend_enable: true,
+            src_color_blend_factor: BlendFactor::ConstantColor,
+            src_alpha_blend_factor: BlendFactor::ConstantAlpha,
+            ..Default::default()
+        });
+        pipeline.set_color_blend_state(color_blend_state);
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+
+        // Color components are sRGB encoded, while alpha is stored linearly.
+        let encoded = (linear_to_srgb(0.5) * 255.0).round() as u8;
+        assert_eq!(encoded, 188);
+        let pixels = memory.read_bytes(&rt.image.binding, 0, (WIDTH * HEIGHT * 4) as u64);
+        for pixel in pixels.chunks_exact(4) {
+            assert_eq!(pixel, [encoded, 0, 0, 128]);
+        }
+    }
+
+    #[test]
+    fn draw_triangle_blends_each_attachment_with_its_state() {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt0) = triangle_pipeline(&mut memory, &positions);
+        let rt1 = RenderTarget {
+            index: RenderTargetIndex(1),
+            image: DescriptorImage {
+                binding: bind_memory(&mut memory, &[0; (WIDTH * HEIGHT * 4) as usize]),
+                ..rt0.image
+            },
+            ..rt0
+        };
+        pipeline.bind_render_target(rt1.clone());
+        pipeline.set_shader_state(ShaderState {
+            vertex_shader: Shader::new("main", VERTEX_SHADER.to_vec()).ok(),
+            fragment_shader: Shader::new("main", MULTIPLE_OUTPUTS_FRAGMENT_SHADER.to_vec()).ok(),
+        });
+        let render_area = RenderArea {
+            offset: Offset2 { x: 0, y: 0 },
+            extent: Extent2 {
+                width: WIDTH,
+                height: HEIGHT,
+            },
+        };
+        let blue = Color::from_sfloat32_raw(0.0, 0.0, 1.0, 1.0);
+        pipeline.clear_render_target(&mut memory, rt0.index, render_area, blue);
+        pipeline.clear_render_target(&mut memory, rt1.index, render_area, blue);
+
+        // Red output is added to first attachment, and green one replaces all but red component
+        // of the second one.
+        let mut color_blend_state = ColorBlendState::default();
+        color_blend_state.attachments[0] = Some(ColorBlendAttachmentState {
+            blend_enable: true,
+            src_color_blend_factor: BlendFactor::One,
+            dst_color_blend_factor: BlendFactor::One,
+            src_alpha_blend_factor: BlendFactor::One,
+            ..Default::default()
+        });
+        color_blend_state.attachments[1] = Some(ColorBlendAttachmentState {
+            color_write_mask: ColorComponentFlags {
+                r: false,
+                g: true,
+                b: true,
+                a: true,
+            },
+            ..Default::default()
+        });
+        pipeline.set_color_blend_state(color_blend_state);
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+
+        for (rt, expected) in [(rt0, [255, 0, 255, 255]), (rt1, [0, 255, 0, 255])] {
+            let pixels = memory.read_bytes(&rt.image.binding, 0, (WIDTH * HEIGHT * 4) as u64);
+            for pixel in pixels.chunks_exact(4) {
+                assert_eq!(pixel, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn draw_triangle_writes_each_output_to_its_attachment() {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt0) = triangle_pipeline(&mut memory, &positions);
+        let rt1 = RenderTarget {
+            index: RenderTargetIndex(1),
+            image: DescriptorImage {
+                binding: bind_memory(&mut memory, &[0; (WIDTH * HEIGHT * 4) as usize]),
+                ..rt0.image
+            },
+            ..rt0
+        };
+        pipeline.bind_render_target(rt1.clone());
+        pipeline.set_shader_state(ShaderState {
+            vertex_shader: Shader::new("main", VERTEX_SHADER.to_vec()).ok(),
+            fragment_shader: Shader::new("main", MULTIPLE_OUTPUTS_FRAGMENT_SHADER.to_vec()).ok(),
+        });
+        // Locations need not match indices of render targets they write.
+        let mut color_attachments = [None; MAX_COLOR_ATTACHMENTS as usize];
+        color_attachments[0] = Some(rt1.index);
+        color_attachments[1] = Some(rt0.index);
+        pipeline.set_color_attachments(color_attachments);
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+
+        for (rt, expected) in [(rt0, [0, 255, 0, 255]), (rt1, [255, 0, 0, 255])] {
+            let pixels = memory.read_bytes(&rt.image.binding, 0, (WIDTH * HEIGHT * 4) as u64);
+            for pixel in pixels.chunks_exact(4) {
+                assert_eq!(pixel, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn draw_triangle_logic_op_xor() {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        let dst = [0x0f, 0xf0, 0x33, 0xff];
+        memory.write_bytes(&dst.repeat((WIDTH * HEIGHT) as usize), &rt.image.binding, 0);
+        // Enabled blending is ignored in favor of logical operation.
+        let mut color_blend_state = ColorBlendState {
+            logic_op_enable: true,
+            logic_op: LogicOp::Xor,
+            ..Default::default()
+        };
+        color_blend_state.attachments[0] = Some(ColorBlendAttachmentState {
+            blend_enable: true,
+            src_color_blend_factor: BlendFactor::One,
+            dst_color_blend_factor: BlendFactor::One,
+            ..Default::default()
+        });
+        pipeline.set_color_blend_state(color_blend_state);
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+
+        // Red fragment color flips all bits of red and alpha components.
+        let pixels = memory.read_bytes(&rt.image.binding, 0, (WIDTH * HEIGHT * 4) as u64);
+        for pixel in pixels.chunks_exact(4) {
+            assert_eq!(pixel, [0xf0, 0xf0, 0x33, 0x00]);
+        }
+    }
+
+    #[test]
+    fn draw_triangle_specialized_fragment_color() {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut specialization = SpecializationInfo::default();
+        specialization.constants.insert(0, 0.5f32.to_bits());
+        // Default value of red component is kept without specialization.
+        for (specialization, expected) in [
+            (SpecializationInfo::default(), [255, 0, 0, 255]),
+            (specialization, [128, 0, 0, 255]),
+        ] {
+            let mut memory = Memory::new();
+            let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+            let fragment_shader = Shader::with_specialization(
+                "main",
+                SPEC_CONSTANT_FRAGMENT_SHADER.to_vec(),
+                &specialization,
+            );
+            pipeline.set_shader_state(ShaderState {
+                vertex_shader: Shader::new("main", VERTEX_SHADER.to_vec()).ok(),
+                fragment_shader: fragment_shader.ok(),
+            });
+            pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+
+            let pixels = memory.read_bytes(&rt.image.binding, 0, (WIDTH * HEIGHT * 4) as u64);
+            for pixel in pixels.chunks_exact(4) {
+                assert_eq!(pixel, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn draw_triangle_frag_coord_gradient() {
+        let positions = [
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.set_shader_state(ShaderState {
+            vertex_shader: Shader::new("main", VERTEX_SHADER.to_vec()).ok(),
+            fragment_shader: Shader::new("main", FRAG_COORD_FRAGMENT_SHADER.to_vec()).ok(),
+        });
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+
+        // Red component grows with x coordinate of pixel center.
+        let pixels = memory.read_bytes(&rt.image.binding, 0, (WIDTH * HEIGHT * 4) as u64);
+        for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+            let x = (i as u32 % WIDTH) as f32 + 0.5;
+            let red = (x / WIDTH as f32 * 255.0).round() as u8;
+            assert_eq!(pixel, [red, 0, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn draw_triangle_frag_depth_replaces_depth() {
+        // Triangle lies behind stored depth.
+        let positions = [
+            [-1.0, -1.0, 0.75, 1.0],
+            [3.0, -1.0, 0.75, 1.0],
+            [-1.0, 3.0, 0.75, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        let depth = 0.5f32.to_ne_bytes().repeat((WIDTH * HEIGHT) as usize);
+        let depth_rt = RenderTarget {
+            index: RenderTargetIndex(1),
+            format: Format::D32Sfloat,
+            samples: 1,
+            image: DescriptorImage {
+                binding: bind_memory(&mut memory, &depth),
+                extent: rt.image.extent,
+                components: ComponentMapping::default(),
+            },
+        };
+        pipeline.bind_render_target(depth_rt.clone());
+        pipeline.set_depth_stencil_state(DepthStencilState {
+            depth_test_enable: true,
+            depth_write_enable: true,
+            depth_compare_op: CompareOp::Less,
+            ..Default::default()
+        });
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+        assert_eq!(covered_pixels(&memory, &rt), vec![]);
+
+        // Depth written by shader ramps up from left to right, passing over left half.
+        pipeline.set_shader_state(ShaderState {
+            vertex_shader: Shader::new("main", VERTEX_SHADER.to_vec()).ok(),
+            fragment_shader: Shader::new("main", FRAG_DEPTH_FRAGMENT_SHADER.to_vec()).ok(),
+        });
+        pipeline.draw_primitive(&mut memory, 3, 1, 0, 0);
+        let left_half = (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH / 2).map(move |x| (x, y)))
+            .collect::<Vec<_>>();
+        assert_eq!(covered_pixels(&memory, &rt), left_half);
+        let stored = memory.read_bytes(&depth_rt.image.binding, 0, depth.len() as u64);
+        assert_eq!(
+            read_texel(Format::D32Sfloat, &stored[..4])[0],
+            0.5 / WIDTH as f32
+        );
+    }
+
+    #[test]
+    fn tiled_rasterizer_matches_simple_one() {
+        let extent = Extent2 {
+            width: 100,
+            height: 70,
+        };
+        for samples in [1, 4] {
+            let simple = render_scene(RasterizerBackend::Simple, samples, extent, 64);
+            let tiled = render_scene(RasterizerBackend::Tiled, samples, extent, 64);
+            assert!(simple.0.iter().any(|&x| x != 0));
+            assert!(simple.0 == tiled.0, "color differs with {samples} samples");
+            assert!(simple.1 == tiled.1, "depth differs with {samples} samples");
+        }
+    }
+
+    /// Compares time taken by both rasterizers, run with
+    /// `cargo test --release -p gpu -- --ignored --nocapture tiled_rasterizer_speedup`.
+    #[test]
+    #[ignore]
+    fn tiled_rasterizer_speedup() {
+        let extent = Extent2 {
+            width: 256,
+            height: 256,
+        };
+        let time = |backend| {
+            let start = std::time::Instant::now();
+            let output = render_scene(backend, 1, extent, 256);
+            (start.elapsed(), output)
+        };
+        let (simple_time, simple) = time(RasterizerBackend::Simple);
+        let (tiled_time, tiled) = time(RasterizerBackend::Tiled);
+        assert!(simple == tiled);
+        println!(
+            "simple: {simple_time:?}, tiled: {tiled_time:?}, speedup: {:.2}",
+            simple_time.as_secs_f64() / tiled_time.as_secs_f64()
+        );
+    }
+
+    #[test]
+    fn draw_triangle_flat_and_smooth_attributes() {
+        // Triangle is made of vertices 3 to 5, the first of them being provoking vertex.
+        let positions = [
+            [0.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+            [-1.0, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ];
+        let mut memory = Memory::new();
+        let (mut pipeline, rt) = triangle_pipeline(&mut memory, &positions);
+        pipeline.set_shader_state(ShaderState {
+            vertex_shader: Shader::new("main", FLAT_VERTEX_SHADER.to_vec()).ok(),
+            fragment_shader: Shader::new("main", FLAT_FRAGMENT_SHADER.to_vec()).ok(),
+        });
+        let mut vertex_input_state = VertexInputState::default();
+        vertex_input_state.bindings[0] = Some(VertexBinding {
+            number: VertexBindingNumber(0),
+            stride: 16,
+            input_rate: VertexInputRate::Vertex,
+        });
+        vertex_input_state.bindings[1] = Some(VertexBinding {
+            number: VertexBindingNumber(1),
+            stride: 4,
+            input_rate: VertexInputRate::Vertex,
+        });
+        vertex_input_state.attributes[0] = Some(VertexAttribute {
+            location: 0,
+            binding: VertexBindingNumber(0),
+            format: Format::R32G32B32A32Sfloat,
+            offset: 0,
+        });
+        vertex_input_state.attributes[1] = Some(VertexAttribute {
+            location: 1,
+            binding: VertexBindingNumber(1),
+            format: Format::R32Sfloat,
+            offset: 0,
+        });
+        pipeline.set_vertex_input_state(vertex_input_state);
+        let values = [0.0f32, 0.0, 0.0, 0.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        pipeline.bind_vertex_buffer(VertexBuffer {
+            binding_number: VertexBindingNumber(1),
+            buffer: DescriptorBuffer {
+                binding: bind_memory(&mut memory, &values),
+            },
+            offset: 0,
+        });
+        pipeline.draw_primitive(&mut memory, 3, 1, 3, 0);
+
+        // Value rises towards second vertex lying at twice the framebuffer width, while index of
+        // provoking vertex is shared by all fragments.
+        let pixels = memory.read_bytes(&rt.image.binding, 0, (WIDTH * HEIGHT * 4) as u64);
+        for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+            let x = (i as u32 % WIDTH) as f32 + 0.5;
+            let red = (x / (2 * WIDTH) as f32 * 255.0).round() as u8;
+            assert_eq!(pixel, [red, 3, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn fetch_vertex_input_interleaved_attributes() {
+        let mut memory = Memory::new();
+        let (mut pipeline, _) = triangle_pipeline(&mut memory, &[]);
+
+        // Interleaved vec2 position and RGBA8 color, with per-instance integer attribute.
+        let vertex_bytes = [[0.0f32, 0.5], [1.0, 0.25], [-1.0, 0.75]]
+            .iter()
+            .zip([[255u8, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 51]])
+            .flat_map(|(position, color)| {
+                position
+                    .iter()
+                    .flat_map(|x| x.to_ne_bytes())
+                    .chain(color)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let instance_bytes = [[1u8, 2, 3, 4], [5, 6, 7, 8]].concat();
+        let mut vertex_input_state = VertexInputState::default();
+        vertex_input_state.bindings[0] = Some(VertexBinding {
+            number: VertexBindingNumber(0),
+            stride: 12,
+            input_rate: VertexInputRate::Vertex,
+        });
+        vertex_input_state.bindings[1] = Some(VertexBinding {
+            number: VertexBindingNumber(1),
+            stride: 4,
+            input_rate: VertexInputRate::Instance,
+        });
+        // Color is passed through to position output by vertex shader.
+        vertex_input_state.attributes[0] = Some(VertexAttribute {
+            location: 0,
+            binding: VertexBindingNumber(0),
+            format: Format::R8G8B8A8Unorm,
+            offset: 8,
+        });
+        vertex_input_state.attributes[1] = Some(VertexAttribute {
+            location: 1,
+            binding: VertexBindingNumber(0),
+            format: Format::R32G32Sfloat,
+            offset: 0,
+        });
+        vertex_input_state.attributes[2] = Some(VertexAttribute {
+            location: 2,
+            binding: VertexBindingNumber(1),
+            format: Format::R8G8Uint,
+            offset: 1,
+        });
+        pipeline.set_vertex_input_state(vertex_input_state);
+        for (number, bytes) in [vertex_bytes, instance_bytes].iter().enumerate() {
+            pipeline.bind_vertex_buffer(VertexBuffer {
+                binding_number: VertexBindingNumber(number as u32),
+                buffer: DescriptorBuffer {
+                    binding: bind_memory(&mut memory, bytes),
+                },
+                offset: 0,
+            });
+        }
+
+        let vertices = pipeline.fetch_vertex_input(&memory, &[0, 1, 2], 1);
+        let attributes = vertices[2].attributes.map(|x| x.get_as_f32_array());
+        assert_eq!(attributes[0], [0.0, 0.0, 1.0, 0.2]);
+        assert_eq!(attributes[1], [-1.0, 0.75, 0.0, 1.0]);
+        assert_eq!(attributes[2], [6.0, 7.0, 0.0, 1.0]);
+
+        let resources = ShaderResources::default();
+        let outputs =
+            pipeline.execute_vertex_shader(&pipeline.vertex_input_state, &resources, vertices);
+        assert_eq!(outputs[2].position.get_as_f32_array(), [0.0, 0.0, 1.0, 0.2]);
+    }
+}
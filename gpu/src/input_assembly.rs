@@ -0,0 +1,163 @@
+use crate::PrimitiveTopology;
+
+/// Primitive holding positions of its vertices in the vertex stream of a draw.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Primitive {
+    Point(usize),
+    Line([usize; 2]),
+    Triangle([usize; 3]),
+}
+
+/// Assembles primitives of `topology` from stream of `vertex_count` vertices, ignoring vertices
+/// left over by incomplete primitives. Triangles of strips keep winding order of the first one.
+///
+/// Adjacent vertices are only accessible to geometry shaders, which aren't supported, so they are
+/// dropped. Patches are only consumed by tessellation, which isn't supported either, so pipelines
+/// with them are rejected and none are assembled.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#drawing-primitive-topologies
+pub fn assemble_primitives(topology: PrimitiveTopology, vertex_count: usize) -> Vec<Primitive> {
+    match topology {
+        PrimitiveTopology::PointList => (0..vertex_count).map(Primitive::Point).collect(),
+        PrimitiveTopology::LineList => (0..vertex_count / 2)
+            .map(|i| Primitive::Line([2 * i, 2 * i + 1]))
+            .collect(),
+        PrimitiveTopology::LineStrip => (0..vertex_count.saturating_sub(1))
+            .map(|i| Primitive::Line([i, i + 1]))
+            .collect(),
+        PrimitiveTopology::TriangleList => (0..vertex_count / 3)
+            .map(|i| Primitive::Triangle([3 * i, 3 * i + 1, 3 * i + 2]))
+            .collect(),
+        PrimitiveTopology::TriangleStrip => (0..vertex_count.saturating_sub(2))
+            .map(|i| Primitive::Triangle([i, i + 1 + i % 2, i + 2 - i % 2]))
+            .collect(),
+        PrimitiveTopology::TriangleFan => (0..vertex_count.saturating_sub(2))
+            .map(|i| Primitive::Triangle([i + 1, i + 2, 0]))
+            .collect(),
+        PrimitiveTopology::LineListWithAdjacency => (0..vertex_count / 4)
+            .map(|i| Primitive::Line([4 * i + 1, 4 * i + 2]))
+            .collect(),
+        PrimitiveTopology::LineStripWithAdjacency => (0..vertex_count.saturating_sub(3))
+            .map(|i| Primitive::Line([i + 1, i + 2]))
+            .collect(),
+        PrimitiveTopology::TriangleListWithAdjacency => (0..vertex_count / 6)
+            .map(|i| Primitive::Triangle([6 * i, 6 * i + 2, 6 * i + 4]))
+            .collect(),
+        PrimitiveTopology::TriangleStripWithAdjacency => (0..vertex_count.saturating_sub(4) / 2)
+            .map(|i| Primitive::Triangle([2 * i, 2 * i + 2 + 2 * (i % 2), 2 * i + 4 - 2 * (i % 2)]))
+            .collect(),
+        PrimitiveTopology::PatchList => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assemble_point_list() {
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::PointList, 3),
+            [
+                Primitive::Point(0),
+                Primitive::Point(1),
+                Primitive::Point(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn assemble_line_list() {
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::LineList, 5),
+            [Primitive::Line([0, 1]), Primitive::Line([2, 3])]
+        );
+    }
+
+    #[test]
+    fn assemble_line_strip() {
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::LineStrip, 4),
+            [
+                Primitive::Line([0, 1]),
+                Primitive::Line([1, 2]),
+                Primitive::Line([2, 3])
+            ]
+        );
+        assert_eq!(assemble_primitives(PrimitiveTopology::LineStrip, 1), []);
+    }
+
+    #[test]
+    fn assemble_triangle_list() {
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::TriangleList, 7),
+            [
+                Primitive::Triangle([0, 1, 2]),
+                Primitive::Triangle([3, 4, 5])
+            ]
+        );
+    }
+
+    #[test]
+    fn assemble_triangle_strip_alternates_winding() {
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::TriangleStrip, 5),
+            [
+                Primitive::Triangle([0, 1, 2]),
+                Primitive::Triangle([1, 3, 2]),
+                Primitive::Triangle([2, 3, 4])
+            ]
+        );
+        assert_eq!(assemble_primitives(PrimitiveTopology::TriangleStrip, 2), []);
+    }
+
+    #[test]
+    fn assemble_line_lists_with_adjacency_drop_adjacent_vertices() {
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::LineListWithAdjacency, 9),
+            [Primitive::Line([1, 2]), Primitive::Line([5, 6])]
+        );
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::LineStripWithAdjacency, 5),
+            [Primitive::Line([1, 2]), Primitive::Line([2, 3])]
+        );
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::LineStripWithAdjacency, 3),
+            []
+        );
+    }
+
+    #[test]
+    fn assemble_triangles_with_adjacency_drop_adjacent_vertices() {
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::TriangleListWithAdjacency, 13),
+            [
+                Primitive::Triangle([0, 2, 4]),
+                Primitive::Triangle([6, 8, 10])
+            ]
+        );
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::TriangleStripWithAdjacency, 10),
+            [
+                Primitive::Triangle([0, 2, 4]),
+                Primitive::Triangle([2, 6, 4]),
+                Primitive::Triangle([4, 6, 8])
+            ]
+        );
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::TriangleStripWithAdjacency, 5),
+            []
+        );
+    }
+
+    #[test]
+    fn assemble_triangle_fan_shares_first_vertex() {
+        assert_eq!(
+            assemble_primitives(PrimitiveTopology::TriangleFan, 5),
+            [
+                Primitive::Triangle([1, 2, 0]),
+                Primitive::Triangle([2, 3, 0]),
+                Primitive::Triangle([3, 4, 0])
+            ]
+        );
+    }
+}
@@ -2,12 +2,33 @@ extern crate common;
 extern crate core;
 extern crate shader;
 
+pub mod bindings;
+pub mod blend;
+pub mod clipping;
+pub mod compute_pipeline;
+pub mod depth_stencil;
+pub mod event;
 pub mod gpu;
 pub mod graphics_pipeline;
+pub mod input_assembly;
 pub mod memory;
+pub mod query;
 pub mod rasterization;
+pub mod sampler;
+pub mod tiling;
+pub mod viewport;
 
+pub use bindings::*;
+pub use blend::*;
+pub use clipping::*;
+pub use compute_pipeline::*;
+pub use depth_stencil::*;
+pub use event::*;
 pub use gpu::*;
 pub use graphics_pipeline::*;
+pub use input_assembly::*;
 pub use memory::*;
+pub use query::*;
 pub use rasterization::*;
+pub use sampler::*;
+pub use tiling::*;
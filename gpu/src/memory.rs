@@ -8,7 +8,7 @@ use std::sync::atomic::{AtomicU64, Ordering};
 #[derive(Default)]
 pub struct Memory {
     // TODO: Usa bitmap allocator.
-    allocations: HashMap<MemoryAllocationHandle, Vec<u8>>,
+    allocations: HashMap<MemoryAllocationHandle, Storage>,
     allocation_index: AtomicU64,
 }
 
@@ -26,7 +26,20 @@ impl Memory {
 
     pub fn allocate_memory(&mut self, size: u64) -> MemoryAllocation {
         let handle = MemoryAllocationHandle(self.allocation_index.fetch_add(1, Ordering::Relaxed));
-        self.allocations.insert(handle, vec![0; size as usize]);
+        self.allocations
+            .insert(handle, Storage::Owned(vec![0; size as usize]));
+        MemoryAllocation { handle, size }
+    }
+
+    /// Allocates memory backed by `size` bytes at `ptr` owned by caller, instead of fresh bytes.
+    ///
+    /// # Safety
+    ///
+    /// Bytes must stay valid for reads and writes until allocation is freed.
+    pub unsafe fn import_memory(&mut self, ptr: NonNull<u8>, size: u64) -> MemoryAllocation {
+        let handle = MemoryAllocationHandle(self.allocation_index.fetch_add(1, Ordering::Relaxed));
+        self.allocations
+            .insert(handle, Storage::Imported(ptr, size as usize));
         MemoryAllocation { handle, size }
     }
 
@@ -51,7 +64,7 @@ impl Memory {
     pub fn get_memory_many_mut<const N: usize>(
         &mut self,
         memories: &[&dyn MemoryHandle; N],
-    ) -> [&mut Vec<u8>; N] {
+    ) -> [&mut [u8]; N] {
         let ks = memories
             .iter()
             .map(|x| x.memory_handle())
@@ -62,6 +75,7 @@ impl Memory {
                 ks.as_slice().try_into().unwrap_or_else(|_| unreachable!()),
             )
             .unwrap_or_else(|| unreachable!())
+            .map(Storage::as_mut_slice)
     }
 
     pub fn copy_bytes(
@@ -72,6 +86,12 @@ impl Memory {
         dst_offset: u64,
         size: u64,
     ) {
+        if src.memory_handle() == dst.memory_handle() {
+            let memory = self.get_memory_mut(src);
+            let src_offset = src_offset as usize;
+            memory.copy_within(src_offset..src_offset + size as usize, dst_offset as usize);
+            return;
+        }
         let [src, dst] = self.get_memory_many_mut(&[src, dst]);
         let src = &src[src_offset as usize..(src_offset + size) as usize];
         let dst = &mut dst[dst_offset as usize..(dst_offset + size) as usize];
@@ -120,7 +140,8 @@ impl Memory {
         let memory = self
             .allocations
             .get_mut(&memory_allocation.handle)
-            .unwrap_or_else(|| unreachable!());
+            .unwrap_or_else(|| unreachable!())
+            .as_mut_slice();
         let ptr = memory[offset as usize..(offset + size) as usize].as_mut_ptr();
         NonNull::new(ptr as *mut std::ffi::c_void)
     }
@@ -130,7 +151,38 @@ impl Memory {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Bytes backing allocation.
+enum Storage {
+    Owned(Vec<u8>),
+    /// Bytes owned outside of `Memory`, e.g. mapped from file descriptor.
+    Imported(NonNull<u8>, usize),
+}
+
+// SAFETY: Imported bytes are only accessed through `Memory`, like owned ones.
+unsafe impl Send for Storage {}
+unsafe impl Sync for Storage {}
+
+impl Storage {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Owned(bytes) => bytes,
+            // SAFETY: Importer keeps bytes valid until allocation is freed.
+            Self::Imported(ptr, size) => unsafe { std::slice::from_raw_parts(ptr.as_ptr(), *size) },
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        match self {
+            Self::Owned(bytes) => bytes,
+            // SAFETY: Importer keeps bytes valid until allocation is freed.
+            Self::Imported(ptr, size) => unsafe {
+                std::slice::from_raw_parts_mut(ptr.as_ptr(), *size)
+            },
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct MemoryAllocation {
     handle: MemoryAllocationHandle,
     pub size: u64,
@@ -0,0 +1,39 @@
+use crate::{Memory, MemoryAllocation};
+
+/// Size in bytes of query slot, holding 64-bit result followed by 64-bit availability.
+pub const QUERY_SIZE: u64 = 16;
+
+/// Slot of query pool memory holding result of single query.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub memory: MemoryAllocation,
+    pub index: u32,
+}
+
+impl Query {
+    const fn offset(&self) -> u64 {
+        self.index as u64 * QUERY_SIZE
+    }
+
+    /// Makes query unavailable, zeroing its result.
+    pub fn reset(&self, memory: &mut Memory) {
+        memory.write_bytes(&[0; QUERY_SIZE as usize], &self.memory, self.offset());
+    }
+
+    /// Stores `result` of query, making it available.
+    pub fn write_result(&self, memory: &mut Memory, result: u64) {
+        let mut bytes = [0; QUERY_SIZE as usize];
+        bytes[..8].copy_from_slice(&result.to_le_bytes());
+        bytes[8..].copy_from_slice(&1u64.to_le_bytes());
+        memory.write_bytes(&bytes, &self.memory, self.offset());
+    }
+
+    /// Returns result of query, `None` if it is not available.
+    pub fn read_result(&self, memory: &Memory) -> Option<u64> {
+        let bytes = memory.read_bytes(&self.memory, self.offset(), QUERY_SIZE);
+        let (result, available) = bytes.split_at(8);
+        let read_u64 =
+            |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap_or_else(|_| unreachable!()));
+        (read_u64(available) != 0).then(|| read_u64(result))
+    }
+}
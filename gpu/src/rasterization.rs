@@ -1,65 +1,739 @@
-use common::math::{Color, Fragment, Position, Vertex};
-use log::warn;
-
-pub fn draw_line_bresenham(v0: Vertex, v1: Vertex, fragments: &mut Vec<Fragment>, color: Color) {
-    // Bresenham's line algorithm
-    warn!("TODO: Replace line segment rasterization.");
-    // https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-lines-basic
-    let v0 = v0.position;
-    let v1 = v1.position;
-
-    let (mut x0, mut y0) = (
-        (v0.get_as_sfloat32(0)) as i32,
-        (v0.get_as_sfloat32(1)) as i32,
-    );
-    let (mut x1, mut y1) = (
-        (v1.get_as_sfloat32(0)) as i32,
-        (v1.get_as_sfloat32(1)) as i32,
+use crate::RenderArea;
+use common::consts::MAX_VERTEX_ATTRIBUTES;
+use common::graphics::{CullMode, FrontFace, Interpolation};
+use common::math::{Color, Fragment, Position, Vector4, Vertex};
+
+#[cfg(feature = "simd")]
+mod simd;
+
+/// Interpolation of each vertex attribute, indexed by location.
+pub type Interpolations = [Interpolation; MAX_VERTEX_ATTRIBUTES as usize];
+
+/// Rasterizes line segment `width` pixels wide into fragments at pixel centers within
+/// `render_area`.
+///
+/// Pixels are stepped along the major axis, excluding the one at `v1` so that
+/// connected segments do not cover it twice. Depth and `w`, holding reciprocal of clip `w`, are
+/// interpolated linearly in framebuffer space, while attributes follow `interpolations`.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-lines-bresenham
+pub fn rasterize_line(
+    v0: Vertex,
+    v1: Vertex,
+    width: f32,
+    render_area: RenderArea,
+    fragments: &mut Vec<Fragment>,
+    color: Color,
+    interpolations: &Interpolations,
+) {
+    let [p0, p1] = [v0, v1].map(framebuffer_position);
+    let [inv_w0, inv_w1] = [v0, v1].map(|vertex| vertex.position.get_as_sfloat32(3));
+    let (dx, dy) = (p1[0] - p0[0], p1[1] - p0[1]);
+    if (dx == 0.0f32 && dy == 0.0f32) || !dx.is_finite() || !dy.is_finite() {
+        // Zero length line does not cover any pixel.
+        return;
+    }
+    let (major, minor) = if dx.abs() >= dy.abs() { (0, 1) } else { (1, 0) };
+    let offset = [render_area.offset.x as f32, render_area.offset.y as f32];
+    let extent = [
+        render_area.extent.width as f32,
+        render_area.extent.height as f32,
+    ];
+    let bounds = |axis: usize, min: f32, max: f32| {
+        let (start, end) = (offset[axis].max(0.0f32), offset[axis] + extent[axis]);
+        (min.clamp(start, end) as u32, max.clamp(start, end) as u32)
+    };
+
+    // Wide lines cover `width` pixels centered on the line along the minor axis.
+    let width = width.round().max(1.0f32);
+    let length = p1[major] - p0[major];
+    let (major_min, major_max) = bounds(
+        major,
+        (p0[major].min(p1[major]) - 0.5f32).floor(),
+        (p0[major].max(p1[major]) + 0.5f32).ceil(),
     );
-    let steep = if (y1 - y0).abs() > (x1 - x0).abs() {
-        std::mem::swap(&mut x0, &mut y0);
-        std::mem::swap(&mut x1, &mut y1);
-        true
-    } else {
-        false
+    for i in major_min..major_max {
+        let center = i as f32 + 0.5f32;
+        let t = (center - p0[major]) / length;
+        if !(0.0f32..1.0f32).contains(&t) {
+            continue;
+        }
+        let line_minor = (p1[minor] - p0[minor]).mul_add(t, p0[minor]);
+        let (minor_min, minor_max) = bounds(
+            minor,
+            (line_minor - width / 2.0f32 - 0.5f32).ceil(),
+            (line_minor + width / 2.0f32 - 0.5f32).ceil(),
+        );
+        let z = (p1[2] - p0[2]).mul_add(t, p0[2]);
+        let inv_w = (inv_w1 - inv_w0).mul_add(t, inv_w0);
+        let attributes = interpolate_attributes([v0, v1], [1.0f32 - t, t], interpolations);
+        for j in minor_min..minor_max {
+            let mut p = [0.0f32; 2];
+            p[major] = center;
+            p[minor] = j as f32 + 0.5f32;
+            fragments.push(Fragment {
+                position: Position::from_sfloat32_raw(p[0], p[1], z, inv_w),
+                color,
+                front_facing: true,
+                coverage: !0,
+                attributes,
+            });
+        }
+    }
+}
+
+/// Rasterizes point into fragments at centers of pixels within `render_area` covered by square
+/// `size` pixels wide centered on the point.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-points-basic
+pub fn rasterize_point(
+    vertex: Vertex,
+    size: f32,
+    render_area: RenderArea,
+    fragments: &mut Vec<Fragment>,
+    color: Color,
+) {
+    let [x, y, z] = framebuffer_position(vertex);
+    let inv_w = vertex.position.get_as_sfloat32(3);
+    // Point of size 1 or less still covers the pixel containing it.
+    let half_size = size.max(1.0f32) / 2.0f32;
+    let bounds = |center: f32, offset: i32, size: u32| {
+        let (start, end) = ((offset as f32).max(0.0f32), offset as f32 + size as f32);
+        let min = (center - half_size - 0.5f32).ceil().clamp(start, end);
+        let max = (center + half_size - 0.5f32).ceil().clamp(start, end);
+        (min as u32, max as u32)
     };
-    if x0 > x1 {
-        std::mem::swap(&mut x0, &mut x1);
-        std::mem::swap(&mut y0, &mut y1);
+    let (x_min, x_max) = bounds(x, render_area.offset.x, render_area.extent.width);
+    let (y_min, y_max) = bounds(y, render_area.offset.y, render_area.extent.height);
+    for y in y_min..y_max {
+        for x in x_min..x_max {
+            fragments.push(Fragment {
+                position: Position::from_sfloat32_raw(
+                    x as f32 + 0.5f32,
+                    y as f32 + 0.5f32,
+                    z,
+                    inv_w,
+                ),
+                color,
+                front_facing: true,
+                coverage: !0,
+                attributes: vertex.attributes,
+            });
+        }
     }
+}
+
+/// Rasterizes filled triangle into fragments at pixels within `render_area` having any of their
+/// `samples` covered.
+///
+/// Fragments are placed at pixel centers, carrying coverage of samples at standard locations and
+/// attributes interpolated as given by `interpolations`, flat ones taken from the first vertex.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-polygons-basic
+pub fn draw_triangle(
+    vertices: [Vertex; 3],
+    render_area: RenderArea,
+    samples: u32,
+    fragments: &mut Vec<Fragment>,
+    color: Color,
+    interpolations: &Interpolations,
+) {
+    let Some(triangle) = Triangle::new(vertices, render_area) else {
+        // Degenerate triangle does not cover any pixel.
+        return;
+    };
+    #[cfg(feature = "simd")]
+    simd::rasterize_triangle(&triangle, samples, fragments, color, interpolations);
+    #[cfg(not(feature = "simd"))]
+    rasterize_triangle(&triangle, samples, fragments, color, interpolations);
+}
 
-    let d_err = (y1 - y0).abs();
-    let d_x = x1 - x0;
-    let y_step = if y0 > y1 { -1_i32 } else { 1 };
+/// Non-degenerate triangle in framebuffer coordinates, set up for rasterization within render
+/// area.
+struct Triangle {
+    /// Vertices ordered so that edge functions are positive inside triangle, keeping the first
+    /// one.
+    vertices: [Vertex; 3],
+    positions: [[f32; 3]; 3],
+    /// Inverse of clip w of each vertex, which varies linearly in framebuffer coordinates.
+    inv_w: [f32; 3],
+    /// Twice the area of triangle.
+    area: f32,
+    /// Ranges of pixels of bounding box of triangle clamped to render area.
+    x: (u32, u32),
+    y: (u32, u32),
+}
 
-    let mut err = d_x / 2; // Pixel center.
-    let mut y = y0;
-    for x in x0..=x1 {
-        // TODO: z_screen
-        let (x_fragment, y_fragment) = if steep {
-            (y as f32, x as f32)
+impl Triangle {
+    fn new(vertices: [Vertex; 3], render_area: RenderArea) -> Option<Self> {
+        let [p0, p1, p2] = vertices.map(framebuffer_position);
+        let area = edge_function(p0, p1, p2);
+        if area == 0.0f32 || !area.is_finite() {
+            return None;
+        }
+        let [v0, v1, v2] = vertices;
+        let (vertices, area) = if area < 0.0f32 {
+            ([v0, v2, v1], -area)
         } else {
-            (x as f32, y as f32)
+            ([v0, v1, v2], area)
         };
-        fragments.push(Fragment {
-            position: Position::from_sfloat32_raw(x_fragment, y_fragment, 0.0f32, 1.0f32), // TODO: Get z and w from vertex shader.
-            color,
-        });
-        err -= d_err;
-        if err < 0 {
-            y += y_step;
-            err += d_x;
-        }
+        let positions = vertices.map(framebuffer_position);
+        let [p0, p1, p2] = positions;
+        let bounds = |i: usize, offset: i32, size: u32| {
+            let (start, end) = (offset as f32, offset as f32 + size as f32);
+            let min = p0[i].min(p1[i]).min(p2[i]).floor().clamp(start, end);
+            let max = p0[i].max(p1[i]).max(p2[i]).ceil().clamp(start, end);
+            (min.max(0.0f32) as u32, max.max(0.0f32) as u32)
+        };
+        Some(Self {
+            vertices,
+            positions,
+            inv_w: vertices.map(|vertex| vertex.position.get_as_sfloat32(3)),
+            area,
+            x: bounds(0, render_area.offset.x, render_area.extent.width),
+            y: bounds(1, render_area.offset.y, render_area.extent.height),
+        })
+    }
+
+    /// Returns edge functions of edges opposite to each vertex at point `p`.
+    #[cfg(any(test, not(feature = "simd")))]
+    fn edges(&self, p: [f32; 3]) -> [f32; 3] {
+        let [p0, p1, p2] = self.positions;
+        [
+            edge_function(p1, p2, p),
+            edge_function(p2, p0, p),
+            edge_function(p0, p1, p),
+        ]
     }
 }
 
-pub fn draw_points(
-    vertices: impl IntoIterator<Item = Vertex>,
+/// Rasterizes triangle one pixel at a time. Reference for the SIMD path.
+#[cfg(any(test, not(feature = "simd")))]
+fn rasterize_triangle(
+    triangle: &Triangle,
+    samples: u32,
     fragments: &mut Vec<Fragment>,
     color: Color,
+    interpolations: &Interpolations,
 ) {
-    for vertex in vertices {
-        let position = Position::from_sfloat32(vertex.position);
-        fragments.push(Fragment { position, color });
+    let [p0, p1, p2] = triangle.positions;
+    let [q0, q1, q2] = triangle.inv_w;
+    let area = triangle.area;
+    let is_inside = |p: [f32; 3]| {
+        let [w0, w1, w2] = triangle.edges(p);
+        is_covered(w0, p1, p2) && is_covered(w1, p2, p0) && is_covered(w2, p0, p1)
+    };
+    let sample_locations = sample_locations(samples);
+
+    for y in triangle.y.0..triangle.y.1 {
+        for x in triangle.x.0..triangle.x.1 {
+            let coverage = sample_locations
+                .iter()
+                .enumerate()
+                .filter(|(_, [sx, sy])| is_inside([x as f32 + sx, y as f32 + sy, 0.0f32]))
+                .fold(0, |coverage, (i, _)| coverage | 1 << i);
+            if coverage == 0 {
+                continue;
+            }
+            let p = [x as f32 + 0.5f32, y as f32 + 0.5f32, 0.0f32];
+            let [w0, w1, w2] = triangle.edges(p);
+            let z = w2.mul_add(p2[2], w0.mul_add(p0[2], w1 * p1[2])) / area;
+            let inv_w = w2.mul_add(q2, w0.mul_add(q0, w1 * q1)) / area;
+            let weights = [w0 / area, w1 / area, w2 / area];
+            fragments.push(Fragment {
+                position: Position::from_sfloat32_raw(p[0], p[1], z, inv_w),
+                color,
+                front_facing: true,
+                coverage,
+                attributes: interpolate_attributes(triangle.vertices, weights, interpolations),
+            });
+        }
+    }
+}
+
+/// Returns standard locations of `samples` samples within pixel, relative to its top left corner.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-multisampling
+pub fn sample_locations(samples: u32) -> &'static [[f32; 2]] {
+    match samples {
+        1 => &[[0.5f32, 0.5f32]],
+        2 => &[[0.75f32, 0.75f32], [0.25f32, 0.25f32]],
+        4 => &[
+            [0.375f32, 0.125f32],
+            [0.875f32, 0.375f32],
+            [0.125f32, 0.625f32],
+            [0.625f32, 0.875f32],
+        ],
+        _ => unimplemented!("{samples} samples per pixel"),
+    }
+}
+
+/// Returns derivatives of texture coordinates linearly interpolated across triangle along
+/// framebuffer `x` and `y` axes, or `None` if triangle is degenerate.
+pub fn texture_coordinate_derivatives(
+    vertices: [Vertex; 3],
+    texture_coordinates: [[f32; 2]; 3],
+) -> Option<([f32; 2], [f32; 2])> {
+    let [p0, p1, p2] = vertices.map(framebuffer_position);
+    let area = edge_function(p0, p1, p2);
+    if area == 0.0f32 || !area.is_finite() {
+        return None;
+    }
+    let (dx1, dy1) = (p1[0] - p0[0], p1[1] - p0[1]);
+    let (dx2, dy2) = (p2[0] - p0[0], p2[1] - p0[1]);
+    let [t0, t1, t2] = texture_coordinates;
+    let mut derivatives_x = [0.0f32; 2];
+    let mut derivatives_y = [0.0f32; 2];
+    for i in 0..2 {
+        let (dt1, dt2) = (t1[i] - t0[i], t2[i] - t0[i]);
+        derivatives_x[i] = dt1.mul_add(dy2, -dt2 * dy1) / area;
+        derivatives_y[i] = dt2.mul_add(dx1, -dt1 * dx2) / area;
+    }
+    Some((derivatives_x, derivatives_y))
+}
+
+/// Returns larger of absolute depth derivatives of triangle along framebuffer `x` and `y` axes,
+/// or `None` if triangle is degenerate.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-depthbias-computation
+pub fn max_depth_slope(vertices: [Vertex; 3]) -> Option<f32> {
+    let [p0, p1, p2] = vertices.map(framebuffer_position);
+    let area = edge_function(p0, p1, p2);
+    if area == 0.0f32 || !area.is_finite() {
+        return None;
+    }
+    let (dx1, dy1, dz1) = (p1[0] - p0[0], p1[1] - p0[1], p1[2] - p0[2]);
+    let (dx2, dy2, dz2) = (p2[0] - p0[0], p2[1] - p0[1], p2[2] - p0[2]);
+    let derivative_x = dz1.mul_add(dy2, -dz2 * dy1) / area;
+    let derivative_y = dz2.mul_add(dx1, -dz1 * dx2) / area;
+    Some(derivative_x.abs().max(derivative_y.abs()))
+}
+
+/// Determines whether triangle in framebuffer coordinates is front-facing, returning `None` for
+/// degenerate triangle.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-polygons-basic
+pub fn is_triangle_front_facing(vertices: [Vertex; 3], front_face: FrontFace) -> Option<bool> {
+    let [p0, p1, p2] = vertices.map(framebuffer_position);
+    // Framebuffer y axis points down, so counter-clockwise triangles have negative edge function.
+    let area = -edge_function(p0, p1, p2);
+    if area == 0.0f32 || !area.is_finite() {
+        return None;
+    }
+    Some(match front_face {
+        FrontFace::CounterClockwise => area > 0.0f32,
+        FrontFace::Clockwise => area < 0.0f32,
+    })
+}
+
+/// Determines whether triangle in framebuffer coordinates is discarded by culling.
+pub fn is_triangle_culled(
+    vertices: [Vertex; 3],
+    cull_mode: CullMode,
+    front_face: FrontFace,
+) -> bool {
+    let Some(is_front_facing) = is_triangle_front_facing(vertices, front_face) else {
+        // Degenerate triangle is always culled.
+        return true;
+    };
+    match cull_mode {
+        CullMode::None => false,
+        CullMode::Front => is_front_facing,
+        CullMode::Back => !is_front_facing,
+        CullMode::FrontAndBack => true,
+    }
+}
+
+/// Interpolates attributes of primitive `vertices` at point with barycentric `weights` in
+/// framebuffer space.
+///
+/// Perspective-correct weights are those divided by clip `w` of each vertex, normalized. Flat
+/// attributes keep raw components of the first vertex.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-polygons-basic
+fn interpolate_attributes<const N: usize>(
+    vertices: [Vertex; N],
+    weights: [f32; N],
+    interpolations: &Interpolations,
+) -> [Vector4; MAX_VERTEX_ATTRIBUTES as usize] {
+    let inv_w = vertices.map(|vertex| vertex.position.get_as_sfloat32(3));
+    let perspective_weights = std::array::from_fn::<_, N, _>(|i| weights[i] * inv_w[i]);
+    let sum = perspective_weights.iter().sum::<f32>();
+    let perspective_weights = perspective_weights.map(|weight| weight / sum);
+    std::array::from_fn(|location| {
+        let weights = match interpolations[location] {
+            Interpolation::Flat => return vertices[0].attributes[location],
+            Interpolation::NoPerspective => weights,
+            Interpolation::Smooth => perspective_weights,
+        };
+        let [x, y, z, w] = [0, 1, 2, 3].map(|component| {
+            std::iter::zip(&vertices, weights).fold(0.0f32, |value, (vertex, weight)| {
+                vertex.attributes[location]
+                    .get_as_sfloat32(component)
+                    .mul_add(weight, value)
+            })
+        });
+        Vector4::from_sfloat32_raw(x, y, z, w)
+    })
+}
+
+fn framebuffer_position(vertex: Vertex) -> [f32; 3] {
+    let position = vertex.position;
+    [
+        position.get_as_sfloat32(0),
+        position.get_as_sfloat32(1),
+        position.get_as_sfloat32(2),
+    ]
+}
+
+/// Returns twice the signed area of triangle `a`, `b`, `p`.
+// Multiply-add is not fused, as SIMD lanes only fuse it on targets with FMA, so that every
+// rasterization path classifies samples on edges the same.
+#[allow(clippy::suboptimal_flops)]
+fn edge_function(a: [f32; 3], b: [f32; 3], p: [f32; 3]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Sample lying exactly on edge is covered only for top and left edges, so that pixels on edges
+/// shared by adjacent triangles are rasterized once.
+#[cfg(any(test, not(feature = "simd")))]
+fn is_covered(w: f32, a: [f32; 3], b: [f32; 3]) -> bool {
+    w > 0.0f32 || (w == 0.0f32 && is_top_left(a, b))
+}
+
+fn is_top_left(a: [f32; 3], b: [f32; 3]) -> bool {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    (dy == 0.0f32 && dx > 0.0f32) || dy < 0.0f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::math::{Extent2, Offset2};
+
+    fn vertex(x: f32, y: f32) -> Vertex {
+        Vertex {
+            position: Position::from_sfloat32_raw(x, y, 0.0f32, 1.0f32),
+            point_size: 1.0f32,
+            index: 0,
+            instance_index: 0,
+            clip_distances: Default::default(),
+            attributes: Default::default(),
+        }
+    }
+
+    fn render_area() -> RenderArea {
+        RenderArea {
+            offset: Offset2 { x: 0, y: 0 },
+            extent: Extent2 {
+                width: 8,
+                height: 8,
+            },
+        }
+    }
+
+    fn color() -> Color {
+        Color::from_sfloat32_raw(1.0f32, 1.0f32, 1.0f32, 1.0f32)
+    }
+
+    fn fragment_pixels(fragments: &[Fragment]) -> Vec<(u32, u32)> {
+        let mut pixels = fragments
+            .iter()
+            .map(|fragment| {
+                let [x, y, _, _] = fragment.position.get_as_f32_array();
+                (x as u32, y as u32)
+            })
+            .collect::<Vec<_>>();
+        pixels.sort_by_key(|&(x, y)| (y, x));
+        pixels
+    }
+
+    fn line_pixels(v0: Vertex, v1: Vertex, width: f32) -> Vec<(u32, u32)> {
+        let mut fragments = vec![];
+        rasterize_line(
+            v0,
+            v1,
+            width,
+            render_area(),
+            &mut fragments,
+            color(),
+            &Default::default(),
+        );
+        fragment_pixels(&fragments)
+    }
+
+    fn point_pixels(v: Vertex, size: f32) -> Vec<(u32, u32)> {
+        let mut fragments = vec![];
+        rasterize_point(v, size, render_area(), &mut fragments, color());
+        fragment_pixels(&fragments)
+    }
+
+    #[test]
+    fn rasterize_line_horizontal() {
+        // First endpoint is covered, last one is left for the next connected segment.
+        assert_eq!(
+            line_pixels(vertex(0.5, 1.5), vertex(4.5, 1.5), 1.0),
+            [(0, 1), (1, 1), (2, 1), (3, 1)]
+        );
+        assert_eq!(
+            line_pixels(vertex(4.5, 1.5), vertex(0.5, 1.5), 1.0),
+            [(1, 1), (2, 1), (3, 1), (4, 1)]
+        );
+        // Wide line covers pixels centered on it along minor axis.
+        assert_eq!(
+            line_pixels(vertex(0.5, 2.0), vertex(2.5, 2.0), 2.0),
+            [(0, 1), (1, 1), (0, 2), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn rasterize_line_diagonal() {
+        assert_eq!(
+            line_pixels(vertex(0.5, 0.5), vertex(3.5, 3.5), 1.0),
+            [(0, 0), (1, 1), (2, 2)]
+        );
+        // Steep line steps along y axis.
+        assert_eq!(
+            line_pixels(vertex(1.5, 0.5), vertex(3.5, 4.5), 1.0),
+            [(1, 0), (1, 1), (2, 2), (2, 3)]
+        );
+    }
+
+    #[test]
+    fn rasterize_line_clipped_to_render_area() {
+        assert_eq!(
+            line_pixels(vertex(-100.5, 0.5), vertex(100.5, 0.5), 1.0),
+            (0..8).map(|x| (x, 0)).collect::<Vec<_>>()
+        );
+        assert_eq!(line_pixels(vertex(1.5, 1.5), vertex(1.5, 1.5), 1.0), []);
+    }
+
+    #[test]
+    fn rasterize_point_square() {
+        assert_eq!(point_pixels(vertex(2.3, 5.9), 1.0), [(2, 5)]);
+        assert_eq!(point_pixels(vertex(2.3, 5.9), 0.5), [(2, 5)]);
+        let expected = (2..6)
+            .flat_map(|y| (3..7).map(move |x| (x, y)))
+            .collect::<Vec<_>>();
+        assert_eq!(point_pixels(vertex(5.0, 4.0), 4.0), expected);
+        // Square is clipped to render area.
+        assert_eq!(
+            point_pixels(vertex(0.0, 0.0), 4.0),
+            [(0, 0), (1, 0), (0, 1), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn draw_triangle_sample_coverage() {
+        let mut fragments = vec![];
+        let vertices = [vertex(0.0, 0.0), vertex(8.0, 0.0), vertex(0.0, 8.0)];
+        draw_triangle(
+            vertices,
+            render_area(),
+            4,
+            &mut fragments,
+            color(),
+            &Default::default(),
+        );
+        let coverage = |x: f32, y: f32| {
+            fragments
+                .iter()
+                .find(|fragment| fragment.position.get_as_f32_array()[..2] == [x, y])
+                .map(|fragment| fragment.coverage)
+        };
+        assert_eq!(coverage(3.5, 3.5), Some(0b1111));
+        // Diagonal edge crosses pixel between its samples, leaving the right ones uncovered.
+        assert_eq!(coverage(3.5, 4.5), Some(0b0101));
+        assert_eq!(coverage(4.5, 4.5), None);
+    }
+
+    #[test]
+    fn draw_triangle_interpolates_inverse_w() {
+        let mut fragments = vec![];
+        let mut vertices = [vertex(0.0, 0.0), vertex(8.0, 0.0), vertex(0.0, 8.0)];
+        vertices[1].position = Position::from_sfloat32_raw(8.0, 0.0, 0.0, 0.5);
+        draw_triangle(
+            vertices,
+            render_area(),
+            1,
+            &mut fragments,
+            color(),
+            &Default::default(),
+        );
+        let inv_w = |x: f32, y: f32| {
+            fragments
+                .iter()
+                .find(|fragment| fragment.position.get_as_f32_array()[..2] == [x, y])
+                .map(|fragment| fragment.position.get_as_sfloat32(3))
+        };
+        assert_eq!(inv_w(0.5, 0.5), Some(0.96875));
+        assert_eq!(inv_w(3.5, 0.5), Some(0.78125));
+        assert_eq!(inv_w(0.5, 6.5), Some(0.96875));
+    }
+
+    #[test]
+    fn draw_triangle_interpolates_attributes() {
+        // Second vertex lies twice as far from viewer, carrying value 1 in each attribute but the
+        // flat one, which holds raw integer distinct for each vertex.
+        let mut vertices = [vertex(0.0, 0.0), vertex(8.0, 0.0), vertex(0.0, 8.0)];
+        vertices[1].position = Position::from_sfloat32_raw(8.0, 0.0, 0.0, 0.5);
+        for (i, vertex) in vertices.iter_mut().enumerate() {
+            let value = if i == 1 { 1.0f32 } else { 0.0f32 };
+            vertex.attributes[0] = Vector4::from_sfloat32_raw(value, 0.0, 0.0, 0.0);
+            vertex.attributes[1] = vertex.attributes[0];
+            vertex.attributes[2] = Vector4::from_raw(10 + i as u64, 0, 0, 0);
+        }
+        let mut interpolations = Interpolations::default();
+        interpolations[1] = Interpolation::NoPerspective;
+        interpolations[2] = Interpolation::Flat;
+        let mut fragments = vec![];
+        draw_triangle(
+            vertices,
+            render_area(),
+            1,
+            &mut fragments,
+            color(),
+            &interpolations,
+        );
+        let Some(fragment) = fragments
+            .iter()
+            .find(|fragment| fragment.position.get_as_f32_array()[..2] == [3.5, 0.5])
+        else {
+            unreachable!()
+        };
+
+        // Perspective-correct value is pulled towards nearer vertices.
+        let smooth = fragment.attributes[0].get_as_sfloat32(0);
+        assert!((smooth - 0.28).abs() < 1e-6, "{smooth}");
+        assert_eq!(fragment.attributes[1].get_as_sfloat32(0), 0.4375);
+        assert_eq!(fragment.attributes[2], Vector4::from_raw(10, 0, 0, 0));
+    }
+
+    /// Rasterizes triangles with scalar and SIMD paths within unaligned render area, with varying
+    /// depth, clip `w` and attributes at vertices.
+    #[cfg(feature = "simd")]
+    fn rasterize_scalar_and_simd(
+        triangles: &[[[f32; 2]; 3]],
+        samples: u32,
+    ) -> (Vec<Fragment>, Vec<Fragment>) {
+        let render_area = RenderArea {
+            offset: Offset2 { x: 3, y: 2 },
+            extent: Extent2 {
+                width: 61,
+                height: 45,
+            },
+        };
+        let mut interpolations = Interpolations::default();
+        interpolations[1] = Interpolation::NoPerspective;
+        interpolations[2] = Interpolation::Flat;
+        let (mut scalar, mut simd) = (vec![], vec![]);
+        for triangle in triangles {
+            let vertices = std::array::from_fn(|i| {
+                let [x, y] = triangle[i];
+                let mut vertex = vertex(x, y);
+                let (z, inv_w) = (
+                    0.3f32.mul_add(i as f32, 0.1f32),
+                    1.0f32 / (1.0f32 + i as f32),
+                );
+                vertex.position = Position::from_sfloat32_raw(x, y, z, inv_w);
+                for location in 0..3 {
+                    let value = (i * 3 + location) as f32 * 0.37f32;
+                    vertex.attributes[location] =
+                        Vector4::from_sfloat32_raw(value, -value, 1.0f32, x);
+                }
+                vertex
+            });
+            let Some(triangle) = Triangle::new(vertices, render_area) else {
+                continue;
+            };
+            rasterize_triangle(&triangle, samples, &mut scalar, color(), &interpolations);
+            simd::rasterize_triangle(&triangle, samples, &mut simd, color(), &interpolations);
+        }
+        (scalar, simd)
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn simd_triangle_rasterization_matches_scalar() {
+        let triangles = [
+            [[0.5, 0.25], [37.75, 3.5], [12.125, 29.0]],
+            // Clockwise, with samples lying exactly on edges.
+            [[30.0, 2.0], [2.0, 30.0], [31.0, 31.0]],
+            [[0.0, 0.0], [40.0, 1.0], [40.0, 1.5]],
+            // Partly outside render area.
+            [[-10.0, -5.0], [70.0, 10.0], [5.0, 55.0]],
+            [[4.0, 4.0], [20.0, 4.0], [4.0, 20.0]],
+            [[20.0, 4.0], [20.0, 20.0], [4.0, 20.0]],
+        ];
+        let close = |a: f32, b: f32| (a - b).abs() <= 1e-5f32 * a.abs().max(1.0f32);
+        for samples in [1, 4] {
+            let (scalar, simd) = rasterize_scalar_and_simd(&triangles, samples);
+            assert_eq!(scalar.len(), simd.len());
+            for (scalar, simd) in std::iter::zip(&scalar, &simd) {
+                let [x, y, z, inv_w] = scalar.position.get_as_f32_array();
+                let [simd_x, simd_y, simd_z, simd_inv_w] = simd.position.get_as_f32_array();
+                assert_eq!((x, y, scalar.coverage), (simd_x, simd_y, simd.coverage));
+                assert!(close(z, simd_z) && close(inv_w, simd_inv_w), "({x}, {y})");
+                assert_eq!(scalar.attributes[2], simd.attributes[2]);
+                for location in 0..2 {
+                    let scalar = scalar.attributes[location].get_as_f32_array();
+                    let simd = simd.attributes[location].get_as_f32_array();
+                    assert!(
+                        std::iter::zip(scalar, simd).all(|(a, b)| close(a, b)),
+                        "({x}, {y}): {scalar:?} {simd:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Measures speedup of SIMD rasterization of triangles over scalar one. Run with
+    /// `cargo test --release -p gpu --features simd -- --ignored --nocapture simd_triangle_rasterization_speedup`.
+    #[cfg(feature = "simd")]
+    #[test]
+    #[ignore]
+    fn simd_triangle_rasterization_speedup() {
+        let triangles = (0..2000)
+            .map(|i| {
+                let offset = (i % 7) as f32 * 0.125f32;
+                [[offset, 1.0], [64.0, offset], [30.0 - offset, 48.0]]
+            })
+            .collect::<Vec<_>>();
+        let time = |rasterize: fn(&Triangle, u32, &mut Vec<Fragment>, Color, &Interpolations)| {
+            let render_area = RenderArea {
+                offset: Offset2 { x: 0, y: 0 },
+                extent: Extent2 {
+                    width: 64,
+                    height: 48,
+                },
+            };
+            let interpolations = Interpolations::default();
+            let mut fragments = vec![];
+            let start = std::time::Instant::now();
+            for &[v0, v1, v2] in &triangles {
+                let vertices = [v0, v1, v2].map(|[x, y]| vertex(x, y));
+                let Some(triangle) = Triangle::new(vertices, render_area) else {
+                    unreachable!()
+                };
+                fragments.clear();
+                rasterize(&triangle, 4, &mut fragments, color(), &interpolations);
+            }
+            start.elapsed()
+        };
+        let scalar_time = time(rasterize_triangle);
+        let simd_time = time(simd::rasterize_triangle);
+        println!(
+            "scalar: {scalar_time:?}, simd: {simd_time:?}, speedup: {:.2}",
+            scalar_time.as_secs_f64() / simd_time.as_secs_f64()
+        );
+    }
+
+    #[test]
+    fn max_depth_slope_takes_steeper_axis() {
+        let with_depth = |x: f32, y: f32, z: f32| Vertex {
+            position: Position::from_sfloat32_raw(x, y, z, 1.0f32),
+            ..vertex(x, y)
+        };
+        let vertices = [
+            with_depth(0.0, 0.0, 0.5),
+            with_depth(8.0, 0.0, 0.75),
+            with_depth(0.0, 8.0, 0.0),
+        ];
+        assert_eq!(max_depth_slope(vertices), Some(0.0625));
+        let degenerate = [vertex(0.0, 0.0), vertex(4.0, 4.0), vertex(8.0, 8.0)];
+        assert_eq!(max_depth_slope(degenerate), None);
     }
 }
@@ -0,0 +1,123 @@
+use super::{is_top_left, sample_locations, Interpolations, Triangle};
+use common::consts::MAX_VERTEX_ATTRIBUTES;
+use common::graphics::Interpolation;
+use common::math::{Color, Fragment, Position, Vector4};
+use wide::{f32x8, CmpEq, CmpGt};
+
+/// Number of pixels of row rasterized at once.
+const LANES: usize = 8;
+
+/// Rasterizes triangle `LANES` pixels of row at a time, evaluating edge functions and
+/// interpolating attributes of all of them at once. Coverage is the same as of the scalar path,
+/// attributes are up to rounding of multiply-adds not being fused on targets without FMA.
+pub(super) fn rasterize_triangle(
+    triangle: &Triangle,
+    samples: u32,
+    fragments: &mut Vec<Fragment>,
+    color: Color,
+    interpolations: &Interpolations,
+) {
+    let [p0, p1, p2] = triangle.positions;
+    let edges = [(p1, p2), (p2, p0), (p0, p1)];
+    // Lane masks of edges, set for top and left ones covering samples lying exactly on them.
+    let all = f32x8::splat(f32::from_bits(!0));
+    let zero = f32x8::splat(0.0f32);
+    let top_left = edges.map(|(a, b)| if is_top_left(a, b) { all } else { zero });
+    let offsets = f32x8::from(std::array::from_fn::<_, LANES, _>(|lane| lane as f32));
+    let sample_locations = sample_locations(samples);
+    let (x_min, x_max) = triangle.x;
+
+    for y in triangle.y.0..triangle.y.1 {
+        for x in (x_min..x_max).step_by(LANES) {
+            let xs = f32x8::splat(x as f32) + offsets;
+            let edge_functions = |[sx, sy]: [f32; 2]| {
+                let (px, py) = (xs + f32x8::splat(sx), y as f32 + sy);
+                // Same operations as scalar `edge_function`, for samples on edges to be
+                // classified the same.
+                edges.map(|(a, b)| {
+                    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+                    f32x8::splat(dx) * f32x8::splat(py - a[1])
+                        - f32x8::splat(dy) * (px - f32x8::splat(a[0]))
+                })
+            };
+
+            let lanes = (x_max - x).min(LANES as u32);
+            let mut coverage = [0u32; LANES];
+            for (i, &location) in sample_locations.iter().enumerate() {
+                let inside = std::iter::zip(edge_functions(location), top_left)
+                    .map(|(w, top_left)| w.cmp_gt(zero) | (w.cmp_eq(zero) & top_left))
+                    .fold(all, |inside, covered| inside & covered)
+                    .move_mask();
+                for (lane, coverage) in coverage.iter_mut().enumerate() {
+                    *coverage |= (inside as u32 >> lane & 1) << i;
+                }
+            }
+            if coverage[..lanes as usize]
+                .iter()
+                .all(|&coverage| coverage == 0)
+            {
+                continue;
+            }
+
+            let [w0, w1, w2] = edge_functions([0.5f32, 0.5f32]);
+            let area = f32x8::splat(triangle.area);
+            let interpolate = |[a0, a1, a2]: [f32; 3]| {
+                let value = f32x8::splat(a0).mul_add(w0, f32x8::splat(a1) * w1);
+                f32x8::splat(a2).mul_add(w2, value) / area
+            };
+            let z = interpolate([p0[2], p1[2], p2[2]]).to_array();
+            let inv_w = interpolate(triangle.inv_w).to_array();
+            let attributes = interpolate_attributes(triangle, [w0, w1, w2], interpolations);
+
+            for lane in 0..lanes as usize {
+                if coverage[lane] == 0 {
+                    continue;
+                }
+                let px = (x + lane as u32) as f32 + 0.5f32;
+                fragments.push(Fragment {
+                    position: Position::from_sfloat32_raw(
+                        px,
+                        y as f32 + 0.5f32,
+                        z[lane],
+                        inv_w[lane],
+                    ),
+                    color,
+                    front_facing: true,
+                    coverage: coverage[lane],
+                    attributes: attributes.map(|attribute| attribute[lane]),
+                });
+            }
+        }
+    }
+}
+
+/// Interpolates attributes of `triangle` at pixels with edge functions `edges`, returning them
+/// per location for each lane.
+fn interpolate_attributes(
+    triangle: &Triangle,
+    edges: [f32x8; 3],
+    interpolations: &Interpolations,
+) -> [[Vector4; LANES]; MAX_VERTEX_ATTRIBUTES as usize] {
+    let area = f32x8::splat(triangle.area);
+    let weights = edges.map(|w| w / area);
+    let perspective_weights =
+        std::array::from_fn::<_, 3, _>(|i| weights[i] * f32x8::splat(triangle.inv_w[i]));
+    let sum = perspective_weights[0] + perspective_weights[1] + perspective_weights[2];
+    let perspective_weights = perspective_weights.map(|weight| weight / sum);
+    std::array::from_fn(|location| {
+        let weights = match interpolations[location] {
+            Interpolation::Flat => return [triangle.vertices[0].attributes[location]; LANES],
+            Interpolation::NoPerspective => weights,
+            Interpolation::Smooth => perspective_weights,
+        };
+        let [x, y, z, w] = [0, 1, 2, 3].map(|component| {
+            std::iter::zip(&triangle.vertices, weights)
+                .fold(f32x8::splat(0.0f32), |value, (vertex, weight)| {
+                    let attribute = vertex.attributes[location].get_as_sfloat32(component);
+                    f32x8::splat(attribute).mul_add(weight, value)
+                })
+                .to_array()
+        });
+        std::array::from_fn(|lane| Vector4::from_sfloat32_raw(x[lane], y[lane], z[lane], w[lane]))
+    })
+}
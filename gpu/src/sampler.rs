@@ -0,0 +1,535 @@
+use crate::Memory;
+use common::bc;
+use common::consts::MAX_SAMPLER_LOD_BIAS;
+use common::graphics::{
+    BorderColor, DescriptorImage, Filter, SamplerAddressMode, SamplerMipmapMode, SamplerState,
+};
+use common::math::{Extent3, Format, Texel};
+
+/// Reads texel of image through image view swizzle, decompressing block containing it for
+/// block-compressed formats.
+pub fn read_texel(
+    memory: &Memory,
+    image: &DescriptorImage,
+    format: Format,
+    x: u32,
+    y: u32,
+) -> Texel {
+    let bytes_per_pixel = format.info().bytes_per_pixel as u64;
+    let (block_width, block_height) = format.block_extent();
+    let blocks_per_row = image.extent.width.div_ceil(block_width) as u64;
+    let block_index = (y / block_height) as u64 * blocks_per_row + (x / block_width) as u64;
+    let offset = image.binding.offset + block_index * bytes_per_pixel;
+    let bytes = memory.read_bytes(&image.binding, offset, bytes_per_pixel);
+    let texel = if format.is_block_compressed() {
+        let [r, g, b, a] = bc::read_texel(format, bytes, x % block_width, y % block_height);
+        Texel::from_sfloat32_raw(r, g, b, a)
+    } else {
+        Texel::from_vertex_buffer_bytes(format, bytes)
+    };
+    image.components.apply(texel, format)
+}
+
+/// Reads texel at unnormalized coordinates, clamping texels outside of image to its edge.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#textures-texel-filtering
+pub fn read_texel_filtered(
+    memory: &Memory,
+    image: &DescriptorImage,
+    format: Format,
+    u: f32,
+    v: f32,
+    filter: Filter,
+) -> Texel {
+    let clamp = |i: f32, size: u32| i.clamp(0.0f32, (size - 1) as f32) as u32;
+    let (width, height) = (image.extent.width, image.extent.height);
+    match filter {
+        Filter::Nearest => read_texel(
+            memory,
+            image,
+            format,
+            clamp(u.floor(), width),
+            clamp(v.floor(), height),
+        ),
+        Filter::Linear => {
+            let (u, v) = (u - 0.5f32, v - 0.5f32);
+            let (i0, j0) = (u.floor(), v.floor());
+            let (alpha, beta) = (u - i0, v - j0);
+            let (i1, j1) = (clamp(i0 + 1.0f32, width), clamp(j0 + 1.0f32, height));
+            let (i0, j0) = (clamp(i0, width), clamp(j0, height));
+            let texel = |i: u32, j: u32| read_texel(memory, image, format, i, j).get_as_f32_array();
+            let top = lerp(texel(i0, j0), texel(i1, j0), alpha);
+            let bottom = lerp(texel(i0, j1), texel(i1, j1), alpha);
+            let [r, g, b, a] = lerp(top, bottom, beta);
+            Texel::from_sfloat32_raw(r, g, b, a)
+        }
+    }
+}
+
+fn lerp(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    let mut result = [0.0f32; 4];
+    for (i, value) in result.iter_mut().enumerate() {
+        *value = (b[i] - a[i]).mul_add(t, a[i]);
+    }
+    result
+}
+
+/// Reads texel at normalized coordinates from single level of image, filtering it with sampler
+/// magnification filter.
+pub fn sample(
+    memory: &Memory,
+    image: &DescriptorImage,
+    format: Format,
+    sampler: &SamplerState,
+    u: f32,
+    v: f32,
+) -> Texel {
+    sample_level(memory, image, format, sampler, sampler.mag_filter, u, v)
+}
+
+/// Reads texel at normalized coordinates from mip levels of image, selected by level of detail
+/// computed with [`compute_lod`].
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#textures-image-level-selection
+pub fn sample_lod(
+    memory: &Memory,
+    levels: &[DescriptorImage],
+    format: Format,
+    sampler: &SamplerState,
+    u: f32,
+    v: f32,
+    lod: f32,
+) -> Texel {
+    let Some(max_level) = levels.len().checked_sub(1) else {
+        unreachable!("Sampled image has no mip levels");
+    };
+    // Magnification uses only the base level.
+    let filter = if lod <= 0.0f32 {
+        sampler.mag_filter
+    } else {
+        sampler.min_filter
+    };
+    let level = lod.clamp(0.0f32, max_level as f32);
+    let sample = |level: usize| sample_level(memory, &levels[level], format, sampler, filter, u, v);
+    match sampler.mipmap_mode {
+        SamplerMipmapMode::Nearest => sample(((level + 0.5f32).ceil() - 1.0f32) as usize),
+        SamplerMipmapMode::Linear => {
+            let level_hi = level.floor();
+            let delta = level - level_hi;
+            let level_hi = level_hi as usize;
+            let level_lo = (level_hi + 1).min(max_level);
+            let texel = lerp(
+                sample(level_hi).get_as_f32_array(),
+                sample(level_lo).get_as_f32_array(),
+                delta,
+            );
+            let [r, g, b, a] = texel;
+            Texel::from_sfloat32_raw(r, g, b, a)
+        }
+    }
+}
+
+/// Computes level of detail from derivatives of normalized texture coordinates along framebuffer
+/// `x` and `y` axes, applying sampler bias and clamping it to sampler LOD range.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#textures-scale-factor
+pub fn compute_lod(
+    sampler: &SamplerState,
+    base_extent: Extent3<u32>,
+    derivatives_x: [f32; 2],
+    derivatives_y: [f32; 2],
+) -> f32 {
+    let (width, height) = (base_extent.width as f32, base_extent.height as f32);
+    let scale = |[du, dv]: [f32; 2]| (du * width).hypot(dv * height);
+    let rho = scale(derivatives_x).max(scale(derivatives_y));
+    let bias = sampler
+        .mip_lod_bias
+        .clamp(-MAX_SAMPLER_LOD_BIAS, MAX_SAMPLER_LOD_BIAS);
+    (rho.log2() + bias)
+        .max(sampler.min_lod)
+        .min(sampler.max_lod)
+}
+
+fn sample_level(
+    memory: &Memory,
+    image: &DescriptorImage,
+    format: Format,
+    sampler: &SamplerState,
+    filter: Filter,
+    u: f32,
+    v: f32,
+) -> Texel {
+    let (width, height) = (image.extent.width, image.extent.height);
+    let texel = |i: i32, j: i32| {
+        let i = wrap_texel_coordinate(i, width, sampler.address_mode_u);
+        let j = wrap_texel_coordinate(j, height, sampler.address_mode_v);
+        match (i, j) {
+            (Some(i), Some(j)) => read_texel(memory, image, format, i, j),
+            _ => image
+                .components
+                .apply(border_color(sampler.border_color), format),
+        }
+    };
+    let (u, v) = (u * width as f32, v * height as f32);
+    match filter {
+        Filter::Nearest => texel(u.floor() as i32, v.floor() as i32),
+        Filter::Linear => {
+            let (u, v) = (u - 0.5f32, v - 0.5f32);
+            let (i0, j0) = (u.floor(), v.floor());
+            let (alpha, beta) = (u - i0, v - j0);
+            let (i0, j0) = (i0 as i32, j0 as i32);
+            // sRGB texels are already decoded, so filtering happens in linear space.
+            let texel = |i: i32, j: i32| texel(i, j).get_as_f32_array();
+            let top = lerp(texel(i0, j0), texel(i0 + 1, j0), alpha);
+            let bottom = lerp(texel(i0, j0 + 1), texel(i0 + 1, j0 + 1), alpha);
+            let [r, g, b, a] = lerp(top, bottom, beta);
+            Texel::from_sfloat32_raw(r, g, b, a)
+        }
+    }
+}
+
+/// Maps texel coordinate into image of given size, returning `None` if border color is used
+/// instead of texel.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#textures-wrapping-operation
+pub fn wrap_texel_coordinate(i: i32, size: u32, address_mode: SamplerAddressMode) -> Option<u32> {
+    let size = size as i32;
+    let mirror = |n: i32| if n >= 0 { n } else { -(1 + n) };
+    let i = match address_mode {
+        SamplerAddressMode::Repeat => i.rem_euclid(size),
+        SamplerAddressMode::MirroredRepeat => (size - 1) - mirror(i.rem_euclid(2 * size) - size),
+        SamplerAddressMode::ClampToEdge => i.clamp(0, size - 1),
+        SamplerAddressMode::ClampToBorder => {
+            if !(0..size).contains(&i) {
+                return None;
+            }
+            i
+        }
+        SamplerAddressMode::MirrorClampToEdge => mirror(i).clamp(0, size - 1),
+    };
+    Some(i as u32)
+}
+
+/// Returns border color, whose components missing from image format are substituted like ones
+/// of texels read from image.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#textures-texel-replacement
+fn border_color(border_color: BorderColor) -> Texel {
+    match border_color {
+        BorderColor::TransparentBlack => Texel::from_sfloat32_raw(0.0, 0.0, 0.0, 0.0),
+        BorderColor::OpaqueBlack => Texel::from_sfloat32_raw(0.0, 0.0, 0.0, 1.0),
+        BorderColor::OpaqueWhite => Texel::from_sfloat32_raw(1.0, 1.0, 1.0, 1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture_coordinate_derivatives;
+    use crate::MemoryHandleStore;
+    use common::graphics::{ComponentMapping, ComponentSwizzle, MemoryBinding};
+    use common::math::{srgb_to_linear, Position, Vertex};
+
+    fn assert_texel_eq(texel: Texel, expected: [f32; 4]) {
+        for (actual, expected) in texel.get_as_f32_array().iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() < 1e-6,
+                "{texel:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_texel_swizzle() {
+        let mut memory = Memory::new();
+        let allocation = memory.allocate_memory(4 * 4);
+        memory.write_bytes(&[255, 51, 0, 102], &allocation, 8);
+        let mut binding = MemoryBinding::new();
+        binding.store(allocation, 0, 4 * 4);
+        let mut image = DescriptorImage {
+            binding,
+            extent: Extent3 {
+                width: 2,
+                height: 2,
+                depth: 1,
+            },
+            components: ComponentMapping::default(),
+        };
+
+        let texel = read_texel(&memory, &image, Format::R8G8B8A8Unorm, 0, 1);
+        assert_texel_eq(texel, [1.0, 0.2, 0.0, 0.4]);
+
+        image.components = ComponentMapping {
+            r: ComponentSwizzle::B,
+            b: ComponentSwizzle::R,
+            ..Default::default()
+        };
+        let texel = read_texel(&memory, &image, Format::R8G8B8A8Unorm, 0, 1);
+        assert_texel_eq(texel, [0.0, 0.2, 1.0, 0.4]);
+
+        image.components = ComponentMapping {
+            g: ComponentSwizzle::Zero,
+            a: ComponentSwizzle::One,
+            ..Default::default()
+        };
+        let texel = read_texel(&memory, &image, Format::R8G8B8A8Unorm, 0, 1);
+        assert_texel_eq(texel, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn read_texel_missing_components() {
+        let mut memory = Memory::new();
+        let allocation = memory.allocate_memory(1);
+        memory.write_bytes(&[255], &allocation, 0);
+        let mut binding = MemoryBinding::new();
+        binding.store(allocation, 0, 1);
+        let image = DescriptorImage {
+            binding,
+            extent: Extent3 {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+            components: ComponentMapping {
+                r: ComponentSwizzle::G,
+                g: ComponentSwizzle::R,
+                b: ComponentSwizzle::R,
+                a: ComponentSwizzle::Identity,
+            },
+        };
+        let texel = read_texel(&memory, &image, Format::R8Unorm, 0, 0);
+        assert_texel_eq(texel, [0.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn sample_bc1_block_corners() {
+        // Red and blue endpoints, with the four corners using palette indices 0 to 3.
+        let block = [0x00, 0xf8, 0x1f, 0x00, 0x40, 0x00, 0x00, 0xc2];
+        let mut memory = Memory::new();
+        let allocation = memory.allocate_memory(8);
+        memory.write_bytes(&block, &allocation, 0);
+        let mut binding = MemoryBinding::new();
+        binding.store(allocation, 0, 8);
+        let image = DescriptorImage {
+            binding,
+            extent: Extent3 {
+                width: 4,
+                height: 4,
+                depth: 1,
+            },
+            components: ComponentMapping::default(),
+        };
+        let sample_at = |u, v| {
+            let sampler = SamplerState::default();
+            sample(&memory, &image, Format::Bc1RgbaUnormBlock, &sampler, u, v)
+        };
+
+        assert_texel_eq(sample_at(0.125, 0.125), [1.0, 0.0, 0.0, 1.0]);
+        assert_texel_eq(sample_at(0.875, 0.125), [0.0, 0.0, 1.0, 1.0]);
+        assert_texel_eq(sample_at(0.125, 0.875), [2.0 / 3.0, 0.0, 1.0 / 3.0, 1.0]);
+        assert_texel_eq(sample_at(0.875, 0.875), [1.0 / 3.0, 0.0, 2.0 / 3.0, 1.0]);
+    }
+
+    /// Creates single row image of 4 byte texels.
+    fn create_row_image(memory: &mut Memory, texels: &[[u8; 4]]) -> DescriptorImage {
+        let size = texels.len() as u64 * 4;
+        let allocation = memory.allocate_memory(size);
+        memory.write_bytes(texels.as_flattened(), &allocation, 0);
+        let mut binding = MemoryBinding::new();
+        binding.store(allocation, 0, size);
+        DescriptorImage {
+            binding,
+            extent: Extent3 {
+                width: texels.len() as u32,
+                height: 1,
+                depth: 1,
+            },
+            components: ComponentMapping::default(),
+        }
+    }
+
+    #[test]
+    fn sample_past_edge_address_modes() {
+        let mut memory = Memory::new();
+        let image = create_row_image(
+            &mut memory,
+            &[
+                [255, 0, 0, 255],
+                [0, 0, 0, 255],
+                [0, 0, 0, 255],
+                [0, 0, 255, 255],
+            ],
+        );
+        let sample_with = |address_mode_u| {
+            let sampler = SamplerState {
+                address_mode_u,
+                border_color: BorderColor::OpaqueWhite,
+                ..Default::default()
+            };
+            sample(&memory, &image, Format::R8G8B8A8Unorm, &sampler, 1.05, 0.5)
+        };
+
+        assert_texel_eq(
+            sample_with(SamplerAddressMode::Repeat),
+            [1.0, 0.0, 0.0, 1.0],
+        );
+        assert_texel_eq(
+            sample_with(SamplerAddressMode::ClampToEdge),
+            [0.0, 0.0, 1.0, 1.0],
+        );
+        assert_texel_eq(
+            sample_with(SamplerAddressMode::MirroredRepeat),
+            [0.0, 0.0, 1.0, 1.0],
+        );
+        assert_texel_eq(
+            sample_with(SamplerAddressMode::ClampToBorder),
+            [1.0, 1.0, 1.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn sample_linear_blends_border_with_edge_texels() {
+        let mut memory = Memory::new();
+        let image = create_row_image(&mut memory, &[[255, 0, 0, 255], [0, 0, 255, 255]]);
+        let sampler = SamplerState {
+            mag_filter: Filter::Linear,
+            address_mode_u: SamplerAddressMode::ClampToBorder,
+            address_mode_v: SamplerAddressMode::ClampToEdge,
+            border_color: BorderColor::OpaqueWhite,
+            ..Default::default()
+        };
+        let sample_at = |u, v| sample(&memory, &image, Format::R8G8B8A8Unorm, &sampler, u, v);
+
+        // Right edge is halfway between the last texel center and the border.
+        assert_texel_eq(sample_at(1.0, 0.5), [0.5, 0.5, 1.0, 1.0]);
+        assert_texel_eq(sample_at(0.0, 0.5), [1.0, 0.5, 0.5, 1.0]);
+        // Only the axis clamped to border uses border color.
+        assert_texel_eq(sample_at(0.75, 1.5), [0.0, 0.0, 1.0, 1.0]);
+        assert_texel_eq(sample_at(1.5, 0.5), [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn sample_border_substitutes_missing_components() {
+        let mut memory = Memory::new();
+        let mut image = create_row_image(&mut memory, &[[0; 4]]);
+        let sampler = SamplerState {
+            address_mode_u: SamplerAddressMode::ClampToBorder,
+            border_color: BorderColor::OpaqueWhite,
+            ..Default::default()
+        };
+        let texel = sample(&memory, &image, Format::R8Unorm, &sampler, 1.5, 0.5);
+        assert_texel_eq(texel, [1.0, 0.0, 0.0, 1.0]);
+
+        image.components.g = ComponentSwizzle::R;
+        let texel = sample(&memory, &image, Format::R8Unorm, &sampler, 1.5, 0.5);
+        assert_texel_eq(texel, [1.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn wrap_texel_coordinate_address_modes() {
+        let wrap =
+            |address_mode| [-5, -1, 0, 3, 4, 9].map(|i| wrap_texel_coordinate(i, 4, address_mode));
+        assert_eq!(
+            wrap(SamplerAddressMode::Repeat),
+            [3, 3, 0, 3, 0, 1].map(Some)
+        );
+        assert_eq!(
+            wrap(SamplerAddressMode::MirroredRepeat),
+            [3, 0, 0, 3, 3, 1].map(Some)
+        );
+        assert_eq!(
+            wrap(SamplerAddressMode::ClampToEdge),
+            [0, 0, 0, 3, 3, 3].map(Some)
+        );
+        assert_eq!(
+            wrap(SamplerAddressMode::ClampToBorder),
+            [None, None, Some(0), Some(3), None, None]
+        );
+        assert_eq!(
+            wrap(SamplerAddressMode::MirrorClampToEdge),
+            [3, 0, 0, 3, 3, 3].map(Some)
+        );
+    }
+
+    #[test]
+    fn sample_linear_midpoint_averages_texels() {
+        let mut memory = Memory::new();
+        let image = create_row_image(&mut memory, &[[255, 128, 0, 255], [0, 0, 255, 0]]);
+        let sampler = SamplerState {
+            mag_filter: Filter::Linear,
+            ..Default::default()
+        };
+
+        // Midpoint between texel centers at 0.25 and 0.75.
+        let texel = sample(&memory, &image, Format::R8G8B8A8Unorm, &sampler, 0.5, 0.5);
+        assert_texel_eq(texel, [0.5, 128.0 / 255.0 / 2.0, 0.5, 0.5]);
+
+        // sRGB texels are decoded before averaging.
+        let texel = sample(&memory, &image, Format::R8G8B8A8Srgb, &sampler, 0.5, 0.5);
+        let green = srgb_to_linear(128.0 / 255.0) / 2.0;
+        assert_texel_eq(texel, [0.5, green, 0.5, 0.5]);
+    }
+
+    /// Creates square image of `R8G8B8A8_UNORM` texels of single color.
+    fn create_solid_image(memory: &mut Memory, size: u32, texel: [u8; 4]) -> DescriptorImage {
+        let texels = vec![texel; (size * size) as usize];
+        let mut image = create_row_image(memory, &texels);
+        image.extent.width = size;
+        image.extent.height = size;
+        image
+    }
+
+    #[test]
+    fn sample_lod_blends_mip_levels() {
+        let mut memory = Memory::new();
+        let levels = [
+            create_solid_image(&mut memory, 4, [255, 0, 0, 255]),
+            create_solid_image(&mut memory, 2, [0, 255, 0, 255]),
+            create_solid_image(&mut memory, 1, [0, 0, 255, 255]),
+        ];
+        // Triangle covering 2x2 pixels maps whole 4x4 texture, so 2 texels per pixel.
+        let vertex = |x: f32, y: f32| Vertex {
+            position: Position::from_sfloat32_raw(x, y, 0.0f32, 1.0f32),
+            point_size: 1.0f32,
+            index: 0,
+            instance_index: 0,
+            clip_distances: Default::default(),
+            attributes: Default::default(),
+        };
+        let Some((derivatives_x, derivatives_y)) = texture_coordinate_derivatives(
+            [vertex(0.0, 0.0), vertex(2.0, 0.0), vertex(0.0, 2.0)],
+            [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+        ) else {
+            unreachable!()
+        };
+        assert_eq!((derivatives_x, derivatives_y), ([0.5, 0.0], [0.0, 0.5]));
+
+        let mut sampler = SamplerState {
+            min_filter: Filter::Linear,
+            mipmap_mode: SamplerMipmapMode::Linear,
+            mip_lod_bias: 0.5,
+            max_lod: 2.0,
+            ..Default::default()
+        };
+        let lod = compute_lod(&sampler, levels[0].extent, derivatives_x, derivatives_y);
+        assert!((lod - 1.5).abs() < 1e-6, "{lod}");
+
+        let texel = sample_lod(
+            &memory,
+            &levels,
+            Format::R8G8B8A8Unorm,
+            &sampler,
+            0.3,
+            0.6,
+            lod,
+        );
+        assert_texel_eq(texel, [0.0, 0.5, 0.5, 1.0]);
+
+        sampler.mipmap_mode = SamplerMipmapMode::Nearest;
+        let texel = sample_lod(
+            &memory,
+            &levels,
+            Format::R8G8B8A8Unorm,
+            &sampler,
+            0.3,
+            0.6,
+            lod,
+        );
+        assert_texel_eq(texel, [0.0, 1.0, 0.0, 1.0]);
+    }
+}
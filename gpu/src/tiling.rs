@@ -0,0 +1,236 @@
+use crate::{Memory, RenderArea, RenderTarget};
+use common::math::{Extent2, Offset2};
+
+/// Width and height of tiles framebuffer is split into by tiled rasterizer.
+pub const TILE_SIZE: u32 = 32;
+
+/// Rasterizer turning primitives of draws into fragments and running per-fragment operations on
+/// them. Both produce the same results.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum RasterizerBackend {
+    /// Rasterizes all primitives and processes their fragments on calling thread.
+    #[default]
+    Simple,
+    /// Bins primitives into tiles of framebuffer, rasterizing and processing fragments of each
+    /// tile on a pool of threads.
+    Tiled,
+}
+
+/// Samples of render targets read and written by per-fragment operations.
+pub trait SampleStore {
+    fn sample(&self, rt: &RenderTarget, x: u32, y: u32, sample: u32) -> &[u8];
+
+    fn set_sample(&mut self, rt: &RenderTarget, x: u32, y: u32, sample: u32, bytes: &[u8]);
+}
+
+impl SampleStore for Memory {
+    fn sample(&self, rt: &RenderTarget, x: u32, y: u32, sample: u32) -> &[u8] {
+        let bytes_per_pixel = rt.format.info().bytes_per_pixel as u64;
+        self.read_bytes(
+            &rt.image.binding,
+            rt.sample_offset(x, y, sample),
+            bytes_per_pixel,
+        )
+    }
+
+    fn set_sample(&mut self, rt: &RenderTarget, x: u32, y: u32, sample: u32, bytes: &[u8]) {
+        self.write_bytes(bytes, &rt.image.binding, rt.sample_offset(x, y, sample));
+    }
+}
+
+/// Samples of render targets within tile, copied out of memory so that tiles are processed in
+/// parallel without sharing it.
+pub struct TileSamples {
+    area: RenderArea,
+    render_targets: Vec<(RenderTarget, Vec<u8>)>,
+}
+
+impl TileSamples {
+    pub fn new<'a>(
+        memory: &Memory,
+        area: RenderArea,
+        render_targets: impl IntoIterator<Item = &'a RenderTarget>,
+    ) -> Self {
+        let render_targets = render_targets
+            .into_iter()
+            .map(|rt| {
+                let row_size = Self::row_size(rt, area);
+                let mut samples = Vec::with_capacity(row_size * area.extent.height as usize);
+                for y in Self::rows(area) {
+                    let offset = rt.sample_offset(area.offset.x as u32, y, 0);
+                    samples.extend_from_slice(memory.read_bytes(
+                        &rt.image.binding,
+                        offset,
+                        row_size as u64,
+                    ));
+                }
+                (rt.clone(), samples)
+            })
+            .collect();
+        Self {
+            area,
+            render_targets,
+        }
+    }
+
+    /// Writes samples back to render targets in memory.
+    pub fn store(&self, memory: &mut Memory) {
+        for (rt, samples) in &self.render_targets {
+            let row_size = Self::row_size(rt, self.area);
+            for (y, row) in Self::rows(self.area).zip(samples.chunks_exact(row_size)) {
+                let offset = rt.sample_offset(self.area.offset.x as u32, y, 0);
+                memory.write_bytes(row, &rt.image.binding, offset);
+            }
+        }
+    }
+
+    const fn rows(area: RenderArea) -> std::ops::Range<u32> {
+        area.offset.y as u32..area.offset.y as u32 + area.extent.height
+    }
+
+    const fn row_size(rt: &RenderTarget, area: RenderArea) -> usize {
+        (area.extent.width * rt.samples * rt.format.info().bytes_per_pixel as u32) as usize
+    }
+
+    /// Returns range of sample bytes within samples of tile.
+    fn sample_range(
+        &self,
+        rt: &RenderTarget,
+        x: u32,
+        y: u32,
+        sample: u32,
+    ) -> std::ops::Range<usize> {
+        assert!(self.area.contains(x as f32 + 0.5f32, y as f32 + 0.5f32));
+        let x = (x - self.area.offset.x as u32) as usize;
+        let y = (y - self.area.offset.y as u32) as usize;
+        let pixel = y * self.area.extent.width as usize + x;
+        let sample = pixel * rt.samples as usize + sample as usize;
+        let bytes_per_pixel = rt.format.info().bytes_per_pixel as usize;
+        sample * bytes_per_pixel..(sample + 1) * bytes_per_pixel
+    }
+}
+
+impl SampleStore for TileSamples {
+    fn sample(&self, rt: &RenderTarget, x: u32, y: u32, sample: u32) -> &[u8] {
+        let range = self.sample_range(rt, x, y, sample);
+        let Some((_, samples)) = self
+            .render_targets
+            .iter()
+            .find(|(target, _)| target.index == rt.index)
+        else {
+            unreachable!()
+        };
+        &samples[range]
+    }
+
+    fn set_sample(&mut self, rt: &RenderTarget, x: u32, y: u32, sample: u32, bytes: &[u8]) {
+        let range = self.sample_range(rt, x, y, sample);
+        let Some((_, samples)) = self
+            .render_targets
+            .iter_mut()
+            .find(|(target, _)| target.index == rt.index)
+        else {
+            unreachable!()
+        };
+        samples[range].copy_from_slice(bytes);
+    }
+}
+
+/// Splits `area` into tiles aligned to multiples of `TILE_SIZE`, row by row.
+pub fn tiles(area: RenderArea) -> Vec<RenderArea> {
+    let ([x0, x1], [y0, y1]) = tile_ranges(area, area);
+    (y0..y1)
+        .flat_map(|row| (x0..x1).map(move |column| (column, row)))
+        .map(|(column, row)| tile(column, row).intersection(&area))
+        .collect()
+}
+
+/// Returns indices into `tiles(area)` of tiles overlapping `bounds`.
+pub fn overlapping_tiles(area: RenderArea, bounds: RenderArea) -> impl Iterator<Item = usize> {
+    let ([area_x0, area_x1], [area_y0, _]) = tile_ranges(area, area);
+    let ([x0, x1], [y0, y1]) = tile_ranges(area, bounds);
+    let columns = area_x1 - area_x0;
+    (y0..y1).flat_map(move |row| {
+        (x0..x1).map(move |column| ((row - area_y0) * columns + column - area_x0) as usize)
+    })
+}
+
+/// Returns ranges of columns and rows of tiles overlapping `bounds` within `area`.
+fn tile_ranges(area: RenderArea, bounds: RenderArea) -> ([u32; 2], [u32; 2]) {
+    let bounds = bounds.intersection(&area);
+    if bounds.extent.width == 0 || bounds.extent.height == 0 {
+        return ([0, 0], [0, 0]);
+    }
+    let range = |offset: i32, size: u32| {
+        let offset = offset as u32;
+        [offset / TILE_SIZE, (offset + size).div_ceil(TILE_SIZE)]
+    };
+    (
+        range(bounds.offset.x, bounds.extent.width),
+        range(bounds.offset.y, bounds.extent.height),
+    )
+}
+
+const fn tile(column: u32, row: u32) -> RenderArea {
+    RenderArea {
+        extent: Extent2 {
+            width: TILE_SIZE,
+            height: TILE_SIZE,
+        },
+        offset: Offset2 {
+            x: (column * TILE_SIZE) as i32,
+            y: (row * TILE_SIZE) as i32,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_area(x: i32, y: i32, width: u32, height: u32) -> RenderArea {
+        RenderArea {
+            extent: Extent2 { width, height },
+            offset: Offset2 { x, y },
+        }
+    }
+
+    #[test]
+    fn tiles_cover_area() {
+        let area = render_area(16, 0, 64, 40);
+        let tiles = tiles(area)
+            .iter()
+            .map(|tile| {
+                (
+                    tile.offset.x,
+                    tile.offset.y,
+                    tile.extent.width,
+                    tile.extent.height,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tiles,
+            [
+                (16, 0, 16, 32),
+                (32, 0, 32, 32),
+                (64, 0, 16, 32),
+                (16, 32, 16, 8),
+                (32, 32, 32, 8),
+                (64, 32, 16, 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_tiles_index_tiles_of_area() {
+        let area = render_area(16, 0, 64, 40);
+        let bounds = render_area(40, 20, 30, 30);
+        assert_eq!(
+            overlapping_tiles(area, bounds).collect::<Vec<_>>(),
+            [1, 2, 4, 5]
+        );
+        let outside = render_area(100, 0, 10, 10);
+        assert_eq!(overlapping_tiles(area, outside).count(), 0);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::Viewport;
+use common::math::Position;
+
+/// Transforms clip coordinates into framebuffer coordinates with reciprocal of clip `w` in place of
+/// `w`, returning `None` when vertex cannot be mapped because its `w` is zero.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vertexpostproc-viewport
+pub fn transform(clip_position: Position, viewport: &Viewport) -> Option<Position> {
+    let [x, y, z, w] = clip_position.get_as_f32_array();
+    if w == 0.0f32 {
+        return None;
+    }
+
+    // Perspective division.
+    let (x_ndc, y_ndc, z_ndc) = (x / w, y / w, z / w);
+
+    // Negative viewport height flips y axis, which the same equations handle.
+    let (p_x, p_y, p_z) = (
+        viewport.extent.width,
+        viewport.extent.height,
+        viewport.depth.max - viewport.depth.min,
+    );
+    let (o_x, o_y, o_z) = (
+        viewport.offset.x + viewport.extent.width / 2.0f32,
+        viewport.offset.y + viewport.extent.height / 2.0f32,
+        viewport.depth.min,
+    );
+    Some(Position::from_sfloat32_raw(
+        (p_x / 2.0f32).mul_add(x_ndc, o_x),
+        (p_y / 2.0f32).mul_add(y_ndc, o_y),
+        p_z.mul_add(z_ndc, o_z),
+        1.0f32 / w,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::math::{Extent2, Offset2, Range2};
+
+    fn viewport(y: f32, height: f32) -> Viewport {
+        Viewport {
+            offset: Offset2 { x: 0.0, y },
+            extent: Extent2 {
+                width: 800.0,
+                height,
+            },
+            depth: Range2 {
+                min: 0.25,
+                max: 0.75,
+            },
+        }
+    }
+
+    fn transform_xyz(clip_position: [f32; 4], viewport: &Viewport) -> Option<[f32; 3]> {
+        let [x, y, z, w] = clip_position;
+        let position = transform(Position::from_sfloat32_raw(x, y, z, w), viewport)?;
+        let [x, y, z, _] = position.get_as_f32_array();
+        Some([x, y, z])
+    }
+
+    #[test]
+    fn viewport_transform_corners() {
+        let viewport = viewport(0.0, 600.0);
+        assert_eq!(
+            transform_xyz([-1.0, -1.0, 0.0, 1.0], &viewport),
+            Some([0.0, 0.0, 0.25])
+        );
+        assert_eq!(
+            transform_xyz([1.0, 1.0, 1.0, 1.0], &viewport),
+            Some([800.0, 600.0, 0.75])
+        );
+        // Perspective division happens before mapping.
+        assert_eq!(
+            transform_xyz([1.0, -1.0, 1.0, 2.0], &viewport),
+            Some([600.0, 150.0, 0.5])
+        );
+        assert_eq!(transform_xyz([1.0, 1.0, 1.0, 0.0], &viewport), None);
+    }
+
+    #[test]
+    fn viewport_transform_negative_height() {
+        let viewport = viewport(600.0, -600.0);
+        assert_eq!(
+            transform_xyz([-1.0, -1.0, 0.0, 1.0], &viewport),
+            Some([0.0, 600.0, 0.25])
+        );
+        assert_eq!(
+            transform_xyz([1.0, 1.0, 0.0, 1.0], &viewport),
+            Some([800.0, 0.0, 0.25])
+        );
+    }
+}
@@ -20,9 +20,10 @@ use xcb;
 pub struct VkDispatchableHandle(pub Option<NonNull<VkDispatchableHandleInner>>);
 
 impl std::hash::Hash for VkDispatchableHandle {
+    /// Hashes address of handle without dereferencing it, so that looking up handles that were
+    /// already destroyed is sound.
     fn hash<H: Hasher>(&self, state: &mut H) {
-        state.write_u64(self.0.map_or(0, |x| unsafe { x.as_ref() }.key));
-        state.finish();
+        state.write_usize(self.0.map_or(0, |x| x.as_ptr().addr()));
     }
 }
 
@@ -61,6 +62,10 @@ pub const VK_NULL_HANDLE: VkNonDispatchableHandle = VkNonDispatchableHandle(None
 pub type xcb_connection_t = xcb::ffi::xcb_connection_t;
 pub type xcb_window_t = u32;
 
+/// Opaque libwayland-client objects, only ever used behind pointers.
+pub enum wl_display {}
+pub enum wl_surface {}
+
 // TODO: Smarter handling of unsupported FFI types.
 pub(crate) type VkUnsupportedType = *const std::ffi::c_void;
 
@@ -315,6 +320,70 @@ impl VkFormat {
             | Self(1000156034_u32..=u32::MAX) => unreachable!(),
         }
     }
+
+    /// Returns width and height of blocks texels of compressed formats are stored in, and 1×1
+    /// for uncompressed formats, so that [`Self::bytes_per_pixel`] is size of whole block.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#formats-compatibility
+    pub const fn block_extent(&self) -> (u32, u32) {
+        match *self {
+            Self::VK_FORMAT_BC1_RGB_UNORM_BLOCK
+            | Self::VK_FORMAT_BC1_RGB_SRGB_BLOCK
+            | Self::VK_FORMAT_BC1_RGBA_UNORM_BLOCK
+            | Self::VK_FORMAT_BC1_RGBA_SRGB_BLOCK
+            | Self::VK_FORMAT_BC2_UNORM_BLOCK
+            | Self::VK_FORMAT_BC2_SRGB_BLOCK
+            | Self::VK_FORMAT_BC3_UNORM_BLOCK
+            | Self::VK_FORMAT_BC3_SRGB_BLOCK
+            | Self::VK_FORMAT_BC4_UNORM_BLOCK
+            | Self::VK_FORMAT_BC4_SNORM_BLOCK
+            | Self::VK_FORMAT_BC5_UNORM_BLOCK
+            | Self::VK_FORMAT_BC5_SNORM_BLOCK
+            | Self::VK_FORMAT_BC6H_UFLOAT_BLOCK
+            | Self::VK_FORMAT_BC6H_SFLOAT_BLOCK
+            | Self::VK_FORMAT_BC7_UNORM_BLOCK
+            | Self::VK_FORMAT_BC7_SRGB_BLOCK
+            | Self::VK_FORMAT_ETC2_R8G8B8_UNORM_BLOCK
+            | Self::VK_FORMAT_ETC2_R8G8B8_SRGB_BLOCK
+            | Self::VK_FORMAT_ETC2_R8G8B8A1_UNORM_BLOCK
+            | Self::VK_FORMAT_ETC2_R8G8B8A1_SRGB_BLOCK
+            | Self::VK_FORMAT_ETC2_R8G8B8A8_UNORM_BLOCK
+            | Self::VK_FORMAT_ETC2_R8G8B8A8_SRGB_BLOCK
+            | Self::VK_FORMAT_EAC_R11_UNORM_BLOCK
+            | Self::VK_FORMAT_EAC_R11_SNORM_BLOCK
+            | Self::VK_FORMAT_EAC_R11G11_UNORM_BLOCK
+            | Self::VK_FORMAT_EAC_R11G11_SNORM_BLOCK
+            | Self::VK_FORMAT_ASTC_4x4_UNORM_BLOCK
+            | Self::VK_FORMAT_ASTC_4x4_SRGB_BLOCK
+            | Self::VK_FORMAT_PVRTC1_4BPP_UNORM_BLOCK_IMG
+            | Self::VK_FORMAT_PVRTC1_4BPP_SRGB_BLOCK_IMG
+            | Self::VK_FORMAT_PVRTC2_4BPP_UNORM_BLOCK_IMG
+            | Self::VK_FORMAT_PVRTC2_4BPP_SRGB_BLOCK_IMG => (4, 4),
+            Self::VK_FORMAT_ASTC_5x4_UNORM_BLOCK | Self::VK_FORMAT_ASTC_5x4_SRGB_BLOCK => (5, 4),
+            Self::VK_FORMAT_ASTC_5x5_UNORM_BLOCK | Self::VK_FORMAT_ASTC_5x5_SRGB_BLOCK => (5, 5),
+            Self::VK_FORMAT_ASTC_6x5_UNORM_BLOCK | Self::VK_FORMAT_ASTC_6x5_SRGB_BLOCK => (6, 5),
+            Self::VK_FORMAT_ASTC_6x6_UNORM_BLOCK | Self::VK_FORMAT_ASTC_6x6_SRGB_BLOCK => (6, 6),
+            Self::VK_FORMAT_ASTC_8x5_UNORM_BLOCK | Self::VK_FORMAT_ASTC_8x5_SRGB_BLOCK => (8, 5),
+            Self::VK_FORMAT_ASTC_8x6_UNORM_BLOCK | Self::VK_FORMAT_ASTC_8x6_SRGB_BLOCK => (8, 6),
+            Self::VK_FORMAT_ASTC_8x8_UNORM_BLOCK | Self::VK_FORMAT_ASTC_8x8_SRGB_BLOCK => (8, 8),
+            Self::VK_FORMAT_ASTC_10x5_UNORM_BLOCK | Self::VK_FORMAT_ASTC_10x5_SRGB_BLOCK => (10, 5),
+            Self::VK_FORMAT_ASTC_10x6_UNORM_BLOCK | Self::VK_FORMAT_ASTC_10x6_SRGB_BLOCK => (10, 6),
+            Self::VK_FORMAT_ASTC_10x8_UNORM_BLOCK | Self::VK_FORMAT_ASTC_10x8_SRGB_BLOCK => (10, 8),
+            Self::VK_FORMAT_ASTC_10x10_UNORM_BLOCK | Self::VK_FORMAT_ASTC_10x10_SRGB_BLOCK => {
+                (10, 10)
+            }
+            Self::VK_FORMAT_ASTC_12x10_UNORM_BLOCK | Self::VK_FORMAT_ASTC_12x10_SRGB_BLOCK => {
+                (12, 10)
+            }
+            Self::VK_FORMAT_ASTC_12x12_UNORM_BLOCK | Self::VK_FORMAT_ASTC_12x12_SRGB_BLOCK => {
+                (12, 12)
+            }
+            Self::VK_FORMAT_PVRTC1_2BPP_UNORM_BLOCK_IMG
+            | Self::VK_FORMAT_PVRTC1_2BPP_SRGB_BLOCK_IMG
+            | Self::VK_FORMAT_PVRTC2_2BPP_UNORM_BLOCK_IMG
+            | Self::VK_FORMAT_PVRTC2_2BPP_SRGB_BLOCK_IMG => (8, 4),
+            _ => (1, 1),
+        }
+    }
 }
 
 impl From<VkFormat> for common::math::Format {
@@ -330,18 +399,18 @@ impl From<VkFormat> for common::math::Format {
             VkFormat::VK_FORMAT_B5G5R5A1_UNORM_PACK16 => unimplemented!(),
             VkFormat::VK_FORMAT_A1R5G5B5_UNORM_PACK16 => unimplemented!(),
             VkFormat::VK_FORMAT_R8_UNORM => Self::R8Unorm,
-            VkFormat::VK_FORMAT_R8_SNORM => unimplemented!(),
+            VkFormat::VK_FORMAT_R8_SNORM => Self::R8Snorm,
             VkFormat::VK_FORMAT_R8_USCALED => unimplemented!(),
             VkFormat::VK_FORMAT_R8_SSCALED => unimplemented!(),
-            VkFormat::VK_FORMAT_R8_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R8_SINT => unimplemented!(),
+            VkFormat::VK_FORMAT_R8_UINT => Self::R8Uint,
+            VkFormat::VK_FORMAT_R8_SINT => Self::R8Sint,
             VkFormat::VK_FORMAT_R8_SRGB => unimplemented!(),
             VkFormat::VK_FORMAT_R8G8_UNORM => Self::R8G8Unorm,
-            VkFormat::VK_FORMAT_R8G8_SNORM => unimplemented!(),
+            VkFormat::VK_FORMAT_R8G8_SNORM => Self::R8G8Snorm,
             VkFormat::VK_FORMAT_R8G8_USCALED => unimplemented!(),
             VkFormat::VK_FORMAT_R8G8_SSCALED => unimplemented!(),
-            VkFormat::VK_FORMAT_R8G8_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R8G8_SINT => unimplemented!(),
+            VkFormat::VK_FORMAT_R8G8_UINT => Self::R8G8Uint,
+            VkFormat::VK_FORMAT_R8G8_SINT => Self::R8G8Sint,
             VkFormat::VK_FORMAT_R8G8_SRGB => unimplemented!(),
             VkFormat::VK_FORMAT_R8G8B8_UNORM => unimplemented!(),
             VkFormat::VK_FORMAT_R8G8B8_SNORM => unimplemented!(),
@@ -358,19 +427,19 @@ impl From<VkFormat> for common::math::Format {
             VkFormat::VK_FORMAT_B8G8R8_SINT => unimplemented!(),
             VkFormat::VK_FORMAT_B8G8R8_SRGB => unimplemented!(),
             VkFormat::VK_FORMAT_R8G8B8A8_UNORM => Self::R8G8B8A8Unorm,
-            VkFormat::VK_FORMAT_R8G8B8A8_SNORM => unimplemented!(),
+            VkFormat::VK_FORMAT_R8G8B8A8_SNORM => Self::R8G8B8A8Snorm,
             VkFormat::VK_FORMAT_R8G8B8A8_USCALED => unimplemented!(),
             VkFormat::VK_FORMAT_R8G8B8A8_SSCALED => unimplemented!(),
-            VkFormat::VK_FORMAT_R8G8B8A8_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R8G8B8A8_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R8G8B8A8_SRGB => unimplemented!(),
-            VkFormat::VK_FORMAT_B8G8R8A8_UNORM => unimplemented!(),
+            VkFormat::VK_FORMAT_R8G8B8A8_UINT => Self::R8G8B8A8Uint,
+            VkFormat::VK_FORMAT_R8G8B8A8_SINT => Self::R8G8B8A8Sint,
+            VkFormat::VK_FORMAT_R8G8B8A8_SRGB => Self::R8G8B8A8Srgb,
+            VkFormat::VK_FORMAT_B8G8R8A8_UNORM => Self::B8G8R8A8Unorm,
             VkFormat::VK_FORMAT_B8G8R8A8_SNORM => unimplemented!(),
             VkFormat::VK_FORMAT_B8G8R8A8_USCALED => unimplemented!(),
             VkFormat::VK_FORMAT_B8G8R8A8_SSCALED => unimplemented!(),
             VkFormat::VK_FORMAT_B8G8R8A8_UINT => unimplemented!(),
             VkFormat::VK_FORMAT_B8G8R8A8_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_B8G8R8A8_SRGB => unimplemented!(),
+            VkFormat::VK_FORMAT_B8G8R8A8_SRGB => Self::B8G8R8A8Srgb,
             VkFormat::VK_FORMAT_A8B8G8R8_UNORM_PACK32 => unimplemented!(),
             VkFormat::VK_FORMAT_A8B8G8R8_SNORM_PACK32 => unimplemented!(),
             VkFormat::VK_FORMAT_A8B8G8R8_USCALED_PACK32 => unimplemented!(),
@@ -388,22 +457,22 @@ impl From<VkFormat> for common::math::Format {
             VkFormat::VK_FORMAT_A2B10G10R10_SNORM_PACK32 => unimplemented!(),
             VkFormat::VK_FORMAT_A2B10G10R10_USCALED_PACK32 => unimplemented!(),
             VkFormat::VK_FORMAT_A2B10G10R10_SSCALED_PACK32 => unimplemented!(),
-            VkFormat::VK_FORMAT_A2B10G10R10_UINT_PACK32 => unimplemented!(),
+            VkFormat::VK_FORMAT_A2B10G10R10_UINT_PACK32 => Self::A2b10g10r10UintPack32,
             VkFormat::VK_FORMAT_A2B10G10R10_SINT_PACK32 => unimplemented!(),
-            VkFormat::VK_FORMAT_R16_UNORM => unimplemented!(),
-            VkFormat::VK_FORMAT_R16_SNORM => unimplemented!(),
+            VkFormat::VK_FORMAT_R16_UNORM => Self::R16Unorm,
+            VkFormat::VK_FORMAT_R16_SNORM => Self::R16Snorm,
             VkFormat::VK_FORMAT_R16_USCALED => unimplemented!(),
             VkFormat::VK_FORMAT_R16_SSCALED => unimplemented!(),
-            VkFormat::VK_FORMAT_R16_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R16_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R16_SFLOAT => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16_UNORM => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16_SNORM => unimplemented!(),
+            VkFormat::VK_FORMAT_R16_UINT => Self::R16Uint,
+            VkFormat::VK_FORMAT_R16_SINT => Self::R16Sint,
+            VkFormat::VK_FORMAT_R16_SFLOAT => Self::R16Sfloat,
+            VkFormat::VK_FORMAT_R16G16_UNORM => Self::R16G16Unorm,
+            VkFormat::VK_FORMAT_R16G16_SNORM => Self::R16G16Snorm,
             VkFormat::VK_FORMAT_R16G16_USCALED => unimplemented!(),
             VkFormat::VK_FORMAT_R16G16_SSCALED => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16_SFLOAT => unimplemented!(),
+            VkFormat::VK_FORMAT_R16G16_UINT => Self::R16G16Uint,
+            VkFormat::VK_FORMAT_R16G16_SINT => Self::R16G16Sint,
+            VkFormat::VK_FORMAT_R16G16_SFLOAT => Self::R16G16Sfloat,
             VkFormat::VK_FORMAT_R16G16B16_UNORM => unimplemented!(),
             VkFormat::VK_FORMAT_R16G16B16_SNORM => unimplemented!(),
             VkFormat::VK_FORMAT_R16G16B16_USCALED => unimplemented!(),
@@ -411,24 +480,24 @@ impl From<VkFormat> for common::math::Format {
             VkFormat::VK_FORMAT_R16G16B16_UINT => unimplemented!(),
             VkFormat::VK_FORMAT_R16G16B16_SINT => unimplemented!(),
             VkFormat::VK_FORMAT_R16G16B16_SFLOAT => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16B16A16_UNORM => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16B16A16_SNORM => unimplemented!(),
+            VkFormat::VK_FORMAT_R16G16B16A16_UNORM => Self::R16G16B16A16Unorm,
+            VkFormat::VK_FORMAT_R16G16B16A16_SNORM => Self::R16G16B16A16Snorm,
             VkFormat::VK_FORMAT_R16G16B16A16_USCALED => unimplemented!(),
             VkFormat::VK_FORMAT_R16G16B16A16_SSCALED => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16B16A16_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16B16A16_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R16G16B16A16_SFLOAT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32_SFLOAT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32_SFLOAT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32B32_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32B32_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32B32_SFLOAT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32B32A32_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32B32A32_SINT => unimplemented!(),
+            VkFormat::VK_FORMAT_R16G16B16A16_UINT => Self::R16G16B16A16Uint,
+            VkFormat::VK_FORMAT_R16G16B16A16_SINT => Self::R16G16B16A16Sint,
+            VkFormat::VK_FORMAT_R16G16B16A16_SFLOAT => Self::R16G16B16A16Sfloat,
+            VkFormat::VK_FORMAT_R32_UINT => Self::R32Uint,
+            VkFormat::VK_FORMAT_R32_SINT => Self::R32Sint,
+            VkFormat::VK_FORMAT_R32_SFLOAT => Self::R32Sfloat,
+            VkFormat::VK_FORMAT_R32G32_UINT => Self::R32G32Uint,
+            VkFormat::VK_FORMAT_R32G32_SINT => Self::R32G32Sint,
+            VkFormat::VK_FORMAT_R32G32_SFLOAT => Self::R32G32Sfloat,
+            VkFormat::VK_FORMAT_R32G32B32_UINT => Self::R32G32B32Uint,
+            VkFormat::VK_FORMAT_R32G32B32_SINT => Self::R32G32B32Sint,
+            VkFormat::VK_FORMAT_R32G32B32_SFLOAT => Self::R32G32B32Sfloat,
+            VkFormat::VK_FORMAT_R32G32B32A32_UINT => Self::R32G32B32A32Uint,
+            VkFormat::VK_FORMAT_R32G32B32A32_SINT => Self::R32G32B32A32Sint,
             VkFormat::VK_FORMAT_R32G32B32A32_SFLOAT => Self::R32G32B32A32Sfloat,
             VkFormat::VK_FORMAT_R64_UINT => unimplemented!(),
             VkFormat::VK_FORMAT_R64_SINT => unimplemented!(),
@@ -442,23 +511,23 @@ impl From<VkFormat> for common::math::Format {
             VkFormat::VK_FORMAT_R64G64B64A64_UINT => unimplemented!(),
             VkFormat::VK_FORMAT_R64G64B64A64_SINT => unimplemented!(),
             VkFormat::VK_FORMAT_R64G64B64A64_SFLOAT => unimplemented!(),
-            VkFormat::VK_FORMAT_B10G11R11_UFLOAT_PACK32 => unimplemented!(),
+            VkFormat::VK_FORMAT_B10G11R11_UFLOAT_PACK32 => Self::B10g11r11UfloatPack32,
             VkFormat::VK_FORMAT_E5B9G9R9_UFLOAT_PACK32 => unimplemented!(),
             VkFormat::VK_FORMAT_D16_UNORM => Self::D16Unorm,
             VkFormat::VK_FORMAT_X8_D24_UNORM_PACK32 => unimplemented!(),
-            VkFormat::VK_FORMAT_D32_SFLOAT => unimplemented!(),
-            VkFormat::VK_FORMAT_S8_UINT => unimplemented!(),
+            VkFormat::VK_FORMAT_D32_SFLOAT => Self::D32Sfloat,
+            VkFormat::VK_FORMAT_S8_UINT => Self::S8Uint,
             VkFormat::VK_FORMAT_D16_UNORM_S8_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_D24_UNORM_S8_UINT => unimplemented!(),
+            VkFormat::VK_FORMAT_D24_UNORM_S8_UINT => Self::D24UnormS8Uint,
             VkFormat::VK_FORMAT_D32_SFLOAT_S8_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_BC1_RGB_UNORM_BLOCK => unimplemented!(),
-            VkFormat::VK_FORMAT_BC1_RGB_SRGB_BLOCK => unimplemented!(),
-            VkFormat::VK_FORMAT_BC1_RGBA_UNORM_BLOCK => unimplemented!(),
-            VkFormat::VK_FORMAT_BC1_RGBA_SRGB_BLOCK => unimplemented!(),
-            VkFormat::VK_FORMAT_BC2_UNORM_BLOCK => unimplemented!(),
-            VkFormat::VK_FORMAT_BC2_SRGB_BLOCK => unimplemented!(),
-            VkFormat::VK_FORMAT_BC3_UNORM_BLOCK => unimplemented!(),
-            VkFormat::VK_FORMAT_BC3_SRGB_BLOCK => unimplemented!(),
+            VkFormat::VK_FORMAT_BC1_RGB_UNORM_BLOCK => Self::Bc1RgbUnormBlock,
+            VkFormat::VK_FORMAT_BC1_RGB_SRGB_BLOCK => Self::Bc1RgbSrgbBlock,
+            VkFormat::VK_FORMAT_BC1_RGBA_UNORM_BLOCK => Self::Bc1RgbaUnormBlock,
+            VkFormat::VK_FORMAT_BC1_RGBA_SRGB_BLOCK => Self::Bc1RgbaSrgbBlock,
+            VkFormat::VK_FORMAT_BC2_UNORM_BLOCK => Self::Bc2UnormBlock,
+            VkFormat::VK_FORMAT_BC2_SRGB_BLOCK => Self::Bc2SrgbBlock,
+            VkFormat::VK_FORMAT_BC3_UNORM_BLOCK => Self::Bc3UnormBlock,
+            VkFormat::VK_FORMAT_BC3_SRGB_BLOCK => Self::Bc3SrgbBlock,
             VkFormat::VK_FORMAT_BC4_UNORM_BLOCK => unimplemented!(),
             VkFormat::VK_FORMAT_BC4_SNORM_BLOCK => unimplemented!(),
             VkFormat::VK_FORMAT_BC5_UNORM_BLOCK => unimplemented!(),
@@ -556,17 +625,118 @@ impl From<VkFormat> for common::math::Format {
 
 impl From<VkClearValue> for common::math::Color {
     fn from(value: VkClearValue) -> Self {
+        unsafe { (*value.color).into() }
+    }
+}
+
+impl From<VkClearColorValue> for common::math::Color {
+    fn from(value: VkClearColorValue) -> Self {
         unsafe {
             Self::from_raw(
-                value.color.uint32[0] as u64,
-                value.color.uint32[1] as u64,
-                value.color.uint32[2] as u64,
-                value.color.uint32[3] as u64,
+                value.uint32[0] as u64,
+                value.uint32[1] as u64,
+                value.uint32[2] as u64,
+                value.uint32[3] as u64,
             )
         }
     }
 }
 
+impl From<VkCompareOp> for common::graphics::CompareOp {
+    fn from(value: VkCompareOp) -> Self {
+        match value {
+            VkCompareOp::VK_COMPARE_OP_NEVER => Self::Never,
+            VkCompareOp::VK_COMPARE_OP_LESS => Self::Less,
+            VkCompareOp::VK_COMPARE_OP_EQUAL => Self::Equal,
+            VkCompareOp::VK_COMPARE_OP_LESS_OR_EQUAL => Self::LessOrEqual,
+            VkCompareOp::VK_COMPARE_OP_GREATER => Self::Greater,
+            VkCompareOp::VK_COMPARE_OP_NOT_EQUAL => Self::NotEqual,
+            VkCompareOp::VK_COMPARE_OP_GREATER_OR_EQUAL => Self::GreaterOrEqual,
+            VkCompareOp::VK_COMPARE_OP_ALWAYS => Self::Always,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkStencilOp> for common::graphics::StencilOp {
+    fn from(value: VkStencilOp) -> Self {
+        match value {
+            VkStencilOp::VK_STENCIL_OP_KEEP => Self::Keep,
+            VkStencilOp::VK_STENCIL_OP_ZERO => Self::Zero,
+            VkStencilOp::VK_STENCIL_OP_REPLACE => Self::Replace,
+            VkStencilOp::VK_STENCIL_OP_INCREMENT_AND_CLAMP => Self::IncrementAndClamp,
+            VkStencilOp::VK_STENCIL_OP_DECREMENT_AND_CLAMP => Self::DecrementAndClamp,
+            VkStencilOp::VK_STENCIL_OP_INVERT => Self::Invert,
+            VkStencilOp::VK_STENCIL_OP_INCREMENT_AND_WRAP => Self::IncrementAndWrap,
+            VkStencilOp::VK_STENCIL_OP_DECREMENT_AND_WRAP => Self::DecrementAndWrap,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkFilter> for common::graphics::Filter {
+    fn from(value: VkFilter) -> Self {
+        match value {
+            VkFilter::VK_FILTER_NEAREST => Self::Nearest,
+            VkFilter::VK_FILTER_LINEAR => Self::Linear,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkSamplerMipmapMode> for common::graphics::SamplerMipmapMode {
+    fn from(value: VkSamplerMipmapMode) -> Self {
+        match value {
+            VkSamplerMipmapMode::VK_SAMPLER_MIPMAP_MODE_NEAREST => Self::Nearest,
+            VkSamplerMipmapMode::VK_SAMPLER_MIPMAP_MODE_LINEAR => Self::Linear,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkSamplerAddressMode> for common::graphics::SamplerAddressMode {
+    fn from(value: VkSamplerAddressMode) -> Self {
+        match value {
+            VkSamplerAddressMode::VK_SAMPLER_ADDRESS_MODE_REPEAT => Self::Repeat,
+            VkSamplerAddressMode::VK_SAMPLER_ADDRESS_MODE_MIRRORED_REPEAT => Self::MirroredRepeat,
+            VkSamplerAddressMode::VK_SAMPLER_ADDRESS_MODE_CLAMP_TO_EDGE => Self::ClampToEdge,
+            VkSamplerAddressMode::VK_SAMPLER_ADDRESS_MODE_CLAMP_TO_BORDER => Self::ClampToBorder,
+            VkSamplerAddressMode::VK_SAMPLER_ADDRESS_MODE_MIRROR_CLAMP_TO_EDGE => {
+                Self::MirrorClampToEdge
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkBorderColor> for common::graphics::BorderColor {
+    fn from(value: VkBorderColor) -> Self {
+        match value {
+            VkBorderColor::VK_BORDER_COLOR_FLOAT_TRANSPARENT_BLACK
+            | VkBorderColor::VK_BORDER_COLOR_INT_TRANSPARENT_BLACK => Self::TransparentBlack,
+            VkBorderColor::VK_BORDER_COLOR_FLOAT_OPAQUE_BLACK
+            | VkBorderColor::VK_BORDER_COLOR_INT_OPAQUE_BLACK => Self::OpaqueBlack,
+            VkBorderColor::VK_BORDER_COLOR_FLOAT_OPAQUE_WHITE
+            | VkBorderColor::VK_BORDER_COLOR_INT_OPAQUE_WHITE => Self::OpaqueWhite,
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl From<VkStencilOpState> for common::graphics::StencilOpState {
+    fn from(value: VkStencilOpState) -> Self {
+        Self {
+            fail_op: value.failOp.into(),
+            pass_op: value.passOp.into(),
+            depth_fail_op: value.depthFailOp.into(),
+            compare_op: value.compareOp.into(),
+            compare_mask: value.compareMask,
+            write_mask: value.writeMask,
+            reference: value.reference,
+        }
+    }
+}
+
 impl From<VkPolygonMode> for common::graphics::PolygonMode {
     fn from(value: VkPolygonMode) -> Self {
         match value {
@@ -611,6 +781,108 @@ impl From<VkFrontFace> for common::graphics::FrontFace {
     }
 }
 
+impl From<VkBlendFactor> for common::graphics::BlendFactor {
+    fn from(value: VkBlendFactor) -> Self {
+        match value {
+            VkBlendFactor::VK_BLEND_FACTOR_ZERO => Self::Zero,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE => Self::One,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC_COLOR => Self::SrcColor,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_SRC_COLOR => Self::OneMinusSrcColor,
+            VkBlendFactor::VK_BLEND_FACTOR_DST_COLOR => Self::DstColor,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_DST_COLOR => Self::OneMinusDstColor,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC_ALPHA => Self::SrcAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_SRC_ALPHA => Self::OneMinusSrcAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_DST_ALPHA => Self::DstAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_DST_ALPHA => Self::OneMinusDstAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_CONSTANT_COLOR => Self::ConstantColor,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_CONSTANT_COLOR => Self::OneMinusConstantColor,
+            VkBlendFactor::VK_BLEND_FACTOR_CONSTANT_ALPHA => Self::ConstantAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_CONSTANT_ALPHA => Self::OneMinusConstantAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC_ALPHA_SATURATE => Self::SrcAlphaSaturate,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC1_COLOR => Self::Src1Color,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_SRC1_COLOR => Self::OneMinusSrc1Color,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC1_ALPHA => Self::Src1Alpha,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_SRC1_ALPHA => Self::OneMinusSrc1Alpha,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkBlendOp> for common::graphics::BlendOp {
+    fn from(value: VkBlendOp) -> Self {
+        match value {
+            VkBlendOp::VK_BLEND_OP_ADD => Self::Add,
+            VkBlendOp::VK_BLEND_OP_SUBTRACT => Self::Subtract,
+            VkBlendOp::VK_BLEND_OP_REVERSE_SUBTRACT => Self::ReverseSubtract,
+            VkBlendOp::VK_BLEND_OP_MIN => Self::Min,
+            VkBlendOp::VK_BLEND_OP_MAX => Self::Max,
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl From<VkLogicOp> for common::graphics::LogicOp {
+    fn from(value: VkLogicOp) -> Self {
+        match value {
+            VkLogicOp::VK_LOGIC_OP_CLEAR => Self::Clear,
+            VkLogicOp::VK_LOGIC_OP_AND => Self::And,
+            VkLogicOp::VK_LOGIC_OP_AND_REVERSE => Self::AndReverse,
+            VkLogicOp::VK_LOGIC_OP_COPY => Self::Copy,
+            VkLogicOp::VK_LOGIC_OP_AND_INVERTED => Self::AndInverted,
+            VkLogicOp::VK_LOGIC_OP_NO_OP => Self::NoOp,
+            VkLogicOp::VK_LOGIC_OP_XOR => Self::Xor,
+            VkLogicOp::VK_LOGIC_OP_OR => Self::Or,
+            VkLogicOp::VK_LOGIC_OP_NOR => Self::Nor,
+            VkLogicOp::VK_LOGIC_OP_EQUIVALENT => Self::Equivalent,
+            VkLogicOp::VK_LOGIC_OP_INVERT => Self::Invert,
+            VkLogicOp::VK_LOGIC_OP_OR_REVERSE => Self::OrReverse,
+            VkLogicOp::VK_LOGIC_OP_COPY_INVERTED => Self::CopyInverted,
+            VkLogicOp::VK_LOGIC_OP_OR_INVERTED => Self::OrInverted,
+            VkLogicOp::VK_LOGIC_OP_NAND => Self::Nand,
+            VkLogicOp::VK_LOGIC_OP_SET => Self::Set,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkFlag<VkColorComponentFlags>> for common::graphics::ColorComponentFlags {
+    fn from(value: VkFlag<VkColorComponentFlags>) -> Self {
+        let has = |bit: VkColorComponentFlagBits| value.0 & bit.0 != 0;
+        Self {
+            r: has(VkColorComponentFlagBits::VK_COLOR_COMPONENT_R_BIT),
+            g: has(VkColorComponentFlagBits::VK_COLOR_COMPONENT_G_BIT),
+            b: has(VkColorComponentFlagBits::VK_COLOR_COMPONENT_B_BIT),
+            a: has(VkColorComponentFlagBits::VK_COLOR_COMPONENT_A_BIT),
+        }
+    }
+}
+
+impl From<VkComponentSwizzle> for common::graphics::ComponentSwizzle {
+    fn from(value: VkComponentSwizzle) -> Self {
+        match value {
+            VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY => Self::Identity,
+            VkComponentSwizzle::VK_COMPONENT_SWIZZLE_ZERO => Self::Zero,
+            VkComponentSwizzle::VK_COMPONENT_SWIZZLE_ONE => Self::One,
+            VkComponentSwizzle::VK_COMPONENT_SWIZZLE_R => Self::R,
+            VkComponentSwizzle::VK_COMPONENT_SWIZZLE_G => Self::G,
+            VkComponentSwizzle::VK_COMPONENT_SWIZZLE_B => Self::B,
+            VkComponentSwizzle::VK_COMPONENT_SWIZZLE_A => Self::A,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkComponentMapping> for common::graphics::ComponentMapping {
+    fn from(value: VkComponentMapping) -> Self {
+        Self {
+            r: value.r.into(),
+            g: value.g.into(),
+            b: value.b.into(),
+            a: value.a.into(),
+        }
+    }
+}
+
 impl VkIndexType {
     pub fn size_in_bytes(&self) -> u8 {
         match *self {
@@ -13,8 +13,9 @@ pub unsafe extern "C" fn vkCreateBuffer(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pBuffer: Option<NonNull<VkBuffer>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -43,8 +44,8 @@ pub unsafe extern "C" fn vkDestroyBuffer(
     buffer: VkBuffer,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -57,12 +58,12 @@ pub unsafe extern "C" fn vkGetBufferMemoryRequirements(
     buffer: VkBuffer,
     pMemoryRequirements: Option<NonNull<VkMemoryRequirements>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
-    let Some(buffer) = Buffer::from_handle(buffer) else {
-        unreachable!()
+    let Ok(buffer) = Buffer::lookup(buffer) else {
+        return;
     };
 
     let Some(pMemoryRequirements) = pMemoryRequirements else {
@@ -78,16 +79,18 @@ pub unsafe extern "C" fn vkBindBufferMemory(
     memory: VkDeviceMemory,
     memoryOffset: VkDeviceSize,
 ) -> VkResult {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
-    };
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
 
-    let Some(buffer) = Buffer::from_handle(buffer) else {
-        unreachable!()
+    let buffer = match Buffer::lookup(buffer) {
+        Ok(buffer) => buffer,
+        Err(err) => return err.to_vk_result(),
     };
 
-    let Some(memory) = MemoryAllocation::from_handle(memory) else {
-        unreachable!()
+    let memory = match MemoryAllocation::lookup(memory) {
+        Ok(memory) => memory,
+        Err(err) => return err.to_vk_result(),
     };
 
     let result = buffer.lock().bind_memory(memory, memoryOffset);
@@ -100,16 +103,18 @@ pub unsafe extern "C" fn vkCreateBufferView(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pView: Option<NonNull<VkBufferView>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
         unreachable!()
     };
     let create_info = pCreateInfo.as_ref();
-    let Some(buffer) = Buffer::from_handle(create_info.buffer) else {
-        unreachable!()
+    let buffer = match Buffer::lookup(create_info.buffer) {
+        Ok(buffer) => buffer,
+        Err(err) => return err.to_vk_result(),
     };
 
     let _ = pAllocator;
@@ -132,8 +137,8 @@ pub unsafe extern "C" fn vkDestroyBufferView(
     bufferView: VkBufferView,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -4,9 +4,9 @@ use headers::vk_decls::*;
 use runtime::buffer::Buffer;
 use runtime::command_buffer::*;
 use runtime::context::{Dispatchable, NonDispatchable};
-use runtime::image::Image;
+use runtime::image::{Image, ImageView};
 use runtime::logical_device::LogicalDevice;
-use runtime::pipeline::{Framebuffer, Pipeline, PipelineLayout, RenderPass};
+use runtime::pipeline::{ComputePipeline, Framebuffer, Pipeline, PipelineLayout, RenderPass};
 
 
 pub unsafe extern "C" fn vkCreateCommandPool(
@@ -15,8 +15,9 @@ pub unsafe extern "C" fn vkCreateCommandPool(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pCommandPool: Option<NonNull<VkCommandPool>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -40,8 +41,8 @@ pub unsafe extern "C" fn vkDestroyCommandPool(
     commandPool: VkCommandPool,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -54,9 +55,9 @@ pub unsafe extern "C" fn vkAllocateCommandBuffers(
     pAllocateInfo: Option<NonNull<VkCommandBufferAllocateInfo>>,
     pCommandBuffers: Option<NonNull<VkCommandBuffer>>,
 ) -> VkResult {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
-    };
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
 
     let Some(pAllocateInfo) = pAllocateInfo else {
         unreachable!()
@@ -68,7 +69,13 @@ pub unsafe extern "C" fn vkAllocateCommandBuffers(
     };
 
     let command_buffer_count = allocate_info.commandBufferCount as usize;
-    let command_buffers = vec![CommandBuffer::create(allocate_info); command_buffer_count].to_vec();
+    let command_buffers = (0..command_buffer_count)
+        .map(|_| CommandBuffer::create(allocate_info))
+        .collect::<Result<Vec<_>, _>>();
+    let command_buffers = match command_buffers {
+        Ok(command_buffers) => command_buffers,
+        Err(err) => return err.to_vk_result(),
+    };
     std::ptr::copy_nonoverlapping(
         command_buffers.as_ptr(),
         pCommandBuffers.as_ptr(),
@@ -84,12 +91,12 @@ pub unsafe extern "C" fn vkFreeCommandBuffers(
     commandBufferCount: u32,
     pCommandBuffers: Option<NonNull<VkCommandBuffer>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
-    let Some(_commandPool) = CommandPool::from_handle(commandPool) else {
-        unreachable!()
+    let Ok(_commandPool) = CommandPool::lookup(commandPool) else {
+        return;
     };
 
     pCommandBuffers
@@ -104,28 +111,69 @@ pub unsafe extern "C" fn vkBeginCommandBuffer(
     commandBuffer: VkCommandBuffer,
     pBeginInfo: Option<NonNull<VkCommandBufferBeginInfo>>,
 ) -> VkResult {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let commandBuffer = match CommandBuffer::lookup(commandBuffer) {
+        Ok(commandBuffer) => commandBuffer,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pBeginInfo) = pBeginInfo else {
         unreachable!()
     };
-    let _ = pBeginInfo.as_ref();
+    let begin_info = pBeginInfo.as_ref();
+
+    let render_pass_continue =
+        VkCommandBufferUsageFlagBits::VK_COMMAND_BUFFER_USAGE_RENDER_PASS_CONTINUE_BIT;
+    let inheritance = match begin_info.pInheritanceInfo {
+        Some(inheritance_info) if begin_info.flags & u32::from(render_pass_continue) != 0 => {
+            let inheritance_info = inheritance_info.as_ref();
+            let render_pass = match RenderPass::lookup(inheritance_info.renderPass) {
+                Ok(render_pass) => render_pass,
+                Err(err) => return err.to_vk_result(),
+            };
+            Some(CommandBufferInheritance {
+                render_pass,
+                subpass: inheritance_info.subpass,
+            })
+        }
+        _ => None,
+    };
+
+    let result = commandBuffer.lock().begin_with_inheritance(inheritance);
+    match result {
+        Ok(()) => VkResult::VK_SUCCESS,
+        Err(err) => err,
+    }
+}
 
-    commandBuffer.lock().begin();
+pub unsafe extern "C" fn vkEndCommandBuffer(commandBuffer: VkCommandBuffer) -> VkResult {
+    let commandBuffer = match CommandBuffer::lookup(commandBuffer) {
+        Ok(commandBuffer) => commandBuffer,
+        Err(err) => return err.to_vk_result(),
+    };
 
-    VkResult::VK_SUCCESS
+    let result = commandBuffer.lock().end();
+    match result {
+        Ok(()) => VkResult::VK_SUCCESS,
+        Err(err) => err,
+    }
 }
 
-pub unsafe extern "C" fn vkEndCommandBuffer(commandBuffer: VkCommandBuffer) -> VkResult {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+pub unsafe extern "C" fn vkResetCommandBuffer(
+    commandBuffer: VkCommandBuffer,
+    flags: VkCommandBufferResetFlags,
+) -> VkResult {
+    let commandBuffer = match CommandBuffer::lookup(commandBuffer) {
+        Ok(commandBuffer) => commandBuffer,
+        Err(err) => return err.to_vk_result(),
     };
 
-    commandBuffer.lock().end();
+    let _ = flags;
 
-    VkResult::VK_SUCCESS
+    let result = commandBuffer.lock().reset();
+    match result {
+        Ok(()) => VkResult::VK_SUCCESS,
+        Err(err) => err,
+    }
 }
 
 pub unsafe extern "C" fn vkCmdPipelineBarrier(
@@ -140,8 +188,8 @@ pub unsafe extern "C" fn vkCmdPipelineBarrier(
     imageMemoryBarrierCount: u32,
     pImageMemoryBarriers: Option<NonNull<VkImageMemoryBarrier>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
     let _ = srcStageMask;
@@ -151,10 +199,36 @@ pub unsafe extern "C" fn vkCmdPipelineBarrier(
     let _ = pMemoryBarriers;
     let _ = bufferMemoryBarrierCount;
     let _ = pBufferMemoryBarriers;
-    let _ = imageMemoryBarrierCount;
-    let _ = pImageMemoryBarriers;
+    let image_memory_barriers = pImageMemoryBarriers.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), imageMemoryBarrierCount as usize)
+    });
+
+    commandBuffer
+        .lock()
+        .cmd_pipeline_barrier(image_memory_barriers);
+}
+
+pub unsafe extern "C" fn vkCmdPipelineBarrier2(
+    commandBuffer: VkCommandBuffer,
+    pDependencyInfo: Option<NonNull<VkDependencyInfo>>,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Some(pDependencyInfo) = pDependencyInfo else {
+        unreachable!()
+    };
+    let dependency_info = pDependencyInfo.as_ref();
+    let image_memory_barriers = dependency_info
+        .pImageMemoryBarriers
+        .map_or(&[] as &[_], |x| {
+            std::slice::from_raw_parts(x.as_ptr(), dependency_info.imageMemoryBarrierCount as usize)
+        });
 
-    commandBuffer.lock().cmd_pipeline_barrier();
+    commandBuffer
+        .lock()
+        .cmd_pipeline_barrier2(image_memory_barriers);
 }
 
 pub unsafe extern "C" fn vkCmdBeginRenderPass(
@@ -162,19 +236,19 @@ pub unsafe extern "C" fn vkCmdBeginRenderPass(
     pRenderPassBegin: Option<NonNull<VkRenderPassBeginInfo>>,
     contents: VkSubpassContents,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
     let Some(pRenderPassBegin) = pRenderPassBegin else {
         unreachable!()
     };
     let render_pass_begin = pRenderPassBegin.as_ref();
-    let Some(render_pass) = RenderPass::from_handle(render_pass_begin.renderPass) else {
-        unreachable!()
+    let Ok(render_pass) = RenderPass::lookup(render_pass_begin.renderPass) else {
+        return;
     };
-    let Some(framebuffer) = Framebuffer::from_handle(render_pass_begin.framebuffer) else {
-        unreachable!()
+    let Ok(framebuffer) = Framebuffer::lookup(render_pass_begin.framebuffer) else {
+        return;
     };
     let clear_values = render_pass_begin.pClearValues.map_or(&[] as &[_], |x| {
         std::slice::from_raw_parts(x.as_ptr(), render_pass_begin.clearValueCount as usize)
@@ -189,25 +263,106 @@ pub unsafe extern "C" fn vkCmdBeginRenderPass(
     );
 }
 
+pub unsafe extern "C" fn vkCmdNextSubpass(
+    commandBuffer: VkCommandBuffer,
+    contents: VkSubpassContents,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    commandBuffer.lock().cmd_next_subpass(contents);
+}
+
 pub unsafe extern "C" fn vkCmdEndRenderPass(commandBuffer: VkCommandBuffer) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
     commandBuffer.lock().cmd_end_render_pass();
 }
 
+pub unsafe extern "C" fn vkCmdBeginRendering(
+    commandBuffer: VkCommandBuffer,
+    pRenderingInfo: Option<NonNull<VkRenderingInfo>>,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Some(pRenderingInfo) = pRenderingInfo else {
+        unreachable!()
+    };
+    let rendering_info = pRenderingInfo.as_ref();
+    // Attachments without image view are not written.
+    let attachment = |info: &VkRenderingAttachmentInfo| {
+        let image_view = ImageView::from_handle(info.imageView)?;
+        let resolve_image_view = if info.resolveMode == VkResolveModeFlagBits::VK_RESOLVE_MODE_NONE
+        {
+            None
+        } else {
+            ImageView::from_handle(info.resolveImageView)
+        };
+        Some(RenderingAttachment {
+            image_view,
+            image_layout: info.imageLayout,
+            resolve_image_view,
+            resolve_image_layout: info.resolveImageLayout,
+            load_op: info.loadOp,
+            store_op: info.storeOp,
+            clear_value: info.clearValue,
+        })
+    };
+    let color_attachments = rendering_info
+        .pColorAttachments
+        .map_or(&[] as &[_], |x| {
+            std::slice::from_raw_parts(x.as_ptr(), rendering_info.colorAttachmentCount as usize)
+        })
+        .iter()
+        .map(attachment)
+        .collect::<Vec<_>>();
+    let depth_attachment = rendering_info
+        .pDepthAttachment
+        .and_then(|x| attachment(x.as_ref()));
+    let stencil_attachment = rendering_info
+        .pStencilAttachment
+        .and_then(|x| attachment(x.as_ref()));
+
+    commandBuffer.lock().cmd_begin_rendering(
+        rendering_info.renderArea,
+        &color_attachments,
+        depth_attachment.as_ref(),
+        stencil_attachment.as_ref(),
+    );
+}
+
+pub unsafe extern "C" fn vkCmdEndRendering(commandBuffer: VkCommandBuffer) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    commandBuffer.lock().cmd_end_rendering();
+}
+
 pub unsafe extern "C" fn vkCmdBindPipeline(
     commandBuffer: VkCommandBuffer,
     pipelineBindPoint: VkPipelineBindPoint,
     pipeline: VkPipeline,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
-    let Some(pipeline) = Pipeline::from_handle(pipeline) else {
-        unreachable!()
+    if pipelineBindPoint == VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_COMPUTE {
+        let Ok(pipeline) = ComputePipeline::lookup(pipeline) else {
+            return;
+        };
+        commandBuffer.lock().cmd_bind_compute_pipeline(pipeline);
+        return;
+    }
+
+    let Ok(pipeline) = Pipeline::lookup(pipeline) else {
+        return;
     };
 
     commandBuffer
@@ -225,12 +380,12 @@ pub unsafe extern "C" fn vkCmdBindDescriptorSets(
     dynamicOffsetCount: u32,
     pDynamicOffsets: Option<NonNull<u32>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
-    let Some(pipeline_layout) = PipelineLayout::from_handle(layout) else {
-        unreachable!()
+    let Ok(pipeline_layout) = PipelineLayout::lookup(layout) else {
+        return;
     };
 
     let descriptor_sets = pDescriptorSets.map_or(&[] as &[_], |x| {
@@ -250,6 +405,34 @@ pub unsafe extern "C" fn vkCmdBindDescriptorSets(
     );
 }
 
+pub unsafe extern "C" fn vkCmdPushDescriptorSetKHR(
+    commandBuffer: VkCommandBuffer,
+    pipelineBindPoint: VkPipelineBindPoint,
+    layout: VkPipelineLayout,
+    set: u32,
+    descriptorWriteCount: u32,
+    pDescriptorWrites: Option<NonNull<VkWriteDescriptorSet>>,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(pipeline_layout) = PipelineLayout::lookup(layout) else {
+        return;
+    };
+
+    let descriptor_writes = pDescriptorWrites.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), descriptorWriteCount as usize)
+    });
+
+    commandBuffer.lock().cmd_push_descriptor_set(
+        pipelineBindPoint,
+        pipeline_layout,
+        set,
+        descriptor_writes,
+    );
+}
+
 pub unsafe extern "C" fn vkCmdPushConstants(
     commandBuffer: VkCommandBuffer,
     layout: VkPipelineLayout,
@@ -258,12 +441,12 @@ pub unsafe extern "C" fn vkCmdPushConstants(
     size: u32,
     pValues: Option<NonNull<std::ffi::c_void>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
-    let Some(pipeline_layout) = PipelineLayout::from_handle(layout) else {
-        unreachable!()
+    let Ok(pipeline_layout) = PipelineLayout::lookup(layout) else {
+        return;
     };
 
     let values = pValues.map_or(&[] as &[_], |x| {
@@ -282,8 +465,8 @@ pub unsafe extern "C" fn vkCmdBindVertexBuffers(
     pBuffers: Option<NonNull<VkBuffer>>,
     pOffsets: Option<NonNull<VkDeviceSize>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
     let buffers = pBuffers.map_or(&[] as &[_], |x| {
@@ -300,8 +483,8 @@ pub unsafe extern "C" fn vkCmdBindVertexBuffers(
         buffers,
         offsets
     ) {
-        let Some(buffer) = Buffer::from_handle(buffer) else {
-            unreachable!()
+        let Ok(buffer) = Buffer::lookup(buffer) else {
+            return;
         };
         command_buffer.cmd_bind_vertex_buffer(binding, buffer, offset);
     }
@@ -313,12 +496,12 @@ pub unsafe extern "C" fn vkCmdBindIndexBuffer(
     offset: VkDeviceSize,
     indexType: VkIndexType,
 ) {
-    let Some(command_buffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(command_buffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
-    let Some(buffer) = Buffer::from_handle(buffer) else {
-        unreachable!()
+    let Ok(buffer) = Buffer::lookup(buffer) else {
+        return;
     };
 
     let index_size = indexType.size_in_bytes();
@@ -334,8 +517,8 @@ pub unsafe extern "C" fn vkCmdSetViewport(
     viewportCount: u32,
     pViewports: Option<NonNull<VkViewport>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
     let viewports = pViewports.map_or(&[] as &[_], |x| {
@@ -353,8 +536,8 @@ pub unsafe extern "C" fn vkCmdSetScissor(
     scissorCount: u32,
     pScissors: Option<NonNull<VkRect2D>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
     let scissors = pScissors.map_or(&[] as &[_], |x| {
@@ -366,6 +549,37 @@ pub unsafe extern "C" fn vkCmdSetScissor(
         .cmd_set_scissors(firstScissor, scissors);
 }
 
+pub unsafe extern "C" fn vkCmdSetDepthBias(
+    commandBuffer: VkCommandBuffer,
+    depthBiasConstantFactor: f32,
+    depthBiasClamp: f32,
+    depthBiasSlopeFactor: f32,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    commandBuffer.lock().cmd_set_depth_bias(
+        depthBiasConstantFactor,
+        depthBiasClamp,
+        depthBiasSlopeFactor,
+    );
+}
+
+pub unsafe extern "C" fn vkCmdSetDepthBounds(
+    commandBuffer: VkCommandBuffer,
+    minDepthBounds: f32,
+    maxDepthBounds: f32,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_set_depth_bounds(minDepthBounds, maxDepthBounds);
+}
+
 pub unsafe extern "C" fn vkCmdDraw(
     commandBuffer: VkCommandBuffer,
     vertexCount: u32,
@@ -373,8 +587,8 @@ pub unsafe extern "C" fn vkCmdDraw(
     firstVertex: u32,
     firstInstance: u32,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
     commandBuffer
@@ -390,8 +604,8 @@ pub unsafe extern "C" fn vkCmdDrawIndexed(
     vertexOffset: i32,
     firstInstance: u32,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
     commandBuffer.lock().cmd_draw_indexed(
@@ -403,6 +617,91 @@ pub unsafe extern "C" fn vkCmdDrawIndexed(
     );
 }
 
+pub unsafe extern "C" fn vkCmdDispatch(
+    commandBuffer: VkCommandBuffer,
+    groupCountX: u32,
+    groupCountY: u32,
+    groupCountZ: u32,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_dispatch(groupCountX, groupCountY, groupCountZ);
+}
+
+pub unsafe extern "C" fn vkCmdBlitImage(
+    commandBuffer: VkCommandBuffer,
+    srcImage: VkImage,
+    srcImageLayout: VkImageLayout,
+    dstImage: VkImage,
+    dstImageLayout: VkImageLayout,
+    regionCount: u32,
+    pRegions: Option<NonNull<VkImageBlit>>,
+    filter: VkFilter,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(srcImage) = Image::lookup(srcImage) else {
+        return;
+    };
+
+    let Ok(dstImage) = Image::lookup(dstImage) else {
+        return;
+    };
+
+    let regions = pRegions.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), regionCount as usize)
+    });
+
+    commandBuffer.lock().cmd_blit_image(
+        srcImage,
+        srcImageLayout,
+        dstImage,
+        dstImageLayout,
+        regions,
+        filter,
+    );
+}
+
+pub unsafe extern "C" fn vkCmdResolveImage(
+    commandBuffer: VkCommandBuffer,
+    srcImage: VkImage,
+    srcImageLayout: VkImageLayout,
+    dstImage: VkImage,
+    dstImageLayout: VkImageLayout,
+    regionCount: u32,
+    pRegions: Option<NonNull<VkImageResolve>>,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(srcImage) = Image::lookup(srcImage) else {
+        return;
+    };
+
+    let Ok(dstImage) = Image::lookup(dstImage) else {
+        return;
+    };
+
+    let regions = pRegions.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), regionCount as usize)
+    });
+
+    commandBuffer.lock().cmd_resolve_image(
+        srcImage,
+        srcImageLayout,
+        dstImage,
+        dstImageLayout,
+        regions,
+    );
+}
+
 pub unsafe extern "C" fn vkCmdCopyBufferToImage(
     commandBuffer: VkCommandBuffer,
     srcBuffer: VkBuffer,
@@ -411,16 +710,16 @@ pub unsafe extern "C" fn vkCmdCopyBufferToImage(
     regionCount: u32,
     pRegions: Option<NonNull<VkBufferImageCopy>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
-    let Some(srcBuffer) = Buffer::from_handle(srcBuffer) else {
-        unreachable!()
+    let Ok(srcBuffer) = Buffer::lookup(srcBuffer) else {
+        return;
     };
 
-    let Some(dstImage) = Image::from_handle(dstImage) else {
-        unreachable!()
+    let Ok(dstImage) = Image::lookup(dstImage) else {
+        return;
     };
 
     let regions = pRegions.map_or(&[] as &[_], |x| {
@@ -440,16 +739,16 @@ pub unsafe extern "C" fn vkCmdCopyImageToBuffer(
     regionCount: u32,
     pRegions: Option<NonNull<VkBufferImageCopy>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
-    let Some(srcImage) = Image::from_handle(srcImage) else {
-        unreachable!()
+    let Ok(srcImage) = Image::lookup(srcImage) else {
+        return;
     };
 
-    let Some(dstBuffer) = Buffer::from_handle(dstBuffer) else {
-        unreachable!()
+    let Ok(dstBuffer) = Buffer::lookup(dstBuffer) else {
+        return;
     };
 
     let regions = pRegions.map_or(&[] as &[_], |x| {
@@ -468,16 +767,16 @@ pub unsafe extern "C" fn vkCmdCopyBuffer(
     regionCount: u32,
     pRegions: Option<NonNull<VkBufferCopy>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
-    let Some(srcBuffer) = Buffer::from_handle(srcBuffer) else {
-        unreachable!()
+    let Ok(srcBuffer) = Buffer::lookup(srcBuffer) else {
+        return;
     };
 
-    let Some(dstBuffer) = Buffer::from_handle(dstBuffer) else {
-        unreachable!()
+    let Ok(dstBuffer) = Buffer::lookup(dstBuffer) else {
+        return;
     };
 
     let regions = pRegions.map_or(&[] as &[_], |x| {
@@ -489,13 +788,118 @@ pub unsafe extern "C" fn vkCmdCopyBuffer(
         .cmd_copy_buffer_to_buffer(srcBuffer, dstBuffer, regions);
 }
 
+pub unsafe extern "C" fn vkCmdUpdateBuffer(
+    commandBuffer: VkCommandBuffer,
+    dstBuffer: VkBuffer,
+    dstOffset: VkDeviceSize,
+    dataSize: VkDeviceSize,
+    pData: Option<NonNull<std::ffi::c_void>>,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(dstBuffer) = Buffer::lookup(dstBuffer) else {
+        return;
+    };
+
+    let data = pData.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr() as *const u8, dataSize as usize)
+    });
+
+    commandBuffer
+        .lock()
+        .cmd_update_buffer(dstBuffer, dstOffset, data);
+}
+
+pub unsafe extern "C" fn vkCmdFillBuffer(
+    commandBuffer: VkCommandBuffer,
+    dstBuffer: VkBuffer,
+    dstOffset: VkDeviceSize,
+    size: VkDeviceSize,
+    data: u32,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(dstBuffer) = Buffer::lookup(dstBuffer) else {
+        return;
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_fill_buffer(dstBuffer, dstOffset, size, data);
+}
+
+pub unsafe extern "C" fn vkCmdClearColorImage(
+    commandBuffer: VkCommandBuffer,
+    image: VkImage,
+    imageLayout: VkImageLayout,
+    pColor: Option<NonNull<VkClearColorValue>>,
+    rangeCount: u32,
+    pRanges: Option<NonNull<VkImageSubresourceRange>>,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(image) = Image::lookup(image) else {
+        return;
+    };
+
+    let Some(pColor) = pColor else {
+        unreachable!()
+    };
+
+    let ranges = pRanges.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), rangeCount as usize)
+    });
+
+    commandBuffer
+        .lock()
+        .cmd_clear_color_image(image, imageLayout, *pColor.as_ref(), ranges);
+}
+
+pub unsafe extern "C" fn vkCmdClearDepthStencilImage(
+    commandBuffer: VkCommandBuffer,
+    image: VkImage,
+    imageLayout: VkImageLayout,
+    pDepthStencil: Option<NonNull<VkClearDepthStencilValue>>,
+    rangeCount: u32,
+    pRanges: Option<NonNull<VkImageSubresourceRange>>,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(image) = Image::lookup(image) else {
+        return;
+    };
+
+    let Some(pDepthStencil) = pDepthStencil else {
+        unreachable!()
+    };
+
+    let ranges = pRanges.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), rangeCount as usize)
+    });
+
+    commandBuffer.lock().cmd_clear_depth_stencil_image(
+        image,
+        imageLayout,
+        *pDepthStencil.as_ref(),
+        ranges,
+    );
+}
+
 pub unsafe extern "C" fn vkCmdExecuteCommands(
     commandBuffer: VkCommandBuffer,
     commandBufferCount: u32,
     pCommandBuffers: Option<NonNull<VkCommandBuffer>>,
 ) {
-    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
-        unreachable!()
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
     };
 
     let command_buffers = pCommandBuffers
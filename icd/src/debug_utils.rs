@@ -0,0 +1,104 @@
+//! VK_EXT_debug_utils extension commands
+
+use headers::vk_decls::*;
+use runtime::context::{Context, Dispatchable};
+use runtime::debug_utils::*;
+use runtime::instance::Instance;
+use runtime::logical_device::LogicalDevice;
+use std::ffi::CStr;
+
+pub unsafe extern "C" fn vkCreateDebugUtilsMessengerEXT(
+    instance: VkInstance,
+    pCreateInfo: Option<NonNull<VkDebugUtilsMessengerCreateInfoEXT>>,
+    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
+    pMessenger: Option<NonNull<VkDebugUtilsMessengerEXT>>,
+) -> VkResult {
+    let instance = match Instance::lookup(instance) {
+        Ok(instance) => instance,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pCreateInfo) = pCreateInfo else {
+        unreachable!()
+    };
+    let create_info = pCreateInfo.as_ref();
+    assert_eq!(
+        create_info.sType,
+        VkStructureType::VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT
+    );
+
+    let _ = pAllocator;
+
+    let Some(pMessenger) = pMessenger else {
+        unreachable!()
+    };
+
+    *pMessenger.as_ptr() = DebugUtilsMessenger::create(instance, create_info);
+
+    VkResult::VK_SUCCESS
+}
+
+pub unsafe extern "C" fn vkDestroyDebugUtilsMessengerEXT(
+    instance: VkInstance,
+    messenger: VkDebugUtilsMessengerEXT,
+    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
+) {
+    let Ok(_instance) = Instance::lookup(instance) else {
+        return;
+    };
+
+    let _ = pAllocator;
+
+    DebugUtilsMessenger::destroy(messenger);
+}
+
+pub unsafe extern "C" fn vkSubmitDebugUtilsMessageEXT(
+    instance: VkInstance,
+    messageSeverity: VkDebugUtilsMessageSeverityFlagBitsEXT,
+    messageTypes: VkDebugUtilsMessageTypeFlagsEXT,
+    pCallbackData: Option<NonNull<VkDebugUtilsMessengerCallbackDataEXT>>,
+) {
+    let Ok(instance) = Instance::lookup(instance) else {
+        return;
+    };
+
+    let Some(pCallbackData) = pCallbackData else {
+        unreachable!()
+    };
+    let callback_data = pCallbackData.as_ref();
+
+    DebugUtilsMessenger::submit_message(
+        Some(&instance),
+        messageSeverity,
+        messageTypes,
+        callback_data,
+    );
+}
+
+pub unsafe extern "C" fn vkSetDebugUtilsObjectNameEXT(
+    device: VkDevice,
+    pNameInfo: Option<NonNull<VkDebugUtilsObjectNameInfoEXT>>,
+) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let Some(pNameInfo) = pNameInfo else {
+        unreachable!()
+    };
+    let name_info = pNameInfo.as_ref();
+    assert_eq!(
+        name_info.sType,
+        VkStructureType::VK_STRUCTURE_TYPE_DEBUG_UTILS_OBJECT_NAME_INFO_EXT
+    );
+
+    // Empty or null name removes previously set one.
+    let name = name_info
+        .pObjectName
+        .map(|name| CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned())
+        .filter(|name| !name.is_empty());
+    // Naming is only a debugging aid, so unknown objects don't fail command.
+    let _ = Context::set_object_name(name_info.objectType, name_info.objectHandle, name);
+
+    VkResult::VK_SUCCESS
+}
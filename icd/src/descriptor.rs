@@ -1,7 +1,6 @@
 //! VkDescriptorSets device commands
 
 use headers::vk_decls::*;
-use log::warn;
 use runtime::context::{Dispatchable, NonDispatchable};
 use runtime::descriptor::*;
 use runtime::logical_device::LogicalDevice;
@@ -13,18 +12,18 @@ pub unsafe extern "C" fn vkCreateDescriptorSetLayout(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pSetLayout: Option<NonNull<VkDescriptorSetLayout>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
         unreachable!()
     };
     let create_info = pCreateInfo.as_ref();
-    let Some(bindings) = create_info.pBindings else {
-        unreachable!()
-    };
-    let bindings = std::slice::from_raw_parts(bindings.as_ptr(), create_info.bindingCount as usize);
+    let bindings = create_info.pBindings.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), create_info.bindingCount as usize)
+    });
 
     let _ = pAllocator;
 
@@ -32,7 +31,10 @@ pub unsafe extern "C" fn vkCreateDescriptorSetLayout(
         unreachable!()
     };
 
-    *pSetLayout.as_ptr() = DescriptorSetLayout::create(device, create_info.flags, bindings);
+    *pSetLayout.as_ptr() = match DescriptorSetLayout::create(device, create_info.flags, bindings) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -42,8 +44,8 @@ pub unsafe extern "C" fn vkDestroyDescriptorSetLayout(
     descriptorSetLayout: VkDescriptorSetLayout,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -57,8 +59,9 @@ pub unsafe extern "C" fn vkCreateDescriptorPool(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pDescriptorPool: Option<NonNull<VkDescriptorPool>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -78,7 +81,10 @@ pub unsafe extern "C" fn vkCreateDescriptorPool(
     };
 
     *pDescriptorPool.as_ptr() =
-        DescriptorPool::create(device, create_info.flags, create_info.maxSets, pool_sizes);
+        match DescriptorPool::create(device, create_info.flags, create_info.maxSets, pool_sizes) {
+            Ok(object) => object,
+            Err(err) => return err.to_vk_result(),
+        };
 
     VkResult::VK_SUCCESS
 }
@@ -88,8 +94,8 @@ pub unsafe extern "C" fn vkDestroyDescriptorPool(
     descriptorPool: VkDescriptorPool,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -102,16 +108,17 @@ pub unsafe extern "C" fn vkAllocateDescriptorSets(
     pAllocateInfo: Option<NonNull<VkDescriptorSetAllocateInfo>>,
     pDescriptorSets: Option<NonNull<VkDescriptorSet>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
-    };
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
 
     let Some(pAllocateInfo) = pAllocateInfo else {
         unreachable!()
     };
     let allocate_info = pAllocateInfo.as_ref();
-    let Some(descriptorPool) = DescriptorPool::from_handle(allocate_info.descriptorPool) else {
-        unreachable!()
+    let descriptorPool = match DescriptorPool::lookup(allocate_info.descriptorPool) {
+        Ok(descriptorPool) => descriptorPool,
+        Err(err) => return err.to_vk_result(),
     };
     let Some(pSetLayouts) = allocate_info.pSetLayouts else {
         unreachable!()
@@ -119,7 +126,14 @@ pub unsafe extern "C" fn vkAllocateDescriptorSets(
     let set_layouts = std::slice::from_raw_parts(
         pSetLayouts.as_ptr(),
         allocate_info.descriptorSetCount as usize,
-    );
+    )
+    .iter()
+    .map(|&set_layout| DescriptorSetLayout::lookup(set_layout))
+    .collect::<Result<Vec<_>, _>>();
+    let set_layouts = match set_layouts {
+        Ok(set_layouts) => set_layouts,
+        Err(err) => return err.to_vk_result(),
+    };
     let Some(pDescriptorSets) = pDescriptorSets else {
         unreachable!()
     };
@@ -128,11 +142,13 @@ pub unsafe extern "C" fn vkAllocateDescriptorSets(
         allocate_info.descriptorSetCount as usize,
     );
 
-    for (set_layout, descriptor_set) in std::iter::zip(set_layouts, descriptor_sets) {
-        *descriptor_set = DescriptorSet::create(device.clone(), descriptorPool.clone(), set_layout);
+    match DescriptorPool::allocate_descriptor_sets(&descriptorPool, &set_layouts) {
+        Ok(allocated) => {
+            descriptor_sets.copy_from_slice(&allocated);
+            VkResult::VK_SUCCESS
+        }
+        Err(err) => err.to_vk_result(),
     }
-
-    VkResult::VK_SUCCESS
 }
 
 pub unsafe extern "C" fn vkFreeDescriptorSets(
@@ -141,12 +157,13 @@ pub unsafe extern "C" fn vkFreeDescriptorSets(
     descriptorSetCount: u32,
     pDescriptorSets: Option<NonNull<VkDescriptorSet>>,
 ) -> VkResult {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
-    };
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
 
-    let Some(_descriptorPool) = DescriptorPool::from_handle(descriptorPool) else {
-        unreachable!()
+    let descriptorPool = match DescriptorPool::lookup(descriptorPool) {
+        Ok(descriptorPool) => descriptorPool,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pDescriptorSets) = pDescriptorSets else {
@@ -155,11 +172,29 @@ pub unsafe extern "C" fn vkFreeDescriptorSets(
     let descriptor_sets =
         std::slice::from_raw_parts(pDescriptorSets.as_ptr(), descriptorSetCount as usize);
 
-    for descriptor_set in descriptor_sets {
-        DescriptorSet::drop_handle(*descriptor_set);
-        warn!("TODO: Remove from DescriptorPool in DescriptorSet::drop()");
+    descriptorPool.lock().free_descriptor_sets(descriptor_sets);
+
+    VkResult::VK_SUCCESS
+}
+
+pub unsafe extern "C" fn vkResetDescriptorPool(
+    device: VkDevice,
+    descriptorPool: VkDescriptorPool,
+    flags: VkDescriptorPoolResetFlags,
+) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
     }
 
+    let descriptorPool = match DescriptorPool::lookup(descriptorPool) {
+        Ok(descriptorPool) => descriptorPool,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let _ = flags;
+
+    descriptorPool.lock().reset();
+
     VkResult::VK_SUCCESS
 }
 
@@ -170,20 +205,16 @@ pub unsafe extern "C" fn vkUpdateDescriptorSets(
     descriptorCopyCount: u32,
     pDescriptorCopies: Option<NonNull<VkCopyDescriptorSet>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
-    let descriptor_writes = pDescriptorWrites
-        .map(|x| std::slice::from_raw_parts(x.as_ptr(), descriptorWriteCount as usize));
-    let descriptor_copies = pDescriptorCopies
-        .map(|x| std::slice::from_raw_parts(x.as_ptr(), descriptorCopyCount as usize));
-
-    if let Some(_descriptor_write) = descriptor_writes {
-        warn!("TODO: Descriptor write")
-    }
+    let descriptor_writes = pDescriptorWrites.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), descriptorWriteCount as usize)
+    });
+    let descriptor_copies = pDescriptorCopies.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), descriptorCopyCount as usize)
+    });
 
-    if let Some(_descriptor_copy) = descriptor_copies {
-        warn!("TODO: Descriptor copy")
-    }
+    DescriptorSet::update_descriptor_sets(descriptor_writes, descriptor_copies);
 }
@@ -0,0 +1,173 @@
+//! VkEvent device commands
+
+use headers::vk_decls::*;
+use runtime::command_buffer::CommandBuffer;
+use runtime::context::{Dispatchable, NonDispatchable};
+use runtime::event::*;
+use runtime::logical_device::LogicalDevice;
+
+pub unsafe extern "C" fn vkCreateEvent(
+    device: VkDevice,
+    pCreateInfo: Option<NonNull<VkEventCreateInfo>>,
+    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
+    pEvent: Option<NonNull<VkEvent>>,
+) -> VkResult {
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pCreateInfo) = pCreateInfo else {
+        unreachable!()
+    };
+    let create_info = pCreateInfo.as_ref();
+    assert_eq!(
+        create_info.sType,
+        VkStructureType::VK_STRUCTURE_TYPE_EVENT_CREATE_INFO
+    );
+
+    let _ = pAllocator;
+
+    let Some(pEvent) = pEvent else { unreachable!() };
+
+    *pEvent.as_ptr() = match Event::create(device, create_info) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    VkResult::VK_SUCCESS
+}
+
+pub unsafe extern "C" fn vkDestroyEvent(
+    device: VkDevice,
+    event: VkEvent,
+    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
+) {
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
+    };
+
+    let _ = pAllocator;
+
+    Event::drop_handle(event);
+}
+
+pub unsafe extern "C" fn vkGetEventStatus(device: VkDevice, event: VkEvent) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let event = match Event::lookup(event) {
+        Ok(event) => event,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let status = event.lock().status();
+    status
+}
+
+pub unsafe extern "C" fn vkSetEvent(device: VkDevice, event: VkEvent) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let event = match Event::lookup(event) {
+        Ok(event) => event,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    event.lock().set();
+
+    VkResult::VK_SUCCESS
+}
+
+pub unsafe extern "C" fn vkResetEvent(device: VkDevice, event: VkEvent) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let event = match Event::lookup(event) {
+        Ok(event) => event,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    event.lock().reset();
+
+    VkResult::VK_SUCCESS
+}
+
+pub unsafe extern "C" fn vkCmdSetEvent(
+    commandBuffer: VkCommandBuffer,
+    event: VkEvent,
+    stageMask: VkPipelineStageFlags,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(event) = Event::lookup(event) else {
+        return;
+    };
+
+    let _ = stageMask;
+
+    commandBuffer.lock().cmd_set_event(event);
+}
+
+pub unsafe extern "C" fn vkCmdResetEvent(
+    commandBuffer: VkCommandBuffer,
+    event: VkEvent,
+    stageMask: VkPipelineStageFlags,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(event) = Event::lookup(event) else {
+        return;
+    };
+
+    let _ = stageMask;
+
+    commandBuffer.lock().cmd_reset_event(event);
+}
+
+pub unsafe extern "C" fn vkCmdWaitEvents(
+    commandBuffer: VkCommandBuffer,
+    eventCount: u32,
+    pEvents: Option<NonNull<VkEvent>>,
+    srcStageMask: VkPipelineStageFlags,
+    dstStageMask: VkPipelineStageFlags,
+    memoryBarrierCount: u32,
+    pMemoryBarriers: Option<NonNull<VkMemoryBarrier>>,
+    bufferMemoryBarrierCount: u32,
+    pBufferMemoryBarriers: Option<NonNull<VkBufferMemoryBarrier>>,
+    imageMemoryBarrierCount: u32,
+    pImageMemoryBarriers: Option<NonNull<VkImageMemoryBarrier>>,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let events = pEvents.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), eventCount as usize)
+    });
+    let events = events
+        .iter()
+        .flat_map(|&event| Event::from_handle(event))
+        .collect();
+    let _ = srcStageMask;
+    let _ = dstStageMask;
+    let _ = memoryBarrierCount;
+    let _ = pMemoryBarriers;
+    let _ = bufferMemoryBarrierCount;
+    let _ = pBufferMemoryBarriers;
+    let image_memory_barriers = pImageMemoryBarriers.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), imageMemoryBarrierCount as usize)
+    });
+
+    let mut commandBuffer = commandBuffer.lock();
+    commandBuffer.cmd_wait_events(events);
+    commandBuffer.cmd_pipeline_barrier(image_memory_barriers);
+    drop(commandBuffer);
+}
@@ -14,8 +14,9 @@ pub unsafe extern "C" fn vkCreateImageView(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pView: Option<NonNull<VkImageView>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -27,7 +28,10 @@ pub unsafe extern "C" fn vkCreateImageView(
 
     let Some(pView) = pView else { unreachable!() };
 
-    *pView.as_ptr() = ImageView::create(device, create_info);
+    *pView.as_ptr() = match ImageView::create(device, create_info) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -37,8 +41,8 @@ pub unsafe extern "C" fn vkDestroyImageView(
     imageView: VkImageView,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -52,8 +56,9 @@ pub unsafe extern "C" fn vkCreateImage(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pImage: Option<NonNull<VkImage>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -65,14 +70,10 @@ pub unsafe extern "C" fn vkCreateImage(
 
     let Some(pImage) = pImage else { unreachable!() };
 
-    *pImage.as_ptr() = Image::create(
-        device,
-        create_info.format,
-        create_info.extent.width,
-        create_info.extent.height,
-        create_info.arrayLayers,
-        create_info.usage,
-    );
+    *pImage.as_ptr() = match Image::create(device, create_info) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -82,8 +83,8 @@ pub unsafe extern "C" fn vkDestroyImage(
     image: VkImage,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -96,12 +97,12 @@ pub unsafe extern "C" fn vkGetImageMemoryRequirements(
     image: VkImage,
     pMemoryRequirements: Option<NonNull<VkMemoryRequirements>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
-    let Some(image) = Image::from_handle(image) else {
-        unreachable!()
+    let Ok(image) = Image::lookup(image) else {
+        return;
     };
 
     let Some(pMemoryRequirements) = pMemoryRequirements else {
@@ -111,18 +112,42 @@ pub unsafe extern "C" fn vkGetImageMemoryRequirements(
     *pMemoryRequirements.as_ptr() = image.lock().memory_requirements();
 }
 
+pub unsafe extern "C" fn vkGetImageSparseMemoryRequirements(
+    device: VkDevice,
+    image: VkImage,
+    pSparseMemoryRequirementCount: Option<NonNull<u32>>,
+    pSparseMemoryRequirements: Option<NonNull<VkSparseImageMemoryRequirements>>,
+) {
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
+    };
+
+    let Ok(_image) = Image::lookup(image) else {
+        return;
+    };
+
+    let Some(pSparseMemoryRequirementCount) = pSparseMemoryRequirementCount else {
+        unreachable!()
+    };
+
+    // Sparse features aren't supported, so no image is created with sparse residency and none
+    // has sparse memory requirements.
+    let _ = pSparseMemoryRequirements;
+    *pSparseMemoryRequirementCount.as_ptr() = 0;
+}
+
 pub unsafe extern "C" fn vkGetImageSubresourceLayout(
     device: VkDevice,
     image: VkImage,
     pSubresource: Option<NonNull<VkImageSubresource>>,
     pLayout: Option<NonNull<VkSubresourceLayout>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
-    let Some(image) = Image::from_handle(image) else {
-        unreachable!()
+    let Ok(image) = Image::lookup(image) else {
+        return;
     };
 
     let Some(pSubresource) = pSubresource else {
@@ -143,16 +168,18 @@ pub unsafe extern "C" fn vkBindImageMemory(
     memory: VkDeviceMemory,
     memoryOffset: VkDeviceSize,
 ) -> VkResult {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
-    };
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
 
-    let Some(image) = Image::from_handle(image) else {
-        unreachable!()
+    let image = match Image::lookup(image) {
+        Ok(image) => image,
+        Err(err) => return err.to_vk_result(),
     };
 
-    let Some(memory) = MemoryAllocation::from_handle(memory) else {
-        unreachable!()
+    let memory = match MemoryAllocation::lookup(memory) {
+        Ok(memory) => memory,
+        Err(err) => return err.to_vk_result(),
     };
 
     let result = image.lock().bind_memory(memory, memoryOffset);
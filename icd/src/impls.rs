@@ -4,22 +4,27 @@
 
 use crate::buffer::*;
 use crate::command_buffer::*;
+use crate::debug_utils::*;
 use crate::descriptor::*;
+use crate::event::*;
 use crate::image::*;
 use crate::memory::*;
 use crate::pipeline::*;
+use crate::query::*;
 use crate::sampler::*;
 use crate::swapchain::*;
 use headers::vk_decls::*;
 use headers::vk_defs::*;
+use log::error;
 use runtime::command_buffer::CommandBuffer;
 use runtime::context::{Dispatchable, NonDispatchable};
 use runtime::fence::Fence;
 use runtime::instance::Instance;
 use runtime::logical_device::LogicalDevice;
 use runtime::physical_device::PhysicalDevice;
-use runtime::queue::Queue;
+use runtime::queue::{Queue, SubmitInfo};
 use runtime::semaphore::Semaphore;
+use runtime::surface::Surface;
 use runtime::*;
 use std::sync::{Arc, Weak};
 
@@ -45,7 +50,7 @@ pub unsafe extern "C" fn vkCreateInstance(
 
     *pInstance.as_ptr() = match Instance::create() {
         Ok(inner) => inner,
-        Err(e) => return e,
+        Err(err) => return err.to_vk_result(),
     };
 
     VkResult::VK_SUCCESS
@@ -56,8 +61,9 @@ pub unsafe extern "C" fn vkEnumeratePhysicalDevices(
     pPhysicalDeviceCount: Option<NonNull<u32>>,
     pPhysicalDevices: Option<NonNull<VkPhysicalDevice>>,
 ) -> VkResult {
-    let Some(instance) = Instance::from_handle(instance) else {
-        unreachable!()
+    let instance = match Instance::lookup(instance) {
+        Ok(instance) => instance,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pPhysicalDeviceCount) = pPhysicalDeviceCount else {
@@ -80,8 +86,8 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceProperties(
     physicalDevice: VkPhysicalDevice,
     pProperties: Option<NonNull<VkPhysicalDeviceProperties>>,
 ) {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
     };
 
     let Some(pProperties) = pProperties else {
@@ -92,13 +98,31 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceProperties(
     *pProperties.as_ptr() = physicalDevice.lock().properties();
 }
 
+pub unsafe extern "C" fn vkGetPhysicalDeviceProperties2(
+    physicalDevice: VkPhysicalDevice,
+    pProperties: Option<NonNull<VkPhysicalDeviceProperties2>>,
+) {
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
+    };
+
+    let Some(pProperties) = pProperties else {
+        unreachable!()
+    };
+
+    physicalDevice
+        .lock()
+        .properties2(&mut *pProperties.as_ptr());
+}
+
 pub unsafe extern "C" fn vkGetPhysicalDeviceToolProperties(
     physicalDevice: VkPhysicalDevice,
     pToolCount: Option<NonNull<u32>>,
     pToolProperties: Option<NonNull<VkPhysicalDeviceToolProperties>>,
 ) -> VkResult {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let physicalDevice = match PhysicalDevice::lookup(physicalDevice) {
+        Ok(physicalDevice) => physicalDevice,
+        Err(err) => return err.to_vk_result(),
     };
 
     if pToolProperties.is_none() {
@@ -124,8 +148,8 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceMemoryProperties(
     physicalDevice: VkPhysicalDevice,
     pMemoryProperties: Option<NonNull<VkPhysicalDeviceMemoryProperties>>,
 ) {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
     };
 
     let Some(pMemoryProperties) = pMemoryProperties else {
@@ -140,8 +164,8 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceFeatures(
     physicalDevice: VkPhysicalDevice,
     pFeatures: Option<NonNull<VkPhysicalDeviceFeatures>>,
 ) {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
     };
 
     let Some(pFeatures) = pFeatures else {
@@ -152,13 +176,28 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceFeatures(
     *pFeatures.as_ptr() = physicalDevice.lock().features();
 }
 
+pub unsafe extern "C" fn vkGetPhysicalDeviceFeatures2(
+    physicalDevice: VkPhysicalDevice,
+    pFeatures: Option<NonNull<VkPhysicalDeviceFeatures2>>,
+) {
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
+    };
+
+    let Some(pFeatures) = pFeatures else {
+        unreachable!()
+    };
+
+    physicalDevice.lock().features2(&mut *pFeatures.as_ptr());
+}
+
 pub unsafe extern "C" fn vkGetPhysicalDeviceQueueFamilyProperties(
     physicalDevice: VkPhysicalDevice,
     pQueueFamilyPropertyCount: Option<NonNull<u32>>,
     pQueueFamilyProperties: Option<NonNull<VkQueueFamilyProperties>>,
 ) {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
     };
 
     let Some(pQueueFamilyPropertyCount) = pQueueFamilyPropertyCount else {
@@ -174,10 +213,13 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceQueueFamilyProperties(
             unreachable!()
         };
         let queue_family_properties = physicalDevice.lock().queue_family_properties();
-        pQueueFamilyProperties.as_ptr().copy_from(
-            queue_family_properties.as_ptr(),
-            queue_family_properties.len(),
-        );
+        let count = queue_family_properties
+            .len()
+            .min(*pQueueFamilyPropertyCount.as_ptr() as usize);
+        pQueueFamilyProperties
+            .as_ptr()
+            .copy_from(queue_family_properties.as_ptr(), count);
+        *pQueueFamilyPropertyCount.as_ptr() = count as u32;
     }
 }
 
@@ -222,8 +264,9 @@ pub unsafe extern "C" fn vkEnumerateDeviceExtensionProperties(
     pPropertyCount: Option<NonNull<u32>>,
     pProperties: Option<NonNull<VkExtensionProperties>>,
 ) -> VkResult {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let physicalDevice = match PhysicalDevice::lookup(physicalDevice) {
+        Ok(physicalDevice) => physicalDevice,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pPropertyCount) = pPropertyCount else {
@@ -262,8 +305,9 @@ pub unsafe extern "C" fn vkCreateDevice(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pDevice: Option<NonNull<VkDevice>>,
 ) -> VkResult {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let physicalDevice = match PhysicalDevice::lookup(physicalDevice) {
+        Ok(physicalDevice) => physicalDevice,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -274,25 +318,26 @@ pub unsafe extern "C" fn vkCreateDevice(
         create_info.sType,
         VkStructureType::VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO
     );
-    let queue_create_info = create_info.pQueueCreateInfos;
-
     let _ = pAllocator;
 
     let Some(pDevice) = pDevice else {
         unreachable!()
     };
 
-    let Some(queue_create_info) = create_info.pQueueCreateInfos else {
+    let Some(queue_create_infos) = create_info.pQueueCreateInfos else {
         return VkResult::VK_ERROR_INITIALIZATION_FAILED;
     };
-    let queue_create_info = queue_create_info.as_ref();
+    let queue_create_infos = std::slice::from_raw_parts(
+        queue_create_infos.as_ptr(),
+        create_info.queueCreateInfoCount as usize,
+    );
 
     let enabled_features = create_info.pEnabledFeatures.map(|x| x.as_ref());
 
     *pDevice.as_ptr() =
-        match LogicalDevice::create(physicalDevice, enabled_features, queue_create_info) {
+        match LogicalDevice::create(physicalDevice, enabled_features, queue_create_infos) {
             Ok(object) => object,
-            Err(err) => return err,
+            Err(err) => return err.to_vk_result(),
         };
     VkResult::VK_SUCCESS
 }
@@ -301,8 +346,8 @@ pub unsafe extern "C" fn vkGetDeviceProcAddr(
     device: VkDevice,
     pName: Option<NonNull<std::ffi::c_char>>,
 ) -> PFN_vkVoidFunction {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(device) = LogicalDevice::lookup(device) else {
+        return None;
     };
 
     let Some(pName) = pName else { unreachable!() };
@@ -363,6 +408,9 @@ pub unsafe extern "C" fn vkGetDeviceProcAddr(
         "vkGetQueryPoolResults" => unsafe {
             std::mem::transmute(vkGetQueryPoolResults as *const ())
         },
+        "vkResetQueryPool" | "vkResetQueryPoolEXT" => unsafe {
+            std::mem::transmute(vkResetQueryPool as *const ())
+        },
         "vkCreateBuffer" => unsafe { std::mem::transmute(vkCreateBuffer as *const ()) },
         "vkDestroyBuffer" => unsafe { std::mem::transmute(vkDestroyBuffer as *const ()) },
         "vkCreateBufferView" => unsafe { std::mem::transmute(vkCreateBufferView as *const ()) },
@@ -465,6 +513,9 @@ pub unsafe extern "C" fn vkGetDeviceProcAddr(
         "vkCmdBindDescriptorSets" => unsafe {
             std::mem::transmute(vkCmdBindDescriptorSets as *const ())
         },
+        "vkCmdPushDescriptorSetKHR" => unsafe {
+            std::mem::transmute(vkCmdPushDescriptorSetKHR as *const ())
+        },
         "vkCmdBindIndexBuffer" => unsafe { std::mem::transmute(vkCmdBindIndexBuffer as *const ()) },
         "vkCmdBindVertexBuffers" => unsafe {
             std::mem::transmute(vkCmdBindVertexBuffers as *const ())
@@ -514,6 +565,42 @@ pub unsafe extern "C" fn vkGetDeviceProcAddr(
         "vkCmdNextSubpass" => unsafe { std::mem::transmute(vkCmdNextSubpass as *const ()) },
         "vkCmdEndRenderPass" => unsafe { std::mem::transmute(vkCmdEndRenderPass as *const ()) },
         "vkCmdExecuteCommands" => unsafe { std::mem::transmute(vkCmdExecuteCommands as *const ()) },
+        /* VK_KHR_dynamic_rendering extension device commands */
+        "vkCmdBeginRendering" | "vkCmdBeginRenderingKHR" => unsafe {
+            std::mem::transmute(vkCmdBeginRendering as *const ())
+        },
+        "vkCmdEndRendering" | "vkCmdEndRenderingKHR" => unsafe {
+            std::mem::transmute(vkCmdEndRendering as *const ())
+        },
+        /* VK_KHR_synchronization2 extension device commands */
+        "vkQueueSubmit2" | "vkQueueSubmit2KHR" => unsafe {
+            std::mem::transmute(vkQueueSubmit2 as *const ())
+        },
+        "vkCmdPipelineBarrier2" | "vkCmdPipelineBarrier2KHR" => unsafe {
+            std::mem::transmute(vkCmdPipelineBarrier2 as *const ())
+        },
+        "vkCmdWriteTimestamp2" | "vkCmdWriteTimestamp2KHR" => unsafe {
+            std::mem::transmute(vkCmdWriteTimestamp2 as *const ())
+        },
+        /* VK_KHR_timeline_semaphore extension device commands */
+        "vkGetSemaphoreCounterValue" | "vkGetSemaphoreCounterValueKHR" => unsafe {
+            std::mem::transmute(vkGetSemaphoreCounterValue as *const ())
+        },
+        "vkWaitSemaphores" | "vkWaitSemaphoresKHR" => unsafe {
+            std::mem::transmute(vkWaitSemaphores as *const ())
+        },
+        "vkSignalSemaphore" | "vkSignalSemaphoreKHR" => unsafe {
+            std::mem::transmute(vkSignalSemaphore as *const ())
+        },
+        /* VK_EXT_debug_utils extension device commands */
+        "vkSetDebugUtilsObjectNameEXT" => unsafe {
+            std::mem::transmute(vkSetDebugUtilsObjectNameEXT as *const ())
+        },
+        /* VK_KHR_external_memory_fd extension device commands */
+        "vkGetMemoryFdKHR" => unsafe { std::mem::transmute(vkGetMemoryFdKHR as *const ()) },
+        "vkGetMemoryFdPropertiesKHR" => unsafe {
+            std::mem::transmute(vkGetMemoryFdPropertiesKHR as *const ())
+        },
         /* VK_KHR_swapchain extension device commands */
         "vkCreateSwapchainKHR" => unsafe { std::mem::transmute(vkCreateSwapchainKHR as *const ()) },
         "vkDestroySwapchainKHR" => unsafe {
@@ -544,8 +631,8 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceFormatProperties(
     format: VkFormat,
     pFormatProperties: Option<NonNull<VkFormatProperties>>,
 ) {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
     };
 
     let Some(pFormatProperties) = pFormatProperties else {
@@ -565,8 +652,9 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceImageFormatProperties(
     flags: VkImageCreateFlags,
     pImageFormatProperties: Option<NonNull<VkImageFormatProperties>>,
 ) -> VkResult {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let physicalDevice = match PhysicalDevice::lookup(physicalDevice) {
+        Ok(physicalDevice) => physicalDevice,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pImageFormatProperties) = pImageFormatProperties else {
@@ -582,13 +670,78 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceImageFormatProperties(
     })
 }
 
+pub unsafe extern "C" fn vkGetPhysicalDeviceSparseImageFormatProperties(
+    physicalDevice: VkPhysicalDevice,
+    format: VkFormat,
+    type_: VkImageType,
+    samples: VkSampleCountFlagBits,
+    usage: VkImageUsageFlags,
+    tiling: VkImageTiling,
+    pPropertyCount: Option<NonNull<u32>>,
+    pProperties: Option<NonNull<VkSparseImageFormatProperties>>,
+) {
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
+    };
+
+    let Some(pPropertyCount) = pPropertyCount else {
+        unreachable!()
+    };
+
+    let properties = physicalDevice
+        .lock()
+        .sparse_image_format_properties(format, type_, samples, usage, tiling);
+    let count = pProperties.map_or(properties.len(), |pProperties| {
+        let count = properties.len().min(*pPropertyCount.as_ptr() as usize);
+        pProperties.as_ptr().copy_from(properties.as_ptr(), count);
+        count
+    });
+    *pPropertyCount.as_ptr() = count as u32;
+}
+
+pub unsafe extern "C" fn vkGetPhysicalDeviceSparseImageFormatProperties2(
+    physicalDevice: VkPhysicalDevice,
+    pFormatInfo: Option<NonNull<VkPhysicalDeviceSparseImageFormatInfo2>>,
+    pPropertyCount: Option<NonNull<u32>>,
+    pProperties: Option<NonNull<VkSparseImageFormatProperties2>>,
+) {
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
+    };
+
+    let Some(pFormatInfo) = pFormatInfo else {
+        unreachable!()
+    };
+    let format_info = pFormatInfo.as_ref();
+
+    let Some(pPropertyCount) = pPropertyCount else {
+        unreachable!()
+    };
+
+    let properties = physicalDevice.lock().sparse_image_format_properties(
+        format_info.format,
+        format_info.type_,
+        format_info.samples,
+        format_info.usage,
+        format_info.tiling,
+    );
+    let count = pProperties.map_or(properties.len(), |pProperties| {
+        let count = properties.len().min(*pPropertyCount.as_ptr() as usize);
+        for (i, properties) in properties.iter().take(count).enumerate() {
+            (*pProperties.as_ptr().add(i)).properties = *properties;
+        }
+        count
+    });
+    *pPropertyCount.as_ptr() = count as u32;
+}
+
 pub unsafe extern "C" fn vkDestroyDevice(
     device: VkDevice,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
     let _ = pAllocator;
 
-    LogicalDevice::drop_handle(device);
+    LogicalDevice::destroy(device);
 }
 
 pub unsafe extern "C" fn vkDestroyInstance(
@@ -608,13 +761,20 @@ pub unsafe extern "C" fn vkGetDeviceQueue(
     queueIndex: u32,
     pQueue: Option<NonNull<VkQueue>>,
 ) {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let Some(pQueue) = pQueue else { unreachable!() };
 
-    let queue = device.lock().queue(queueFamilyIndex, queueIndex);
+    let Some(queue) = device.lock().queue(queueFamilyIndex, queueIndex) else {
+        error!(
+            "Device has no queue {} of family {}",
+            queueIndex, queueFamilyIndex
+        );
+        *pQueue.as_ptr() = VkDispatchableHandle(None);
+        return;
+    };
     *pQueue.as_ptr() = queue.lock().get_handle();
 }
 
@@ -624,8 +784,9 @@ pub unsafe extern "C" fn vkCreateFence(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pFence: Option<NonNull<VkFence>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -643,7 +804,10 @@ pub unsafe extern "C" fn vkCreateFence(
         unreachable!()
     };
 
-    *pFence.as_ptr() = Fence::create(device, create_info);
+    *pFence.as_ptr() = match Fence::create(device, create_info) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -653,8 +817,8 @@ pub unsafe extern "C" fn vkDestroyFence(
     fence: VkFence,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -668,8 +832,9 @@ pub unsafe extern "C" fn vkCreateSemaphore(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pSemaphore: Option<NonNull<VkSemaphore>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -687,7 +852,10 @@ pub unsafe extern "C" fn vkCreateSemaphore(
         unreachable!()
     };
 
-    *pSemaphore.as_ptr() = Semaphore::create(create_info);
+    *pSemaphore.as_ptr() = match Semaphore::create(device, create_info) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -697,8 +865,8 @@ pub unsafe extern "C" fn vkDestroySemaphore(
     semaphore: VkSemaphore,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -706,6 +874,80 @@ pub unsafe extern "C" fn vkDestroySemaphore(
     Semaphore::drop_handle(semaphore);
 }
 
+pub unsafe extern "C" fn vkGetSemaphoreCounterValue(
+    device: VkDevice,
+    semaphore: VkSemaphore,
+    pValue: Option<NonNull<u64>>,
+) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let semaphore = match Semaphore::lookup(semaphore) {
+        Ok(semaphore) => semaphore,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pValue) = pValue else { unreachable!() };
+
+    *pValue.as_ptr() = semaphore.lock().counter_value();
+
+    VkResult::VK_SUCCESS
+}
+
+pub unsafe extern "C" fn vkWaitSemaphores(
+    device: VkDevice,
+    pWaitInfo: Option<NonNull<VkSemaphoreWaitInfo>>,
+    timeout: u64,
+) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let Some(pWaitInfo) = pWaitInfo else {
+        unreachable!()
+    };
+    let wait_info = pWaitInfo.as_ref();
+
+    let semaphores = wait_info.pSemaphores.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), wait_info.semaphoreCount as usize)
+    });
+    let values = wait_info.pValues.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), wait_info.semaphoreCount as usize)
+    });
+    let semaphores = semaphores
+        .iter()
+        .zip(values)
+        .flat_map(|(&handle, &value)| Semaphore::from_handle(handle).map(|x| (x, value)))
+        .collect::<Vec<_>>();
+    let wait_any = wait_info.flags & VkSemaphoreWaitFlagBits::VK_SEMAPHORE_WAIT_ANY_BIT.0 != 0;
+
+    Semaphore::wait_for_values(&semaphores, wait_any, timeout)
+}
+
+pub unsafe extern "C" fn vkSignalSemaphore(
+    device: VkDevice,
+    pSignalInfo: Option<NonNull<VkSemaphoreSignalInfo>>,
+) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let Some(pSignalInfo) = pSignalInfo else {
+        unreachable!()
+    };
+    let signal_info = pSignalInfo.as_ref();
+
+    let semaphore = match Semaphore::lookup(signal_info.semaphore) {
+        Ok(semaphore) => semaphore,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    Semaphore::signal_and_resume(&semaphore, signal_info.value);
+
+    VkResult::VK_SUCCESS
+}
+
 pub unsafe extern "C" fn vkWaitForFences(
     device: VkDevice,
     fenceCount: u32,
@@ -713,8 +955,9 @@ pub unsafe extern "C" fn vkWaitForFences(
     waitAll: VkBool32,
     timeout: u64,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     if fenceCount == 0 {
@@ -730,8 +973,7 @@ pub unsafe extern "C" fn vkWaitForFences(
         .flat_map(|&handle| Fence::from_handle(handle))
         .collect::<Vec<_>>();
 
-    device.lock().wait_for_fences(fences, waitAll != 0, timeout);
-    VkResult::VK_SUCCESS
+    LogicalDevice::wait_for_fences(&device, fences, waitAll != 0, timeout)
 }
 
 pub unsafe extern "C" fn vkResetFences(
@@ -739,8 +981,9 @@ pub unsafe extern "C" fn vkResetFences(
     fenceCount: u32,
     pFences: Option<NonNull<VkFence>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     if fenceCount == 0 {
@@ -766,67 +1009,124 @@ pub unsafe extern "C" fn vkQueueSubmit(
     pSubmits: Option<NonNull<VkSubmitInfo>>,
     fence: VkFence,
 ) -> VkResult {
-    let Some(queue) = Queue::from_handle(queue) else {
-        unreachable!()
+    let queue = match Queue::lookup(queue) {
+        Ok(queue) => queue,
+        Err(err) => return err.to_vk_result(),
     };
 
     let submits = pSubmits.map_or(&[] as &[_], |x| {
         std::slice::from_raw_parts(x.as_ptr(), submitCount as usize)
     });
 
-    for submit in submits {
-        let wait_semaphores = submit
+    let submits = submits.iter().map(|submit| SubmitInfo {
+        wait_semaphore_values: timeline_semaphore_submit_info(submit.pNext)
+            .and_then(|x| {
+                x.pWaitSemaphoreValues
+                    .map(|y| (y, x.waitSemaphoreValueCount))
+            })
+            .map_or(&[] as &[_], |(x, count)| {
+                std::slice::from_raw_parts(x.as_ptr(), count as usize)
+            })
+            .to_vec(),
+        signal_semaphore_values: timeline_semaphore_submit_info(submit.pNext)
+            .and_then(|x| {
+                x.pSignalSemaphoreValues
+                    .map(|y| (y, x.signalSemaphoreValueCount))
+            })
+            .map_or(&[] as &[_], |(x, count)| {
+                std::slice::from_raw_parts(x.as_ptr(), count as usize)
+            })
+            .to_vec(),
+        wait_semaphores: submit
             .pWaitSemaphores
             .map_or(&[] as &[_], |x| {
                 std::slice::from_raw_parts(x.as_ptr(), submit.waitSemaphoreCount as usize)
             })
             .iter()
-            .flat_map(|&handle| Semaphore::from_handle(handle));
-        let wait_semaphores_stage_flags = submit.pWaitDstStageMask.map_or(&[] as &[_], |x| {
-            std::slice::from_raw_parts(x.as_ptr(), submit.waitSemaphoreCount as usize)
-        });
-        let signal_semaphores = submit
-            .pSignalSemaphores
+            .flat_map(|&handle| Semaphore::from_handle(handle))
+            .collect(),
+        wait_dst_stage_masks: submit
+            .pWaitDstStageMask
             .map_or(&[] as &[_], |x| {
-                std::slice::from_raw_parts(x.as_ptr(), submit.signalSemaphoreCount as usize)
+                std::slice::from_raw_parts(x.as_ptr(), submit.waitSemaphoreCount as usize)
             })
-            .iter()
-            .flat_map(|&handle| Semaphore::from_handle(handle));
-        let command_buffers = submit
+            .to_vec(),
+        command_buffers: submit
             .pCommandBuffers
             .map_or(&[] as &[_], |x| {
                 std::slice::from_raw_parts(x.as_ptr(), submit.commandBufferCount as usize)
             })
             .iter()
-            .flat_map(|&handle| CommandBuffer::from_handle(handle));
+            .flat_map(|&handle| CommandBuffer::from_handle(handle))
+            .collect(),
+        signal_semaphores: submit
+            .pSignalSemaphores
+            .map_or(&[] as &[_], |x| {
+                std::slice::from_raw_parts(x.as_ptr(), submit.signalSemaphoreCount as usize)
+            })
+            .iter()
+            .flat_map(|&handle| Semaphore::from_handle(handle))
+            .collect(),
+    });
+    let fence = Fence::from_handle(fence);
 
-        queue.lock().submit(
-            wait_semaphores,
-            wait_semaphores_stage_flags,
-            signal_semaphores,
-            command_buffers,
-        );
+    queue.lock().submit(submits, fence);
+
+    VkResult::VK_SUCCESS
+}
+
+/// Returns `VkTimelineSemaphoreSubmitInfo` of `pNext` chain of submit info, if any.
+unsafe fn timeline_semaphore_submit_info<'a>(
+    pNext: Option<NonNull<std::ffi::c_void>>,
+) -> Option<&'a VkTimelineSemaphoreSubmitInfo> {
+    let mut next = pNext.map(NonNull::cast::<VkBaseInStructure>);
+    while let Some(structure) = next {
+        let structure = structure.as_ref();
+        if structure.sType == VkStructureType::VK_STRUCTURE_TYPE_TIMELINE_SEMAPHORE_SUBMIT_INFO {
+            return Some(NonNull::from(structure).cast().as_ref());
+        }
+        next = structure.pNext;
     }
+    None
+}
+
+pub unsafe extern "C" fn vkQueueSubmit2(
+    queue: VkQueue,
+    submitCount: u32,
+    pSubmits: Option<NonNull<VkSubmitInfo2>>,
+    fence: VkFence,
+) -> VkResult {
+    let queue = match Queue::lookup(queue) {
+        Ok(queue) => queue,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let submits = pSubmits.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), submitCount as usize)
+    });
+    let fence = Fence::from_handle(fence);
+
+    queue.lock().submit2(submits, fence);
 
     VkResult::VK_SUCCESS
 }
 
 pub unsafe extern "C" fn vkDeviceWaitIdle(device: VkDevice) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
-    let result = device.lock().wait_idle();
-    result
+    LogicalDevice::wait_idle(&device)
 }
 
 pub unsafe extern "C" fn vkQueueWaitIdle(queue: VkQueue) -> VkResult {
-    let Some(queue) = Queue::from_handle(queue) else {
-        unreachable!()
+    let queue = match Queue::lookup(queue) {
+        Ok(queue) => queue,
+        Err(err) => return err.to_vk_result(),
     };
 
-    let result = queue.lock().wait_idle();
-    result
+    Queue::wait_idle(&queue)
 }
 
 /* VK_KHR_surface extension instance commands */
@@ -837,8 +1137,9 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceSurfaceSupportKHR(
     surface: VkSurfaceKHR,
     pSupported: Option<NonNull<VkBool32>>,
 ) -> VkResult {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let physicalDevice = match PhysicalDevice::lookup(physicalDevice) {
+        Ok(physicalDevice) => physicalDevice,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pSupported) = pSupported else {
@@ -858,8 +1159,9 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceSurfacePresentModesKHR(
     pPresentModeCount: Option<NonNull<u32>>,
     pPresentModes: Option<NonNull<VkPresentModeKHR>>,
 ) -> VkResult {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let physicalDevice = match PhysicalDevice::lookup(physicalDevice) {
+        Ok(physicalDevice) => physicalDevice,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pPresentModeCount) = pPresentModeCount else {
@@ -889,24 +1191,30 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceSurfaceFormatsKHR(
     pSurfaceFormatCount: Option<NonNull<u32>>,
     pSurfaceFormats: Option<NonNull<VkSurfaceFormatKHR>>,
 ) -> VkResult {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let physicalDevice = match PhysicalDevice::lookup(physicalDevice) {
+        Ok(physicalDevice) => physicalDevice,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let surface = match Surface::lookup(surface) {
+        Ok(surface) => surface,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pSurfaceFormatCount) = pSurfaceFormatCount else {
         unreachable!()
     };
 
+    let surface_formats = physicalDevice.lock().surface_formats(&surface.lock());
     pSurfaceFormats.map_or_else(
         || {
-            *pSurfaceFormatCount.as_ptr() = physicalDevice.lock().surface_formats().len() as u32;
+            *pSurfaceFormatCount.as_ptr() = surface_formats.len() as u32;
         },
         |pSurfaceFormats| {
-            let surface_formats = physicalDevice.lock().surface_formats();
             std::ptr::copy_nonoverlapping(
                 surface_formats.as_ptr(),
                 pSurfaceFormats.as_ptr(),
-                *pSurfaceFormatCount.as_ptr() as usize,
+                (*pSurfaceFormatCount.as_ptr() as usize).min(surface_formats.len()),
             );
         },
     );
@@ -919,15 +1227,25 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceSurfaceCapabilitiesKHR(
     surface: VkSurfaceKHR,
     pSurfaceCapabilities: Option<NonNull<VkSurfaceCapabilitiesKHR>>,
 ) -> VkResult {
-    let Some(physicalDevice) = PhysicalDevice::from_handle(physicalDevice) else {
-        unreachable!()
+    let physicalDevice = match PhysicalDevice::lookup(physicalDevice) {
+        Ok(physicalDevice) => physicalDevice,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pSurfaceCapabilities) = pSurfaceCapabilities else {
         unreachable!()
     };
 
-    *pSurfaceCapabilities.as_ptr() = physicalDevice.lock().surface_capabilities();
+    let surface = match Surface::lookup(surface) {
+        Ok(surface) => surface,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let surface_capabilities = physicalDevice.lock().surface_capabilities(&surface.lock());
+    *pSurfaceCapabilities.as_ptr() = match surface_capabilities {
+        Ok(surface_capabilities) => surface_capabilities,
+        Err(err) => return err,
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -1038,28 +1356,6 @@ pub unsafe extern "C" fn vkBindVideoSessionMemoryKHR(
     )
 }
 
-pub unsafe extern "C" fn vkCmdResolveImage(
-    commandBuffer: VkCommandBuffer,
-    srcImage: VkImage,
-    srcImageLayout: VkImageLayout,
-    dstImage: VkImage,
-    dstImageLayout: VkImageLayout,
-    regionCount: u32,
-    pRegions: Option<NonNull<VkImageResolve>>,
-) {
-    unimplemented!(
-        "vkCmdResolveImage(
-        commandBuffer,
-        srcImage,
-        srcImageLayout,
-        dstImage,
-        dstImageLayout,
-        regionCount,
-        pRegions,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkCmdSetProvokingVertexModeEXT(
     commandBuffer: VkCommandBuffer,
     provokingVertexMode: VkProvokingVertexModeEXT,
@@ -1110,26 +1406,6 @@ pub unsafe extern "C" fn vkCmdSetCoverageModulationTableEnableNV(
     )
 }
 
-pub unsafe extern "C" fn vkCreateComputePipelines(
-    device: VkDevice,
-    pipelineCache: VkPipelineCache,
-    createInfoCount: u32,
-    pCreateInfos: Option<NonNull<VkComputePipelineCreateInfo>>,
-    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
-    pPipelines: Option<NonNull<VkPipeline>>,
-) -> VkResult {
-    unimplemented!(
-        "vkCreateComputePipelines(
-        device,
-        pipelineCache,
-        createInfoCount,
-        pCreateInfos,
-        pAllocator,
-        pPipelines,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkCmdRefreshObjectsKHR(
     commandBuffer: VkCommandBuffer,
     pRefreshObjects: Option<NonNull<VkRefreshObjectListKHR>>,
@@ -1137,13 +1413,6 @@ pub unsafe extern "C" fn vkCmdRefreshObjectsKHR(
     unimplemented!("vkCmdRefreshObjectsKHR(commandBuffer, pRefreshObjects")
 }
 
-pub unsafe extern "C" fn vkCmdPipelineBarrier2(
-    commandBuffer: VkCommandBuffer,
-    pDependencyInfo: Option<NonNull<VkDependencyInfo>>,
-) {
-    unimplemented!("vkCmdPipelineBarrier2(commandBuffer, pDependencyInfo")
-}
-
 pub unsafe extern "C" fn vkGetPhysicalDeviceWin32PresentationSupportKHR(
     physicalDevice: VkPhysicalDevice,
     queueFamilyIndex: u32,
@@ -1463,14 +1732,6 @@ pub unsafe extern "C" fn vkCopyMemoryToAccelerationStructureKHR(
     unimplemented!("vkCopyMemoryToAccelerationStructureKHR(device, deferredOperation, pInfo")
 }
 
-pub unsafe extern "C" fn vkDestroyEvent(
-    device: VkDevice,
-    event: VkEvent,
-    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
-) {
-    unimplemented!("vkDestroyEvent(device, event, pAllocator")
-}
-
 pub unsafe extern "C" fn vkDestroyDescriptorUpdateTemplate(
     device: VkDevice,
     descriptorUpdateTemplate: VkDescriptorUpdateTemplate,
@@ -1534,26 +1795,6 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceSurfaceCapabilities2EXT(
     )
 }
 
-pub unsafe extern "C" fn vkCmdClearColorImage(
-    commandBuffer: VkCommandBuffer,
-    image: VkImage,
-    imageLayout: VkImageLayout,
-    pColor: Option<NonNull<VkClearColorValue>>,
-    rangeCount: u32,
-    pRanges: Option<NonNull<VkImageSubresourceRange>>,
-) {
-    unimplemented!(
-        "vkCmdClearColorImage(
-        commandBuffer,
-        image,
-        imageLayout,
-        pColor,
-        rangeCount,
-        pRanges,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkCmdDrawMeshTasksEXT(
     commandBuffer: VkCommandBuffer,
     groupCountX: u32,
@@ -1563,13 +1804,6 @@ pub unsafe extern "C" fn vkCmdDrawMeshTasksEXT(
     unimplemented!("vkCmdDrawMeshTasksEXT(commandBuffer, groupCountX, groupCountY, groupCountZ")
 }
 
-pub unsafe extern "C" fn vkSignalSemaphore(
-    device: VkDevice,
-    pSignalInfo: Option<NonNull<VkSemaphoreSignalInfo>>,
-) -> VkResult {
-    unimplemented!("vkSignalSemaphore(device, pSignalInfo")
-}
-
 pub unsafe extern "C" fn vkCmdDrawIndexedIndirect(
     commandBuffer: VkCommandBuffer,
     buffer: VkBuffer,
@@ -1660,10 +1894,6 @@ pub unsafe extern "C" fn vkCreateMacOSSurfaceMVK(
     unimplemented!("vkCreateMacOSSurfaceMVK(instance, pCreateInfo, pAllocator, pSurface")
 }
 
-pub unsafe extern "C" fn vkSetEvent(device: VkDevice, event: VkEvent) -> VkResult {
-    unimplemented!("vkSetEvent(device, event")
-}
-
 pub unsafe extern "C" fn vkCmdSetStencilReference(
     commandBuffer: VkCommandBuffer,
     faceMask: VkStencilFaceFlags,
@@ -1676,23 +1906,15 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceMemoryProperties2(
     physicalDevice: VkPhysicalDevice,
     pMemoryProperties: Option<NonNull<VkPhysicalDeviceMemoryProperties2>>,
 ) {
-    unimplemented!("vkGetPhysicalDeviceMemoryProperties2(physicalDevice, pMemoryProperties")
-}
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
+    };
 
-pub unsafe extern "C" fn vkGetPhysicalDeviceSparseImageFormatProperties2(
-    physicalDevice: VkPhysicalDevice,
-    pFormatInfo: Option<NonNull<VkPhysicalDeviceSparseImageFormatInfo2>>,
-    pPropertyCount: Option<NonNull<u32>>,
-    pProperties: Option<NonNull<VkSparseImageFormatProperties2>>,
-) {
-    unimplemented!(
-        "vkGetPhysicalDeviceSparseImageFormatProperties2(
-        physicalDevice,
-        pFormatInfo,
-        pPropertyCount,
-        pProperties,
-    "
-    )
+    let Some(pMemoryProperties) = pMemoryProperties else {
+        unreachable!()
+    };
+
+    (*pMemoryProperties.as_ptr()).memoryProperties = physicalDevice.lock().memory_properties();
 }
 
 pub unsafe extern "C" fn vkGetDisplayPlaneCapabilities2KHR(
@@ -1740,22 +1962,6 @@ pub unsafe extern "C" fn vkCreateSemaphoreSciSyncPoolNV(
     unimplemented!("vkCreateSemaphoreSciSyncPoolNV(device, pCreateInfo, pAllocator, pSemaphorePool")
 }
 
-pub unsafe extern "C" fn vkCmdSetDepthBias(
-    commandBuffer: VkCommandBuffer,
-    depthBiasConstantFactor: f32,
-    depthBiasClamp: f32,
-    depthBiasSlopeFactor: f32,
-) {
-    unimplemented!(
-        "vkCmdSetDepthBias(
-        commandBuffer,
-        depthBiasConstantFactor,
-        depthBiasClamp,
-        depthBiasSlopeFactor,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkCmdDrawClusterIndirectHUAWEI(
     commandBuffer: VkCommandBuffer,
     buffer: VkBuffer,
@@ -1840,30 +2046,6 @@ pub unsafe extern "C" fn vkGetMemoryWin32HandlePropertiesKHR(
     )
 }
 
-pub unsafe extern "C" fn vkGetPhysicalDeviceSparseImageFormatProperties(
-    physicalDevice: VkPhysicalDevice,
-    format: VkFormat,
-    type_: VkImageType,
-    samples: VkSampleCountFlagBits,
-    usage: VkImageUsageFlags,
-    tiling: VkImageTiling,
-    pPropertyCount: Option<NonNull<u32>>,
-    pProperties: Option<NonNull<VkSparseImageFormatProperties>>,
-) {
-    unimplemented!(
-        "vkGetPhysicalDeviceSparseImageFormatProperties(
-        physicalDevice,
-        format,
-        type_,
-        samples,
-        usage,
-        tiling,
-        pPropertyCount,
-        pProperties,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkInitializePerformanceApiINTEL(
     device: VkDevice,
     pInitializeInfo: Option<NonNull<VkInitializePerformanceApiInfoINTEL>>,
@@ -1882,30 +2064,6 @@ pub unsafe extern "C" fn vkCreateIndirectCommandsLayoutNV(
     )
 }
 
-pub unsafe extern "C" fn vkCmdBlitImage(
-    commandBuffer: VkCommandBuffer,
-    srcImage: VkImage,
-    srcImageLayout: VkImageLayout,
-    dstImage: VkImage,
-    dstImageLayout: VkImageLayout,
-    regionCount: u32,
-    pRegions: Option<NonNull<VkImageBlit>>,
-    filter: VkFilter,
-) {
-    unimplemented!(
-        "vkCmdBlitImage(
-        commandBuffer,
-        srcImage,
-        srcImageLayout,
-        dstImage,
-        dstImageLayout,
-        regionCount,
-        pRegions,
-        filter,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkCmdSetRasterizationStreamEXT(
     commandBuffer: VkCommandBuffer,
     rasterizationStream: u32,
@@ -2069,15 +2227,6 @@ pub unsafe extern "C" fn vkCreateMicromapEXT(
     unimplemented!("vkCreateMicromapEXT(device, pCreateInfo, pAllocator, pMicromap")
 }
 
-pub unsafe extern "C" fn vkCreateQueryPool(
-    device: VkDevice,
-    pCreateInfo: Option<NonNull<VkQueryPoolCreateInfo>>,
-    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
-    pQueryPool: Option<NonNull<VkQueryPool>>,
-) -> VkResult {
-    unimplemented!("vkCreateQueryPool(device, pCreateInfo, pAllocator, pQueryPool")
-}
-
 pub unsafe extern "C" fn vkGetDescriptorSetLayoutSupport(
     device: VkDevice,
     pCreateInfo: Option<NonNull<VkDescriptorSetLayoutCreateInfo>>,
@@ -2093,13 +2242,6 @@ pub unsafe extern "C" fn vkCmdControlVideoCodingKHR(
     unimplemented!("vkCmdControlVideoCodingKHR(commandBuffer, pCodingControlInfo")
 }
 
-pub unsafe extern "C" fn vkSetDebugUtilsObjectNameEXT(
-    device: VkDevice,
-    pNameInfo: Option<NonNull<VkDebugUtilsObjectNameInfoEXT>>,
-) -> VkResult {
-    unimplemented!("vkSetDebugUtilsObjectNameEXT(device, pNameInfo")
-}
-
 pub unsafe extern "C" fn vkCreateXlibSurfaceKHR(
     instance: VkInstance,
     pCreateInfo: Option<NonNull<VkXlibSurfaceCreateInfoKHR>>,
@@ -2162,14 +2304,6 @@ pub unsafe extern "C" fn vkCmdCuLaunchKernelNVX(
     unimplemented!("vkCmdCuLaunchKernelNVX(commandBuffer, pLaunchInfo")
 }
 
-pub unsafe extern "C" fn vkDestroyQueryPool(
-    device: VkDevice,
-    queryPool: VkQueryPool,
-    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
-) {
-    unimplemented!("vkDestroyQueryPool(device, queryPool, pAllocator")
-}
-
 pub unsafe extern "C" fn vkQueueBeginDebugUtilsLabelEXT(
     queue: VkQueue,
     pLabelInfo: Option<NonNull<VkDebugUtilsLabelEXT>>,
@@ -2184,10 +2318,6 @@ pub unsafe extern "C" fn vkCmdCopyAccelerationStructureToMemoryKHR(
     unimplemented!("vkCmdCopyAccelerationStructureToMemoryKHR(commandBuffer, pInfo")
 }
 
-pub unsafe extern "C" fn vkGetEventStatus(device: VkDevice, event: VkEvent) -> VkResult {
-    unimplemented!("vkGetEventStatus(device, event")
-}
-
 pub unsafe extern "C" fn vkGetPhysicalDeviceSurfaceCapabilities2KHR(
     physicalDevice: VkPhysicalDevice,
     pSurfaceInfo: Option<NonNull<VkPhysicalDeviceSurfaceInfo2KHR>>,
@@ -2210,13 +2340,6 @@ pub unsafe extern "C" fn vkGetDeviceImageMemoryRequirements(
     unimplemented!("vkGetDeviceImageMemoryRequirements(device, pInfo, pMemoryRequirements")
 }
 
-pub unsafe extern "C" fn vkGetPhysicalDeviceFeatures2(
-    physicalDevice: VkPhysicalDevice,
-    pFeatures: Option<NonNull<VkPhysicalDeviceFeatures2>>,
-) {
-    unimplemented!("vkGetPhysicalDeviceFeatures2(physicalDevice, pFeatures")
-}
-
 pub unsafe extern "C" fn vkDestroyVideoSessionKHR(
     device: VkDevice,
     videoSession: VkVideoSessionKHR,
@@ -2225,15 +2348,6 @@ pub unsafe extern "C" fn vkDestroyVideoSessionKHR(
     unimplemented!("vkDestroyVideoSessionKHR(device, videoSession, pAllocator")
 }
 
-pub unsafe extern "C" fn vkCreateHeadlessSurfaceEXT(
-    instance: VkInstance,
-    pCreateInfo: Option<NonNull<VkHeadlessSurfaceCreateInfoEXT>>,
-    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
-    pSurface: Option<NonNull<VkSurfaceKHR>>,
-) -> VkResult {
-    unimplemented!("vkCreateHeadlessSurfaceEXT(instance, pCreateInfo, pAllocator, pSurface")
-}
-
 pub unsafe extern "C" fn vkGetMemorySciBufNV(
     device: VkDevice,
     pGetSciBufInfo: Option<NonNull<VkMemoryGetSciBufInfoNV>>,
@@ -2257,52 +2371,6 @@ pub unsafe extern "C" fn vkImportFenceWin32HandleKHR(
     unimplemented!("vkImportFenceWin32HandleKHR(device, pImportFenceWin32HandleInfo")
 }
 
-pub unsafe extern "C" fn vkCmdWaitEvents(
-    commandBuffer: VkCommandBuffer,
-    eventCount: u32,
-    pEvents: Option<NonNull<VkEvent>>,
-    srcStageMask: VkPipelineStageFlags,
-    dstStageMask: VkPipelineStageFlags,
-    memoryBarrierCount: u32,
-    pMemoryBarriers: Option<NonNull<VkMemoryBarrier>>,
-    bufferMemoryBarrierCount: u32,
-    pBufferMemoryBarriers: Option<NonNull<VkBufferMemoryBarrier>>,
-    imageMemoryBarrierCount: u32,
-    pImageMemoryBarriers: Option<NonNull<VkImageMemoryBarrier>>,
-) {
-    unimplemented!(
-        "vkCmdWaitEvents(
-        commandBuffer,
-        eventCount,
-        pEvents,
-        srcStageMask,
-        dstStageMask,
-        memoryBarrierCount,
-        pMemoryBarriers,
-        bufferMemoryBarrierCount,
-        pBufferMemoryBarriers,
-        imageMemoryBarrierCount,
-        pImageMemoryBarriers,
-    "
-    )
-}
-
-pub unsafe extern "C" fn vkGetImageSparseMemoryRequirements(
-    device: VkDevice,
-    image: VkImage,
-    pSparseMemoryRequirementCount: Option<NonNull<u32>>,
-    pSparseMemoryRequirements: Option<NonNull<VkSparseImageMemoryRequirements>>,
-) {
-    unimplemented!(
-        "vkGetImageSparseMemoryRequirements(
-        device,
-        image,
-        pSparseMemoryRequirementCount,
-        pSparseMemoryRequirements,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkGetRandROutputDisplayEXT(
     physicalDevice: VkPhysicalDevice,
     dpy: Option<NonNull<Display>>,
@@ -2517,10 +2585,6 @@ pub unsafe extern "C" fn vkCmdSetDepthBoundsTestEnable(
     unimplemented!("vkCmdSetDepthBoundsTestEnable(commandBuffer, depthBoundsTestEnable")
 }
 
-pub unsafe extern "C" fn vkResetEvent(device: VkDevice, event: VkEvent) -> VkResult {
-    unimplemented!("vkResetEvent(device, event")
-}
-
 pub unsafe extern "C" fn vkCmdSetViewportWScalingEnableNV(
     commandBuffer: VkCommandBuffer,
     viewportWScalingEnable: VkBool32,
@@ -2687,14 +2751,6 @@ pub unsafe extern "C" fn vkGetDrmDisplayEXT(
     unimplemented!("vkGetDrmDisplayEXT(physicalDevice, drmFd, connectorId, display")
 }
 
-pub unsafe extern "C" fn vkGetPhysicalDeviceWaylandPresentationSupportKHR(
-    physicalDevice: VkPhysicalDevice,
-    queueFamilyIndex: u32,
-    display: Option<NonNull<wl_display>>,
-) -> VkBool32 {
-    unimplemented!("vkGetPhysicalDeviceWaylandPresentationSupportKHR(physicalDevice, queueFamilyIndex, display")
-}
-
 pub unsafe extern "C" fn vkDebugMarkerSetObjectNameEXT(
     device: VkDevice,
     pNameInfo: Option<NonNull<VkDebugMarkerObjectNameInfoEXT>>,
@@ -2792,15 +2848,6 @@ pub unsafe extern "C" fn vkCreateRayTracingPipelinesNV(
     )
 }
 
-pub unsafe extern "C" fn vkCmdBeginQuery(
-    commandBuffer: VkCommandBuffer,
-    queryPool: VkQueryPool,
-    query: u32,
-    flags: VkQueryControlFlags,
-) {
-    unimplemented!("vkCmdBeginQuery(commandBuffer, queryPool, query, flags")
-}
-
 pub unsafe extern "C" fn vkReleasePerformanceConfigurationINTEL(
     device: VkDevice,
     configuration: VkPerformanceConfigurationINTEL,
@@ -2907,13 +2954,6 @@ pub unsafe extern "C" fn vkCreateShadersEXT(
     unimplemented!("vkCreateShadersEXT(device, createInfoCount, pCreateInfos, pAllocator, pShaders")
 }
 
-pub unsafe extern "C" fn vkGetPhysicalDeviceProperties2(
-    physicalDevice: VkPhysicalDevice,
-    pProperties: Option<NonNull<VkPhysicalDeviceProperties2>>,
-) {
-    unimplemented!("vkGetPhysicalDeviceProperties2(physicalDevice, pProperties")
-}
-
 pub unsafe extern "C" fn vkDebugMarkerSetObjectTagEXT(
     device: VkDevice,
     pTagInfo: Option<NonNull<VkDebugMarkerObjectTagInfoEXT>>,
@@ -3099,15 +3139,6 @@ pub unsafe extern "C" fn vkCreateImagePipeSurfaceFUCHSIA(
     unimplemented!("vkCreateImagePipeSurfaceFUCHSIA(instance, pCreateInfo, pAllocator, pSurface")
 }
 
-pub unsafe extern "C" fn vkCreateWaylandSurfaceKHR(
-    instance: VkInstance,
-    pCreateInfo: Option<NonNull<VkWaylandSurfaceCreateInfoKHR>>,
-    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
-    pSurface: Option<NonNull<VkSurfaceKHR>>,
-) -> VkResult {
-    unimplemented!("vkCreateWaylandSurfaceKHR(instance, pCreateInfo, pAllocator, pSurface")
-}
-
 pub unsafe extern "C" fn vkSetLocalDimmingAMD(
     device: VkDevice,
     swapChain: VkSwapchainKHR,
@@ -3116,15 +3147,6 @@ pub unsafe extern "C" fn vkSetLocalDimmingAMD(
     unimplemented!("vkSetLocalDimmingAMD(device, swapChain, localDimmingEnable")
 }
 
-pub unsafe extern "C" fn vkCmdWriteTimestamp2(
-    commandBuffer: VkCommandBuffer,
-    stage: VkPipelineStageFlags2,
-    queryPool: VkQueryPool,
-    query: u32,
-) {
-    unimplemented!("vkCmdWriteTimestamp2(commandBuffer, stage, queryPool, query")
-}
-
 pub unsafe extern "C" fn vkCmdBindVertexBuffers2(
     commandBuffer: VkCommandBuffer,
     firstBinding: u32,
@@ -3191,7 +3213,16 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceFormatProperties2(
     format: VkFormat,
     pFormatProperties: Option<NonNull<VkFormatProperties2>>,
 ) {
-    unimplemented!("vkGetPhysicalDeviceFormatProperties2(physicalDevice, format, pFormatProperties")
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
+    };
+
+    let Some(pFormatProperties) = pFormatProperties else {
+        unreachable!()
+    };
+
+    (*pFormatProperties.as_ptr()).formatProperties =
+        physicalDevice.lock().format_properties(format);
 }
 
 pub unsafe extern "C" fn vkCmdInsertDebugUtilsLabelEXT(
@@ -3288,13 +3319,25 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceQueueFamilyProperties2(
     pQueueFamilyPropertyCount: Option<NonNull<u32>>,
     pQueueFamilyProperties: Option<NonNull<VkQueueFamilyProperties2>>,
 ) {
-    unimplemented!(
-        "vkGetPhysicalDeviceQueueFamilyProperties2(
-        physicalDevice,
-        pQueueFamilyPropertyCount,
-        pQueueFamilyProperties,
-    "
-    )
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
+    };
+
+    let Some(pQueueFamilyPropertyCount) = pQueueFamilyPropertyCount else {
+        unreachable!()
+    };
+
+    let queue_family_properties = physicalDevice.lock().queue_family_properties();
+    let count = pQueueFamilyProperties.map_or(queue_family_properties.len(), |pProperties| {
+        let count = queue_family_properties
+            .len()
+            .min(*pQueueFamilyPropertyCount.as_ptr() as usize);
+        for (i, properties) in queue_family_properties.iter().take(count).enumerate() {
+            (*pProperties.as_ptr().add(i)).queueFamilyProperties = *properties;
+        }
+        count
+    });
+    *pQueueFamilyPropertyCount.as_ptr() = count as u32;
 }
 
 pub unsafe extern "C" fn vkCreateMetalSurfaceEXT(
@@ -3372,14 +3415,6 @@ pub unsafe extern "C" fn vkGetShaderBinaryDataEXT(
     unimplemented!("vkGetShaderBinaryDataEXT(device, shader, pDataSize, pData")
 }
 
-pub unsafe extern "C" fn vkGetMemoryFdKHR(
-    device: VkDevice,
-    pGetFdInfo: Option<NonNull<VkMemoryGetFdInfoKHR>>,
-    pFd: Option<NonNull<int>>,
-) -> VkResult {
-    unimplemented!("vkGetMemoryFdKHR(device, pGetFdInfo, pFd")
-}
-
 pub unsafe extern "C" fn vkGetSemaphoreZirconHandleFUCHSIA(
     device: VkDevice,
     pGetZirconHandleInfo: Option<NonNull<VkSemaphoreGetZirconHandleInfoFUCHSIA>>,
@@ -3388,14 +3423,6 @@ pub unsafe extern "C" fn vkGetSemaphoreZirconHandleFUCHSIA(
     unimplemented!("vkGetSemaphoreZirconHandleFUCHSIA(device, pGetZirconHandleInfo, pZirconHandle")
 }
 
-pub unsafe extern "C" fn vkDestroyDebugUtilsMessengerEXT(
-    instance: VkInstance,
-    messenger: VkDebugUtilsMessengerEXT,
-    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
-) {
-    unimplemented!("vkDestroyDebugUtilsMessengerEXT(instance, messenger, pAllocator")
-}
-
 pub unsafe extern "C" fn vkCmdSetSampleLocationsEXT(
     commandBuffer: VkCommandBuffer,
     pSampleLocationsInfo: Option<NonNull<VkSampleLocationsInfoEXT>>,
@@ -3605,15 +3632,6 @@ pub unsafe extern "C" fn vkGetFaultData(
     )
 }
 
-pub unsafe extern "C" fn vkQueueSubmit2(
-    queue: VkQueue,
-    submitCount: u32,
-    pSubmits: Option<NonNull<VkSubmitInfo2>>,
-    fence: VkFence,
-) -> VkResult {
-    unimplemented!("vkQueueSubmit2(queue, submitCount, pSubmits, fence")
-}
-
 pub unsafe extern "C" fn vkCmdDebugMarkerInsertEXT(
     commandBuffer: VkCommandBuffer,
     pMarkerInfo: Option<NonNull<VkDebugMarkerMarkerInfoEXT>>,
@@ -3637,26 +3655,6 @@ pub unsafe extern "C" fn vkCmdSetViewportShadingRatePaletteNV(
     )
 }
 
-pub unsafe extern "C" fn vkCmdPushDescriptorSetKHR(
-    commandBuffer: VkCommandBuffer,
-    pipelineBindPoint: VkPipelineBindPoint,
-    layout: VkPipelineLayout,
-    set: u32,
-    descriptorWriteCount: u32,
-    pDescriptorWrites: Option<NonNull<VkWriteDescriptorSet>>,
-) {
-    unimplemented!(
-        "vkCmdPushDescriptorSetKHR(
-        commandBuffer,
-        pipelineBindPoint,
-        layout,
-        set,
-        descriptorWriteCount,
-        pDescriptorWrites,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkCmdSetDeviceMask(commandBuffer: VkCommandBuffer, deviceMask: u32) {
     unimplemented!("vkCmdSetDeviceMask(commandBuffer, deviceMask")
 }
@@ -3684,26 +3682,6 @@ pub unsafe extern "C" fn vkCmdSetRasterizationSamplesEXT(
     unimplemented!("vkCmdSetRasterizationSamplesEXT(commandBuffer, rasterizationSamples")
 }
 
-pub unsafe extern "C" fn vkCmdClearDepthStencilImage(
-    commandBuffer: VkCommandBuffer,
-    image: VkImage,
-    imageLayout: VkImageLayout,
-    pDepthStencil: Option<NonNull<VkClearDepthStencilValue>>,
-    rangeCount: u32,
-    pRanges: Option<NonNull<VkImageSubresourceRange>>,
-) {
-    unimplemented!(
-        "vkCmdClearDepthStencilImage(
-        commandBuffer,
-        image,
-        imageLayout,
-        pDepthStencil,
-        rangeCount,
-        pRanges,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkCmdSetShadingRateImageEnableNV(
     commandBuffer: VkCommandBuffer,
     shadingRateImageEnable: VkBool32,
@@ -3758,15 +3736,6 @@ pub unsafe extern "C" fn vkCreateRenderPass2(
     unimplemented!("vkCreateRenderPass2(device, pCreateInfo, pAllocator, pRenderPass")
 }
 
-pub unsafe extern "C" fn vkCmdDispatch(
-    commandBuffer: VkCommandBuffer,
-    groupCountX: u32,
-    groupCountY: u32,
-    groupCountZ: u32,
-) {
-    unimplemented!("vkCmdDispatch(commandBuffer, groupCountX, groupCountY, groupCountZ")
-}
-
 pub unsafe extern "C" fn vkDestroyAccelerationStructureNV(
     device: VkDevice,
     accelerationStructure: VkAccelerationStructureNV,
@@ -3832,23 +3801,6 @@ pub unsafe extern "C" fn vkGetRayTracingShaderGroupStackSizeKHR(
     unimplemented!("vkGetRayTracingShaderGroupStackSizeKHR(device, pipeline, group, groupShader")
 }
 
-pub unsafe extern "C" fn vkResetDescriptorPool(
-    device: VkDevice,
-    descriptorPool: VkDescriptorPool,
-    flags: VkDescriptorPoolResetFlags,
-) -> VkResult {
-    unimplemented!("vkResetDescriptorPool(device, descriptorPool, flags")
-}
-
-pub unsafe extern "C" fn vkGetPipelineCacheData(
-    device: VkDevice,
-    pipelineCache: VkPipelineCache,
-    pDataSize: Option<NonNull<isize>>,
-    pData: Option<NonNull<std::ffi::c_void>>,
-) -> VkResult {
-    unimplemented!("vkGetPipelineCacheData(device, pipelineCache, pDataSize, pData")
-}
-
 pub unsafe extern "C" fn vkGetFenceSciSyncFenceNV(
     device: VkDevice,
     pGetSciSyncHandleInfo: Option<NonNull<VkFenceGetSciSyncInfoNV>>,
@@ -3899,16 +3851,6 @@ pub unsafe extern "C" fn vkCmdSetCoarseSampleOrderNV(
     )
 }
 
-pub unsafe extern "C" fn vkCmdFillBuffer(
-    commandBuffer: VkCommandBuffer,
-    dstBuffer: VkBuffer,
-    dstOffset: VkDeviceSize,
-    size: VkDeviceSize,
-    data: u32,
-) {
-    unimplemented!("vkCmdFillBuffer(commandBuffer, dstBuffer, dstOffset, size, data")
-}
-
 pub unsafe extern "C" fn vkCmdSetBlendConstants(
     commandBuffer: VkCommandBuffer,
     blendConstants: *const f32,
@@ -3922,22 +3864,26 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceImageFormatProperties2(
     pImageFormatInfo: Option<NonNull<VkPhysicalDeviceImageFormatInfo2>>,
     pImageFormatProperties: Option<NonNull<VkImageFormatProperties2>>,
 ) -> VkResult {
-    unimplemented!(
-        "vkGetPhysicalDeviceImageFormatProperties2(
-        physicalDevice,
-        pImageFormatInfo,
-        pImageFormatProperties,
-    "
-    )
-}
+    let physicalDevice = match PhysicalDevice::lookup(physicalDevice) {
+        Ok(physicalDevice) => physicalDevice,
+        Err(err) => return err.to_vk_result(),
+    };
 
-pub unsafe extern "C" fn vkCreateEvent(
-    device: VkDevice,
-    pCreateInfo: Option<NonNull<VkEventCreateInfo>>,
-    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
-    pEvent: Option<NonNull<VkEvent>>,
-) -> VkResult {
-    unimplemented!("vkCreateEvent(device, pCreateInfo, pAllocator, pEvent")
+    let Some(pImageFormatInfo) = pImageFormatInfo else {
+        unreachable!()
+    };
+    let image_format_info = pImageFormatInfo.as_ref();
+
+    let Some(pImageFormatProperties) = pImageFormatProperties else {
+        unreachable!()
+    };
+
+    let properties = physicalDevice
+        .lock()
+        .image_format_properties2(image_format_info, &mut *pImageFormatProperties.as_ptr());
+    properties.map_or(VkResult::VK_ERROR_FORMAT_NOT_SUPPORTED, |()| {
+        VkResult::VK_SUCCESS
+    })
 }
 
 pub unsafe extern "C" fn vkBindBufferMemory2(
@@ -4206,10 +4152,6 @@ pub unsafe extern "C" fn vkCmdEndDebugUtilsLabelEXT(commandBuffer: VkCommandBuff
     unimplemented!("vkCmdEndDebugUtilsLabelEXT(commandBuffer")
 }
 
-pub unsafe extern "C" fn vkCmdEndRendering(commandBuffer: VkCommandBuffer) {
-    unimplemented!("vkCmdEndRendering(commandBuffer")
-}
-
 pub unsafe extern "C" fn vkGetPerformanceParameterINTEL(
     device: VkDevice,
     parameter: VkPerformanceParameterTypeINTEL,
@@ -4311,13 +4253,22 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceExternalBufferProperties(
     pExternalBufferInfo: Option<NonNull<VkPhysicalDeviceExternalBufferInfo>>,
     pExternalBufferProperties: Option<NonNull<VkExternalBufferProperties>>,
 ) {
-    unimplemented!(
-        "vkGetPhysicalDeviceExternalBufferProperties(
-        physicalDevice,
-        pExternalBufferInfo,
-        pExternalBufferProperties,
-    "
-    )
+    let Ok(_physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return;
+    };
+
+    let Some(pExternalBufferInfo) = pExternalBufferInfo else {
+        unreachable!()
+    };
+    let external_buffer_info = pExternalBufferInfo.as_ref();
+
+    let Some(pExternalBufferProperties) = pExternalBufferProperties else {
+        unreachable!()
+    };
+
+    // Buffers are bound to external memory like any other, whatever their usage.
+    (*pExternalBufferProperties.as_ptr()).externalMemoryProperties =
+        runtime::memory::external_memory_properties(external_buffer_info.handleType);
 }
 
 pub unsafe extern "C" fn vkCmdSetExclusiveScissorNV(
@@ -4644,14 +4595,6 @@ pub unsafe extern "C" fn vkCmdSetLogicOpEnableEXT(
     unimplemented!("vkCmdSetLogicOpEnableEXT(commandBuffer, logicOpEnable")
 }
 
-pub unsafe extern "C" fn vkCmdSetDepthBounds(
-    commandBuffer: VkCommandBuffer,
-    minDepthBounds: f32,
-    maxDepthBounds: f32,
-) {
-    unimplemented!("vkCmdSetDepthBounds(commandBuffer, minDepthBounds, maxDepthBounds")
-}
-
 pub unsafe extern "C" fn vkMapMemory2KHR(
     device: VkDevice,
     pMemoryMapInfo: Option<NonNull<VkMemoryMapInfoKHR>>,
@@ -4820,15 +4763,6 @@ pub unsafe extern "C" fn vkCmdSetDiscardRectangleModeEXT(
     unimplemented!("vkCmdSetDiscardRectangleModeEXT(commandBuffer, discardRectangleMode")
 }
 
-pub unsafe extern "C" fn vkCmdResetQueryPool(
-    commandBuffer: VkCommandBuffer,
-    queryPool: VkQueryPool,
-    firstQuery: u32,
-    queryCount: u32,
-) {
-    unimplemented!("vkCmdResetQueryPool(commandBuffer, queryPool, firstQuery, queryCount")
-}
-
 pub unsafe extern "C" fn vkCmdSetConservativeRasterizationModeEXT(
     commandBuffer: VkCommandBuffer,
     conservativeRasterizationMode: VkConservativeRasterizationModeEXT,
@@ -4921,14 +4855,6 @@ pub unsafe extern "C" fn vkDestroyVideoSessionParametersKHR(
     unimplemented!("vkDestroyVideoSessionParametersKHR(device, videoSessionParameters, pAllocator")
 }
 
-pub unsafe extern "C" fn vkCmdResetEvent(
-    commandBuffer: VkCommandBuffer,
-    event: VkEvent,
-    stageMask: VkPipelineStageFlags,
-) {
-    unimplemented!("vkCmdResetEvent(commandBuffer, event, stageMask")
-}
-
 pub unsafe extern "C" fn vkCmdDrawMeshTasksIndirectNV(
     commandBuffer: VkCommandBuffer,
     buffer: VkBuffer,
@@ -4955,15 +4881,6 @@ pub unsafe extern "C" fn vkGetMemoryHostPointerPropertiesEXT(
     )
 }
 
-pub unsafe extern "C" fn vkCmdWriteTimestamp(
-    commandBuffer: VkCommandBuffer,
-    pipelineStage: VkPipelineStageFlagBits,
-    queryPool: VkQueryPool,
-    query: u32,
-) {
-    unimplemented!("vkCmdWriteTimestamp(commandBuffer, pipelineStage, queryPool, query")
-}
-
 pub unsafe extern "C" fn vkCreateAccelerationStructureNV(
     device: VkDevice,
     pCreateInfo: Option<NonNull<VkAccelerationStructureCreateInfoNV>>,
@@ -4975,15 +4892,6 @@ pub unsafe extern "C" fn vkCreateAccelerationStructureNV(
     )
 }
 
-pub unsafe extern "C" fn vkGetMemoryFdPropertiesKHR(
-    device: VkDevice,
-    handleType: VkExternalMemoryHandleTypeFlagBits,
-    fd: int,
-    pMemoryFdProperties: Option<NonNull<VkMemoryFdPropertiesKHR>>,
-) -> VkResult {
-    unimplemented!("vkGetMemoryFdPropertiesKHR(device, handleType, fd, pMemoryFdProperties")
-}
-
 pub unsafe extern "C" fn vkCmdSetAlphaToCoverageEnableEXT(
     commandBuffer: VkCommandBuffer,
     alphaToCoverageEnable: VkBool32,
@@ -5124,14 +5032,6 @@ pub unsafe extern "C" fn vkCopyAccelerationStructureToMemoryKHR(
     unimplemented!("vkCopyAccelerationStructureToMemoryKHR(device, deferredOperation, pInfo")
 }
 
-pub unsafe extern "C" fn vkWaitSemaphores(
-    device: VkDevice,
-    pWaitInfo: Option<NonNull<VkSemaphoreWaitInfo>>,
-    timeout: u64,
-) -> VkResult {
-    unimplemented!("vkWaitSemaphores(device, pWaitInfo, timeout")
-}
-
 pub unsafe extern "C" fn vkGetSemaphoreSciSyncObjNV(
     device: VkDevice,
     pGetSciSyncInfo: Option<NonNull<VkSemaphoreGetSciSyncInfoNV>>,
@@ -5140,15 +5040,6 @@ pub unsafe extern "C" fn vkGetSemaphoreSciSyncObjNV(
     unimplemented!("vkGetSemaphoreSciSyncObjNV(device, pGetSciSyncInfo, pHandle")
 }
 
-pub unsafe extern "C" fn vkCreateDebugUtilsMessengerEXT(
-    instance: VkInstance,
-    pCreateInfo: Option<NonNull<VkDebugUtilsMessengerCreateInfoEXT>>,
-    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
-    pMessenger: Option<NonNull<VkDebugUtilsMessengerEXT>>,
-) -> VkResult {
-    unimplemented!("vkCreateDebugUtilsMessengerEXT(instance, pCreateInfo, pAllocator, pMessenger")
-}
-
 pub unsafe extern "C" fn vkGetPhysicalDevicePresentRectanglesKHR(
     physicalDevice: VkPhysicalDevice,
     surface: VkSurfaceKHR,
@@ -5293,13 +5184,6 @@ pub unsafe extern "C" fn vkWaitForPresentKHR(
     unimplemented!("vkWaitForPresentKHR(device, swapchain, presentId, timeout")
 }
 
-pub unsafe extern "C" fn vkResetCommandBuffer(
-    commandBuffer: VkCommandBuffer,
-    flags: VkCommandBufferResetFlags,
-) -> VkResult {
-    unimplemented!("vkResetCommandBuffer(commandBuffer, flags")
-}
-
 pub unsafe extern "C" fn vkSetDeviceMemoryPriorityEXT(
     device: VkDevice,
     memory: VkDeviceMemory,
@@ -5324,23 +5208,6 @@ pub unsafe extern "C" fn vkGetMicromapBuildSizesEXT(
     unimplemented!("vkGetMicromapBuildSizesEXT(device, buildType, pBuildInfo, pSizeInfo")
 }
 
-pub unsafe extern "C" fn vkGetQueryPoolResults(
-    device: VkDevice,
-    queryPool: VkQueryPool,
-    firstQuery: u32,
-    queryCount: u32,
-    dataSize: isize,
-    pData: Option<NonNull<std::ffi::c_void>>,
-    stride: VkDeviceSize,
-    flags: VkQueryResultFlags,
-) -> VkResult {
-    unimplemented!(
-        "vkGetQueryPoolResults(
-        device, queryPool, firstQuery, queryCount, dataSize, pData, stride, flags,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkGetDeviceFaultInfoEXT(
     device: VkDevice,
     pFaultCounts: Option<NonNull<VkDeviceFaultCountsEXT>>,
@@ -5398,24 +5265,6 @@ pub unsafe extern "C" fn vkGetDisplayPlaneSupportedDisplaysKHR(
     unimplemented!("vkGetDisplayPlaneSupportedDisplaysKHR(physicalDevice, planeIndex, pDisplayCount, pDisplays")
 }
 
-pub unsafe extern "C" fn vkCmdUpdateBuffer(
-    commandBuffer: VkCommandBuffer,
-    dstBuffer: VkBuffer,
-    dstOffset: VkDeviceSize,
-    dataSize: VkDeviceSize,
-    pData: Option<NonNull<std::ffi::c_void>>,
-) {
-    unimplemented!("vkCmdUpdateBuffer(commandBuffer, dstBuffer, dstOffset, dataSize, pData")
-}
-
-pub unsafe extern "C" fn vkCmdSetEvent(
-    commandBuffer: VkCommandBuffer,
-    event: VkEvent,
-    stageMask: VkPipelineStageFlags,
-) {
-    unimplemented!("vkCmdSetEvent(commandBuffer, event, stageMask")
-}
-
 pub unsafe extern "C" fn vkCmdSetTessellationDomainOriginEXT(
     commandBuffer: VkCommandBuffer,
     domainOrigin: VkTessellationDomainOrigin,
@@ -5423,14 +5272,6 @@ pub unsafe extern "C" fn vkCmdSetTessellationDomainOriginEXT(
     unimplemented!("vkCmdSetTessellationDomainOriginEXT(commandBuffer, domainOrigin")
 }
 
-pub unsafe extern "C" fn vkCmdEndQuery(
-    commandBuffer: VkCommandBuffer,
-    queryPool: VkQueryPool,
-    query: u32,
-) {
-    unimplemented!("vkCmdEndQuery(commandBuffer, queryPool, query")
-}
-
 pub unsafe extern "C" fn vkGetPhysicalDeviceDisplayPropertiesKHR(
     physicalDevice: VkPhysicalDevice,
     pPropertyCount: Option<NonNull<u32>>,
@@ -5524,13 +5365,6 @@ pub unsafe extern "C" fn vkCmdSetScissorWithCount(
     unimplemented!("vkCmdSetScissorWithCount(commandBuffer, scissorCount, pScissors")
 }
 
-pub unsafe extern "C" fn vkCmdNextSubpass(
-    commandBuffer: VkCommandBuffer,
-    contents: VkSubpassContents,
-) {
-    unimplemented!("vkCmdNextSubpass(commandBuffer, contents")
-}
-
 pub unsafe extern "C" fn vkCmdBindTransformFeedbackBuffersEXT(
     commandBuffer: VkCommandBuffer,
     firstBinding: u32,
@@ -5566,13 +5400,6 @@ pub unsafe extern "C" fn vkGetRayTracingCaptureReplayShaderGroupHandlesKHR(
     )
 }
 
-pub unsafe extern "C" fn vkCmdBeginRendering(
-    commandBuffer: VkCommandBuffer,
-    pRenderingInfo: Option<NonNull<VkRenderingInfo>>,
-) {
-    unimplemented!("vkCmdBeginRendering(commandBuffer, pRenderingInfo")
-}
-
 pub unsafe extern "C" fn vkGetAccelerationStructureMemoryRequirementsNV(
     device: VkDevice,
     pInfo: Option<NonNull<VkAccelerationStructureMemoryRequirementsInfoNV>>,
@@ -5650,14 +5477,6 @@ pub unsafe extern "C" fn vkGetFenceFdKHR(
     unimplemented!("vkGetFenceFdKHR(device, pGetFdInfo, pFd")
 }
 
-pub unsafe extern "C" fn vkGetSemaphoreCounterValue(
-    device: VkDevice,
-    semaphore: VkSemaphore,
-    pValue: Option<NonNull<u64>>,
-) -> VkResult {
-    unimplemented!("vkGetSemaphoreCounterValue(device, semaphore, pValue")
-}
-
 pub unsafe extern "C" fn vkCmdSetStencilTestEnable(
     commandBuffer: VkCommandBuffer,
     stencilTestEnable: VkBool32,
@@ -5728,17 +5547,6 @@ pub unsafe extern "C" fn vkGetSwapchainGrallocUsageANDROID(
     unimplemented!("vkGetSwapchainGrallocUsageANDROID(device, format, imageUsage, grallocUsage")
 }
 
-pub unsafe extern "C" fn vkSubmitDebugUtilsMessageEXT(
-    instance: VkInstance,
-    messageSeverity: VkDebugUtilsMessageSeverityFlagBitsEXT,
-    messageTypes: VkDebugUtilsMessageTypeFlagsEXT,
-    pCallbackData: Option<NonNull<VkDebugUtilsMessengerCallbackDataEXT>>,
-) {
-    unimplemented!(
-        "vkSubmitDebugUtilsMessageEXT(instance, messageSeverity, messageTypes, pCallbackData"
-    )
-}
-
 pub unsafe extern "C" fn vkCmdSetPolygonModeEXT(
     commandBuffer: VkCommandBuffer,
     polygonMode: VkPolygonMode,
@@ -5760,15 +5568,6 @@ pub unsafe extern "C" fn vkCmdBeginConditionalRenderingEXT(
     unimplemented!("vkCmdBeginConditionalRenderingEXT(commandBuffer, pConditionalRenderingBegin")
 }
 
-pub unsafe extern "C" fn vkResetQueryPool(
-    device: VkDevice,
-    queryPool: VkQueryPool,
-    firstQuery: u32,
-    queryCount: u32,
-) {
-    unimplemented!("vkResetQueryPool(device, queryPool, firstQuery, queryCount")
-}
-
 pub unsafe extern "C" fn vkWriteMicromapsPropertiesEXT(
     device: VkDevice,
     micromapCount: u32,
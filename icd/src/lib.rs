@@ -3,11 +3,14 @@
 
 mod buffer;
 mod command_buffer;
+mod debug_utils;
 mod descriptor;
+mod event;
 mod image;
 mod impls;
 mod memory;
 mod pipeline;
+mod query;
 mod sampler;
 mod surface;
 mod swapchain;
@@ -91,6 +94,49 @@ pub unsafe extern "C" fn vk_icdGetInstanceProcAddr(
         "vkGetPhysicalDeviceSparseImageFormatProperties" => unsafe {
             std::mem::transmute(vkGetPhysicalDeviceSparseImageFormatProperties as *const ())
         },
+        /* VK_KHR_get_physical_device_properties2 extension instance commands */
+        "vkGetPhysicalDeviceFeatures2" | "vkGetPhysicalDeviceFeatures2KHR" => unsafe {
+            std::mem::transmute(vkGetPhysicalDeviceFeatures2 as *const ())
+        },
+        "vkGetPhysicalDeviceProperties2" | "vkGetPhysicalDeviceProperties2KHR" => unsafe {
+            std::mem::transmute(vkGetPhysicalDeviceProperties2 as *const ())
+        },
+        "vkGetPhysicalDeviceFormatProperties2" | "vkGetPhysicalDeviceFormatProperties2KHR" => unsafe {
+            std::mem::transmute(vkGetPhysicalDeviceFormatProperties2 as *const ())
+        },
+        "vkGetPhysicalDeviceImageFormatProperties2"
+        | "vkGetPhysicalDeviceImageFormatProperties2KHR" => unsafe {
+            std::mem::transmute(vkGetPhysicalDeviceImageFormatProperties2 as *const ())
+        },
+        "vkGetPhysicalDeviceQueueFamilyProperties2"
+        | "vkGetPhysicalDeviceQueueFamilyProperties2KHR" => unsafe {
+            std::mem::transmute(vkGetPhysicalDeviceQueueFamilyProperties2 as *const ())
+        },
+        "vkGetPhysicalDeviceMemoryProperties2" | "vkGetPhysicalDeviceMemoryProperties2KHR" => unsafe {
+            std::mem::transmute(vkGetPhysicalDeviceMemoryProperties2 as *const ())
+        },
+        "vkGetPhysicalDeviceSparseImageFormatProperties2"
+        | "vkGetPhysicalDeviceSparseImageFormatProperties2KHR" => unsafe {
+            std::mem::transmute(vkGetPhysicalDeviceSparseImageFormatProperties2 as *const ())
+        },
+        /* VK_KHR_external_memory_capabilities extension instance commands */
+        "vkGetPhysicalDeviceExternalBufferProperties"
+        | "vkGetPhysicalDeviceExternalBufferPropertiesKHR" => unsafe {
+            std::mem::transmute(vkGetPhysicalDeviceExternalBufferProperties as *const ())
+        },
+        /* VK_EXT_debug_utils extension instance commands */
+        "vkCreateDebugUtilsMessengerEXT" => unsafe {
+            std::mem::transmute(debug_utils::vkCreateDebugUtilsMessengerEXT as *const ())
+        },
+        "vkDestroyDebugUtilsMessengerEXT" => unsafe {
+            std::mem::transmute(debug_utils::vkDestroyDebugUtilsMessengerEXT as *const ())
+        },
+        "vkSubmitDebugUtilsMessageEXT" => unsafe {
+            std::mem::transmute(debug_utils::vkSubmitDebugUtilsMessageEXT as *const ())
+        },
+        "vkSetDebugUtilsObjectNameEXT" => unsafe {
+            std::mem::transmute(debug_utils::vkSetDebugUtilsObjectNameEXT as *const ())
+        },
         /* VK_KHR_surface extension instance commands */
         "vkDestroySurfaceKHR" => unsafe {
             std::mem::transmute(surface::vkDestroySurfaceKHR as *const ())
@@ -114,6 +160,19 @@ pub unsafe extern "C" fn vk_icdGetInstanceProcAddr(
         "vkGetPhysicalDeviceXcbPresentationSupportKHR" => unsafe {
             std::mem::transmute(surface::vkGetPhysicalDeviceXcbPresentationSupportKHR as *const ())
         },
+        /* VK_KHR_wayland_surface extension instance commands */
+        "vkCreateWaylandSurfaceKHR" => unsafe {
+            std::mem::transmute(surface::vkCreateWaylandSurfaceKHR as *const ())
+        },
+        "vkGetPhysicalDeviceWaylandPresentationSupportKHR" => unsafe {
+            std::mem::transmute(
+                surface::vkGetPhysicalDeviceWaylandPresentationSupportKHR as *const (),
+            )
+        },
+        /* VK_EXT_headless_surface extension instance commands */
+        "vkCreateHeadlessSurfaceEXT" => unsafe {
+            std::mem::transmute(surface::vkCreateHeadlessSurfaceEXT as *const ())
+        },
 
         /* VK_KHR_swapchain extension instance commands */
         "vkGetPhysicalDevicePresentRectanglesKHR" => unsafe {
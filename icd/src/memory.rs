@@ -13,8 +13,9 @@ pub unsafe extern "C" fn vkAllocateMemory(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pMemory: Option<NonNull<VkDeviceMemory>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pAllocateInfo) = pAllocateInfo else {
@@ -28,22 +29,78 @@ pub unsafe extern "C" fn vkAllocateMemory(
         unreachable!()
     };
 
-    *pMemory.as_ptr() = MemoryAllocation::create(
-        device,
-        allocate_info.allocationSize,
-        allocate_info.memoryTypeIndex,
-    );
+    let export_handle_types = export_memory_allocate_info(allocate_info.pNext)
+        .map_or(0, |export_info| export_info.handleTypes);
+    *pMemory.as_ptr() = match import_memory_fd_info(allocate_info.pNext) {
+        Some(import_fd_info) => match MemoryAllocation::import_fd(
+            device,
+            allocate_info.allocationSize,
+            allocate_info.memoryTypeIndex,
+            import_fd_info.handleType,
+            import_fd_info.fd,
+        ) {
+            Ok(object) => object,
+            Err(err) => return err.to_vk_result(),
+        },
+        None if export_handle_types != 0 => match MemoryAllocation::create_exportable(
+            device,
+            allocate_info.allocationSize,
+            allocate_info.memoryTypeIndex,
+            export_handle_types,
+        ) {
+            Ok(object) => object,
+            Err(err) => return err.to_vk_result(),
+        },
+        None => match MemoryAllocation::create(
+            device,
+            allocate_info.allocationSize,
+            allocate_info.memoryTypeIndex,
+        ) {
+            Ok(object) => object,
+            Err(err) => return err.to_vk_result(),
+        },
+    };
 
     VkResult::VK_SUCCESS
 }
 
+/// Returns `VkImportMemoryFdInfoKHR` of `pNext` chain of allocate info, if any.
+unsafe fn import_memory_fd_info<'a>(
+    pNext: Option<NonNull<std::ffi::c_void>>,
+) -> Option<&'a VkImportMemoryFdInfoKHR> {
+    let mut next = pNext.map(NonNull::cast::<VkBaseInStructure>);
+    while let Some(structure) = next {
+        let structure = structure.as_ref();
+        if structure.sType == VkStructureType::VK_STRUCTURE_TYPE_IMPORT_MEMORY_FD_INFO_KHR {
+            return Some(NonNull::from(structure).cast().as_ref());
+        }
+        next = structure.pNext;
+    }
+    None
+}
+
+/// Returns `VkExportMemoryAllocateInfo` of `pNext` chain of allocate info, if any.
+unsafe fn export_memory_allocate_info<'a>(
+    pNext: Option<NonNull<std::ffi::c_void>>,
+) -> Option<&'a VkExportMemoryAllocateInfo> {
+    let mut next = pNext.map(NonNull::cast::<VkBaseInStructure>);
+    while let Some(structure) = next {
+        let structure = structure.as_ref();
+        if structure.sType == VkStructureType::VK_STRUCTURE_TYPE_EXPORT_MEMORY_ALLOCATE_INFO {
+            return Some(NonNull::from(structure).cast().as_ref());
+        }
+        next = structure.pNext;
+    }
+    None
+}
+
 pub unsafe extern "C" fn vkFreeMemory(
     device: VkDevice,
     memory: VkDeviceMemory,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -59,12 +116,13 @@ pub unsafe extern "C" fn vkMapMemory(
     _flags: VkMemoryMapFlags,
     ppData: Option<NonNull<NonNull<std::ffi::c_void>>>,
 ) -> VkResult {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
-    };
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
 
-    let Some(memory) = MemoryAllocation::from_handle(memory) else {
-        unreachable!()
+    let memory = match MemoryAllocation::lookup(memory) {
+        Ok(memory) => memory,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pData) = ppData else { unreachable!() };
@@ -80,12 +138,12 @@ pub unsafe extern "C" fn vkMapMemory(
 }
 
 pub unsafe extern "C" fn vkUnmapMemory(device: VkDevice, memory: VkDeviceMemory) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
-    let Some(memory) = MemoryAllocation::from_handle(memory) else {
-        unreachable!()
+    let Ok(memory) = MemoryAllocation::lookup(memory) else {
+        return;
     };
 
     memory.lock().unmap_host();
@@ -96,8 +154,9 @@ pub unsafe extern "C" fn vkFlushMappedMemoryRanges(
     memoryRangeCount: u32,
     pMemoryRanges: Option<NonNull<VkMappedMemoryRange>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let memory_ranges = pMemoryRanges.map_or(&[] as &[_], |x| {
@@ -113,8 +172,9 @@ pub unsafe extern "C" fn vkInvalidateMappedMemoryRanges(
     memoryRangeCount: u32,
     pMemoryRanges: Option<NonNull<VkMappedMemoryRange>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let memory_ranges = pMemoryRanges.map_or(&[] as &[_], |x| {
@@ -124,3 +184,70 @@ pub unsafe extern "C" fn vkInvalidateMappedMemoryRanges(
     let result = device.lock().invalidate_memory_ranges(memory_ranges);
     result
 }
+
+pub unsafe extern "C" fn vkGetMemoryFdPropertiesKHR(
+    device: VkDevice,
+    handleType: VkExternalMemoryHandleTypeFlagBits,
+    fd: int,
+    pMemoryFdProperties: Option<NonNull<VkMemoryFdPropertiesKHR>>,
+) -> VkResult {
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pMemoryFdProperties) = pMemoryFdProperties else {
+        unreachable!()
+    };
+
+    let _ = fd;
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkGetMemoryFdPropertiesKHR-handleType-00674
+    if handleType
+        != VkExternalMemoryHandleTypeFlagBits::VK_EXTERNAL_MEMORY_HANDLE_TYPE_DMA_BUF_BIT_EXT
+    {
+        return VkResult::VK_ERROR_INVALID_EXTERNAL_HANDLE;
+    }
+    // Any fd can be mapped as memory of any type.
+    let memory_type_count = device
+        .lock()
+        .physical_device()
+        .memory_properties()
+        .memoryTypeCount;
+    (*pMemoryFdProperties.as_ptr()).memoryTypeBits = (1 << memory_type_count) - 1;
+
+    VkResult::VK_SUCCESS
+}
+
+pub unsafe extern "C" fn vkGetMemoryFdKHR(
+    device: VkDevice,
+    pGetFdInfo: Option<NonNull<VkMemoryGetFdInfoKHR>>,
+    pFd: Option<NonNull<int>>,
+) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let Some(pGetFdInfo) = pGetFdInfo else {
+        unreachable!()
+    };
+    let get_fd_info = pGetFdInfo.as_ref();
+    assert_eq!(
+        get_fd_info.sType,
+        VkStructureType::VK_STRUCTURE_TYPE_MEMORY_GET_FD_INFO_KHR
+    );
+
+    let memory = match MemoryAllocation::lookup(get_fd_info.memory) {
+        Ok(memory) => memory,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pFd) = pFd else { unreachable!() };
+
+    let fd = memory.lock().export_fd(get_fd_info.handleType);
+    *pFd.as_ptr() = match fd {
+        Ok(fd) => fd,
+        Err(err) => return err,
+    };
+
+    VkResult::VK_SUCCESS
+}
@@ -15,8 +15,9 @@ pub unsafe extern "C" fn vkCreatePipelineLayout(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pPipelineLayout: Option<NonNull<VkPipelineLayout>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -36,8 +37,15 @@ pub unsafe extern "C" fn vkCreatePipelineLayout(
         unreachable!()
     };
 
-    *pPipelineLayout.as_ptr() =
-        PipelineLayout::create(device, create_info.flags, set_layouts, push_constant_ranges);
+    *pPipelineLayout.as_ptr() = match PipelineLayout::create(
+        device,
+        create_info.flags,
+        set_layouts,
+        push_constant_ranges,
+    ) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -47,8 +55,8 @@ pub unsafe extern "C" fn vkDestroyPipelineLayout(
     pipelineLayout: VkPipelineLayout,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -62,8 +70,9 @@ pub unsafe extern "C" fn vkCreateRenderPass(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pRenderPass: Option<NonNull<VkRenderPass>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -139,7 +148,10 @@ pub unsafe extern "C" fn vkCreateRenderPass(
         unreachable!()
     };
 
-    *pRenderPass.as_ptr() = RenderPass::create(device, attachments, dependencies, subpasses);
+    *pRenderPass.as_ptr() = match RenderPass::create(device, attachments, dependencies, subpasses) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -149,8 +161,8 @@ pub unsafe extern "C" fn vkDestroyRenderPass(
     renderPass: VkRenderPass,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -164,8 +176,9 @@ pub unsafe extern "C" fn vkCreateShaderModule(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pShaderModule: Option<NonNull<VkShaderModule>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -185,7 +198,10 @@ pub unsafe extern "C" fn vkCreateShaderModule(
         unreachable!()
     };
 
-    *pShaderModule.as_ptr() = ShaderModule::create(device, create_info.flags, code);
+    *pShaderModule.as_ptr() = match ShaderModule::create(device, create_info.flags, code) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -195,8 +211,8 @@ pub unsafe extern "C" fn vkDestroyShaderModule(
     shaderModule: VkShaderModule,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -210,8 +226,9 @@ pub unsafe extern "C" fn vkCreatePipelineCache(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pPipelineCache: Option<NonNull<VkPipelineCache>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -228,7 +245,11 @@ pub unsafe extern "C" fn vkCreatePipelineCache(
         unreachable!()
     };
 
-    *pPipelineCache.as_ptr() = PipelineCache::create(device, create_info.flags, initial_data);
+    *pPipelineCache.as_ptr() = match PipelineCache::create(device, create_info.flags, initial_data)
+    {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -238,8 +259,8 @@ pub unsafe extern "C" fn vkDestroyPipelineCache(
     pipelineCache: VkPipelineCache,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -247,6 +268,44 @@ pub unsafe extern "C" fn vkDestroyPipelineCache(
     PipelineCache::drop_handle(pipelineCache);
 }
 
+pub unsafe extern "C" fn vkGetPipelineCacheData(
+    device: VkDevice,
+    pipelineCache: VkPipelineCache,
+    pDataSize: Option<NonNull<isize>>,
+    pData: Option<NonNull<std::ffi::c_void>>,
+) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let pipelineCache = match PipelineCache::lookup(pipelineCache) {
+        Ok(pipelineCache) => pipelineCache,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pDataSize) = pDataSize else {
+        unreachable!()
+    };
+
+    let data = pipelineCache.lock().get_data();
+    pData.map_or_else(
+        || {
+            *pDataSize.as_ptr() = data.len() as isize;
+            VkResult::VK_SUCCESS
+        },
+        |pData| {
+            // Partial data would not be valid initial data of other cache.
+            if (*pDataSize.as_ptr() as usize) < data.len() {
+                *pDataSize.as_ptr() = 0;
+                return VkResult::VK_INCOMPLETE;
+            }
+            std::ptr::copy_nonoverlapping(data.as_ptr(), pData.as_ptr() as *mut u8, data.len());
+            *pDataSize.as_ptr() = data.len() as isize;
+            VkResult::VK_SUCCESS
+        },
+    )
+}
+
 pub unsafe extern "C" fn vkCreateGraphicsPipelines(
     device: VkDevice,
     pipelineCache: VkPipelineCache,
@@ -257,8 +316,9 @@ pub unsafe extern "C" fn vkCreateGraphicsPipelines(
 ) -> VkResult {
     let mut result = VkResult::VK_SUCCESS;
 
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let pipelineCache = PipelineCache::from_handle(pipelineCache);
@@ -281,13 +341,14 @@ pub unsafe extern "C" fn vkCreateGraphicsPipelines(
             .map_or(&[] as &[VkPipelineShaderStageCreateInfo], |x| {
                 std::slice::from_raw_parts(x.as_ptr(), create_info.stageCount as usize)
             });
-        let shader_state = match PhysicalDevice::parse_shader_stages(shader_stages) {
-            Ok(inner) => inner,
-            Err(err) => {
-                result = err;
-                continue;
-            }
-        };
+        let shader_state =
+            match PhysicalDevice::parse_shader_stages(shader_stages, pipelineCache.as_deref()) {
+                Ok(inner) => inner,
+                Err(err) => {
+                    result = err;
+                    continue;
+                }
+            };
         let vertex_input_state = create_info
             .pVertexInputState
             .map(|x| PhysicalDevice::parse_vertex_input_state(*x.as_ref()));
@@ -303,10 +364,16 @@ pub unsafe extern "C" fn vkCreateGraphicsPipelines(
             .pRasterizationState
             .map(|x| PhysicalDevice::parse_rasterization_state(*x.as_ref()));
         let _multisample_state = create_info.pMultisampleState.map(|x| x.as_ref());
-        let _depth_stencil_state = create_info.pDepthStencilState.map(|x| x.as_ref());
-        let _color_blend_state = create_info.pColorBlendState.map(|x| x.as_ref());
-        let _dynamic_state = create_info.pDynamicState.map(|x| x.as_ref());
-        *pipeline = Pipeline::create(
+        let depth_stencil_state = create_info
+            .pDepthStencilState
+            .map(|x| PhysicalDevice::parse_depth_stencil_state(*x.as_ref()));
+        let color_blend_state = create_info
+            .pColorBlendState
+            .map(|x| PhysicalDevice::parse_color_blend_state(*x.as_ref()));
+        let dynamic_state = create_info
+            .pDynamicState
+            .map(|x| PhysicalDevice::parse_dynamic_state(*x.as_ref()));
+        *pipeline = match Pipeline::create(
             device.clone(),
             pipelineCache.clone(),
             shader_state,
@@ -314,7 +381,68 @@ pub unsafe extern "C" fn vkCreateGraphicsPipelines(
             input_assembly_state,
             viewport_state,
             rasterization_state,
-        );
+            depth_stencil_state,
+            color_blend_state,
+            dynamic_state,
+        ) {
+            Ok(object) => object,
+            Err(err) => {
+                result = err.to_vk_result();
+                VK_NULL_HANDLE
+            }
+        };
+    }
+
+    result
+}
+
+pub unsafe extern "C" fn vkCreateComputePipelines(
+    device: VkDevice,
+    pipelineCache: VkPipelineCache,
+    createInfoCount: u32,
+    pCreateInfos: Option<NonNull<VkComputePipelineCreateInfo>>,
+    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
+    pPipelines: Option<NonNull<VkPipeline>>,
+) -> VkResult {
+    let mut result = VkResult::VK_SUCCESS;
+
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let pipelineCache = PipelineCache::from_handle(pipelineCache);
+
+    let _ = pAllocator;
+
+    let Some(pPipelines) = pPipelines else {
+        unreachable!()
+    };
+    let pipelines = std::slice::from_raw_parts_mut(pPipelines.as_ptr(), createInfoCount as usize);
+
+    let Some(pCreateInfos) = pCreateInfos else {
+        unreachable!()
+    };
+    let create_infos = std::slice::from_raw_parts(pCreateInfos.as_ptr(), createInfoCount as usize);
+
+    for (create_info, pipeline) in std::iter::zip(create_infos, pipelines) {
+        let shader = match PhysicalDevice::parse_shader_stage(
+            &create_info.stage,
+            pipelineCache.as_deref(),
+        ) {
+            Ok(inner) => inner,
+            Err(err) => {
+                result = err;
+                continue;
+            }
+        };
+        *pipeline = match ComputePipeline::create(device.clone(), pipelineCache.clone(), shader) {
+            Ok(object) => object,
+            Err(err) => {
+                result = err.to_vk_result();
+                VK_NULL_HANDLE
+            }
+        };
     }
 
     result
@@ -325,13 +453,15 @@ pub unsafe extern "C" fn vkDestroyPipeline(
     pipeline: VkPipeline,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
 
+    // Handles are unique across object types, so only the table holding pipeline drops it.
     Pipeline::drop_handle(pipeline);
+    ComputePipeline::drop_handle(pipeline);
 }
 
 pub unsafe extern "C" fn vkCreateFramebuffer(
@@ -340,8 +470,9 @@ pub unsafe extern "C" fn vkCreateFramebuffer(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pFramebuffer: Option<NonNull<VkFramebuffer>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -354,10 +485,15 @@ pub unsafe extern "C" fn vkCreateFramebuffer(
             std::slice::from_raw_parts(x.as_ptr(), create_info.attachmentCount as usize)
         })
         .iter()
-        .flat_map(|&handle| ImageView::from_handle(handle))
-        .collect::<Vec<_>>();
-    let Some(render_pass) = RenderPass::from_handle(create_info.renderPass) else {
-        unreachable!()
+        .map(|&handle| ImageView::lookup(handle))
+        .collect::<Result<Vec<_>, _>>();
+    let attachments = match attachments {
+        Ok(attachments) => attachments,
+        Err(err) => return err.to_vk_result(),
+    };
+    let render_pass = match RenderPass::lookup(create_info.renderPass) {
+        Ok(render_pass) => render_pass,
+        Err(err) => return err.to_vk_result(),
     };
 
     let _ = pAllocator;
@@ -366,7 +502,7 @@ pub unsafe extern "C" fn vkCreateFramebuffer(
         unreachable!()
     };
 
-    *pFramebuffer.as_ptr() = Framebuffer::create(
+    *pFramebuffer.as_ptr() = match Framebuffer::create(
         device,
         create_info.flags,
         create_info.width,
@@ -374,7 +510,10 @@ pub unsafe extern "C" fn vkCreateFramebuffer(
         create_info.layers,
         attachments,
         render_pass,
-    );
+    ) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -384,8 +523,8 @@ pub unsafe extern "C" fn vkDestroyFramebuffer(
     framebuffer: VkFramebuffer,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
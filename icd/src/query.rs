@@ -0,0 +1,188 @@
+//! VkQueryPool device commands
+
+use headers::vk_decls::*;
+use runtime::command_buffer::CommandBuffer;
+use runtime::context::{Dispatchable, NonDispatchable};
+use runtime::logical_device::LogicalDevice;
+use runtime::query::*;
+
+pub unsafe extern "C" fn vkCreateQueryPool(
+    device: VkDevice,
+    pCreateInfo: Option<NonNull<VkQueryPoolCreateInfo>>,
+    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
+    pQueryPool: Option<NonNull<VkQueryPool>>,
+) -> VkResult {
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pCreateInfo) = pCreateInfo else {
+        unreachable!()
+    };
+    let create_info = pCreateInfo.as_ref();
+
+    let _ = pAllocator;
+
+    let Some(pQueryPool) = pQueryPool else {
+        unreachable!()
+    };
+
+    *pQueryPool.as_ptr() = match QueryPool::create(device, create_info) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    VkResult::VK_SUCCESS
+}
+
+pub unsafe extern "C" fn vkDestroyQueryPool(
+    device: VkDevice,
+    queryPool: VkQueryPool,
+    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
+) {
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
+    };
+
+    let _ = pAllocator;
+
+    QueryPool::drop_handle(queryPool);
+}
+
+pub unsafe extern "C" fn vkGetQueryPoolResults(
+    device: VkDevice,
+    queryPool: VkQueryPool,
+    firstQuery: u32,
+    queryCount: u32,
+    dataSize: isize,
+    pData: Option<NonNull<std::ffi::c_void>>,
+    stride: VkDeviceSize,
+    flags: VkQueryResultFlags,
+) -> VkResult {
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
+
+    let queryPool = match QueryPool::lookup(queryPool) {
+        Ok(queryPool) => queryPool,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pData) = pData else { unreachable!() };
+    let data = std::slice::from_raw_parts_mut(pData.as_ptr() as *mut u8, dataSize as usize);
+
+    let result = queryPool
+        .lock()
+        .results(firstQuery, queryCount, data, stride, flags);
+    result
+}
+
+pub unsafe extern "C" fn vkResetQueryPool(
+    device: VkDevice,
+    queryPool: VkQueryPool,
+    firstQuery: u32,
+    queryCount: u32,
+) {
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
+    };
+
+    let Ok(queryPool) = QueryPool::lookup(queryPool) else {
+        return;
+    };
+
+    queryPool.lock().reset(firstQuery, queryCount);
+}
+
+pub unsafe extern "C" fn vkCmdResetQueryPool(
+    commandBuffer: VkCommandBuffer,
+    queryPool: VkQueryPool,
+    firstQuery: u32,
+    queryCount: u32,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(queryPool) = QueryPool::lookup(queryPool) else {
+        return;
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_reset_query_pool(queryPool, firstQuery, queryCount);
+}
+
+pub unsafe extern "C" fn vkCmdBeginQuery(
+    commandBuffer: VkCommandBuffer,
+    queryPool: VkQueryPool,
+    query: u32,
+    flags: VkQueryControlFlags,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(queryPool) = QueryPool::lookup(queryPool) else {
+        return;
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_begin_query(queryPool, query, flags);
+}
+
+pub unsafe extern "C" fn vkCmdEndQuery(
+    commandBuffer: VkCommandBuffer,
+    queryPool: VkQueryPool,
+    query: u32,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(queryPool) = QueryPool::lookup(queryPool) else {
+        return;
+    };
+
+    commandBuffer.lock().cmd_end_query(queryPool, query);
+}
+
+pub unsafe extern "C" fn vkCmdWriteTimestamp(
+    commandBuffer: VkCommandBuffer,
+    pipelineStage: VkPipelineStageFlagBits,
+    queryPool: VkQueryPool,
+    query: u32,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(queryPool) = QueryPool::lookup(queryPool) else {
+        return;
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_write_timestamp(pipelineStage.0.into(), queryPool, query);
+}
+
+pub unsafe extern "C" fn vkCmdWriteTimestamp2(
+    commandBuffer: VkCommandBuffer,
+    stage: VkPipelineStageFlags2,
+    queryPool: VkQueryPool,
+    query: u32,
+) {
+    let Ok(commandBuffer) = CommandBuffer::lookup(commandBuffer) else {
+        return;
+    };
+
+    let Ok(queryPool) = QueryPool::lookup(queryPool) else {
+        return;
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_write_timestamp(stage, queryPool, query);
+}
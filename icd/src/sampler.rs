@@ -11,8 +11,9 @@ pub unsafe extern "C" fn vkCreateSampler(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pSampler: Option<NonNull<VkSampler>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -26,7 +27,11 @@ pub unsafe extern "C" fn vkCreateSampler(
         unreachable!()
     };
 
-    *pSampler.as_ptr() = Sampler::create(device, create_info.flags);
+    *pSampler.as_ptr() =
+        match Sampler::create(device, create_info.flags, Sampler::parse_state(create_info)) {
+            Ok(object) => object,
+            Err(err) => return err.to_vk_result(),
+        };
 
     VkResult::VK_SUCCESS
 }
@@ -36,8 +41,8 @@ pub unsafe extern "C" fn vkDestroySampler(
     sampler: VkSampler,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
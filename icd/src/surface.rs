@@ -1,8 +1,10 @@
-//! VK_KHR_surface and VK_KHR_xcb_surface extension instance commands
+//! VK_KHR_surface, VK_KHR_xcb_surface, VK_KHR_wayland_surface and VK_EXT_headless_surface
+//! extension instance commands
 
 use headers::vk_decls::*;
 use runtime::context::{Dispatchable, NonDispatchable};
 use runtime::instance::Instance;
+use runtime::physical_device::PhysicalDevice;
 use runtime::surface::*;
 
 
@@ -12,8 +14,9 @@ pub unsafe extern "C" fn vkCreateXcbSurfaceKHR(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pSurface: Option<NonNull<VkSurfaceKHR>>,
 ) -> VkResult {
-    let Some(instance) = Instance::from_handle(instance) else {
-        unreachable!()
+    let instance = match Instance::lookup(instance) {
+        Ok(instance) => instance,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -31,7 +34,10 @@ pub unsafe extern "C" fn vkCreateXcbSurfaceKHR(
         unreachable!()
     };
 
-    *pSurface.as_ptr() = Surface::create(instance, create_info);
+    *pSurface.as_ptr() = match Surface::create(instance, create_info) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -52,13 +58,95 @@ pub unsafe extern "C" fn vkGetPhysicalDeviceXcbPresentationSupportKHR(
     )
 }
 
+pub unsafe extern "C" fn vkCreateWaylandSurfaceKHR(
+    instance: VkInstance,
+    pCreateInfo: Option<NonNull<VkWaylandSurfaceCreateInfoKHR>>,
+    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
+    pSurface: Option<NonNull<VkSurfaceKHR>>,
+) -> VkResult {
+    let instance = match Instance::lookup(instance) {
+        Ok(instance) => instance,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pCreateInfo) = pCreateInfo else {
+        unreachable!()
+    };
+    let create_info = pCreateInfo.as_ref();
+    assert_eq!(
+        create_info.sType,
+        VkStructureType::VK_STRUCTURE_TYPE_WAYLAND_SURFACE_CREATE_INFO_KHR
+    );
+
+    let _ = pAllocator;
+
+    let Some(pSurface) = pSurface else {
+        unreachable!()
+    };
+
+    *pSurface.as_ptr() = match Surface::create_wayland(instance, create_info) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    VkResult::VK_SUCCESS
+}
+
+pub unsafe extern "C" fn vkGetPhysicalDeviceWaylandPresentationSupportKHR(
+    physicalDevice: VkPhysicalDevice,
+    queueFamilyIndex: u32,
+    display: Option<NonNull<wl_display>>,
+) -> VkBool32 {
+    let Ok(physicalDevice) = PhysicalDevice::lookup(physicalDevice) else {
+        return VK_FALSE;
+    };
+
+    let _ = display;
+
+    let supported = physicalDevice
+        .lock()
+        .surface_support(queueFamilyIndex, VK_NULL_HANDLE);
+    supported as VkBool32
+}
+
+pub unsafe extern "C" fn vkCreateHeadlessSurfaceEXT(
+    instance: VkInstance,
+    pCreateInfo: Option<NonNull<VkHeadlessSurfaceCreateInfoEXT>>,
+    pAllocator: Option<NonNull<VkAllocationCallbacks>>,
+    pSurface: Option<NonNull<VkSurfaceKHR>>,
+) -> VkResult {
+    let instance = match Instance::lookup(instance) {
+        Ok(instance) => instance,
+        Err(err) => return err.to_vk_result(),
+    };
+
+    let Some(pCreateInfo) = pCreateInfo else {
+        unreachable!()
+    };
+    let create_info = pCreateInfo.as_ref();
+    assert_eq!(
+        create_info.sType,
+        VkStructureType::VK_STRUCTURE_TYPE_HEADLESS_SURFACE_CREATE_INFO_EXT
+    );
+
+    let _ = pAllocator;
+
+    let Some(pSurface) = pSurface else {
+        unreachable!()
+    };
+
+    *pSurface.as_ptr() = Surface::create_headless(instance, create_info);
+
+    VkResult::VK_SUCCESS
+}
+
 pub unsafe extern "C" fn vkDestroySurfaceKHR(
     instance: VkInstance,
     surface: VkSurfaceKHR,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_instance) = Instance::from_handle(instance) else {
-        unreachable!()
+    let Ok(_instance) = Instance::lookup(instance) else {
+        return;
     };
 
     let _ = pAllocator;
@@ -16,8 +16,9 @@ pub unsafe extern "C" fn vkCreateSwapchainKHR(
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
     pSwapchain: Option<NonNull<VkSwapchainKHR>>,
 ) -> VkResult {
-    let Some(device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let device = match LogicalDevice::lookup(device) {
+        Ok(device) => device,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pCreateInfo) = pCreateInfo else {
@@ -31,7 +32,10 @@ pub unsafe extern "C" fn vkCreateSwapchainKHR(
         unreachable!()
     };
 
-    *pSwapchain.as_ptr() = Swapchain::create(device, create_info);
+    *pSwapchain.as_ptr() = match Swapchain::create(device, create_info) {
+        Ok(object) => object,
+        Err(err) => return err.to_vk_result(),
+    };
 
     VkResult::VK_SUCCESS
 }
@@ -41,12 +45,15 @@ pub unsafe extern "C" fn vkDestroySwapchainKHR(
     swapchain: VkSwapchainKHR,
     pAllocator: Option<NonNull<VkAllocationCallbacks>>,
 ) {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
+    let Ok(_device) = LogicalDevice::lookup(device) else {
+        return;
     };
 
     let _ = pAllocator;
 
+    if let Some(object) = Swapchain::from_handle(swapchain) {
+        object.lock().destroy();
+    }
     Swapchain::drop_handle(swapchain);
 }
 
@@ -56,36 +63,34 @@ pub unsafe extern "C" fn vkGetSwapchainImagesKHR(
     pSwapchainImageCount: Option<NonNull<u32>>,
     pSwapchainImages: Option<NonNull<VkImage>>,
 ) -> VkResult {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
-    };
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
 
-    let Some(swapchain) = Swapchain::from_handle(swapchain) else {
-        unreachable!()
+    let swapchain = match Swapchain::lookup(swapchain) {
+        Ok(swapchain) => swapchain,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pSwapchainImageCount) = pSwapchainImageCount else {
         unreachable!()
     };
 
+    let images = swapchain.lock().images();
     pSwapchainImages.map_or_else(
         || {
-            *pSwapchainImageCount.as_ptr() = swapchain.lock().images.len() as u32;
+            *pSwapchainImageCount.as_ptr() = images.len() as u32;
             VkResult::VK_SUCCESS
         },
         |pSwapchainImages| {
-            let images = swapchain
-                .lock()
-                .images
-                .iter()
-                .map(|x| x.lock().get_handle())
-                .collect::<Vec<_>>();
-            std::ptr::copy_nonoverlapping(
-                images.as_ptr(),
-                pSwapchainImages.as_ptr(),
-                *pSwapchainImageCount.as_ptr() as usize,
-            );
-            VkResult::VK_SUCCESS
+            let count = images.len().min(*pSwapchainImageCount.as_ptr() as usize);
+            std::ptr::copy_nonoverlapping(images.as_ptr(), pSwapchainImages.as_ptr(), count);
+            *pSwapchainImageCount.as_ptr() = count as u32;
+            if count < images.len() {
+                VkResult::VK_INCOMPLETE
+            } else {
+                VkResult::VK_SUCCESS
+            }
         },
     )
 }
@@ -98,12 +103,13 @@ pub unsafe extern "C" fn vkAcquireNextImageKHR(
     fence: VkFence,
     pImageIndex: Option<NonNull<u32>>,
 ) -> VkResult {
-    let Some(_device) = LogicalDevice::from_handle(device) else {
-        unreachable!()
-    };
+    if let Err(err) = LogicalDevice::lookup(device) {
+        return err.to_vk_result();
+    }
 
-    let Some(swapchain) = Swapchain::from_handle(swapchain) else {
-        unreachable!()
+    let swapchain = match Swapchain::lookup(swapchain) {
+        Ok(swapchain) => swapchain,
+        Err(err) => return err.to_vk_result(),
     };
 
     let semaphore = Semaphore::from_handle(semaphore);
@@ -114,19 +120,23 @@ pub unsafe extern "C" fn vkAcquireNextImageKHR(
         unreachable!()
     };
 
-    *pImageIndex.as_ptr() = swapchain
+    let (image_index, result) = swapchain
         .lock()
         .acquire_next_image(timeout, semaphore, fence);
+    if matches!(result, VkResult::VK_SUCCESS | VkResult::VK_SUBOPTIMAL_KHR) {
+        *pImageIndex.as_ptr() = image_index;
+    }
 
-    VkResult::VK_SUCCESS
+    result
 }
 
 pub unsafe extern "C" fn vkQueuePresentKHR(
     queue: VkQueue,
     pPresentInfo: Option<NonNull<VkPresentInfoKHR>>,
 ) -> VkResult {
-    let Some(queue) = Queue::from_handle(queue) else {
-        unreachable!()
+    let queue = match Queue::lookup(queue) {
+        Ok(queue) => queue,
+        Err(err) => return err.to_vk_result(),
     };
 
     let Some(pPresentInfo) = pPresentInfo else {
@@ -14,7 +14,7 @@ use std::sync::Arc;
 #[derive(Debug)]
 pub struct Buffer {
     pub(crate) handle: VkNonDispatchableHandle,
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     size: VkDeviceSize,
     gpu_binding: MemoryBinding,
 }
@@ -54,14 +54,15 @@ impl Buffer {
     }
 
     pub fn bind_memory(&mut self, memory: Arc<Mutex<MemoryAllocation>>, offset: u64) -> VkResult {
-        self.gpu_binding.store(
-            memory.lock().gpu_memory_allocation,
-            offset,
-            self.size.saturating_sub(offset),
-        );
+        self.gpu_binding
+            .store(memory.lock().gpu_memory_allocation, offset, self.size);
         VkResult::VK_SUCCESS
     }
 
+    pub const fn size(&self) -> VkDeviceSize {
+        self.size
+    }
+
     pub fn descriptor(&self) -> DescriptorBuffer {
         let binding = self.gpu_binding.clone();
         DescriptorBuffer { binding }
@@ -72,7 +73,7 @@ impl Buffer {
 #[derive(Debug)]
 pub struct BufferView {
     pub(crate) handle: VkNonDispatchableHandle,
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     buffer: Arc<Mutex<Buffer>>,
     format: VkFormat,
     size: VkDeviceSize,
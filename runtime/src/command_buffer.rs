@@ -1,17 +1,29 @@
 //! Image
 
 use crate::buffer::Buffer;
-use crate::context::{Dispatchable, NonDispatchable};
-use crate::image::Image;
+use crate::context::{Dispatchable, Named, NonDispatchable};
+use crate::descriptor::DescriptorSet;
+use crate::error::RuntimeError;
+use crate::event::Event;
+use crate::image::{Image, ImageSubresourceRange, ImageView};
 use crate::logical_device::LogicalDevice;
-use crate::pipeline::{Framebuffer, Pipeline, PipelineLayout, RenderPass};
+use crate::physical_device::PhysicalDevice;
+use crate::pipeline::{
+    AttachmentDescription, ComputePipeline, Framebuffer, Pipeline, PipelineLayout, RenderPass,
+    SubpassDescription,
+};
+use crate::query::QueryPool;
+use common::consts::{
+    MAX_COLOR_ATTACHMENTS, MAX_COMPUTE_WORK_GROUP_COUNT, MAX_VIEWPORTS,
+    MIN_STORAGE_BUFFER_OFFSET_ALIGNMENT, MIN_UNIFORM_BUFFER_OFFSET_ALIGNMENT,
+};
 use common::graphics::{IndexBuffer, VertexBindingNumber, VertexBuffer};
-use common::math::{Extent2, Extent3, Offset2, Offset3};
-use gpu::{Command, RegionCopyBufferImage};
+use common::math::{Extent2, Extent3, Format, Offset2, Offset3, Range2};
+use gpu::{Command, RegionBlitImage, RegionCopyBufferImage, RegionResolveImage};
 use headers::vk_decls::*;
-use itertools::izip;
 use log::*;
 use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
 
@@ -20,7 +32,7 @@ use std::sync::Arc;
 #[allow(dead_code)]
 pub struct CommandPool {
     pub(crate) handle: VkNonDispatchableHandle,
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     flags: VkCommandPoolCreateFlags,
     queue_family_index: u32,
 }
@@ -45,49 +57,269 @@ impl CommandPool {
     }
 }
 
+/// Command buffer lifecycle state.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#commandbuffers-lifecycle
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CommandBufferState {
+    Initial,
+    Recording,
+    Executable,
+    Pending,
+}
+
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct CommandBuffer {
     pub(crate) handle: VkDispatchableHandle,
     level: VkCommandBufferLevel,
     command_pool: Arc<Mutex<CommandPool>>,
+    state: CommandBufferState,
     gpu_command_buffer: gpu::CommandBuffer,
-    gpu_bound_render_target_indices: Vec<gpu::RenderTargetIndex>,
+    render_pass: Option<RenderPassInstance>,
+    /// Render pass and subpass secondary command buffer is executed within.
+    inheritance: Option<CommandBufferInheritance>,
+    bound_pipeline: Option<Arc<Mutex<Pipeline>>>,
+    /// Sets of descriptors pushed by `cmd_push_descriptor_set`, by bind point, pipeline layout
+    /// and set number.
+    push_descriptor_sets: HashMap<(gpu::PipelineBindPoint, VkPipelineLayout, u32), DescriptorSet>,
+    layout_transitions: Vec<LayoutTransition>,
+}
+
+/// Render pass state secondary command buffer continues, as given when it begins.
+#[derive(Debug, Clone)]
+pub struct CommandBufferInheritance {
+    pub render_pass: Arc<Mutex<RenderPass>>,
+    pub subpass: u32,
+}
+
+/// Attachment written by dynamic rendering instance, without render pass and framebuffer objects.
+#[derive(Debug, Clone)]
+pub struct RenderingAttachment {
+    pub image_view: Arc<Mutex<ImageView>>,
+    pub image_layout: VkImageLayout,
+    /// View multisampled attachment is resolved into at the end of rendering.
+    pub resolve_image_view: Option<Arc<Mutex<ImageView>>>,
+    pub resolve_image_layout: VkImageLayout,
+    pub load_op: VkAttachmentLoadOp,
+    pub store_op: VkAttachmentStoreOp,
+    pub clear_value: VkClearValue,
+}
+
+/// Image layout transition applied when command buffer is executed.
+#[derive(Debug)]
+struct LayoutTransition {
+    image: Arc<Mutex<Image>>,
+    range: ImageSubresourceRange,
+    old_layout: VkImageLayout,
+    new_layout: VkImageLayout,
+}
+
+/// Render pass instance begun in command buffer.
+#[derive(Debug)]
+struct RenderPassInstance {
+    /// Render pass object instance was begun with, `None` for dynamic rendering.
+    render_pass: Option<Arc<Mutex<RenderPass>>>,
+    attachments: Arc<[AttachmentDescription]>,
+    image_views: Arc<[Arc<Mutex<ImageView>>]>,
+    render_target_indices: Vec<gpu::RenderTargetIndex>,
+    subpasses: Arc<[SubpassDescription]>,
+    render_area: gpu::RenderArea,
+    subpass: u32,
 }
 
 impl CommandBuffer {
-    pub fn create(allocate_info: &VkCommandBufferAllocateInfo) -> VkDispatchableHandle {
+    pub fn create(
+        allocate_info: &VkCommandBufferAllocateInfo,
+    ) -> Result<VkDispatchableHandle, RuntimeError> {
         info!("new CommandBuffer");
         let handle = VkDispatchableHandle(None);
         let level = allocate_info.level;
-        let Some(command_pool) = CommandPool::from_handle(allocate_info.commandPool) else {
-            unreachable!()
-        };
+        let command_pool = CommandPool::lookup(allocate_info.commandPool)?;
 
         let object = Self {
             handle,
             level,
             command_pool,
+            state: CommandBufferState::Initial,
             gpu_command_buffer: gpu::CommandBuffer::new(),
-            gpu_bound_render_target_indices: vec![],
+            render_pass: None,
+            inheritance: None,
+            bound_pipeline: None,
+            push_descriptor_sets: HashMap::new(),
+            layout_transitions: vec![],
         };
-        object.register_object()
+        Ok(object.register_object())
+    }
+
+    pub const fn state(&self) -> CommandBufferState {
+        self.state
+    }
+
+    /// Returns index of current subpass, if command buffer is inside render pass.
+    pub fn subpass(&self) -> Option<u32> {
+        self.render_pass
+            .as_ref()
+            .map(|render_pass| render_pass.subpass)
+    }
+
+    /// Returns graphics pipeline bound by the last `cmd_bind_pipeline`.
+    pub fn bound_pipeline(&self) -> Option<Arc<Mutex<Pipeline>>> {
+        self.bound_pipeline.clone()
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        self.gpu_command_buffer.commands()
+    }
+
+    /// Moves executable command buffer into pending state once it is submitted, which it stays in
+    /// while its batch waits to be executed.
+    pub fn mark_pending(&mut self) {
+        assert_eq!(
+            self.state,
+            CommandBufferState::Executable,
+            "submitted command buffer is not executable"
+        );
+        self.state = CommandBufferState::Pending;
+    }
+
+    /// Returns commands of pending command buffer to execute.
+    pub fn gpu_command_buffer_for_submit(&self) -> gpu::CommandBuffer {
+        assert_eq!(self.state, CommandBufferState::Pending);
+        self.gpu_command_buffer.clone()
+    }
+
+    /// Returns pending command buffer into executable state after its commands were executed.
+    pub fn complete(&mut self) {
+        assert_eq!(self.state, CommandBufferState::Pending);
+        for transition in &self.layout_transitions {
+            transition.image.lock().transition_layout(
+                &transition.range,
+                transition.old_layout,
+                transition.new_layout,
+            );
+        }
+        self.state = CommandBufferState::Executable;
+    }
+
+    pub fn begin(&mut self) -> Result<(), VkResult> {
+        self.begin_with_inheritance(None)
     }
 
-    pub fn gpu_command_buffer_for_submit(&mut self) -> gpu::CommandBuffer {
-        std::mem::replace(&mut self.gpu_command_buffer, gpu::CommandBuffer::new())
+    /// Begins recording, continuing render pass of `inheritance` if command buffer is secondary.
+    pub fn begin_with_inheritance(
+        &mut self,
+        inheritance: Option<CommandBufferInheritance>,
+    ) -> Result<(), VkResult> {
+        match self.state {
+            CommandBufferState::Initial | CommandBufferState::Executable => {
+                // Beginning executable command buffer implicitly resets it.
+                self.reset()?;
+                if self.level == VkCommandBufferLevel::VK_COMMAND_BUFFER_LEVEL_SECONDARY {
+                    self.inheritance = inheritance;
+                }
+                self.state = CommandBufferState::Recording;
+                Ok(())
+            }
+            CommandBufferState::Recording | CommandBufferState::Pending => {
+                error!("Command buffer cannot begin in {:?} state", self.state);
+                Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT)
+            }
+        }
+    }
+
+    pub fn end(&mut self) -> Result<(), VkResult> {
+        if self.state != CommandBufferState::Recording {
+            error!("Command buffer cannot end in {:?} state", self.state);
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        }
+        if self.render_pass.is_some() {
+            error!("Command buffer cannot end inside render pass");
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        }
+        self.state = CommandBufferState::Executable;
+        Ok(())
+    }
+
+    pub fn reset(&mut self) -> Result<(), VkResult> {
+        if self.state == CommandBufferState::Pending {
+            error!("Pending command buffer cannot be reset");
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        }
+        self.gpu_command_buffer = gpu::CommandBuffer::new();
+        self.render_pass = None;
+        self.inheritance = None;
+        self.bound_pipeline = None;
+        self.push_descriptor_sets.clear();
+        self.layout_transitions.clear();
+        self.state = CommandBufferState::Initial;
+        Ok(())
+    }
+
+    fn assert_recording(&self) {
+        assert_eq!(
+            self.state,
+            CommandBufferState::Recording,
+            "command recorded outside of recording state"
+        );
     }
 
-    pub fn begin(&mut self) {
-        warn!("TODO: Start recording command buffer");
+    fn record(&mut self, command: Command) {
+        self.assert_recording();
+        self.gpu_command_buffer.record(command);
     }
 
-    pub fn end(&mut self) {
-        warn!("TODO: Stop recording command buffer");
+    pub fn cmd_pipeline_barrier(&mut self, image_memory_barriers: &[VkImageMemoryBarrier]) {
+        trace!(
+            "CommandBuffer::cmd_pipeline_barrier {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        // NOTE: Commands are executed in order on submit, so only layout transitions have effect.
+        for barrier in image_memory_barriers {
+            self.transition_layout(
+                barrier.image,
+                &barrier.subresourceRange,
+                barrier.oldLayout,
+                barrier.newLayout,
+            );
+        }
+    }
+
+    pub fn cmd_pipeline_barrier2(&mut self, image_memory_barriers: &[VkImageMemoryBarrier2]) {
+        trace!(
+            "CommandBuffer::cmd_pipeline_barrier2 {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        for barrier in image_memory_barriers {
+            self.transition_layout(
+                barrier.image,
+                &barrier.subresourceRange,
+                barrier.oldLayout,
+                barrier.newLayout,
+            );
+        }
     }
 
-    pub fn cmd_pipeline_barrier(&mut self) {
-        warn!("TODO: Record pipeline barrier");
+    fn transition_layout(
+        &mut self,
+        image: VkImage,
+        subresource_range: &VkImageSubresourceRange,
+        old_layout: VkImageLayout,
+        new_layout: VkImageLayout,
+    ) {
+        let Ok(image) = Image::lookup(image) else {
+            return;
+        };
+        let Ok(range) = image.lock().subresource_range(subresource_range) else {
+            return;
+        };
+        self.layout_transitions.push(LayoutTransition {
+            image,
+            range,
+            old_layout,
+            new_layout,
+        });
     }
 
     pub fn cmd_begin_render_pass(
@@ -98,101 +330,417 @@ impl CommandBuffer {
         clear_values: &[VkClearValue],
         contents: VkSubpassContents,
     ) {
-        let render_pass = render_pass.lock();
-        let descriptions = render_pass.attachments.clone();
-        drop(render_pass);
+        self.assert_recording();
+        assert!(
+            self.render_pass.is_none(),
+            "render pass begun inside render pass"
+        );
+        let render_pass_guard = render_pass.lock();
+        let attachments = render_pass_guard.attachments.clone();
+        let subpasses = render_pass_guard.subpasses.clone();
+        drop(render_pass_guard);
         let framebuffer = framebuffer.lock();
         let image_views = framebuffer.attachments.clone();
         drop(framebuffer);
         let _ = contents;
 
-        let render_area = gpu::RenderArea {
-            extent: Extent2::<u32> {
-                width: render_area.extent.width,
-                height: render_area.extent.height,
-            },
-            offset: Offset2::<i32> {
-                x: render_area.offset.x,
-                y: render_area.offset.y,
+        self.begin_render_pass_instance(
+            RenderPassInstance {
+                render_pass: Some(render_pass),
+                render_target_indices: (0..attachments.len()).map(gpu::RenderTargetIndex).collect(),
+                attachments,
+                image_views,
+                subpasses,
+                render_area: to_render_area(render_area),
+                subpass: 0,
             },
-        };
+            clear_values,
+        );
+    }
 
-        assert!(self.gpu_bound_render_target_indices.is_empty());
-        izip!(descriptions.iter(), image_views.iter(), clear_values.iter())
-            .enumerate()
-            .for_each(|(index, (description, image_view, clear_value))| {
-                let index = gpu::RenderTargetIndex(index);
-                self.gpu_bound_render_target_indices.push(index);
+    /// Binds attachments of render pass instance as render targets, applies their load operations
+    /// within render area and begins its first subpass.
+    fn begin_render_pass_instance(
+        &mut self,
+        instance: RenderPassInstance,
+        clear_values: &[VkClearValue],
+    ) {
+        let render_area = instance.render_area;
+        for ((description, image_view), &index) in instance
+            .attachments
+            .iter()
+            .zip(instance.image_views.iter())
+            .zip(instance.render_target_indices.iter())
+        {
+            self.record(Command::BindRenderTarget {
+                render_target: gpu::RenderTarget {
+                    index,
+                    format: description.format.into(),
+                    samples: description.samples.into(),
+                    image: image_view.lock().descriptor(),
+                },
+            });
 
-                self.gpu_command_buffer.record(Command::BindRenderTarget {
-                    render_target: gpu::RenderTarget {
+            match description.load_op {
+                VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_LOAD => {
+                    // Previous contents are preserved.
+                }
+                VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR => {
+                    let Some(clear_value) = clear_values.get(index.0) else {
+                        unreachable!("missing clear value for attachment {}", index.0)
+                    };
+                    self.record(Command::ClearRenderTarget {
                         index,
-                        format: description.format.into(),
-                        samples: description.samples.into(),
-                        image: image_view.lock().image.lock().descriptor(),
-                    },
+                        render_area,
+                        color: (*clear_value).into(),
+                    });
+                }
+                VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE
+                | VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_NONE_EXT => {
+                    // Contents are undefined, previous contents are left as they are.
+                }
+                _ => unreachable!(),
+            }
+
+            let format = Format::from(description.format);
+            match description.stencil_load_pp {
+                VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_LOAD => {
+                    // Previous contents are preserved.
+                }
+                VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR if format.has_stencil() => {
+                    let Some(clear_value) = clear_values.get(index.0) else {
+                        unreachable!("missing clear value for attachment {}", index.0)
+                    };
+                    let stencil = unsafe { clear_value.depthStencil.stencil };
+                    self.record(Command::ClearRenderTargetStencil {
+                        index,
+                        render_area,
+                        stencil: stencil as u8,
+                    });
+                }
+                VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR => {
+                    // Attachment has no stencil aspect.
+                }
+                VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE
+                | VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_NONE_EXT => {
+                    // No-op.
+                }
+                _ => unreachable!(),
+            };
+        }
+
+        self.render_pass = Some(instance);
+        self.set_subpass_attachments();
+    }
+
+    pub fn cmd_next_subpass(&mut self, contents: VkSubpassContents) {
+        self.assert_recording();
+        let _ = contents;
+        self.resolve_subpass_attachments();
+        let Some(render_pass) = self.render_pass.as_mut() else {
+            unreachable!("next subpass outside of render pass")
+        };
+        assert!(
+            (render_pass.subpass as usize + 1) < render_pass.subpasses.len(),
+            "next subpass after last subpass"
+        );
+        render_pass.subpass += 1;
+        self.set_subpass_attachments();
+    }
+
+    pub fn cmd_end_render_pass(&mut self) {
+        self.assert_recording();
+        assert!(
+            self.render_pass
+                .as_ref()
+                .is_some_and(|instance| instance.render_pass.is_some()),
+            "render pass ended outside of render pass"
+        );
+        self.end_render_pass_instance();
+    }
+
+    /// Begins dynamic rendering instance of single subpass writing `color_attachments` at their
+    /// locations and depth and stencil attachments.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkCmdBeginRendering
+    pub fn cmd_begin_rendering(
+        &mut self,
+        render_area: VkRect2D,
+        color_attachments: &[Option<RenderingAttachment>],
+        depth_attachment: Option<&RenderingAttachment>,
+        stencil_attachment: Option<&RenderingAttachment>,
+    ) {
+        self.assert_recording();
+        assert!(
+            self.render_pass.is_none(),
+            "rendering begun inside render pass"
+        );
+        const DONT_CARE: (VkAttachmentLoadOp, VkAttachmentStoreOp) = (
+            VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE,
+            VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE,
+        );
+        let unused = VkAttachmentReference {
+            attachment: VK_ATTACHMENT_UNUSED,
+            layout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+        };
+
+        let mut attachments = vec![];
+        let mut image_views = vec![];
+        let mut clear_values = vec![];
+        // Attachments stay in their layouts, as no render pass transitions them.
+        let mut add_attachment =
+            |image_view: &Arc<Mutex<ImageView>>,
+             layout: VkImageLayout,
+             [(load_op, store_op), (stencil_load_op, stencil_store_op)]: [_; 2],
+             clear_value: VkClearValue| {
+                let image_view_guard = image_view.lock();
+                let format = image_view_guard.format;
+                let samples = image_view_guard.image.lock().samples;
+                drop(image_view_guard);
+                attachments.push(AttachmentDescription {
+                    flags: 0.into(),
+                    format,
+                    samples: samples.into(),
+                    load_op,
+                    store_op,
+                    stencil_load_pp: stencil_load_op,
+                    stencil_store_op,
+                    initial_layout: layout,
+                    final_layout: layout,
                 });
+                image_views.push(image_view.clone());
+                clear_values.push(clear_value);
+                VkAttachmentReference {
+                    attachment: attachments.len() as u32 - 1,
+                    layout,
+                }
+            };
 
-                match description.load_op {
-                    VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_LOAD => {
-                        // No-op.
-                    }
-                    VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR => {
-                        self.gpu_command_buffer.record(Command::ClearRenderTarget {
-                            index,
-                            render_area,
-                            color: (*clear_value).into(),
-                        });
-                    }
-                    VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE
-                    | VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_NONE_EXT => {
-                        // No-op.
-                    }
-                    _ => unreachable!(),
+        let color_references = color_attachments
+            .iter()
+            .map(|attachment| {
+                attachment.as_ref().map_or(unused, |attachment| {
+                    add_attachment(
+                        &attachment.image_view,
+                        attachment.image_layout,
+                        [(attachment.load_op, attachment.store_op), DONT_CARE],
+                        attachment.clear_value,
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        let resolve_references = if color_attachments
+            .iter()
+            .flatten()
+            .any(|attachment| attachment.resolve_image_view.is_some())
+        {
+            color_attachments
+                .iter()
+                .map(|attachment| {
+                    let Some((attachment, resolve_image_view)) =
+                        attachment.as_ref().and_then(|attachment| {
+                            Some((attachment, attachment.resolve_image_view.as_ref()?))
+                        })
+                    else {
+                        return unused;
+                    };
+                    let store = (
+                        VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE,
+                        VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE,
+                    );
+                    add_attachment(
+                        resolve_image_view,
+                        attachment.resolve_image_layout,
+                        [store, DONT_CARE],
+                        attachment.clear_value,
+                    )
+                })
+                .collect::<Vec<_>>()
+        } else {
+            vec![]
+        };
+        // Depth and stencil aspects are expected to be of the same image view, clear value
+        // combining depth of one with stencil of the other.
+        let depth_stencil_reference = depth_attachment.or(stencil_attachment).map(|attachment| {
+            let ops = |attachment: Option<&RenderingAttachment>| {
+                attachment.map_or(DONT_CARE, |attachment| {
+                    (attachment.load_op, attachment.store_op)
+                })
+            };
+            let depth = depth_attachment.map_or(0.0, |attachment| unsafe {
+                attachment.clear_value.depthStencil.depth
+            });
+            let stencil = stencil_attachment.map_or(0, |attachment| unsafe {
+                attachment.clear_value.depthStencil.stencil
+            });
+            add_attachment(
+                &attachment.image_view,
+                attachment.image_layout,
+                [ops(depth_attachment), ops(stencil_attachment)],
+                VkClearValue {
+                    depthStencil: std::mem::ManuallyDrop::new(VkClearDepthStencilValue {
+                        depth,
+                        stencil,
+                    }),
+                },
+            )
+        });
+
+        let subpass = SubpassDescription {
+            flags: 0.into(),
+            pipeline_bind_point: VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+            input_attachments: Arc::new([]),
+            color_attachments: color_references.into(),
+            resolve_attachments: resolve_references.into(),
+            depth_stencil_attachment: depth_stencil_reference,
+            preserve_attachments: Arc::new([]),
+        };
+        self.begin_render_pass_instance(
+            RenderPassInstance {
+                render_pass: None,
+                render_target_indices: (0..attachments.len()).map(gpu::RenderTargetIndex).collect(),
+                attachments: attachments.into(),
+                image_views: image_views.into(),
+                subpasses: Arc::new([subpass]),
+                render_area: to_render_area(render_area),
+                subpass: 0,
+            },
+            &clear_values,
+        );
+    }
+
+    pub fn cmd_end_rendering(&mut self) {
+        self.assert_recording();
+        assert!(
+            self.render_pass
+                .as_ref()
+                .is_some_and(|instance| instance.render_pass.is_none()),
+            "rendering ended outside of dynamic rendering"
+        );
+        self.end_render_pass_instance();
+    }
+
+    /// Resolves attachments of last subpass, then applies store operations of attachments and
+    /// unbinds them.
+    fn end_render_pass_instance(&mut self) {
+        self.resolve_subpass_attachments();
+        let Some(render_pass) = self.render_pass.take() else {
+            unreachable!()
+        };
+        assert_eq!(
+            render_pass.subpass as usize + 1,
+            render_pass.subpasses.len(),
+            "render pass ended before last subpass"
+        );
+        for ((description, index), image_view) in render_pass
+            .attachments
+            .iter()
+            .zip(render_pass.render_target_indices)
+            .zip(render_pass.image_views.iter())
+        {
+            match description.store_op {
+                VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE => {
+                    // Rendered contents are already written to attachment memory.
+                }
+                VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE
+                | VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_NONE => {
+                    // Contents become undefined, keeping rendered ones is valid.
                 }
+                _ => unreachable!(),
+            };
 
-                match description.store_op {
-                    VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE => {
-                        // No-op.
-                    }
-                    VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE
-                    | VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_NONE => {
-                        // No-op.
-                    }
-                    _ => unreachable!(),
-                };
+            match description.stencil_store_op {
+                VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE => {
+                    // Rendered stencil values are already written to attachment memory.
+                }
+                VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE
+                | VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_NONE => {
+                    // No-op.
+                }
+                _ => unreachable!(),
+            };
 
-                match description.stencil_load_pp {
-                    VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_LOAD => {
-                        warn!("TODO: Stencil commands support");
-                    }
-                    VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR => {
-                        warn!("TODO: Stencil commands support");
-                    }
-                    VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE
-                    | VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_NONE_EXT => {
-                        // No-op.
-                    }
-                    _ => unreachable!(),
-                };
+            self.record(Command::UnbindRenderTarget { index });
 
-                match description.stencil_store_op {
-                    VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE => {
-                        warn!("TODO: Stencil commands support");
-                    }
-                    VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE
-                    | VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_NONE => {
-                        // No-op.
-                    }
-                    _ => unreachable!(),
-                };
+            // Attachment is transitioned into its final layout at the end of render pass.
+            if render_pass.render_pass.is_none() {
+                continue;
+            }
+            let image_view = image_view.lock();
+            self.layout_transitions.push(LayoutTransition {
+                image: image_view.image.clone(),
+                range: image_view.subresource_range,
+                old_layout: description.initial_layout,
+                new_layout: description.final_layout,
             });
+            drop(image_view);
+        }
     }
 
-    pub fn cmd_end_render_pass(&mut self) {
-        for index in self.gpu_bound_render_target_indices.drain(..) {
-            self.gpu_command_buffer
-                .record(Command::UnbindRenderTarget { index });
+    /// Makes fragment shader outputs write color attachments of current subpass at their
+    /// locations, and fragment shader read its input attachments.
+    fn set_subpass_attachments(&mut self) {
+        let Some(render_pass) = self.render_pass.as_ref() else {
+            unreachable!("subpass begun outside of render pass")
+        };
+        let subpass = &render_pass.subpasses[render_pass.subpass as usize];
+        let mut color_attachments = [None; MAX_COLOR_ATTACHMENTS as usize];
+        for (location, color) in subpass.color_attachments.iter().enumerate() {
+            if color.attachment != VK_ATTACHMENT_UNUSED {
+                color_attachments[location] =
+                    Some(render_pass.render_target_indices[color.attachment as usize]);
+            }
+        }
+        let input_attachments = subpass
+            .input_attachments
+            .iter()
+            .map(|input| {
+                (input.attachment != VK_ATTACHMENT_UNUSED)
+                    .then(|| render_pass.render_target_indices[input.attachment as usize])
+            })
+            .collect();
+        self.record(Command::SetColorAttachments { color_attachments });
+        self.record(Command::SetInputAttachments { input_attachments });
+    }
+
+    /// Resolves multisampled color attachments of current subpass into their resolve attachments
+    /// within render area.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#renderpass-resolve-operations
+    fn resolve_subpass_attachments(&mut self) {
+        let Some(render_pass) = self.render_pass.as_ref() else {
+            unreachable!("subpass ended outside of render pass")
+        };
+        let subpass = &render_pass.subpasses[render_pass.subpass as usize];
+        let area = render_pass.render_area;
+        let mut commands = vec![];
+        for (color, resolve) in subpass
+            .color_attachments
+            .iter()
+            .zip(subpass.resolve_attachments.iter())
+        {
+            if color.attachment == VK_ATTACHMENT_UNUSED
+                || resolve.attachment == VK_ATTACHMENT_UNUSED
+            {
+                continue;
+            }
+            let description = &render_pass.attachments[color.attachment as usize];
+            commands.push(Command::ResolveImage {
+                src_image: render_pass.image_views[color.attachment as usize]
+                    .lock()
+                    .descriptor(),
+                dst_image: render_pass.image_views[resolve.attachment as usize]
+                    .lock()
+                    .descriptor(),
+                region: RegionResolveImage {
+                    src_offset: area.offset,
+                    dst_offset: area.offset,
+                    extent: area.extent,
+                    format: description.format.into(),
+                    samples: description.samples.into(),
+                },
+            });
+        }
+        for command in commands {
+            self.record(command);
         }
     }
 
@@ -201,43 +749,219 @@ impl CommandBuffer {
         bind_point: VkPipelineBindPoint,
         pipeline: Arc<Mutex<Pipeline>>,
     ) {
+        self.assert_recording();
         if bind_point == VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS {
             pipeline.lock().bind_states(&mut self.gpu_command_buffer);
+            self.bound_pipeline = Some(pipeline);
         } else {
             unreachable!();
         }
     }
 
+    pub fn cmd_bind_compute_pipeline(&mut self, pipeline: Arc<Mutex<ComputePipeline>>) {
+        self.assert_recording();
+        pipeline.lock().bind_states(&mut self.gpu_command_buffer);
+    }
+
     pub fn cmd_bind_descriptor_sets(
         &mut self,
         bind_point: VkPipelineBindPoint,
-        pipeline: Arc<Mutex<PipelineLayout>>,
+        pipeline_layout: Arc<Mutex<PipelineLayout>>,
         first_set: u32,
         descriptor_sets: &[VkDescriptorSet],
         dynamic_offsets: &[u32],
     ) {
-        trace!("CommandBuffer::cmd_bind_descriptor_sets");
-        let _ = bind_point;
-        let _ = pipeline;
-        let _ = first_set;
-        let _ = descriptor_sets;
-        let _ = dynamic_offsets;
-        // TODO: Record descriptor sets bindings.
+        trace!(
+            "CommandBuffer::cmd_bind_descriptor_sets {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let Some(bind_point) = gpu_bind_point(bind_point) else {
+            return;
+        };
+        let set_layouts = pipeline_layout.lock().set_layouts.clone();
+        if first_set as usize + descriptor_sets.len() > set_layouts.len() {
+            error!(
+                "Descriptor sets {first_set}..{} are not in pipeline layout with {} sets",
+                first_set as usize + descriptor_sets.len(),
+                set_layouts.len()
+            );
+            return;
+        }
+
+        let mut sets = Vec::with_capacity(descriptor_sets.len());
+        let mut dynamic_descriptors = vec![];
+        for (i, &descriptor_set) in descriptor_sets.iter().enumerate() {
+            let set_number = first_set as usize + i;
+            let Ok(descriptor_set) = DescriptorSet::lookup(descriptor_set) else {
+                return;
+            };
+            let descriptor_set = descriptor_set.lock();
+            let pipeline_set_layout = &set_layouts[set_number];
+            if !Arc::ptr_eq(&descriptor_set.set_layout, pipeline_set_layout)
+                && !descriptor_set
+                    .set_layout
+                    .lock()
+                    .is_compatible(&pipeline_set_layout.lock())
+            {
+                error!(
+                    "Layout of descriptor set {set_number} is incompatible with pipeline layout"
+                );
+                return;
+            }
+            dynamic_descriptors.extend(descriptor_set.dynamic_descriptors());
+            sets.push(descriptor_set.descriptor());
+        }
+        if dynamic_offsets.len() != dynamic_descriptors.len() {
+            error!(
+                "{} dynamic offsets given for {} dynamic descriptors",
+                dynamic_offsets.len(),
+                dynamic_descriptors.len()
+            );
+            return;
+        }
+        for (&dynamic_offset, &(descriptor_type, range)) in
+            dynamic_offsets.iter().zip(&dynamic_descriptors)
+        {
+            let dynamic_offset = u64::from(dynamic_offset);
+            let alignment =
+                if descriptor_type == VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER_DYNAMIC {
+                    MIN_UNIFORM_BUFFER_OFFSET_ALIGNMENT
+                } else {
+                    MIN_STORAGE_BUFFER_OFFSET_ALIGNMENT
+                };
+            // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkCmdBindDescriptorSets-pDynamicOffsets-01971
+            if !dynamic_offset.is_multiple_of(alignment) {
+                error!(
+                    "Dynamic offset {dynamic_offset} of {descriptor_type:?} descriptor is not \
+                     multiple of {alignment}"
+                );
+                return;
+            }
+            // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkCmdBindDescriptorSets-pDynamicOffsets-01979
+            if range.is_some_and(|(end, size)| end + dynamic_offset > size) {
+                error!("Dynamic offset {dynamic_offset} moves descriptor range past its buffer");
+                return;
+            }
+        }
+
+        // Binding sets replaces descriptors pushed into their set numbers.
+        let set_numbers = first_set..first_set + descriptor_sets.len() as u32;
+        self.push_descriptor_sets
+            .retain(|&(push_bind_point, _, set_number), _| {
+                push_bind_point != bind_point || !set_numbers.contains(&set_number)
+            });
+        self.record(Command::BindDescriptorSets {
+            bind_point,
+            first_set,
+            sets,
+            dynamic_offsets: dynamic_offsets.to_vec(),
+        });
+    }
+
+    /// Writes descriptors into set `set_number` of command buffer and binds it, without
+    /// allocating descriptor set.
+    ///
+    /// # Safety
+    ///
+    /// Image and buffer infos of every write must point to `descriptorCount` elements.
+    pub unsafe fn cmd_push_descriptor_set(
+        &mut self,
+        bind_point: VkPipelineBindPoint,
+        pipeline_layout: Arc<Mutex<PipelineLayout>>,
+        set_number: u32,
+        writes: &[VkWriteDescriptorSet],
+    ) {
+        trace!(
+            "CommandBuffer::cmd_push_descriptor_set {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let Some(bind_point) = gpu_bind_point(bind_point) else {
+            return;
+        };
+        let pipeline_layout = pipeline_layout.lock();
+        let Some(set_layout) = pipeline_layout
+            .set_layouts
+            .get(set_number as usize)
+            .cloned()
+        else {
+            error!(
+                "Descriptors pushed to set {set_number} outside of pipeline layout with {} sets",
+                pipeline_layout.set_layouts.len()
+            );
+            return;
+        };
+        let key = (bind_point, pipeline_layout.handle, set_number);
+        drop(pipeline_layout);
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkCmdPushDescriptorSetKHR-set-00365
+        if !set_layout.lock().is_push_descriptor() {
+            error!("Descriptors pushed to set {set_number} of layout not for push descriptors");
+            return;
+        }
+
+        let descriptor_set = self.push_descriptor_sets.entry(key).or_insert_with(|| {
+            let logical_device = set_layout.lock().logical_device.clone();
+            DescriptorSet::new_push(logical_device, set_layout)
+        });
+        for write in writes {
+            unsafe { descriptor_set.write(write) };
+        }
+        let set = descriptor_set.descriptor();
+        self.record(Command::BindDescriptorSets {
+            bind_point,
+            first_set: set_number,
+            sets: vec![set],
+            dynamic_offsets: vec![],
+        });
     }
 
     pub fn cmd_push_constants(
         &mut self,
-        pipeline: Arc<Mutex<PipelineLayout>>,
+        pipeline_layout: Arc<Mutex<PipelineLayout>>,
         shader_stage_flags: VkShaderStageFlags,
         offset: u32,
         values: &[u8],
     ) {
-        trace!("CommandBuffer::cmd_push_constants");
-        let _ = pipeline;
-        let _ = shader_stage_flags;
-        let _ = offset;
-        let _ = values;
-        // TODO: Record push constant update.
+        trace!(
+            "CommandBuffer::cmd_push_constants {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        if !offset.is_multiple_of(4) || !values.len().is_multiple_of(4) {
+            error!(
+                "Push constant offset {offset} and size {} must be multiples of 4",
+                values.len()
+            );
+            return;
+        }
+        let ranges = pipeline_layout.lock().push_constant_ranges.clone();
+        for byte in offset..offset + values.len() as u32 {
+            let overlapping = ranges
+                .iter()
+                .filter(|range| (range.offset..range.offset + range.size).contains(&byte));
+            let mut covered_stages = 0;
+            for range in overlapping {
+                if range.stageFlags & !shader_stage_flags != 0 {
+                    error!(
+                        "Push constant byte {byte} overlaps range with stages {:#x} not in {shader_stage_flags:#x}",
+                        range.stageFlags
+                    );
+                    return;
+                }
+                covered_stages |= range.stageFlags;
+            }
+            if shader_stage_flags & !covered_stages != 0 {
+                error!(
+                    "Push constant byte {byte} is not in a range of pipeline layout for stages {shader_stage_flags:#x}"
+                );
+                return;
+            }
+        }
+        self.record(Command::PushConstants {
+            offset,
+            values: values.to_vec(),
+        });
     }
 
     pub fn cmd_bind_vertex_buffer(
@@ -246,7 +970,7 @@ impl CommandBuffer {
         buffer: Arc<Mutex<Buffer>>,
         offset: VkDeviceSize,
     ) {
-        self.gpu_command_buffer.record(Command::BindVertexBuffer {
+        self.record(Command::BindVertexBuffer {
             vertex_buffer: VertexBuffer {
                 binding_number: VertexBindingNumber(binding),
                 buffer: buffer.lock().descriptor(),
@@ -261,7 +985,7 @@ impl CommandBuffer {
         offset: VkDeviceSize,
         index_size: u8,
     ) {
-        self.gpu_command_buffer.record(Command::BindIndexBuffer {
+        self.record(Command::BindIndexBuffer {
             index_buffer: IndexBuffer {
                 buffer: buffer.lock().descriptor(),
                 offset,
@@ -271,17 +995,75 @@ impl CommandBuffer {
     }
 
     pub fn cmd_set_viewport(&mut self, first_viewport: u32, viewports: &[VkViewport]) {
-        trace!("CommandBuffer::cmd_set_viewport");
-        let _ = first_viewport;
-        let _ = viewports;
-        // TODO: Record viewport dynamic state change.
+        trace!(
+            "CommandBuffer::cmd_set_viewport {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        if first_viewport as usize + viewports.len() > MAX_VIEWPORTS as usize {
+            error!(
+                "Viewports {}..{} exceed maxViewports {}",
+                first_viewport,
+                first_viewport as usize + viewports.len(),
+                MAX_VIEWPORTS
+            );
+            return;
+        }
+        self.record(Command::SetViewport {
+            first_viewport,
+            viewports: viewports
+                .iter()
+                .map(PhysicalDevice::parse_viewport)
+                .collect(),
+        });
     }
 
     pub fn cmd_set_scissors(&mut self, first_scissor: u32, scissors: &[VkRect2D]) {
-        trace!("CommandBuffer::cmd_set_scissors");
-        let _ = first_scissor;
-        let _ = scissors;
-        // TODO: Record scissors dynamic state change.
+        trace!(
+            "CommandBuffer::cmd_set_scissors {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        if first_scissor as usize + scissors.len() > MAX_VIEWPORTS as usize {
+            error!(
+                "Scissors {}..{} exceed maxViewports {}",
+                first_scissor,
+                first_scissor as usize + scissors.len(),
+                MAX_VIEWPORTS
+            );
+            return;
+        }
+        self.record(Command::SetScissor {
+            first_scissor,
+            scissors: scissors.iter().map(PhysicalDevice::parse_scissor).collect(),
+        });
+    }
+
+    pub fn cmd_set_depth_bias(&mut self, constant_factor: f32, clamp: f32, slope_factor: f32) {
+        trace!(
+            "CommandBuffer::cmd_set_depth_bias {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        self.record(Command::SetDepthBias {
+            constant_factor,
+            clamp,
+            slope_factor,
+        });
+    }
+
+    pub fn cmd_set_depth_bounds(&mut self, min_depth_bounds: f32, max_depth_bounds: f32) {
+        trace!(
+            "CommandBuffer::cmd_set_depth_bounds {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        self.record(Command::SetDepthBounds {
+            depth_bounds: Range2 {
+                min: min_depth_bounds,
+                max: max_depth_bounds,
+            },
+        });
     }
 
     pub fn cmd_draw(
@@ -291,7 +1073,7 @@ impl CommandBuffer {
         first_vertex: u32,
         first_instance: u32,
     ) {
-        self.gpu_command_buffer.record(Command::DrawPrimitive {
+        self.record(Command::DrawPrimitive {
             vertex_count,
             instance_count,
             first_vertex,
@@ -307,14 +1089,266 @@ impl CommandBuffer {
         vertex_offset: i32,
         first_instance: u32,
     ) {
-        self.gpu_command_buffer
-            .record(Command::DrawPrimitiveIndexed {
-                index_count,
-                instance_count,
-                first_index,
-                vertex_offset,
-                first_instance,
-            });
+        self.record(Command::DrawPrimitiveIndexed {
+            index_count,
+            instance_count,
+            first_index,
+            vertex_offset,
+            first_instance,
+        });
+    }
+
+    pub fn cmd_dispatch(&mut self, group_count_x: u32, group_count_y: u32, group_count_z: u32) {
+        trace!("CommandBuffer::cmd_dispatch {}", Named::from(self.handle));
+        self.assert_recording();
+        let group_count = [group_count_x, group_count_y, group_count_z];
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkCmdDispatch-groupCountX-00386
+        if group_count
+            .iter()
+            .zip(MAX_COMPUTE_WORK_GROUP_COUNT)
+            .any(|(&count, max_count)| count > max_count)
+        {
+            error!(
+                "Dispatch of {:?} work groups exceeds maxComputeWorkGroupCount {:?}",
+                group_count, MAX_COMPUTE_WORK_GROUP_COUNT
+            );
+            return;
+        }
+        // Dispatch of no work groups is valid, but has nothing to execute.
+        if group_count.contains(&0) {
+            return;
+        }
+        self.record(Command::Dispatch {
+            x: group_count_x,
+            y: group_count_y,
+            z: group_count_z,
+        });
+    }
+
+    pub fn cmd_blit_image(
+        &mut self,
+        src_image: Arc<Mutex<Image>>,
+        src_image_layout: VkImageLayout,
+        dst_image: Arc<Mutex<Image>>,
+        dst_image_layout: VkImageLayout,
+        regions: &[VkImageBlit],
+        filter: VkFilter,
+    ) {
+        trace!("CommandBuffer::cmd_blit_image {}", Named::from(self.handle));
+        self.assert_recording();
+        let _ = src_image_layout;
+        let _ = dst_image_layout;
+        let same_image = Arc::ptr_eq(&src_image, &dst_image);
+        let src_image = src_image.lock();
+        let dst_guard = if same_image {
+            None
+        } else {
+            Some(dst_image.lock())
+        };
+        let dst_image = dst_guard.as_deref().unwrap_or(&src_image);
+        for region in regions {
+            let (src, dst) = (&region.srcSubresource, &region.dstSubresource);
+            if src.layerCount != dst.layerCount {
+                error!(
+                    "Blit source and destination layer counts differ: {:?}",
+                    region
+                );
+                continue;
+            }
+            let [src0, src1] = region.srcOffsets;
+            let [dst0, dst1] = region.dstOffsets;
+            if src0.z != 0 || src1.z != 1 || dst0.z != 0 || dst1.z != 1 {
+                warn!("TODO: Blit 3D images");
+            }
+            let bounds = |[offset0, offset1]: [VkOffset3D; 2]| {
+                let offset = VkOffset3D {
+                    x: offset0.x.min(offset1.x),
+                    y: offset0.y.min(offset1.y),
+                    z: 0,
+                };
+                let extent = VkExtent3D {
+                    width: offset0.x.abs_diff(offset1.x),
+                    height: offset0.y.abs_diff(offset1.y),
+                    depth: 1,
+                };
+                (offset, extent)
+            };
+            let (src_offset, src_extent) = bounds(region.srcOffsets);
+            let (dst_offset, dst_extent) = bounds(region.dstOffsets);
+            if src_image
+                .validate_copy_region(src, src_offset, src_extent)
+                .is_err()
+                || dst_image
+                    .validate_copy_region(dst, dst_offset, dst_extent)
+                    .is_err()
+            {
+                continue;
+            }
+            let offset_2d = |offset: VkOffset3D| Offset2 {
+                x: offset.x,
+                y: offset.y,
+            };
+            for layer in 0..src.layerCount {
+                self.record(Command::BlitImage {
+                    src_image: src_image
+                        .subresource_descriptor(src.mipLevel, src.baseArrayLayer + layer),
+                    dst_image: dst_image
+                        .subresource_descriptor(dst.mipLevel, dst.baseArrayLayer + layer),
+                    region: RegionBlitImage {
+                        src_offsets: [offset_2d(src0), offset_2d(src1)],
+                        src_format: src_image.format.into(),
+                        dst_offsets: [offset_2d(dst0), offset_2d(dst1)],
+                        dst_format: dst_image.format.into(),
+                    },
+                    filter: filter.into(),
+                });
+            }
+        }
+        drop(dst_guard);
+        drop(src_image);
+    }
+
+    pub fn cmd_resolve_image(
+        &mut self,
+        src_image: Arc<Mutex<Image>>,
+        src_image_layout: VkImageLayout,
+        dst_image: Arc<Mutex<Image>>,
+        dst_image_layout: VkImageLayout,
+        regions: &[VkImageResolve],
+    ) {
+        trace!(
+            "CommandBuffer::cmd_resolve_image {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let _ = src_image_layout;
+        let _ = dst_image_layout;
+        if Arc::ptr_eq(&src_image, &dst_image) {
+            error!("Resolve source and destination are the same image");
+            return;
+        }
+        let src_image = src_image.lock();
+        let dst_image = dst_image.lock();
+        if src_image.samples == 1 || dst_image.samples != 1 {
+            error!(
+                "Resolve from image with {} samples into image with {} samples",
+                src_image.samples, dst_image.samples
+            );
+            return;
+        }
+        if src_image.format != dst_image.format {
+            error!(
+                "Resolve source format {:?} differs from destination format {:?}",
+                src_image.format, dst_image.format
+            );
+            return;
+        }
+        for region in regions {
+            let (src, dst) = (&region.srcSubresource, &region.dstSubresource);
+            if src.layerCount != dst.layerCount {
+                error!(
+                    "Resolve source and destination layer counts differ: {:?}",
+                    region
+                );
+                continue;
+            }
+            if src_image
+                .validate_copy_region(src, region.srcOffset, region.extent)
+                .is_err()
+                || dst_image
+                    .validate_copy_region(dst, region.dstOffset, region.extent)
+                    .is_err()
+            {
+                continue;
+            }
+            for layer in 0..src.layerCount {
+                self.record(Command::ResolveImage {
+                    src_image: src_image
+                        .subresource_descriptor(src.mipLevel, src.baseArrayLayer + layer),
+                    dst_image: dst_image
+                        .subresource_descriptor(dst.mipLevel, dst.baseArrayLayer + layer),
+                    region: RegionResolveImage {
+                        src_offset: Offset2 {
+                            x: region.srcOffset.x,
+                            y: region.srcOffset.y,
+                        },
+                        dst_offset: Offset2 {
+                            x: region.dstOffset.x,
+                            y: region.dstOffset.y,
+                        },
+                        extent: Extent2 {
+                            width: region.extent.width,
+                            height: region.extent.height,
+                        },
+                        format: src_image.format.into(),
+                        samples: src_image.samples,
+                    },
+                });
+            }
+        }
+        drop(dst_image);
+        drop(src_image);
+    }
+
+    pub fn cmd_clear_color_image(
+        &mut self,
+        image: Arc<Mutex<Image>>,
+        image_layout: VkImageLayout,
+        color: VkClearColorValue,
+        ranges: &[VkImageSubresourceRange],
+    ) {
+        trace!(
+            "CommandBuffer::cmd_clear_color_image {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let _ = image_layout;
+        let image = image.lock();
+        let format = image.format.into();
+        for range in ranges {
+            let Ok(descriptors) = image.subresource_range_descriptors(range) else {
+                continue;
+            };
+            for descriptor in descriptors {
+                self.record(Command::ClearColorImage {
+                    image: descriptor,
+                    format,
+                    color: color.into(),
+                });
+            }
+        }
+        drop(image);
+    }
+
+    pub fn cmd_clear_depth_stencil_image(
+        &mut self,
+        image: Arc<Mutex<Image>>,
+        image_layout: VkImageLayout,
+        depth_stencil: VkClearDepthStencilValue,
+        ranges: &[VkImageSubresourceRange],
+    ) {
+        trace!(
+            "CommandBuffer::cmd_clear_depth_stencil_image {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let _ = image_layout;
+        let image = image.lock();
+        let format = image.format.into();
+        for range in ranges {
+            let Ok(descriptors) = image.subresource_range_descriptors(range) else {
+                continue;
+            };
+            for descriptor in descriptors {
+                self.record(Command::ClearDepthStencilImage {
+                    image: descriptor,
+                    format,
+                    depth: depth_stencil.depth,
+                    stencil: depth_stencil.stencil,
+                });
+            }
+        }
+        drop(image);
     }
 
     pub fn cmd_copy_buffer_to_image(
@@ -324,20 +1358,44 @@ impl CommandBuffer {
         dst_image_layout: VkImageLayout,
         regions: &[VkBufferImageCopy],
     ) {
+        trace!(
+            "CommandBuffer::cmd_copy_buffer_to_image {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
         let _ = dst_image_layout;
         let src_buffer = src_buffer.lock();
         let dst_image = dst_image.lock();
+        let bytes_per_pixel = dst_image.format.bytes_per_pixel() as u64;
         for region in regions {
-            self.gpu_command_buffer.record(Command::CopyBufferToImage {
+            let subresource = &region.imageSubresource;
+            if dst_image
+                .validate_copy_region(subresource, region.imageOffset, region.imageExtent)
+                .is_err()
+            {
+                continue;
+            }
+            let buffer_size = Self::buffer_image_copy_size(region, bytes_per_pixel);
+            if region.bufferOffset + buffer_size > src_buffer.size() {
+                error!(
+                    "Copy of {} bytes at {} is outside of buffer of size {}",
+                    buffer_size,
+                    region.bufferOffset,
+                    src_buffer.size()
+                );
+                continue;
+            }
+            self.record(Command::CopyBufferToImage {
                 src_buffer: src_buffer.descriptor(),
-                dst_image: dst_image.descriptor(),
+                dst_image: dst_image
+                    .subresource_descriptor(subresource.mipLevel, subresource.baseArrayLayer),
                 region: RegionCopyBufferImage {
                     buffer_offset: region.bufferOffset,
                     buffer_row_len: region.bufferRowLength,
                     buffer_image_height: region.bufferImageHeight,
-                    image_mip_level: region.imageSubresource.mipLevel,
-                    image_base_array_level: region.imageSubresource.baseArrayLayer,
-                    image_array_level_count: region.imageSubresource.layerCount,
+                    image_mip_level: subresource.mipLevel,
+                    image_base_array_level: subresource.baseArrayLayer,
+                    image_array_level_count: subresource.layerCount,
                     image_offset: Offset3::<i32> {
                         x: region.imageOffset.x,
                         y: region.imageOffset.y,
@@ -354,6 +1412,28 @@ impl CommandBuffer {
         }
     }
 
+    /// Returns number of buffer bytes addressed by region, from its buffer offset.
+    const fn buffer_image_copy_size(region: &VkBufferImageCopy, bytes_per_pixel: u64) -> u64 {
+        let extent = region.imageExtent;
+        if extent.width == 0 || extent.height == 0 || extent.depth == 0 {
+            return 0;
+        }
+        let row_len = if region.bufferRowLength == 0 {
+            extent.width
+        } else {
+            region.bufferRowLength
+        } as u64;
+        let image_height = if region.bufferImageHeight == 0 {
+            extent.height
+        } else {
+            region.bufferImageHeight
+        } as u64;
+        let slices = region.imageSubresource.layerCount as u64 * extent.depth as u64;
+        let texels = ((slices - 1) * image_height + extent.height as u64 - 1) * row_len
+            + extent.width as u64;
+        texels * bytes_per_pixel
+    }
+
     pub fn cmd_copy_image_to_buffer(
         &mut self,
         src_image: Arc<Mutex<Image>>,
@@ -365,7 +1445,7 @@ impl CommandBuffer {
         let src_image = src_image.lock();
         let dst_buffer = dst_buffer.lock();
         for region in regions {
-            self.gpu_command_buffer.record(Command::CopyImageToBuffer {
+            self.record(Command::CopyImageToBuffer {
                 src_image: src_image.descriptor(),
                 dst_buffer: dst_buffer.descriptor(),
                 region: RegionCopyBufferImage {
@@ -397,11 +1477,29 @@ impl CommandBuffer {
         dst_buffer: Arc<Mutex<Buffer>>,
         regions: &[VkBufferCopy],
     ) {
-        let _ = regions;
+        trace!(
+            "CommandBuffer::cmd_copy_buffer_to_buffer {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let same_buffer = Arc::ptr_eq(&src_buffer, &dst_buffer);
+        let (src_buffer, src_size) = {
+            let src_buffer = src_buffer.lock();
+            (src_buffer.descriptor(), src_buffer.size())
+        };
+        let (dst_buffer, dst_size) = if same_buffer {
+            (src_buffer.clone(), src_size)
+        } else {
+            let dst_buffer = dst_buffer.lock();
+            (dst_buffer.descriptor(), dst_buffer.size())
+        };
+        if !Self::validate_buffer_copy_regions(src_size, dst_size, same_buffer, regions) {
+            return;
+        }
         for region in regions {
-            self.gpu_command_buffer.record(Command::CopyBufferToBuffer {
-                src_buffer: src_buffer.lock().descriptor(),
-                dst_buffer: dst_buffer.lock().descriptor(),
+            self.record(Command::CopyBufferToBuffer {
+                src_buffer: src_buffer.clone(),
+                dst_buffer: dst_buffer.clone(),
                 region: gpu::RegionCopyBufferBuffer {
                     src_offset: region.srcOffset,
                     dst_offset: region.dstOffset,
@@ -411,14 +1509,3903 @@ impl CommandBuffer {
         }
     }
 
-    pub fn cmd_execute_commands(
-        &mut self,
-        command_buffers: impl IntoIterator<Item = Arc<Mutex<Self>>>,
-    ) {
-        for command_buffer in command_buffers {
-            self.gpu_command_buffer.record(Command::ExecuteCommands {
-                command_buffer: command_buffer.lock().gpu_command_buffer.clone(),
-            })
+    /// Checks that copied ranges lie within buffers and that copying within the same buffer
+    /// does not read bytes written by the copy.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkCmdCopyBuffer
+    fn validate_buffer_copy_regions(
+        src_size: u64,
+        dst_size: u64,
+        same_buffer: bool,
+        regions: &[VkBufferCopy],
+    ) -> bool {
+        let range = |offset: u64, size: u64| offset..offset.saturating_add(size);
+        for region in regions {
+            if region.size == 0 {
+                error!("Buffer copy region size must be greater than 0");
+                return false;
+            }
+            let src_range = range(region.srcOffset, region.size);
+            let dst_range = range(region.dstOffset, region.size);
+            if src_range.end > src_size || dst_range.end > dst_size {
+                error!(
+                    "Buffer copy region {:?} -> {:?} exceeds buffer sizes {} and {}",
+                    src_range, dst_range, src_size, dst_size
+                );
+                return false;
+            }
+        }
+        if same_buffer {
+            for src in regions {
+                let src_range = range(src.srcOffset, src.size);
+                for dst in regions {
+                    let dst_range = range(dst.dstOffset, dst.size);
+                    if src_range.start < dst_range.end && dst_range.start < src_range.end {
+                        error!(
+                            "Buffer copy source {:?} overlaps destination {:?}",
+                            src_range, dst_range
+                        );
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkCmdUpdateBuffer
+    pub fn cmd_update_buffer(&mut self, dst_buffer: Arc<Mutex<Buffer>>, offset: u64, data: &[u8]) {
+        trace!(
+            "CommandBuffer::cmd_update_buffer {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let (buffer, buffer_size) = {
+            let dst_buffer = dst_buffer.lock();
+            (dst_buffer.descriptor(), dst_buffer.size())
+        };
+        let size = data.len() as u64;
+        if size == 0 || size > 65536 || !size.is_multiple_of(4) {
+            error!(
+                "Buffer update size {} must be a multiple of 4 in 4..=65536",
+                size
+            );
+            return;
         }
+        if !offset.is_multiple_of(4) || offset.saturating_add(size) > buffer_size {
+            error!(
+                "Buffer update of {} bytes at offset {} is unaligned or exceeds buffer size {}",
+                size, offset, buffer_size
+            );
+            return;
+        }
+        self.record(Command::UpdateBuffer {
+            buffer,
+            offset,
+            data: data.to_vec(),
+        })
+    }
+
+    /// Records fill of `size` bytes of buffer at `offset`, to the end of buffer rounded down to
+    /// a multiple of 4 bytes for `VK_WHOLE_SIZE`.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkCmdFillBuffer
+    pub fn cmd_fill_buffer(
+        &mut self,
+        dst_buffer: Arc<Mutex<Buffer>>,
+        offset: u64,
+        size: u64,
+        value: u32,
+    ) {
+        trace!(
+            "CommandBuffer::cmd_fill_buffer {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let (buffer, buffer_size) = {
+            let dst_buffer = dst_buffer.lock();
+            (dst_buffer.descriptor(), dst_buffer.size())
+        };
+        if !offset.is_multiple_of(4) || offset >= buffer_size {
+            error!(
+                "Buffer fill offset {} is unaligned or exceeds buffer size {}",
+                offset, buffer_size
+            );
+            return;
+        }
+        let size = if size == VK_WHOLE_SIZE {
+            (buffer_size - offset) & !3
+        } else {
+            size
+        };
+        if size == 0 || !size.is_multiple_of(4) || offset + size > buffer_size {
+            error!(
+                "Buffer fill of {} bytes at offset {} is unaligned or exceeds buffer size {}",
+                size, offset, buffer_size
+            );
+            return;
+        }
+        self.record(Command::FillBuffer {
+            buffer,
+            offset,
+            size,
+            value,
+        })
+    }
+
+    /// Records setting `event` outside of render pass. Commands execute in order, so every stage
+    /// of previous ones is complete by then.
+    pub fn cmd_set_event(&mut self, event: Arc<Mutex<Event>>) {
+        trace!("CommandBuffer::cmd_set_event {}", Named::from(self.handle));
+        self.assert_recording();
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkCmdSetEvent-renderpass
+        if self.render_pass.is_some() {
+            error!("Event set inside render pass");
+            return;
+        }
+        let event = event.lock().gpu_event();
+        self.record(Command::SetEvent { event });
+    }
+
+    /// Records resetting `event` outside of render pass.
+    pub fn cmd_reset_event(&mut self, event: Arc<Mutex<Event>>) {
+        trace!(
+            "CommandBuffer::cmd_reset_event {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkCmdResetEvent-renderpass
+        if self.render_pass.is_some() {
+            error!("Event reset inside render pass");
+            return;
+        }
+        let event = event.lock().gpu_event();
+        self.record(Command::ResetEvent { event });
+    }
+
+    /// Records waiting with execution of following commands until every one of `events` is set,
+    /// either by previous commands or by the host.
+    pub fn cmd_wait_events(&mut self, events: Vec<Arc<Mutex<Event>>>) {
+        trace!(
+            "CommandBuffer::cmd_wait_events {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let events = events
+            .iter()
+            .map(|event| event.lock().gpu_event())
+            .collect();
+        self.record(Command::WaitEvents { events });
+    }
+
+    /// Records making queries unavailable, outside of render pass.
+    pub fn cmd_reset_query_pool(
+        &mut self,
+        query_pool: Arc<Mutex<QueryPool>>,
+        first_query: u32,
+        query_count: u32,
+    ) {
+        trace!(
+            "CommandBuffer::cmd_reset_query_pool {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        if self.render_pass.is_some() {
+            error!("Query pool reset inside render pass");
+            return;
+        }
+        let Some(queries) = query_pool.lock().queries(first_query, query_count) else {
+            return;
+        };
+        for query in queries {
+            self.record(Command::ResetQuery { query });
+        }
+    }
+
+    /// Records beginning of query counting samples passing depth and stencil tests of following
+    /// draws.
+    pub fn cmd_begin_query(
+        &mut self,
+        query_pool: Arc<Mutex<QueryPool>>,
+        query: u32,
+        flags: VkQueryControlFlags,
+    ) {
+        trace!(
+            "CommandBuffer::cmd_begin_query {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        // Sample counts are always exact, as if precise occlusion query was requested.
+        let _ = flags;
+        let Some(query) =
+            Self::query_of_type(&query_pool, query, VkQueryType::VK_QUERY_TYPE_OCCLUSION)
+        else {
+            return;
+        };
+        self.record(Command::BeginQuery { query });
+    }
+
+    pub fn cmd_end_query(&mut self, query_pool: Arc<Mutex<QueryPool>>, query: u32) {
+        trace!("CommandBuffer::cmd_end_query {}", Named::from(self.handle));
+        self.assert_recording();
+        let Some(query) =
+            Self::query_of_type(&query_pool, query, VkQueryType::VK_QUERY_TYPE_OCCLUSION)
+        else {
+            return;
+        };
+        self.record(Command::EndQuery { query });
+    }
+
+    /// Records writing time at which command executes into timestamp query. Commands execute in
+    /// order, so `pipeline_stage` doesn't make difference.
+    pub fn cmd_write_timestamp(
+        &mut self,
+        pipeline_stage: VkPipelineStageFlags2,
+        query_pool: Arc<Mutex<QueryPool>>,
+        query: u32,
+    ) {
+        trace!(
+            "CommandBuffer::cmd_write_timestamp {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let _ = pipeline_stage;
+        let Some(query) =
+            Self::query_of_type(&query_pool, query, VkQueryType::VK_QUERY_TYPE_TIMESTAMP)
+        else {
+            return;
+        };
+        self.record(Command::WriteTimestamp { query });
+    }
+
+    /// Returns `query` of pool, `None` if it is out of range or pool holds queries of other type
+    /// than `query_type`.
+    fn query_of_type(
+        query_pool: &Arc<Mutex<QueryPool>>,
+        query: u32,
+        query_type: VkQueryType,
+    ) -> Option<gpu::Query> {
+        let query_pool = query_pool.lock();
+        if query_pool.query_type() != query_type {
+            error!(
+                "Query of {:?} type used as query of {:?} type",
+                query_pool.query_type(),
+                query_type
+            );
+            return None;
+        }
+        query_pool.queries(query, 1)?.pop()
+    }
+
+    /// Records commands of secondary command buffers to be executed in order. Inside render
+    /// pass, secondaries must continue compatible render pass at current subpass.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkCmdExecuteCommands
+    pub fn cmd_execute_commands(
+        &mut self,
+        command_buffers: impl IntoIterator<Item = Arc<Mutex<Self>>>,
+    ) {
+        trace!(
+            "CommandBuffer::cmd_execute_commands {}",
+            Named::from(self.handle)
+        );
+        self.assert_recording();
+        let mut buffers = vec![];
+        for command_buffer in command_buffers {
+            let command_buffer = command_buffer.lock();
+            if command_buffer.level != VkCommandBufferLevel::VK_COMMAND_BUFFER_LEVEL_SECONDARY {
+                error!("Executed command buffer is not secondary");
+                return;
+            }
+            if command_buffer.state != CommandBufferState::Executable {
+                error!(
+                    "Executed command buffer is in {:?} state",
+                    command_buffer.state
+                );
+                return;
+            }
+            if let Some(render_pass) = &self.render_pass {
+                let Some(inheritance) = &command_buffer.inheritance else {
+                    error!("Command buffer executed inside render pass does not continue it");
+                    return;
+                };
+                let Some(current_render_pass) = &render_pass.render_pass else {
+                    error!(
+                        "Command buffer continuing render pass executed inside dynamic rendering"
+                    );
+                    return;
+                };
+                if !Arc::ptr_eq(&inheritance.render_pass, current_render_pass)
+                    && !inheritance
+                        .render_pass
+                        .lock()
+                        .is_compatible(&current_render_pass.lock())
+                {
+                    error!("Inherited render pass is incompatible with current render pass");
+                    return;
+                }
+                if inheritance.subpass != render_pass.subpass {
+                    error!(
+                        "Inherited subpass {} is not current subpass {}",
+                        inheritance.subpass, render_pass.subpass
+                    );
+                    return;
+                }
+            }
+            buffers.push(command_buffer.gpu_command_buffer.clone());
+        }
+        self.record(Command::ExecuteCommands { buffers })
+    }
+}
+
+/// Returns pipeline of gpu that resources bound to `bind_point` are used by.
+fn gpu_bind_point(bind_point: VkPipelineBindPoint) -> Option<gpu::PipelineBindPoint> {
+    match bind_point {
+        VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS => {
+            Some(gpu::PipelineBindPoint::Graphics)
+        }
+        VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_COMPUTE => {
+            Some(gpu::PipelineBindPoint::Compute)
+        }
+        _ => {
+            warn!("TODO: Bind descriptor sets to {bind_point:?}");
+            None
+        }
+    }
+}
+
+/// Converts render area rectangle into area of render targets.
+const fn to_render_area(render_area: VkRect2D) -> gpu::RenderArea {
+    gpu::RenderArea {
+        extent: Extent2::<u32> {
+            width: render_area.extent.width,
+            height: render_area.extent.height,
+        },
+        offset: Offset2::<i32> {
+            x: render_area.offset.x,
+            y: render_area.offset.y,
+        },
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::descriptor::{DescriptorPool, DescriptorSetLayout};
+    use crate::error::RuntimeError;
+    use crate::fence::Fence;
+    use crate::logical_device::tests::create_logical_device;
+    use crate::memory::MemoryAllocation;
+    use crate::physical_device::PhysicalDevice;
+    use crate::pipeline::SubpassDescription;
+    use crate::queue::SubmitInfo;
+    use crate::semaphore::Semaphore;
+    use common::graphics::{
+        CompareOp, VertexAttribute, VertexBinding, VertexInputRate, VertexInputState,
+    };
+    use common::math::Format;
+    use gpu::{InputAssemblyState, PrimitiveTopology, ViewportState};
+    use shader::glsl::{Shader, ShaderState};
+    use std::ptr::NonNull;
+
+    pub fn create_command_buffer(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+    ) -> Arc<Mutex<CommandBuffer>> {
+        create_command_buffer_of_level(
+            logical_device,
+            VkCommandBufferLevel::VK_COMMAND_BUFFER_LEVEL_PRIMARY,
+        )
+    }
+
+    fn create_command_buffer_of_level(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        level: VkCommandBufferLevel,
+    ) -> Arc<Mutex<CommandBuffer>> {
+        let command_pool_create_info = VkCommandPoolCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_COMMAND_POOL_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queueFamilyIndex: 0,
+        };
+        let command_pool = CommandPool::create(logical_device, &command_pool_create_info);
+        let allocate_info = VkCommandBufferAllocateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
+            pNext: None,
+            commandPool: command_pool,
+            level,
+            commandBufferCount: 1,
+        };
+        let Ok(command_buffer) = CommandBuffer::create(&allocate_info) else {
+            unreachable!()
+        };
+        let Some(command_buffer) = CommandBuffer::from_handle(command_buffer) else {
+            unreachable!()
+        };
+        command_buffer
+    }
+
+    #[test]
+    fn command_buffer_records_commands() {
+        let command_buffer = create_command_buffer(create_logical_device());
+        let mut command_buffer = command_buffer.lock();
+        assert_eq!(command_buffer.state(), CommandBufferState::Initial);
+
+        assert_eq!(command_buffer.begin(), Ok(()));
+        assert_eq!(command_buffer.state(), CommandBufferState::Recording);
+        command_buffer.cmd_draw(3, 1, 0, 0);
+        command_buffer.cmd_draw_indexed(6, 2, 1, -1, 1);
+        assert_eq!(command_buffer.end(), Ok(()));
+        assert_eq!(command_buffer.state(), CommandBufferState::Executable);
+
+        let commands = command_buffer.commands();
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(
+            commands[0],
+            Command::DrawPrimitive {
+                vertex_count: 3,
+                instance_count: 1,
+                first_vertex: 0,
+                first_instance: 0,
+            }
+        ));
+        assert!(matches!(
+            commands[1],
+            Command::DrawPrimitiveIndexed {
+                index_count: 6,
+                instance_count: 2,
+                first_index: 1,
+                vertex_offset: -1,
+                first_instance: 1,
+            }
+        ));
+
+        command_buffer.mark_pending();
+        assert_eq!(command_buffer.state(), CommandBufferState::Pending);
+        assert_eq!(
+            command_buffer.begin(),
+            Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT)
+        );
+        command_buffer.complete();
+        assert_eq!(command_buffer.commands().len(), 2);
+
+        // Beginning again clears previously recorded commands.
+        assert_eq!(command_buffer.begin(), Ok(()));
+        assert!(command_buffer.commands().is_empty());
+        drop(command_buffer);
+    }
+
+    #[test]
+    fn dispatch_records_only_counts_within_limits() {
+        let command_buffer = create_command_buffer(create_logical_device());
+        let mut command_buffer = command_buffer.lock();
+        assert_eq!(command_buffer.begin(), Ok(()));
+        let [max_x, max_y, max_z] = MAX_COMPUTE_WORK_GROUP_COUNT;
+        command_buffer.cmd_dispatch(max_x + 1, 1, 1);
+        command_buffer.cmd_dispatch(1, 1, max_z + 1);
+        command_buffer.cmd_dispatch(0, 1, 1);
+        command_buffer.cmd_dispatch(max_x, max_y, max_z);
+        assert_eq!(command_buffer.end(), Ok(()));
+
+        let commands = command_buffer.commands();
+        assert_eq!(commands.len(), 1);
+        assert!(matches!(
+            commands[0],
+            Command::Dispatch { x, y, z } if [x, y, z] == MAX_COMPUTE_WORK_GROUP_COUNT
+        ));
+        drop(command_buffer);
+    }
+
+    #[test]
+    fn command_buffer_rejects_invalid_transitions() {
+        let command_buffer = create_command_buffer(create_logical_device());
+        let mut command_buffer = command_buffer.lock();
+        assert_eq!(
+            command_buffer.end(),
+            Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT)
+        );
+        assert_eq!(command_buffer.begin(), Ok(()));
+        assert_eq!(
+            command_buffer.begin(),
+            Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT)
+        );
+        assert_eq!(command_buffer.reset(), Ok(()));
+        assert_eq!(command_buffer.state(), CommandBufferState::Initial);
+        drop(command_buffer);
+    }
+
+    #[test]
+    #[should_panic(expected = "command recorded outside of recording state")]
+    fn command_buffer_record_after_end_panics() {
+        let command_buffer = create_command_buffer(create_logical_device());
+        let mut command_buffer = command_buffer.lock();
+        assert_eq!(command_buffer.begin(), Ok(()));
+        assert_eq!(command_buffer.end(), Ok(()));
+        command_buffer.cmd_draw(3, 1, 0, 0);
+    }
+
+    /// Creates R8G8B8A8 attachment cleared at the beginning of single subpass render pass.
+    pub fn create_framebuffer(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        extent: (u32, u32),
+    ) -> (Arc<Mutex<RenderPass>>, Arc<Mutex<Framebuffer>>) {
+        create_multisampled_framebuffer(
+            logical_device,
+            extent,
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+        )
+    }
+
+    /// Creates R8G8B8A8 attachment with given sample count cleared at the beginning of single
+    /// subpass render pass.
+    fn create_multisampled_framebuffer(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        (width, height): (u32, u32),
+        samples: VkSampleCountFlagBits,
+    ) -> (Arc<Mutex<RenderPass>>, Arc<Mutex<Framebuffer>>) {
+        let image_view = create_color_attachment(logical_device.clone(), (width, height), samples);
+        create_framebuffer_of_attachment(logical_device, image_view, (width, height), samples)
+    }
+
+    /// Creates single subpass render pass clearing R8G8B8A8 `image_view` with given sample count
+    /// at its beginning, along with framebuffer of the view.
+    fn create_framebuffer_of_attachment(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        image_view: Arc<Mutex<ImageView>>,
+        (width, height): (u32, u32),
+        samples: VkSampleCountFlagBits,
+    ) -> (Arc<Mutex<RenderPass>>, Arc<Mutex<Framebuffer>>) {
+        let attachment = AttachmentDescription {
+            flags: 0.into(),
+            format: VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            samples,
+            load_op: VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR,
+            store_op: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE,
+            stencil_load_pp: VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE,
+            stencil_store_op: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE,
+            initial_layout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+            final_layout: VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+        };
+        let subpass = SubpassDescription {
+            flags: 0.into(),
+            pipeline_bind_point: VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+            input_attachments: Arc::new([]),
+            color_attachments: Arc::new([VkAttachmentReference {
+                attachment: 0,
+                layout: VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+            }]),
+            resolve_attachments: Arc::new([]),
+            depth_stencil_attachment: None,
+            preserve_attachments: Arc::new([]),
+        };
+        let Ok(render_pass) =
+            RenderPass::create(logical_device.clone(), &[attachment], &[], &[subpass])
+        else {
+            unreachable!()
+        };
+        let Some(render_pass) = RenderPass::from_handle(render_pass) else {
+            unreachable!()
+        };
+        let Ok(framebuffer) = Framebuffer::create(
+            logical_device,
+            0,
+            width,
+            height,
+            1,
+            vec![image_view],
+            render_pass.clone(),
+        ) else {
+            unreachable!()
+        };
+        let Some(framebuffer) = Framebuffer::from_handle(framebuffer) else {
+            unreachable!()
+        };
+        (render_pass, framebuffer)
+    }
+
+    /// Creates R8G8B8A8 image with given sample count bound to new memory, returning its view.
+    fn create_color_attachment(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        extent: (u32, u32),
+        samples: VkSampleCountFlagBits,
+    ) -> Arc<Mutex<ImageView>> {
+        create_attachment(
+            logical_device,
+            extent,
+            samples,
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+        )
+    }
+
+    /// Creates color or depth image of `format` with given sample count bound to new memory,
+    /// returning its view.
+    fn create_attachment(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        (width, height): (u32, u32),
+        samples: VkSampleCountFlagBits,
+        format: VkFormat,
+    ) -> Arc<Mutex<ImageView>> {
+        let (usage, aspect_mask) = if Format::from(format).has_depth() {
+            (
+                VkImageUsageFlagBits::VK_IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT,
+                VkImageAspectFlagBits::VK_IMAGE_ASPECT_DEPTH_BIT,
+            )
+        } else {
+            (
+                VkImageUsageFlagBits::VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT,
+                VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT,
+            )
+        };
+        let extent = VkExtent3D {
+            width,
+            height,
+            depth: 1,
+        };
+        let image_create_info = VkImageCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            imageType: VkImageType::VK_IMAGE_TYPE_2D,
+            format,
+            extent,
+            mipLevels: 1,
+            arrayLayers: 1,
+            samples,
+            tiling: VkImageTiling::VK_IMAGE_TILING_LINEAR,
+            usage: usage.into(),
+            sharingMode: VkSharingMode::VK_SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: None,
+            initialLayout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+        };
+        let Ok(image) = Image::create(logical_device.clone(), &image_create_info) else {
+            unreachable!()
+        };
+        let Some(image_object) = Image::from_handle(image) else {
+            unreachable!()
+        };
+        let size = image_object.lock().size_in_bytes();
+        let Ok(memory) = MemoryAllocation::create(logical_device.clone(), size, 0) else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        assert_eq!(
+            image_object.lock().bind_memory(memory, 0),
+            VkResult::VK_SUCCESS
+        );
+        create_image_view(logical_device, image, format, aspect_mask)
+    }
+
+    /// Creates 2D view of `aspect_mask` of first mip level and array layer of `image`.
+    pub fn create_image_view(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        image: VkImage,
+        format: VkFormat,
+        aspect_mask: VkImageAspectFlagBits,
+    ) -> Arc<Mutex<ImageView>> {
+        let image_view_create_info = VkImageViewCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            image,
+            viewType: VkImageViewType::VK_IMAGE_VIEW_TYPE_2D,
+            format,
+            components: VkComponentMapping {
+                r: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+                g: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+                b: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+                a: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+            },
+            subresourceRange: VkImageSubresourceRange {
+                aspectMask: aspect_mask.into(),
+                baseMipLevel: 0,
+                levelCount: 1,
+                baseArrayLayer: 0,
+                layerCount: 1,
+            },
+        };
+        let Ok(image_view) = ImageView::create(logical_device, &image_view_create_info) else {
+            unreachable!()
+        };
+        let Some(image_view) = ImageView::from_handle(image_view) else {
+            unreachable!()
+        };
+        image_view
+    }
+
+    #[test]
+    fn framebuffer_rejects_incompatible_attachments() {
+        let logical_device = create_logical_device();
+        let (render_pass, framebuffer) = create_framebuffer(logical_device.clone(), (4, 4));
+        let (_, multisampled_framebuffer) = create_multisampled_framebuffer(
+            logical_device.clone(),
+            (4, 4),
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_4_BIT,
+        );
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let multisampled_image_view = multisampled_framebuffer.lock().attachments[0].clone();
+        let create = |width, attachments| {
+            Framebuffer::create(
+                logical_device.clone(),
+                0,
+                width,
+                4,
+                1,
+                attachments,
+                render_pass.clone(),
+            )
+        };
+
+        assert!(create(4, vec![image_view.clone()]).is_ok());
+        assert_eq!(create(4, vec![]), Err(RuntimeError::ValidationFailed));
+        assert_eq!(
+            create(4, vec![multisampled_image_view]),
+            Err(RuntimeError::ValidationFailed)
+        );
+        assert_eq!(
+            create(8, vec![image_view]),
+            Err(RuntimeError::ValidationFailed)
+        );
+    }
+
+    #[test]
+    fn render_pass_clears_attachment() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) =
+            create_framebuffer(logical_device.clone(), (extent.width, extent.height));
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let size = (extent.width * extent.height * 4) as u64;
+
+        let clear_color = [0.0f32, 1.0f32, 0.0f32, 1.0f32];
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new(clear_color),
+            }),
+        };
+        let render_area = VkRect2D {
+            offset: VkOffset2D { x: 0, y: 0 },
+            extent,
+        };
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        assert_eq!(command_buffer_guard.subpass(), None);
+        command_buffer_guard.cmd_begin_render_pass(
+            render_pass,
+            framebuffer,
+            render_area,
+            &[clear_value],
+            VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+        );
+        assert_eq!(command_buffer_guard.subpass(), Some(0));
+        command_buffer_guard.cmd_end_render_pass();
+        assert_eq!(command_buffer_guard.subpass(), None);
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                command_buffers: vec![command_buffer],
+                ..Default::default()
+            }],
+            None,
+        );
+
+        let descriptor = image_view.lock().descriptor();
+        let logical_device = logical_device.lock();
+        let pixels = logical_device
+            .physical_device()
+            .gpu
+            .memory
+            .read_bytes(&descriptor.binding, 0, size)
+            .to_vec();
+        drop(logical_device);
+        assert!(pixels
+            .chunks_exact(4)
+            .all(|pixel| pixel == [0, 255, 0, 255]));
+    }
+
+    fn create_buffer(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        size: u64,
+        memory: Arc<Mutex<MemoryAllocation>>,
+        offset: u64,
+    ) -> Arc<Mutex<Buffer>> {
+        let Some(buffer) = Buffer::from_handle(Buffer::create(logical_device, size, 0, 0)) else {
+            unreachable!()
+        };
+        assert_eq!(
+            buffer.lock().bind_memory(memory, offset),
+            VkResult::VK_SUCCESS
+        );
+        buffer
+    }
+
+    #[test]
+    fn copy_buffer_copies_regions() {
+        let logical_device = create_logical_device();
+        let Ok(memory) = MemoryAllocation::create(logical_device.clone(), 64, 0) else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        // Both buffers share single allocation.
+        let src_buffer = create_buffer(logical_device.clone(), 16, memory.clone(), 0);
+        let dst_buffer = create_buffer(logical_device.clone(), 16, memory, 32);
+        let src_descriptor = src_buffer.lock().descriptor();
+        let dst_descriptor = dst_buffer.lock().descriptor();
+        let src_bytes = (0..16).collect::<Vec<u8>>();
+        logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .write_bytes(&src_bytes, &src_descriptor.binding, 0);
+
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        command_buffer_guard.cmd_copy_buffer_to_buffer(
+            src_buffer,
+            dst_buffer,
+            &[
+                VkBufferCopy {
+                    srcOffset: 0,
+                    dstOffset: 8,
+                    size: 4,
+                },
+                VkBufferCopy {
+                    srcOffset: 8,
+                    dstOffset: 0,
+                    size: 8,
+                },
+            ],
+        );
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                command_buffers: vec![command_buffer],
+                ..Default::default()
+            }],
+            None,
+        );
+
+        let logical_device = logical_device.lock();
+        let bytes = logical_device
+            .physical_device()
+            .gpu
+            .memory
+            .read_bytes(&dst_descriptor.binding, 32, 16)
+            .to_vec();
+        drop(logical_device);
+        assert_eq!(
+            bytes,
+            [8, 9, 10, 11, 12, 13, 14, 15, 0, 1, 2, 3, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn commands_after_wait_events_execute_once_host_sets_event() {
+        let logical_device = create_logical_device();
+        let Ok(memory) = MemoryAllocation::create(logical_device.clone(), 16, 0) else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        let buffer = create_buffer(logical_device.clone(), 16, memory, 0);
+        let descriptor = buffer.lock().descriptor();
+        let event_create_info = VkEventCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_EVENT_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let Ok(set_event) = Event::create(logical_device.clone(), &event_create_info) else {
+            unreachable!()
+        };
+        let Some(set_event) = Event::from_handle(set_event) else {
+            unreachable!()
+        };
+        let Ok(host_event) = Event::create(logical_device.clone(), &event_create_info) else {
+            unreachable!()
+        };
+        let Some(host_event) = Event::from_handle(host_event) else {
+            unreachable!()
+        };
+
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        command_buffer_guard.cmd_fill_buffer(buffer.clone(), 0, 8, 0x0101_0101);
+        // Event set earlier in the same submission doesn't stall execution.
+        command_buffer_guard.cmd_set_event(set_event.clone());
+        command_buffer_guard.cmd_wait_events(vec![set_event.clone()]);
+        command_buffer_guard.cmd_wait_events(vec![set_event.clone(), host_event.clone()]);
+        command_buffer_guard.cmd_fill_buffer(buffer, 8, 8, 0x0202_0202);
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+
+        let fence_create_info = VkFenceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let Ok(fence) = Fence::create(logical_device.clone(), &fence_create_info) else {
+            unreachable!()
+        };
+        let Some(fence) = Fence::from_handle(fence) else {
+            unreachable!()
+        };
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                command_buffers: vec![command_buffer.clone()],
+                ..Default::default()
+            }],
+            Some(fence.clone()),
+        );
+
+        let read_buffer = || {
+            let logical_device = logical_device.lock();
+            let bytes = logical_device
+                .physical_device()
+                .gpu
+                .memory
+                .read_bytes(&descriptor.binding, 0, 16)
+                .to_vec();
+            drop(logical_device);
+            bytes
+        };
+        assert_eq!(set_event.lock().status(), VkResult::VK_EVENT_SET);
+        assert_eq!(host_event.lock().status(), VkResult::VK_EVENT_RESET);
+        assert_eq!(read_buffer(), [[1; 8], [0; 8]].concat());
+        assert!(!fence.lock().is_signaled());
+        assert_eq!(command_buffer.lock().state(), CommandBufferState::Pending);
+
+        host_event.lock().set();
+        assert_eq!(host_event.lock().status(), VkResult::VK_EVENT_SET);
+        assert_eq!(read_buffer(), [[1; 8], [2; 8]].concat());
+        assert!(fence.lock().is_signaled());
+        assert_eq!(
+            command_buffer.lock().state(),
+            CommandBufferState::Executable
+        );
+    }
+
+    #[test]
+    fn batch_waiting_for_timeline_semaphore_executes_once_host_signals_it() {
+        let logical_device = create_logical_device();
+        let Ok(memory) = MemoryAllocation::create(logical_device.clone(), 8, 0) else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        let buffer = create_buffer(logical_device.clone(), 8, memory, 0);
+        let descriptor = buffer.lock().descriptor();
+        let semaphore_type_create_info = VkSemaphoreTypeCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_TYPE_CREATE_INFO,
+            pNext: None,
+            semaphoreType: VkSemaphoreType::VK_SEMAPHORE_TYPE_TIMELINE,
+            initialValue: 0,
+        };
+        let semaphore_create_info = VkSemaphoreCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+            pNext: Some(NonNull::from(&semaphore_type_create_info).cast()),
+            flags: 0,
+        };
+        let Ok(semaphore) = Semaphore::create(logical_device.clone(), &semaphore_create_info)
+        else {
+            unreachable!()
+        };
+        let Some(semaphore) = Semaphore::from_handle(semaphore) else {
+            unreachable!()
+        };
+
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        command_buffer_guard.cmd_fill_buffer(buffer, 0, 8, 0x0101_0101);
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                wait_semaphores: vec![semaphore.clone()],
+                wait_semaphore_values: vec![2],
+                command_buffers: vec![command_buffer.clone()],
+                ..Default::default()
+            }],
+            None,
+        );
+
+        let read_buffer = || {
+            let logical_device = logical_device.lock();
+            let bytes = logical_device
+                .physical_device()
+                .gpu
+                .memory
+                .read_bytes(&descriptor.binding, 0, 8)
+                .to_vec();
+            drop(logical_device);
+            bytes
+        };
+        assert_eq!(read_buffer(), [0; 8]);
+        assert!(!queue.lock().is_idle());
+
+        // Value below the one waited for doesn't resume the batch.
+        Semaphore::signal_and_resume(&semaphore, 1);
+        assert_eq!(read_buffer(), [0; 8]);
+        assert_eq!(command_buffer.lock().state(), CommandBufferState::Pending);
+
+        Semaphore::signal_and_resume(&semaphore, 2);
+        assert_eq!(read_buffer(), [1; 8]);
+        assert!(queue.lock().is_idle());
+        assert_eq!(
+            command_buffer.lock().state(),
+            CommandBufferState::Executable
+        );
+    }
+
+    #[test]
+    fn copy_buffer_rejects_invalid_regions() {
+        let logical_device = create_logical_device();
+        let Ok(memory) = MemoryAllocation::create(logical_device.clone(), 32, 0) else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        let src_buffer = create_buffer(logical_device.clone(), 16, memory.clone(), 0);
+        let dst_buffer = create_buffer(logical_device.clone(), 16, memory, 16);
+        let command_buffer = create_command_buffer(logical_device);
+        let mut command_buffer = command_buffer.lock();
+        assert_eq!(command_buffer.begin(), Ok(()));
+
+        let region = |src_offset, dst_offset, size| VkBufferCopy {
+            srcOffset: src_offset,
+            dstOffset: dst_offset,
+            size,
+        };
+        // Out of destination buffer.
+        command_buffer.cmd_copy_buffer_to_buffer(
+            src_buffer.clone(),
+            dst_buffer,
+            &[region(0, 12, 8)],
+        );
+        // Overlapping within the same buffer.
+        command_buffer.cmd_copy_buffer_to_buffer(
+            src_buffer.clone(),
+            src_buffer.clone(),
+            &[region(0, 4, 8)],
+        );
+        assert!(command_buffer.commands().is_empty());
+
+        // Disjoint ranges within the same buffer can be copied.
+        command_buffer.cmd_copy_buffer_to_buffer(
+            src_buffer.clone(),
+            src_buffer,
+            &[region(0, 8, 8)],
+        );
+        assert_eq!(command_buffer.commands().len(), 1);
+        drop(command_buffer);
+    }
+
+    fn submit_and_read_buffer(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        command_buffer: Arc<Mutex<CommandBuffer>>,
+        buffer: &Arc<Mutex<Buffer>>,
+    ) -> Vec<u8> {
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                command_buffers: vec![command_buffer],
+                ..Default::default()
+            }],
+            None,
+        );
+        let (descriptor, size) = {
+            let buffer = buffer.lock();
+            (buffer.descriptor(), buffer.size())
+        };
+        let logical_device = logical_device.lock();
+        let bytes = logical_device
+            .physical_device()
+            .gpu
+            .memory
+            .read_bytes(&descriptor.binding, descriptor.binding.offset, size)
+            .to_vec();
+        bytes
+    }
+
+    #[test]
+    fn update_buffer_writes_inline_data() {
+        let logical_device = create_logical_device();
+        let Ok(memory) = MemoryAllocation::create(logical_device.clone(), 32, 0) else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        let buffer = create_buffer(logical_device.clone(), 32, memory, 0);
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        let mut data = (1..=16).collect::<Vec<u8>>();
+        command_buffer_guard.cmd_update_buffer(buffer.clone(), 8, &data);
+        // Data is captured when recorded.
+        data.fill(0xff);
+        // Unaligned offset and range past the end of buffer.
+        command_buffer_guard.cmd_update_buffer(buffer.clone(), 2, &data[..4]);
+        command_buffer_guard.cmd_update_buffer(buffer.clone(), 24, &data);
+        assert_eq!(command_buffer_guard.commands().len(), 1);
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+
+        let bytes = submit_and_read_buffer(&logical_device, command_buffer, &buffer);
+        let mut expected = vec![0; 32];
+        expected[8..24].copy_from_slice(&(1..=16).collect::<Vec<u8>>());
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn fill_buffer_fills_range() {
+        let logical_device = create_logical_device();
+        let Ok(memory) = MemoryAllocation::create(logical_device.clone(), 64, 0) else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        let buffer = create_buffer(logical_device.clone(), 30, memory, 16);
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        command_buffer_guard.cmd_fill_buffer(buffer.clone(), 4, 8, 0x04030201);
+        // Whole size is rounded down to a multiple of 4 bytes.
+        command_buffer_guard.cmd_fill_buffer(buffer.clone(), 20, VK_WHOLE_SIZE, 0xaabbccdd);
+        // Unaligned size and range past the end of buffer.
+        command_buffer_guard.cmd_fill_buffer(buffer.clone(), 0, 6, 0xffffffff);
+        command_buffer_guard.cmd_fill_buffer(buffer.clone(), 24, 8, 0xffffffff);
+        assert_eq!(command_buffer_guard.commands().len(), 2);
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+
+        let bytes = submit_and_read_buffer(&logical_device, command_buffer, &buffer);
+        let mut expected = vec![0; 30];
+        expected[4..12].copy_from_slice(&[1, 2, 3, 4, 1, 2, 3, 4]);
+        expected[20..28].copy_from_slice(&[0xdd, 0xcc, 0xbb, 0xaa, 0xdd, 0xcc, 0xbb, 0xaa]);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn copy_buffer_to_image_region() {
+        let logical_device = create_logical_device();
+        let image_create_info = VkImageCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            imageType: VkImageType::VK_IMAGE_TYPE_2D,
+            format: VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            extent: VkExtent3D {
+                width: 4,
+                height: 4,
+                depth: 1,
+            },
+            mipLevels: 1,
+            arrayLayers: 1,
+            samples: VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+            tiling: VkImageTiling::VK_IMAGE_TILING_OPTIMAL,
+            usage: VkImageUsageFlagBits::VK_IMAGE_USAGE_TRANSFER_DST_BIT.into(),
+            sharingMode: VkSharingMode::VK_SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: None,
+            initialLayout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+        };
+        let Ok(image) = Image::create(logical_device.clone(), &image_create_info) else {
+            unreachable!()
+        };
+        let Some(image) = Image::from_handle(image) else {
+            unreachable!()
+        };
+        let image_size = image.lock().size_in_bytes();
+        let Ok(memory) = MemoryAllocation::create(logical_device.clone(), image_size + 24, 0)
+        else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        assert_eq!(
+            image.lock().bind_memory(memory.clone(), 0),
+            VkResult::VK_SUCCESS
+        );
+        // Rows of 2x2 texels are padded to 3 texels in buffer.
+        let buffer = create_buffer(logical_device.clone(), 24, memory, image_size);
+        let texels = [
+            [1, 2, 3, 4],
+            [5, 6, 7, 8],
+            [9, 10, 11, 12],
+            [13, 14, 15, 16],
+        ];
+        let buffer_bytes = [
+            texels[0], texels[1], [0xff; 4], texels[2], texels[3], [0xff; 4],
+        ];
+        let buffer_descriptor = buffer.lock().descriptor();
+        logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .write_bytes(
+                buffer_bytes.as_flattened(),
+                &buffer_descriptor.binding,
+                image_size,
+            );
+
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        command_buffer_guard.cmd_copy_buffer_to_image(
+            buffer,
+            image.clone(),
+            VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+            &[VkBufferImageCopy {
+                bufferOffset: 0,
+                bufferRowLength: 3,
+                bufferImageHeight: 0,
+                imageSubresource: VkImageSubresourceLayers {
+                    aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+                    mipLevel: 0,
+                    baseArrayLayer: 0,
+                    layerCount: 1,
+                },
+                imageOffset: VkOffset3D { x: 1, y: 2, z: 0 },
+                imageExtent: VkExtent3D {
+                    width: 2,
+                    height: 2,
+                    depth: 1,
+                },
+            }],
+        );
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                command_buffers: vec![command_buffer],
+                ..Default::default()
+            }],
+            None,
+        );
+
+        let image = image.lock();
+        let descriptor = image.descriptor();
+        let logical_device = logical_device.lock();
+        let physical_device = logical_device.physical_device();
+        let memory = &physical_device.gpu.memory;
+        for y in 0..4 {
+            for x in 0..4 {
+                let texel =
+                    memory.read_bytes(&descriptor.binding, image.texel_offset(x, y, 0, 0), 4);
+                let expected = match (x, y) {
+                    (1..=2, 2..=3) => texels[((y - 2) * 2 + x - 1) as usize],
+                    _ => [0; 4],
+                };
+                assert_eq!(texel, expected, "texel ({x}, {y})");
+            }
+        }
+        drop(physical_device);
+        drop(logical_device);
+        drop(image);
+    }
+
+    /// Creates 2D image bound to its own memory.
+    pub fn create_image(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        format: VkFormat,
+        (width, height): (u32, u32),
+        mip_levels: u32,
+        array_layers: u32,
+    ) -> Arc<Mutex<Image>> {
+        let image = create_unbound_image(
+            logical_device.clone(),
+            format,
+            (width, height),
+            mip_levels,
+            array_layers,
+        );
+        let size = image.lock().size_in_bytes();
+        let Ok(memory) = MemoryAllocation::create(logical_device, size, 0) else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        assert_eq!(image.lock().bind_memory(memory, 0), VkResult::VK_SUCCESS);
+        image
+    }
+
+    /// Creates image without memory bound to it.
+    pub fn create_unbound_image(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        format: VkFormat,
+        (width, height): (u32, u32),
+        mip_levels: u32,
+        array_layers: u32,
+    ) -> Arc<Mutex<Image>> {
+        let image_create_info = VkImageCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            imageType: VkImageType::VK_IMAGE_TYPE_2D,
+            format,
+            extent: VkExtent3D {
+                width,
+                height,
+                depth: 1,
+            },
+            mipLevels: mip_levels,
+            arrayLayers: array_layers,
+            samples: VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+            tiling: VkImageTiling::VK_IMAGE_TILING_OPTIMAL,
+            usage: (VkImageUsageFlagBits::VK_IMAGE_USAGE_TRANSFER_SRC_BIT
+                | VkImageUsageFlagBits::VK_IMAGE_USAGE_TRANSFER_DST_BIT)
+                .into(),
+            sharingMode: VkSharingMode::VK_SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: None,
+            initialLayout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+        };
+        let Ok(image) = Image::create(logical_device, &image_create_info) else {
+            unreachable!()
+        };
+        let Some(image) = Image::from_handle(image) else {
+            unreachable!()
+        };
+        image
+    }
+
+    /// Creates R8G8B8A8 image bound to its own memory and fills it with given texels.
+    pub fn create_image_with_texels(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        width: u32,
+        height: u32,
+        texels: &[[u8; 4]],
+    ) -> Arc<Mutex<Image>> {
+        let image = create_image(
+            logical_device.clone(),
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            (width, height),
+            1,
+            1,
+        );
+        let descriptor = image.lock().descriptor();
+        logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .write_bytes(texels.as_flattened(), &descriptor.binding, 0);
+        image
+    }
+
+    fn read_image_texels(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        image: &Arc<Mutex<Image>>,
+    ) -> Vec<[u8; 4]> {
+        let image = image.lock();
+        let descriptor = image.descriptor();
+        let logical_device = logical_device.lock();
+        let texels = logical_device
+            .physical_device()
+            .gpu
+            .memory
+            .read_bytes(&descriptor.binding, 0, image.size_in_bytes())
+            .chunks_exact(4)
+            .map(|texel| [texel[0], texel[1], texel[2], texel[3]])
+            .collect();
+        drop(logical_device);
+        drop(image);
+        texels
+    }
+
+    pub fn blit_image(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        src_image: Arc<Mutex<Image>>,
+        dst_image: Arc<Mutex<Image>>,
+        region: VkImageBlit,
+        filter: VkFilter,
+    ) {
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        command_buffer_guard.cmd_blit_image(
+            src_image,
+            VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+            dst_image,
+            VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+            &[region],
+            filter,
+        );
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                command_buffers: vec![command_buffer],
+                ..Default::default()
+            }],
+            None,
+        );
+    }
+
+    pub fn blit_region(src_offsets: [(i32, i32); 2], dst_offsets: [(i32, i32); 2]) -> VkImageBlit {
+        let subresource = VkImageSubresourceLayers {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            mipLevel: 0,
+            baseArrayLayer: 0,
+            layerCount: 1,
+        };
+        let offsets = |offsets: [(i32, i32); 2]| {
+            [(offsets[0], 0), (offsets[1], 1)].map(|((x, y), z)| VkOffset3D { x, y, z })
+        };
+        VkImageBlit {
+            srcSubresource: subresource,
+            srcOffsets: offsets(src_offsets),
+            dstSubresource: subresource,
+            dstOffsets: offsets(dst_offsets),
+        }
+    }
+
+    #[test]
+    fn blit_image_linear_upscale() {
+        let logical_device = create_logical_device();
+        // Red increases to the right and green increases downwards.
+        let src_image = create_image_with_texels(
+            logical_device.clone(),
+            2,
+            2,
+            &[
+                [0, 0, 0, 255],
+                [255, 0, 0, 255],
+                [0, 255, 0, 255],
+                [255, 255, 0, 255],
+            ],
+        );
+        let dst_image = create_image_with_texels(logical_device.clone(), 4, 4, &[[0; 4]; 16]);
+        blit_image(
+            &logical_device,
+            src_image,
+            dst_image.clone(),
+            blit_region([(0, 0), (2, 2)], [(0, 0), (4, 4)]),
+            VkFilter::VK_FILTER_LINEAR,
+        );
+
+        // Texel centers of destination map to 1/4 and 3/4 between source texel centers, and
+        // texels outside of source texel centers are clamped to edge.
+        let ramp = [0, 64, 191, 255];
+        let expected = (0..4)
+            .flat_map(|y| (0..4).map(move |x| [ramp[x], ramp[y], 0, 255]))
+            .collect::<Vec<_>>();
+        assert_eq!(read_image_texels(&logical_device, &dst_image), expected);
+    }
+
+    #[test]
+    fn blit_image_nearest_mirrored() {
+        let logical_device = create_logical_device();
+        let texels = [[1, 0, 0, 0], [2, 0, 0, 0], [3, 0, 0, 0], [4, 0, 0, 0]];
+        let src_image = create_image_with_texels(logical_device.clone(), 2, 2, &texels);
+        let dst_image = create_image_with_texels(logical_device.clone(), 2, 2, &[[0; 4]; 4]);
+        blit_image(
+            &logical_device,
+            src_image,
+            dst_image.clone(),
+            blit_region([(0, 0), (2, 2)], [(2, 0), (0, 2)]),
+            VkFilter::VK_FILTER_NEAREST,
+        );
+        assert_eq!(
+            read_image_texels(&logical_device, &dst_image),
+            [texels[1], texels[0], texels[3], texels[2]]
+        );
+    }
+
+    #[test]
+    fn blit_image_decodes_bc1_blocks() {
+        let logical_device = create_logical_device();
+        let src_image = create_image(
+            logical_device.clone(),
+            VkFormat::VK_FORMAT_BC1_RGB_UNORM_BLOCK,
+            (4, 4),
+            1,
+            1,
+        );
+        // Red and blue endpoints, with first row of texels indexing red and others blue.
+        let block = [0x00, 0xf8, 0x1f, 0x00, 0x00, 0x55, 0x55, 0x55];
+        let descriptor = src_image.lock().descriptor();
+        logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .write_bytes(&block, &descriptor.binding, 0);
+        let dst_image = create_image_with_texels(logical_device.clone(), 4, 4, &[[0; 4]; 16]);
+        blit_image(
+            &logical_device,
+            src_image,
+            dst_image.clone(),
+            blit_region([(0, 0), (4, 4)], [(0, 0), (4, 4)]),
+            VkFilter::VK_FILTER_NEAREST,
+        );
+
+        let expected = (0..16)
+            .map(|i| {
+                if i < 4 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 255]
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(read_image_texels(&logical_device, &dst_image), expected);
+    }
+
+    pub fn record_and_submit(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        record: impl FnOnce(&mut CommandBuffer),
+    ) {
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        record(&mut command_buffer_guard);
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                command_buffers: vec![command_buffer],
+                ..Default::default()
+            }],
+            None,
+        );
+    }
+
+    fn read_texel_bytes(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        image: &Image,
+        (x, y): (u32, u32),
+        mip_level: u32,
+        array_layer: u32,
+    ) -> Vec<u8> {
+        let descriptor = image.descriptor();
+        let offset = image.texel_offset(x, y, mip_level, array_layer);
+        let size = image.format.bytes_per_pixel() as u64;
+        let logical_device = logical_device.lock();
+        let bytes = logical_device
+            .physical_device()
+            .gpu
+            .memory
+            .read_bytes(&descriptor.binding, offset, size)
+            .to_vec();
+        drop(logical_device);
+        bytes
+    }
+
+    #[test]
+    fn clear_color_image_mip_levels() {
+        let logical_device = create_logical_device();
+        let image = create_image(
+            logical_device.clone(),
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            (4, 4),
+            3,
+            2,
+        );
+        let color = VkClearColorValue {
+            float32: std::mem::ManuallyDrop::new([0.2, 0.4, 0.6, 1.0]),
+        };
+        let range = VkImageSubresourceRange {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            baseMipLevel: 0,
+            levelCount: VK_REMAINING_MIP_LEVELS,
+            baseArrayLayer: 1,
+            layerCount: 1,
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_clear_color_image(
+                image.clone(),
+                VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                color,
+                &[range],
+            );
+        });
+
+        let image = image.lock();
+        for (mip_level, size) in [(0, 4), (1, 2), (2, 1)] {
+            for texel in [(0, 0), (size - 1, size - 1)] {
+                assert_eq!(
+                    read_texel_bytes(&logical_device, &image, texel, mip_level, 1),
+                    [51, 102, 153, 255]
+                );
+                // Array layer outside of range is left untouched.
+                assert_eq!(
+                    read_texel_bytes(&logical_device, &image, texel, mip_level, 0),
+                    [0; 4]
+                );
+            }
+        }
+        drop(image);
+    }
+
+    #[test]
+    fn clear_depth_stencil_image() {
+        let logical_device = create_logical_device();
+        let image = create_image(
+            logical_device.clone(),
+            VkFormat::VK_FORMAT_D16_UNORM,
+            (4, 4),
+            1,
+            1,
+        );
+        let range = VkImageSubresourceRange {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_DEPTH_BIT.into(),
+            baseMipLevel: 0,
+            levelCount: 1,
+            baseArrayLayer: 0,
+            layerCount: VK_REMAINING_ARRAY_LAYERS,
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_clear_depth_stencil_image(
+                image.clone(),
+                VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                VkClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+                &[range],
+            );
+        });
+
+        let image = image.lock();
+        for texel in [(0, 0), (1, 2), (3, 3)] {
+            assert_eq!(
+                read_texel_bytes(&logical_device, &image, texel, 0, 0),
+                [0xff, 0xff]
+            );
+        }
+        drop(image);
+    }
+
+    #[test]
+    fn pipeline_barrier_transitions_image_layout() {
+        let logical_device = create_logical_device();
+        let image = create_image(
+            logical_device.clone(),
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            (4, 4),
+            2,
+            1,
+        );
+        let barrier = VkImageMemoryBarrier {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_MEMORY_BARRIER,
+            pNext: None,
+            srcAccessMask: 0,
+            dstAccessMask: VkAccessFlagBits::VK_ACCESS_COLOR_ATTACHMENT_WRITE_BIT.into(),
+            oldLayout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+            newLayout: VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+            srcQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+            dstQueueFamilyIndex: VK_QUEUE_FAMILY_IGNORED,
+            image: image.lock().handle,
+            subresourceRange: VkImageSubresourceRange {
+                aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+                baseMipLevel: 0,
+                levelCount: 1,
+                baseArrayLayer: 0,
+                layerCount: 1,
+            },
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_pipeline_barrier(&[barrier]);
+        });
+
+        let image = image.lock();
+        assert_eq!(
+            image.current_layout(0, 0),
+            VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL
+        );
+        // Mip level outside of barrier range keeps its layout.
+        assert_eq!(
+            image.current_layout(1, 0),
+            VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED
+        );
+        drop(image);
+    }
+
+    // #version 450
+    // layout(location = 0) in vec4 position;
+    // void main() { gl_Position = position; }
+    pub const VERTEX_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000015, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0007000f, 0x00000000, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000003, 0x00000004, 0x00050048, 0x0000000c, 0x00000000, 0x0000000b,
+        0x00000000, 0x00050048, 0x0000000c, 0x00000001, 0x0000000b, 0x00000001, 0x00050048,
+        0x0000000c, 0x00000002, 0x0000000b, 0x00000003, 0x00050048, 0x0000000c, 0x00000003,
+        0x0000000b, 0x00000004, 0x00030047, 0x0000000c, 0x00000002, 0x00040047, 0x00000004,
+        0x0000001e, 0x00000000, 0x00020013, 0x00000005, 0x00030021, 0x00000006, 0x00000005,
+        0x00030016, 0x00000007, 0x00000020, 0x00040017, 0x00000008, 0x00000007, 0x00000004,
+        0x00040015, 0x00000009, 0x00000020, 0x00000000, 0x0004002b, 0x00000009, 0x0000000a,
+        0x00000001, 0x0004001c, 0x0000000b, 0x00000007, 0x0000000a, 0x0006001e, 0x0000000c,
+        0x00000008, 0x00000007, 0x0000000b, 0x0000000b, 0x00040020, 0x0000000d, 0x00000003,
+        0x0000000c, 0x0004003b, 0x0000000d, 0x00000003, 0x00000003, 0x00040015, 0x0000000e,
+        0x00000020, 0x00000001, 0x0004002b, 0x0000000e, 0x0000000f, 0x00000000, 0x00040020,
+        0x00000010, 0x00000001, 0x00000008, 0x0004003b, 0x00000010, 0x00000004, 0x00000001,
+        0x00040020, 0x00000011, 0x00000003, 0x00000008, 0x00050036, 0x00000005, 0x00000001,
+        0x00000000, 0x00000006, 0x000200f8, 0x00000012, 0x0004003d, 0x00000008, 0x00000013,
+        0x00000004, 0x00050041, 0x00000011, 0x00000014, 0x00000003, 0x0000000f, 0x0003003e,
+        0x00000014, 0x00000013, 0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(location = 0) out vec4 color;
+    // void main() { color = vec4(1.0, 0.0, 0.0, 1.0); }
+    const FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x0000000e, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0006000f, 0x00000004, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00030010, 0x00000001, 0x00000007, 0x00040047, 0x00000002,
+        0x0000001e, 0x00000000, 0x00020013, 0x00000003, 0x00030021, 0x00000004, 0x00000003,
+        0x00030016, 0x00000005, 0x00000020, 0x00040017, 0x00000006, 0x00000005, 0x00000004,
+        0x00040020, 0x00000007, 0x00000003, 0x00000006, 0x0004003b, 0x00000007, 0x00000002,
+        0x00000003, 0x0004002b, 0x00000005, 0x00000008, 0x3f800000, 0x0004002b, 0x00000005,
+        0x00000009, 0x00000000, 0x0004002b, 0x00000005, 0x0000000a, 0x00000000, 0x0004002b,
+        0x00000005, 0x0000000b, 0x3f800000, 0x0007002c, 0x00000006, 0x0000000c, 0x00000008,
+        0x00000009, 0x0000000a, 0x0000000b, 0x00050036, 0x00000003, 0x00000001, 0x00000000,
+        0x00000004, 0x000200f8, 0x0000000d, 0x0003003e, 0x00000002, 0x0000000c, 0x000100fd,
+        0x00010038,
+    ];
+
+    // #version 450
+    // layout(input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput color;
+    // layout(location = 0) out vec4 out_color;
+    // void main() { out_color = subpassLoad(color) * 0.5; }
+    const TINT_INPUT_ATTACHMENT_FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000014, 0x00000000, 0x00020011, 0x00000001,
+        0x00020011, 0x00000028, 0x0003000e, 0x00000000, 0x00000001, 0x0006000f, 0x00000004,
+        0x00000001, 0x6e69616d, 0x00000000, 0x00000002, 0x00030010, 0x00000001, 0x00000007,
+        0x00040047, 0x00000002, 0x0000001e, 0x00000000, 0x00040047, 0x00000003, 0x00000022,
+        0x00000000, 0x00040047, 0x00000003, 0x00000021, 0x00000000, 0x00040047, 0x00000003,
+        0x0000002b, 0x00000000, 0x00020013, 0x00000004, 0x00030021, 0x00000005, 0x00000004,
+        0x00030016, 0x00000006, 0x00000020, 0x00040017, 0x00000007, 0x00000006, 0x00000004,
+        0x00040020, 0x00000008, 0x00000003, 0x00000007, 0x0004003b, 0x00000008, 0x00000002,
+        0x00000003, 0x00090019, 0x00000009, 0x00000006, 0x00000006, 0x00000000, 0x00000000,
+        0x00000000, 0x00000002, 0x00000000, 0x00040020, 0x0000000a, 0x00000000, 0x00000009,
+        0x0004003b, 0x0000000a, 0x00000003, 0x00000000, 0x00040015, 0x0000000b, 0x00000020,
+        0x00000001, 0x00040017, 0x0000000c, 0x0000000b, 0x00000002, 0x0004002b, 0x0000000b,
+        0x0000000d, 0x00000000, 0x0005002c, 0x0000000c, 0x0000000e, 0x0000000d, 0x0000000d,
+        0x0004002b, 0x00000006, 0x0000000f, 0x3f000000, 0x00050036, 0x00000004, 0x00000001,
+        0x00000000, 0x00000005, 0x000200f8, 0x00000010, 0x0004003d, 0x00000009, 0x00000011,
+        0x00000003, 0x00050062, 0x00000007, 0x00000012, 0x00000011, 0x0000000e, 0x0005008e,
+        0x00000007, 0x00000013, 0x00000012, 0x0000000f, 0x0003003e, 0x00000002, 0x00000013,
+        0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(input_attachment_index = 0, set = 0, binding = 0) uniform usubpassInput value;
+    // layout(location = 0) out vec4 out_color;
+    // void main() { out_color = vec4(subpassLoad(value)) / 255.0; }
+    const UINT_INPUT_ATTACHMENT_FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000017, 0x00000000, 0x00020011, 0x00000001,
+        0x00020011, 0x00000028, 0x0003000e, 0x00000000, 0x00000001, 0x0006000f, 0x00000004,
+        0x00000001, 0x6e69616d, 0x00000000, 0x00000002, 0x00030010, 0x00000001, 0x00000007,
+        0x00040047, 0x00000002, 0x0000001e, 0x00000000, 0x00040047, 0x00000003, 0x00000022,
+        0x00000000, 0x00040047, 0x00000003, 0x00000021, 0x00000000, 0x00040047, 0x00000003,
+        0x0000002b, 0x00000000, 0x00020013, 0x00000004, 0x00030021, 0x00000005, 0x00000004,
+        0x00030016, 0x00000006, 0x00000020, 0x00040017, 0x00000007, 0x00000006, 0x00000004,
+        0x00040020, 0x00000008, 0x00000003, 0x00000007, 0x0004003b, 0x00000008, 0x00000002,
+        0x00000003, 0x00040015, 0x00000014, 0x00000020, 0x00000000, 0x00040017, 0x00000015,
+        0x00000014, 0x00000004, 0x00090019, 0x00000009, 0x00000014, 0x00000006, 0x00000000,
+        0x00000000, 0x00000000, 0x00000002, 0x00000000, 0x00040020, 0x0000000a, 0x00000000,
+        0x00000009, 0x0004003b, 0x0000000a, 0x00000003, 0x00000000, 0x00040015, 0x0000000b,
+        0x00000020, 0x00000001, 0x00040017, 0x0000000c, 0x0000000b, 0x00000002, 0x0004002b,
+        0x0000000b, 0x0000000d, 0x00000000, 0x0005002c, 0x0000000c, 0x0000000e, 0x0000000d,
+        0x0000000d, 0x0004002b, 0x00000006, 0x0000000f, 0x3b808081, 0x00050036, 0x00000004,
+        0x00000001, 0x00000000, 0x00000005, 0x000200f8, 0x00000010, 0x0004003d, 0x00000009,
+        0x00000011, 0x00000003, 0x00050062, 0x00000015, 0x00000012, 0x00000011, 0x0000000e,
+        0x00040070, 0x00000007, 0x00000016, 0x00000012, 0x0005008e, 0x00000007, 0x00000013,
+        0x00000016, 0x0000000f, 0x0003003e, 0x00000002, 0x00000013, 0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(set = 0, binding = 0) uniform Ubo { vec4 color; } ubo;
+    // layout(location = 0) out vec4 color;
+    // void main() { color = ubo.color; }
+    const UNIFORM_COLOR_FRAGMENT_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000011, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0006000f, 0x00000004, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00030010, 0x00000001, 0x00000007, 0x00040047, 0x00000002,
+        0x0000001e, 0x00000000, 0x00050048, 0x00000008, 0x00000000, 0x00000023, 0x00000000,
+        0x00030047, 0x00000008, 0x00000002, 0x00040047, 0x0000000a, 0x00000022, 0x00000000,
+        0x00040047, 0x0000000a, 0x00000021, 0x00000000, 0x00020013, 0x00000003, 0x00030021,
+        0x00000004, 0x00000003, 0x00030016, 0x00000005, 0x00000020, 0x00040017, 0x00000006,
+        0x00000005, 0x00000004, 0x00040020, 0x00000007, 0x00000003, 0x00000006, 0x0004003b,
+        0x00000007, 0x00000002, 0x00000003, 0x0003001e, 0x00000008, 0x00000006, 0x00040020,
+        0x00000009, 0x00000002, 0x00000008, 0x0004003b, 0x00000009, 0x0000000a, 0x00000002,
+        0x00040015, 0x0000000b, 0x00000020, 0x00000001, 0x0004002b, 0x0000000b, 0x0000000c,
+        0x00000000, 0x00040020, 0x0000000d, 0x00000002, 0x00000006, 0x00050036, 0x00000003,
+        0x00000001, 0x00000000, 0x00000004, 0x000200f8, 0x0000000e, 0x00050041, 0x0000000d,
+        0x0000000f, 0x0000000a, 0x0000000c, 0x0004003d, 0x00000006, 0x00000010, 0x0000000f,
+        0x0003003e, 0x00000002, 0x00000010, 0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(location = 0) in vec4 position;
+    // layout(push_constant) uniform PushConstants { vec4 offset; } pc;
+    // void main() { gl_Position = position - pc.offset; }
+    const PUSH_CONSTANT_OFFSET_VERTEX_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x0000001c, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0007000f, 0x00000000, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000003, 0x00000004, 0x00050048, 0x0000000c, 0x00000000, 0x0000000b,
+        0x00000000, 0x00050048, 0x0000000c, 0x00000001, 0x0000000b, 0x00000001, 0x00050048,
+        0x0000000c, 0x00000002, 0x0000000b, 0x00000003, 0x00050048, 0x0000000c, 0x00000003,
+        0x0000000b, 0x00000004, 0x00030047, 0x0000000c, 0x00000002, 0x00040047, 0x00000004,
+        0x0000001e, 0x00000000, 0x00050048, 0x00000012, 0x00000000, 0x00000023, 0x00000000,
+        0x00030047, 0x00000012, 0x00000002, 0x00020013, 0x00000005, 0x00030021, 0x00000006,
+        0x00000005, 0x00030016, 0x00000007, 0x00000020, 0x00040017, 0x00000008, 0x00000007,
+        0x00000004, 0x00040015, 0x00000009, 0x00000020, 0x00000000, 0x0004002b, 0x00000009,
+        0x0000000a, 0x00000001, 0x0004001c, 0x0000000b, 0x00000007, 0x0000000a, 0x0006001e,
+        0x0000000c, 0x00000008, 0x00000007, 0x0000000b, 0x0000000b, 0x00040020, 0x0000000d,
+        0x00000003, 0x0000000c, 0x0004003b, 0x0000000d, 0x00000003, 0x00000003, 0x00040015,
+        0x0000000e, 0x00000020, 0x00000001, 0x0004002b, 0x0000000e, 0x0000000f, 0x00000000,
+        0x00040020, 0x00000010, 0x00000001, 0x00000008, 0x0004003b, 0x00000010, 0x00000004,
+        0x00000001, 0x0003001e, 0x00000012, 0x00000008, 0x00040020, 0x00000013, 0x00000009,
+        0x00000012, 0x0004003b, 0x00000013, 0x00000014, 0x00000009, 0x00040020, 0x00000015,
+        0x00000009, 0x00000008, 0x00040020, 0x00000011, 0x00000003, 0x00000008, 0x00050036,
+        0x00000005, 0x00000001, 0x00000000, 0x00000006, 0x000200f8, 0x00000016, 0x0004003d,
+        0x00000008, 0x00000017, 0x00000004, 0x00050041, 0x00000015, 0x00000018, 0x00000014,
+        0x0000000f, 0x0004003d, 0x00000008, 0x00000019, 0x00000018, 0x00050083, 0x00000008,
+        0x0000001a, 0x00000017, 0x00000019, 0x00050041, 0x00000011, 0x0000001b, 0x00000003,
+        0x0000000f, 0x0003003e, 0x0000001b, 0x0000001a, 0x000100fd, 0x00010038,
+    ];
+
+    // #version 450
+    // layout(location = 0) in vec4 position;
+    // void main() {
+    //     gl_Position = position - vec4(-2.0 / 3.0, 0.0, 0.0, 0.0) * float(gl_InstanceIndex);
+    // }
+    const INSTANCE_OFFSET_VERTEX_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x0000001e, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0008000f, 0x00000000, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000003, 0x00000004, 0x00000013, 0x00050048, 0x0000000c, 0x00000000,
+        0x0000000b, 0x00000000, 0x00050048, 0x0000000c, 0x00000001, 0x0000000b, 0x00000001,
+        0x00050048, 0x0000000c, 0x00000002, 0x0000000b, 0x00000003, 0x00050048, 0x0000000c,
+        0x00000003, 0x0000000b, 0x00000004, 0x00030047, 0x0000000c, 0x00000002, 0x00040047,
+        0x00000004, 0x0000001e, 0x00000000, 0x00040047, 0x00000013, 0x0000000b, 0x0000002b,
+        0x00020013, 0x00000005, 0x00030021, 0x00000006, 0x00000005, 0x00030016, 0x00000007,
+        0x00000020, 0x00040017, 0x00000008, 0x00000007, 0x00000004, 0x00040015, 0x00000009,
+        0x00000020, 0x00000000, 0x0004002b, 0x00000009, 0x0000000a, 0x00000001, 0x0004001c,
+        0x0000000b, 0x00000007, 0x0000000a, 0x0006001e, 0x0000000c, 0x00000008, 0x00000007,
+        0x0000000b, 0x0000000b, 0x00040020, 0x0000000d, 0x00000003, 0x0000000c, 0x0004003b,
+        0x0000000d, 0x00000003, 0x00000003, 0x00040015, 0x0000000e, 0x00000020, 0x00000001,
+        0x0004002b, 0x0000000e, 0x0000000f, 0x00000000, 0x00040020, 0x00000010, 0x00000001,
+        0x00000008, 0x0004003b, 0x00000010, 0x00000004, 0x00000001, 0x00040020, 0x00000012,
+        0x00000001, 0x0000000e, 0x0004003b, 0x00000012, 0x00000013, 0x00000001, 0x0004002b,
+        0x00000007, 0x00000014, 0xbf2aaaab, 0x0004002b, 0x00000007, 0x00000015, 0x00000000,
+        0x0007002c, 0x00000008, 0x00000016, 0x00000014, 0x00000015, 0x00000015, 0x00000015,
+        0x00040020, 0x00000011, 0x00000003, 0x00000008, 0x00050036, 0x00000005, 0x00000001,
+        0x00000000, 0x00000006, 0x000200f8, 0x00000017, 0x0004003d, 0x00000008, 0x00000018,
+        0x00000004, 0x0004003d, 0x0000000e, 0x00000019, 0x00000013, 0x0004006f, 0x00000007,
+        0x0000001a, 0x00000019, 0x0005008e, 0x00000008, 0x0000001b, 0x00000016, 0x0000001a,
+        0x00050083, 0x00000008, 0x0000001c, 0x00000018, 0x0000001b, 0x00050041, 0x00000011,
+        0x0000001d, 0x00000003, 0x0000000f, 0x0003003e, 0x0000001d, 0x0000001c, 0x000100fd,
+        0x00010038,
+    ];
+
+    /// Creates pipeline drawing triangles with `vec4` positions from vertex binding 0.
+    fn create_triangle_pipeline(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        vertex_shader: &[u32],
+        fragment_shader: &[u32],
+        (width, height): (u32, u32),
+    ) -> Arc<Mutex<Pipeline>> {
+        let shader_state = ShaderState {
+            vertex_shader: Shader::new("main", vertex_shader.to_vec()).ok(),
+            fragment_shader: Shader::new("main", fragment_shader.to_vec()).ok(),
+        };
+        let mut vertex_input_state = VertexInputState::default();
+        vertex_input_state.bindings[0] = Some(VertexBinding {
+            number: VertexBindingNumber(0),
+            stride: 16,
+            input_rate: VertexInputRate::Vertex,
+        });
+        vertex_input_state.attributes[0] = Some(VertexAttribute {
+            location: 0,
+            binding: VertexBindingNumber(0),
+            format: Format::R32G32B32A32Sfloat,
+            offset: 0,
+        });
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(PhysicalDevice::parse_viewport(&VkViewport {
+            x: 0.0,
+            y: 0.0,
+            width: width as f32,
+            height: height as f32,
+            minDepth: 0.0,
+            maxDepth: 1.0,
+        }));
+        let Ok(pipeline) = Pipeline::create(
+            logical_device,
+            None,
+            shader_state,
+            Some(vertex_input_state),
+            Some(InputAssemblyState {
+                topology: PrimitiveTopology::TriangleList,
+                primitive_restart: false,
+            }),
+            Some(viewport_state),
+            None,
+            None,
+            None,
+            None,
+        ) else {
+            unreachable!()
+        };
+        let Some(pipeline) = Pipeline::from_handle(pipeline) else {
+            unreachable!()
+        };
+        pipeline
+    }
+
+    /// Creates buffer bound to new memory holding `bytes`.
+    pub fn create_buffer_with_bytes(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        bytes: &[u8],
+    ) -> Arc<Mutex<Buffer>> {
+        let Ok(memory) = MemoryAllocation::create(logical_device.clone(), bytes.len() as u64, 0)
+        else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(memory) else {
+            unreachable!()
+        };
+        let buffer = create_buffer(logical_device.clone(), bytes.len() as u64, memory, 0);
+        let descriptor = buffer.lock().descriptor();
+        logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .write_bytes(bytes, &descriptor.binding, 0);
+        buffer
+    }
+
+    /// Creates vertex buffer holding triangle covering the upper left corner of framebuffer.
+    fn create_triangle_vertex_buffer(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+    ) -> Arc<Mutex<Buffer>> {
+        let positions = [
+            [-1.0f32, -1.0, 0.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+        ];
+        let bytes = positions
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        create_buffer_with_bytes(logical_device, &bytes)
+    }
+
+    #[test]
+    fn queue_submit_clear_and_draw_signals_fence() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) =
+            create_framebuffer(logical_device.clone(), (extent.width, extent.height));
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+            }),
+        };
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        command_buffer_guard.cmd_begin_render_pass(
+            render_pass,
+            framebuffer,
+            VkRect2D {
+                offset: VkOffset2D { x: 0, y: 0 },
+                extent,
+            },
+            &[clear_value],
+            VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+        );
+        command_buffer_guard.cmd_bind_pipeline(
+            VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+            pipeline,
+        );
+        command_buffer_guard.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+        command_buffer_guard.cmd_draw(3, 1, 0, 0);
+        command_buffer_guard.cmd_end_render_pass();
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+
+        let fence_create_info = VkFenceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let Ok(fence) = Fence::create(logical_device.clone(), &fence_create_info) else {
+            unreachable!()
+        };
+        let Some(fence) = Fence::from_handle(fence) else {
+            unreachable!()
+        };
+        assert!(!fence.lock().is_signaled());
+
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                command_buffers: vec![command_buffer.clone()],
+                ..Default::default()
+            }],
+            Some(fence.clone()),
+        );
+        assert!(fence.lock().is_signaled());
+        assert_eq!(
+            command_buffer.lock().state(),
+            CommandBufferState::Executable
+        );
+
+        let image = image_view.lock().image.clone();
+        let image = image.lock();
+        // Texel covered by triangle is drawn over cleared texel on the opposite corner.
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (0, 0), 0, 0),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (3, 3), 0, 0),
+            [0, 255, 0, 255]
+        );
+        drop(image);
+    }
+
+    /// Span recorded by [`SpanRecorder`], with index of span it was created in.
+    #[derive(Debug)]
+    struct RecordedSpan {
+        name: &'static str,
+        parent: Option<usize>,
+        fields: Vec<(&'static str, String)>,
+    }
+
+    /// Subscriber recording spans and their nesting, events are ignored.
+    #[derive(Debug, Default)]
+    struct SpanRecorder {
+        spans: Mutex<Vec<RecordedSpan>>,
+        entered: Mutex<Vec<usize>>,
+    }
+
+    impl tracing::field::Visit for RecordedSpan {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.fields.push((field.name(), format!("{:?}", value)));
+        }
+    }
+
+    impl tracing::Subscriber for SpanRecorder {
+        fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut recorded = RecordedSpan {
+                name: span.metadata().name(),
+                parent: self.entered.lock().last().copied(),
+                fields: vec![],
+            };
+            span.record(&mut recorded);
+            let mut spans = self.spans.lock();
+            spans.push(recorded);
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+        fn event(&self, _event: &tracing::Event<'_>) {}
+
+        fn enter(&self, span: &tracing::span::Id) {
+            self.entered.lock().push(span.into_u64() as usize - 1);
+        }
+
+        fn exit(&self, _span: &tracing::span::Id) {
+            self.entered.lock().pop();
+        }
+    }
+
+    #[test]
+    fn queue_submit_spans_nest_command_buffers_and_draws() {
+        let logical_device = create_logical_device();
+        let recorder = Arc::new(SpanRecorder::default());
+        tracing::subscriber::with_default(recorder.clone(), || {
+            render_triangle(&logical_device);
+        });
+
+        let spans = recorder.spans.lock();
+        let tree = spans
+            .iter()
+            .map(|span| (span.name, span.parent.map(|parent| spans[parent].name)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tree,
+            [
+                ("queue_submit", None),
+                ("command_buffer", Some("queue_submit")),
+                ("draw", Some("command_buffer")),
+            ]
+        );
+        assert_eq!(spans[0].fields, [("batches", "1".to_string())]);
+        assert_eq!(spans[1].fields[0].0, "handle");
+        assert_eq!(
+            spans[2].fields,
+            [
+                ("vertex_count", "3".to_string()),
+                ("instance_count", "1".to_string())
+            ]
+        );
+        drop(spans);
+    }
+
+    /// Draws triangle over upper left half of cleared 4x4 framebuffer, returning its image.
+    fn render_triangle(logical_device: &Arc<Mutex<LogicalDevice>>) -> Arc<Mutex<Image>> {
+        let image_view = create_color_attachment(
+            logical_device.clone(),
+            (4, 4),
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+        );
+        render_triangle_into(logical_device, image_view.clone());
+        let image = image_view.lock().image.clone();
+        image
+    }
+
+    /// Draws red triangle over upper left half of R8G8B8A8 4x4 `image_view`, clearing the rest
+    /// to green.
+    pub fn render_triangle_into(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        image_view: Arc<Mutex<ImageView>>,
+    ) {
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) = create_framebuffer_of_attachment(
+            logical_device.clone(),
+            image_view,
+            (extent.width, extent.height),
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+        );
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+            }),
+        };
+        record_and_submit(logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_end_render_pass();
+        });
+    }
+
+    #[test]
+    fn execute_commands_of_secondary_inside_render_pass() {
+        let logical_device = create_logical_device();
+        let subresource = VkImageSubresource {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            mipLevel: 0,
+            arrayLayer: 0,
+        };
+        let mut expected = vec![0u8; 4 * 4 * 4];
+        let image = render_triangle(&logical_device);
+        assert_eq!(
+            image.lock().read_to_host(&subresource, &mut expected),
+            Ok(())
+        );
+
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) =
+            create_framebuffer(logical_device.clone(), (extent.width, extent.height));
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+            }),
+        };
+
+        let secondary = create_command_buffer_of_level(
+            logical_device.clone(),
+            VkCommandBufferLevel::VK_COMMAND_BUFFER_LEVEL_SECONDARY,
+        );
+        let mut secondary_guard = secondary.lock();
+        assert_eq!(
+            secondary_guard.begin_with_inheritance(Some(CommandBufferInheritance {
+                render_pass: render_pass.clone(),
+                subpass: 0,
+            })),
+            Ok(())
+        );
+        secondary_guard.cmd_bind_pipeline(
+            VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+            pipeline,
+        );
+        secondary_guard.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+        secondary_guard.cmd_draw(3, 1, 0, 0);
+        assert_eq!(secondary_guard.end(), Ok(()));
+        drop(secondary_guard);
+
+        // Secondary not continuing render pass cannot be executed inside it.
+        let unrelated = create_command_buffer_of_level(
+            logical_device.clone(),
+            VkCommandBufferLevel::VK_COMMAND_BUFFER_LEVEL_SECONDARY,
+        );
+        assert_eq!(unrelated.lock().begin(), Ok(()));
+        assert_eq!(unrelated.lock().end(), Ok(()));
+
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_SECONDARY_COMMAND_BUFFERS,
+            );
+            let command_count = command_buffer.commands().len();
+            command_buffer.cmd_execute_commands([unrelated]);
+            assert_eq!(command_buffer.commands().len(), command_count);
+            command_buffer.cmd_execute_commands([secondary]);
+            assert_eq!(command_buffer.commands().len(), command_count + 1);
+            command_buffer.cmd_end_render_pass();
+        });
+
+        let image = image_view.lock().image.clone();
+        let mut bytes = vec![0u8; 4 * 4 * 4];
+        assert_eq!(image.lock().read_to_host(&subresource, &mut bytes), Ok(()));
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn subpass_reads_input_attachment_written_by_previous_subpass() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let samples = VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT;
+        let image_views = [0, 1].map(|_| {
+            create_color_attachment(
+                logical_device.clone(),
+                (extent.width, extent.height),
+                samples,
+            )
+        });
+        let attachment = AttachmentDescription {
+            flags: 0.into(),
+            format: VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            samples,
+            load_op: VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR,
+            store_op: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE,
+            stencil_load_pp: VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE,
+            stencil_store_op: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE,
+            initial_layout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+            final_layout: VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+        };
+        let reference = |attachment, layout| VkAttachmentReference { attachment, layout };
+        let subpass = |input_attachments: &[_], color_attachment| SubpassDescription {
+            flags: 0.into(),
+            pipeline_bind_point: VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+            input_attachments: input_attachments.into(),
+            color_attachments: Arc::new([reference(
+                color_attachment,
+                VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+            )]),
+            resolve_attachments: Arc::new([]),
+            depth_stencil_attachment: None,
+            preserve_attachments: Arc::new([]),
+        };
+        // Second subpass reads attachment written by the first one.
+        let subpasses = [
+            subpass(&[], 0),
+            subpass(
+                &[reference(
+                    0,
+                    VkImageLayout::VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL,
+                )],
+                1,
+            ),
+        ];
+        let Ok(render_pass) = RenderPass::create(
+            logical_device.clone(),
+            &[attachment.clone(), attachment],
+            &[],
+            &subpasses,
+        ) else {
+            unreachable!()
+        };
+        let Some(render_pass) = RenderPass::from_handle(render_pass) else {
+            unreachable!()
+        };
+        let Ok(framebuffer) = Framebuffer::create(
+            logical_device.clone(),
+            0,
+            extent.width,
+            extent.height,
+            1,
+            image_views.to_vec(),
+            render_pass.clone(),
+        ) else {
+            unreachable!()
+        };
+        let Some(framebuffer) = Framebuffer::from_handle(framebuffer) else {
+            unreachable!()
+        };
+
+        let triangle_pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let tint_pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            TINT_INPUT_ATTACHMENT_FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let triangle_vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+        // Triangle covering the whole framebuffer.
+        let bytes = [
+            [-1.0f32, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ]
+        .iter()
+        .flatten()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<_>>();
+        let fullscreen_vertex_buffer = create_buffer_with_bytes(logical_device.clone(), &bytes);
+        let clear_value = |float32| VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new(float32),
+            }),
+        };
+
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value([0.0, 1.0, 0.0, 1.0]), clear_value([0.0; 4])],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                triangle_pipeline,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, triangle_vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_next_subpass(VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE);
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                tint_pipeline,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, fullscreen_vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_end_render_pass();
+        });
+
+        let image = image_views[1].lock().image.clone();
+        let image = image.lock();
+        // Every texel is half of the texel of first attachment at the same position.
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (0, 0), 0, 0),
+            [128, 0, 0, 128]
+        );
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (3, 3), 0, 0),
+            [0, 128, 0, 128]
+        );
+        drop(image);
+    }
+
+    #[test]
+    fn subpass_reads_integers_of_uint_input_attachment() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let samples = VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT;
+        let uint_format = VkFormat::VK_FORMAT_R32_UINT;
+        let image_views = [
+            create_attachment(
+                logical_device.clone(),
+                (extent.width, extent.height),
+                samples,
+                uint_format,
+            ),
+            create_color_attachment(
+                logical_device.clone(),
+                (extent.width, extent.height),
+                samples,
+            ),
+        ];
+        // Texels of integer attachment hold their index times 17, which are loaded by render pass.
+        let values = (0..16).map(|i| i * 17).collect::<Vec<u32>>();
+        let bytes = values
+            .iter()
+            .flat_map(|x| x.to_ne_bytes())
+            .collect::<Vec<_>>();
+        let descriptor = image_views[0].lock().image.lock().descriptor();
+        logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .write_bytes(&bytes, &descriptor.binding, 0);
+        let attachment = |format, load_op| AttachmentDescription {
+            flags: 0.into(),
+            format,
+            samples,
+            load_op,
+            store_op: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE,
+            stencil_load_pp: VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE,
+            stencil_store_op: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE,
+            initial_layout: VkImageLayout::VK_IMAGE_LAYOUT_GENERAL,
+            final_layout: VkImageLayout::VK_IMAGE_LAYOUT_GENERAL,
+        };
+        let reference = |attachment, layout| VkAttachmentReference { attachment, layout };
+        let subpass = |input_attachments: &[_], color_attachment| SubpassDescription {
+            flags: 0.into(),
+            pipeline_bind_point: VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+            input_attachments: input_attachments.into(),
+            color_attachments: Arc::new([reference(
+                color_attachment,
+                VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+            )]),
+            resolve_attachments: Arc::new([]),
+            depth_stencil_attachment: None,
+            preserve_attachments: Arc::new([]),
+        };
+        // First subpass leaves integer attachment as loaded, which the second one reads.
+        let subpasses = [
+            subpass(&[], 0),
+            subpass(
+                &[reference(
+                    0,
+                    VkImageLayout::VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL,
+                )],
+                1,
+            ),
+        ];
+        let Ok(render_pass) = RenderPass::create(
+            logical_device.clone(),
+            &[
+                attachment(uint_format, VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_LOAD),
+                attachment(
+                    VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+                    VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR,
+                ),
+            ],
+            &[],
+            &subpasses,
+        ) else {
+            unreachable!()
+        };
+        let Some(render_pass) = RenderPass::from_handle(render_pass) else {
+            unreachable!()
+        };
+        let Ok(framebuffer) = Framebuffer::create(
+            logical_device.clone(),
+            0,
+            extent.width,
+            extent.height,
+            1,
+            image_views.to_vec(),
+            render_pass.clone(),
+        ) else {
+            unreachable!()
+        };
+        let Some(framebuffer) = Framebuffer::from_handle(framebuffer) else {
+            unreachable!()
+        };
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            UINT_INPUT_ATTACHMENT_FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        // Triangle covering the whole framebuffer.
+        let bytes = [
+            [-1.0f32, -1.0, 0.0, 1.0],
+            [3.0, -1.0, 0.0, 1.0],
+            [-1.0, 3.0, 0.0, 1.0],
+        ]
+        .iter()
+        .flatten()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<_>>();
+        let vertex_buffer = create_buffer_with_bytes(logical_device.clone(), &bytes);
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0; 4]),
+            }),
+        };
+
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value, clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_next_subpass(VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE);
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_end_render_pass();
+        });
+
+        // Shader divides integers by 255, so red takes their value and alpha of 1 substituted
+        // for missing component becomes 1 too.
+        let image = image_views[1].lock().image.clone();
+        let image = image.lock();
+        for (x, y) in itertools::iproduct!(0..4, 0..4) {
+            assert_eq!(
+                read_texel_bytes(&logical_device, &image, (x, y), 0, 0),
+                [values[(y * 4 + x) as usize] as u8, 0, 0, 1]
+            );
+        }
+        drop(image);
+    }
+
+    #[test]
+    fn dynamic_rendering_draws_into_attachment() {
+        let logical_device = create_logical_device();
+        let subresource = VkImageSubresource {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            mipLevel: 0,
+            arrayLayer: 0,
+        };
+        let mut expected = vec![0u8; 4 * 4 * 4];
+        let image = render_triangle(&logical_device);
+        assert_eq!(
+            image.lock().read_to_host(&subresource, &mut expected),
+            Ok(())
+        );
+
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let image_view = create_color_attachment(
+            logical_device.clone(),
+            (extent.width, extent.height),
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+        );
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+        let attachment = RenderingAttachment {
+            image_view: image_view.clone(),
+            image_layout: VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+            resolve_image_view: None,
+            resolve_image_layout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+            load_op: VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR,
+            store_op: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE,
+            clear_value: VkClearValue {
+                color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                    float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+                }),
+            },
+        };
+
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_rendering(
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[Some(attachment)],
+                None,
+                None,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_end_rendering();
+        });
+
+        // Same as drawn inside render pass clearing attachment to the same color.
+        let image = image_view.lock().image.clone();
+        let mut bytes = vec![0u8; 4 * 4 * 4];
+        assert_eq!(image.lock().read_to_host(&subresource, &mut bytes), Ok(()));
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn occlusion_query_counts_visible_samples() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let color_view = create_color_attachment(
+            logical_device.clone(),
+            (extent.width, extent.height),
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+        );
+        let depth_view = create_attachment(
+            logical_device.clone(),
+            (extent.width, extent.height),
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+            VkFormat::VK_FORMAT_D32_SFLOAT,
+        );
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        pipeline.lock().depth_stencil_state = gpu::DepthStencilState {
+            depth_test_enable: true,
+            depth_write_enable: true,
+            depth_compare_op: CompareOp::Less,
+            ..Default::default()
+        };
+        // Triangle covering the upper left corner in front of one covering the whole framebuffer.
+        let positions = [
+            [-1.0f32, -1.0, 0.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+            [-1.0, -1.0, 0.5, 1.0],
+            [3.0, -1.0, 0.5, 1.0],
+            [-1.0, 3.0, 0.5, 1.0],
+        ];
+        let bytes = positions
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        let vertex_buffer = create_buffer_with_bytes(logical_device.clone(), &bytes);
+        let query_pool_create_info = VkQueryPoolCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_QUERY_POOL_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queryType: VkQueryType::VK_QUERY_TYPE_OCCLUSION,
+            queryCount: 1,
+            pipelineStatistics: 0,
+        };
+        let Ok(query_pool) = QueryPool::create(logical_device.clone(), &query_pool_create_info)
+        else {
+            unreachable!()
+        };
+        let Some(query_pool) = QueryPool::from_handle(query_pool) else {
+            unreachable!()
+        };
+        let mut result = [0u8; 8];
+        let flags = VkQueryResultFlagBits::VK_QUERY_RESULT_64_BIT.into();
+        assert_eq!(
+            query_pool.lock().results(0, 1, &mut result, 8, flags),
+            VkResult::VK_NOT_READY
+        );
+
+        let color_attachment = RenderingAttachment {
+            image_view: color_view,
+            image_layout: VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+            resolve_image_view: None,
+            resolve_image_layout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+            load_op: VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR,
+            store_op: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE,
+            clear_value: VkClearValue {
+                color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                    float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+                }),
+            },
+        };
+        let depth_attachment = RenderingAttachment {
+            image_view: depth_view.clone(),
+            image_layout: VkImageLayout::VK_IMAGE_LAYOUT_DEPTH_ATTACHMENT_OPTIMAL,
+            clear_value: VkClearValue {
+                depthStencil: std::mem::ManuallyDrop::new(VkClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                }),
+            },
+            ..color_attachment.clone()
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_reset_query_pool(query_pool.clone(), 0, 1);
+            command_buffer.cmd_begin_rendering(
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[Some(color_attachment)],
+                Some(&depth_attachment),
+                None,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_begin_query(query_pool.clone(), 0, 0);
+            command_buffer.cmd_draw(3, 1, 3, 0);
+            command_buffer.cmd_end_query(query_pool.clone(), 0);
+            command_buffer.cmd_end_rendering();
+        });
+
+        // Samples of the far triangle that passed depth test are the ones left at its depth.
+        let image = depth_view.lock().image.clone();
+        let image = image.lock();
+        let visible_samples = (0..extent.height)
+            .flat_map(|y| (0..extent.width).map(move |x| (x, y)))
+            .filter(|&texel| {
+                let bytes = read_texel_bytes(&logical_device, &image, texel, 0, 0);
+                bytes == 0.5f32.to_le_bytes()
+            })
+            .count() as u64;
+        drop(image);
+        assert!(0 < visible_samples && visible_samples < 16);
+        assert_eq!(
+            query_pool.lock().results(0, 1, &mut result, 8, flags),
+            VkResult::VK_SUCCESS
+        );
+        assert_eq!(u64::from_le_bytes(result), visible_samples);
+    }
+
+    #[test]
+    fn timestamps_around_draw_are_ordered() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) =
+            create_framebuffer(logical_device.clone(), (extent.width, extent.height));
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+        let query_pool_create_info = VkQueryPoolCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_QUERY_POOL_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queryType: VkQueryType::VK_QUERY_TYPE_TIMESTAMP,
+            queryCount: 2,
+            pipelineStatistics: 0,
+        };
+        let Ok(query_pool) = QueryPool::create(logical_device.clone(), &query_pool_create_info)
+        else {
+            unreachable!()
+        };
+        let Some(query_pool) = QueryPool::from_handle(query_pool) else {
+            unreachable!()
+        };
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+            }),
+        };
+
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_reset_query_pool(query_pool.clone(), 0, 2);
+            command_buffer.cmd_write_timestamp(
+                VkPipelineStageFlagBits2::VK_PIPELINE_STAGE_2_TOP_OF_PIPE_BIT.into(),
+                query_pool.clone(),
+                0,
+            );
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_end_render_pass();
+            command_buffer.cmd_write_timestamp(
+                VkPipelineStageFlagBits2::VK_PIPELINE_STAGE_2_BOTTOM_OF_PIPE_BIT.into(),
+                query_pool.clone(),
+                1,
+            );
+        });
+
+        // Each timestamp is followed by its availability.
+        let mut results = [0u8; 32];
+        let flags = (VkQueryResultFlagBits::VK_QUERY_RESULT_64_BIT
+            | VkQueryResultFlagBits::VK_QUERY_RESULT_WITH_AVAILABILITY_BIT)
+            .into();
+        assert_eq!(
+            query_pool.lock().results(0, 2, &mut results, 16, flags),
+            VkResult::VK_SUCCESS
+        );
+        let [first, first_available, second, second_available] = std::array::from_fn(|i| {
+            u64::from_le_bytes(
+                results[i * 8..i * 8 + 8]
+                    .try_into()
+                    .unwrap_or_else(|_| unreachable!()),
+            )
+        });
+        assert_eq!((first_available, second_available), (1, 1));
+        assert!(second >= first);
+    }
+
+    #[test]
+    fn host_reset_makes_written_queries_unavailable() {
+        let logical_device = create_logical_device();
+        let query_pool_create_info = VkQueryPoolCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_QUERY_POOL_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queryType: VkQueryType::VK_QUERY_TYPE_TIMESTAMP,
+            queryCount: 2,
+            pipelineStatistics: 0,
+        };
+        let Ok(query_pool) = QueryPool::create(logical_device.clone(), &query_pool_create_info)
+        else {
+            unreachable!()
+        };
+        let Some(query_pool) = QueryPool::from_handle(query_pool) else {
+            unreachable!()
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_reset_query_pool(query_pool.clone(), 0, 2);
+            for query in 0..2 {
+                command_buffer.cmd_write_timestamp(
+                    VkPipelineStageFlagBits2::VK_PIPELINE_STAGE_2_TOP_OF_PIPE_BIT.into(),
+                    query_pool.clone(),
+                    query,
+                );
+            }
+        });
+
+        // Each result is followed by its availability.
+        let mut results = [0u8; 16];
+        let flags = VkQueryResultFlagBits::VK_QUERY_RESULT_WITH_AVAILABILITY_BIT.into();
+        assert_eq!(
+            query_pool.lock().results(0, 2, &mut results, 8, flags),
+            VkResult::VK_SUCCESS
+        );
+        assert_eq!(results[4..8], 1u32.to_le_bytes());
+        assert_eq!(results[12..16], 1u32.to_le_bytes());
+
+        query_pool.lock().reset(1, 1);
+        assert_eq!(
+            query_pool.lock().results(0, 2, &mut results, 8, flags),
+            VkResult::VK_NOT_READY
+        );
+        assert_eq!(results[4..8], 1u32.to_le_bytes());
+        assert_eq!(results[12..16], 0u32.to_le_bytes());
+    }
+
+    #[test]
+    fn read_to_host_returns_rendered_texels() {
+        let logical_device = create_logical_device();
+        let image = render_triangle(&logical_device);
+        let image = image.lock();
+        let subresource = VkImageSubresource {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            mipLevel: 0,
+            arrayLayer: 0,
+        };
+        let mut bytes = vec![0u8; 4 * 4 * 4];
+        assert_eq!(image.read_to_host(&subresource, &mut bytes), Ok(()));
+        // Triangle covers texels above the diagonal, the rest keeps clear color.
+        let expected = (0..4u32)
+            .flat_map(|y| (0..4u32).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                if x + y < 3 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 255, 0, 255]
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bytes, expected);
+
+        assert_eq!(
+            image.read_to_host(&subresource, &mut bytes[..4 * 4 * 4 - 1]),
+            Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT)
+        );
+        let outside = VkImageSubresource {
+            mipLevel: 1,
+            ..subresource
+        };
+        assert_eq!(
+            image.read_to_host(&outside, &mut bytes),
+            Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT)
+        );
+        drop(image);
+    }
+
+    #[test]
+    fn save_ppm_writes_rendered_texels() {
+        let logical_device = create_logical_device();
+        let image = render_triangle(&logical_device);
+        let temp_dir = assert_fs::TempDir::new().unwrap_or_else(|_| unreachable!());
+        let path = temp_dir.join("triangle.ppm");
+        assert!(crate::image::save_ppm(&image.lock(), &path).is_ok());
+
+        let bytes = std::fs::read(&path).unwrap_or_else(|_| unreachable!());
+        let header = b"P6\n4 4\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let texels = bytes[header.len()..]
+            .chunks_exact(3)
+            .map(|texel| [texel[0], texel[1], texel[2], 255])
+            .collect::<Vec<_>>();
+        assert_eq!(texels, read_image_texels(&logical_device, &image));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn save_png_writes_rendered_texels() {
+        let logical_device = create_logical_device();
+        let image = render_triangle(&logical_device);
+        let temp_dir = assert_fs::TempDir::new().unwrap_or_else(|_| unreachable!());
+        let path = temp_dir.join("triangle.png");
+        assert!(crate::image::save_png(&image.lock(), &path).is_ok());
+
+        let file = std::fs::File::open(&path).unwrap_or_else(|_| unreachable!());
+        let mut reader = png::Decoder::new(file)
+            .read_info()
+            .unwrap_or_else(|_| unreachable!());
+        let mut bytes = vec![0u8; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut bytes)
+            .unwrap_or_else(|_| unreachable!());
+        assert_eq!((info.width, info.height), (4, 4));
+        let texels = bytes
+            .chunks_exact(4)
+            .map(|texel| [texel[0], texel[1], texel[2], texel[3]])
+            .collect::<Vec<_>>();
+        assert_eq!(texels, read_image_texels(&logical_device, &image));
+    }
+
+    /// Creates pipeline layout and allocates set of single dynamic uniform buffer descriptor,
+    /// viewing first 16 bytes of buffer holding `colors`.
+    fn create_dynamic_uniform_buffer_set(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        colors: &[[f32; 4]],
+    ) -> (Arc<Mutex<PipelineLayout>>, Vec<VkDescriptorSet>) {
+        let Ok(set_layout) = DescriptorSetLayout::create(
+            logical_device.clone(),
+            0,
+            &[VkDescriptorSetLayoutBinding {
+                binding: 0,
+                descriptorType: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER_DYNAMIC,
+                descriptorCount: 1,
+                stageFlags: VkShaderStageFlagBits::VK_SHADER_STAGE_FRAGMENT_BIT.into(),
+                pImmutableSamplers: None,
+            }],
+        ) else {
+            unreachable!()
+        };
+        let Ok(pipeline_layout) =
+            PipelineLayout::create(logical_device.clone(), 0, Some(&[set_layout]), None)
+        else {
+            unreachable!()
+        };
+        let Some(pipeline_layout) = PipelineLayout::from_handle(pipeline_layout) else {
+            unreachable!()
+        };
+        let Ok(descriptor_pool) = DescriptorPool::create(
+            logical_device.clone(),
+            0,
+            1,
+            &[VkDescriptorPoolSize {
+                type_: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER_DYNAMIC,
+                descriptorCount: 1,
+            }],
+        ) else {
+            unreachable!()
+        };
+        let Some(descriptor_pool) = DescriptorPool::from_handle(descriptor_pool) else {
+            unreachable!()
+        };
+        let Some(set_layout) = DescriptorSetLayout::from_handle(set_layout) else {
+            unreachable!()
+        };
+        let Ok(descriptor_sets) =
+            DescriptorPool::allocate_descriptor_sets(&descriptor_pool, &[set_layout])
+        else {
+            unreachable!()
+        };
+
+        let bytes = colors
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        let uniform_buffer = create_buffer_with_bytes(logical_device.clone(), &bytes);
+        let buffer_info = VkDescriptorBufferInfo {
+            buffer: uniform_buffer.lock().handle,
+            offset: 0,
+            range: 16,
+        };
+        unsafe {
+            DescriptorSet::update_descriptor_sets(
+                &[VkWriteDescriptorSet {
+                    sType: VkStructureType::VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET,
+                    pNext: None,
+                    dstSet: descriptor_sets[0],
+                    dstBinding: 0,
+                    dstArrayElement: 0,
+                    descriptorCount: 1,
+                    descriptorType: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER_DYNAMIC,
+                    pImageInfo: None,
+                    pBufferInfo: Some(NonNull::from(&buffer_info)),
+                    pTexelBufferView: None,
+                }],
+                &[],
+            );
+        }
+        (pipeline_layout, descriptor_sets)
+    }
+
+    #[test]
+    fn bind_descriptor_sets_uniform_buffer_color() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) =
+            create_framebuffer(logical_device.clone(), (extent.width, extent.height));
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            UNIFORM_COLOR_FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+        // Color read by shader is found at dynamic offset, past a different color.
+        let (pipeline_layout, descriptor_sets) = create_dynamic_uniform_buffer_set(
+            &logical_device,
+            &[[1.0, 1.0, 1.0, 1.0], [0.0, 0.0, 1.0, 1.0]],
+        );
+
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+            }),
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            command_buffer.cmd_bind_descriptor_sets(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline_layout,
+                0,
+                &descriptor_sets,
+                &[16],
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_end_render_pass();
+        });
+
+        let image = image_view.lock().image.clone();
+        let image = image.lock();
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (0, 0), 0, 0),
+            [0, 0, 255, 255]
+        );
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (3, 3), 0, 0),
+            [0, 255, 0, 255]
+        );
+        drop(image);
+    }
+
+    #[test]
+    fn draws_read_uniform_buffer_slices_of_their_dynamic_offsets() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) =
+            create_framebuffer(logical_device.clone(), (extent.width, extent.height));
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            UNIFORM_COLOR_FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        // Triangles cover upper left and lower right halves of framebuffer.
+        let upper_left = create_triangle_vertex_buffer(logical_device.clone());
+        let positions = [
+            [1.0f32, -1.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+        ];
+        let bytes = positions
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        let lower_right = create_buffer_with_bytes(logical_device.clone(), &bytes);
+        let (pipeline_layout, descriptor_sets) = create_dynamic_uniform_buffer_set(
+            &logical_device,
+            &[[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]],
+        );
+
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+            }),
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            for (dynamic_offset, vertex_buffer) in [(0, upper_left), (16, lower_right)] {
+                command_buffer.cmd_bind_descriptor_sets(
+                    VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                    pipeline_layout.clone(),
+                    0,
+                    &descriptor_sets,
+                    &[dynamic_offset],
+                );
+                command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+                command_buffer.cmd_draw(3, 1, 0, 0);
+            }
+            command_buffer.cmd_end_render_pass();
+        });
+
+        let image = image_view.lock().image.clone();
+        let image = image.lock();
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (0, 0), 0, 0),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (3, 3), 0, 0),
+            [0, 0, 255, 255]
+        );
+        drop(image);
+    }
+
+    #[test]
+    fn bind_descriptor_sets_rejects_invalid_dynamic_offsets() {
+        let logical_device = create_logical_device();
+        let (pipeline_layout, descriptor_sets) =
+            create_dynamic_uniform_buffer_set(&logical_device, &[[0.0; 4]; 2]);
+        let command_buffer = create_command_buffer(logical_device);
+        let mut command_buffer = command_buffer.lock();
+        assert_eq!(command_buffer.begin(), Ok(()));
+        // Offsets are missing, unaligned, or move 16 bytes of descriptor past 32 bytes of buffer.
+        for dynamic_offsets in [&[] as &[_], &[4], &[32], &[16, 16]] {
+            command_buffer.cmd_bind_descriptor_sets(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline_layout.clone(),
+                0,
+                &descriptor_sets,
+                dynamic_offsets,
+            );
+        }
+        assert!(command_buffer.commands().is_empty());
+        command_buffer.cmd_bind_descriptor_sets(
+            VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+            pipeline_layout,
+            0,
+            &descriptor_sets,
+            &[16],
+        );
+        assert_eq!(command_buffer.commands().len(), 1);
+        drop(command_buffer);
+    }
+
+    #[test]
+    fn push_descriptor_set_uniform_buffer_color() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) =
+            create_framebuffer(logical_device.clone(), (extent.width, extent.height));
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            UNIFORM_COLOR_FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+
+        let Ok(set_layout) = DescriptorSetLayout::create(
+            logical_device.clone(),
+            VkDescriptorSetLayoutCreateFlagBits::VK_DESCRIPTOR_SET_LAYOUT_CREATE_PUSH_DESCRIPTOR_BIT_KHR
+                .into(),
+            &[VkDescriptorSetLayoutBinding {
+                binding: 0,
+                descriptorType: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER,
+                descriptorCount: 1,
+                stageFlags: VkShaderStageFlagBits::VK_SHADER_STAGE_FRAGMENT_BIT.into(),
+                pImmutableSamplers: None,
+            }],
+        ) else { unreachable!() };
+        let Ok(pipeline_layout) =
+            PipelineLayout::create(logical_device.clone(), 0, Some(&[set_layout]), None)
+        else {
+            unreachable!()
+        };
+        let Some(pipeline_layout) = PipelineLayout::from_handle(pipeline_layout) else {
+            unreachable!()
+        };
+
+        // Color read by shader is the one of pushed descriptor, past a different color.
+        let colors = [[1.0f32, 1.0, 1.0, 1.0], [0.0, 0.0, 1.0, 1.0]];
+        let bytes = colors
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        let uniform_buffer = create_buffer_with_bytes(logical_device.clone(), &bytes);
+        let buffer_info = VkDescriptorBufferInfo {
+            buffer: uniform_buffer.lock().handle,
+            offset: 16,
+            range: 16,
+        };
+        let write = VkWriteDescriptorSet {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET,
+            pNext: None,
+            dstSet: VK_NULL_HANDLE,
+            dstBinding: 0,
+            dstArrayElement: 0,
+            descriptorCount: 1,
+            descriptorType: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER,
+            pImageInfo: None,
+            pBufferInfo: Some(NonNull::from(&buffer_info)),
+            pTexelBufferView: None,
+        };
+
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+            }),
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            unsafe {
+                command_buffer.cmd_push_descriptor_set(
+                    VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                    pipeline_layout,
+                    0,
+                    &[write],
+                );
+            }
+            assert_eq!(command_buffer.push_descriptor_sets.len(), 1);
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_end_render_pass();
+        });
+
+        let image = image_view.lock().image.clone();
+        let image = image.lock();
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (0, 0), 0, 0),
+            [0, 0, 255, 255]
+        );
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (3, 3), 0, 0),
+            [0, 255, 0, 255]
+        );
+        drop(image);
+    }
+
+    #[test]
+    fn push_constants_offset_vertices() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) =
+            create_framebuffer(logical_device.clone(), (extent.width, extent.height));
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            PUSH_CONSTANT_OFFSET_VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+        let Ok(pipeline_layout) = PipelineLayout::create(
+            logical_device.clone(),
+            0,
+            None,
+            Some(&[VkPushConstantRange {
+                stageFlags: VkShaderStageFlagBits::VK_SHADER_STAGE_VERTEX_BIT.into(),
+                offset: 0,
+                size: 16,
+            }]),
+        ) else {
+            unreachable!()
+        };
+        let Some(pipeline_layout) = PipelineLayout::from_handle(pipeline_layout) else {
+            unreachable!()
+        };
+
+        // Subtracting negative offset moves triangle to the lower right quadrant.
+        let offset = [-1.0f32, -1.0, 0.0, 0.0];
+        let bytes = offset
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+            }),
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            // Write outside of declared range is rejected and leaves offset untouched.
+            command_buffer.cmd_push_constants(
+                pipeline_layout.clone(),
+                VkShaderStageFlagBits::VK_SHADER_STAGE_VERTEX_BIT.into(),
+                16,
+                &[0xff; 4],
+            );
+            command_buffer.cmd_push_constants(
+                pipeline_layout,
+                VkShaderStageFlagBits::VK_SHADER_STAGE_VERTEX_BIT.into(),
+                0,
+                &bytes,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_end_render_pass();
+        });
+
+        let image = image_view.lock().image.clone();
+        let image = image.lock();
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (0, 0), 0, 0),
+            [0, 255, 0, 255]
+        );
+        assert_eq!(
+            read_texel_bytes(&logical_device, &image, (2, 2), 0, 0),
+            [255, 0, 0, 255]
+        );
+        drop(image);
+    }
+
+    #[test]
+    fn draw_instances_offset_by_instance_index() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 6,
+            height: 2,
+        };
+        let (render_pass, framebuffer) =
+            create_framebuffer(logical_device.clone(), (extent.width, extent.height));
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            INSTANCE_OFFSET_VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        // Triangle covering upper left texel, moved two texels right by each instance.
+        let positions = [
+            [-1.0f32, -1.0, 0.0, 1.0],
+            [-1.0 / 3.0, -1.0, 0.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0],
+        ];
+        let bytes = positions
+            .iter()
+            .flatten()
+            .flat_map(|x| x.to_le_bytes())
+            .collect::<Vec<_>>();
+        let vertex_buffer = create_buffer_with_bytes(logical_device.clone(), &bytes);
+
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 1.0, 0.0, 1.0]),
+            }),
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 3, 0, 0);
+            command_buffer.cmd_end_render_pass();
+        });
+
+        let image = image_view.lock().image.clone();
+        let image = image.lock();
+        for x in 0..extent.width {
+            let expected = if x % 2 == 0 {
+                [255, 0, 0, 255]
+            } else {
+                [0, 255, 0, 255]
+            };
+            assert_eq!(
+                read_texel_bytes(&logical_device, &image, (x, 0), 0, 0),
+                expected
+            );
+            assert_eq!(
+                read_texel_bytes(&logical_device, &image, (x, 1), 0, 0),
+                [0, 255, 0, 255]
+            );
+        }
+        drop(image);
+    }
+
+    #[test]
+    fn draw_multisampled_slanted_edge_and_resolve() {
+        let logical_device = create_logical_device();
+        let extent = VkExtent2D {
+            width: 4,
+            height: 4,
+        };
+        let (render_pass, framebuffer) = create_multisampled_framebuffer(
+            logical_device.clone(),
+            (extent.width, extent.height),
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_4_BIT,
+        );
+        let image_view = framebuffer.lock().attachments[0].clone();
+        let pipeline = create_triangle_pipeline(
+            logical_device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            (extent.width, extent.height),
+        );
+        let vertex_buffer = create_triangle_vertex_buffer(logical_device.clone());
+        let resolved = create_image(
+            logical_device.clone(),
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            (extent.width, extent.height),
+            1,
+            1,
+        );
+
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new([0.0, 0.0, 0.0, 1.0]),
+            }),
+        };
+        let subresource = VkImageSubresourceLayers {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            mipLevel: 0,
+            baseArrayLayer: 0,
+            layerCount: 1,
+        };
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_begin_render_pass(
+                render_pass,
+                framebuffer,
+                VkRect2D {
+                    offset: VkOffset2D { x: 0, y: 0 },
+                    extent,
+                },
+                &[clear_value],
+                VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+            );
+            command_buffer.cmd_bind_pipeline(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+                pipeline,
+            );
+            command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+            command_buffer.cmd_draw(3, 1, 0, 0);
+            command_buffer.cmd_end_render_pass();
+            command_buffer.cmd_resolve_image(
+                image_view.lock().image.clone(),
+                VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+                resolved.clone(),
+                VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                &[VkImageResolve {
+                    srcSubresource: subresource,
+                    srcOffset: VkOffset3D { x: 0, y: 0, z: 0 },
+                    dstSubresource: subresource,
+                    dstOffset: VkOffset3D { x: 0, y: 0, z: 0 },
+                    extent: VkExtent3D {
+                        width: extent.width,
+                        height: extent.height,
+                        depth: 1,
+                    },
+                }],
+            );
+        });
+
+        // Triangle edge runs along the diagonal, covering half of samples of texels it crosses.
+        let texels = read_image_texels(&logical_device, &resolved);
+        for y in 0..extent.height {
+            for x in 0..extent.width {
+                let expected = match x + y {
+                    0..3 => [255, 0, 0, 255],
+                    3 => [128, 0, 0, 255],
+                    _ => [0, 0, 0, 255],
+                };
+                assert_eq!(
+                    texels[(y * extent.width + x) as usize],
+                    expected,
+                    "({x}, {y})"
+                );
+            }
+        }
+    }
+
+    // #version 450
+    // layout(local_size_x = 4) in;
+    // layout(set = 0, binding = 0) buffer Buffer { uint data[]; } buf;
+    // void main() { buf.data[gl_GlobalInvocationID.x] = gl_GlobalInvocationID.x; }
+    const STORE_GLOBAL_INVOCATION_ID_COMPUTE_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x0000001a, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0006000f, 0x00000005, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00060010, 0x00000001, 0x00000011, 0x00000004, 0x00000001,
+        0x00000001, 0x00040047, 0x00000006, 0x00000006, 0x00000004, 0x00050048, 0x00000007,
+        0x00000000, 0x00000023, 0x00000000, 0x00030047, 0x00000007, 0x00000003, 0x00040047,
+        0x00000009, 0x00000022, 0x00000000, 0x00040047, 0x00000009, 0x00000021, 0x00000000,
+        0x00040047, 0x00000002, 0x0000000b, 0x0000001c, 0x00040047, 0x00000013, 0x0000000b,
+        0x00000019, 0x00020013, 0x00000003, 0x00030021, 0x00000004, 0x00000003, 0x00040015,
+        0x00000005, 0x00000020, 0x00000000, 0x0003001d, 0x00000006, 0x00000005, 0x0003001e,
+        0x00000007, 0x00000006, 0x00040020, 0x00000008, 0x00000002, 0x00000007, 0x0004003b,
+        0x00000008, 0x00000009, 0x00000002, 0x00040015, 0x0000000a, 0x00000020, 0x00000001,
+        0x0004002b, 0x0000000a, 0x0000000b, 0x00000000, 0x00040017, 0x0000000c, 0x00000005,
+        0x00000003, 0x00040020, 0x0000000d, 0x00000001, 0x0000000c, 0x0004003b, 0x0000000d,
+        0x00000002, 0x00000001, 0x0004002b, 0x00000005, 0x0000000e, 0x00000000, 0x00040020,
+        0x0000000f, 0x00000001, 0x00000005, 0x00040020, 0x00000010, 0x00000002, 0x00000005,
+        0x0004002b, 0x00000005, 0x00000011, 0x00000001, 0x0004002b, 0x00000005, 0x00000012,
+        0x00000004, 0x0006002c, 0x0000000c, 0x00000013, 0x00000012, 0x00000011, 0x00000011,
+        0x00050036, 0x00000003, 0x00000001, 0x00000000, 0x00000004, 0x000200f8, 0x00000014,
+        0x00050041, 0x0000000f, 0x00000015, 0x00000002, 0x0000000e, 0x0004003d, 0x00000005,
+        0x00000016, 0x00000015, 0x00050041, 0x0000000f, 0x00000017, 0x00000002, 0x0000000e,
+        0x0004003d, 0x00000005, 0x00000018, 0x00000017, 0x00060041, 0x00000010, 0x00000019,
+        0x00000009, 0x0000000b, 0x00000016, 0x0003003e, 0x00000019, 0x00000018, 0x000100fd,
+        0x00010038,
+    ];
+
+    // #version 450
+    // layout(local_size_x = 4) in;
+    // layout(set = 0, binding = 0) buffer Buffer { uint data[]; } buf;
+    // shared uint tmp[4];
+    // void main() {
+    //     tmp[gl_LocalInvocationID.x] = gl_GlobalInvocationID.x;
+    //     barrier();
+    //     buf.data[gl_GlobalInvocationID.x] = tmp[3];
+    // }
+    const SHARED_MEMORY_BARRIER_COMPUTE_SHADER: &[u32] = &[
+        0x07230203, 0x00010000, 0x00080000, 0x00000027, 0x00000000, 0x00020011, 0x00000001,
+        0x0003000e, 0x00000000, 0x00000001, 0x0007000f, 0x00000005, 0x00000001, 0x6e69616d,
+        0x00000000, 0x00000002, 0x00000003, 0x00060010, 0x00000001, 0x00000011, 0x00000004,
+        0x00000001, 0x00000001, 0x00040047, 0x00000007, 0x00000006, 0x00000004, 0x00050048,
+        0x00000008, 0x00000000, 0x00000023, 0x00000000, 0x00030047, 0x00000008, 0x00000003,
+        0x00040047, 0x0000000a, 0x00000022, 0x00000000, 0x00040047, 0x0000000a, 0x00000021,
+        0x00000000, 0x00040047, 0x00000002, 0x0000000b, 0x0000001c, 0x00040047, 0x00000003,
+        0x0000000b, 0x0000001b, 0x00040047, 0x00000014, 0x0000000b, 0x00000019, 0x00020013,
+        0x00000004, 0x00030021, 0x00000005, 0x00000004, 0x00040015, 0x00000006, 0x00000020,
+        0x00000000, 0x0003001d, 0x00000007, 0x00000006, 0x0003001e, 0x00000008, 0x00000007,
+        0x00040020, 0x00000009, 0x00000002, 0x00000008, 0x0004003b, 0x00000009, 0x0000000a,
+        0x00000002, 0x00040015, 0x0000000b, 0x00000020, 0x00000001, 0x0004002b, 0x0000000b,
+        0x0000000c, 0x00000000, 0x00040017, 0x0000000d, 0x00000006, 0x00000003, 0x00040020,
+        0x0000000e, 0x00000001, 0x0000000d, 0x0004003b, 0x0000000e, 0x00000002, 0x00000001,
+        0x0004003b, 0x0000000e, 0x00000003, 0x00000001, 0x0004002b, 0x00000006, 0x0000000f,
+        0x00000000, 0x00040020, 0x00000010, 0x00000001, 0x00000006, 0x00040020, 0x00000011,
+        0x00000002, 0x00000006, 0x0004002b, 0x00000006, 0x00000012, 0x00000001, 0x0004002b,
+        0x00000006, 0x00000013, 0x00000004, 0x0006002c, 0x0000000d, 0x00000014, 0x00000013,
+        0x00000012, 0x00000012, 0x0004001c, 0x00000015, 0x00000006, 0x00000013, 0x00040020,
+        0x00000016, 0x00000004, 0x00000015, 0x0004003b, 0x00000016, 0x00000017, 0x00000004,
+        0x00040020, 0x00000018, 0x00000004, 0x00000006, 0x0004002b, 0x00000006, 0x00000019,
+        0x00000002, 0x0004002b, 0x00000006, 0x0000001a, 0x00000108, 0x0004002b, 0x00000006,
+        0x0000001b, 0x00000003, 0x00050036, 0x00000004, 0x00000001, 0x00000000, 0x00000005,
+        0x000200f8, 0x0000001c, 0x00050041, 0x00000010, 0x0000001d, 0x00000003, 0x0000000f,
+        0x0004003d, 0x00000006, 0x0000001e, 0x0000001d, 0x00050041, 0x00000010, 0x0000001f,
+        0x00000002, 0x0000000f, 0x0004003d, 0x00000006, 0x00000020, 0x0000001f, 0x00050041,
+        0x00000018, 0x00000021, 0x00000017, 0x0000001e, 0x0003003e, 0x00000021, 0x00000020,
+        0x000400e0, 0x00000019, 0x00000019, 0x0000001a, 0x00050041, 0x00000010, 0x00000022,
+        0x00000002, 0x0000000f, 0x0004003d, 0x00000006, 0x00000023, 0x00000022, 0x00050041,
+        0x00000018, 0x00000024, 0x00000017, 0x0000001b, 0x0004003d, 0x00000006, 0x00000026,
+        0x00000024, 0x00060041, 0x00000011, 0x00000025, 0x0000000a, 0x0000000c, 0x00000023,
+        0x0003003e, 0x00000025, 0x00000026, 0x000100fd, 0x00010038,
+    ];
+
+    /// Dispatches `workgroup_count` workgroups of compute shader with zeroed storage buffer of
+    /// `len` words bound to set 0, binding 0, returning buffer contents after submit.
+    fn dispatch_with_storage_buffer(
+        compute_shader: &[u32],
+        workgroup_count: [u32; 3],
+        len: usize,
+    ) -> Vec<u32> {
+        let logical_device = create_logical_device();
+        let Ok(shader) = Shader::new("main", compute_shader.to_vec()) else {
+            unreachable!()
+        };
+        let Ok(pipeline) = ComputePipeline::create(logical_device.clone(), None, shader) else {
+            unreachable!()
+        };
+        let Some(pipeline) = ComputePipeline::from_handle(pipeline) else {
+            unreachable!()
+        };
+
+        let Ok(set_layout) = DescriptorSetLayout::create(
+            logical_device.clone(),
+            0,
+            &[VkDescriptorSetLayoutBinding {
+                binding: 0,
+                descriptorType: VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER,
+                descriptorCount: 1,
+                stageFlags: VkShaderStageFlagBits::VK_SHADER_STAGE_COMPUTE_BIT.into(),
+                pImmutableSamplers: None,
+            }],
+        ) else {
+            unreachable!()
+        };
+        let Ok(pipeline_layout) =
+            PipelineLayout::create(logical_device.clone(), 0, Some(&[set_layout]), None)
+        else {
+            unreachable!()
+        };
+        let Some(pipeline_layout) = PipelineLayout::from_handle(pipeline_layout) else {
+            unreachable!()
+        };
+        let Ok(descriptor_pool) = DescriptorPool::create(
+            logical_device.clone(),
+            0,
+            1,
+            &[VkDescriptorPoolSize {
+                type_: VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER,
+                descriptorCount: 1,
+            }],
+        ) else {
+            unreachable!()
+        };
+        let Some(descriptor_pool) = DescriptorPool::from_handle(descriptor_pool) else {
+            unreachable!()
+        };
+        let Some(set_layout) = DescriptorSetLayout::from_handle(set_layout) else {
+            unreachable!()
+        };
+        let Ok(descriptor_sets) =
+            DescriptorPool::allocate_descriptor_sets(&descriptor_pool, &[set_layout])
+        else {
+            unreachable!()
+        };
+
+        let size = (len * std::mem::size_of::<u32>()) as u64;
+        let storage_buffer =
+            create_buffer_with_bytes(logical_device.clone(), &vec![0; size as usize]);
+        let buffer_info = VkDescriptorBufferInfo {
+            buffer: storage_buffer.lock().handle,
+            offset: 0,
+            range: size,
+        };
+        unsafe {
+            DescriptorSet::update_descriptor_sets(
+                &[VkWriteDescriptorSet {
+                    sType: VkStructureType::VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET,
+                    pNext: None,
+                    dstSet: descriptor_sets[0],
+                    dstBinding: 0,
+                    dstArrayElement: 0,
+                    descriptorCount: 1,
+                    descriptorType: VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER,
+                    pImageInfo: None,
+                    pBufferInfo: Some(NonNull::from(&buffer_info)),
+                    pTexelBufferView: None,
+                }],
+                &[],
+            );
+        }
+
+        record_and_submit(&logical_device, |command_buffer| {
+            command_buffer.cmd_bind_compute_pipeline(pipeline);
+            command_buffer.cmd_bind_descriptor_sets(
+                VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_COMPUTE,
+                pipeline_layout,
+                0,
+                &descriptor_sets,
+                &[],
+            );
+            let [x, y, z] = workgroup_count;
+            command_buffer.cmd_dispatch(x, y, z);
+        });
+
+        let descriptor = storage_buffer.lock().descriptor();
+        let logical_device = logical_device.lock();
+        let bytes = logical_device
+            .physical_device()
+            .gpu
+            .memory
+            .read_bytes(&descriptor.binding, 0, size)
+            .to_vec();
+        drop(logical_device);
+        bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect()
+    }
+
+    #[test]
+    fn dispatch_writes_global_invocation_id_to_storage_buffer() {
+        assert_eq!(
+            dispatch_with_storage_buffer(STORE_GLOBAL_INVOCATION_ID_COMPUTE_SHADER, [2, 1, 1], 8),
+            (0..8).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn dispatch_shares_workgroup_memory_past_barrier() {
+        // Each invocation reads value written by the last invocation of its workgroup.
+        assert_eq!(
+            dispatch_with_storage_buffer(SHARED_MEMORY_BARRIER_COMPUTE_SHADER, [2, 1, 1], 8),
+            [3, 3, 3, 3, 7, 7, 7, 7]
+        );
     }
 }
@@ -1,95 +1,136 @@
 //! Context
 
+use crate::error::RuntimeError;
 use crate::impl_dispatchable_trait;
 use crate::impl_non_dispatchable_trait;
+use crate::logical_device::LogicalDevice;
 use headers::vk_decls::*;
 use lazy_static::lazy_static;
+use log::*;
 
-use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
-use std::collections::HashMap;
+use parking_lot::{Mutex, RwLock};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
 use std::num::NonZeroU64;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+type Table<H, T> = RwLock<HashMap<H, Arc<Mutex<T>>>>;
+
+/// Objects of each type are kept in their own table behind a separate lock, so that creating or
+/// looking up objects of one type doesn't block threads using another.
 #[derive(Debug, Default)]
 pub struct Context {
-    // TODO: Better way to do concurrency than Arc<Mutex<_>>?
-    instances: HashMap<VkDispatchableHandle, Arc<Mutex<crate::instance::Instance>>>,
-    physical_devices:
-        HashMap<VkDispatchableHandle, Arc<Mutex<crate::physical_device::PhysicalDevice>>>,
-    logical_devices:
-        HashMap<VkDispatchableHandle, Arc<Mutex<crate::logical_device::LogicalDevice>>>,
-    queues: HashMap<VkDispatchableHandle, Arc<Mutex<crate::queue::Queue>>>,
-    fences: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::fence::Fence>>>,
-    semaphores: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::semaphore::Semaphore>>>,
-    surfaces: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::surface::Surface>>>,
-    swapchains: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::swapchain::Swapchain>>>,
-    images: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::image::Image>>>,
-    image_views: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::image::ImageView>>>,
-    command_pools: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::command_buffer::CommandPool>>>,
-    command_buffers:
-        HashMap<VkDispatchableHandle, Arc<Mutex<crate::command_buffer::CommandBuffer>>>,
-    memory_allocations:
-        HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::memory::MemoryAllocation>>>,
-    samplers: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::sampler::Sampler>>>,
-    buffers: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::buffer::Buffer>>>,
-    buffer_views: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::buffer::BufferView>>>,
-    descriptor_set_layouts:
-        HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::descriptor::DescriptorSetLayout>>>,
-    pipeline_layouts: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::pipeline::PipelineLayout>>>,
-    render_passes: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::pipeline::RenderPass>>>,
-    shader_modules: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::pipeline::ShaderModule>>>,
-    pipeline_caches: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::pipeline::PipelineCache>>>,
-    pipelines: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::pipeline::Pipeline>>>,
-    descriptor_pools:
-        HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::descriptor::DescriptorPool>>>,
-    descriptor_sets: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::descriptor::DescriptorSet>>>,
-    framebuffers: HashMap<VkNonDispatchableHandle, Arc<Mutex<crate::pipeline::Framebuffer>>>,
+    instances: Table<VkDispatchableHandle, crate::instance::Instance>,
+    physical_devices: Table<VkDispatchableHandle, crate::physical_device::PhysicalDevice>,
+    logical_devices: Table<VkDispatchableHandle, crate::logical_device::LogicalDevice>,
+    queues: Table<VkDispatchableHandle, crate::queue::Queue>,
+    fences: Table<VkNonDispatchableHandle, crate::fence::Fence>,
+    semaphores: Table<VkNonDispatchableHandle, crate::semaphore::Semaphore>,
+    events: Table<VkNonDispatchableHandle, crate::event::Event>,
+    surfaces: Table<VkNonDispatchableHandle, crate::surface::Surface>,
+    swapchains: Table<VkNonDispatchableHandle, crate::swapchain::Swapchain>,
+    images: Table<VkNonDispatchableHandle, crate::image::Image>,
+    image_views: Table<VkNonDispatchableHandle, crate::image::ImageView>,
+    command_pools: Table<VkNonDispatchableHandle, crate::command_buffer::CommandPool>,
+    command_buffers: Table<VkDispatchableHandle, crate::command_buffer::CommandBuffer>,
+    memory_allocations: Table<VkNonDispatchableHandle, crate::memory::MemoryAllocation>,
+    samplers: Table<VkNonDispatchableHandle, crate::sampler::Sampler>,
+    buffers: Table<VkNonDispatchableHandle, crate::buffer::Buffer>,
+    buffer_views: Table<VkNonDispatchableHandle, crate::buffer::BufferView>,
+    descriptor_set_layouts: Table<VkNonDispatchableHandle, crate::descriptor::DescriptorSetLayout>,
+    pipeline_layouts: Table<VkNonDispatchableHandle, crate::pipeline::PipelineLayout>,
+    render_passes: Table<VkNonDispatchableHandle, crate::pipeline::RenderPass>,
+    shader_modules: Table<VkNonDispatchableHandle, crate::pipeline::ShaderModule>,
+    pipeline_caches: Table<VkNonDispatchableHandle, crate::pipeline::PipelineCache>,
+    pipelines: Table<VkNonDispatchableHandle, crate::pipeline::Pipeline>,
+    compute_pipelines: Table<VkNonDispatchableHandle, crate::pipeline::ComputePipeline>,
+    descriptor_pools: Table<VkNonDispatchableHandle, crate::descriptor::DescriptorPool>,
+    descriptor_sets: Table<VkNonDispatchableHandle, crate::descriptor::DescriptorSet>,
+    framebuffers: Table<VkNonDispatchableHandle, crate::pipeline::Framebuffer>,
+    query_pools: Table<VkNonDispatchableHandle, crate::query::QueryPool>,
+    debug_utils_messengers: Table<VkNonDispatchableHandle, crate::debug_utils::DebugUtilsMessenger>,
+    /// Names application gave objects along with their type, keyed by raw value of their handle.
+    object_names: RwLock<HashMap<u64, (VkObjectType, String)>>,
 }
 
 impl_dispatchable_trait!(crate::instance::Instance, instances);
 impl_dispatchable_trait!(crate::physical_device::PhysicalDevice, physical_devices);
 impl_dispatchable_trait!(crate::logical_device::LogicalDevice, logical_devices);
 impl_dispatchable_trait!(crate::queue::Queue, queues);
-impl_non_dispatchable_trait!(crate::fence::Fence, fences);
-impl_non_dispatchable_trait!(crate::semaphore::Semaphore, semaphores);
+impl_non_dispatchable_trait!(crate::fence::Fence, fences, logical_device);
+impl_non_dispatchable_trait!(crate::semaphore::Semaphore, semaphores, logical_device);
+impl_non_dispatchable_trait!(crate::event::Event, events, logical_device);
 impl_non_dispatchable_trait!(crate::surface::Surface, surfaces);
-impl_non_dispatchable_trait!(crate::swapchain::Swapchain, swapchains);
-impl_non_dispatchable_trait!(crate::image::Image, images);
-impl_non_dispatchable_trait!(crate::image::ImageView, image_views);
-impl_non_dispatchable_trait!(crate::command_buffer::CommandPool, command_pools);
+impl_non_dispatchable_trait!(crate::swapchain::Swapchain, swapchains, logical_device);
+impl_non_dispatchable_trait!(crate::image::Image, images, logical_device);
+impl_non_dispatchable_trait!(crate::image::ImageView, image_views, logical_device);
+impl_non_dispatchable_trait!(
+    crate::command_buffer::CommandPool,
+    command_pools,
+    logical_device
+);
 impl_dispatchable_trait!(crate::command_buffer::CommandBuffer, command_buffers);
-impl_non_dispatchable_trait!(crate::memory::MemoryAllocation, memory_allocations);
-impl_non_dispatchable_trait!(crate::sampler::Sampler, samplers);
-impl_non_dispatchable_trait!(crate::buffer::Buffer, buffers);
-impl_non_dispatchable_trait!(crate::buffer::BufferView, buffer_views);
+impl_non_dispatchable_trait!(
+    crate::memory::MemoryAllocation,
+    memory_allocations,
+    logical_device
+);
+impl_non_dispatchable_trait!(crate::sampler::Sampler, samplers, logical_device);
+impl_non_dispatchable_trait!(crate::buffer::Buffer, buffers, logical_device);
+impl_non_dispatchable_trait!(crate::buffer::BufferView, buffer_views, logical_device);
 impl_non_dispatchable_trait!(
     crate::descriptor::DescriptorSetLayout,
-    descriptor_set_layouts
+    descriptor_set_layouts,
+    logical_device
+);
+impl_non_dispatchable_trait!(
+    crate::pipeline::PipelineLayout,
+    pipeline_layouts,
+    logical_device
+);
+impl_non_dispatchable_trait!(crate::pipeline::RenderPass, render_passes, logical_device);
+impl_non_dispatchable_trait!(
+    crate::pipeline::ShaderModule,
+    shader_modules,
+    logical_device
+);
+impl_non_dispatchable_trait!(
+    crate::pipeline::PipelineCache,
+    pipeline_caches,
+    logical_device
+);
+impl_non_dispatchable_trait!(crate::pipeline::Pipeline, pipelines, logical_device);
+impl_non_dispatchable_trait!(
+    crate::pipeline::ComputePipeline,
+    compute_pipelines,
+    logical_device
+);
+impl_non_dispatchable_trait!(
+    crate::descriptor::DescriptorPool,
+    descriptor_pools,
+    logical_device
+);
+impl_non_dispatchable_trait!(
+    crate::descriptor::DescriptorSet,
+    descriptor_sets,
+    logical_device
+);
+impl_non_dispatchable_trait!(crate::pipeline::Framebuffer, framebuffers, logical_device);
+impl_non_dispatchable_trait!(crate::query::QueryPool, query_pools, logical_device);
+impl_non_dispatchable_trait!(
+    crate::debug_utils::DebugUtilsMessenger,
+    debug_utils_messengers
 );
-impl_non_dispatchable_trait!(crate::pipeline::PipelineLayout, pipeline_layouts);
-impl_non_dispatchable_trait!(crate::pipeline::RenderPass, render_passes);
-impl_non_dispatchable_trait!(crate::pipeline::ShaderModule, shader_modules);
-impl_non_dispatchable_trait!(crate::pipeline::PipelineCache, pipeline_caches);
-impl_non_dispatchable_trait!(crate::pipeline::Pipeline, pipelines);
-impl_non_dispatchable_trait!(crate::descriptor::DescriptorPool, descriptor_pools);
-impl_non_dispatchable_trait!(crate::descriptor::DescriptorSet, descriptor_sets);
-impl_non_dispatchable_trait!(crate::pipeline::Framebuffer, framebuffers);
 
 #[macro_export]
 macro_rules! impl_non_dispatchable_trait {
-    ($object:ty, $container:ident) => {
+    ($object:ty, $container:ident $(, $logical_device:ident)?) => {
         impl NonDispatchable for $object {
-            fn get_hash(context: &Context) -> &HashMap<VkNonDispatchableHandle, Arc<Mutex<Self>>> {
+            fn get_hash(context: &Context) -> &Table<VkNonDispatchableHandle, Self> {
                 &context.$container
             }
 
-            fn get_hash_mut(
-                context: &mut Context,
-            ) -> &mut HashMap<VkNonDispatchableHandle, Arc<Mutex<Self>>> {
-                &mut context.$container
-            }
-
             fn set_handle(&mut self, handle: VkNonDispatchableHandle) {
                 self.handle = handle;
             }
@@ -97,6 +138,12 @@ macro_rules! impl_non_dispatchable_trait {
             fn get_handle(&self) -> VkNonDispatchableHandle {
                 self.handle
             }
+
+            $(
+                fn logical_device(&self) -> Option<&Arc<Mutex<LogicalDevice>>> {
+                    Some(&self.$logical_device)
+                }
+            )?
         }
     };
 }
@@ -105,16 +152,10 @@ macro_rules! impl_non_dispatchable_trait {
 macro_rules! impl_dispatchable_trait {
     ($object:ty, $container:ident) => {
         impl Dispatchable for $object {
-            fn get_hash(context: &Context) -> &HashMap<VkDispatchableHandle, Arc<Mutex<Self>>> {
+            fn get_hash(context: &Context) -> &Table<VkDispatchableHandle, Self> {
                 &context.$container
             }
 
-            fn get_hash_mut(
-                context: &mut Context,
-            ) -> &mut HashMap<VkDispatchableHandle, Arc<Mutex<Self>>> {
-                &mut context.$container
-            }
-
             fn set_handle(&mut self, handle: VkDispatchableHandle) {
                 self.handle = handle;
             }
@@ -130,28 +171,271 @@ impl Context {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Destroys objects created by `logical_device`, returning how many were still alive.
+    pub(crate) fn drop_objects_of_device(logical_device: &Arc<Mutex<LogicalDevice>>) -> usize {
+        [
+            crate::query::QueryPool::drop_handles_of_device,
+            crate::descriptor::DescriptorSet::drop_handles_of_device,
+            crate::descriptor::DescriptorPool::drop_handles_of_device,
+            crate::descriptor::DescriptorSetLayout::drop_handles_of_device,
+            crate::pipeline::Framebuffer::drop_handles_of_device,
+            crate::pipeline::Pipeline::drop_handles_of_device,
+            crate::pipeline::ComputePipeline::drop_handles_of_device,
+            crate::pipeline::PipelineCache::drop_handles_of_device,
+            crate::pipeline::ShaderModule::drop_handles_of_device,
+            crate::pipeline::RenderPass::drop_handles_of_device,
+            crate::pipeline::PipelineLayout::drop_handles_of_device,
+            crate::buffer::BufferView::drop_handles_of_device,
+            crate::buffer::Buffer::drop_handles_of_device,
+            crate::sampler::Sampler::drop_handles_of_device,
+            crate::command_buffer::CommandPool::drop_handles_of_device,
+            crate::swapchain::Swapchain::drop_handles_of_device,
+            crate::image::ImageView::drop_handles_of_device,
+            crate::image::Image::drop_handles_of_device,
+            crate::memory::MemoryAllocation::drop_handles_of_device,
+            crate::event::Event::drop_handles_of_device,
+            crate::semaphore::Semaphore::drop_handles_of_device,
+            crate::fence::Fence::drop_handles_of_device,
+        ]
+        .iter()
+        .map(|drop_handles| drop_handles(logical_device))
+        .sum()
+    }
+
+    /// Names object of `object_type` with raw `handle`, or removes its name if `name` is `None`.
+    /// Returns `false` without naming anything if there is no such object.
+    pub fn set_object_name(object_type: VkObjectType, handle: u64, name: Option<String>) -> bool {
+        if !Self::object_exists(object_type, handle) {
+            warn!("Naming unknown {:?} object {:#x}", object_type, handle);
+            return false;
+        }
+        let mut object_names = CONTEXT.object_names.write();
+        match name {
+            Some(name) => object_names.insert(handle, (object_type, name)),
+            None => object_names.remove(&handle),
+        };
+        drop(object_names);
+        true
+    }
+
+    /// Returns name application gave object of raw `handle`, if any.
+    pub fn object_name(handle: u64) -> Option<String> {
+        let object_names = CONTEXT.object_names.read();
+        object_names.get(&handle).map(|(_, name)| name.clone())
+    }
+
+    fn object_exists(object_type: VkObjectType, handle: u64) -> bool {
+        use crate::*;
+        // Lookup only compares addresses of dispatchable handles, see `Dispatchable::from_handle`.
+        let dispatchable = VkDispatchableHandle(NonNull::new(std::ptr::without_provenance_mut(
+            handle as usize,
+        )));
+        let non_dispatchable = VkNonDispatchableHandle(NonZeroU64::new(handle));
+        match object_type {
+            VkObjectType::VK_OBJECT_TYPE_INSTANCE => {
+                instance::Instance::from_handle(dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_PHYSICAL_DEVICE => {
+                physical_device::PhysicalDevice::from_handle(dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_DEVICE => {
+                logical_device::LogicalDevice::from_handle(dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_QUEUE => queue::Queue::from_handle(dispatchable).is_some(),
+            VkObjectType::VK_OBJECT_TYPE_COMMAND_BUFFER => {
+                command_buffer::CommandBuffer::from_handle(dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_SEMAPHORE => {
+                semaphore::Semaphore::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_FENCE => {
+                fence::Fence::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_DEVICE_MEMORY => {
+                memory::MemoryAllocation::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_BUFFER => {
+                buffer::Buffer::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_IMAGE => {
+                image::Image::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_EVENT => {
+                event::Event::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_QUERY_POOL => {
+                query::QueryPool::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_BUFFER_VIEW => {
+                buffer::BufferView::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_IMAGE_VIEW => {
+                image::ImageView::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_SHADER_MODULE => {
+                pipeline::ShaderModule::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_PIPELINE_CACHE => {
+                pipeline::PipelineCache::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_PIPELINE_LAYOUT => {
+                pipeline::PipelineLayout::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_RENDER_PASS => {
+                pipeline::RenderPass::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_PIPELINE => {
+                pipeline::Pipeline::from_handle(non_dispatchable).is_some()
+                    || pipeline::ComputePipeline::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_DESCRIPTOR_SET_LAYOUT => {
+                descriptor::DescriptorSetLayout::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_SAMPLER => {
+                sampler::Sampler::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_DESCRIPTOR_POOL => {
+                descriptor::DescriptorPool::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_DESCRIPTOR_SET => {
+                descriptor::DescriptorSet::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_FRAMEBUFFER => {
+                pipeline::Framebuffer::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_COMMAND_POOL => {
+                command_buffer::CommandPool::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_SURFACE_KHR => {
+                surface::Surface::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_SWAPCHAIN_KHR => {
+                swapchain::Swapchain::from_handle(non_dispatchable).is_some()
+            }
+            VkObjectType::VK_OBJECT_TYPE_DEBUG_UTILS_MESSENGER_EXT => {
+                debug_utils::DebugUtilsMessenger::from_handle(non_dispatchable).is_some()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Handle displayed along with name application gave its object, if any.
+#[derive(Debug, Copy, Clone)]
+pub struct Named(u64);
+
+impl From<VkDispatchableHandle> for Named {
+    fn from(handle: VkDispatchableHandle) -> Self {
+        Self(handle.0.map_or(0, |x| x.as_ptr().addr() as u64))
+    }
+}
+
+impl From<VkNonDispatchableHandle> for Named {
+    fn from(handle: VkNonDispatchableHandle) -> Self {
+        Self(handle.0.map_or(0, NonZeroU64::get))
+    }
+}
+
+impl Named {
+    /// Runs `f`, returning its result along with handles displayed on this thread meanwhile, so
+    /// that messages can report which objects they are about.
+    pub fn collect<R>(f: impl FnOnce() -> R) -> (R, Vec<Self>) {
+        let outer = DISPLAYED.replace(Some(Vec::new()));
+        let result = f();
+        let displayed = DISPLAYED.replace(outer).unwrap_or_default();
+        (result, displayed.into_iter().map(Self).collect())
+    }
+
+    pub const fn raw(self) -> u64 {
+        self.0
+    }
+
+    /// Returns type and name application gave object, if any.
+    pub fn name(self) -> Option<(VkObjectType, String)> {
+        let object_names = CONTEXT.object_names.read();
+        object_names.get(&self.0).cloned()
+    }
+}
+
+impl std::fmt::Display for Named {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        DISPLAYED.with_borrow_mut(|displayed| {
+            if let Some(displayed) = displayed.as_mut().filter(|x| !x.contains(&self.0)) {
+                displayed.push(self.0);
+            }
+        });
+        write!(f, "{:#x}", self.0)?;
+        if let Some(name) = Context::object_name(self.0) {
+            write!(f, " \"{}\"", name)?;
+        }
+        Ok(())
+    }
 }
 
 lazy_static! {
-    static ref CONTEXT: RwLock<Context> = RwLock::new(Context::new());
+    static ref CONTEXT: Context = Context::new();
+}
+
+thread_local! {
+    /// Raw handles displayed while `Named::collect` runs on this thread.
+    static DISPLAYED: RefCell<Option<Vec<u64>>> = const { RefCell::new(None) };
 }
 
 static ID_COUNTER: AtomicU64 = AtomicU64::new(1);
 
+static HANDLE_IDS: Mutex<HandleIds> = Mutex::new(HandleIds::new(1));
+
+/// Number of ids of destroyed objects held back from reuse, so that stale handles of recently
+/// destroyed objects don't refer to new ones.
+const HANDLE_ID_REUSE_DELAY: usize = 1024;
+
+/// Allocator of non-dispatchable handles, recycling ids of destroyed objects so that handles stay
+/// unique and non-null however many objects are created over time.
+#[derive(Debug)]
+struct HandleIds {
+    /// Lowest id never handed out, `None` once all were.
+    next: Option<NonZeroU64>,
+    /// Ids of destroyed objects, oldest first.
+    free: VecDeque<NonZeroU64>,
+}
+
+impl HandleIds {
+    const fn new(next: u64) -> Self {
+        Self {
+            next: NonZeroU64::new(next),
+            free: VecDeque::new(),
+        }
+    }
+
+    /// Returns unused id, `None` if all ids are in use.
+    fn allocate(&mut self) -> Option<NonZeroU64> {
+        if self.free.len() > HANDLE_ID_REUSE_DELAY || self.next.is_none() {
+            if let Some(id) = self.free.pop_front() {
+                return Some(id);
+            }
+        }
+        let id = self.next?;
+        self.next = id.checked_add(1);
+        Some(id)
+    }
+
+    fn release(&mut self, id: NonZeroU64) {
+        self.free.push_back(id);
+    }
+}
+
 pub trait Dispatchable<T = Self>
 where
     Self: Sized + Send + Sync,
 {
-    fn get_hash(context: &Context) -> &HashMap<VkDispatchableHandle, Arc<Mutex<Self>>>;
-
-    fn get_hash_mut(context: &mut Context) -> &mut HashMap<VkDispatchableHandle, Arc<Mutex<Self>>>;
+    fn get_hash(context: &Context) -> &Table<VkDispatchableHandle, Self>;
 
     fn set_handle(&mut self, handle: VkDispatchableHandle);
 
     fn get_handle(&self) -> VkDispatchableHandle;
 
-    fn register_object(self) -> VkDispatchableHandle {
-        let mut context: RwLockWriteGuard<'_, _> = CONTEXT.write();
+    fn register_object(mut self) -> VkDispatchableHandle {
         let handle = VkDispatchableHandle(NonNull::new(Box::leak(Box::new(
             VkDispatchableHandleInner {
                 loader_data: VkLoaderData {
@@ -160,22 +444,48 @@ where
                 key: ID_COUNTER.fetch_add(1, Ordering::Relaxed),
             },
         ))));
-        let object = Arc::new(Mutex::new(self));
-        Self::get_hash_mut(&mut context).insert(handle, object.clone());
-        object.lock().set_handle(handle);
+        self.set_handle(handle);
+        Self::get_hash(&CONTEXT)
+            .write()
+            .insert(handle, Arc::new(Mutex::new(self)));
         handle
     }
 
+    /// Returns object of `handle`, `None` if it is null or destroyed.
+    ///
+    /// Handles are pointers returned by `register_object` and keep their provenance on the way
+    /// through the application, while lookup compares their addresses only and never dereferences
+    /// them.
     fn from_handle(handle: VkDispatchableHandle) -> Option<Arc<Mutex<Self>>> {
-        let context = CONTEXT.read();
-        Self::get_hash(&context).get(&handle).cloned()
+        Self::get_hash(&CONTEXT).read().get(&handle).cloned()
+    }
+
+    /// Returns object of `handle`, failing with `InvalidHandle` where `from_handle` returns `None`.
+    fn lookup(handle: VkDispatchableHandle) -> Result<Arc<Mutex<Self>>, RuntimeError> {
+        Self::from_handle(handle).ok_or_else(|| {
+            error!(
+                "Invalid {} handle {}",
+                std::any::type_name::<Self>(),
+                Named::from(handle)
+            );
+            RuntimeError::InvalidHandle
+        })
     }
 
     fn drop_handle(handle: VkDispatchableHandle) {
-        let mut context = CONTEXT.write();
-        Self::get_hash_mut(&mut context).remove(&handle);
-        let inner = unsafe { Box::from_raw(handle.0.expect("null handle").as_ptr()) };
-        drop(inner);
+        // Only handles of registered objects are freed, destroying handle twice or one of another
+        // type must not free its allocation twice.
+        let removed = Self::get_hash(&CONTEXT).write().remove(&handle);
+        if let (Some(_), Some(ptr)) = (removed, handle.0) {
+            trace!(
+                "destroy {} {}",
+                std::any::type_name::<Self>(),
+                Named::from(handle)
+            );
+            CONTEXT.object_names.write().remove(&Named::from(handle).0);
+            let inner = unsafe { Box::from_raw(ptr.as_ptr()) };
+            drop(inner);
+        }
     }
 }
 
@@ -183,32 +493,354 @@ pub trait NonDispatchable<T = Self>
 where
     Self: Sized + Send + Sync,
 {
-    fn get_hash(context: &Context) -> &HashMap<VkNonDispatchableHandle, Arc<Mutex<Self>>>;
-
-    fn get_hash_mut(
-        context: &mut Context,
-    ) -> &mut HashMap<VkNonDispatchableHandle, Arc<Mutex<Self>>>;
+    fn get_hash(context: &Context) -> &Table<VkNonDispatchableHandle, Self>;
 
     fn set_handle(&mut self, handle: VkNonDispatchableHandle);
     fn get_handle(&self) -> VkNonDispatchableHandle;
 
-    fn register_object(self) -> VkNonDispatchableHandle {
-        let mut context: RwLockWriteGuard<'_, _> = CONTEXT.write();
-        let handle =
-            VkNonDispatchableHandle(NonZeroU64::new(ID_COUNTER.fetch_add(1, Ordering::Relaxed)));
-        let object = Arc::new(Mutex::new(self));
-        Self::get_hash_mut(&mut context).insert(handle, object.clone());
-        object.lock().set_handle(handle);
+    fn register_object(mut self) -> VkNonDispatchableHandle {
+        // Running out of ids takes more live objects than fit in memory.
+        let id = HANDLE_IDS
+            .lock()
+            .allocate()
+            .unwrap_or_else(|| unreachable!("all handle ids in use"));
+        let handle = VkNonDispatchableHandle(Some(id));
+        self.set_handle(handle);
+        Self::get_hash(&CONTEXT)
+            .write()
+            .insert(handle, Arc::new(Mutex::new(self)));
         handle
     }
 
+    /// Returns object of `handle`, `None` if it is null, destroyed or of object of other type.
     fn from_handle(handle: VkNonDispatchableHandle) -> Option<Arc<Mutex<Self>>> {
-        let context = CONTEXT.read();
-        Self::get_hash(&context).get(&handle).cloned()
+        Self::get_hash(&CONTEXT).read().get(&handle).cloned()
+    }
+
+    /// Returns object of `handle`, failing with `InvalidHandle` where `from_handle` returns `None`.
+    fn lookup(handle: VkNonDispatchableHandle) -> Result<Arc<Mutex<Self>>, RuntimeError> {
+        Self::from_handle(handle).ok_or_else(|| {
+            error!(
+                "Invalid {} handle {}",
+                std::any::type_name::<Self>(),
+                Named::from(handle)
+            );
+            RuntimeError::InvalidHandle
+        })
+    }
+
+    /// Returns all objects of this type that weren't destroyed yet.
+    fn objects() -> Vec<Arc<Mutex<Self>>> {
+        Self::get_hash(&CONTEXT).read().values().cloned().collect()
+    }
+
+    /// Returns device object was created by, `None` for objects not owned by device.
+    fn logical_device(&self) -> Option<&Arc<Mutex<LogicalDevice>>> {
+        None
+    }
+
+    /// Destroys objects of this type created by `logical_device`, warning about each of them and
+    /// returning their count.
+    fn drop_handles_of_device(logical_device: &Arc<Mutex<LogicalDevice>>) -> usize {
+        let objects = Self::get_hash(&CONTEXT).read().clone();
+        let handles = objects
+            .into_iter()
+            .filter(|(_, object)| {
+                let object = object.lock();
+                object
+                    .logical_device()
+                    .is_some_and(|x| Arc::ptr_eq(x, logical_device))
+            })
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+        for &handle in &handles {
+            warn!(
+                "VUID-vkDestroyDevice-device-05137: {} {} not destroyed before its device",
+                std::any::type_name::<Self>(),
+                Named::from(handle)
+            );
+            Self::drop_handle(handle);
+        }
+        handles.len()
     }
 
     fn drop_handle(handle: VkNonDispatchableHandle) {
-        let mut context = CONTEXT.write();
-        Self::get_hash_mut(&mut context).remove(&handle);
+        // Only ids of registered objects go back to allocator, destroying handle twice or one of
+        // another type must not hand out its id twice.
+        let removed = Self::get_hash(&CONTEXT).write().remove(&handle);
+        if let (Some(_), Some(id)) = (removed, handle.0) {
+            trace!(
+                "destroy {} {}",
+                std::any::type_name::<Self>(),
+                Named::from(handle)
+            );
+            CONTEXT.object_names.write().remove(&id.get());
+            HANDLE_IDS.lock().release(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fence::Fence;
+    use crate::instance::Instance;
+    use crate::logical_device::tests::create_logical_device;
+    use crate::semaphore::Semaphore;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    const THREADS: usize = 8;
+    const OBJECTS_PER_THREAD: usize = 100;
+
+    #[test]
+    fn register_and_look_up_objects_from_many_threads() {
+        let logical_device = create_logical_device();
+        let threads = (0..THREADS)
+            .map(|i| {
+                let logical_device = logical_device.clone();
+                thread::spawn(move || {
+                    let fence_create_info = VkFenceCreateInfo {
+                        sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+                        pNext: None,
+                        flags: 0,
+                    };
+                    let semaphore_create_info = VkSemaphoreCreateInfo {
+                        sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+                        pNext: None,
+                        flags: 0,
+                    };
+                    // Threads alternate between object types, looking up each object right
+                    // after creating it.
+                    (0..OBJECTS_PER_THREAD)
+                        .map(|_| {
+                            if i % 2 == 0 {
+                                let Ok(handle) =
+                                    Fence::create(logical_device.clone(), &fence_create_info)
+                                else {
+                                    unreachable!()
+                                };
+                                assert!(Fence::from_handle(handle).is_some());
+                                handle
+                            } else {
+                                let Ok(handle) = Semaphore::create(
+                                    logical_device.clone(),
+                                    &semaphore_create_info,
+                                ) else {
+                                    unreachable!()
+                                };
+                                assert!(Semaphore::from_handle(handle).is_some());
+                                handle
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut handles = vec![];
+        for thread in threads {
+            let Ok(thread_handles) = thread.join() else {
+                unreachable!()
+            };
+            handles.extend(thread_handles);
+        }
+        handles.sort_by_key(|handle| handle.0);
+        handles.dedup();
+        assert_eq!(handles.len(), THREADS * OBJECTS_PER_THREAD);
+
+        let (fences, semaphores): (Vec<_>, Vec<_>) = handles
+            .iter()
+            .partition(|&&handle| Fence::from_handle(handle).is_some());
+        assert_eq!(fences.len(), THREADS / 2 * OBJECTS_PER_THREAD);
+        assert_eq!(semaphores.len(), THREADS / 2 * OBJECTS_PER_THREAD);
+        for &handle in &fences {
+            Fence::drop_handle(handle);
+            assert!(Fence::from_handle(handle).is_none());
+        }
+        for &handle in &semaphores {
+            assert!(Semaphore::from_handle(handle).is_some());
+            Semaphore::drop_handle(handle);
+        }
+    }
+
+    #[test]
+    fn drop_handle_keeps_other_dispatchable_objects() {
+        let handles = (0..THREADS * OBJECTS_PER_THREAD)
+            .map(|_| {
+                let Ok(handle) = Instance::create() else {
+                    unreachable!()
+                };
+                handle
+            })
+            .collect::<Vec<_>>();
+        // Objects are dropped out of creation order, each removal leaving the rest in place.
+        let (dropped, kept): (Vec<_>, Vec<_>) =
+            handles.iter().enumerate().partition(|(i, _)| i % 2 == 0);
+        for (_, &handle) in dropped.into_iter().rev() {
+            Instance::drop_handle(handle);
+        }
+        for (_, &handle) in kept {
+            let Some(instance) = Instance::from_handle(handle) else {
+                unreachable!()
+            };
+            assert_eq!(instance.lock().get_handle(), handle);
+            Instance::drop_handle(handle);
+        }
+    }
+
+    #[test]
+    fn drop_handle_keeps_objects_referenced_elsewhere_alive() {
+        let Ok(instance_handle) = Instance::create() else {
+            unreachable!()
+        };
+        let fence_create_info = VkFenceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let Ok(fence_handle) = Fence::create(create_logical_device(), &fence_create_info) else {
+            unreachable!()
+        };
+        let (Some(instance), Some(fence)) = (
+            Instance::from_handle(instance_handle),
+            Fence::from_handle(fence_handle),
+        ) else {
+            unreachable!()
+        };
+        let (weak_instance, weak_fence) = (Arc::downgrade(&instance), Arc::downgrade(&fence));
+
+        // Destroying handles only drops references of context, objects living on as long as
+        // anything else refers to them.
+        Instance::drop_handle(instance_handle);
+        Fence::drop_handle(fence_handle);
+        assert!(Instance::from_handle(instance_handle).is_none());
+        assert!(Fence::from_handle(fence_handle).is_none());
+        assert!(weak_instance.upgrade().is_some() && weak_fence.upgrade().is_some());
+        drop((instance, fence));
+        assert!(weak_instance.upgrade().is_none() && weak_fence.upgrade().is_none());
+    }
+
+    #[test]
+    fn dispatchable_handles_round_trip() {
+        let handles = (0..OBJECTS_PER_THREAD)
+            .map(|_| {
+                let Ok(handle) = Instance::create() else {
+                    unreachable!()
+                };
+                handle
+            })
+            .collect::<Vec<_>>();
+        for &handle in &handles {
+            let Some(instance) = Instance::from_handle(handle) else {
+                unreachable!()
+            };
+            assert_eq!(instance.lock().get_handle(), handle);
+        }
+        assert!(Instance::from_handle(VkDispatchableHandle(None)).is_none());
+        for &handle in &handles {
+            Instance::drop_handle(handle);
+            assert!(Instance::from_handle(handle).is_none());
+        }
+    }
+
+    #[test]
+    fn lookup_of_invalid_handle_fails_without_panicking() {
+        let fence_create_info = VkFenceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let Ok(handle) = Fence::create(create_logical_device(), &fence_create_info) else {
+            unreachable!()
+        };
+        assert!(Fence::lookup(handle).is_ok());
+        Fence::drop_handle(handle);
+
+        let Err(err) = Fence::lookup(handle) else {
+            unreachable!()
+        };
+        assert_eq!(err, RuntimeError::InvalidHandle);
+        assert_eq!(err.to_vk_result(), VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        assert_eq!(
+            Semaphore::lookup(VK_NULL_HANDLE).err(),
+            Some(RuntimeError::InvalidHandle)
+        );
+    }
+
+    #[test]
+    fn destroying_dispatchable_handle_twice_does_not_free_it_again() {
+        let Ok(handle) = Instance::create() else {
+            unreachable!()
+        };
+        Instance::drop_handle(handle);
+        Instance::drop_handle(handle);
+        Instance::drop_handle(VkDispatchableHandle(None));
+        assert!(Instance::from_handle(handle).is_none());
+        assert_eq!(
+            Instance::lookup(handle).err(),
+            Some(RuntimeError::InvalidHandle)
+        );
+    }
+
+    #[test]
+    fn locked_table_does_not_block_other_object_types() {
+        let logical_device = create_logical_device();
+        let fences = Fence::get_hash(&CONTEXT).write();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let semaphore_create_info = VkSemaphoreCreateInfo {
+                sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+                pNext: None,
+                flags: 0,
+            };
+            let Ok(handle) = Semaphore::create(logical_device, &semaphore_create_info) else {
+                unreachable!()
+            };
+            let _ = sender.send(Semaphore::from_handle(handle).is_some());
+            Semaphore::drop_handle(handle);
+        });
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(10)), Ok(true));
+        drop(fences);
+    }
+
+    #[test]
+    fn handle_ids_are_unique_and_non_null_across_exhaustion() {
+        let mut ids = HandleIds::new(u64::MAX - 3);
+        let mut live = (0..4)
+            .map(|_| ids.allocate().unwrap_or_else(|| unreachable!()))
+            .collect::<Vec<_>>();
+        assert_eq!(live.last().map(|id| id.get()), Some(u64::MAX));
+        // Every id being in use, none is left to hand out.
+        assert_eq!(ids.allocate(), None);
+
+        // Once never handed out ones are exhausted, ids of destroyed objects are reused right away.
+        for id in live.drain(..2) {
+            ids.release(id);
+        }
+        for _ in 0..2 {
+            live.push(ids.allocate().unwrap_or_else(|| unreachable!()));
+        }
+        assert_eq!(ids.allocate(), None);
+        let mut unique = live.iter().map(|id| id.get()).collect::<Vec<_>>();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique, [u64::MAX - 3, u64::MAX - 2, u64::MAX - 1, u64::MAX]);
+    }
+
+    #[test]
+    fn handle_ids_are_reused_after_delay() {
+        let mut ids = HandleIds::new(1);
+        let allocated = (0..=HANDLE_ID_REUSE_DELAY)
+            .map(|_| ids.allocate().unwrap_or_else(|| unreachable!()))
+            .collect::<Vec<_>>();
+        for &id in &allocated[..HANDLE_ID_REUSE_DELAY] {
+            ids.release(id);
+            assert_ne!(ids.allocate(), Some(id));
+        }
+        // Going over the delay, oldest destroyed id is reused first.
+        ids.release(allocated[HANDLE_ID_REUSE_DELAY]);
+        assert_eq!(ids.allocate(), Some(allocated[0]));
     }
 }
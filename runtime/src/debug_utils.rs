@@ -0,0 +1,400 @@
+//! VK_EXT_debug_utils
+
+use crate::context::{Named, NonDispatchable};
+use crate::instance::Instance;
+use headers::vk_decls::*;
+use lazy_static::lazy_static;
+use log::*;
+use parking_lot::Mutex;
+use std::ffi::{c_void, CString};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+type Severity = VkDebugUtilsMessageSeverityFlagBitsEXT;
+type MessageType = VkDebugUtilsMessageTypeFlagBitsEXT;
+
+/// Messenger passing messages of requested severities and types to application callback.
+#[derive(Debug)]
+pub struct DebugUtilsMessenger {
+    pub(crate) handle: VkNonDispatchableHandle,
+    instance: Arc<Mutex<Instance>>,
+    message_severity: VkDebugUtilsMessageSeverityFlagsEXT,
+    message_type: VkDebugUtilsMessageTypeFlagsEXT,
+    user_callback: PFN_vkDebugUtilsMessengerCallbackEXT,
+    user_data: Option<NonNull<c_void>>,
+}
+
+// SAFETY: User data is only ever passed back to callback, which application must make callable
+// from any thread that issues Vulkan commands.
+unsafe impl Send for DebugUtilsMessenger {}
+unsafe impl Sync for DebugUtilsMessenger {}
+
+impl DebugUtilsMessenger {
+    pub fn create(
+        instance: Arc<Mutex<Instance>>,
+        create_info: &VkDebugUtilsMessengerCreateInfoEXT,
+    ) -> VkNonDispatchableHandle {
+        info!("new DebugUtilsMessenger");
+        let handle = VK_NULL_HANDLE;
+
+        let messenger = Self {
+            handle,
+            instance,
+            message_severity: create_info.messageSeverity,
+            message_type: create_info.messageType,
+            user_callback: create_info.pfnUserCallback,
+            user_data: create_info.pUserData,
+        };
+        let handle = messenger.register_object();
+        update_max_log_level();
+        handle
+    }
+
+    /// Destroys messenger of `handle`, which is not called anymore once this returns.
+    pub fn destroy(handle: VkNonDispatchableHandle) {
+        Self::drop_handle(handle);
+        update_max_log_level();
+    }
+
+    /// Calls callbacks of messengers of `instance`, or of all instances if `None`, that requested
+    /// messages of `message_severity` and any of `message_types`.
+    pub fn submit_message(
+        instance: Option<&Arc<Mutex<Instance>>>,
+        message_severity: VkDebugUtilsMessageSeverityFlagBitsEXT,
+        message_types: VkDebugUtilsMessageTypeFlagsEXT,
+        callback_data: &VkDebugUtilsMessengerCallbackDataEXT,
+    ) {
+        for messenger in Self::objects() {
+            // Callback isn't called with messenger locked, so that it can't deadlock when
+            // callback triggers further messages.
+            let messenger = messenger.lock();
+            let requested = instance.is_none_or(|x| Arc::ptr_eq(x, &messenger.instance))
+                && messenger.message_severity & message_severity.0 != 0
+                && messenger.message_type & message_types != 0;
+            let (user_callback, user_data) = (messenger.user_callback, messenger.user_data);
+            drop(messenger);
+            let Some(user_callback) = user_callback.filter(|_| requested) else {
+                continue;
+            };
+            // SPEC: The callback returns a VkBool32, which is interpreted in a layer-specified
+            // manner. The application should always return VK_FALSE.
+            let _ = unsafe {
+                user_callback(
+                    message_severity,
+                    message_types,
+                    Some(NonNull::from(callback_data)),
+                    user_data,
+                )
+            };
+        }
+    }
+}
+
+/// Logger turning records of driver into messages of debug utils messengers.
+#[derive(Debug)]
+struct MessengerLogger;
+
+impl Log for MessengerLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message_severity = match record.level() {
+            Level::Error => Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_ERROR_BIT_EXT,
+            Level::Warn => Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_WARNING_BIT_EXT,
+            Level::Info => Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_INFO_BIT_EXT,
+            Level::Debug | Level::Trace => {
+                Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_VERBOSE_BIT_EXT
+            }
+        };
+        // Errors are reported for invalid use of API.
+        let message_types = if record.level() == Level::Error {
+            MessageType::VK_DEBUG_UTILS_MESSAGE_TYPE_VALIDATION_BIT_EXT
+        } else {
+            MessageType::VK_DEBUG_UTILS_MESSAGE_TYPE_GENERAL_BIT_EXT
+        };
+        // Interior nul bytes can't be passed to application, so message is cut at first of them.
+        let c_string = |string: String| {
+            let bytes = string.into_bytes();
+            let len = bytes.iter().position(|&x| x == 0).unwrap_or(bytes.len());
+            CString::new(&bytes[..len]).unwrap_or_default()
+        };
+        let message_id_name = c_string(record.target().to_string());
+        let (message, objects) = Named::collect(|| c_string(record.args().to_string()));
+        let names = objects
+            .iter()
+            .map(|object| {
+                object
+                    .name()
+                    .map(|(object_type, name)| (object_type, c_string(name)))
+            })
+            .collect::<Vec<_>>();
+        let objects = objects
+            .iter()
+            .zip(&names)
+            .map(|(object, name)| VkDebugUtilsObjectNameInfoEXT {
+                sType: VkStructureType::VK_STRUCTURE_TYPE_DEBUG_UTILS_OBJECT_NAME_INFO_EXT,
+                pNext: None,
+                objectType: name
+                    .as_ref()
+                    .map_or(VkObjectType::VK_OBJECT_TYPE_UNKNOWN, |(object_type, _)| {
+                        *object_type
+                    }),
+                objectHandle: object.raw(),
+                pObjectName: name
+                    .as_ref()
+                    .and_then(|(_, name)| NonNull::new(name.as_ptr().cast_mut())),
+            })
+            .collect::<Vec<_>>();
+        let callback_data = VkDebugUtilsMessengerCallbackDataEXT {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CALLBACK_DATA_EXT,
+            pNext: None,
+            flags: 0,
+            pMessageIdName: NonNull::new(message_id_name.as_ptr().cast_mut()),
+            messageIdNumber: 0,
+            pMessage: NonNull::new(message.as_ptr().cast_mut()),
+            queueLabelCount: 0,
+            pQueueLabels: None,
+            cmdBufLabelCount: 0,
+            pCmdBufLabels: None,
+            objectCount: objects.len() as u32,
+            pObjects: NonNull::new(objects.as_ptr().cast_mut()).filter(|_| !objects.is_empty()),
+        };
+        DebugUtilsMessenger::submit_message(
+            None,
+            message_severity,
+            message_types.into(),
+            &callback_data,
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: MessengerLogger = MessengerLogger;
+
+lazy_static! {
+    /// Whether records are passed to messengers, which they aren't if other logger was installed
+    /// first.
+    static ref LOGGER_INSTALLED: bool = set_logger(&LOGGER).is_ok();
+}
+
+/// Limits records logged to most verbose severity requested by any messenger, so that records
+/// nobody listens to aren't formatted.
+fn update_max_log_level() {
+    if !*LOGGER_INSTALLED {
+        return;
+    }
+    let message_severity = DebugUtilsMessenger::objects()
+        .iter()
+        .fold(0, |severity, messenger| {
+            severity | messenger.lock().message_severity
+        });
+    let has_severity = |bit: Severity| message_severity & bit.0 != 0;
+    let level = if has_severity(Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_VERBOSE_BIT_EXT) {
+        LevelFilter::Trace
+    } else if has_severity(Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_INFO_BIT_EXT) {
+        LevelFilter::Info
+    } else if has_severity(Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_WARNING_BIT_EXT) {
+        LevelFilter::Warn
+    } else if has_severity(Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_ERROR_BIT_EXT) {
+        LevelFilter::Error
+    } else {
+        LevelFilter::Off
+    };
+    set_max_level(level);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+    use crate::context::{Context, Dispatchable};
+    use crate::fence::Fence;
+    use crate::logical_device::tests::create_logical_device;
+    use crate::logical_device::LogicalDevice;
+    use std::ffi::CStr;
+    use std::num::NonZeroU64;
+
+    /// Message passed to callback along with type, raw handle and name of objects it is about.
+    #[derive(Debug)]
+    struct Message {
+        severity: Severity,
+        text: String,
+        objects: Vec<(VkObjectType, u64, Option<String>)>,
+    }
+
+    type Messages = Mutex<Vec<Message>>;
+
+    unsafe extern "C" fn record_message(
+        message_severity: Severity,
+        _message_types: VkDebugUtilsMessageTypeFlagsEXT,
+        callback_data: Option<NonNull<VkDebugUtilsMessengerCallbackDataEXT>>,
+        user_data: Option<NonNull<c_void>>,
+    ) -> VkBool32 {
+        let (Some(callback_data), Some(user_data)) = (callback_data, user_data) else {
+            unreachable!()
+        };
+        let callback_data = unsafe { callback_data.as_ref() };
+        let Some(text) = callback_data.pMessage else {
+            unreachable!()
+        };
+        let text = unsafe { CStr::from_ptr(text.as_ptr()) };
+        let objects = callback_data.pObjects.map_or(&[][..], |objects| unsafe {
+            std::slice::from_raw_parts(objects.as_ptr(), callback_data.objectCount as usize)
+        });
+        let objects = objects
+            .iter()
+            .map(|object| {
+                let name = object.pObjectName.map(|name| {
+                    let name = unsafe { CStr::from_ptr(name.as_ptr()) };
+                    name.to_string_lossy().into_owned()
+                });
+                (object.objectType, object.objectHandle, name)
+            })
+            .collect();
+        let messages = unsafe { user_data.cast::<Messages>().as_ref() };
+        messages.lock().push(Message {
+            severity: message_severity,
+            text: text.to_string_lossy().into_owned(),
+            objects,
+        });
+        VK_FALSE
+    }
+
+    /// Destroys device while its fence is still alive, which driver warns about.
+    fn destroy_device_with_fence() {
+        let logical_device = create_logical_device();
+        let handle = logical_device.lock().get_handle();
+        let fence_create_info = VkFenceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let Ok(_) = Fence::create(logical_device, &fence_create_info) else {
+            unreachable!()
+        };
+        LogicalDevice::destroy(handle);
+    }
+
+    /// Creates messenger of warnings and errors recording them into returned messages.
+    fn create_messenger() -> (VkNonDispatchableHandle, &'static Messages) {
+        let Ok(instance) = Instance::create() else {
+            unreachable!()
+        };
+        let Some(instance) = Instance::from_handle(instance) else {
+            unreachable!()
+        };
+        // Records of other threads may still be delivered while messenger is destroyed.
+        let messages: &'static Messages = Box::leak(Box::default());
+        let create_info = VkDebugUtilsMessengerCreateInfoEXT {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT,
+            pNext: None,
+            flags: 0,
+            messageSeverity: (Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_WARNING_BIT_EXT
+                | Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_ERROR_BIT_EXT)
+                .into(),
+            messageType: (MessageType::VK_DEBUG_UTILS_MESSAGE_TYPE_GENERAL_BIT_EXT
+                | MessageType::VK_DEBUG_UTILS_MESSAGE_TYPE_VALIDATION_BIT_EXT)
+                .into(),
+            pfnUserCallback: Some(record_message),
+            pUserData: Some(NonNull::from(messages).cast()),
+        };
+        (
+            DebugUtilsMessenger::create(instance, &create_info),
+            messages,
+        )
+    }
+
+    #[test]
+    fn messenger_receives_warnings_until_destroyed() {
+        let (messenger, messages) = create_messenger();
+        let is_device_warning = |message: &Message| {
+            message.severity == Severity::VK_DEBUG_UTILS_MESSAGE_SEVERITY_WARNING_BIT_EXT
+                && message.text.contains("VUID-vkDestroyDevice-device-05137")
+        };
+
+        destroy_device_with_fence();
+        assert!(messages.lock().iter().any(is_device_warning));
+
+        DebugUtilsMessenger::destroy(messenger);
+        messages.lock().clear();
+        destroy_device_with_fence();
+        assert!(!messages.lock().iter().any(is_device_warning));
+    }
+
+    #[test]
+    fn object_name_appears_in_messages() {
+        let (messenger, messages) = create_messenger();
+        let logical_device = create_logical_device();
+        let device = logical_device.lock().get_handle();
+        let buffer = Buffer::create(logical_device, 16, 0, 0);
+        let Some(handle) = buffer.0.map(NonZeroU64::get) else {
+            unreachable!()
+        };
+        let object_type = VkObjectType::VK_OBJECT_TYPE_BUFFER;
+        assert!(Context::set_object_name(
+            object_type,
+            handle,
+            Some("vertex buffer".to_string())
+        ));
+        assert_eq!(
+            Context::object_name(handle).as_deref(),
+            Some("vertex buffer")
+        );
+
+        LogicalDevice::destroy(device);
+        DebugUtilsMessenger::destroy(messenger);
+        let expected = format!("{:#x} \"vertex buffer\" not destroyed", handle);
+        assert!(messages.lock().iter().any(|message| {
+            message.text.contains(&expected)
+                && message.objects == [(object_type, handle, Some("vertex buffer".to_string()))]
+        }));
+        assert_eq!(Context::object_name(handle), None);
+        // Handle of destroyed buffer may already be reused by other test, so unknown one is named.
+        assert!(!Context::set_object_name(
+            object_type,
+            u64::MAX,
+            Some("unknown".to_string())
+        ));
+    }
+
+    #[test]
+    fn messages_report_named_objects_they_are_about() {
+        let (messenger, messages) = create_messenger();
+        let logical_device = create_logical_device();
+        let device = logical_device.lock().get_handle();
+        let fence_create_info = VkFenceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let Ok(fence) = Fence::create(logical_device.clone(), &fence_create_info) else {
+            unreachable!()
+        };
+        let Some(handle) = fence.0.map(NonZeroU64::get) else {
+            unreachable!()
+        };
+        let object_type = VkObjectType::VK_OBJECT_TYPE_FENCE;
+        assert!(Context::set_object_name(
+            object_type,
+            handle,
+            Some("frame fence".to_string())
+        ));
+        let Some(fence) = Fence::from_handle(fence) else {
+            unreachable!()
+        };
+
+        logical_device.lock().reset_fences(vec![fence]);
+        DebugUtilsMessenger::destroy(messenger);
+        assert!(messages.lock().iter().any(|message| {
+            message.text.contains("VUID-vkResetFences-pFences-01123")
+                && message.objects == [(object_type, handle, Some("frame fence".to_string()))]
+        }));
+        LogicalDevice::destroy(device);
+    }
+}
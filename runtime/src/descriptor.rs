@@ -1,8 +1,13 @@
 //! Descriptors
 
+use crate::buffer::Buffer;
 use crate::context::NonDispatchable;
+use crate::error::RuntimeError;
+use crate::image::ImageView;
 use crate::logical_device::LogicalDevice;
+use crate::sampler::Sampler;
 
+use common::graphics;
 use headers::vk_decls::*;
 use log::*;
 use parking_lot::Mutex;
@@ -13,7 +18,19 @@ use std::sync::Arc;
 #[derive(Debug)]
 pub struct DescriptorSetLayout {
     pub(crate) handle: VkNonDispatchableHandle,
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
+    flags: VkDescriptorSetLayoutCreateFlags,
+    /// Bindings sorted by binding number.
+    pub(crate) bindings: Vec<DescriptorSetLayoutBinding>,
+}
+
+/// Descriptors accessible through single binding number of descriptor set.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DescriptorSetLayoutBinding {
+    pub binding: u32,
+    pub descriptor_type: VkDescriptorType,
+    pub descriptor_count: u32,
+    pub stage_flags: VkShaderStageFlags,
 }
 
 impl DescriptorSetLayout {
@@ -21,74 +38,869 @@ impl DescriptorSetLayout {
         logical_device: Arc<Mutex<LogicalDevice>>,
         flags: VkDescriptorSetLayoutCreateFlags,
         bindings: &[VkDescriptorSetLayoutBinding],
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new DescriptorSetLayouts");
         let handle = VK_NULL_HANDLE;
 
-        let _ = flags;
-        let _ = bindings;
+        let mut bindings = bindings
+            .iter()
+            .map(|binding| {
+                if binding.pImmutableSamplers.is_some() {
+                    warn!("TODO: Immutable samplers");
+                }
+                DescriptorSetLayoutBinding {
+                    binding: binding.binding,
+                    descriptor_type: binding.descriptorType,
+                    descriptor_count: binding.descriptorCount,
+                    stage_flags: binding.stageFlags,
+                }
+            })
+            .collect::<Vec<_>>();
+        bindings.sort_by_key(|binding| binding.binding);
 
         let object = Self {
             handle,
             logical_device,
+            flags,
+            bindings,
         };
-        object.register_object()
+        Ok(object.register_object())
+    }
+
+    pub fn bindings(&self) -> &[DescriptorSetLayoutBinding] {
+        &self.bindings
+    }
+
+    /// Returns count of descriptors of every type within set.
+    pub fn descriptor_counts(&self) -> Vec<VkDescriptorPoolSize> {
+        self.bindings
+            .iter()
+            .map(|binding| VkDescriptorPoolSize {
+                type_: binding.descriptor_type,
+                descriptorCount: binding.descriptor_count,
+            })
+            .collect()
+    }
+
+    /// Returns layout of binding number, if it is present in set.
+    pub fn binding(&self, binding: u32) -> Option<&DescriptorSetLayoutBinding> {
+        self.bindings
+            .binary_search_by_key(&binding, |x| x.binding)
+            .ok()
+            .map(|index| &self.bindings[index])
+    }
+
+    /// Returns whether descriptors of layout are pushed by command buffers instead of being
+    /// allocated from pool.
+    pub const fn is_push_descriptor(&self) -> bool {
+        self.flags
+            & VkDescriptorSetLayoutCreateFlagBits::VK_DESCRIPTOR_SET_LAYOUT_CREATE_PUSH_DESCRIPTOR_BIT_KHR
+                .0
+            != 0
+    }
+
+    /// Returns whether layouts are identically defined, so sets of one can be used with other.
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.bindings == other.bindings
     }
 }
 
+const fn is_dynamic(descriptor_type: VkDescriptorType) -> bool {
+    matches!(
+        descriptor_type,
+        VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER_DYNAMIC
+            | VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER_DYNAMIC
+    )
+}
+
 #[derive(Debug)]
 pub struct DescriptorPool {
     pub(crate) handle: VkNonDispatchableHandle,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     #[allow(dead_code)]
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    flags: VkDescriptorPoolCreateFlags,
+    max_sets: u32,
+    pool_sizes: Vec<VkDescriptorPoolSize>,
+    /// Descriptor sets allocated from pool, with descriptor counts they take from it.
+    allocations: Vec<(VkDescriptorSet, Vec<VkDescriptorPoolSize>)>,
 }
 
 impl DescriptorPool {
     pub fn create(
         logical_device: Arc<Mutex<LogicalDevice>>,
-        flags: VkDescriptorSetLayoutCreateFlags,
+        flags: VkDescriptorPoolCreateFlags,
         max_sets: u32,
         pool_sizes: &[VkDescriptorPoolSize],
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new DescriptorPool");
         let handle = VK_NULL_HANDLE;
-
-        let _ = flags;
-        let _ = max_sets;
-        let _ = pool_sizes;
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkDescriptorPoolCreateInfo-maxSets-00301
+        if max_sets == 0 {
+            error!("Descriptor pool must allow at least one set");
+            return Err(RuntimeError::ValidationFailed);
+        }
 
         let object = Self {
             handle,
             logical_device,
+            flags,
+            max_sets,
+            pool_sizes: pool_sizes.to_vec(),
+            allocations: vec![],
         };
-        object.register_object()
+        Ok(object.register_object())
+    }
+
+    /// Allocates descriptor set for every layout, or none of them if pool doesn't have enough
+    /// sets or descriptors left.
+    pub fn allocate_descriptor_sets(
+        descriptor_pool: &Arc<Mutex<Self>>,
+        set_layouts: &[Arc<Mutex<DescriptorSetLayout>>],
+    ) -> Result<Vec<VkDescriptorSet>, RuntimeError> {
+        let mut pool = descriptor_pool.lock();
+        let descriptor_counts = set_layouts
+            .iter()
+            .map(|set_layout| set_layout.lock().descriptor_counts())
+            .collect::<Vec<_>>();
+
+        if pool.allocations.len() + set_layouts.len() > pool.max_sets as usize {
+            return Err(RuntimeError::OutOfPoolMemory);
+        }
+        let requested = descriptor_counts.iter().flatten().collect::<Vec<_>>();
+        for pool_size in &requested {
+            let descriptor_type = pool_size.type_;
+            let allocated = pool
+                .allocations
+                .iter()
+                .flat_map(|(_, counts)| counts)
+                .chain(requested.iter().copied());
+            if descriptor_count(allocated, descriptor_type)
+                > descriptor_count(&pool.pool_sizes, descriptor_type)
+            {
+                return Err(RuntimeError::OutOfPoolMemory);
+            }
+        }
+
+        let descriptor_sets = set_layouts
+            .iter()
+            .zip(descriptor_counts)
+            .map(|(set_layout, counts)| {
+                let descriptor_set = DescriptorSet::create(
+                    pool.logical_device.clone(),
+                    descriptor_pool.clone(),
+                    set_layout.clone(),
+                )?;
+                pool.allocations.push((descriptor_set, counts));
+                Ok(descriptor_set)
+            })
+            .collect();
+        drop(pool);
+        descriptor_sets
+    }
+
+    /// Returns descriptors of sets into pool.
+    pub fn free_descriptor_sets(&mut self, descriptor_sets: &[VkDescriptorSet]) {
+        self.allocations
+            .retain(|(descriptor_set, _)| !descriptor_sets.contains(descriptor_set));
+        for &descriptor_set in descriptor_sets {
+            DescriptorSet::drop_handle(descriptor_set);
+        }
+    }
+
+    /// Frees every descriptor set allocated from pool.
+    pub fn reset(&mut self) {
+        for (descriptor_set, _) in self.allocations.drain(..) {
+            DescriptorSet::drop_handle(descriptor_set);
+        }
     }
 }
 
+/// Returns total count of descriptors of type.
+fn descriptor_count<'a>(
+    pool_sizes: impl IntoIterator<Item = &'a VkDescriptorPoolSize>,
+    descriptor_type: VkDescriptorType,
+) -> u32 {
+    pool_sizes
+        .into_iter()
+        .filter(|pool_size| pool_size.type_ == descriptor_type)
+        .map(|pool_size| pool_size.descriptorCount)
+        .sum()
+}
+
+/// Descriptor written into descriptor set.
+#[derive(Debug, Clone)]
+pub enum Descriptor {
+    Image {
+        sampler: Option<Arc<Mutex<Sampler>>>,
+        image_view: Option<Arc<Mutex<ImageView>>>,
+        image_layout: VkImageLayout,
+    },
+    Buffer {
+        buffer: Arc<Mutex<Buffer>>,
+        offset: VkDeviceSize,
+        range: VkDeviceSize,
+    },
+}
+
+/// Descriptors of single binding of descriptor set.
+#[derive(Debug, Clone)]
+pub struct DescriptorSetBinding {
+    pub layout: DescriptorSetLayoutBinding,
+    /// Descriptor of every array element, `None` until it is written.
+    pub descriptors: Vec<Option<Descriptor>>,
+}
+
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct DescriptorSet {
     pub(crate) handle: VkNonDispatchableHandle,
-    logical_device: Arc<Mutex<LogicalDevice>>,
-    descriptor_pool: Arc<Mutex<DescriptorPool>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
+    /// Pool set was allocated from, `None` for push descriptors of command buffer.
+    descriptor_pool: Option<Arc<Mutex<DescriptorPool>>>,
+    pub(crate) set_layout: Arc<Mutex<DescriptorSetLayout>>,
+    pub(crate) bindings: Vec<DescriptorSetBinding>,
 }
 
 impl DescriptorSet {
     pub fn create(
         logical_device: Arc<Mutex<LogicalDevice>>,
         descriptor_pool: Arc<Mutex<DescriptorPool>>,
-        set_layout: &VkDescriptorSetLayout,
-    ) -> VkNonDispatchableHandle {
+        set_layout: Arc<Mutex<DescriptorSetLayout>>,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new DescriptorSet");
+        Ok(Self::new(logical_device, Some(descriptor_pool), set_layout).register_object())
+    }
+
+    /// Returns set holding descriptors pushed by command buffer, which has no handle and isn't
+    /// allocated from any pool.
+    pub fn new_push(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        set_layout: Arc<Mutex<DescriptorSetLayout>>,
+    ) -> Self {
+        Self::new(logical_device, None, set_layout)
+    }
+
+    fn new(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        descriptor_pool: Option<Arc<Mutex<DescriptorPool>>>,
+        set_layout: Arc<Mutex<DescriptorSetLayout>>,
+    ) -> Self {
         let handle = VK_NULL_HANDLE;
 
-        let _ = set_layout;
+        let bindings = set_layout
+            .lock()
+            .bindings
+            .iter()
+            .map(|&layout| DescriptorSetBinding {
+                layout,
+                descriptors: vec![None; layout.descriptor_count as usize],
+            })
+            .collect();
 
-        let object = Self {
+        Self {
             handle,
             logical_device,
             descriptor_pool,
+            set_layout,
+            bindings,
+        }
+    }
+
+    pub fn bindings(&self) -> &[DescriptorSetBinding] {
+        &self.bindings
+    }
+
+    /// Returns buffer descriptors of set for use by gpu.
+    pub fn descriptor(&self) -> graphics::DescriptorSet {
+        let bindings = self
+            .bindings
+            .iter()
+            .map(|binding| graphics::DescriptorSetBinding {
+                binding: binding.layout.binding,
+                dynamic: is_dynamic(binding.layout.descriptor_type),
+                descriptors: binding
+                    .descriptors
+                    .iter()
+                    .map(|descriptor| match descriptor {
+                        Some(Descriptor::Buffer {
+                            buffer,
+                            offset,
+                            range,
+                        }) => {
+                            let buffer = buffer.lock();
+                            let range = if *range == VK_WHOLE_SIZE {
+                                buffer.size() - offset
+                            } else {
+                                *range
+                            };
+                            Some(graphics::Descriptor::Buffer {
+                                buffer: buffer.descriptor(),
+                                offset: *offset,
+                                range,
+                            })
+                        }
+                        Some(Descriptor::Image { .. }) | None => None,
+                    })
+                    .collect(),
+            })
+            .collect();
+        graphics::DescriptorSet { bindings }
+    }
+
+    /// Returns type of every dynamic descriptor in order dynamic offsets are applied to them, along
+    /// with end of its buffer range and size of its buffer once it is written.
+    pub fn dynamic_descriptors(
+        &self,
+    ) -> Vec<(VkDescriptorType, Option<(VkDeviceSize, VkDeviceSize)>)> {
+        self.bindings
+            .iter()
+            .filter(|binding| is_dynamic(binding.layout.descriptor_type))
+            .flat_map(|binding| {
+                binding.descriptors.iter().map(|descriptor| {
+                    let range = match descriptor {
+                        Some(Descriptor::Buffer {
+                            buffer,
+                            offset,
+                            range,
+                        }) => {
+                            let size = buffer.lock().size();
+                            let range = if *range == VK_WHOLE_SIZE {
+                                size - offset
+                            } else {
+                                *range
+                            };
+                            Some((offset + range, size))
+                        }
+                        Some(Descriptor::Image { .. }) | None => None,
+                    };
+                    (binding.layout.descriptor_type, range)
+                })
+            })
+            .collect()
+    }
+
+    /// Applies descriptor writes and then descriptor copies.
+    ///
+    /// # Safety
+    ///
+    /// Image and buffer infos of every write must point to `descriptorCount` elements.
+    pub unsafe fn update_descriptor_sets(
+        writes: &[VkWriteDescriptorSet],
+        copies: &[VkCopyDescriptorSet],
+    ) {
+        for write in writes {
+            let Ok(descriptor_set) = Self::lookup(write.dstSet) else {
+                continue;
+            };
+            unsafe { descriptor_set.lock().write(write) };
+        }
+
+        for copy in copies {
+            let (Ok(src_set), Ok(dst_set)) = (Self::lookup(copy.srcSet), Self::lookup(copy.dstSet))
+            else {
+                continue;
+            };
+            // Source set is unlocked before writing, as it may be the destination set.
+            let src_set = src_set.lock();
+            let Some(src_binding) = src_set
+                .bindings
+                .iter()
+                .find(|binding| binding.layout.binding == copy.srcBinding)
+            else {
+                error!(
+                    "Copy from binding {} outside of descriptor set",
+                    copy.srcBinding
+                );
+                continue;
+            };
+            let descriptor_type = src_binding.layout.descriptor_type;
+            let Some(positions) = src_set.descriptor_positions(
+                copy.srcBinding,
+                copy.srcArrayElement,
+                copy.descriptorCount,
+            ) else {
+                error!("Descriptor copy {:?} is outside of source set", copy);
+                continue;
+            };
+            let descriptors = positions
+                .into_iter()
+                .map(|(binding, element)| src_set.bindings[binding].descriptors[element].clone())
+                .collect();
+            drop(src_set);
+            dst_set.lock().write_descriptors(
+                copy.dstBinding,
+                copy.dstArrayElement,
+                descriptor_type,
+                descriptors,
+            );
+        }
+    }
+
+    /// Stores descriptors of `write`, ignoring its `dstSet`.
+    ///
+    /// # Safety
+    ///
+    /// Image and buffer infos of `write` must point to `descriptorCount` elements.
+    pub unsafe fn write(&mut self, write: &VkWriteDescriptorSet) {
+        let count = write.descriptorCount as usize;
+        let descriptors = match write.descriptorType {
+            VkDescriptorType::VK_DESCRIPTOR_TYPE_SAMPLER
+            | VkDescriptorType::VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER
+            | VkDescriptorType::VK_DESCRIPTOR_TYPE_SAMPLED_IMAGE
+            | VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_IMAGE
+            | VkDescriptorType::VK_DESCRIPTOR_TYPE_INPUT_ATTACHMENT => {
+                let Some(image_infos) = write.pImageInfo else {
+                    unreachable!()
+                };
+                std::slice::from_raw_parts(image_infos.as_ptr(), count)
+                    .iter()
+                    .map(|image_info| {
+                        Some(Descriptor::Image {
+                            sampler: Sampler::from_handle(image_info.sampler),
+                            image_view: ImageView::from_handle(image_info.imageView),
+                            image_layout: image_info.imageLayout,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            }
+            VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER
+            | VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER
+            | VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER_DYNAMIC
+            | VkDescriptorType::VK_DESCRIPTOR_TYPE_STORAGE_BUFFER_DYNAMIC => {
+                let Some(buffer_infos) = write.pBufferInfo else {
+                    unreachable!()
+                };
+                std::slice::from_raw_parts(buffer_infos.as_ptr(), count)
+                    .iter()
+                    .map(|buffer_info| {
+                        let buffer = Buffer::lookup(buffer_info.buffer).ok()?;
+                        Some(Descriptor::Buffer {
+                            buffer,
+                            offset: buffer_info.offset,
+                            range: buffer_info.range,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            }
+            descriptor_type => {
+                warn!("TODO: Write {:?} descriptors", descriptor_type);
+                return;
+            }
+        };
+        self.write_descriptors(
+            write.dstBinding,
+            write.dstArrayElement,
+            write.descriptorType,
+            descriptors,
+        );
+    }
+
+    /// Stores descriptors starting at array element of binding, continuing into following
+    /// bindings once array element passes descriptor count of binding.
+    fn write_descriptors(
+        &mut self,
+        binding: u32,
+        array_element: u32,
+        descriptor_type: VkDescriptorType,
+        descriptors: Vec<Option<Descriptor>>,
+    ) {
+        let Some(positions) =
+            self.descriptor_positions(binding, array_element, descriptors.len() as u32)
+        else {
+            error!(
+                "Writing {} descriptors at binding {binding}, array element {array_element} \
+                 is outside of descriptor set",
+                descriptors.len()
+            );
+            return;
+        };
+        if positions
+            .iter()
+            .any(|&(binding, _)| self.bindings[binding].layout.descriptor_type != descriptor_type)
+        {
+            error!("Writing {descriptor_type:?} descriptors into binding of different type");
+            return;
+        }
+        for ((binding, element), descriptor) in positions.into_iter().zip(descriptors) {
+            self.bindings[binding].descriptors[element] = descriptor;
+        }
+    }
+
+    /// Returns indices of binding and array element of `count` consecutive descriptors.
+    fn descriptor_positions(
+        &self,
+        binding: u32,
+        array_element: u32,
+        count: u32,
+    ) -> Option<Vec<(usize, usize)>> {
+        let mut binding_index = self
+            .bindings
+            .iter()
+            .position(|x| x.layout.binding == binding)?;
+        let mut element = array_element as usize;
+        let mut positions = Vec::with_capacity(count as usize);
+        while positions.len() < count as usize {
+            let binding = self.bindings.get(binding_index)?;
+            if element < binding.descriptors.len() {
+                positions.push((binding_index, element));
+                element += 1;
+            } else {
+                element -= binding.descriptors.len();
+                binding_index += 1;
+            }
+        }
+        Some(positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Image;
+    use crate::logical_device::tests::create_logical_device;
+    use crate::pipeline::PipelineLayout;
+    use std::ptr::NonNull;
+
+    #[test]
+    fn descriptor_set_layout_stores_bindings() {
+        let logical_device = create_logical_device();
+        let fragment_stage: VkShaderStageFlags =
+            VkShaderStageFlagBits::VK_SHADER_STAGE_FRAGMENT_BIT.into();
+        let bindings = [
+            VkDescriptorSetLayoutBinding {
+                binding: 1,
+                descriptorType: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER,
+                descriptorCount: 1,
+                stageFlags: fragment_stage,
+                pImmutableSamplers: None,
+            },
+            VkDescriptorSetLayoutBinding {
+                binding: 0,
+                descriptorType: VkDescriptorType::VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER,
+                descriptorCount: 2,
+                stageFlags: fragment_stage,
+                pImmutableSamplers: None,
+            },
+        ];
+        let Ok(set_layout_handle) =
+            DescriptorSetLayout::create(logical_device.clone(), 0, &bindings)
+        else {
+            unreachable!()
+        };
+        let Some(set_layout) = DescriptorSetLayout::from_handle(set_layout_handle) else {
+            unreachable!()
+        };
+        assert_eq!(
+            set_layout.lock().bindings(),
+            [
+                DescriptorSetLayoutBinding {
+                    binding: 0,
+                    descriptor_type: VkDescriptorType::VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER,
+                    descriptor_count: 2,
+                    stage_flags: fragment_stage,
+                },
+                DescriptorSetLayoutBinding {
+                    binding: 1,
+                    descriptor_type: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER,
+                    descriptor_count: 1,
+                    stage_flags: fragment_stage,
+                },
+            ]
+        );
+        assert_eq!(
+            set_layout
+                .lock()
+                .binding(1)
+                .map(|binding| binding.descriptor_type),
+            Some(VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER)
+        );
+        assert_eq!(set_layout.lock().binding(2), None);
+
+        let push_constant_range = VkPushConstantRange {
+            stageFlags: fragment_stage,
+            offset: 0,
+            size: 16,
+        };
+        let Ok(pipeline_layout) = PipelineLayout::create(
+            logical_device,
+            0,
+            Some(&[set_layout_handle]),
+            Some(&[push_constant_range]),
+        ) else {
+            unreachable!()
+        };
+        let Some(pipeline_layout) = PipelineLayout::from_handle(pipeline_layout) else {
+            unreachable!()
+        };
+        let pipeline_layout = pipeline_layout.lock();
+        assert_eq!(pipeline_layout.set_layouts.len(), 1);
+        assert!(Arc::ptr_eq(&pipeline_layout.set_layouts[0], &set_layout));
+        assert_eq!(pipeline_layout.push_constant_ranges.len(), 1);
+        assert_eq!(pipeline_layout.push_constant_ranges[0].size, 16);
+        drop(pipeline_layout);
+    }
+
+    #[test]
+    fn descriptor_pool_allocates_up_to_limits() {
+        let logical_device = create_logical_device();
+        let uniform_buffer_binding = VkDescriptorSetLayoutBinding {
+            binding: 0,
+            descriptorType: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER,
+            descriptorCount: 1,
+            stageFlags: VkShaderStageFlagBits::VK_SHADER_STAGE_VERTEX_BIT.into(),
+            pImmutableSamplers: None,
+        };
+        let create_set_layout = |bindings: &[VkDescriptorSetLayoutBinding]| {
+            let Ok(set_layout) = DescriptorSetLayout::create(logical_device.clone(), 0, bindings)
+            else {
+                unreachable!()
+            };
+            let Some(set_layout) = DescriptorSetLayout::from_handle(set_layout) else {
+                unreachable!()
+            };
+            set_layout
+        };
+        let uniform_buffer_layout = create_set_layout(&[uniform_buffer_binding]);
+        let empty_layout = create_set_layout(&[]);
+        let pool_size = VkDescriptorPoolSize {
+            type_: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER,
+            descriptorCount: 2,
+        };
+        let Ok(descriptor_pool) =
+            DescriptorPool::create(logical_device.clone(), 0, 3, &[pool_size])
+        else {
+            unreachable!()
+        };
+        let Some(descriptor_pool) = DescriptorPool::from_handle(descriptor_pool) else {
+            unreachable!()
+        };
+        let allocate = |set_layouts: &[&Arc<Mutex<DescriptorSetLayout>>]| {
+            let set_layouts = set_layouts.iter().copied().cloned().collect::<Vec<_>>();
+            DescriptorPool::allocate_descriptor_sets(&descriptor_pool, &set_layouts)
+        };
+
+        let Ok(descriptor_sets) = allocate(&[&uniform_buffer_layout, &uniform_buffer_layout])
+        else {
+            unreachable!()
+        };
+        assert_eq!(descriptor_sets.len(), 2);
+        let Some(descriptor_set) = DescriptorSet::from_handle(descriptor_sets[0]) else {
+            unreachable!()
+        };
+        assert_eq!(descriptor_set.lock().bindings().len(), 1);
+        assert!(descriptor_set.lock().bindings()[0]
+            .descriptors
+            .iter()
+            .all(Option::is_none));
+
+        // Uniform buffer descriptors are exhausted before sets.
+        assert_eq!(
+            allocate(&[&uniform_buffer_layout]).err(),
+            Some(RuntimeError::OutOfPoolMemory)
+        );
+        assert!(allocate(&[&empty_layout]).is_ok());
+        assert_eq!(
+            allocate(&[&empty_layout]).err(),
+            Some(RuntimeError::OutOfPoolMemory)
+        );
+
+        descriptor_pool.lock().reset();
+        assert!(DescriptorSet::from_handle(descriptor_sets[0]).is_none());
+        assert!(allocate(&[&uniform_buffer_layout, &uniform_buffer_layout]).is_ok());
+    }
+
+    fn create_image_view(logical_device: Arc<Mutex<LogicalDevice>>) -> VkImageView {
+        let format = VkFormat::VK_FORMAT_R8G8B8A8_UNORM;
+        let image_create_info = VkImageCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            imageType: VkImageType::VK_IMAGE_TYPE_2D,
+            format,
+            extent: VkExtent3D {
+                width: 4,
+                height: 4,
+                depth: 1,
+            },
+            mipLevels: 1,
+            arrayLayers: 1,
+            samples: VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+            tiling: VkImageTiling::VK_IMAGE_TILING_OPTIMAL,
+            usage: VkImageUsageFlagBits::VK_IMAGE_USAGE_SAMPLED_BIT.into(),
+            sharingMode: VkSharingMode::VK_SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: None,
+            initialLayout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+        };
+        let Ok(image) = Image::create(logical_device.clone(), &image_create_info) else {
+            unreachable!()
+        };
+        let image_view_create_info = VkImageViewCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            image,
+            viewType: VkImageViewType::VK_IMAGE_VIEW_TYPE_2D,
+            format,
+            components: VkComponentMapping {
+                r: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+                g: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+                b: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+                a: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+            },
+            subresourceRange: VkImageSubresourceRange {
+                aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+                baseMipLevel: 0,
+                levelCount: 1,
+                baseArrayLayer: 0,
+                layerCount: 1,
+            },
+        };
+        let Ok(image_view) = ImageView::create(logical_device, &image_view_create_info) else {
+            unreachable!()
+        };
+        image_view
+    }
+
+    #[test]
+    fn update_descriptor_sets_writes_slots() {
+        let logical_device = create_logical_device();
+        let stage_flags: VkShaderStageFlags =
+            VkShaderStageFlagBits::VK_SHADER_STAGE_FRAGMENT_BIT.into();
+        let layout_binding =
+            |binding, descriptor_type, descriptor_count| VkDescriptorSetLayoutBinding {
+                binding,
+                descriptorType: descriptor_type,
+                descriptorCount: descriptor_count,
+                stageFlags: stage_flags,
+                pImmutableSamplers: None,
+            };
+        let Ok(set_layout) = DescriptorSetLayout::create(
+            logical_device.clone(),
+            0,
+            &[
+                layout_binding(
+                    0,
+                    VkDescriptorType::VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER,
+                    2,
+                ),
+                layout_binding(1, VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER, 1),
+                layout_binding(2, VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER, 1),
+            ],
+        ) else {
+            unreachable!()
+        };
+        let Some(set_layout) = DescriptorSetLayout::from_handle(set_layout) else {
+            unreachable!()
+        };
+        let pool_sizes = [
+            VkDescriptorPoolSize {
+                type_: VkDescriptorType::VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER,
+                descriptorCount: 2,
+            },
+            VkDescriptorPoolSize {
+                type_: VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER,
+                descriptorCount: 2,
+            },
+        ];
+        let Ok(descriptor_pool) = DescriptorPool::create(logical_device.clone(), 0, 1, &pool_sizes)
+        else {
+            unreachable!()
+        };
+        let Some(descriptor_pool) = DescriptorPool::from_handle(descriptor_pool) else {
+            unreachable!()
+        };
+        let Ok(descriptor_sets) =
+            DescriptorPool::allocate_descriptor_sets(&descriptor_pool, &[set_layout])
+        else {
+            unreachable!()
+        };
+
+        let Ok(sampler) = Sampler::create(logical_device.clone(), 0, Default::default()) else {
+            unreachable!()
+        };
+        let image_view = create_image_view(logical_device.clone());
+        let buffers = [0, 1].map(|_| Buffer::create(logical_device.clone(), 256, 0, 0));
+        let image_info = VkDescriptorImageInfo {
+            sampler,
+            imageView: image_view,
+            imageLayout: VkImageLayout::VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL,
+        };
+        let buffer_infos = [
+            VkDescriptorBufferInfo {
+                buffer: buffers[0],
+                offset: 0,
+                range: 64,
+            },
+            VkDescriptorBufferInfo {
+                buffer: buffers[1],
+                offset: 128,
+                range: VK_WHOLE_SIZE,
+            },
+        ];
+        let write = |binding, array_element, descriptor_type, count| VkWriteDescriptorSet {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET,
+            pNext: None,
+            dstSet: descriptor_sets[0],
+            dstBinding: binding,
+            dstArrayElement: array_element,
+            descriptorCount: count,
+            descriptorType: descriptor_type,
+            pImageInfo: Some(NonNull::from(&image_info)),
+            pBufferInfo: NonNull::new(buffer_infos.as_ptr().cast_mut()),
+            pTexelBufferView: None,
+        };
+        unsafe {
+            DescriptorSet::update_descriptor_sets(
+                &[
+                    write(
+                        0,
+                        1,
+                        VkDescriptorType::VK_DESCRIPTOR_TYPE_COMBINED_IMAGE_SAMPLER,
+                        1,
+                    ),
+                    // Second descriptor continues into the following binding.
+                    write(1, 0, VkDescriptorType::VK_DESCRIPTOR_TYPE_UNIFORM_BUFFER, 2),
+                ],
+                &[],
+            );
+        }
+
+        let Some(descriptor_set) = DescriptorSet::from_handle(descriptor_sets[0]) else {
+            unreachable!()
+        };
+        let descriptor_set = descriptor_set.lock();
+        let bindings = descriptor_set.bindings();
+        assert!(bindings[0].descriptors[0].is_none());
+        let Some(Descriptor::Image {
+            sampler: Some(written_sampler),
+            image_view: Some(written_image_view),
+            image_layout,
+        }) = &bindings[0].descriptors[1]
+        else {
+            unreachable!()
         };
-        object.register_object()
+        assert_eq!(written_sampler.lock().handle, sampler);
+        assert_eq!(written_image_view.lock().handle, image_view);
+        assert_eq!(
+            *image_layout,
+            VkImageLayout::VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL
+        );
+        for (binding, (buffer, offset, range)) in [
+            (1, (buffers[0], 0, 64)),
+            (2, (buffers[1], 128, VK_WHOLE_SIZE)),
+        ] {
+            let Some(Descriptor::Buffer {
+                buffer: written_buffer,
+                offset: written_offset,
+                range: written_range,
+            }) = &bindings[binding].descriptors[0]
+            else {
+                unreachable!()
+            };
+            assert_eq!(written_buffer.lock().handle, buffer);
+            assert_eq!((*written_offset, *written_range), (offset, range));
+        }
+        drop(descriptor_set);
     }
 }
@@ -0,0 +1,61 @@
+//! Errors
+
+use headers::vk_decls::*;
+use std::fmt::{Display, Formatter};
+
+/// Error of driver operation, translated into `VkResult` where it is returned to application.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+    OutOfHostMemory,
+    OutOfDeviceMemory,
+    OutOfPoolMemory,
+    InitializationFailed,
+    FeatureNotPresent,
+    InvalidExternalHandle,
+    /// Handle is null, of destroyed object or of object of other type.
+    InvalidHandle,
+    /// Application broke valid usage of the API.
+    ValidationFailed,
+}
+
+impl RuntimeError {
+    /// Returns error code reported to application. Vulkan has no code for invalid handles, which
+    /// are reported as failed validation.
+    pub const fn to_vk_result(self) -> VkResult {
+        match self {
+            Self::OutOfHostMemory => VkResult::VK_ERROR_OUT_OF_HOST_MEMORY,
+            Self::OutOfDeviceMemory => VkResult::VK_ERROR_OUT_OF_DEVICE_MEMORY,
+            Self::OutOfPoolMemory => VkResult::VK_ERROR_OUT_OF_POOL_MEMORY,
+            Self::InitializationFailed => VkResult::VK_ERROR_INITIALIZATION_FAILED,
+            Self::FeatureNotPresent => VkResult::VK_ERROR_FEATURE_NOT_PRESENT,
+            Self::InvalidExternalHandle => VkResult::VK_ERROR_INVALID_EXTERNAL_HANDLE,
+            Self::InvalidHandle | Self::ValidationFailed => {
+                VkResult::VK_ERROR_VALIDATION_FAILED_EXT
+            }
+        }
+    }
+}
+
+impl From<RuntimeError> for VkResult {
+    fn from(error: RuntimeError) -> Self {
+        error.to_vk_result()
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Self::OutOfHostMemory => "out of host memory",
+            Self::OutOfDeviceMemory => "out of device memory",
+            Self::OutOfPoolMemory => "out of pool memory",
+            Self::InitializationFailed => "initialization failed",
+            Self::FeatureNotPresent => "feature not present",
+            Self::InvalidExternalHandle => "invalid external handle",
+            Self::InvalidHandle => "invalid handle",
+            Self::ValidationFailed => "validation failed",
+        };
+        f.write_str(description)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
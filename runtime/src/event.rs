@@ -0,0 +1,92 @@
+//! Event
+
+use crate::context::{Named, NonDispatchable};
+use crate::error::RuntimeError;
+use crate::logical_device::LogicalDevice;
+use gpu::EVENT_SIZE;
+use headers::vk_decls::*;
+use log::*;
+use parking_lot::Mutex;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Synchronization primitive set and reset by commands and the host, that commands can wait on.
+#[derive(Debug)]
+pub struct Event {
+    pub(crate) handle: VkNonDispatchableHandle,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
+    gpu_event: gpu::Event,
+}
+
+impl Event {
+    pub fn create(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        create_info: &VkEventCreateInfo,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
+        info!("new Event");
+        let handle = VK_NULL_HANDLE;
+        let _ = create_info.flags;
+        // Events start unsignaled, as memory is zeroed on allocation.
+        let memory = logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .allocate_memory(EVENT_SIZE);
+        let event = Self {
+            handle,
+            logical_device,
+            gpu_event: gpu::Event { memory },
+        };
+        Ok(event.register_object())
+    }
+
+    pub const fn gpu_event(&self) -> gpu::Event {
+        self.gpu_event
+    }
+
+    /// Returns `VK_EVENT_SET` or `VK_EVENT_RESET`.
+    pub fn status(&self) -> VkResult {
+        let logical_device = self.logical_device.lock();
+        let is_set = self
+            .gpu_event
+            .is_set(&logical_device.physical_device().gpu.memory);
+        drop(logical_device);
+        if is_set {
+            VkResult::VK_EVENT_SET
+        } else {
+            VkResult::VK_EVENT_RESET
+        }
+    }
+
+    /// Signals event from host and resumes execution of work submitted to queues of device that
+    /// was waiting for it.
+    pub fn set(&self) {
+        trace!("Event::set {}", Named::from(self.handle));
+        let logical_device = self.logical_device.lock();
+        self.gpu_event
+            .set(&mut logical_device.physical_device().gpu.memory);
+        drop(logical_device);
+        LogicalDevice::resume_queues(&self.logical_device);
+    }
+
+    /// Unsignals event from host.
+    pub fn reset(&self) {
+        trace!("Event::reset {}", Named::from(self.handle));
+        let logical_device = self.logical_device.lock();
+        self.gpu_event
+            .reset(&mut logical_device.physical_device().gpu.memory);
+        drop(logical_device);
+    }
+}
+
+impl Drop for Event {
+    fn drop(&mut self) {
+        self.logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .free_memory(self.gpu_event.memory);
+    }
+}
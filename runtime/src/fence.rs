@@ -1,7 +1,9 @@
 //! Fence
 
-use crate::context::NonDispatchable;
+use crate::context::{Named, NonDispatchable};
+use crate::error::RuntimeError;
 use crate::logical_device::LogicalDevice;
+use crate::semaphore;
 
 use headers::vk_decls::*;
 use log::*;
@@ -23,7 +25,7 @@ impl Fence {
     pub fn create(
         logical_device: Arc<Mutex<LogicalDevice>>,
         create_info: &VkFenceCreateInfo,
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new Fence");
         let handle = VK_NULL_HANDLE;
         let flags = create_info.flags;
@@ -36,16 +38,30 @@ impl Fence {
             flags,
             signaled,
         };
-        fence.register_object()
+        Ok(fence.register_object())
+    }
+
+    pub const fn is_signaled(&self) -> bool {
+        self.signaled
     }
 
     pub fn signal(&mut self) {
-        trace!("fence {} signal", self.signaled);
+        trace!(
+            "fence {} {} signal",
+            Named::from(self.handle),
+            self.signaled
+        );
         self.signaled = true;
     }
 
+    /// Signals `fence` like [`Fence::signal`], then wakes host threads waiting for fences.
+    pub fn signal_and_notify(fence: &Arc<Mutex<Self>>) {
+        fence.lock().signal();
+        semaphore::notify_signaled();
+    }
+
     pub fn reset(&mut self) {
-        trace!("fence {} reset", self.signaled);
+        trace!("fence {} {} reset", Named::from(self.handle), self.signaled);
         self.signaled = false;
     }
 }
@@ -0,0 +1,387 @@
+//! Golden image tests rendering fixed scenes with every rasterizer backend and comparing results
+//! with reference images in `tests/golden`. Running them with `UPDATE_GOLDEN=1` set writes
+//! rendered images as new references.
+
+use crate::command_buffer::tests::{
+    blit_image, blit_region, create_buffer_with_bytes, create_framebuffer,
+    create_image_with_texels, record_and_submit,
+};
+use crate::command_buffer::CommandBuffer;
+use crate::context::NonDispatchable;
+use crate::image::{save_ppm, Image};
+use crate::logical_device::tests::create_logical_device;
+use crate::logical_device::LogicalDevice;
+use crate::physical_device::PhysicalDevice;
+use crate::pipeline::Pipeline;
+use common::graphics::{
+    BlendFactor, VertexAttribute, VertexBinding, VertexBindingNumber, VertexInputRate,
+    VertexInputState,
+};
+use common::math::Format;
+use gpu::{
+    ColorBlendAttachmentState, ColorBlendState, InputAssemblyState, PrimitiveTopology,
+    RasterizerBackend, ViewportState,
+};
+use headers::vk_decls::*;
+use parking_lot::Mutex;
+use shader::glsl::{Shader, ShaderState};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const EXTENT: VkExtent2D = VkExtent2D {
+    width: 32,
+    height: 32,
+};
+const CLEAR_COLOR: [f32; 4] = [0.1, 0.2, 0.3, 1.0];
+/// Number of differing pixels listed in failure message.
+const REPORTED_PIXELS: usize = 16;
+
+/// Vertex position followed by color.
+type Vertex = [f32; 8];
+
+// #version 450
+// layout(location = 0) in vec4 position;
+// layout(location = 1) in vec4 color;
+// layout(location = 0) out vec4 out_color;
+// void main() { gl_Position = position; out_color = color; }
+const COLOR_VERTEX_SHADER: &[u32] = &[
+    0x07230203, 0x00010000, 0x00080000, 0x00000017, 0x00000000, 0x00020011, 0x00000001, 0x0003000e,
+    0x00000000, 0x00000001, 0x0009000f, 0x00000000, 0x00000001, 0x6e69616d, 0x00000000, 0x00000002,
+    0x00000003, 0x00000004, 0x00000005, 0x00050048, 0x0000000d, 0x00000000, 0x0000000b, 0x00000000,
+    0x00050048, 0x0000000d, 0x00000001, 0x0000000b, 0x00000001, 0x00050048, 0x0000000d, 0x00000002,
+    0x0000000b, 0x00000003, 0x00050048, 0x0000000d, 0x00000003, 0x0000000b, 0x00000004, 0x00030047,
+    0x0000000d, 0x00000002, 0x00040047, 0x00000003, 0x0000001e, 0x00000000, 0x00040047, 0x00000004,
+    0x0000001e, 0x00000000, 0x00040047, 0x00000005, 0x0000001e, 0x00000001, 0x00020013, 0x00000006,
+    0x00030021, 0x00000007, 0x00000006, 0x00030016, 0x00000008, 0x00000020, 0x00040017, 0x00000009,
+    0x00000008, 0x00000004, 0x00040015, 0x0000000a, 0x00000020, 0x00000000, 0x0004002b, 0x0000000a,
+    0x0000000b, 0x00000001, 0x0004001c, 0x0000000c, 0x00000008, 0x0000000b, 0x0006001e, 0x0000000d,
+    0x00000009, 0x00000008, 0x0000000c, 0x0000000c, 0x00040020, 0x0000000e, 0x00000003, 0x0000000d,
+    0x0004003b, 0x0000000e, 0x00000002, 0x00000003, 0x00040015, 0x0000000f, 0x00000020, 0x00000001,
+    0x0004002b, 0x0000000f, 0x00000010, 0x00000000, 0x00040020, 0x00000011, 0x00000001, 0x00000009,
+    0x0004003b, 0x00000011, 0x00000003, 0x00000001, 0x0004003b, 0x00000011, 0x00000005, 0x00000001,
+    0x00040020, 0x00000012, 0x00000003, 0x00000009, 0x0004003b, 0x00000012, 0x00000004, 0x00000003,
+    0x00050036, 0x00000006, 0x00000001, 0x00000000, 0x00000007, 0x000200f8, 0x00000013, 0x0004003d,
+    0x00000009, 0x00000014, 0x00000003, 0x00050041, 0x00000012, 0x00000015, 0x00000002, 0x00000010,
+    0x0003003e, 0x00000015, 0x00000014, 0x0004003d, 0x00000009, 0x00000016, 0x00000005, 0x0003003e,
+    0x00000004, 0x00000016, 0x000100fd, 0x00010038,
+];
+
+// #version 450
+// layout(location = 0) in vec4 color;
+// layout(location = 0) out vec4 out_color;
+// void main() { out_color = color; }
+const COLOR_FRAGMENT_SHADER: &[u32] = &[
+    0x07230203, 0x00010000, 0x00080000, 0x0000000c, 0x00000000, 0x00020011, 0x00000001, 0x0003000e,
+    0x00000000, 0x00000001, 0x0007000f, 0x00000004, 0x00000001, 0x6e69616d, 0x00000000, 0x00000002,
+    0x00000003, 0x00030010, 0x00000001, 0x00000007, 0x00040047, 0x00000002, 0x0000001e, 0x00000000,
+    0x00040047, 0x00000003, 0x0000001e, 0x00000000, 0x00020013, 0x00000004, 0x00030021, 0x00000005,
+    0x00000004, 0x00030016, 0x00000006, 0x00000020, 0x00040017, 0x00000007, 0x00000006, 0x00000004,
+    0x00040020, 0x00000008, 0x00000003, 0x00000007, 0x0004003b, 0x00000008, 0x00000002, 0x00000003,
+    0x00040020, 0x00000009, 0x00000001, 0x00000007, 0x0004003b, 0x00000009, 0x00000003, 0x00000001,
+    0x00050036, 0x00000004, 0x00000001, 0x00000000, 0x00000005, 0x000200f8, 0x0000000a, 0x0004003d,
+    0x00000007, 0x0000000b, 0x00000003, 0x0003003e, 0x00000002, 0x0000000b, 0x000100fd, 0x00010038,
+];
+
+/// Renders scene with every rasterizer backend and checks that no channel of any pixel differs
+/// from reference image `name` by more than `tolerance`.
+fn check_golden(
+    name: &str,
+    tolerance: u8,
+    render: impl Fn(&Arc<Mutex<LogicalDevice>>) -> Arc<Mutex<Image>>,
+) {
+    let reference_path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"))
+        .join(format!("{name}.ppm"));
+    let temp_dir = assert_fs::TempDir::new().unwrap_or_else(|_| unreachable!());
+    for backend in [RasterizerBackend::Simple, RasterizerBackend::Tiled] {
+        let logical_device = create_logical_device();
+        logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .graphics_pipeline
+            .set_rasterizer_backend(backend);
+        let image = render(&logical_device);
+        let rendered_path = temp_dir.join(format!("{name}_{backend:?}.ppm"));
+        assert!(save_ppm(&image.lock(), &rendered_path).is_ok());
+        if backend == RasterizerBackend::Simple && std::env::var_os("UPDATE_GOLDEN").is_some() {
+            assert!(std::fs::copy(&rendered_path, &reference_path).is_ok());
+        }
+
+        let Some(reference) = read_ppm(&reference_path) else {
+            panic!(
+                "Missing reference image {reference_path:?}, run with UPDATE_GOLDEN=1 to create it"
+            )
+        };
+        let Some(rendered) = read_ppm(&rendered_path) else {
+            unreachable!()
+        };
+        assert_eq!(
+            rendered.extent, reference.extent,
+            "{name} rendered with {backend:?} rasterizer differs from reference in size"
+        );
+        let diff = diff(&rendered, &reference, tolerance);
+        assert!(
+            diff.failing_pixels.is_empty(),
+            "{name} rendered with {backend:?} rasterizer differs from reference by up to {} over \
+             tolerance {tolerance} in {} pixels, first of them at {:?}",
+            diff.max,
+            diff.failing_pixels.len(),
+            &diff.failing_pixels[..diff.failing_pixels.len().min(REPORTED_PIXELS)],
+        );
+    }
+}
+
+/// Pixels of PPM file.
+struct Pixels {
+    extent: (u32, u32),
+    rgb: Vec<[u8; 3]>,
+}
+
+/// Reads binary PPM file with 8-bit channels, as written by `save_ppm`.
+fn read_ppm(path: &Path) -> Option<Pixels> {
+    let bytes = std::fs::read(path).ok()?;
+    let mut fields = bytes.splitn(5, u8::is_ascii_whitespace);
+    let mut field = || std::str::from_utf8(fields.next()?).ok();
+    let (magic, width, height, max) = (field()?, field()?, field()?, field()?);
+    let extent = (width.parse().ok()?, height.parse().ok()?);
+    if magic != "P6" || max != "255" {
+        return None;
+    }
+    let rgb = fields
+        .next()?
+        .chunks_exact(3)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect::<Vec<_>>();
+    (rgb.len() == extent.0 as usize * extent.1 as usize).then_some(Pixels { extent, rgb })
+}
+
+/// Largest channel difference between two images, along with coordinates of pixels differing by
+/// more than tolerance.
+struct Diff {
+    max: u8,
+    failing_pixels: Vec<(u32, u32)>,
+}
+
+fn diff(rendered: &Pixels, reference: &Pixels, tolerance: u8) -> Diff {
+    let mut diff = Diff {
+        max: 0,
+        failing_pixels: Vec::new(),
+    };
+    let width = reference.extent.0 as usize;
+    for (i, (a, b)) in std::iter::zip(&rendered.rgb, &reference.rgb).enumerate() {
+        let max = std::iter::zip(a, b).map(|(a, b)| a.abs_diff(*b)).max();
+        let max = max.unwrap_or_default();
+        diff.max = diff.max.max(max);
+        if max > tolerance {
+            diff.failing_pixels
+                .push(((i % width) as u32, (i / width) as u32));
+        }
+    }
+    diff
+}
+
+/// Creates pipeline drawing triangle lists of `Vertex` with interpolated colors, blending them
+/// with attachment if `blend` is set.
+fn create_color_pipeline(
+    logical_device: Arc<Mutex<LogicalDevice>>,
+    blend: Option<ColorBlendAttachmentState>,
+) -> Arc<Mutex<Pipeline>> {
+    let shader_state = ShaderState {
+        vertex_shader: Shader::new("main", COLOR_VERTEX_SHADER.to_vec()).ok(),
+        fragment_shader: Shader::new("main", COLOR_FRAGMENT_SHADER.to_vec()).ok(),
+    };
+    let mut vertex_input_state = VertexInputState::default();
+    vertex_input_state.bindings[0] = Some(VertexBinding {
+        number: VertexBindingNumber(0),
+        stride: std::mem::size_of::<Vertex>() as u32,
+        input_rate: VertexInputRate::Vertex,
+    });
+    for (location, offset) in [(0, 0), (1, 16)] {
+        vertex_input_state.attributes[location as usize] = Some(VertexAttribute {
+            location,
+            binding: VertexBindingNumber(0),
+            format: Format::R32G32B32A32Sfloat,
+            offset,
+        });
+    }
+    let mut viewport_state = ViewportState::default();
+    viewport_state.viewports[0] = Some(PhysicalDevice::parse_viewport(&VkViewport {
+        x: 0.0,
+        y: 0.0,
+        width: EXTENT.width as f32,
+        height: EXTENT.height as f32,
+        minDepth: 0.0,
+        maxDepth: 1.0,
+    }));
+    let mut color_blend_state = ColorBlendState::default();
+    color_blend_state.attachments[0] = blend;
+    let Ok(pipeline) = Pipeline::create(
+        logical_device,
+        None,
+        shader_state,
+        Some(vertex_input_state),
+        Some(InputAssemblyState {
+            topology: PrimitiveTopology::TriangleList,
+            primitive_restart: false,
+        }),
+        Some(viewport_state),
+        None,
+        None,
+        Some(color_blend_state),
+        None,
+    ) else {
+        unreachable!()
+    };
+    let Some(pipeline) = Pipeline::from_handle(pipeline) else {
+        unreachable!()
+    };
+    pipeline
+}
+
+/// Clears framebuffer and draws `vertices` with `pipeline` in it, returning its image.
+fn draw(
+    logical_device: &Arc<Mutex<LogicalDevice>>,
+    pipeline: Arc<Mutex<Pipeline>>,
+    vertices: &[Vertex],
+) -> Arc<Mutex<Image>> {
+    let (render_pass, framebuffer) =
+        create_framebuffer(logical_device.clone(), (EXTENT.width, EXTENT.height));
+    let image_view = framebuffer.lock().attachments[0].clone();
+    let bytes = vertices
+        .iter()
+        .flatten()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<_>>();
+    let vertex_buffer = create_buffer_with_bytes(logical_device.clone(), &bytes);
+    let clear_value = VkClearValue {
+        color: std::mem::ManuallyDrop::new(VkClearColorValue {
+            float32: std::mem::ManuallyDrop::new(CLEAR_COLOR),
+        }),
+    };
+    record_and_submit(logical_device, |command_buffer: &mut CommandBuffer| {
+        command_buffer.cmd_begin_render_pass(
+            render_pass,
+            framebuffer,
+            VkRect2D {
+                offset: VkOffset2D { x: 0, y: 0 },
+                extent: EXTENT,
+            },
+            &[clear_value],
+            VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+        );
+        command_buffer.cmd_bind_pipeline(
+            VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+            pipeline,
+        );
+        command_buffer.cmd_bind_vertex_buffer(0, vertex_buffer, 0);
+        command_buffer.cmd_draw(vertices.len() as u32, 1, 0, 0);
+        command_buffer.cmd_end_render_pass();
+    });
+    let image = image_view.lock().image.clone();
+    image
+}
+
+/// Returns two triangles covering rectangle between corners in normalized device coordinates,
+/// with colors of its corners in order top left, top right, bottom left and bottom right.
+fn quad([x0, y0]: [f32; 2], [x1, y1]: [f32; 2], colors: [[f32; 4]; 4]) -> [Vertex; 6] {
+    let vertex = |[x, y]: [f32; 2], [r, g, b, a]: [f32; 4]| [x, y, 0.0, 1.0, r, g, b, a];
+    let corners = [
+        vertex([x0, y0], colors[0]),
+        vertex([x1, y0], colors[1]),
+        vertex([x0, y1], colors[2]),
+        vertex([x1, y1], colors[3]),
+    ];
+    [0, 1, 2, 2, 1, 3].map(|i| corners[i])
+}
+
+#[test]
+fn golden_triangles() {
+    // Triangles sharing edge through pixel centers, covered pixels along it are owned by exactly
+    // one of them according to fill rule.
+    check_golden("triangles", 0, |logical_device| {
+        let vertex = |[x, y]: [f32; 2], [r, g, b]: [f32; 3]| [x, y, 0.0, 1.0, r, g, b, 1.0];
+        let (red, blue) = ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+        let (top, bottom) = ([-0.03125, -0.90625], [0.28125, 0.84375]);
+        let vertices = [
+            vertex(top, red),
+            vertex(bottom, red),
+            vertex([-0.8, 0.3], red),
+            vertex(top, blue),
+            vertex([0.9, -0.1], blue),
+            vertex(bottom, blue),
+        ];
+        let pipeline = create_color_pipeline(logical_device.clone(), None);
+        draw(logical_device, pipeline, &vertices)
+    });
+}
+
+#[test]
+fn golden_textured_quad() {
+    // Shaders don't sample images yet, so texture is mapped onto quad by linearly filtered blit.
+    check_golden("textured_quad", 0, |logical_device| {
+        let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 255]);
+        let texels = (0..16)
+            .map(|i| {
+                if (i % 4 + i / 4) % 2 == 0 {
+                    black
+                } else {
+                    white
+                }
+            })
+            .collect::<Vec<_>>();
+        let texture = create_image_with_texels(logical_device.clone(), 4, 4, &texels);
+        let pipeline = create_color_pipeline(logical_device.clone(), None);
+        let gray = [0.5, 0.5, 0.5, 1.0];
+        let frame = quad([-0.75, -0.75], [0.75, 0.75], [gray; 4]);
+        let image = draw(logical_device, pipeline, &frame);
+        blit_image(
+            logical_device,
+            texture,
+            image.clone(),
+            blit_region([(0, 0), (4, 4)], [(6, 7), (26, 25)]),
+            VkFilter::VK_FILTER_LINEAR,
+        );
+        image
+    });
+}
+
+#[test]
+fn golden_blended_sprites() {
+    // Overlapping translucent sprites with colors interpolated across them, blended in order.
+    // Interpolation of SIMD rasterizer rounds differently where multiply-add isn't fused.
+    check_golden("blended_sprites", 1, |logical_device| {
+        let blend = ColorBlendAttachmentState {
+            blend_enable: true,
+            src_color_blend_factor: BlendFactor::SrcAlpha,
+            dst_color_blend_factor: BlendFactor::OneMinusSrcAlpha,
+            src_alpha_blend_factor: BlendFactor::One,
+            dst_alpha_blend_factor: BlendFactor::OneMinusSrcAlpha,
+            ..Default::default()
+        };
+        let pipeline = create_color_pipeline(logical_device.clone(), Some(blend));
+        let sprites = [
+            quad(
+                [-0.9, -0.9],
+                [0.3, 0.3],
+                [
+                    [1.0, 0.0, 0.0, 0.5],
+                    [1.0, 1.0, 0.0, 0.5],
+                    [1.0, 0.0, 0.0, 0.5],
+                    [1.0, 1.0, 0.0, 0.5],
+                ],
+            ),
+            quad(
+                [-0.4, -0.5],
+                [0.7, 0.6],
+                [
+                    [0.0, 1.0, 0.0, 0.75],
+                    [0.0, 1.0, 0.0, 0.25],
+                    [0.0, 1.0, 1.0, 0.75],
+                    [0.0, 1.0, 1.0, 0.25],
+                ],
+            ),
+            quad([-0.1, -0.2], [0.95, 0.9], [[0.0, 0.0, 1.0, 0.6]; 4]),
+        ];
+        draw(logical_device, pipeline, sprites.as_flattened())
+    });
+}
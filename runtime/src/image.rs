@@ -1,55 +1,230 @@
 //! Image
 
 use crate::context::NonDispatchable;
+use crate::error::RuntimeError;
 use crate::logical_device::LogicalDevice;
 use crate::memory::MemoryAllocation;
-use common::graphics::{DescriptorImage, MemoryBinding};
-use common::math::Extent3;
+use anyhow::anyhow;
+use common::format::read_texel;
+use common::graphics::{ComponentMapping, DescriptorImage, MemoryBinding};
+use common::math::{linear_to_srgb, Extent3, Format};
 use gpu::MemoryHandleStore;
 use headers::vk_decls::*;
 use log::*;
 use parking_lot::Mutex;
 use std::fmt::Debug;
+use std::path::Path;
 use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct Image {
     pub(crate) handle: VkNonDispatchableHandle,
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     pub(crate) format: VkFormat,
-    width: u32,
-    height: u32,
+    /// Number of samples per texel, stored one after another.
+    pub(crate) samples: u32,
+    layout: ImageLayout,
+    /// Current layout of every subresource, indexed by mip level and then array layer.
+    subresource_layouts: Vec<VkImageLayout>,
     gpu_binding: MemoryBinding,
 }
 
 impl Image {
     pub fn create(
         logical_device: Arc<Mutex<LogicalDevice>>,
-        format: VkFormat,
-        width: u32,
-        height: u32,
-        array_layers: u32,
-        image_usage: VkImageUsageFlags,
-    ) -> VkNonDispatchableHandle {
+        create_info: &VkImageCreateInfo,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new Image");
         let handle = VK_NULL_HANDLE;
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkImageCreateInfo-extent-00944
+        let extent = create_info.extent;
+        if [extent.width, extent.height, extent.depth].contains(&0)
+            || create_info.mipLevels == 0
+            || create_info.arrayLayers == 0
+        {
+            error!(
+                "Image of {:?} with {} mip levels and {} array layers is empty",
+                extent, create_info.mipLevels, create_info.arrayLayers
+            );
+            return Err(RuntimeError::ValidationFailed);
+        }
+
+        let format = create_info.format;
+        let samples: u32 = create_info.samples.into();
+        let layout = ImageLayout::new(
+            format.bytes_per_pixel() as u32 * samples,
+            format.block_extent(),
+            Extent3 {
+                width: create_info.extent.width,
+                height: create_info.extent.height,
+                depth: create_info.extent.depth,
+            },
+            create_info.mipLevels,
+            create_info.arrayLayers,
+        );
 
-        let _ = array_layers;
-        let _ = image_usage;
+        let subresource_layouts = vec![
+            create_info.initialLayout;
+            create_info.mipLevels as usize
+                * create_info.arrayLayers as usize
+        ];
+
+        let _ = create_info.imageType;
+        let _ = create_info.tiling;
+        let _ = create_info.usage;
 
         let image = Self {
             handle,
             logical_device,
             format,
-            width,
-            height,
+            samples,
+            layout,
+            subresource_layouts,
             gpu_binding: Default::default(),
         };
-        image.register_object()
+        Ok(image.register_object())
+    }
+
+    pub fn size_in_bytes(&self) -> u64 {
+        self.layout.size_in_bytes()
+    }
+
+    /// Returns offset of texel within memory bound to image.
+    pub fn texel_offset(&self, x: u32, y: u32, mip_level: u32, array_layer: u32) -> u64 {
+        self.layout.texel_offset(x, y, mip_level, array_layer)
+    }
+
+    /// Returns layout the subresource was last transitioned to.
+    pub fn current_layout(&self, mip_level: u32, array_layer: u32) -> VkImageLayout {
+        self.subresource_layouts[self.subresource_index(mip_level, array_layer)]
+    }
+
+    /// Transitions every subresource within range to `new_layout`. Transition from layout other
+    /// than the tracked one is reported, unless `old_layout` is `UNDEFINED` which discards contents.
+    pub fn transition_layout(
+        &mut self,
+        range: &ImageSubresourceRange,
+        old_layout: VkImageLayout,
+        new_layout: VkImageLayout,
+    ) {
+        for mip_level in range.base_mip_level..range.base_mip_level + range.level_count {
+            for array_layer in range.base_array_layer..range.base_array_layer + range.layer_count {
+                let index = self.subresource_index(mip_level, array_layer);
+                let current_layout = self.subresource_layouts[index];
+                if old_layout != VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED
+                    && old_layout != current_layout
+                {
+                    warn!(
+                        "Image subresource (mip level {mip_level}, array layer {array_layer}) \
+                         transitioned from {old_layout:?}, but it is in {current_layout:?}"
+                    );
+                }
+                self.subresource_layouts[index] = new_layout;
+            }
+        }
+    }
+
+    const fn subresource_index(&self, mip_level: u32, array_layer: u32) -> usize {
+        (mip_level * self.layout.array_layers + array_layer) as usize
+    }
+
+    /// Resolves subresource range against mip levels and array layers of image.
+    pub fn subresource_range(
+        &self,
+        range: &VkImageSubresourceRange,
+    ) -> Result<ImageSubresourceRange, RuntimeError> {
+        ImageSubresourceRange::new(range, &self.layout)
+    }
+
+    /// Checks that copy region lies within subresource of image.
+    pub fn validate_copy_region(
+        &self,
+        subresource: &VkImageSubresourceLayers,
+        offset: VkOffset3D,
+        extent: VkExtent3D,
+    ) -> Result<(), VkResult> {
+        let Some(mip_level) = self.layout.mip_levels.get(subresource.mipLevel as usize) else {
+            error!(
+                "Copy mip level {} is outside of image",
+                subresource.mipLevel
+            );
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        };
+        let within = |offset: i32, size: u32, limit: u32| {
+            offset >= 0 && offset as u64 + size as u64 <= limit as u64
+        };
+        if subresource.layerCount == 0
+            || subresource.baseArrayLayer as u64 + subresource.layerCount as u64
+                > self.layout.array_layers as u64
+            || !within(offset.x, extent.width, mip_level.extent.width)
+            || !within(offset.y, extent.height, mip_level.extent.height)
+            || !within(offset.z, extent.depth, mip_level.extent.depth)
+        {
+            error!(
+                "Copy region {:?} {:?} of {:?} is outside of image",
+                offset, extent, subresource
+            );
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        }
+        Ok(())
     }
 
-    pub const fn size_in_bytes(&self) -> u64 {
-        self.width as u64 * self.height as u64 * self.format.bytes_per_pixel() as u64
+    /// Copies texels of array layer of mip level to `out`, tightly packed rows in format of image.
+    /// Only the first sample of multisampled texels is read.
+    pub fn read_to_host(
+        &self,
+        subresource: &VkImageSubresource,
+        out: &mut [u8],
+    ) -> Result<(), VkResult> {
+        let Some(mip_level) = self.layout.mip_levels.get(subresource.mipLevel as usize) else {
+            error!(
+                "Read mip level {} is outside of image",
+                subresource.mipLevel
+            );
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        };
+        if subresource.arrayLayer >= self.layout.array_layers {
+            error!(
+                "Read array layer {} is outside of image",
+                subresource.arrayLayer
+            );
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        }
+        if self.gpu_binding.size == 0 {
+            error!("Read image is not bound to memory");
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        }
+        let bytes_per_pixel = self.format.bytes_per_pixel() as usize;
+        let extent = mip_level.extent;
+        let row_size = extent.width as usize * bytes_per_pixel;
+        let size = row_size * extent.height as usize * extent.depth as usize;
+        let Some(out) = out.get_mut(..size) else {
+            error!(
+                "Host buffer of {} bytes is smaller than {size} bytes of subresource",
+                out.len()
+            );
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        };
+
+        let offset = self.gpu_binding.offset
+            + self.texel_offset(0, 0, subresource.mipLevel, subresource.arrayLayer);
+        let logical_device = self.logical_device.lock();
+        let physical_device = logical_device.physical_device();
+        let texels =
+            physical_device
+                .gpu
+                .memory
+                .read_bytes(&self.gpu_binding, offset, mip_level.layer_size);
+        let rows = texels.chunks_exact(mip_level.row_pitch as usize);
+        for (row, out) in rows.zip(out.chunks_exact_mut(row_size)) {
+            let texels = row.chunks_exact(self.layout.bytes_per_texel as usize);
+            for (texel, out) in texels.zip(out.chunks_exact_mut(bytes_per_pixel)) {
+                out.copy_from_slice(&texel[..bytes_per_pixel]);
+            }
+        }
+        drop(physical_device);
+        drop(logical_device);
+        Ok(())
     }
 
     pub fn memory_requirements(&self) -> VkMemoryRequirements {
@@ -65,16 +240,14 @@ impl Image {
     }
 
     pub fn subresource_layout(&self, subresource: &VkImageSubresource) -> VkSubresourceLayout {
-        if subresource.aspectMask == VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into()
-            && subresource.arrayLayer == 0
-            && subresource.mipLevel == 0
-        {
+        if subresource.aspectMask == u32::from(VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT) {
+            let mip_level = self.layout.mip_level(subresource.mipLevel);
             VkSubresourceLayout {
-                offset: 0,
-                size: self.size_in_bytes(),
-                rowPitch: self.width as u64 * self.format.bytes_per_pixel() as u64,
-                arrayPitch: 0,
-                depthPitch: 0,
+                offset: self.texel_offset(0, 0, subresource.mipLevel, subresource.arrayLayer),
+                size: mip_level.layer_size,
+                rowPitch: mip_level.row_pitch,
+                arrayPitch: mip_level.layer_size,
+                depthPitch: mip_level.row_pitch * mip_level.extent.height as u64,
             }
         } else {
             unimplemented!("subresource: {:?}", subresource)
@@ -82,11 +255,13 @@ impl Image {
     }
 
     pub fn bind_memory(&mut self, memory: Arc<Mutex<MemoryAllocation>>, offset: u64) -> VkResult {
-        self.gpu_binding.store(
-            memory.lock().gpu_memory_allocation,
-            offset,
-            self.size_in_bytes().saturating_sub(offset),
-        );
+        let memory = memory.lock();
+        let size = self.size_in_bytes();
+        if offset + size > memory.gpu_memory_allocation.size {
+            return VkResult::VK_ERROR_OUT_OF_DEVICE_MEMORY;
+        }
+        self.gpu_binding
+            .store(memory.gpu_memory_allocation, offset, size);
         VkResult::VK_SUCCESS
     }
 
@@ -94,40 +269,384 @@ impl Image {
         let binding = self.gpu_binding.clone();
         DescriptorImage {
             binding,
-            extent: Extent3::<u32> {
-                width: self.width,
-                height: self.height,
-                depth: 1,
-            },
+            extent: self.layout.extent(),
+            components: ComponentMapping::default(),
+        }
+    }
+
+    /// Returns descriptors of every array layer of every mip level within subresource range.
+    pub fn subresource_range_descriptors(
+        &self,
+        range: &VkImageSubresourceRange,
+    ) -> Result<Vec<DescriptorImage>, VkResult> {
+        let range = ImageSubresourceRange::new(range, &self.layout)?;
+        let mip_levels = range.base_mip_level..range.base_mip_level + range.level_count;
+        let array_layers = range.base_array_layer..range.base_array_layer + range.layer_count;
+        Ok(mip_levels
+            .flat_map(|mip_level| {
+                array_layers
+                    .clone()
+                    .map(move |array_layer| self.subresource_descriptor(mip_level, array_layer))
+            })
+            .collect())
+    }
+
+    /// Returns descriptor of single array layer of mip level.
+    pub fn subresource_descriptor(&self, mip_level: u32, array_layer: u32) -> DescriptorImage {
+        let mut binding = self.gpu_binding.clone();
+        binding.offset += self.texel_offset(0, 0, mip_level, array_layer);
+        binding.size = self.layout.mip_level(mip_level).layer_size;
+        DescriptorImage {
+            binding,
+            extent: self.layout.mip_level(mip_level).extent,
+            components: ComponentMapping::default(),
         }
     }
 }
 
+/// Writes base mip level of the first array layer of `image` to binary PPM file, dropping alpha.
+/// Meant for looking at rendered images while debugging.
+pub fn save_ppm(image: &Image, path: impl AsRef<Path>) -> anyhow::Result<()> {
+    let (extent, texels) = read_rgba8(image)?;
+    let mut bytes = format!("P6\n{} {}\n255\n", extent.width, extent.height).into_bytes();
+    bytes.extend(texels.iter().flat_map(|&[r, g, b, _]| [r, g, b]));
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Writes base mip level of the first array layer of `image` to PNG file. Meant for looking at
+/// rendered images while debugging.
+#[cfg(feature = "png")]
+pub fn save_png(image: &Image, path: impl AsRef<Path>) -> anyhow::Result<()> {
+    let (extent, texels) = read_rgba8(image)?;
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, extent.width, extent.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(texels.as_flattened())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Reads texels of base mip level of the first array layer of `image` as 8-bit RGBA the way they
+/// are displayed when presented: sRGB formats keep their encoding and other ones are taken as is.
+fn read_rgba8(image: &Image) -> anyhow::Result<(Extent3<u32>, Vec<[u8; 4]>)> {
+    let format = Format::from(image.format);
+    let bytes_per_pixel = format.info().bytes_per_pixel as usize;
+    let extent = image.layout.extent();
+    let subresource = VkImageSubresource {
+        aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+        mipLevel: 0,
+        arrayLayer: 0,
+    };
+    let texel_count = extent.width as usize * extent.height as usize * extent.depth as usize;
+    let mut bytes = vec![0u8; texel_count * bytes_per_pixel];
+    image
+        .read_to_host(&subresource, &mut bytes)
+        .map_err(|result| anyhow!("Reading image failed with {result:?}"))?;
+    let texels = bytes
+        .chunks_exact(bytes_per_pixel)
+        .take(extent.width as usize * extent.height as usize)
+        .map(|bytes| {
+            let mut texel = read_texel(format, bytes);
+            if format.is_srgb() {
+                for component in &mut texel[..3] {
+                    *component = linear_to_srgb(*component);
+                }
+            }
+            texel.map(|component| (component.clamp(0.0f32, 1.0f32) * 255.0f32).round() as u8)
+        })
+        .collect();
+    Ok((extent, texels))
+}
+
+/// Placement of image texels in bound memory. Mip levels are stored one after another, each holding
+/// all array layers of row-major texels.
+#[derive(Debug, Clone)]
+pub struct ImageLayout {
+    /// Size of texel, or of whole block for block-compressed formats.
+    pub bytes_per_texel: u32,
+    /// Width and height of blocks texels are stored in, which is 1×1 for uncompressed formats.
+    pub block_extent: (u32, u32),
+    pub array_layers: u32,
+    pub mip_levels: Vec<MipLevelLayout>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MipLevelLayout {
+    pub extent: Extent3<u32>,
+    /// Offset of the first array layer.
+    pub offset: u64,
+    pub row_pitch: u64,
+    /// Size of single array layer.
+    pub layer_size: u64,
+}
+
+impl ImageLayout {
+    pub fn new(
+        bytes_per_texel: u32,
+        block_extent: (u32, u32),
+        extent: Extent3<u32>,
+        mip_level_count: u32,
+        array_layers: u32,
+    ) -> Self {
+        let mut offset = 0;
+        let mip_levels = (0..mip_level_count)
+            .map(|mip_level| {
+                let extent = Extent3 {
+                    width: (extent.width >> mip_level).max(1),
+                    height: (extent.height >> mip_level).max(1),
+                    depth: (extent.depth >> mip_level).max(1),
+                };
+                // Partial blocks at right and bottom edges are stored whole.
+                let (block_width, block_height) = block_extent;
+                let row_pitch = extent.width.div_ceil(block_width) as u64 * bytes_per_texel as u64;
+                let rows = extent.height.div_ceil(block_height) as u64;
+                let layer_size = row_pitch * rows * extent.depth as u64;
+                let mip_level = MipLevelLayout {
+                    extent,
+                    offset,
+                    row_pitch,
+                    layer_size,
+                };
+                offset += layer_size * array_layers as u64;
+                mip_level
+            })
+            .collect();
+        Self {
+            bytes_per_texel,
+            block_extent,
+            array_layers,
+            mip_levels,
+        }
+    }
+
+    pub fn extent(&self) -> Extent3<u32> {
+        self.mip_level(0).extent
+    }
+
+    pub fn mip_level(&self, mip_level: u32) -> &MipLevelLayout {
+        let Some(mip_level) = self.mip_levels.get(mip_level as usize) else {
+            unreachable!("mip level {mip_level} out of range")
+        };
+        mip_level
+    }
+
+    pub fn size_in_bytes(&self) -> u64 {
+        self.mip_levels.last().map_or(0, |mip_level| {
+            mip_level.offset + mip_level.layer_size * self.array_layers as u64
+        })
+    }
+
+    /// Returns offset of texel, or of block containing it for block-compressed formats.
+    pub fn texel_offset(&self, x: u32, y: u32, mip_level: u32, array_layer: u32) -> u64 {
+        let mip_level = self.mip_level(mip_level);
+        let (block_width, block_height) = self.block_extent;
+        mip_level.offset
+            + array_layer as u64 * mip_level.layer_size
+            + (y / block_height) as u64 * mip_level.row_pitch
+            + (x / block_width) as u64 * self.bytes_per_texel as u64
+    }
+}
+
+/// Mip levels and array layers accessible through image view.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageSubresourceRange {
+    pub aspect_mask: VkImageAspectFlags,
+    pub base_mip_level: u32,
+    pub level_count: u32,
+    pub base_array_layer: u32,
+    pub layer_count: u32,
+}
+
+impl ImageSubresourceRange {
+    /// Resolves remaining levels and layers of `range` and checks it lies within image.
+    pub fn new(
+        range: &VkImageSubresourceRange,
+        layout: &ImageLayout,
+    ) -> Result<Self, RuntimeError> {
+        let mip_levels = layout.mip_levels.len() as u32;
+        let level_count = if range.levelCount == VK_REMAINING_MIP_LEVELS {
+            mip_levels.saturating_sub(range.baseMipLevel)
+        } else {
+            range.levelCount
+        };
+        let layer_count = if range.layerCount == VK_REMAINING_ARRAY_LAYERS {
+            layout.array_layers.saturating_sub(range.baseArrayLayer)
+        } else {
+            range.layerCount
+        };
+        if level_count == 0
+            || layer_count == 0
+            || range.baseMipLevel as u64 + level_count as u64 > mip_levels as u64
+            || range.baseArrayLayer as u64 + layer_count as u64 > layout.array_layers as u64
+        {
+            error!("Subresource range {range:?} is outside of image");
+            return Err(RuntimeError::ValidationFailed);
+        }
+        Ok(Self {
+            aspect_mask: range.aspectMask,
+            base_mip_level: range.baseMipLevel,
+            level_count,
+            base_array_layer: range.baseArrayLayer,
+            layer_count,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct ImageView {
     pub(crate) handle: VkNonDispatchableHandle,
     #[allow(dead_code)]
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     pub(crate) image: Arc<Mutex<Image>>,
+    pub(crate) format: VkFormat,
+    pub(crate) subresource_range: ImageSubresourceRange,
+    pub(crate) components: ComponentMapping,
 }
 
 impl ImageView {
     pub fn create(
         logical_device: Arc<Mutex<LogicalDevice>>,
         create_info: &VkImageViewCreateInfo,
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new ImageView");
         let handle = VK_NULL_HANDLE;
 
-        let Some(image) = Image::from_handle(create_info.image) else {
-            unreachable!()
-        };
+        let image = Image::lookup(create_info.image)?;
+        let subresource_range =
+            ImageSubresourceRange::new(&create_info.subresourceRange, &image.lock().layout)?;
 
         let object = Self {
             handle,
             logical_device,
             image,
+            format: create_info.format,
+            subresource_range,
+            components: create_info.components.into(),
         };
-        object.register_object()
+        Ok(object.register_object())
+    }
+
+    /// Returns extent of the base mip level of view.
+    pub fn extent(&self) -> Extent3<u32> {
+        self.image
+            .lock()
+            .layout
+            .mip_level(self.subresource_range.base_mip_level)
+            .extent
+    }
+
+    /// Returns descriptor of the base mip level and array layer of view.
+    pub fn descriptor(&self) -> DescriptorImage {
+        let mut descriptor = self.image.lock().subresource_descriptor(
+            self.subresource_range.base_mip_level,
+            self.subresource_range.base_array_layer,
+        );
+        descriptor.components = self.components;
+        descriptor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_layout_rgba_64x64() {
+        let layout = ImageLayout::new(
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM.bytes_per_pixel() as u32,
+            (1, 1),
+            Extent3 {
+                width: 64,
+                height: 64,
+                depth: 1,
+            },
+            1,
+            1,
+        );
+        assert_eq!(layout.size_in_bytes(), 64 * 64 * 4);
+        assert_eq!(layout.mip_level(0).row_pitch, 64 * 4);
+
+        let mut last_offset = None;
+        for y in 0..64 {
+            for x in 0..64 {
+                let offset = layout.texel_offset(x, y, 0, 0);
+                assert!(last_offset.is_none_or(|last_offset| offset > last_offset));
+                last_offset = Some(offset);
+            }
+        }
+        assert_eq!(last_offset, Some(layout.size_in_bytes() - 4));
+    }
+
+    #[test]
+    fn image_layout_bc1_partial_blocks() {
+        let format = VkFormat::VK_FORMAT_BC1_RGBA_UNORM_BLOCK;
+        let layout = ImageLayout::new(
+            format.bytes_per_pixel() as u32,
+            format.block_extent(),
+            Extent3 {
+                width: 10,
+                height: 6,
+                depth: 1,
+            },
+            2,
+            1,
+        );
+        // 3×2 blocks of 8 bytes in base level and 2×1 blocks of 5×3 texels in the next one.
+        assert_eq!(layout.mip_level(0).row_pitch, 3 * 8);
+        assert_eq!(layout.mip_level(1).offset, 3 * 2 * 8);
+        assert_eq!(layout.size_in_bytes(), (3 * 2 + 2) * 8);
+        assert_eq!(layout.texel_offset(3, 3, 0, 0), 0);
+        assert_eq!(layout.texel_offset(9, 5, 0, 0), (3 + 2) * 8);
+        assert_eq!(layout.texel_offset(4, 2, 1, 0), 3 * 2 * 8 + 8);
+    }
+
+    #[test]
+    fn image_subresource_range_mip_level() {
+        let layout = ImageLayout::new(
+            4,
+            (1, 1),
+            Extent3 {
+                width: 64,
+                height: 32,
+                depth: 1,
+            },
+            4,
+            1,
+        );
+        assert_eq!(
+            layout.size_in_bytes(),
+            (64 * 32 + 32 * 16 + 16 * 8 + 8 * 4) * 4
+        );
+
+        let range = VkImageSubresourceRange {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            baseMipLevel: 2,
+            levelCount: 1,
+            baseArrayLayer: 0,
+            layerCount: VK_REMAINING_ARRAY_LAYERS,
+        };
+        let Ok(range) = ImageSubresourceRange::new(&range, &layout) else {
+            unreachable!()
+        };
+        assert_eq!(range.layer_count, 1);
+        let mip_level = layout.mip_level(range.base_mip_level);
+        assert_eq!((mip_level.extent.width, mip_level.extent.height), (16, 8));
+        assert_eq!(mip_level.offset, (64 * 32 + 32 * 16) * 4);
+
+        let range = VkImageSubresourceRange {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            baseMipLevel: 3,
+            levelCount: 2,
+            baseArrayLayer: 0,
+            layerCount: 1,
+        };
+        assert_eq!(
+            ImageSubresourceRange::new(&range, &layout),
+            Err(RuntimeError::ValidationFailed)
+        );
     }
 }
@@ -1,6 +1,7 @@
 //! Instance
 
 use crate::context::Dispatchable;
+use crate::error::RuntimeError;
 
 use crate::physical_device::PhysicalDevice;
 use headers::c_char_array;
@@ -19,10 +20,10 @@ pub struct Instance {
 }
 impl Instance {
     // TODO: Remove all create() accepting create info.
-    pub fn create() -> Result<VkDispatchableHandle, VkResult> {
+    pub fn create() -> Result<VkDispatchableHandle, RuntimeError> {
         let physical_device = PhysicalDevice::create();
         let physical_device = PhysicalDevice::from_handle(physical_device)
-            .map_or_else(|| Err(VkResult::VK_ERROR_INITIALIZATION_FAILED), Ok)?;
+            .map_or_else(|| Err(RuntimeError::InitializationFailed), Ok)?;
 
         let instance = Self {
             handle: VkDispatchableHandle(None),
@@ -43,7 +44,17 @@ impl Instance {
         Self::extension_properties().len()
     }
 
-    pub fn extension_properties() -> [VkExtensionProperties; 2] {
+    pub fn extension_properties() -> [VkExtensionProperties; 7] {
+        c_char_array!(
+            VK_KHR_GET_PHYSICAL_DEVICE_PROPERTIES_2_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_KHR_get_physical_device_properties2"
+        );
+        c_char_array!(
+            VK_EXT_DEBUG_UTILS_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_EXT_debug_utils"
+        );
         c_char_array!(
             VK_KHR_SURFACE_EXTENSION_NAME,
             VK_MAX_EXTENSION_NAME_SIZE,
@@ -54,7 +65,30 @@ impl Instance {
             VK_MAX_EXTENSION_NAME_SIZE,
             "VK_KHR_xcb_surface"
         );
+        c_char_array!(
+            VK_KHR_WAYLAND_SURFACE_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_KHR_wayland_surface"
+        );
+        c_char_array!(
+            VK_EXT_HEADLESS_SURFACE_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_EXT_headless_surface"
+        );
+        c_char_array!(
+            VK_KHR_EXTERNAL_MEMORY_CAPABILITIES_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_KHR_external_memory_capabilities"
+        );
         [
+            VkExtensionProperties {
+                extensionName: *VK_KHR_GET_PHYSICAL_DEVICE_PROPERTIES_2_EXTENSION_NAME,
+                specVersion: 2,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_EXT_DEBUG_UTILS_EXTENSION_NAME,
+                specVersion: 2,
+            },
             VkExtensionProperties {
                 extensionName: *VK_KHR_SURFACE_EXTENSION_NAME,
                 specVersion: 25,
@@ -63,6 +97,18 @@ impl Instance {
                 extensionName: *VK_KHR_XCB_SURFACE_EXTENSION_NAME,
                 specVersion: 6,
             },
+            VkExtensionProperties {
+                extensionName: *VK_KHR_WAYLAND_SURFACE_EXTENSION_NAME,
+                specVersion: 6,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_EXT_HEADLESS_SURFACE_EXTENSION_NAME,
+                specVersion: 1,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_KHR_EXTERNAL_MEMORY_CAPABILITIES_EXTENSION_NAME,
+                specVersion: 1,
+            },
         ]
     }
 }
@@ -1,14 +1,20 @@
 pub mod buffer;
 pub mod command_buffer;
 pub mod context;
+pub mod debug_utils;
 pub mod descriptor;
+pub mod error;
+pub mod event;
 pub mod fence;
+#[cfg(test)]
+mod golden;
 pub mod image;
 pub mod instance;
 pub mod logical_device;
 pub mod memory;
 pub mod physical_device;
 pub mod pipeline;
+pub mod query;
 pub mod queue;
 pub mod sampler;
 pub mod semaphore;
@@ -1,10 +1,12 @@
 //! LogicalDevice
 
-use crate::context::Dispatchable;
+use crate::context::{Context, Dispatchable, Named};
+use crate::error::RuntimeError;
 use crate::fence::Fence;
 
 use crate::physical_device::PhysicalDevice;
-use crate::queue::Queue;
+use crate::queue::{Queue, Schedule};
+use crate::semaphore;
 
 use headers::vk_decls::*;
 
@@ -18,68 +20,190 @@ use std::sync::Arc;
 pub struct LogicalDevice {
     pub(crate) handle: VkDispatchableHandle,
     physical_device: Arc<Mutex<PhysicalDevice>>,
-    #[allow(dead_code)]
     enabled_features: VkPhysicalDeviceFeatures,
-    queue: Arc<Mutex<Queue>>,
+    /// Queues created by device, indexed by their family and index within it.
+    queues: Vec<Vec<Arc<Mutex<Queue>>>>,
+    /// Queues of device along with their state that is accessed without locking them.
+    schedules: Vec<(Arc<Mutex<Queue>>, Arc<Schedule>)>,
 }
 
 impl LogicalDevice {
     pub fn create(
         physical_device: Arc<Mutex<PhysicalDevice>>,
         enabled_features: Option<&VkPhysicalDeviceFeatures>,
-        queue_create_info: &VkDeviceQueueCreateInfo,
-    ) -> Result<VkDispatchableHandle, VkResult> {
+        queue_create_infos: &[VkDeviceQueueCreateInfo],
+    ) -> Result<VkDispatchableHandle, RuntimeError> {
         info!("new LogicalDevice");
 
         if enabled_features.is_some_and(|x| !physical_device.lock().supports_features(x)) {
-            Err(VkResult::VK_ERROR_FEATURE_NOT_PRESENT)?;
+            Err(RuntimeError::FeatureNotPresent)?;
         }
 
-        let queue = Queue::create(physical_device.clone(), queue_create_info);
-        let queue = Queue::from_handle(queue)
-            .map_or_else(|| Err(VkResult::VK_ERROR_INITIALIZATION_FAILED), Ok)?;
+        let queue_family_properties = physical_device.lock().queue_family_properties();
+        let mut queues = vec![vec![]; queue_family_properties.len()];
+        let mut schedules = vec![];
+        for queue_create_info in queue_create_infos {
+            let family_index = queue_create_info.queueFamilyIndex as usize;
+            let Some(family_properties) = queue_family_properties.get(family_index) else {
+                error!("Queue family index {} is out of range", family_index);
+                return Err(RuntimeError::InitializationFailed);
+            };
+            // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkDeviceCreateInfo-queueFamilyIndex-02802
+            if !queues[family_index].is_empty() {
+                error!(
+                    "Queues of family {} are created more than once",
+                    family_index
+                );
+                return Err(RuntimeError::InitializationFailed);
+            }
+            if queue_create_info.queueCount == 0
+                || queue_create_info.queueCount > family_properties.queueCount
+            {
+                error!(
+                    "Queue count {} of family {} is not in 1..={}",
+                    queue_create_info.queueCount, family_index, family_properties.queueCount
+                );
+                return Err(RuntimeError::InitializationFailed);
+            }
+            let Some(queue_priorities) = queue_create_info.pQueuePriorities else {
+                error!("Queue priorities of family {} are missing", family_index);
+                return Err(RuntimeError::InitializationFailed);
+            };
+            let queue_priorities = unsafe {
+                std::slice::from_raw_parts(
+                    queue_priorities.as_ptr(),
+                    queue_create_info.queueCount as usize,
+                )
+            };
+            // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkDeviceQueueCreateInfo-pQueuePriorities-00383
+            if let Some(priority) = queue_priorities
+                .iter()
+                .find(|priority| !(0.0..=1.0).contains(*priority))
+            {
+                error!(
+                    "Queue priority {} of family {} is not in 0.0..=1.0",
+                    priority, family_index
+                );
+                return Err(RuntimeError::InitializationFailed);
+            }
+            for &priority in queue_priorities {
+                let queue =
+                    Queue::create(physical_device.clone(), queue_create_info.flags, priority);
+                let queue = Queue::from_handle(queue)
+                    .map_or_else(|| Err(RuntimeError::InitializationFailed), Ok)?;
+                let schedule = queue.lock().schedule();
+                schedules.push((queue.clone(), schedule));
+                queues[family_index].push(queue);
+            }
+        }
 
         let logical_device = Self {
             handle: VkDispatchableHandle(None),
             physical_device: physical_device.clone(),
             enabled_features: *enabled_features.unwrap_or(&physical_device.lock().features()),
-            queue,
+            queues,
+            schedules,
         };
         Ok(logical_device.register_object())
     }
 
+    /// Destroys device of `handle` along with its queues and objects created by it that weren't
+    /// destroyed yet.
+    pub fn destroy(handle: VkDispatchableHandle) {
+        let Some(logical_device) = Self::from_handle(handle) else {
+            return;
+        };
+        Context::drop_objects_of_device(&logical_device);
+        let queues = logical_device.lock().queues.concat();
+        for queue in queues {
+            let queue = queue.lock().handle;
+            Queue::drop_handle(queue);
+        }
+        Self::drop_handle(handle);
+    }
+
     pub fn physical_device(&self) -> MutexGuard<'_, PhysicalDevice> {
         self.physical_device.lock()
     }
+
+    pub const fn enabled_features(&self) -> &VkPhysicalDeviceFeatures {
+        &self.enabled_features
+    }
 }
 
 impl LogicalDevice {
-    pub fn queue(&self, queue_family_index: u32, queue_index: u32) -> Arc<Mutex<Queue>> {
-        let _ = queue_family_index;
-        let _ = queue_index;
-        self.queue.clone()
+    /// Returns queue of index within family, `None` if device didn't create it.
+    pub fn queue(&self, queue_family_index: u32, queue_index: u32) -> Option<Arc<Mutex<Queue>>> {
+        self.queues
+            .get(queue_family_index as usize)?
+            .get(queue_index as usize)
+            .cloned()
     }
 
-    pub fn wait_for_fences(&self, fences: Vec<Arc<Mutex<Fence>>>, wait_all: bool, timeout: u64) {
-        let _ = wait_all;
-        let _ = timeout;
-        for fence in fences {
-            if fence.lock().logical_device.data_ptr() as *const _ != self as *const _ {
-                continue;
+    /// Waits for all or any of `fences` created by `logical_device` to become signaled, skipping
+    /// fences of other devices, for at most `timeout` nanoseconds.
+    ///
+    /// Work waiting for events or semaphores signals its fence once another thread signals them
+    /// and it resumes.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkWaitForFences
+    pub fn wait_for_fences(
+        logical_device: &Arc<Mutex<Self>>,
+        fences: Vec<Arc<Mutex<Fence>>>,
+        wait_all: bool,
+        timeout: u64,
+    ) -> VkResult {
+        semaphore::wait_for_signal(timeout, || {
+            let mut signaled = fences.iter().filter_map(|fence| {
+                let fence = fence.lock();
+                Arc::ptr_eq(&fence.logical_device, logical_device).then(|| fence.is_signaled())
+            });
+            if wait_all {
+                signaled.all(|signaled| signaled)
+            } else {
+                signaled.any(|signaled| signaled)
             }
-            warn!("TODO: Wait for one or more fences to become signaled");
+        })
+    }
+
+    /// Resumes work submitted to queues of `logical_device` that waits for events or semaphores,
+    /// after one of them was signaled.
+    ///
+    /// Queues executing work, which may be the work that signaled, resume their pending
+    /// submissions themselves before they stop.
+    pub fn resume_queues(logical_device: &Arc<Mutex<Self>>) {
+        let schedules = logical_device.lock().schedules.clone();
+        for (queue, schedule) in schedules {
+            schedule.resume(&queue);
         }
     }
 
+    /// Returns whether no work submitted to queues of `logical_device` is still pending, as it
+    /// waits for events.
+    pub fn is_idle(logical_device: &Arc<Mutex<Self>>) -> bool {
+        let queues = logical_device.lock().queues.concat();
+        queues.iter().all(|queue| queue.lock().is_idle())
+    }
+
     pub fn reset_fences(&self, fences: Vec<Arc<Mutex<Fence>>>) {
         for fence in fences {
-            warn!("TODO: VUID-vkResetFences-pFences-01123");
-            fence.lock().reset();
+            let mut fence = fence.lock();
+            warn!(
+                "TODO: VUID-vkResetFences-pFences-01123: {}",
+                Named::from(fence.handle)
+            );
+            fence.reset();
         }
     }
 
-    pub fn wait_idle(&self) -> VkResult {
-        warn!("TODO: LogicalDevice wait idle");
+    /// Blocks until work submitted to every queue of `logical_device` has completed.
+    pub fn wait_idle(logical_device: &Arc<Mutex<Self>>) -> VkResult {
+        let queues = logical_device.lock().queues.concat();
+        for queue in queues {
+            let result = Queue::wait_idle(&queue);
+            if result != VkResult::VK_SUCCESS {
+                return result;
+            }
+        }
         VkResult::VK_SUCCESS
     }
 
@@ -102,7 +226,471 @@ impl Debug for LogicalDevice {
         f.debug_struct("LogicalDevice")
             .field("handle", &self.handle)
             .field("physical_device", &self.physical_device)
-            .field("queue", &self.queue)
+            .field("queues", &self.queues)
             .finish()
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+    use crate::command_buffer::tests::{
+        create_buffer_with_bytes, create_command_buffer, create_image,
+    };
+    use crate::command_buffer::CommandPool;
+    use crate::context::NonDispatchable;
+    use crate::image::Image;
+    use crate::queue::SubmitInfo;
+    use crate::semaphore::Semaphore;
+
+    /// Creates logical device with single queue.
+    pub fn create_logical_device() -> Arc<Mutex<LogicalDevice>> {
+        create_logical_device_with_features(None)
+    }
+
+    /// Creates timeline semaphore of device with counter starting at 0.
+    pub fn create_timeline_semaphore(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+    ) -> Arc<Mutex<Semaphore>> {
+        let semaphore_type_create_info = VkSemaphoreTypeCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_TYPE_CREATE_INFO,
+            pNext: None,
+            semaphoreType: VkSemaphoreType::VK_SEMAPHORE_TYPE_TIMELINE,
+            initialValue: 0,
+        };
+        let semaphore_create_info = VkSemaphoreCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+            pNext: Some(NonNull::from(&semaphore_type_create_info).cast()),
+            flags: 0,
+        };
+        let Ok(semaphore) = Semaphore::create(logical_device, &semaphore_create_info) else {
+            unreachable!()
+        };
+        let Some(semaphore) = Semaphore::from_handle(semaphore) else {
+            unreachable!()
+        };
+        semaphore
+    }
+
+    /// Creates logical device with single queue and given features enabled, or all supported ones
+    /// if `None`.
+    pub fn create_logical_device_with_features(
+        enabled_features: Option<&VkPhysicalDeviceFeatures>,
+    ) -> Arc<Mutex<LogicalDevice>> {
+        let Some(physical_device) = PhysicalDevice::from_handle(PhysicalDevice::create()) else {
+            unreachable!()
+        };
+        let queue_priority = 1.0f32;
+        let queue_create_info = VkDeviceQueueCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queueFamilyIndex: 0,
+            queueCount: 1,
+            pQueuePriorities: Some(NonNull::from(&queue_priority)),
+        };
+        let Ok(logical_device) =
+            LogicalDevice::create(physical_device, enabled_features, &[queue_create_info])
+        else {
+            unreachable!()
+        };
+        let Some(logical_device) = LogicalDevice::from_handle(logical_device) else {
+            unreachable!()
+        };
+        logical_device
+    }
+
+    #[test]
+    fn queues_are_created_per_family() {
+        let Some(physical_device) = PhysicalDevice::from_handle(PhysicalDevice::create()) else {
+            unreachable!()
+        };
+        let transfer_family_properties = physical_device.lock().queue_family_properties()[1];
+        assert_eq!(
+            transfer_family_properties.queueFlags,
+            VkQueueFlagBits::VK_QUEUE_TRANSFER_BIT.0
+        );
+        assert!(!physical_device.lock().surface_support(1, VK_NULL_HANDLE));
+
+        let queue_priority = 1.0f32;
+        let queue_create_infos = [0, 1].map(|queue_family_index| VkDeviceQueueCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queueFamilyIndex: queue_family_index,
+            queueCount: 1,
+            pQueuePriorities: Some(NonNull::from(&queue_priority)),
+        });
+        let Ok(logical_device) = LogicalDevice::create(physical_device, None, &queue_create_infos)
+        else {
+            unreachable!()
+        };
+        let Some(logical_device) = LogicalDevice::from_handle(logical_device) else {
+            unreachable!()
+        };
+
+        let logical_device = logical_device.lock();
+        let (Some(graphics_queue), Some(transfer_queue)) =
+            (logical_device.queue(0, 0), logical_device.queue(1, 0))
+        else {
+            unreachable!()
+        };
+        assert!(!Arc::ptr_eq(&graphics_queue, &transfer_queue));
+        assert!(logical_device.queue(0, 1).is_none());
+        assert!(logical_device.queue(2, 0).is_none());
+        drop(logical_device);
+    }
+
+    #[test]
+    fn queues_of_same_family_are_independent() {
+        let Some(physical_device) = PhysicalDevice::from_handle(PhysicalDevice::create()) else {
+            unreachable!()
+        };
+        let queue_priorities = [1.0f32, 0.5];
+        let queue_create_info = VkDeviceQueueCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queueFamilyIndex: 0,
+            queueCount: 2,
+            pQueuePriorities: Some(NonNull::from(&queue_priorities[0])),
+        };
+        let Ok(logical_device) = LogicalDevice::create(physical_device, None, &[queue_create_info])
+        else {
+            unreachable!()
+        };
+        let Some(logical_device) = LogicalDevice::from_handle(logical_device) else {
+            unreachable!()
+        };
+        let Some(first_queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        let Some(second_queue) = logical_device.lock().queue(0, 1) else {
+            unreachable!()
+        };
+        assert!(!Arc::ptr_eq(&first_queue, &second_queue));
+        assert_eq!(first_queue.lock().priority(), 1.0);
+        assert_eq!(second_queue.lock().priority(), 0.5);
+        assert!(logical_device.lock().queue(0, 2).is_none());
+
+        let fence_create_info = VkFenceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let [first_fence, second_fence] = [(); 2].map(|_| {
+            let Ok(fence) = Fence::create(logical_device.clone(), &fence_create_info) else {
+                unreachable!()
+            };
+            Fence::from_handle(fence).unwrap_or_else(|| unreachable!())
+        });
+
+        // Each queue fills its own buffer, so that work of one queue is told apart from the other.
+        let [first_buffer, second_buffer] =
+            [(); 2].map(|_| create_buffer_with_bytes(logical_device.clone(), &[0; 16]));
+        let submit = |queue: &Mutex<Queue>, buffer, value, fence| {
+            let command_buffer = create_command_buffer(logical_device.clone());
+            let mut command_buffer_guard = command_buffer.lock();
+            assert_eq!(command_buffer_guard.begin(), Ok(()));
+            command_buffer_guard.cmd_fill_buffer(Arc::clone(buffer), 0, 16, value);
+            assert_eq!(command_buffer_guard.end(), Ok(()));
+            drop(command_buffer_guard);
+            queue.lock().submit(
+                [SubmitInfo {
+                    command_buffers: vec![command_buffer],
+                    ..Default::default()
+                }],
+                Some(Arc::clone(fence)),
+            );
+        };
+        let read_words = |buffer: &Mutex<Buffer>| {
+            let descriptor = buffer.lock().descriptor();
+            let logical_device = logical_device.lock();
+            let bytes = logical_device
+                .physical_device()
+                .gpu
+                .memory
+                .read_bytes(&descriptor.binding, 0, 16)
+                .to_vec();
+            drop(logical_device);
+            bytes
+                .chunks_exact(4)
+                .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+                .collect::<Vec<_>>()
+        };
+
+        submit(&first_queue, &first_buffer, 0x1111_1111, &first_fence);
+        assert!(first_fence.lock().is_signaled());
+        assert!(!second_fence.lock().is_signaled());
+        assert_eq!(read_words(&first_buffer), [0x1111_1111; 4]);
+        assert_eq!(read_words(&second_buffer), [0; 4]);
+
+        submit(&second_queue, &second_buffer, 0x2222_2222, &second_fence);
+        assert!(second_fence.lock().is_signaled());
+        assert_eq!(read_words(&first_buffer), [0x1111_1111; 4]);
+        assert_eq!(read_words(&second_buffer), [0x2222_2222; 4]);
+    }
+
+    #[test]
+    fn submit2_signals_timeline_semaphore() {
+        let logical_device = create_logical_device();
+        let semaphore_type_create_info = VkSemaphoreTypeCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_TYPE_CREATE_INFO,
+            pNext: None,
+            semaphoreType: VkSemaphoreType::VK_SEMAPHORE_TYPE_TIMELINE,
+            initialValue: 1,
+        };
+        let semaphore_create_info = VkSemaphoreCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+            pNext: Some(NonNull::from(&semaphore_type_create_info).cast()),
+            flags: 0,
+        };
+        let Ok(handle) = Semaphore::create(logical_device.clone(), &semaphore_create_info) else {
+            unreachable!()
+        };
+        let Some(semaphore) = Semaphore::from_handle(handle) else {
+            unreachable!()
+        };
+        assert_eq!(semaphore.lock().counter_value(), 1);
+
+        let semaphore_submit_info = |value| VkSemaphoreSubmitInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_SUBMIT_INFO,
+            pNext: None,
+            semaphore: handle,
+            value,
+            stageMask: VkPipelineStageFlagBits2::VK_PIPELINE_STAGE_2_ALL_COMMANDS_BIT.into(),
+            deviceIndex: 0,
+        };
+        let wait_semaphore_info = semaphore_submit_info(1);
+        let signal_semaphore_info = semaphore_submit_info(5);
+        let submit_info = VkSubmitInfo2 {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SUBMIT_INFO_2,
+            pNext: None,
+            flags: 0,
+            waitSemaphoreInfoCount: 1,
+            pWaitSemaphoreInfos: Some(NonNull::from(&wait_semaphore_info)),
+            commandBufferInfoCount: 0,
+            pCommandBufferInfos: None,
+            signalSemaphoreInfoCount: 1,
+            pSignalSemaphoreInfos: Some(NonNull::from(&signal_semaphore_info)),
+        };
+
+        let wait_for_value = || Semaphore::wait_for_values(&[(semaphore.clone(), 5)], false, 0);
+        assert_eq!(wait_for_value(), VkResult::VK_TIMEOUT);
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        unsafe { queue.lock().submit2(&[submit_info], None) };
+        assert_eq!(wait_for_value(), VkResult::VK_SUCCESS);
+        assert_eq!(semaphore.lock().counter_value(), 5);
+        Semaphore::drop_handle(handle);
+    }
+
+    #[test]
+    fn wait_for_values_blocks_until_other_thread_signals() {
+        let logical_device = create_logical_device();
+        let semaphore_type_create_info = VkSemaphoreTypeCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_TYPE_CREATE_INFO,
+            pNext: None,
+            semaphoreType: VkSemaphoreType::VK_SEMAPHORE_TYPE_TIMELINE,
+            initialValue: 0,
+        };
+        let semaphore_create_info = VkSemaphoreCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+            pNext: Some(NonNull::from(&semaphore_type_create_info).cast()),
+            flags: 0,
+        };
+        let Ok(handle) = Semaphore::create(logical_device, &semaphore_create_info) else {
+            unreachable!()
+        };
+        let Some(semaphore) = Semaphore::from_handle(handle) else {
+            unreachable!()
+        };
+        let wait_for_value =
+            |timeout| Semaphore::wait_for_values(&[(semaphore.clone(), 1)], false, timeout);
+        assert_eq!(wait_for_value(1_000_000), VkResult::VK_TIMEOUT);
+
+        let signaling_semaphore = semaphore.clone();
+        let signaling_thread = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            Semaphore::signal_and_resume(&signaling_semaphore, 1);
+        });
+        assert_eq!(wait_for_value(u64::MAX), VkResult::VK_SUCCESS);
+        assert!(signaling_thread.join().is_ok());
+        Semaphore::drop_handle(handle);
+    }
+
+    #[test]
+    fn wait_idle_completes_submitted_work() {
+        let logical_device = create_logical_device();
+        let fence_create_info = VkFenceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let fences = (0..2)
+            .map(|_| {
+                let Ok(fence) = Fence::create(logical_device.clone(), &fence_create_info) else {
+                    unreachable!()
+                };
+                let Some(fence) = Fence::from_handle(fence) else {
+                    unreachable!()
+                };
+                fence
+            })
+            .collect::<Vec<_>>();
+
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        for fence in &fences {
+            queue
+                .lock()
+                .submit([SubmitInfo::default()], Some(fence.clone()));
+        }
+        assert_eq!(
+            LogicalDevice::wait_idle(&logical_device),
+            VkResult::VK_SUCCESS
+        );
+        assert!(fences.iter().all(|fence| fence.lock().is_signaled()));
+    }
+
+    #[test]
+    fn wait_for_fences_waits_only_for_fences_of_device() {
+        let create_fence = |logical_device: &Arc<Mutex<LogicalDevice>>, flags| {
+            let fence_create_info = VkFenceCreateInfo {
+                sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+                pNext: None,
+                flags,
+            };
+            let Ok(fence) = Fence::create(logical_device.clone(), &fence_create_info) else {
+                unreachable!()
+            };
+            Fence::from_handle(fence).unwrap_or_else(|| unreachable!())
+        };
+        let (signaled_device, unsignaled_device) =
+            (create_logical_device(), create_logical_device());
+        let signaled = VkFenceCreateFlagBits::VK_FENCE_CREATE_SIGNALED_BIT.into();
+        let fences = vec![
+            create_fence(&signaled_device, signaled),
+            create_fence(&unsignaled_device, 0),
+        ];
+
+        let wait = |logical_device, wait_all| {
+            LogicalDevice::wait_for_fences(logical_device, fences.clone(), wait_all, 0)
+        };
+        assert_eq!(wait(&signaled_device, true), VkResult::VK_SUCCESS);
+        assert_eq!(wait(&unsignaled_device, true), VkResult::VK_TIMEOUT);
+        assert_eq!(wait(&unsignaled_device, false), VkResult::VK_TIMEOUT);
+    }
+
+    #[test]
+    fn wait_for_fences_blocks_until_other_thread_signals() {
+        let logical_device = create_logical_device();
+        let semaphore = create_timeline_semaphore(logical_device.clone());
+        let fence_create_info = VkFenceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let Ok(fence) = Fence::create(logical_device.clone(), &fence_create_info) else {
+            unreachable!()
+        };
+        let Some(fence) = Fence::from_handle(fence) else {
+            unreachable!()
+        };
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                wait_semaphores: vec![semaphore.clone()],
+                wait_semaphore_values: vec![1],
+                ..Default::default()
+            }],
+            Some(fence.clone()),
+        );
+
+        let wait = |timeout| {
+            LogicalDevice::wait_for_fences(&logical_device, vec![fence.clone()], true, timeout)
+        };
+        assert_eq!(wait(1_000_000), VkResult::VK_TIMEOUT);
+        let signaling_thread = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            Semaphore::signal_and_resume(&semaphore, 1);
+        });
+        assert_eq!(wait(u64::MAX), VkResult::VK_SUCCESS);
+        assert!(signaling_thread.join().is_ok());
+    }
+
+    #[test]
+    fn wait_idle_blocks_until_other_thread_resumes_work() {
+        let logical_device = create_logical_device();
+        let semaphore = create_timeline_semaphore(logical_device.clone());
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                wait_semaphores: vec![semaphore.clone()],
+                wait_semaphore_values: vec![1],
+                ..Default::default()
+            }],
+            None,
+        );
+        assert!(!LogicalDevice::is_idle(&logical_device));
+
+        let signaling_thread = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            Semaphore::signal_and_resume(&semaphore, 1);
+        });
+        assert_eq!(
+            LogicalDevice::wait_idle(&logical_device),
+            VkResult::VK_SUCCESS
+        );
+        assert!(LogicalDevice::is_idle(&logical_device));
+        assert!(signaling_thread.join().is_ok());
+    }
+
+    #[test]
+    fn destroy_drops_objects_of_device() {
+        let create_objects = |logical_device: &Arc<Mutex<LogicalDevice>>| {
+            let image = create_image(
+                logical_device.clone(),
+                VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+                (4, 4),
+                1,
+                1,
+            );
+            let command_pool_create_info = VkCommandPoolCreateInfo {
+                sType: VkStructureType::VK_STRUCTURE_TYPE_COMMAND_POOL_CREATE_INFO,
+                pNext: None,
+                flags: 0,
+                queueFamilyIndex: 0,
+            };
+            let image = image.lock().handle;
+            let command_pool =
+                CommandPool::create(logical_device.clone(), &command_pool_create_info);
+            (image, command_pool)
+        };
+        let (destroyed_device, other_device) = (create_logical_device(), create_logical_device());
+        let destroyed_objects = create_objects(&destroyed_device);
+        let other_objects = create_objects(&other_device);
+        let handle = destroyed_device.lock().handle;
+        let Some(queue) = destroyed_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        let queue = queue.lock().handle;
+
+        LogicalDevice::destroy(handle);
+        assert!(LogicalDevice::from_handle(handle).is_none());
+        assert!(Queue::from_handle(queue).is_none());
+        let (image, command_pool) = destroyed_objects;
+        assert!(Image::from_handle(image).is_none());
+        assert!(CommandPool::from_handle(command_pool).is_none());
+        let (image, command_pool) = other_objects;
+        assert!(Image::from_handle(image).is_some());
+        assert!(CommandPool::from_handle(command_pool).is_some());
+    }
+}
@@ -1,19 +1,28 @@
 //! Device memory allocation
 
 use crate::context::NonDispatchable;
+use crate::error::RuntimeError;
 use crate::logical_device::LogicalDevice;
 use headers::vk_decls::*;
 use log::*;
 use parking_lot::Mutex;
 use std::fmt::Debug;
+use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
 use std::sync::Arc;
 
+type HandleType = VkExternalMemoryHandleTypeFlagBits;
+type Feature = VkExternalMemoryFeatureFlagBits;
+
 #[derive(Debug)]
 pub struct MemoryAllocation {
     pub(crate) handle: VkNonDispatchableHandle,
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     pub(crate) gpu_memory_allocation: gpu::MemoryAllocation,
     state: MemoryAllocationState,
+    /// File descriptor backing memory that was imported or is exportable, if any.
+    mapped_fd: Option<MappedFd>,
+    /// Handle types memory can be exported as.
+    export_handle_types: VkExternalMemoryHandleTypeFlags,
 }
 
 #[derive(Debug)]
@@ -27,10 +36,10 @@ impl MemoryAllocation {
         logical_device: Arc<Mutex<LogicalDevice>>,
         size: u64,
         memory_type_index: u32,
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new DeviceMemory");
         let handle = VK_NULL_HANDLE;
-        let _ = memory_type_index; // TODO: Acquire MemoryType from PhysicalDevice.
+        Self::validate_memory_type(&logical_device, memory_type_index)?;
 
         let object = Self {
             handle,
@@ -42,10 +51,132 @@ impl MemoryAllocation {
                 .memory
                 .allocate_memory(size),
             state: MemoryAllocationState::HostUnmapped,
+            mapped_fd: None,
+            export_handle_types: 0,
+        };
+        Ok(object.register_object())
+    }
+
+    /// Allocates memory backed by anonymous file, so that it can be exported as fd of any of
+    /// `handle_types`, which must all be exportable.
+    pub fn create_exportable(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        size: u64,
+        memory_type_index: u32,
+        handle_types: VkExternalMemoryHandleTypeFlags,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
+        info!("new exportable DeviceMemory");
+        if !is_supported(
+            handle_types,
+            Feature::VK_EXTERNAL_MEMORY_FEATURE_EXPORTABLE_BIT,
+        ) {
+            error!(
+                "Unsupported external memory handle types {:#x}",
+                handle_types
+            );
+            return Err(RuntimeError::InvalidExternalHandle);
+        }
+        Self::validate_memory_type(&logical_device, memory_type_index)?;
+        let mapped_fd = MappedFd::create(size)?;
+        Ok(Self::create_mapped(
+            logical_device,
+            size,
+            mapped_fd,
+            handle_types,
+        ))
+    }
+
+    /// Allocates memory backed by mapping of `fd` instead of fresh bytes. Ownership of `fd` is
+    /// transferred to allocation only if import succeeds.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VkImportMemoryFdInfoKHR
+    pub fn import_fd(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        size: u64,
+        memory_type_index: u32,
+        handle_type: HandleType,
+        fd: RawFd,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
+        info!("import DeviceMemory from fd {}", fd);
+        if !is_supported(
+            handle_type.into(),
+            Feature::VK_EXTERNAL_MEMORY_FEATURE_IMPORTABLE_BIT,
+        ) {
+            error!("Unsupported external memory handle type {:?}", handle_type);
+            return Err(RuntimeError::InvalidExternalHandle);
+        }
+        Self::validate_memory_type(&logical_device, memory_type_index)?;
+
+        let mapped_fd = MappedFd::map(fd, size)?;
+        Ok(Self::create_mapped(logical_device, size, mapped_fd, 0))
+    }
+
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkAllocateMemory-pAllocateInfo-01714
+    fn validate_memory_type(
+        logical_device: &Mutex<LogicalDevice>,
+        memory_type_index: u32,
+    ) -> Result<(), RuntimeError> {
+        let memory_type_count = logical_device
+            .lock()
+            .physical_device()
+            .memory_properties()
+            .memoryTypeCount;
+        if memory_type_index >= memory_type_count {
+            error!(
+                "Memory type {} is not one of {} memory types",
+                memory_type_index, memory_type_count
+            );
+            return Err(RuntimeError::ValidationFailed);
+        }
+        Ok(())
+    }
+
+    fn create_mapped(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        size: u64,
+        mapped_fd: MappedFd,
+        export_handle_types: VkExternalMemoryHandleTypeFlags,
+    ) -> VkNonDispatchableHandle {
+        let handle = VK_NULL_HANDLE;
+        let gpu_memory_allocation = unsafe {
+            logical_device
+                .lock()
+                .physical_device()
+                .gpu
+                .memory
+                .import_memory(mapped_fd.ptr, size)
+        };
+        let object = Self {
+            handle,
+            logical_device,
+            gpu_memory_allocation,
+            state: MemoryAllocationState::HostUnmapped,
+            mapped_fd: Some(mapped_fd),
+            export_handle_types,
         };
         object.register_object()
     }
 
+    /// Returns new fd of `handle_type` referencing same pages as memory, owned by caller.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkGetMemoryFdKHR
+    pub fn export_fd(&self, handle_type: HandleType) -> Result<RawFd, VkResult> {
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkMemoryGetFdInfoKHR-handleType-00671
+        let mapped_fd = self
+            .mapped_fd
+            .as_ref()
+            .filter(|_| self.export_handle_types & handle_type.0 != 0);
+        let Some(mapped_fd) = mapped_fd else {
+            error!("Memory is not exportable as {:?}", handle_type);
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        };
+        match mapped_fd.fd.try_clone() {
+            Ok(fd) => Ok(fd.into_raw_fd()),
+            Err(err) => {
+                error!("Exporting fd {:?} failed: {}", mapped_fd.fd, err);
+                Err(VkResult::VK_ERROR_TOO_MANY_OBJECTS)
+            }
+        }
+    }
+
     pub fn map_host(
         &mut self,
         offset: u64,
@@ -97,6 +228,7 @@ impl MemoryAllocation {
 
 impl Drop for MemoryAllocation {
     fn drop(&mut self) {
+        // Imported fd is unmapped only after allocation stops referencing its bytes.
         self.logical_device
             .lock()
             .physical_device()
@@ -105,3 +237,324 @@ impl Drop for MemoryAllocation {
             .free_memory(self.gpu_memory_allocation);
     }
 }
+
+/// Returns how memory can be imported from or exported as fds of `handle_type`.
+///
+/// Memory is exported as fd of anonymous file backing it, which is only opaque as it isn't a
+/// dma-buf. Both opaque fds and dma-bufs can be imported by mapping them.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VkExternalMemoryProperties
+pub fn external_memory_properties(handle_type: HandleType) -> VkExternalMemoryProperties {
+    let features = match handle_type {
+        HandleType::VK_EXTERNAL_MEMORY_HANDLE_TYPE_OPAQUE_FD_BIT => {
+            Feature::VK_EXTERNAL_MEMORY_FEATURE_EXPORTABLE_BIT
+                | Feature::VK_EXTERNAL_MEMORY_FEATURE_IMPORTABLE_BIT
+        }
+        HandleType::VK_EXTERNAL_MEMORY_HANDLE_TYPE_DMA_BUF_BIT_EXT => {
+            Feature::VK_EXTERNAL_MEMORY_FEATURE_IMPORTABLE_BIT
+        }
+        _ => VkExternalMemoryFeatureFlagBits(0),
+    };
+    VkExternalMemoryProperties {
+        externalMemoryFeatures: features.0,
+        // Imported memory can't be exported.
+        exportFromImportedHandleTypes: 0,
+        compatibleHandleTypes: if features.0 == 0 { 0 } else { handle_type.0 },
+    }
+}
+
+/// Returns whether memory can be imported from or exported as fds of all of `handle_types`, as
+/// given by `feature`.
+fn is_supported(handle_types: VkExternalMemoryHandleTypeFlags, feature: Feature) -> bool {
+    (0..VkExternalMemoryHandleTypeFlags::BITS)
+        .map(|bit| handle_types & (1 << bit))
+        .filter(|&handle_type| handle_type != 0)
+        .all(|handle_type| {
+            external_memory_properties(VkExternalMemoryHandleTypeFlagBits(handle_type))
+                .externalMemoryFeatures
+                & feature.0
+                != 0
+        })
+}
+
+/// Shared mapping of file descriptor backing memory, unmapped and closed on drop.
+#[derive(Debug)]
+struct MappedFd {
+    fd: OwnedFd,
+    ptr: NonNull<u8>,
+    size: usize,
+}
+
+// SAFETY: Mapping is only accessed through `gpu::Memory`, which is accessed under lock.
+unsafe impl Send for MappedFd {}
+unsafe impl Sync for MappedFd {}
+
+impl MappedFd {
+    /// Creates anonymous file of `size` bytes and maps it.
+    fn create(size: u64) -> Result<Self, RuntimeError> {
+        let fd = unsafe { libc::memfd_create(c"DeviceMemory".as_ptr(), libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            error!("Creating memfd failed: {}", std::io::Error::last_os_error());
+            return Err(RuntimeError::OutOfDeviceMemory);
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        if unsafe { libc::ftruncate(fd.as_raw_fd(), size as libc::off_t) } != 0 {
+            error!(
+                "Resizing memfd to {} bytes failed: {}",
+                size,
+                std::io::Error::last_os_error()
+            );
+            return Err(RuntimeError::OutOfDeviceMemory);
+        }
+        let mapped_fd = Self::map(fd.as_raw_fd(), size)?;
+        // Mapping owns fd from now on.
+        let _ = fd.into_raw_fd();
+        Ok(mapped_fd)
+    }
+
+    /// Maps first `size` bytes of `fd`, taking ownership of it on success.
+    fn map(fd: RawFd, size: u64) -> Result<Self, RuntimeError> {
+        let mut stat = unsafe { std::mem::zeroed::<libc::stat>() };
+        if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+            error!("Fd {} is invalid: {}", fd, std::io::Error::last_os_error());
+            return Err(RuntimeError::InvalidExternalHandle);
+        }
+        if (stat.st_size as u64) < size {
+            error!(
+                "Fd {} of size {} is smaller than allocation size {}",
+                fd, stat.st_size, size
+            );
+            return Err(RuntimeError::InvalidExternalHandle);
+        }
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                size as usize,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            )
+        };
+        let ptr = NonNull::new(ptr.cast::<u8>()).filter(|_| ptr != libc::MAP_FAILED);
+        let Some(ptr) = ptr else {
+            error!(
+                "Mapping fd {} failed: {}",
+                fd,
+                std::io::Error::last_os_error()
+            );
+            return Err(RuntimeError::InvalidExternalHandle);
+        };
+        Ok(Self {
+            // SAFETY: Ownership of fd is transferred to mapping on success.
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            ptr,
+            size: size as usize,
+        })
+    }
+}
+
+impl Drop for MappedFd {
+    fn drop(&mut self) {
+        // Fd itself is closed when dropped after this.
+        if unsafe { libc::munmap(self.ptr.as_ptr().cast(), self.size) } != 0 {
+            warn!(
+                "Unmapping fd {:?} failed: {}",
+                self.fd,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+// Importing memory is tested with memfd, which only Linux has.
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::command_buffer::tests::{
+        create_image_view, create_unbound_image, render_triangle_into,
+    };
+    use crate::context::NonDispatchable;
+    use crate::logical_device::tests::create_logical_device;
+
+    const OPAQUE_FD: HandleType = HandleType::VK_EXTERNAL_MEMORY_HANDLE_TYPE_OPAQUE_FD_BIT;
+    const DMA_BUF: HandleType = HandleType::VK_EXTERNAL_MEMORY_HANDLE_TYPE_DMA_BUF_BIT_EXT;
+
+    /// Creates anonymous file of `size` bytes.
+    fn create_memfd(size: u64) -> OwnedFd {
+        let fd = unsafe { libc::memfd_create(c"imported".as_ptr(), libc::MFD_CLOEXEC) };
+        assert!(fd >= 0);
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        assert_eq!(
+            unsafe { libc::ftruncate(fd.as_raw_fd(), size as libc::off_t) },
+            0
+        );
+        fd
+    }
+
+    #[test]
+    fn image_renders_into_imported_memfd() {
+        let logical_device = create_logical_device();
+        let image = create_unbound_image(
+            logical_device.clone(),
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            (4, 4),
+            1,
+            1,
+        );
+        let size = image.lock().size_in_bytes();
+        let memfd = create_memfd(size);
+
+        // Fd that is too small stays owned by application.
+        let small_memfd = create_memfd(size - 1);
+        assert_eq!(
+            MemoryAllocation::import_fd(
+                logical_device.clone(),
+                size,
+                0,
+                OPAQUE_FD,
+                small_memfd.as_raw_fd(),
+            ),
+            Err(RuntimeError::InvalidExternalHandle)
+        );
+        drop(small_memfd);
+
+        // Imported fd is owned by allocation, while the original one is read back separately.
+        let Ok(imported_fd) = memfd.try_clone() else {
+            unreachable!()
+        };
+        let Ok(handle) = MemoryAllocation::import_fd(
+            logical_device.clone(),
+            size,
+            0,
+            OPAQUE_FD,
+            imported_fd.into_raw_fd(),
+        ) else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(handle) else {
+            unreachable!()
+        };
+        assert_eq!(image.lock().bind_memory(memory, 0), VkResult::VK_SUCCESS);
+        let image_handle = image.lock().handle;
+        let image_view = create_image_view(
+            logical_device.clone(),
+            image_handle,
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT,
+        );
+        render_triangle_into(&logical_device, image_view);
+
+        let Ok(imported) = MappedFd::map(memfd.into_raw_fd(), size) else {
+            unreachable!()
+        };
+        let bytes = unsafe { std::slice::from_raw_parts(imported.ptr.as_ptr(), imported.size) };
+        // Triangle covers texels above the diagonal, the rest keeps clear color.
+        let expected = (0..4u32)
+            .flat_map(|y| (0..4u32).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                if x + y < 3 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 255, 0, 255]
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bytes, expected);
+        drop(imported);
+        drop(image);
+        MemoryAllocation::drop_handle(handle);
+    }
+
+    #[test]
+    fn exported_fd_shares_pages_written_through_mapping() {
+        let logical_device = create_logical_device();
+        let size = 64;
+        let Ok(handle) =
+            MemoryAllocation::create_exportable(logical_device, size, 0, OPAQUE_FD.into())
+        else {
+            unreachable!()
+        };
+        let Some(memory) = MemoryAllocation::from_handle(handle) else {
+            unreachable!()
+        };
+        let mut memory = memory.lock();
+        let Ok(ptr) = memory.map_host(0, VK_WHOLE_SIZE) else {
+            unreachable!()
+        };
+        let pattern = (0..size as u8).collect::<Vec<_>>();
+        unsafe { std::slice::from_raw_parts_mut(ptr.as_ptr().cast::<u8>(), pattern.len()) }
+            .copy_from_slice(&pattern);
+        memory.unmap_host();
+
+        assert_eq!(
+            memory.export_fd(DMA_BUF),
+            Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT)
+        );
+        let Ok(fd) = memory.export_fd(OPAQUE_FD) else {
+            unreachable!()
+        };
+        drop(memory);
+        MemoryAllocation::drop_handle(handle);
+
+        // Exported fd keeps pages alive after memory is freed.
+        let Ok(exported) = MappedFd::map(fd, size) else {
+            unreachable!()
+        };
+        let bytes = unsafe { std::slice::from_raw_parts(exported.ptr.as_ptr(), exported.size) };
+        assert_eq!(bytes, pattern);
+    }
+
+    #[test]
+    fn allocation_of_unknown_memory_type_fails() {
+        let logical_device = create_logical_device();
+        let memory_type_count = logical_device
+            .lock()
+            .physical_device()
+            .memory_properties()
+            .memoryTypeCount;
+        assert!(
+            MemoryAllocation::create(logical_device.clone(), 64, memory_type_count - 1).is_ok()
+        );
+        assert_eq!(
+            MemoryAllocation::create(logical_device, 64, memory_type_count),
+            Err(RuntimeError::ValidationFailed)
+        );
+    }
+
+    #[test]
+    fn memfd_is_not_exportable_as_dma_buf() {
+        let logical_device = create_logical_device();
+        assert_eq!(
+            MemoryAllocation::create_exportable(
+                logical_device,
+                64,
+                0,
+                (OPAQUE_FD | DMA_BUF).into(),
+            ),
+            Err(RuntimeError::InvalidExternalHandle)
+        );
+
+        let properties = external_memory_properties(DMA_BUF);
+        assert_eq!(
+            properties.externalMemoryFeatures,
+            Feature::VK_EXTERNAL_MEMORY_FEATURE_IMPORTABLE_BIT.0
+        );
+        assert_eq!(properties.compatibleHandleTypes, DMA_BUF.0);
+        let properties = external_memory_properties(OPAQUE_FD);
+        assert_ne!(
+            properties.externalMemoryFeatures
+                & Feature::VK_EXTERNAL_MEMORY_FEATURE_EXPORTABLE_BIT.0,
+            0
+        );
+        let properties = external_memory_properties(
+            HandleType::VK_EXTERNAL_MEMORY_HANDLE_TYPE_HOST_ALLOCATION_BIT_EXT,
+        );
+        assert_eq!(
+            (
+                properties.externalMemoryFeatures,
+                properties.compatibleHandleTypes
+            ),
+            (0, 0)
+        );
+    }
+}
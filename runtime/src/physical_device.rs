@@ -1,16 +1,23 @@
 //! PhysicalDevice
 
-use crate::context::{Dispatchable, NonDispatchable};
-use crate::pipeline::ShaderModule;
+use crate::context::{Dispatchable, Named, NonDispatchable};
+use crate::pipeline::{PipelineCache, ShaderModule};
+use crate::surface::Surface;
 use common::consts::{
-    MAX_VERTEX_ATTRIBUTES, MAX_VERTEX_ATTRIBUTE_OFFSET, MAX_VERTEX_BINDINGS,
-    MAX_VERTEX_BINDING_STRIDE, MAX_VIEWPORTS, MAX_VIEWPORT_DIMENSIONS, VIEWPORT_BOUNDS_RANGE,
+    LINE_WIDTH_GRANULARITY, LINE_WIDTH_RANGE, MAX_BOUND_DESCRIPTOR_SETS, MAX_COLOR_ATTACHMENTS,
+    MAX_COMPUTE_SHARED_MEMORY_SIZE, MAX_COMPUTE_WORK_GROUP_COUNT,
+    MAX_COMPUTE_WORK_GROUP_INVOCATIONS, MAX_COMPUTE_WORK_GROUP_SIZE, MAX_PUSH_CONSTANTS_SIZE,
+    MAX_SAMPLER_LOD_BIAS, MAX_VERTEX_ATTRIBUTES, MAX_VERTEX_ATTRIBUTE_OFFSET, MAX_VERTEX_BINDINGS,
+    MAX_VERTEX_BINDING_STRIDE, MAX_VIEWPORTS, MAX_VIEWPORT_DIMENSIONS,
+    MIN_STORAGE_BUFFER_OFFSET_ALIGNMENT, MIN_UNIFORM_BUFFER_OFFSET_ALIGNMENT,
+    POINT_SIZE_GRANULARITY, POINT_SIZE_RANGE, SAMPLE_COUNTS, VIEWPORT_BOUNDS_RANGE,
 };
 use common::graphics::{
     VertexAttribute, VertexBinding, VertexBindingNumber, VertexInputRate, VertexInputState,
 };
-use common::math::{Extent2, Offset2, Range2};
+use common::math::{Color, Extent2, Offset2, Range2};
 use gpu::{
+    ColorBlendAttachmentState, ColorBlendState, DepthStencilState, DynamicState,
     InputAssemblyState, PrimitiveTopology, RasterizationState, RenderArea, Scissor, Viewport,
     ViewportState,
 };
@@ -18,9 +25,16 @@ use headers::c_char_array;
 use headers::vk_decls::*;
 use lazy_static::lazy_static;
 use log::*;
-use shader::glsl::{Shader, ShaderState};
+use parking_lot::Mutex;
+use shader::glsl::{Shader, ShaderState, SpecializationInfo};
 use std::fmt::{Debug, Formatter};
 
+/// Identifies pipeline cache data compatible with this device, to be changed whenever contents of
+/// cached shaders change.
+pub const PIPELINE_CACHE_UUID: [u8; VK_UUID_SIZE as usize] = [
+    0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x9, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15,
+];
+
 /// Performs rendering operations.
 pub struct PhysicalDevice {
     pub(crate) handle: VkDispatchableHandle,
@@ -40,10 +54,18 @@ impl Debug for PhysicalDevice {
 impl PhysicalDevice {
     pub fn create() -> VkDispatchableHandle {
         info!("new PhysicalDevice");
+        let mut gpu = gpu::Gpu::new();
+        // Tiled rasterizer is opt-in, so that its results can be compared with simple one.
+        let rasterizer_backend = match std::env::var("ICD_RASTERIZER").as_deref() {
+            Ok("tiled") => gpu::RasterizerBackend::Tiled,
+            _ => gpu::RasterizerBackend::Simple,
+        };
+        gpu.graphics_pipeline
+            .set_rasterizer_backend(rasterizer_backend);
         let physical_device = Self {
             handle: VkDispatchableHandle(None),
             physical_device_name: "VkSWR physical device",
-            gpu: gpu::Gpu::new(),
+            gpu,
         };
         physical_device.register_object()
     }
@@ -52,7 +74,7 @@ impl PhysicalDevice {
         Self::extension_properties().len()
     }
 
-    pub fn extension_properties() -> [VkExtensionProperties; 3] {
+    pub fn extension_properties() -> [VkExtensionProperties; 11] {
         c_char_array!(
             VK_KHR_SWAPCHAIN_EXTENSION_NAME,
             VK_MAX_EXTENSION_NAME_SIZE,
@@ -68,6 +90,46 @@ impl PhysicalDevice {
             VK_MAX_EXTENSION_NAME_SIZE,
             "VK_EXT_debug_marker"
         );
+        c_char_array!(
+            VK_KHR_DYNAMIC_RENDERING_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_KHR_dynamic_rendering"
+        );
+        c_char_array!(
+            VK_KHR_SYNCHRONIZATION_2_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_KHR_synchronization2"
+        );
+        c_char_array!(
+            VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_KHR_timeline_semaphore"
+        );
+        c_char_array!(
+            VK_KHR_EXTERNAL_MEMORY_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_KHR_external_memory"
+        );
+        c_char_array!(
+            VK_KHR_EXTERNAL_MEMORY_FD_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_KHR_external_memory_fd"
+        );
+        c_char_array!(
+            VK_EXT_EXTERNAL_MEMORY_DMA_BUF_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_EXT_external_memory_dma_buf"
+        );
+        c_char_array!(
+            VK_KHR_PUSH_DESCRIPTOR_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_KHR_push_descriptor"
+        );
+        c_char_array!(
+            VK_EXT_HOST_QUERY_RESET_EXTENSION_NAME,
+            VK_MAX_EXTENSION_NAME_SIZE,
+            "VK_EXT_host_query_reset"
+        );
         [
             VkExtensionProperties {
                 extensionName: *VK_KHR_SWAPCHAIN_EXTENSION_NAME,
@@ -81,6 +143,38 @@ impl PhysicalDevice {
                 extensionName: *VK_KHR_DEBUG_MARKER_NAME,
                 specVersion: 4,
             },
+            VkExtensionProperties {
+                extensionName: *VK_KHR_DYNAMIC_RENDERING_EXTENSION_NAME,
+                specVersion: 1,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_KHR_SYNCHRONIZATION_2_EXTENSION_NAME,
+                specVersion: 1,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_KHR_TIMELINE_SEMAPHORE_EXTENSION_NAME,
+                specVersion: 2,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_KHR_EXTERNAL_MEMORY_EXTENSION_NAME,
+                specVersion: 1,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_KHR_EXTERNAL_MEMORY_FD_EXTENSION_NAME,
+                specVersion: 1,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_EXT_EXTERNAL_MEMORY_DMA_BUF_EXTENSION_NAME,
+                specVersion: 1,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_KHR_PUSH_DESCRIPTOR_EXTENSION_NAME,
+                specVersion: 2,
+            },
+            VkExtensionProperties {
+                extensionName: *VK_EXT_HOST_QUERY_RESET_EXTENSION_NAME,
+                specVersion: 1,
+            },
         ]
     }
 
@@ -98,10 +192,7 @@ impl PhysicalDevice {
             deviceID: 0,
             deviceType: VkPhysicalDeviceType::VK_PHYSICAL_DEVICE_TYPE_OTHER,
             deviceName: *DEVICE_NAME,
-            pipelineCacheUUID: [
-                0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x9, 0x10, 0x11, 0x12, 0x13, 0x14,
-                0x15,
-            ],
+            pipelineCacheUUID: PIPELINE_CACHE_UUID,
             limits: VkPhysicalDeviceLimits {
                 maxImageDimension1D: 0,
                 maxImageDimension2D: 0,
@@ -111,12 +202,12 @@ impl PhysicalDevice {
                 maxTexelBufferElements: 0,
                 maxUniformBufferRange: 0,
                 maxStorageBufferRange: 0,
-                maxPushConstantsSize: 0,
+                maxPushConstantsSize: MAX_PUSH_CONSTANTS_SIZE,
                 maxMemoryAllocationCount: 0,
                 maxSamplerAllocationCount: 0,
                 bufferImageGranularity: 0,
                 sparseAddressSpaceSize: 0,
-                maxBoundDescriptorSets: 0,
+                maxBoundDescriptorSets: MAX_BOUND_DESCRIPTOR_SETS,
                 maxPerStageDescriptorSamplers: 0,
                 maxPerStageDescriptorUniformBuffers: 0,
                 maxPerStageDescriptorStorageBuffers: 0,
@@ -151,19 +242,19 @@ impl PhysicalDevice {
                 maxGeometryOutputVertices: 0,
                 maxGeometryTotalOutputComponents: 0,
                 maxFragmentInputComponents: 0,
-                maxFragmentOutputAttachments: 0,
+                maxFragmentOutputAttachments: MAX_COLOR_ATTACHMENTS,
                 maxFragmentDualSrcAttachments: 0,
                 maxFragmentCombinedOutputResources: 0,
-                maxComputeSharedMemorySize: 0,
-                maxComputeWorkGroupCount: [0, 0, 0],
-                maxComputeWorkGroupInvocations: 0,
-                maxComputeWorkGroupSize: [0, 0, 0],
+                maxComputeSharedMemorySize: MAX_COMPUTE_SHARED_MEMORY_SIZE,
+                maxComputeWorkGroupCount: MAX_COMPUTE_WORK_GROUP_COUNT,
+                maxComputeWorkGroupInvocations: MAX_COMPUTE_WORK_GROUP_INVOCATIONS,
+                maxComputeWorkGroupSize: MAX_COMPUTE_WORK_GROUP_SIZE,
                 subPixelPrecisionBits: 0,
                 subTexelPrecisionBits: 0,
                 mipmapPrecisionBits: 0,
                 maxDrawIndexedIndexValue: 0,
                 maxDrawIndirectCount: 0,
-                maxSamplerLodBias: 0.0,
+                maxSamplerLodBias: MAX_SAMPLER_LOD_BIAS,
                 maxSamplerAnisotropy: 0.0,
                 maxViewports: MAX_VIEWPORTS,
                 maxViewportDimensions: [MAX_VIEWPORT_DIMENSIONS.0, MAX_VIEWPORT_DIMENSIONS.1],
@@ -171,8 +262,8 @@ impl PhysicalDevice {
                 viewportSubPixelBits: 0,
                 minMemoryMapAlignment: 0,
                 minTexelBufferOffsetAlignment: 0,
-                minUniformBufferOffsetAlignment: 0,
-                minStorageBufferOffsetAlignment: 0,
+                minUniformBufferOffsetAlignment: MIN_UNIFORM_BUFFER_OFFSET_ALIGNMENT,
+                minStorageBufferOffsetAlignment: MIN_STORAGE_BUFFER_OFFSET_ALIGNMENT,
                 minTexelOffset: 0,
                 maxTexelOffset: 0,
                 minTexelGatherOffset: 0,
@@ -183,29 +274,30 @@ impl PhysicalDevice {
                 maxFramebufferWidth: 0,
                 maxFramebufferHeight: 0,
                 maxFramebufferLayers: 0,
-                framebufferColorSampleCounts: 0,
-                framebufferDepthSampleCounts: 0,
-                framebufferStencilSampleCounts: 0,
-                framebufferNoAttachmentsSampleCounts: 0,
-                maxColorAttachments: 0,
+                framebufferColorSampleCounts: SAMPLE_COUNTS,
+                framebufferDepthSampleCounts: SAMPLE_COUNTS,
+                framebufferStencilSampleCounts: SAMPLE_COUNTS,
+                framebufferNoAttachmentsSampleCounts: SAMPLE_COUNTS,
+                maxColorAttachments: MAX_COLOR_ATTACHMENTS,
                 sampledImageColorSampleCounts: 0,
                 sampledImageIntegerSampleCounts: 0,
                 sampledImageDepthSampleCounts: 0,
                 sampledImageStencilSampleCounts: 0,
                 storageImageSampleCounts: 0,
                 maxSampleMaskWords: 0,
-                timestampComputeAndGraphics: 0,
-                timestampPeriod: 0.0,
+                timestampComputeAndGraphics: VK_TRUE,
+                // Timestamps count nanoseconds.
+                timestampPeriod: 1.0,
                 maxClipDistances: 0,
                 maxCullDistances: 0,
                 maxCombinedClipAndCullDistances: 0,
                 discreteQueuePriorities: 0,
-                pointSizeRange: [0.0, 0.0],
-                lineWidthRange: [0.0, 0.0],
-                pointSizeGranularity: 0.0,
-                lineWidthGranularity: 0.0,
+                pointSizeRange: [POINT_SIZE_RANGE.0, POINT_SIZE_RANGE.1],
+                lineWidthRange: [LINE_WIDTH_RANGE.0, LINE_WIDTH_RANGE.1],
+                pointSizeGranularity: POINT_SIZE_GRANULARITY,
+                lineWidthGranularity: LINE_WIDTH_GRANULARITY,
                 strictLines: 0,
-                standardSampleLocations: 0,
+                standardSampleLocations: VK_TRUE,
                 optimalBufferCopyOffsetAlignment: 0,
                 optimalBufferCopyRowPitchAlignment: 0,
                 nonCoherentAtomSize: 1,
@@ -280,26 +372,27 @@ impl PhysicalDevice {
             robustBufferAccess: VK_FALSE,
             fullDrawIndexUint32: VK_FALSE,
             imageCubeArray: VK_FALSE,
-            independentBlend: VK_FALSE,
+            independentBlend: VK_TRUE,
             geometryShader: VK_FALSE,
             tessellationShader: VK_FALSE,
             sampleRateShading: VK_FALSE,
             dualSrcBlend: VK_FALSE,
-            logicOp: VK_FALSE,
+            logicOp: VK_TRUE,
             multiDrawIndirect: VK_FALSE,
             drawIndirectFirstInstance: VK_FALSE,
             depthClamp: VK_FALSE,
             depthBiasClamp: VK_FALSE,
-            fillModeNonSolid: VK_FALSE,
-            depthBounds: VK_FALSE,
-            wideLines: VK_FALSE,
-            largePoints: VK_FALSE,
+            fillModeNonSolid: VK_TRUE,
+            depthBounds: VK_TRUE,
+            wideLines: VK_TRUE,
+            largePoints: VK_TRUE,
             alphaToOne: VK_FALSE,
             multiViewport: VK_FALSE,
             samplerAnisotropy: VK_FALSE,
             textureCompressionETC2: VK_TRUE,
             textureCompressionASTC_LDR: VK_TRUE,
-            textureCompressionBC: VK_TRUE,
+            // BC4 to BC7 blocks aren't decoded yet.
+            textureCompressionBC: VK_FALSE,
             occlusionQueryPrecise: VK_FALSE,
             pipelineStatisticsQuery: VK_FALSE,
             vertexPipelineStoresAndAtomics: VK_FALSE,
@@ -594,6 +687,113 @@ impl PhysicalDevice {
         true
     }
 
+    /// Fills `features` along with structures of its `pNext` chain describing features of
+    /// extensions, leaving structures of unknown types as they are.
+    ///
+    /// # Safety
+    ///
+    /// Structures of `pNext` chain must be valid structures of type given by their `sType`.
+    pub unsafe fn features2(&self, features: &mut VkPhysicalDeviceFeatures2) {
+        features.features = self.features();
+        for structure in unsafe { out_structures(features.pNext) } {
+            match unsafe { structure.as_ref().sType } {
+                VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_VULKAN_1_1_FEATURES => {
+                    let features = unsafe {
+                        structure
+                            .cast::<VkPhysicalDeviceVulkan11Features>()
+                            .as_mut()
+                    };
+                    features.storageBuffer16BitAccess = VK_FALSE;
+                    features.uniformAndStorageBuffer16BitAccess = VK_FALSE;
+                    features.storagePushConstant16 = VK_FALSE;
+                    features.storageInputOutput16 = VK_FALSE;
+                    features.multiview = VK_FALSE;
+                    features.multiviewGeometryShader = VK_FALSE;
+                    features.multiviewTessellationShader = VK_FALSE;
+                    features.variablePointersStorageBuffer = VK_FALSE;
+                    features.variablePointers = VK_FALSE;
+                    features.protectedMemory = VK_FALSE;
+                    features.samplerYcbcrConversion = VK_FALSE;
+                    features.shaderDrawParameters = VK_FALSE;
+                }
+                VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_TIMELINE_SEMAPHORE_FEATURES => {
+                    let features = unsafe {
+                        structure
+                            .cast::<VkPhysicalDeviceTimelineSemaphoreFeatures>()
+                            .as_mut()
+                    };
+                    features.timelineSemaphore = VK_TRUE;
+                }
+                VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_SYNCHRONIZATION_2_FEATURES => {
+                    let features = unsafe {
+                        structure
+                            .cast::<VkPhysicalDeviceSynchronization2Features>()
+                            .as_mut()
+                    };
+                    features.synchronization2 = VK_TRUE;
+                }
+                VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_DYNAMIC_RENDERING_FEATURES => {
+                    let features = unsafe {
+                        structure
+                            .cast::<VkPhysicalDeviceDynamicRenderingFeatures>()
+                            .as_mut()
+                    };
+                    features.dynamicRendering = VK_TRUE;
+                }
+                VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_HOST_QUERY_RESET_FEATURES => {
+                    let features = unsafe {
+                        structure
+                            .cast::<VkPhysicalDeviceHostQueryResetFeatures>()
+                            .as_mut()
+                    };
+                    features.hostQueryReset = VK_TRUE;
+                }
+                s_type => trace!("Unknown structure {:?} in features chain", s_type),
+            }
+        }
+    }
+
+    /// Fills `properties` along with structures of its `pNext` chain describing properties of
+    /// extensions, leaving structures of unknown types as they are.
+    ///
+    /// # Safety
+    ///
+    /// Structures of `pNext` chain must be valid structures of type given by their `sType`.
+    pub unsafe fn properties2(&self, properties: &mut VkPhysicalDeviceProperties2) {
+        properties.properties = self.properties();
+        for structure in unsafe { out_structures(properties.pNext) } {
+            match unsafe { structure.as_ref().sType } {
+                VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_MAINTENANCE_3_PROPERTIES => {
+                    let properties = unsafe {
+                        structure
+                            .cast::<VkPhysicalDeviceMaintenance3Properties>()
+                            .as_mut()
+                    };
+                    properties.maxPerSetDescriptors = 1024;
+                    properties.maxMemoryAllocationSize = gpu::Memory::memory_size_in_bytes() / 2;
+                }
+                VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_TIMELINE_SEMAPHORE_PROPERTIES => {
+                    let properties = unsafe {
+                        structure
+                            .cast::<VkPhysicalDeviceTimelineSemaphoreProperties>()
+                            .as_mut()
+                    };
+                    properties.maxTimelineSemaphoreValueDifference = u64::MAX;
+                }
+                VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_PUSH_DESCRIPTOR_PROPERTIES_KHR => {
+                    let properties = unsafe {
+                        structure
+                            .cast::<VkPhysicalDevicePushDescriptorPropertiesKHR>()
+                            .as_mut()
+                    };
+                    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#limits-maxPushDescriptors
+                    properties.maxPushDescriptors = 32;
+                }
+                s_type => trace!("Unknown structure {:?} in properties chain", s_type),
+            }
+        }
+    }
+
     pub fn format_properties(&self, format: VkFormat) -> VkFormatProperties {
         let unsupported = VkFormatProperties {
             linearTilingFeatures: 0,
@@ -1474,7 +1674,13 @@ impl PhysicalDevice {
                 ),
                 bufferFeatures: 0,
             },
-            VkFormat::VK_FORMAT_S8_UINT => unsupported,
+            VkFormat::VK_FORMAT_S8_UINT => VkFormatProperties {
+                linearTilingFeatures: 0,
+                optimalTilingFeatures: VkFormatFeatureFlags::from(
+                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT,
+                ),
+                bufferFeatures: 0,
+            },
             VkFormat::VK_FORMAT_D16_UNORM_S8_UINT => unsupported,
             VkFormat::VK_FORMAT_D24_UNORM_S8_UINT => VkFormatProperties {
                 linearTilingFeatures: 0,
@@ -1578,94 +1784,14 @@ impl PhysicalDevice {
                 ),
                 bufferFeatures: 0,
             },
-            VkFormat::VK_FORMAT_BC4_UNORM_BLOCK => VkFormatProperties {
-                linearTilingFeatures: 0,
-                optimalTilingFeatures: VkFormatFeatureFlags::from(
-                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_BLIT_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_DST_BIT,
-                ),
-                bufferFeatures: 0,
-            },
-            VkFormat::VK_FORMAT_BC4_SNORM_BLOCK => VkFormatProperties {
-                linearTilingFeatures: 0,
-                optimalTilingFeatures: VkFormatFeatureFlags::from(
-                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_BLIT_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_DST_BIT,
-                ),
-                bufferFeatures: 0,
-            },
-            VkFormat::VK_FORMAT_BC5_UNORM_BLOCK => VkFormatProperties {
-                linearTilingFeatures: 0,
-                optimalTilingFeatures: VkFormatFeatureFlags::from(
-                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_BLIT_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_DST_BIT,
-                ),
-                bufferFeatures: 0,
-            },
-            VkFormat::VK_FORMAT_BC5_SNORM_BLOCK => VkFormatProperties {
-                linearTilingFeatures: 0,
-                optimalTilingFeatures: VkFormatFeatureFlags::from(
-                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_BLIT_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_DST_BIT,
-                ),
-                bufferFeatures: 0,
-            },
-            VkFormat::VK_FORMAT_BC6H_UFLOAT_BLOCK => VkFormatProperties {
-                linearTilingFeatures: 0,
-                optimalTilingFeatures: VkFormatFeatureFlags::from(
-                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_BLIT_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_DST_BIT,
-                ),
-                bufferFeatures: 0,
-            },
-            VkFormat::VK_FORMAT_BC6H_SFLOAT_BLOCK => VkFormatProperties {
-                linearTilingFeatures: 0,
-                optimalTilingFeatures: VkFormatFeatureFlags::from(
-                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_BLIT_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_DST_BIT,
-                ),
-                bufferFeatures: 0,
-            },
-            VkFormat::VK_FORMAT_BC7_UNORM_BLOCK => VkFormatProperties {
-                linearTilingFeatures: 0,
-                optimalTilingFeatures: VkFormatFeatureFlags::from(
-                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_BLIT_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_DST_BIT,
-                ),
-                bufferFeatures: 0,
-            },
-            VkFormat::VK_FORMAT_BC7_SRGB_BLOCK => VkFormatProperties {
-                linearTilingFeatures: 0,
-                optimalTilingFeatures: VkFormatFeatureFlags::from(
-                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_BLIT_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_SRC_BIT
-                        | VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_TRANSFER_DST_BIT,
-                ),
-                bufferFeatures: 0,
-            },
+            VkFormat::VK_FORMAT_BC4_UNORM_BLOCK => unsupported,
+            VkFormat::VK_FORMAT_BC4_SNORM_BLOCK => unsupported,
+            VkFormat::VK_FORMAT_BC5_UNORM_BLOCK => unsupported,
+            VkFormat::VK_FORMAT_BC5_SNORM_BLOCK => unsupported,
+            VkFormat::VK_FORMAT_BC6H_UFLOAT_BLOCK => unsupported,
+            VkFormat::VK_FORMAT_BC6H_SFLOAT_BLOCK => unsupported,
+            VkFormat::VK_FORMAT_BC7_UNORM_BLOCK => unsupported,
+            VkFormat::VK_FORMAT_BC7_SRGB_BLOCK => unsupported,
             VkFormat::VK_FORMAT_ETC2_R8G8B8_UNORM_BLOCK => VkFormatProperties {
                 linearTilingFeatures: 0,
                 optimalTilingFeatures: VkFormatFeatureFlags::from(
@@ -2157,7 +2283,7 @@ impl PhysicalDevice {
         &self,
         format: VkFormat,
         type_: VkImageType,
-        _tiling: VkImageTiling,
+        tiling: VkImageTiling,
         usage: VkImageUsageFlags,
         _flags: VkImageCreateFlags,
     ) -> Option<VkImageFormatProperties> {
@@ -2191,6 +2317,26 @@ impl PhysicalDevice {
             VkImageType(3_u32..=u32::MAX) => unreachable!(),
         };
 
+        // Only optimally tiled 2D images can be multisampled.
+        let sample_counts = if type_ == VkImageType::VK_IMAGE_TYPE_2D
+            && tiling == VkImageTiling::VK_IMAGE_TILING_OPTIMAL
+        {
+            SAMPLE_COUNTS
+        } else {
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT.into()
+        };
+
+        // Block-compressed formats are only sampled from optimally tiled 2D images.
+        let block_compressed = (type_ == VkImageType::VK_IMAGE_TYPE_2D
+            && tiling == VkImageTiling::VK_IMAGE_TILING_OPTIMAL)
+            .then_some(VkImageFormatProperties {
+                maxExtent: max_extent,
+                maxMipLevels: 1,
+                maxArrayLayers: 1, // TODO: VkPhysicalDeviceLimits::maxImageArrayLayers
+                sampleCounts: VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT.into(),
+                maxResourceSize: 2_u64.pow(31), // TODO: VK_ERROR_OUT_OF_DEVICE_MEMORY
+            });
+
         match format {
             VkFormat::VK_FORMAT_UNDEFINED => None,
             VkFormat::VK_FORMAT_R4G4_UNORM_PACK8 => None,
@@ -2233,7 +2379,7 @@ impl PhysicalDevice {
                 maxExtent: max_extent,
                 maxMipLevels: 1,
                 maxArrayLayers: 1, // TODO: VkPhysicalDeviceLimits::maxImageArrayLayers
-                sampleCounts: VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT.into(),
+                sampleCounts: sample_counts,
                 maxResourceSize: 2_u64.pow(31), // TODO: VK_ERROR_OUT_OF_DEVICE_MEMORY
             }),
             VkFormat::VK_FORMAT_R8G8B8A8_SNORM => None,
@@ -2329,14 +2475,14 @@ impl PhysicalDevice {
             VkFormat::VK_FORMAT_D16_UNORM_S8_UINT => None,
             VkFormat::VK_FORMAT_D24_UNORM_S8_UINT => None,
             VkFormat::VK_FORMAT_D32_SFLOAT_S8_UINT => None,
-            VkFormat::VK_FORMAT_BC1_RGB_UNORM_BLOCK => None,
-            VkFormat::VK_FORMAT_BC1_RGB_SRGB_BLOCK => None,
-            VkFormat::VK_FORMAT_BC1_RGBA_UNORM_BLOCK => None,
-            VkFormat::VK_FORMAT_BC1_RGBA_SRGB_BLOCK => None,
-            VkFormat::VK_FORMAT_BC2_UNORM_BLOCK => None,
-            VkFormat::VK_FORMAT_BC2_SRGB_BLOCK => None,
-            VkFormat::VK_FORMAT_BC3_UNORM_BLOCK => None,
-            VkFormat::VK_FORMAT_BC3_SRGB_BLOCK => None,
+            VkFormat::VK_FORMAT_BC1_RGB_UNORM_BLOCK => block_compressed,
+            VkFormat::VK_FORMAT_BC1_RGB_SRGB_BLOCK => block_compressed,
+            VkFormat::VK_FORMAT_BC1_RGBA_UNORM_BLOCK => block_compressed,
+            VkFormat::VK_FORMAT_BC1_RGBA_SRGB_BLOCK => block_compressed,
+            VkFormat::VK_FORMAT_BC2_UNORM_BLOCK => block_compressed,
+            VkFormat::VK_FORMAT_BC2_SRGB_BLOCK => block_compressed,
+            VkFormat::VK_FORMAT_BC3_UNORM_BLOCK => block_compressed,
+            VkFormat::VK_FORMAT_BC3_SRGB_BLOCK => block_compressed,
             VkFormat::VK_FORMAT_BC4_UNORM_BLOCK => None,
             VkFormat::VK_FORMAT_BC4_SNORM_BLOCK => None,
             VkFormat::VK_FORMAT_BC5_UNORM_BLOCK => None,
@@ -2452,7 +2598,78 @@ impl PhysicalDevice {
         }
     }
 
-    pub fn queue_family_properties(&self) -> [VkQueueFamilyProperties; 1] {
+    /// Fills `properties` of images described by `format_info` along with structures of its `pNext`
+    /// chain, returning `None` if such images aren't supported.
+    ///
+    /// # Safety
+    ///
+    /// Structures of `pNext` chains of both `format_info` and `properties` must be valid structures
+    /// of type given by their `sType`.
+    pub unsafe fn image_format_properties2(
+        &self,
+        format_info: &VkPhysicalDeviceImageFormatInfo2,
+        properties: &mut VkImageFormatProperties2,
+    ) -> Option<()> {
+        properties.imageFormatProperties = self.image_format_properties(
+            format_info.format,
+            format_info.type_,
+            format_info.tiling,
+            format_info.usage,
+            format_info.flags,
+        )?;
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VkPhysicalDeviceExternalImageFormatInfo
+        let handle_type = unsafe { in_structures(format_info.pNext) }
+            .find(|structure| unsafe {
+                structure.as_ref().sType
+                    == VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_EXTERNAL_IMAGE_FORMAT_INFO
+            })
+            .map(|structure| unsafe {
+                structure
+                    .cast::<VkPhysicalDeviceExternalImageFormatInfo>()
+                    .as_ref()
+                    .handleType
+            })
+            .filter(|handle_type| handle_type.0 != 0);
+        let external_memory_properties = handle_type.map(crate::memory::external_memory_properties);
+        if external_memory_properties
+            .is_some_and(|properties| properties.externalMemoryFeatures == 0)
+        {
+            return None;
+        }
+        for structure in unsafe { out_structures(properties.pNext) } {
+            match unsafe { structure.as_ref().sType } {
+                VkStructureType::VK_STRUCTURE_TYPE_EXTERNAL_IMAGE_FORMAT_PROPERTIES => {
+                    let Some(external_memory_properties) = external_memory_properties else {
+                        continue;
+                    };
+                    let properties =
+                        unsafe { structure.cast::<VkExternalImageFormatProperties>().as_mut() };
+                    properties.externalMemoryProperties = external_memory_properties;
+                }
+                s_type => trace!(
+                    "Unknown structure {:?} in image format properties chain",
+                    s_type
+                ),
+            }
+        }
+        Some(())
+    }
+
+    /// Returns sparse properties of images created with given parameters, which are empty for all
+    /// of them, as sparse residency isn't supported.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkGetPhysicalDeviceSparseImageFormatProperties
+    pub const fn sparse_image_format_properties(
+        &self,
+        _format: VkFormat,
+        _type_: VkImageType,
+        _samples: VkSampleCountFlagBits,
+        _usage: VkImageUsageFlags,
+        _tiling: VkImageTiling,
+    ) -> &'static [VkSparseImageFormatProperties] {
+        &[]
+    }
+
+    pub fn queue_family_properties(&self) -> [VkQueueFamilyProperties; 2] {
         // SPEC: If an implementation exposes any queue family that supports graphics operations,
         // at least one queue family of at least one physical device exposed by the implementation
         // must support both graphics and compute operations.
@@ -2460,54 +2677,73 @@ impl PhysicalDevice {
             queueFlags: (VkQueueFlagBits::VK_QUEUE_GRAPHICS_BIT
                 | VkQueueFlagBits::VK_QUEUE_COMPUTE_BIT)
                 .into(),
-            queueCount: 1,
-            timestampValidBits: 0,
+            queueCount: 4,
+            timestampValidBits: 64,
             minImageTransferGranularity: VkExtent3D {
                 width: 0,
                 height: 0,
                 depth: 0,
             },
         };
-        [graphics_queue_family_properties]
+        // Dedicated family for apps uploading resources on their own queue. Its queues execute
+        // commands the same way.
+        let transfer_queue_family_properties = VkQueueFamilyProperties {
+            queueFlags: VkQueueFlagBits::VK_QUEUE_TRANSFER_BIT.into(),
+            queueCount: 2,
+            timestampValidBits: 64,
+            minImageTransferGranularity: VkExtent3D {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+        };
+        [
+            graphics_queue_family_properties,
+            transfer_queue_family_properties,
+        ]
     }
 
+    /// Returns whether queues of family can present to surface, which only graphics queues can.
     pub const fn surface_support(&self, queue_family_index: u32, _surface: VkSurfaceKHR) -> bool {
         queue_family_index == 0
     }
 
-    pub const fn present_modes(&self) -> [VkPresentModeKHR; 1] {
-        [VkPresentModeKHR::VK_PRESENT_MODE_FIFO_KHR]
+    pub const fn present_modes(&self) -> [VkPresentModeKHR; 3] {
+        [
+            VkPresentModeKHR::VK_PRESENT_MODE_FIFO_KHR,
+            VkPresentModeKHR::VK_PRESENT_MODE_MAILBOX_KHR,
+            VkPresentModeKHR::VK_PRESENT_MODE_IMMEDIATE_KHR,
+        ]
     }
 
-    pub const fn surface_formats(&self) -> [VkSurfaceFormatKHR; 2] {
-        [
-            VkSurfaceFormatKHR {
-                format: VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+    pub fn surface_formats(&self, surface: &Surface) -> Vec<VkSurfaceFormatKHR> {
+        surface
+            .target()
+            .supported_formats()
+            .iter()
+            .map(|&format| VkSurfaceFormatKHR {
+                format,
                 colorSpace: VkColorSpaceKHR::VK_COLOR_SPACE_SRGB_NONLINEAR_KHR,
-            },
-            VkSurfaceFormatKHR {
-                format: VkFormat::VK_FORMAT_R8G8B8A8_SRGB,
-                colorSpace: VkColorSpaceKHR::VK_COLOR_SPACE_SRGB_NONLINEAR_KHR,
-            },
-        ]
+            })
+            .collect()
     }
 
-    pub fn surface_capabilities(&self) -> VkSurfaceCapabilitiesKHR {
-        VkSurfaceCapabilitiesKHR {
+    pub fn surface_capabilities(
+        &self,
+        surface: &Surface,
+    ) -> Result<VkSurfaceCapabilitiesKHR, VkResult> {
+        let current_extent = surface.target().extent()?;
+        let current_extent = VkExtent2D {
+            width: current_extent.width,
+            height: current_extent.height,
+        };
+        Ok(VkSurfaceCapabilitiesKHR {
             minImageCount: 1,
             maxImageCount: 2,
-            currentExtent: VkExtent2D {
-                width: 0xFFFFFFFF,
-                height: 0xFFFFFFFF,
-            },
-            minImageExtent: VkExtent2D {
-                width: 0,
-                height: 0,
-            },
-            maxImageExtent: VkExtent2D {
-                width: 16384, // TODO: Replace with maxImageDimension2D (1).
-                height: 16384,
-            },
+            // Swapchain images are not scaled when presented, so they must match window size.
+            currentExtent: current_extent,
+            minImageExtent: current_extent,
+            maxImageExtent: current_extent,
             maxImageArrayLayers: 1,
             supportedTransforms:
                 VkSurfaceTransformFlagBitsKHR::VK_SURFACE_TRANSFORM_IDENTITY_BIT_KHR.into(),
@@ -2515,10 +2751,38 @@ impl PhysicalDevice {
             supportedCompositeAlpha: VkCompositeAlphaFlagBitsKHR::VK_COMPOSITE_ALPHA_OPAQUE_BIT_KHR
                 .into(),
             supportedUsageFlags: VkImageUsageFlagBits::VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT.into(),
-        }
+        })
     }
 }
 
+/// Returns structures of `pNext` chain filled by queries, to be identified by their `sType`.
+///
+/// # Safety
+///
+/// Chain must consist of valid structures starting with `VkBaseOutStructure` members.
+unsafe fn out_structures(
+    next: Option<NonNull<std::ffi::c_void>>,
+) -> impl Iterator<Item = NonNull<VkBaseOutStructure>> {
+    std::iter::successors(
+        next.map(NonNull::cast::<VkBaseOutStructure>),
+        |structure| unsafe { structure.as_ref().pNext },
+    )
+}
+
+/// Returns structures of `pNext` chain given to queries, to be identified by their `sType`.
+///
+/// # Safety
+///
+/// Chain must consist of valid structures starting with `VkBaseInStructure` members.
+unsafe fn in_structures(
+    next: Option<NonNull<std::ffi::c_void>>,
+) -> impl Iterator<Item = NonNull<VkBaseInStructure>> {
+    std::iter::successors(
+        next.map(NonNull::cast::<VkBaseInStructure>),
+        |structure| unsafe { structure.as_ref().pNext },
+    )
+}
+
 impl PhysicalDevice {
     pub unsafe fn parse_vertex_input_state(
         vertex_input_state: VkPipelineVertexInputStateCreateInfo,
@@ -2634,41 +2898,49 @@ impl PhysicalDevice {
             let Some(viewport) = viewport_state.viewports.get_mut(i) else {
                 unreachable!()
             };
-            *viewport = Some(Viewport {
-                offset: Offset2 {
-                    x: vk_viewport.x,
-                    y: vk_viewport.y,
-                },
-                extent: Extent2 {
-                    width: vk_viewport.width,
-                    height: vk_viewport.height,
-                },
-                depth: Range2 {
-                    min: vk_viewport.minDepth,
-                    max: vk_viewport.maxDepth,
-                },
-            });
+            *viewport = Some(Self::parse_viewport(vk_viewport));
         }
         for (i, vk_scissor) in vk_scissors.iter().enumerate() {
             let Some(scissor) = viewport_state.scissors.get_mut(i) else {
                 unreachable!()
             };
-            *scissor = Some(Scissor {
-                render_area: RenderArea {
-                    extent: Extent2 {
-                        width: vk_scissor.extent.width,
-                        height: vk_scissor.extent.height,
-                    },
-                    offset: Offset2 {
-                        x: vk_scissor.offset.x,
-                        y: vk_scissor.offset.y,
-                    },
-                },
-            });
+            *scissor = Some(Self::parse_scissor(vk_scissor));
         }
         viewport_state
     }
 
+    pub const fn parse_viewport(vk_viewport: &VkViewport) -> Viewport {
+        Viewport {
+            offset: Offset2 {
+                x: vk_viewport.x,
+                y: vk_viewport.y,
+            },
+            extent: Extent2 {
+                width: vk_viewport.width,
+                height: vk_viewport.height,
+            },
+            depth: Range2 {
+                min: vk_viewport.minDepth,
+                max: vk_viewport.maxDepth,
+            },
+        }
+    }
+
+    pub const fn parse_scissor(vk_scissor: &VkRect2D) -> Scissor {
+        Scissor {
+            render_area: RenderArea {
+                extent: Extent2 {
+                    width: vk_scissor.extent.width,
+                    height: vk_scissor.extent.height,
+                },
+                offset: Offset2 {
+                    x: vk_scissor.offset.x,
+                    y: vk_scissor.offset.y,
+                },
+            },
+        }
+    }
+
     pub fn parse_rasterization_state(
         rasterization_state: VkPipelineRasterizationStateCreateInfo,
     ) -> RasterizationState {
@@ -2686,25 +2958,110 @@ impl PhysicalDevice {
         }
     }
 
-    pub fn parse_shader_stages(
+    pub unsafe fn parse_dynamic_state(
+        dynamic_state: VkPipelineDynamicStateCreateInfo,
+    ) -> DynamicState {
+        let vk_dynamic_states = dynamic_state.pDynamicStates.map_or(&[] as &[_], |x| {
+            std::slice::from_raw_parts(x.as_ptr(), dynamic_state.dynamicStateCount as usize)
+        });
+
+        let mut dynamic_state = DynamicState::default();
+        for &vk_dynamic_state in vk_dynamic_states {
+            match vk_dynamic_state {
+                VkDynamicState::VK_DYNAMIC_STATE_VIEWPORT => dynamic_state.viewport = true,
+                VkDynamicState::VK_DYNAMIC_STATE_SCISSOR => dynamic_state.scissor = true,
+                VkDynamicState::VK_DYNAMIC_STATE_DEPTH_BIAS => dynamic_state.depth_bias = true,
+                VkDynamicState::VK_DYNAMIC_STATE_DEPTH_BOUNDS => dynamic_state.depth_bounds = true,
+                _ => warn!("TODO: Dynamic state {:?}", vk_dynamic_state),
+            }
+        }
+        dynamic_state
+    }
+
+    pub fn parse_depth_stencil_state(
+        depth_stencil_state: VkPipelineDepthStencilStateCreateInfo,
+    ) -> DepthStencilState {
+        DepthStencilState {
+            depth_test_enable: depth_stencil_state.depthTestEnable != 0,
+            depth_write_enable: depth_stencil_state.depthWriteEnable != 0,
+            depth_compare_op: depth_stencil_state.depthCompareOp.into(),
+            depth_bounds_test_enable: depth_stencil_state.depthBoundsTestEnable != 0,
+            stencil_test_enable: depth_stencil_state.stencilTestEnable != 0,
+            front: depth_stencil_state.front.into(),
+            back: depth_stencil_state.back.into(),
+            depth_bounds: Range2 {
+                min: depth_stencil_state.minDepthBounds,
+                max: depth_stencil_state.maxDepthBounds,
+            },
+        }
+    }
+
+    pub unsafe fn parse_color_blend_state(
+        color_blend_state: VkPipelineColorBlendStateCreateInfo,
+    ) -> ColorBlendState {
+        let vk_attachments = color_blend_state.pAttachments.map_or(&[] as &[_], |x| {
+            std::slice::from_raw_parts(x.as_ptr(), color_blend_state.attachmentCount as usize)
+        });
+
+        let [r, g, b, a] = color_blend_state.blendConstants;
+        let mut result = ColorBlendState {
+            logic_op_enable: color_blend_state.logicOpEnable != 0,
+            logic_op: color_blend_state.logicOp.into(),
+            attachments: Default::default(),
+            blend_constants: Color::from_sfloat32_raw(r, g, b, a),
+        };
+        for (i, vk_attachment) in vk_attachments.iter().enumerate() {
+            let Some(attachment) = result.attachments.get_mut(i) else {
+                unreachable!()
+            };
+            *attachment = Some(ColorBlendAttachmentState {
+                blend_enable: vk_attachment.blendEnable != 0,
+                src_color_blend_factor: vk_attachment.srcColorBlendFactor.into(),
+                dst_color_blend_factor: vk_attachment.dstColorBlendFactor.into(),
+                color_blend_op: vk_attachment.colorBlendOp.into(),
+                src_alpha_blend_factor: vk_attachment.srcAlphaBlendFactor.into(),
+                dst_alpha_blend_factor: vk_attachment.dstAlphaBlendFactor.into(),
+                alpha_blend_op: vk_attachment.alphaBlendOp.into(),
+                color_write_mask: VkFlag::new(vk_attachment.colorWriteMask).into(),
+            });
+        }
+        result
+    }
+
+    pub unsafe fn parse_specialization_info(
+        specialization_info: &VkSpecializationInfo,
+    ) -> SpecializationInfo {
+        let map_entries = specialization_info.pMapEntries.map_or(&[] as &[_], |x| {
+            std::slice::from_raw_parts(x.as_ptr(), specialization_info.mapEntryCount as usize)
+        });
+        let data = specialization_info.pData.map_or(&[] as &[_], |x| {
+            std::slice::from_raw_parts(
+                x.as_ptr() as *const u8,
+                specialization_info.dataSize as usize,
+            )
+        });
+        let mut result = SpecializationInfo::default();
+        for map_entry in map_entries {
+            let offset = map_entry.offset as usize;
+            let Ok(bytes) = data[offset..offset + map_entry.size as usize].try_into() else {
+                unimplemented!("specialization constant of size {}", map_entry.size)
+            };
+            result
+                .constants
+                .insert(map_entry.constantID, u32::from_ne_bytes(bytes));
+        }
+        result
+    }
+
+    /// Creates shaders of pipeline from already parsed modules, reusing ones compiled earlier into
+    /// `pipeline_cache` if given.
+    pub unsafe fn parse_shader_stages(
         shader_stages: &[VkPipelineShaderStageCreateInfo],
+        pipeline_cache: Option<&Mutex<PipelineCache>>,
     ) -> Result<ShaderState, VkResult> {
         let mut shader_state = ShaderState::default();
         for shader_stage in shader_stages {
-            assert_eq!(shader_stage.flags, 0);
-            let name = shader_stage.pName.unwrap_or_else(|| unreachable!());
-            let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }
-                .to_str()
-                .unwrap_or_else(|_| unreachable!())
-                .to_string();
-            assert_eq!(shader_stage.pSpecializationInfo, None);
-            let module =
-                ShaderModule::from_handle(shader_stage.module).unwrap_or_else(|| unreachable!());
-            let code = module.lock().code.clone();
-
-            let shader =
-                Shader::new(&name, code).map_err(|_| VkResult::VK_ERROR_INVALID_SHADER_NV)?;
-
+            let shader = unsafe { Self::parse_shader_stage(shader_stage, pipeline_cache) }?;
             match shader_stage.stage {
                 VkShaderStageFlagBits::VK_SHADER_STAGE_VERTEX_BIT => {
                     shader_state.vertex_shader = Some(shader);
@@ -2717,4 +3074,189 @@ impl PhysicalDevice {
         }
         Ok(shader_state)
     }
+
+    /// Creates shader of a single pipeline stage from already parsed module, reusing one compiled
+    /// earlier into `pipeline_cache` if given.
+    pub unsafe fn parse_shader_stage(
+        shader_stage: &VkPipelineShaderStageCreateInfo,
+        pipeline_cache: Option<&Mutex<PipelineCache>>,
+    ) -> Result<Shader, VkResult> {
+        assert_eq!(shader_stage.flags, 0);
+        let name = shader_stage.pName.unwrap_or_else(|| unreachable!());
+        let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }
+            .to_str()
+            .unwrap_or_else(|_| unreachable!())
+            .to_string();
+        let specialization = shader_stage
+            .pSpecializationInfo
+            .map(|x| Self::parse_specialization_info(x.as_ref()))
+            .unwrap_or_default();
+        let module = ShaderModule::lookup(shader_stage.module)?;
+        let (code, spirv) = {
+            let module = module.lock();
+            (module.code.clone(), module.spirv.clone())
+        };
+        // Only drivers of VK_NV_glsl_shader may return VK_ERROR_INVALID_SHADER_NV, so failures of
+        // modules that passed creation are reported as unknown errors.
+        let Some(spirv) = spirv else {
+            error!(
+                "Shader module {} of entry point {name} is invalid",
+                Named::from(shader_stage.module)
+            );
+            return Err(VkResult::VK_ERROR_UNKNOWN);
+        };
+
+        let compile = || Shader::from_spirv(&name, &spirv, &specialization);
+        pipeline_cache
+            .map_or_else(compile, |pipeline_cache| {
+                pipeline_cache
+                    .lock()
+                    .get_or_compile(&code, &name, &specialization, compile)
+            })
+            .map_err(|err| {
+                error!("Failed to compile entry point {name} of shader: {err}");
+                VkResult::VK_ERROR_UNKNOWN
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features2_fills_known_structures_of_chain() {
+        let Some(physical_device) = PhysicalDevice::from_handle(PhysicalDevice::create()) else {
+            unreachable!()
+        };
+        let mut dynamic_rendering_features = VkPhysicalDeviceDynamicRenderingFeatures {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_DYNAMIC_RENDERING_FEATURES,
+            pNext: None,
+            dynamicRendering: VK_FALSE,
+        };
+        // Structure of type driver doesn't know in the middle of chain is skipped.
+        let mut unknown_features = VkPhysicalDeviceDepthClipEnableFeaturesEXT {
+            sType:
+                VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_DEPTH_CLIP_ENABLE_FEATURES_EXT,
+            pNext: Some(NonNull::from(&mut dynamic_rendering_features).cast()),
+            depthClipEnable: VK_TRUE,
+        };
+        let mut timeline_semaphore_features = VkPhysicalDeviceTimelineSemaphoreFeatures {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_TIMELINE_SEMAPHORE_FEATURES,
+            pNext: Some(NonNull::from(&mut unknown_features).cast()),
+            timelineSemaphore: VK_FALSE,
+        };
+        let mut features = VkPhysicalDeviceFeatures2 {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_FEATURES_2,
+            pNext: Some(NonNull::from(&mut timeline_semaphore_features).cast()),
+            features: unsafe { std::mem::zeroed() },
+        };
+        unsafe { physical_device.lock().features2(&mut features) };
+
+        assert_eq!(
+            features.features.independentBlend,
+            physical_device.lock().features().independentBlend
+        );
+        assert_eq!(timeline_semaphore_features.timelineSemaphore, VK_TRUE);
+        assert_eq!(unknown_features.depthClipEnable, VK_TRUE);
+        assert_eq!(dynamic_rendering_features.dynamicRendering, VK_TRUE);
+        assert_eq!(
+            timeline_semaphore_features.pNext,
+            Some(NonNull::from(&mut unknown_features).cast())
+        );
+    }
+
+    #[test]
+    fn image_format_properties2_reports_external_memory() {
+        let Some(physical_device) = PhysicalDevice::from_handle(PhysicalDevice::create()) else {
+            unreachable!()
+        };
+        let query = |handle_type, properties: &mut VkImageFormatProperties2| {
+            let external_image_format_info = VkPhysicalDeviceExternalImageFormatInfo {
+                sType:
+                    VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_EXTERNAL_IMAGE_FORMAT_INFO,
+                pNext: None,
+                handleType: handle_type,
+            };
+            let format_info = VkPhysicalDeviceImageFormatInfo2 {
+                sType: VkStructureType::VK_STRUCTURE_TYPE_PHYSICAL_DEVICE_IMAGE_FORMAT_INFO_2,
+                pNext: Some(NonNull::from(&external_image_format_info).cast()),
+                format: VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+                type_: VkImageType::VK_IMAGE_TYPE_2D,
+                tiling: VkImageTiling::VK_IMAGE_TILING_OPTIMAL,
+                usage: VkImageUsageFlagBits::VK_IMAGE_USAGE_SAMPLED_BIT.into(),
+                flags: 0,
+            };
+            unsafe {
+                physical_device
+                    .lock()
+                    .image_format_properties2(&format_info, properties)
+            }
+        };
+        let mut external_image_format_properties = VkExternalImageFormatProperties {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_EXTERNAL_IMAGE_FORMAT_PROPERTIES,
+            pNext: None,
+            externalMemoryProperties: unsafe { std::mem::zeroed() },
+        };
+        let mut properties = VkImageFormatProperties2 {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_FORMAT_PROPERTIES_2,
+            pNext: Some(NonNull::from(&mut external_image_format_properties).cast()),
+            imageFormatProperties: unsafe { std::mem::zeroed() },
+        };
+        let opaque_fd =
+            VkExternalMemoryHandleTypeFlagBits::VK_EXTERNAL_MEMORY_HANDLE_TYPE_OPAQUE_FD_BIT;
+        assert_eq!(query(opaque_fd, &mut properties), Some(()));
+
+        assert_eq!(
+            properties.imageFormatProperties.maxMipLevels,
+            physical_device
+                .lock()
+                .image_format_properties(
+                    VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+                    VkImageType::VK_IMAGE_TYPE_2D,
+                    VkImageTiling::VK_IMAGE_TILING_OPTIMAL,
+                    VkImageUsageFlagBits::VK_IMAGE_USAGE_SAMPLED_BIT.into(),
+                    0,
+                )
+                .map_or(0, |properties| properties.maxMipLevels)
+        );
+        assert_eq!(
+            external_image_format_properties
+                .externalMemoryProperties
+                .compatibleHandleTypes,
+            opaque_fd.0
+        );
+
+        // Images can't be bound to memory of handle types that can't be imported nor exported.
+        let host_allocation =
+            VkExternalMemoryHandleTypeFlagBits::VK_EXTERNAL_MEMORY_HANDLE_TYPE_HOST_ALLOCATION_BIT_EXT;
+        assert_eq!(query(host_allocation, &mut properties), None);
+    }
+
+    #[test]
+    fn color_format_has_no_sparse_properties() {
+        let Some(physical_device) = PhysicalDevice::from_handle(PhysicalDevice::create()) else {
+            unreachable!()
+        };
+        let format = VkFormat::VK_FORMAT_R8G8B8A8_UNORM;
+        assert!(physical_device
+            .lock()
+            .image_format_properties(
+                format,
+                VkImageType::VK_IMAGE_TYPE_2D,
+                VkImageTiling::VK_IMAGE_TILING_OPTIMAL,
+                VkImageUsageFlagBits::VK_IMAGE_USAGE_SAMPLED_BIT.into(),
+                0,
+            )
+            .is_some());
+
+        let properties = physical_device.lock().sparse_image_format_properties(
+            format,
+            VkImageType::VK_IMAGE_TYPE_2D,
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+            VkImageUsageFlagBits::VK_IMAGE_USAGE_SAMPLED_BIT.into(),
+            VkImageTiling::VK_IMAGE_TILING_OPTIMAL,
+        );
+        assert_eq!(properties.len(), 0);
+    }
 }
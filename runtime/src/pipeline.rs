@@ -1,15 +1,22 @@
 //! Pipeline
 
-
 use crate::context::NonDispatchable;
+use crate::descriptor::DescriptorSetLayout;
+use crate::error::RuntimeError;
 use crate::image::ImageView;
 use crate::logical_device::LogicalDevice;
 use common::graphics::VertexInputState;
-use gpu::{Command, InputAssemblyState, RasterizationState, ViewportState};
+use gpu::{
+    ColorBlendState, Command, DepthStencilState, DynamicState, InputAssemblyState,
+    PrimitiveTopology, RasterizationState, ViewportState,
+};
 use headers::vk_decls::*;
+use itertools::Itertools;
 use log::*;
 use parking_lot::Mutex;
-use shader::glsl::ShaderState;
+use serde::{Deserialize, Serialize};
+use shader::glsl::{Shader, ShaderState, SpecializationInfo};
+use shader::spirv::Spirv;
 use std::fmt::Debug;
 use std::sync::Arc;
 
@@ -17,7 +24,9 @@ use std::sync::Arc;
 pub struct PipelineLayout {
     pub(crate) handle: VkNonDispatchableHandle,
     #[allow(dead_code)]
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
+    pub set_layouts: Vec<Arc<Mutex<DescriptorSetLayout>>>,
+    pub push_constant_ranges: Vec<VkPushConstantRange>,
 }
 
 impl PipelineLayout {
@@ -26,19 +35,25 @@ impl PipelineLayout {
         flags: VkDescriptorSetLayoutCreateFlags,
         set_layouts: Option<&[VkDescriptorSetLayout]>,
         push_constant_ranges: Option<&[VkPushConstantRange]>,
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new PipelineLayout");
         let handle = VK_NULL_HANDLE;
 
         let _ = flags;
-        let _ = set_layouts;
-        let _ = push_constant_ranges;
+        let set_layouts = set_layouts
+            .unwrap_or_default()
+            .iter()
+            .map(|&set_layout| DescriptorSetLayout::lookup(set_layout))
+            .collect::<Result<_, _>>()?;
+        let push_constant_ranges = push_constant_ranges.unwrap_or_default().to_vec();
 
         let object = Self {
             handle,
             logical_device,
+            set_layouts,
+            push_constant_ranges,
         };
-        object.register_object()
+        Ok(object.register_object())
     }
 }
 
@@ -46,11 +61,10 @@ impl PipelineLayout {
 pub struct RenderPass {
     pub(crate) handle: VkNonDispatchableHandle,
     #[allow(dead_code)]
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     pub(crate) attachments: Arc<[AttachmentDescription]>,
     // TODO: dependencies: Arc<[VkSubpassDependency]>,
-    #[allow(dead_code)]
-    subpasses: Arc<[SubpassDescription]>,
+    pub(crate) subpasses: Arc<[SubpassDescription]>,
 }
 
 impl RenderPass {
@@ -59,7 +73,7 @@ impl RenderPass {
         attachments: &[AttachmentDescription],
         dependencies: &[VkSubpassDependency],
         subpasses: &[SubpassDescription],
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new RenderPass");
         let handle = VK_NULL_HANDLE;
         let _ = dependencies;
@@ -70,7 +84,31 @@ impl RenderPass {
             attachments: attachments.into(),
             subpasses: subpasses.into(),
         };
-        object.register_object()
+        Ok(object.register_object())
+    }
+
+    /// Returns whether render passes have attachments of the same formats and sample counts,
+    /// referenced the same way by their subpasses, so either can be used where other is expected.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#renderpass-compatibility
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        let attachments_compatible = self.attachments.len() == other.attachments.len()
+            && std::iter::zip(self.attachments.iter(), other.attachments.iter())
+                .all(|(a, b)| a.format == b.format && a.samples == b.samples);
+        let references = |references: &[VkAttachmentReference]| {
+            references
+                .iter()
+                .map(|reference| reference.attachment)
+                .collect::<Vec<_>>()
+        };
+        let subpasses_compatible = self.subpasses.len() == other.subpasses.len()
+            && std::iter::zip(self.subpasses.iter(), other.subpasses.iter()).all(|(a, b)| {
+                references(&a.input_attachments) == references(&b.input_attachments)
+                    && references(&a.color_attachments) == references(&b.color_attachments)
+                    && references(&a.resolve_attachments) == references(&b.resolve_attachments)
+                    && references(a.depth_stencil_attachment.as_slice())
+                        == references(b.depth_stencil_attachment.as_slice())
+            });
+        attachments_compatible && subpasses_compatible
     }
 }
 
@@ -102,8 +140,10 @@ pub struct SubpassDescription {
 pub struct ShaderModule {
     pub(crate) handle: VkNonDispatchableHandle,
     #[allow(dead_code)]
-    logical_device: Arc<Mutex<LogicalDevice>>,
-    pub(crate) code: Vec<u32>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) code: Arc<[u32]>,
+    /// Module parsed once at creation for all pipelines using it, `None` if code is invalid.
+    pub(crate) spirv: Option<Arc<Spirv>>,
 }
 
 impl ShaderModule {
@@ -111,29 +151,47 @@ impl ShaderModule {
         logical_device: Arc<Mutex<LogicalDevice>>,
         flags: VkDescriptorSetLayoutCreateFlags,
         code: &[u32],
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new ShaderModule");
         let handle = VK_NULL_HANDLE;
 
         let _ = flags;
-        let code = code.to_vec();
+        let code = Arc::from(code);
+        let spirv = Spirv::new(&code)
+            .map_err(|err| error!("Invalid shader module: {err}"))
+            .ok()
+            .map(Arc::new);
 
         let object = Self {
             handle,
             logical_device,
             code,
+            spirv,
         };
-        object.register_object()
+        Ok(object.register_object())
     }
 }
 
+/// Version of payload of pipeline cache data following its header, to be changed along with its
+/// layout.
+const PIPELINE_CACHE_DATA_VERSION: u32 = 2;
+
 #[derive(Debug)]
 pub struct PipelineCache {
     pub(crate) handle: VkNonDispatchableHandle,
-    #[allow(dead_code)]
-    logical_device: Arc<Mutex<LogicalDevice>>,
-    #[allow(dead_code)]
-    initial_data: Vec<u8>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
+    /// Shaders compiled for pipelines created with this cache or loaded from its initial data.
+    shaders: Vec<CachedShader>,
+}
+
+/// Shader compiled from entry point of SPIR-V code with specialization constants.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedShader {
+    code: Vec<u32>,
+    name: String,
+    /// Values of specialization constants sorted by their ids.
+    specialization: Vec<(u32, u32)>,
+    shader: Shader,
 }
 
 impl PipelineCache {
@@ -141,19 +199,90 @@ impl PipelineCache {
         logical_device: Arc<Mutex<LogicalDevice>>,
         flags: VkDescriptorSetLayoutCreateFlags,
         initial_data: &[u8],
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new PipelineCache");
         let handle = VK_NULL_HANDLE;
 
         let _ = flags;
-        let initial_data = initial_data.to_vec();
+        let header = Self::header(&logical_device.lock());
+        let shaders = Self::load(&header, initial_data);
+        if shaders.is_none() && !initial_data.is_empty() {
+            warn!("Ignoring initial data of pipeline cache created by other device or driver");
+        }
 
         let object = Self {
             handle,
             logical_device,
-            initial_data,
+            shaders: shaders.unwrap_or_default(),
         };
-        object.register_object()
+        Ok(object.register_object())
+    }
+
+    /// Returns shaders of cache `data` if it has `header` of this device and payload of current
+    /// version.
+    fn load(header: &[u8], data: &[u8]) -> Option<Vec<CachedShader>> {
+        let payload = data.strip_prefix(header)?;
+        let payload = payload.strip_prefix(&PIPELINE_CACHE_DATA_VERSION.to_ne_bytes())?;
+        serde_json::from_slice(payload).ok()
+    }
+
+    /// Returns header identifying device compiled shaders in cache data are compatible with.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#pipelines-cache-header
+    fn header(logical_device: &LogicalDevice) -> Vec<u8> {
+        let properties = logical_device.physical_device().properties();
+        let mut header = vec![];
+        header.extend(32u32.to_ne_bytes());
+        header.extend(
+            VkPipelineCacheHeaderVersion::VK_PIPELINE_CACHE_HEADER_VERSION_ONE
+                .0
+                .to_ne_bytes(),
+        );
+        header.extend(properties.vendorID.to_ne_bytes());
+        header.extend(properties.deviceID.to_ne_bytes());
+        header.extend(properties.pipelineCacheUUID);
+        header
+    }
+
+    /// Returns data of cache to be provided as initial data of cache created later.
+    ///
+    /// Data consists of header of `VK_PIPELINE_CACHE_HEADER_VERSION_ONE` followed by version of
+    /// payload and compiled shaders serialized as JSON.
+    pub fn get_data(&self) -> Vec<u8> {
+        let mut data = Self::header(&self.logical_device.lock());
+        data.extend(PIPELINE_CACHE_DATA_VERSION.to_ne_bytes());
+        serde_json::to_writer(&mut data, &self.shaders).unwrap_or_else(|_| unreachable!());
+        data
+    }
+
+    /// Returns shader compiled from entry point `name` of `code` with `specialization`, calling
+    /// `compile` and caching its shader if there is none yet.
+    pub(crate) fn get_or_compile(
+        &mut self,
+        code: &[u32],
+        name: &str,
+        specialization: &SpecializationInfo,
+        compile: impl FnOnce() -> anyhow::Result<Shader>,
+    ) -> anyhow::Result<Shader> {
+        let specialization = specialization
+            .constants
+            .iter()
+            .map(|(&id, &value)| (id, value))
+            .sorted()
+            .collect::<Vec<_>>();
+        let cached = self.shaders.iter().find(|cached| {
+            cached.code == code && cached.name == name && cached.specialization == specialization
+        });
+        if let Some(cached) = cached {
+            return Ok(cached.shader.clone());
+        }
+        let shader = compile()?;
+        self.shaders.push(CachedShader {
+            code: code.to_vec(),
+            name: name.to_string(),
+            specialization,
+            shader: shader.clone(),
+        });
+        Ok(shader)
     }
 }
 
@@ -161,7 +290,7 @@ impl PipelineCache {
 pub struct Pipeline {
     pub handle: VkNonDispatchableHandle,
     #[allow(dead_code)]
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     pub pipeline_cache: Option<Arc<Mutex<PipelineCache>>>,
 
     pub shader_state: ShaderState,
@@ -169,9 +298,13 @@ pub struct Pipeline {
     pub input_assembly_state: InputAssemblyState,
     pub viewport_state: ViewportState,
     pub rasterization_state: RasterizationState,
+    pub depth_stencil_state: DepthStencilState,
+    pub color_blend_state: ColorBlendState,
+    pub dynamic_state: DynamicState,
 }
 
 impl Pipeline {
+    #[allow(clippy::too_many_arguments)]
     pub fn create(
         logical_device: Arc<Mutex<LogicalDevice>>,
         pipeline_cache: Option<Arc<Mutex<PipelineCache>>>,
@@ -180,10 +313,35 @@ impl Pipeline {
         input_assembly_state: Option<InputAssemblyState>,
         viewport_state: Option<ViewportState>,
         rasterization_state: Option<RasterizationState>,
-    ) -> VkNonDispatchableHandle {
+        depth_stencil_state: Option<DepthStencilState>,
+        color_blend_state: Option<ColorBlendState>,
+        dynamic_state: Option<DynamicState>,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new Pipeline");
         let handle = VK_NULL_HANDLE;
 
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkGraphicsPipelineCreateInfo-topology-00737
+        if input_assembly_state
+            .as_ref()
+            .is_some_and(|state| state.topology == PrimitiveTopology::PatchList)
+        {
+            error!("Patch list topology requires tessellation shaders, which aren't supported");
+            return Err(RuntimeError::ValidationFailed);
+        }
+
+        let mut color_blend_state = color_blend_state.unwrap_or_default();
+        if logical_device.lock().enabled_features().independentBlend == VK_FALSE {
+            // All attachments share state of the first one without independentBlend feature.
+            let [first, rest @ ..] = &mut color_blend_state.attachments;
+            for attachment in rest.iter_mut().flatten() {
+                let Some(first) = first else { unreachable!() };
+                if attachment != first {
+                    error!("Color blend attachment states differ without independentBlend feature");
+                }
+                *attachment = *first;
+            }
+        }
+
         let object = Self {
             handle,
             logical_device,
@@ -193,8 +351,11 @@ impl Pipeline {
             input_assembly_state: input_assembly_state.unwrap_or_default(),
             viewport_state: viewport_state.unwrap_or_default(),
             rasterization_state: rasterization_state.unwrap_or_default(),
+            depth_stencil_state: depth_stencil_state.unwrap_or_default(),
+            color_blend_state,
+            dynamic_state: dynamic_state.unwrap_or_default(),
         };
-        object.register_object()
+        Ok(object.register_object())
     }
 
     pub fn bind_states(&self, command_buffer: &mut gpu::CommandBuffer) {
@@ -213,16 +374,59 @@ impl Pipeline {
         command_buffer.record(Command::SetRasterizationState {
             rasterization_state: self.rasterization_state.clone(),
         });
+        command_buffer.record(Command::SetDepthStencilState {
+            depth_stencil_state: self.depth_stencil_state.clone(),
+        });
+        command_buffer.record(Command::SetColorBlendState {
+            color_blend_state: self.color_blend_state.clone(),
+        });
+        command_buffer.record(Command::SetDynamicState {
+            dynamic_state: self.dynamic_state,
+        });
         warn!("TODO: Record rest of pipeline state");
     }
 }
 
 #[derive(Debug)]
+pub struct ComputePipeline {
+    pub handle: VkNonDispatchableHandle,
+    #[allow(dead_code)]
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
+    pub pipeline_cache: Option<Arc<Mutex<PipelineCache>>>,
+
+    pub shader: Shader,
+}
+
+impl ComputePipeline {
+    pub fn create(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        pipeline_cache: Option<Arc<Mutex<PipelineCache>>>,
+        shader: Shader,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
+        info!("new ComputePipeline");
+        let handle = VK_NULL_HANDLE;
+
+        let object = Self {
+            handle,
+            logical_device,
+            pipeline_cache,
+            shader,
+        };
+        Ok(object.register_object())
+    }
+
+    pub fn bind_states(&self, command_buffer: &mut gpu::CommandBuffer) {
+        command_buffer.record(Command::SetComputeShader {
+            shader: self.shader.clone(),
+        });
+    }
+}
 
+#[derive(Debug)]
 #[allow(dead_code)]
 pub struct Framebuffer {
     pub(crate) handle: VkNonDispatchableHandle,
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     flags: VkFramebufferCreateFlagBits,
     width: u32,
     height: u32,
@@ -240,10 +444,11 @@ impl Framebuffer {
         layers: u32,
         attachments: Vec<Arc<Mutex<ImageView>>>,
         render_pass: Arc<Mutex<RenderPass>>,
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new Framebuffer");
         let handle = VK_NULL_HANDLE;
         assert_eq!(layers, 1);
+        Self::validate_attachments(width, height, &attachments, &render_pass.lock())?;
 
         let object = Self {
             handle,
@@ -255,6 +460,369 @@ impl Framebuffer {
             attachments: attachments.into(),
             render_pass,
         };
-        object.register_object()
+        Ok(object.register_object())
+    }
+
+    /// Checks that image views match formats and sample counts of render pass attachments and
+    /// are at least as large as framebuffer.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkFramebufferCreateInfo-attachmentCount-00876
+    fn validate_attachments(
+        width: u32,
+        height: u32,
+        attachments: &[Arc<Mutex<ImageView>>],
+        render_pass: &RenderPass,
+    ) -> Result<(), RuntimeError> {
+        if attachments.len() != render_pass.attachments.len() {
+            error!(
+                "Framebuffer has {} attachments, render pass has {}",
+                attachments.len(),
+                render_pass.attachments.len()
+            );
+            return Err(RuntimeError::ValidationFailed);
+        }
+        for (i, (image_view, description)) in
+            std::iter::zip(attachments, render_pass.attachments.iter()).enumerate()
+        {
+            let (format, samples, extent) = {
+                let image_view = image_view.lock();
+                let samples = image_view.image.lock().samples;
+                (image_view.format, samples, image_view.extent())
+            };
+            if format != description.format || samples != u32::from(description.samples) {
+                error!(
+                    "Framebuffer attachment {i} of {format:?} with {samples} samples does not match {:?} with {:?}",
+                    description.format, description.samples
+                );
+                return Err(RuntimeError::ValidationFailed);
+            }
+            if extent.width < width || extent.height < height {
+                error!(
+                    "Framebuffer attachment {i} of {}x{} is smaller than framebuffer of {width}x{height}",
+                    extent.width, extent.height
+                );
+                return Err(RuntimeError::ValidationFailed);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_buffer::tests::VERTEX_SHADER;
+    use crate::logical_device::tests::{
+        create_logical_device, create_logical_device_with_features,
+    };
+    use crate::physical_device::{PhysicalDevice, PIPELINE_CACHE_UUID};
+    use common::graphics::{CompareOp, CullMode, FrontFace, PolygonMode};
+    use std::ptr::NonNull;
+
+    #[test]
+    fn pipeline_stores_fixed_function_state() {
+        let input_assembly_state = VkPipelineInputAssemblyStateCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            topology: VkPrimitiveTopology::VK_PRIMITIVE_TOPOLOGY_TRIANGLE_STRIP,
+            primitiveRestartEnable: VK_TRUE,
+        };
+        let rasterization_state = VkPipelineRasterizationStateCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            depthClampEnable: VK_FALSE,
+            rasterizerDiscardEnable: VK_FALSE,
+            polygonMode: VkPolygonMode::VK_POLYGON_MODE_FILL,
+            cullMode: VkCullModeFlagBits::VK_CULL_MODE_BACK_BIT.into(),
+            frontFace: VkFrontFace::VK_FRONT_FACE_CLOCKWISE,
+            depthBiasEnable: VK_FALSE,
+            depthBiasConstantFactor: 0.0,
+            depthBiasClamp: 0.0,
+            depthBiasSlopeFactor: 0.0,
+            lineWidth: 1.0,
+        };
+        let stencil_op_state = VkStencilOpState {
+            failOp: VkStencilOp::VK_STENCIL_OP_KEEP,
+            passOp: VkStencilOp::VK_STENCIL_OP_REPLACE,
+            depthFailOp: VkStencilOp::VK_STENCIL_OP_KEEP,
+            compareOp: VkCompareOp::VK_COMPARE_OP_ALWAYS,
+            compareMask: 0xff,
+            writeMask: 0xff,
+            reference: 1,
+        };
+        let depth_stencil_state = VkPipelineDepthStencilStateCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            depthTestEnable: VK_TRUE,
+            depthWriteEnable: VK_TRUE,
+            depthCompareOp: VkCompareOp::VK_COMPARE_OP_LESS,
+            depthBoundsTestEnable: VK_FALSE,
+            stencilTestEnable: VK_FALSE,
+            front: stencil_op_state,
+            back: stencil_op_state,
+            minDepthBounds: 0.0,
+            maxDepthBounds: 1.0,
+        };
+        let dynamic_states = [
+            VkDynamicState::VK_DYNAMIC_STATE_VIEWPORT,
+            VkDynamicState::VK_DYNAMIC_STATE_LINE_WIDTH,
+        ];
+        let dynamic_state = VkPipelineDynamicStateCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PIPELINE_DYNAMIC_STATE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            dynamicStateCount: dynamic_states.len() as u32,
+            pDynamicStates: NonNull::new(dynamic_states.as_ptr().cast_mut()),
+        };
+
+        let Ok(pipeline) = Pipeline::create(
+            create_logical_device(),
+            None,
+            ShaderState::default(),
+            None,
+            Some(PhysicalDevice::parse_input_assembly_state(
+                input_assembly_state,
+            )),
+            None,
+            Some(PhysicalDevice::parse_rasterization_state(
+                rasterization_state,
+            )),
+            Some(PhysicalDevice::parse_depth_stencil_state(
+                depth_stencil_state,
+            )),
+            None,
+            Some(unsafe { PhysicalDevice::parse_dynamic_state(dynamic_state) }),
+        ) else {
+            unreachable!()
+        };
+        let Some(pipeline) = Pipeline::from_handle(pipeline) else {
+            unreachable!()
+        };
+        let pipeline = pipeline.lock();
+        assert_eq!(
+            pipeline.input_assembly_state.topology,
+            PrimitiveTopology::TriangleStrip
+        );
+        assert!(pipeline.input_assembly_state.primitive_restart);
+        assert_eq!(pipeline.rasterization_state.cull_mode, CullMode::Back);
+        assert_eq!(
+            pipeline.rasterization_state.front_face,
+            FrontFace::Clockwise
+        );
+        assert_eq!(pipeline.rasterization_state.polygon_mode, PolygonMode::Fill);
+        assert!(pipeline.depth_stencil_state.depth_test_enable);
+        assert_eq!(
+            pipeline.depth_stencil_state.depth_compare_op,
+            CompareOp::Less
+        );
+        assert_eq!(pipeline.depth_stencil_state.front.reference, 1);
+        assert!(pipeline.dynamic_state.viewport);
+        assert!(!pipeline.dynamic_state.scissor);
+        drop(pipeline);
+    }
+
+    #[test]
+    fn pipeline_with_patch_list_topology_fails() {
+        let input_assembly_state = InputAssemblyState {
+            topology: PrimitiveTopology::PatchList,
+            ..Default::default()
+        };
+        let result = Pipeline::create(
+            create_logical_device(),
+            None,
+            ShaderState::default(),
+            None,
+            Some(input_assembly_state),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(result, Err(RuntimeError::ValidationFailed));
+    }
+
+    #[test]
+    fn pipeline_without_independent_blend_shares_first_attachment_state() {
+        let additive = VkPipelineColorBlendAttachmentState {
+            blendEnable: VK_TRUE,
+            srcColorBlendFactor: VkBlendFactor::VK_BLEND_FACTOR_ONE,
+            dstColorBlendFactor: VkBlendFactor::VK_BLEND_FACTOR_ONE,
+            colorBlendOp: VkBlendOp::VK_BLEND_OP_ADD,
+            srcAlphaBlendFactor: VkBlendFactor::VK_BLEND_FACTOR_ONE,
+            dstAlphaBlendFactor: VkBlendFactor::VK_BLEND_FACTOR_ZERO,
+            alphaBlendOp: VkBlendOp::VK_BLEND_OP_ADD,
+            colorWriteMask: (VkColorComponentFlagBits::VK_COLOR_COMPONENT_R_BIT
+                | VkColorComponentFlagBits::VK_COLOR_COMPONENT_G_BIT
+                | VkColorComponentFlagBits::VK_COLOR_COMPONENT_B_BIT
+                | VkColorComponentFlagBits::VK_COLOR_COMPONENT_A_BIT)
+                .into(),
+        };
+        let attachments = [
+            additive,
+            VkPipelineColorBlendAttachmentState {
+                blendEnable: VK_FALSE,
+                ..additive
+            },
+        ];
+        let color_blend_state = VkPipelineColorBlendStateCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PIPELINE_COLOR_BLEND_STATE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            logicOpEnable: VK_FALSE,
+            logicOp: VkLogicOp::VK_LOGIC_OP_COPY,
+            attachmentCount: attachments.len() as u32,
+            pAttachments: NonNull::new(attachments.as_ptr().cast_mut()),
+            blendConstants: [0.0; 4],
+        };
+        let create_pipeline = |logical_device| {
+            let Ok(pipeline) = Pipeline::create(
+                logical_device,
+                None,
+                ShaderState::default(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(unsafe { PhysicalDevice::parse_color_blend_state(color_blend_state) }),
+                None,
+            ) else {
+                unreachable!()
+            };
+            let Some(pipeline) = Pipeline::from_handle(pipeline) else {
+                unreachable!()
+            };
+            let attachments = pipeline.lock().color_blend_state.attachments;
+            attachments.map(|attachment| attachment.map(|attachment| attachment.blend_enable))
+        };
+
+        let independent = create_pipeline(create_logical_device());
+        assert_eq!(independent[..3], [Some(true), Some(false), None]);
+        let features = VkPhysicalDeviceFeatures {
+            independentBlend: VK_FALSE,
+            ..create_logical_device().lock().physical_device().features()
+        };
+        let shared = create_pipeline(create_logical_device_with_features(Some(&features)));
+        assert_eq!(shared[..3], [Some(true), Some(true), None]);
+    }
+
+    /// Returns create info of vertex shader stage running `main` of `module`.
+    fn vertex_stage(module: VkShaderModule) -> VkPipelineShaderStageCreateInfo {
+        VkPipelineShaderStageCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PIPELINE_SHADER_STAGE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            stage: VkShaderStageFlagBits::VK_SHADER_STAGE_VERTEX_BIT,
+            module,
+            pName: NonNull::new(c"main".as_ptr().cast_mut()),
+            pSpecializationInfo: None,
+        }
+    }
+
+    #[test]
+    fn pipelines_share_parsed_shader_module() {
+        let logical_device = create_logical_device();
+        let parsed_modules = shader::spirv::parsed_modules();
+        let Ok(module) = ShaderModule::create(logical_device, 0, VERTEX_SHADER) else {
+            unreachable!()
+        };
+        let stages = [vertex_stage(module)];
+        for _ in 0..2 {
+            let shader_state = unsafe { PhysicalDevice::parse_shader_stages(&stages, None) };
+            assert!(shader_state.is_ok_and(|state| state.vertex_shader.is_some()));
+        }
+        assert_eq!(shader::spirv::parsed_modules(), parsed_modules + 1);
+    }
+
+    #[test]
+    fn invalid_shader_module_fails_pipeline_with_unknown_error() {
+        let logical_device = create_logical_device();
+        // Header of module without any entry point.
+        let code = [0x0723_0203, 0x0001_0000, 0, 1, 0];
+        let Ok(module) = ShaderModule::create(logical_device, 0, &code) else {
+            unreachable!()
+        };
+        let stages = [vertex_stage(module)];
+        let shader_state = unsafe { PhysicalDevice::parse_shader_stages(&stages, None) };
+        assert_eq!(shader_state.err(), Some(VkResult::VK_ERROR_UNKNOWN));
+    }
+
+    #[test]
+    fn pipeline_cache_data_restores_compiled_shaders() {
+        let logical_device = create_logical_device();
+        let Ok(module) = ShaderModule::create(logical_device.clone(), 0, VERTEX_SHADER) else {
+            unreachable!()
+        };
+        let stages = [vertex_stage(module)];
+        let create_cache = |initial_data: &[u8]| {
+            let Ok(cache) = PipelineCache::create(logical_device.clone(), 0, initial_data) else {
+                unreachable!()
+            };
+            PipelineCache::from_handle(cache).unwrap_or_else(|| unreachable!())
+        };
+        let cache = create_cache(&[]);
+        let shader_state = unsafe { PhysicalDevice::parse_shader_stages(&stages, Some(&cache)) };
+        assert!(shader_state.is_ok());
+        let data = cache.lock().get_data();
+
+        // Shader is taken from restored cache without compiling it again.
+        let restored = create_cache(&data);
+        let shader = restored.lock().get_or_compile(
+            VERTEX_SHADER,
+            "main",
+            &SpecializationInfo::default(),
+            || unreachable!(),
+        );
+        assert!(shader.is_ok());
+
+        // Data of device with other pipeline cache UUID is ignored.
+        let mut foreign = data;
+        foreign[16] ^= 0xff;
+        assert!(create_cache(&foreign).lock().shaders.is_empty());
+    }
+
+    #[test]
+    fn pipelines_reuse_shaders_of_restored_pipeline_cache() {
+        let logical_device = create_logical_device();
+        let Ok(module) = ShaderModule::create(logical_device.clone(), 0, VERTEX_SHADER) else {
+            unreachable!()
+        };
+        let stages = [vertex_stage(module)];
+        let create_cache = |initial_data: &[u8]| {
+            let Ok(cache) = PipelineCache::create(logical_device.clone(), 0, initial_data) else {
+                unreachable!()
+            };
+            PipelineCache::from_handle(cache).unwrap_or_else(|| unreachable!())
+        };
+        let create_pipelines = |cache: &Mutex<PipelineCache>| {
+            for _ in 0..2 {
+                let shader_state =
+                    unsafe { PhysicalDevice::parse_shader_stages(&stages, Some(cache)) };
+                assert!(shader_state.is_ok_and(|state| state.vertex_shader.is_some()));
+            }
+        };
+        let cache = create_cache(&[]);
+        create_pipelines(&cache);
+        let data = cache.lock().get_data();
+        assert_eq!(cache.lock().shaders.len(), 1);
+        let header = data[..16]
+            .chunks_exact(4)
+            .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect::<Vec<_>>();
+        assert_eq!(header, [32, 1, 0, 0]);
+        assert_eq!(data[16..32], PIPELINE_CACHE_UUID);
+
+        // Pipelines created with restored cache add no entries to it.
+        let restored = create_cache(&data);
+        create_pipelines(&restored);
+        assert_eq!(restored.lock().get_data(), data);
+
+        // Data of other payload version is ignored.
+        let mut outdated = data;
+        outdated[32] ^= 0xff;
+        assert!(create_cache(&outdated).lock().shaders.is_empty());
     }
 }
@@ -0,0 +1,181 @@
+//! Query pool
+
+use crate::context::{Named, NonDispatchable};
+use crate::error::RuntimeError;
+use crate::logical_device::LogicalDevice;
+use gpu::{Query, QUERY_SIZE};
+use headers::vk_decls::*;
+use log::*;
+use parking_lot::Mutex;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Queries of single type, each holding its result and availability in device memory.
+#[derive(Debug)]
+pub struct QueryPool {
+    pub(crate) handle: VkNonDispatchableHandle,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
+    query_type: VkQueryType,
+    query_count: u32,
+    gpu_memory_allocation: gpu::MemoryAllocation,
+}
+
+impl QueryPool {
+    pub fn create(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        create_info: &VkQueryPoolCreateInfo,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
+        info!("new QueryPool");
+        let handle = VK_NULL_HANDLE;
+        if create_info.queryType != VkQueryType::VK_QUERY_TYPE_OCCLUSION
+            && create_info.queryType != VkQueryType::VK_QUERY_TYPE_TIMESTAMP
+        {
+            error!("Unsupported query type {:?}", create_info.queryType);
+            return Err(RuntimeError::FeatureNotPresent);
+        }
+
+        // Queries start unavailable, as memory is zeroed on allocation.
+        let gpu_memory_allocation = logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .allocate_memory(create_info.queryCount as u64 * QUERY_SIZE);
+        let object = Self {
+            handle,
+            logical_device,
+            query_type: create_info.queryType,
+            query_count: create_info.queryCount,
+            gpu_memory_allocation,
+        };
+        Ok(object.register_object())
+    }
+
+    pub const fn query_type(&self) -> VkQueryType {
+        self.query_type
+    }
+
+    /// Returns queries `first_query..first_query + query_count`, `None` if they are out of range
+    /// of pool.
+    pub fn queries(&self, first_query: u32, query_count: u32) -> Option<Vec<Query>> {
+        if first_query.checked_add(query_count)? > self.query_count {
+            error!(
+                "Queries {}..{}+{} exceed query count {}",
+                first_query, first_query, query_count, self.query_count
+            );
+            return None;
+        }
+        let memory = self.gpu_memory_allocation;
+        Some(
+            (first_query..first_query + query_count)
+                .map(|index| Query { memory, index })
+                .collect(),
+        )
+    }
+
+    /// Makes queries unavailable from host.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkResetQueryPool
+    pub fn reset(&self, first_query: u32, query_count: u32) {
+        let Some(queries) = self.queries(first_query, query_count) else {
+            return;
+        };
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkResetQueryPool-firstQuery-02741
+        if !LogicalDevice::is_idle(&self.logical_device) {
+            warn!(
+                "Resetting queries {}..{}+{} of {} from host while submitted work is pending",
+                first_query,
+                first_query,
+                query_count,
+                Named::from(self.handle)
+            );
+        }
+        let logical_device = self.logical_device.lock();
+        let mut physical_device = logical_device.physical_device();
+        for query in queries {
+            query.reset(&mut physical_device.gpu.memory);
+        }
+        drop(physical_device);
+        drop(logical_device);
+    }
+
+    /// Writes results of queries into `data`, one every `stride` bytes, followed by their
+    /// availability if requested. Results of unavailable queries are written only when partial
+    /// results are requested, as zero.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkGetQueryPoolResults
+    pub fn results(
+        &self,
+        first_query: u32,
+        query_count: u32,
+        data: &mut [u8],
+        stride: u64,
+        flags: VkQueryResultFlags,
+    ) -> VkResult {
+        let Some(queries) = self.queries(first_query, query_count) else {
+            return VkResult::VK_ERROR_VALIDATION_FAILED_EXT;
+        };
+        let has_flag = |bit: VkQueryResultFlagBits| flags & bit.0 != 0;
+        let value_size = if has_flag(VkQueryResultFlagBits::VK_QUERY_RESULT_64_BIT) {
+            8
+        } else {
+            4
+        };
+        let with_availability =
+            has_flag(VkQueryResultFlagBits::VK_QUERY_RESULT_WITH_AVAILABILITY_BIT);
+        let entry_size = value_size * (1 + usize::from(with_availability));
+        if !stride.is_multiple_of(value_size as u64)
+            || (query_count as usize).saturating_sub(1) * stride as usize + entry_size > data.len()
+        {
+            error!(
+                "Results of {} queries with stride {} exceed data size {}",
+                query_count,
+                stride,
+                data.len()
+            );
+            return VkResult::VK_ERROR_VALIDATION_FAILED_EXT;
+        }
+
+        // Submitted commands finish executing before submit returns, so waiting can't make more
+        // queries available.
+        let logical_device = self.logical_device.lock();
+        let physical_device = logical_device.physical_device();
+        let mut result = VkResult::VK_SUCCESS;
+        for (i, query) in queries.into_iter().enumerate() {
+            let value = query.read_result(&physical_device.gpu.memory);
+            if value.is_none() {
+                if has_flag(VkQueryResultFlagBits::VK_QUERY_RESULT_WAIT_BIT) {
+                    warn!(
+                        "Waiting for query {} of {} that was never ended",
+                        query.index,
+                        Named::from(self.handle)
+                    );
+                }
+                result = VkResult::VK_NOT_READY;
+            }
+            let entry = &mut data[i * stride as usize..];
+            let mut write_value = |offset: usize, value: u64| {
+                let bytes = value.to_le_bytes();
+                entry[offset..offset + value_size].copy_from_slice(&bytes[..value_size]);
+            };
+            if value.is_some() || has_flag(VkQueryResultFlagBits::VK_QUERY_RESULT_PARTIAL_BIT) {
+                write_value(0, value.unwrap_or_default());
+            }
+            if with_availability {
+                write_value(value_size, u64::from(value.is_some()));
+            }
+        }
+        drop(physical_device);
+        drop(logical_device);
+        result
+    }
+}
+
+impl Drop for QueryPool {
+    fn drop(&mut self) {
+        self.logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .free_memory(self.gpu_memory_allocation);
+    }
+}
@@ -1,18 +1,125 @@
 //! Queue
 
 use crate::command_buffer::CommandBuffer;
-use crate::context::Dispatchable;
+use crate::context::{Dispatchable, Named, NonDispatchable};
+use crate::fence::Fence;
 
 use crate::physical_device::PhysicalDevice;
-use crate::semaphore::Semaphore;
+use crate::semaphore::{self, Semaphore};
 use crate::swapchain::Swapchain;
 use headers::vk_decls::*;
 
 use log::*;
 use parking_lot::Mutex;
+use std::collections::VecDeque;
 use std::fmt::Debug;
-
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use tracing::info_span;
+
+/// Batch of work submitted to queue, resolved from `VkSubmitInfo`.
+#[derive(Debug, Default)]
+pub struct SubmitInfo {
+    pub wait_semaphores: Vec<Arc<Mutex<Semaphore>>>,
+    pub wait_dst_stage_masks: Vec<VkPipelineStageFlags>,
+    /// Values timeline semaphores of `wait_semaphores` are waited for, ignored for binary ones.
+    pub wait_semaphore_values: Vec<u64>,
+    pub command_buffers: Vec<Arc<Mutex<CommandBuffer>>>,
+    pub signal_semaphores: Vec<Arc<Mutex<Semaphore>>>,
+    /// Values timeline semaphores of `signal_semaphores` are set to, ignored for binary ones.
+    pub signal_semaphore_values: Vec<u64>,
+}
+
+impl SubmitInfo {
+    /// Resolves batch of `VkQueueSubmit2`, treating stage masks conservatively as all commands.
+    ///
+    /// # Safety
+    ///
+    /// Arrays of `submit` must hold as many valid elements as their counts.
+    pub unsafe fn from_submit_info2(submit: &VkSubmitInfo2) -> Self {
+        let wait_semaphore_infos = submit.pWaitSemaphoreInfos.map_or(&[] as &[_], |x| unsafe {
+            std::slice::from_raw_parts(x.as_ptr(), submit.waitSemaphoreInfoCount as usize)
+        });
+        let command_buffer_infos = submit.pCommandBufferInfos.map_or(&[] as &[_], |x| unsafe {
+            std::slice::from_raw_parts(x.as_ptr(), submit.commandBufferInfoCount as usize)
+        });
+        let signal_semaphore_infos = submit
+            .pSignalSemaphoreInfos
+            .map_or(&[] as &[_], |x| unsafe {
+                std::slice::from_raw_parts(x.as_ptr(), submit.signalSemaphoreInfoCount as usize)
+            });
+        let semaphores = |infos: &[VkSemaphoreSubmitInfo]| {
+            infos
+                .iter()
+                .flat_map(|info| Semaphore::from_handle(info.semaphore).map(|x| (x, info.value)))
+                .unzip::<_, _, Vec<_>, Vec<_>>()
+        };
+        let (wait_semaphores, wait_semaphore_values) = semaphores(wait_semaphore_infos);
+        let (signal_semaphores, signal_semaphore_values) = semaphores(signal_semaphore_infos);
+        Self {
+            wait_dst_stage_masks: vec![
+                VkPipelineStageFlagBits::VK_PIPELINE_STAGE_ALL_COMMANDS_BIT
+                    .into();
+                wait_semaphores.len()
+            ],
+            wait_semaphores,
+            wait_semaphore_values,
+            command_buffers: command_buffer_infos
+                .iter()
+                .flat_map(|info| CommandBuffer::from_handle(info.commandBuffer))
+                .collect(),
+            signal_semaphores,
+            signal_semaphore_values,
+        }
+    }
+}
+
+/// Batches submitted together, followed by fence signaled once all of them are complete.
+#[derive(Debug, Default)]
+struct Submission {
+    submits: VecDeque<SubmitInfo>,
+    fence: Option<Arc<Mutex<Fence>>>,
+    /// Commands left of the first command buffer of the first batch, which stopped executing to
+    /// wait for event that isn't set.
+    waiting: Option<gpu::CommandBuffer>,
+    /// Present deferred until semaphores signaled by pending work are signaled.
+    present: Option<Present>,
+}
+
+/// Present waiting for binary semaphores, of image of each swapchain.
+#[derive(Debug)]
+struct Present {
+    wait_semaphores: Vec<Arc<Mutex<Semaphore>>>,
+    images: Vec<(Arc<Mutex<Swapchain>>, u32)>,
+}
+
+/// State of queue accessed by other threads without locking it, to resume its pending work or to
+/// wait until it is idle.
+#[derive(Debug)]
+pub(crate) struct Schedule {
+    /// Whether thread holding the queue executes its pending submissions, and checks
+    /// `resume_requested` before it stops.
+    executing: AtomicBool,
+    /// Whether semaphore or event was signaled since the queue started executing.
+    resume_requested: AtomicBool,
+    /// Whether no submissions are pending.
+    idle: AtomicBool,
+}
+
+impl Schedule {
+    /// Requests resuming pending work of `queue` after semaphore or event was signaled.
+    ///
+    /// Work is resumed right away if `queue` isn't locked, otherwise by the thread holding it. That
+    /// thread checks the request before it stops executing, or resumes once it unlocks `queue`.
+    pub(crate) fn resume(&self, queue: &Mutex<Queue>) {
+        self.resume_requested.store(true, Ordering::SeqCst);
+        if let Some(mut queue) = queue.try_lock() {
+            queue.execute_pending_submissions();
+        } else if !self.executing.load(Ordering::SeqCst) {
+            queue.lock().execute_pending_submissions();
+        }
+    }
+}
 
 /// Queue associated with `LogicalDevice`.
 #[derive(Debug)]
@@ -21,41 +128,192 @@ pub struct Queue {
     physical_device: Arc<Mutex<PhysicalDevice>>,
     #[allow(dead_code)]
     flags: VkDeviceQueueCreateFlags,
+    priority: f32,
+    pending_submissions: VecDeque<Submission>,
+    schedule: Arc<Schedule>,
 }
 
 impl Queue {
     pub fn create(
         physical_device: Arc<Mutex<PhysicalDevice>>,
-        create_info: &VkDeviceQueueCreateInfo,
+        flags: VkDeviceQueueCreateFlags,
+        priority: f32,
     ) -> VkDispatchableHandle {
         info!("new Queue");
-        let flags = create_info.flags;
-
         let queue = Self {
             handle: VkDispatchableHandle(None),
             physical_device,
             flags,
+            priority,
+            pending_submissions: VecDeque::new(),
+            schedule: Arc::new(Schedule {
+                executing: AtomicBool::new(false),
+                resume_requested: AtomicBool::new(false),
+                idle: AtomicBool::new(true),
+            }),
         };
         queue.register_object()
     }
 
-    pub fn submit<'a>(
+    pub(crate) fn schedule(&self) -> Arc<Schedule> {
+        self.schedule.clone()
+    }
+
+    /// Returns priority queue was created with, in `0.0..=1.0`.
+    ///
+    /// Submissions are executed synchronously, so queues of higher priority aren't scheduled
+    /// ahead of others.
+    pub const fn priority(&self) -> f32 {
+        self.priority
+    }
+
+    /// Executes command buffers of every batch in order and signals `fence` once all of them
+    /// are complete.
+    pub fn submit(
         &mut self,
-        wait_semaphores: impl IntoIterator<Item = Arc<Mutex<Semaphore>>>,
-        wait_semaphores_stage_flags: impl IntoIterator<Item = &'a VkPipelineStageFlags>,
-        signal_semaphores: impl IntoIterator<Item = Arc<Mutex<Semaphore>>>,
-        command_buffers: impl IntoIterator<Item = Arc<Mutex<CommandBuffer>>>,
+        submits: impl IntoIterator<Item = SubmitInfo>,
+        fence: Option<Arc<Mutex<Fence>>>,
     ) {
-        info!("Queue::submit");
-        let _ = wait_semaphores.into_iter();
-        let _ = wait_semaphores_stage_flags.into_iter();
-        let _ = signal_semaphores.into_iter();
-        for command_buffer in command_buffers {
-            let gpu = &mut self.physical_device.lock().gpu;
-            gpu.submit(command_buffer.lock().gpu_command_buffer_for_submit());
+        info!("Queue::submit {}", Named::from(self.handle));
+        let submits = submits.into_iter().collect::<VecDeque<_>>();
+        // Command buffers are pending from submission on, also while their batch waits.
+        for command_buffer in submits.iter().flat_map(|submit| &submit.command_buffers) {
+            command_buffer.lock().mark_pending();
         }
+        self.pending_submissions.push_back(Submission {
+            submits,
+            fence,
+            ..Default::default()
+        });
+        self.schedule.idle.store(false, Ordering::SeqCst);
+        // Command buffers are executed synchronously on submitting thread.
+        self.execute_pending_submissions();
     }
 
+    /// Executes batches of `VkQueueSubmit2` like [`Queue::submit`].
+    ///
+    /// # Safety
+    ///
+    /// Arrays of `submits` must hold as many valid elements as their counts.
+    pub unsafe fn submit2(&mut self, submits: &[VkSubmitInfo2], fence: Option<Arc<Mutex<Fence>>>) {
+        let submits = submits
+            .iter()
+            .map(|submit| unsafe { SubmitInfo::from_submit_info2(submit) })
+            .collect::<Vec<_>>();
+        self.submit(submits, fence);
+    }
+
+    /// Executes submissions in order until none are pending, or one of them waits for event that
+    /// isn't set or for value of timeline semaphore that isn't reached. Such submission is resumed
+    /// once the event is set or the semaphore signaled.
+    pub(crate) fn execute_pending_submissions(&mut self) {
+        loop {
+            self.schedule.executing.store(true, Ordering::SeqCst);
+            self.schedule
+                .resume_requested
+                .store(false, Ordering::SeqCst);
+            while let Some(mut submission) = self.pending_submissions.pop_front() {
+                if !self.execute(&mut submission) {
+                    self.pending_submissions.push_front(submission);
+                    break;
+                }
+            }
+            // Signals of other threads either see that the queue stopped executing, and resume it
+            // themselves, or are seen here.
+            self.schedule.executing.store(false, Ordering::SeqCst);
+            if !self.schedule.resume_requested.load(Ordering::SeqCst) {
+                break;
+            }
+        }
+        if self.pending_submissions.is_empty() {
+            self.schedule.idle.store(true, Ordering::SeqCst);
+            semaphore::notify_signaled();
+        }
+    }
+
+    /// Executes batches of `submission` until they are complete, or returns `false` once batch
+    /// waits for semaphore that isn't signaled or command buffer waits for event that isn't set.
+    fn execute(&self, submission: &mut Submission) -> bool {
+        // Spans nest work of submission down to its draws, each timed while it is executed.
+        let _span = info_span!("queue_submit", batches = submission.submits.len()).entered();
+        while let Some(submit) = submission.submits.front_mut() {
+            let mut waiting = submission.waiting.take();
+            // Waits of batch interrupted by event were done before it started executing.
+            if waiting.is_none() {
+                let reached = submit
+                    .wait_semaphores
+                    .iter()
+                    .enumerate()
+                    .all(|(i, semaphore)| {
+                        let value = submit.wait_semaphore_values.get(i).copied();
+                        semaphore.lock().is_reached(value.unwrap_or_default())
+                    });
+                if !reached {
+                    trace!("Batch waits for semaphore that isn't signaled");
+                    return false;
+                }
+                // Semaphores are signaled, so waits are satisfied immediately.
+                for (i, semaphore) in submit.wait_semaphores.iter().enumerate() {
+                    let value = submit.wait_semaphore_values.get(i).copied();
+                    semaphore.lock().wait(value.unwrap_or_default());
+                }
+            }
+            let _ = submit.wait_dst_stage_masks;
+            while let Some(command_buffer) = submit.command_buffers.first().cloned() {
+                let command_buffer_guard = command_buffer.lock();
+                let handle = Named::from(command_buffer_guard.get_handle());
+                let gpu_command_buffer = waiting
+                    .take()
+                    .unwrap_or_else(|| command_buffer_guard.gpu_command_buffer_for_submit());
+                drop(command_buffer_guard);
+                let _span = info_span!("command_buffer", %handle).entered();
+                let gpu = &mut self.physical_device.lock().gpu;
+                if let Some(remaining) = gpu.submit(gpu_command_buffer) {
+                    trace!("Command buffer {} waits for event", handle);
+                    submission.waiting = Some(remaining);
+                    return false;
+                }
+                command_buffer.lock().complete();
+                submit.command_buffers.remove(0);
+            }
+            for (i, semaphore) in submit.signal_semaphores.iter().enumerate() {
+                let value = submit.signal_semaphore_values.get(i).copied();
+                Semaphore::signal_and_resume(semaphore, value.unwrap_or_default());
+            }
+            submission.submits.pop_front();
+        }
+        if let Some(present) = submission.present.take() {
+            if !present
+                .wait_semaphores
+                .iter()
+                .all(|semaphore| semaphore.lock().is_reached(0))
+            {
+                trace!("Present waits for semaphore that isn't signaled");
+                submission.present = Some(present);
+                return false;
+            }
+            for semaphore in &present.wait_semaphores {
+                semaphore.lock().wait(0);
+            }
+            // Result can no longer be returned to application, which finds out about failures on
+            // its next acquire or present.
+            for (swapchain, image_index) in present.images {
+                let result = swapchain.lock().present(image_index);
+                if let Err(result) = result {
+                    warn!("Deferred present failed with {:?}", result);
+                }
+            }
+        }
+        if let Some(fence) = submission.fence.take() {
+            Fence::signal_and_notify(&fence);
+        }
+        true
+    }
+
+    /// Presents image of each swapchain once `wait_semaphores` are signaled.
+    ///
+    /// Present waiting for work that is still pending, because it waits for event or semaphore,
+    /// is deferred until that work completes, and succeeds for now.
     pub fn present<'a>(
         &mut self,
         wait_semaphores: impl IntoIterator<Item = Arc<Mutex<Semaphore>>>,
@@ -63,10 +321,35 @@ impl Queue {
         image_indices: impl IntoIterator<Item = &'a u32>,
         results: impl IntoIterator<Item = &'a mut VkResult>,
     ) -> Result<VkResult, VkResult> {
-        let _ = wait_semaphores.into_iter();
+        let wait_semaphores = wait_semaphores.into_iter().collect::<Vec<_>>();
         let mut swapchains = swapchains.into_iter();
         let mut image_indices = image_indices.into_iter();
         let mut results = results.into_iter();
+        self.execute_pending_submissions();
+        let signaled = wait_semaphores
+            .iter()
+            .all(|semaphore| semaphore.lock().is_reached(0));
+        if !self.pending_submissions.is_empty() || !signaled {
+            trace!("Present waits for pending work");
+            let images = swapchains.zip(image_indices.copied()).collect();
+            self.pending_submissions.push_back(Submission {
+                present: Some(Present {
+                    wait_semaphores,
+                    images,
+                }),
+                ..Default::default()
+            });
+            self.schedule.idle.store(false, Ordering::SeqCst);
+            // Semaphores may have been signaled by other queues in the meantime.
+            self.execute_pending_submissions();
+            for result in results {
+                *result = VkResult::VK_SUCCESS;
+            }
+            return Ok(VkResult::VK_SUCCESS);
+        }
+        for semaphore in &wait_semaphores {
+            semaphore.lock().wait(0);
+        }
         let mut last_failure = Ok(VkResult::VK_SUCCESS);
         loop {
             let (Some(swapchain), Some(image_index), result) =
@@ -81,14 +364,30 @@ impl Queue {
                     Err(result) => result,
                 };
             }
-            if last_failure.is_ok() && last_result.is_err() {
+            // Errors take precedence over suboptimal swapchains, which precede success.
+            if last_failure == Ok(VkResult::VK_SUCCESS)
+                || (last_failure.is_ok() && last_result.is_err())
+            {
                 last_failure = last_result;
             }
         }
     }
 
-    pub fn wait_idle(&self) -> VkResult {
-        warn!("TODO: LogicalDevice wait idle");
-        VkResult::VK_SUCCESS
+    /// Returns whether all submitted work has completed.
+    pub fn is_idle(&self) -> bool {
+        self.pending_submissions.is_empty()
+    }
+
+    /// Blocks until all work submitted to `queue` has completed.
+    ///
+    /// Work waiting for event or semaphore completes once another thread signals it, so `queue`
+    /// is unlocked while waiting.
+    pub fn wait_idle(queue: &Mutex<Self>) -> VkResult {
+        let mut queue = queue.lock();
+        trace!("Queue::wait_idle {}", Named::from(queue.handle));
+        queue.execute_pending_submissions();
+        let schedule = queue.schedule();
+        drop(queue);
+        semaphore::wait_for_signal(u64::MAX, || schedule.idle.load(Ordering::SeqCst))
     }
 }
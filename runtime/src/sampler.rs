@@ -1,7 +1,9 @@
 //! Sampler
 
 use crate::context::NonDispatchable;
+use crate::error::RuntimeError;
 use crate::logical_device::LogicalDevice;
+use common::graphics::SamplerState;
 use headers::vk_decls::*;
 use log::*;
 use parking_lot::Mutex;
@@ -13,16 +15,18 @@ use std::sync::Arc;
 pub struct Sampler {
     pub(crate) handle: VkNonDispatchableHandle,
     #[allow(dead_code)]
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     #[allow(dead_code)]
     flags: VkSamplerCreateFlags,
+    pub state: SamplerState,
 }
 
 impl Sampler {
     pub fn create(
         logical_device: Arc<Mutex<LogicalDevice>>,
         flags: VkSamplerCreateFlags,
-    ) -> VkNonDispatchableHandle {
+        state: SamplerState,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new Sampler");
         let handle = VK_NULL_HANDLE;
 
@@ -30,7 +34,32 @@ impl Sampler {
             handle,
             logical_device,
             flags,
+            state,
         };
-        object.register_object()
+        Ok(object.register_object())
+    }
+
+    pub fn parse_state(create_info: &VkSamplerCreateInfo) -> SamplerState {
+        if create_info.anisotropyEnable != 0 {
+            warn!("TODO: Anisotropic filtering");
+        }
+        if create_info.compareEnable != 0 {
+            warn!("TODO: Depth compare sampling");
+        }
+        if create_info.unnormalizedCoordinates != 0 {
+            warn!("TODO: Unnormalized sampler coordinates");
+        }
+        SamplerState {
+            mag_filter: create_info.magFilter.into(),
+            min_filter: create_info.minFilter.into(),
+            mipmap_mode: create_info.mipmapMode.into(),
+            address_mode_u: create_info.addressModeU.into(),
+            address_mode_v: create_info.addressModeV.into(),
+            address_mode_w: create_info.addressModeW.into(),
+            mip_lod_bias: create_info.mipLodBias,
+            min_lod: create_info.minLod,
+            max_lod: create_info.maxLod,
+            border_color: create_info.borderColor.into(),
+        }
     }
 }
@@ -1,30 +1,210 @@
 //! Semaphore
 
-use crate::context::NonDispatchable;
-
+use crate::context::{Named, NonDispatchable};
+use crate::error::RuntimeError;
+use crate::logical_device::LogicalDevice;
 
 use headers::vk_decls::*;
 use log::*;
-
+use parking_lot::{Condvar, Mutex};
 use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+/// Notified whenever semaphore or fence is signaled or queue becomes idle, waking host threads
+/// waiting for them.
+static SIGNALED: Condvar = Condvar::new();
+/// Held while checking semaphores, fences or queues before waiting for [`SIGNALED`], so that
+/// signals can't be missed in between.
+static SIGNAL_LOCK: Mutex<()> = Mutex::new(());
 
 /// Synchronization primitive that can be used to insert a dependency between queue operations or
 /// between a queue operation and the host.
 #[derive(Debug)]
 pub struct Semaphore {
     pub(crate) handle: VkNonDispatchableHandle,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     #[allow(dead_code)]
     flags: VkSemaphoreCreateFlags,
+    semaphore_type: VkSemaphoreType,
+    signaled: bool,
+    /// Counter of timeline semaphore, only ever increasing.
+    value: u64,
 }
 
 impl Semaphore {
-    pub fn create(create_info: &VkSemaphoreCreateInfo) -> VkNonDispatchableHandle {
+    pub fn create(
+        logical_device: Arc<Mutex<LogicalDevice>>,
+        create_info: &VkSemaphoreCreateInfo,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new Semaphore");
         let handle = VK_NULL_HANDLE;
         let flags = create_info.flags;
 
-        let semaphore = Self { handle, flags };
-        semaphore.register_object()
+        let mut semaphore_type = VkSemaphoreType::VK_SEMAPHORE_TYPE_BINARY;
+        let mut value = 0;
+        let mut next = create_info.pNext.map(NonNull::cast::<VkBaseInStructure>);
+        while let Some(structure) = next {
+            let structure = unsafe { structure.as_ref() };
+            if structure.sType == VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_TYPE_CREATE_INFO {
+                let type_create_info = unsafe {
+                    NonNull::from(structure)
+                        .cast::<VkSemaphoreTypeCreateInfo>()
+                        .as_ref()
+                };
+                semaphore_type = type_create_info.semaphoreType;
+                value = type_create_info.initialValue;
+            }
+            next = structure.pNext;
+        }
+
+        let semaphore = Self {
+            handle,
+            logical_device,
+            flags,
+            semaphore_type,
+            signaled: false,
+            value,
+        };
+        Ok(semaphore.register_object())
+    }
+
+    pub const fn semaphore_type(&self) -> VkSemaphoreType {
+        self.semaphore_type
+    }
+
+    fn is_timeline(&self) -> bool {
+        self.semaphore_type == VkSemaphoreType::VK_SEMAPHORE_TYPE_TIMELINE
+    }
+
+    /// Signals binary semaphore, or sets counter of timeline semaphore to `value`.
+    pub fn signal(&mut self, value: u64) {
+        trace!(
+            "semaphore {} {} signal {}",
+            Named::from(self.handle),
+            self.signaled,
+            value
+        );
+        if !self.is_timeline() {
+            self.signaled = true;
+            return;
+        }
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkSemaphoreSignalInfo-value-03258
+        if value <= self.value {
+            error!(
+                "Timeline semaphore signaled with value {} not greater than its value {}",
+                value, self.value
+            );
+            return;
+        }
+        self.value = value;
+    }
+
+    /// Signals `semaphore` like [`Semaphore::signal`], then wakes host threads waiting for
+    /// semaphore values and resumes work submitted to queues of its device that waits for it.
+    pub fn signal_and_resume(semaphore: &Arc<Mutex<Self>>, value: u64) {
+        let mut semaphore = semaphore.lock();
+        semaphore.signal(value);
+        let logical_device = semaphore.logical_device.clone();
+        drop(semaphore);
+        notify_signaled();
+        LogicalDevice::resume_queues(&logical_device);
+    }
+
+    /// Returns whether queue operation waiting for `value` can start. Timeline semaphores must
+    /// have reached it, while binary ones must be signaled, which happens after their signal was
+    /// submitted if that work waits itself.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-vkQueueSubmit-pWaitSemaphores-03238
+    pub fn is_reached(&self, value: u64) -> bool {
+        if self.is_timeline() {
+            self.value >= value
+        } else {
+            self.signaled
+        }
+    }
+
+    /// Unsignals binary semaphore waited on by queue operation. Timeline semaphores keep their
+    /// value, which is reached before queue operation starts.
+    pub fn wait(&mut self, value: u64) {
+        trace!(
+            "semaphore {} {} wait {}",
+            Named::from(self.handle),
+            self.signaled,
+            value
+        );
+        if !self.is_timeline() {
+            self.signaled = false;
+        }
+    }
+
+    /// Returns current counter value of timeline semaphore.
+    pub const fn counter_value(&self) -> u64 {
+        self.value
+    }
+
+    /// Waits on host for all or any of timeline `semaphores` to reach their values, for at most
+    /// `timeout` nanoseconds.
+    ///
+    /// Values are reached once another thread signals them from host, or by work that waited for
+    /// other semaphores or events and resumes.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkWaitSemaphores
+    pub fn wait_for_values(
+        semaphores: &[(Arc<Mutex<Self>>, u64)],
+        wait_any: bool,
+        timeout: u64,
+    ) -> VkResult {
+        wait_for_signal(timeout, || {
+            let mut reached = semaphores
+                .iter()
+                .map(|(semaphore, value)| semaphore.lock().value >= *value);
+            if wait_any {
+                reached.any(|reached| reached)
+            } else {
+                reached.all(|reached| reached)
+            }
+        })
+    }
+}
+
+/// Wakes host threads waiting in [`wait_for_signal`], after semaphore or fence was signaled or
+/// queue became idle.
+///
+/// Callers must not hold locks of objects checked by waiting threads, which check them while
+/// holding [`SIGNAL_LOCK`].
+pub(crate) fn notify_signaled() {
+    let signal_lock = SIGNAL_LOCK.lock();
+    SIGNALED.notify_all();
+    drop(signal_lock);
+}
+
+/// Blocks host thread until `satisfied` returns `true`, for at most `timeout` nanoseconds.
+/// `satisfied` is checked again whenever [`notify_signaled`] is called.
+pub(crate) fn wait_for_signal(timeout: u64, satisfied: impl Fn() -> bool) -> VkResult {
+    let deadline = Instant::now().checked_add(Duration::from_nanos(timeout));
+    let mut signal_lock = SIGNAL_LOCK.lock();
+    loop {
+        if satisfied() {
+            return VkResult::VK_SUCCESS;
+        }
+        if timeout == 0 {
+            return VkResult::VK_TIMEOUT;
+        }
+        // Deadline too far to represent is never reached.
+        let timed_out = match deadline {
+            Some(deadline) => SIGNALED.wait_until(&mut signal_lock, deadline).timed_out(),
+            None => {
+                SIGNALED.wait(&mut signal_lock);
+                false
+            }
+        };
+        if timed_out {
+            let result = if satisfied() {
+                VkResult::VK_SUCCESS
+            } else {
+                VkResult::VK_TIMEOUT
+            };
+            drop(signal_lock);
+            return result;
+        }
     }
 }
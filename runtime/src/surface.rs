@@ -1,61 +1,558 @@
-//! XCB surface
+//! XCB, Wayland and headless surfaces
 
 use crate::context::NonDispatchable;
+use crate::error::RuntimeError;
 use crate::instance::Instance;
-use crate::memory::MemoryAllocation;
-use common::math::Extent3;
+use common::math::Extent2;
 use headers::vk_decls::*;
 use log::*;
 use parking_lot::Mutex;
+use std::any::Any;
 use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::io::ErrorKind;
 use std::mem::ManuallyDrop;
+use std::os::fd::{AsFd, FromRawFd, OwnedFd};
+use std::os::unix::fs::FileExt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use wayland_client::backend::{Backend, ObjectId, WaylandError};
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::wl_buffer::{self, WlBuffer};
+use wayland_client::protocol::wl_registry::WlRegistry;
+use wayland_client::protocol::wl_shm::{self, WlShm};
+use wayland_client::protocol::wl_shm_pool::WlShmPool;
+use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, Proxy, QueueHandle};
 use xcb;
 use xcb::x;
+use xcb::Xid;
 
+/// Extent of surfaces without window size, used when their environment variable is not set.
+const DEFAULT_EXTENT: Extent2<u32> = Extent2 {
+    width: 800,
+    height: 600,
+};
+
+/// Reads extent from environment variable `name` formatted as `<width>x<height>`.
+fn extent_from_env(name: &str) -> Extent2<u32> {
+    std::env::var(name)
+        .ok()
+        .and_then(|x| {
+            let (width, height) = x.split_once('x')?;
+            Some(Extent2 {
+                width: width.parse().ok()?,
+                height: height.parse().ok()?,
+            })
+        })
+        .unwrap_or(DEFAULT_EXTENT)
+}
+
+/// Formats of swapchain images presented by surfaces.
+const SURFACE_FORMATS: [VkFormat; 2] = [
+    VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+    VkFormat::VK_FORMAT_R8G8B8A8_SRGB,
+];
+
+/// Window of windowing system images are presented to, implemented by each surface backend.
+pub trait PresentTarget: Any + Debug + Send + Sync {
+    /// Returns current extent of window, which is zero while window is minimized.
+    fn extent(&self) -> Result<Extent2<u32>, VkResult>;
+
+    /// Presents `pixels` of the first array layer of image, tightly packed rows of texels of
+    /// `format`. Returns `VK_SUBOPTIMAL_KHR` if `extent` doesn't match window.
+    fn present(
+        &mut self,
+        pixels: &[u8],
+        format: VkFormat,
+        extent: Extent2<u32>,
+    ) -> Result<VkResult, VkResult>;
+
+    /// Returns formats of images window can present.
+    fn supported_formats(&self) -> &[VkFormat];
+}
+
+#[derive(Debug)]
 pub struct Surface {
     pub(crate) handle: VkNonDispatchableHandle,
+    #[allow(dead_code)]
     instance: Arc<Mutex<Instance>>,
-    flags: VkXcbSurfaceCreateFlagsKHR,
-    connection: ManuallyDrop<xcb::Connection>,
-    window: ManuallyDrop<x::Window>,
-
-    gc: Option<x::Gcontext>,
-    depth: Option<u8>,
+    target: Box<dyn PresentTarget>,
 }
 
 impl Surface {
     pub fn create(
         instance: Arc<Mutex<Instance>>,
         create_info: &VkXcbSurfaceCreateInfoKHR,
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new Surface");
+        let window = XcbWindow::new(create_info)?;
+        Ok(Self::create_with_target(instance, Box::new(window)))
+    }
+
+    /// Creates surface of Wayland `wl_surface`. Wayland windows take the size of buffers attached
+    /// to them, which is read from `ICD_WAYLAND_EXTENT` as `<width>x<height>`.
+    pub fn create_wayland(
+        instance: Arc<Mutex<Instance>>,
+        create_info: &VkWaylandSurfaceCreateInfoKHR,
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
+        info!("new Wayland Surface");
+        let window = WaylandWindow::new(create_info)?;
+        Ok(Self::create_with_target(instance, Box::new(window)))
+    }
+
+    /// Creates surface without window, which keeps the last presented image for readback. Its
+    /// extent is read from `ICD_HEADLESS_EXTENT` as `<width>x<height>`.
+    pub fn create_headless(
+        instance: Arc<Mutex<Instance>>,
+        create_info: &VkHeadlessSurfaceCreateInfoEXT,
+    ) -> VkNonDispatchableHandle {
+        info!("new headless Surface");
+        let _ = create_info.flags;
+        let window = HeadlessWindow {
+            extent: extent_from_env("ICD_HEADLESS_EXTENT"),
+            presented_image: None,
+        };
+        Self::create_with_target(instance, Box::new(window))
+    }
+
+    /// Creates surface presenting to `target` of any window system.
+    pub fn create_with_target(
+        instance: Arc<Mutex<Instance>>,
+        target: Box<dyn PresentTarget>,
+    ) -> VkNonDispatchableHandle {
         let handle = VK_NULL_HANDLE;
+        let surface = Self {
+            handle,
+            instance,
+            target,
+        };
+        surface.register_object()
+    }
+
+    pub fn target(&self) -> &dyn PresentTarget {
+        self.target.as_ref()
+    }
+
+    pub fn target_mut(&mut self) -> &mut dyn PresentTarget {
+        self.target.as_mut()
+    }
+
+    /// Resizes headless surface, as window system would resize window.
+    pub fn set_headless_extent(&mut self, extent: Extent2<u32>) {
+        let target: &mut dyn Any = self.target.as_mut();
+        match target.downcast_mut::<HeadlessWindow>() {
+            Some(window) => window.extent = extent,
+            None => error!("Resizing surface that is not headless"),
+        }
+    }
+
+    /// Returns the last image presented to headless surface.
+    pub fn presented_image(&self) -> Option<&PresentedImage> {
+        let target: &dyn Any = self.target.as_ref();
+        target
+            .downcast_ref::<HeadlessWindow>()?
+            .presented_image
+            .as_ref()
+    }
+}
+
+/// Image presented to headless surface, with texels of its first array layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresentedImage {
+    pub format: VkFormat,
+    pub extent: Extent2<u32>,
+    pub data: Vec<u8>,
+}
+
+/// Surface without window, retaining presented image in memory.
+#[derive(Debug)]
+struct HeadlessWindow {
+    extent: Extent2<u32>,
+    presented_image: Option<PresentedImage>,
+}
+
+impl PresentTarget for HeadlessWindow {
+    fn extent(&self) -> Result<Extent2<u32>, VkResult> {
+        Ok(self.extent)
+    }
+
+    fn present(
+        &mut self,
+        pixels: &[u8],
+        format: VkFormat,
+        extent: Extent2<u32>,
+    ) -> Result<VkResult, VkResult> {
+        self.presented_image = Some(PresentedImage {
+            format,
+            extent,
+            data: pixels.to_vec(),
+        });
+        if extent == self.extent {
+            Ok(VkResult::VK_SUCCESS)
+        } else {
+            Ok(VkResult::VK_SUBOPTIMAL_KHR)
+        }
+    }
+
+    fn supported_formats(&self) -> &[VkFormat] {
+        &SURFACE_FORMATS
+    }
+}
+
+/// Surface of Wayland connection owned by application.
+struct WaylandWindow {
+    flags: VkWaylandSurfaceCreateFlagsKHR,
+    connection: Connection,
+    surface: WlSurface,
+    extent: Extent2<u32>,
+
+    shm: Option<WaylandShm>,
+}
+
+/// Event queue and shared memory buffers of surface, created on first present.
+struct WaylandShm {
+    event_queue: EventQueue<WaylandState>,
+    shm: WlShm,
+    buffers: Vec<ShmBuffer>,
+}
+
+impl WaylandShm {
+    /// Most buffers surface keeps, further presents wait for compositor to release one of them.
+    const MAX_BUFFERS: usize = 3;
+
+    /// Reads events compositor sent, like releases of buffers, without blocking and dispatches
+    /// them.
+    fn dispatch_events(&mut self) -> Result<(), VkResult> {
+        if let Some(guard) = self.event_queue.prepare_read() {
+            match guard.read() {
+                Ok(_) => {}
+                Err(WaylandError::Io(err)) if err.kind() == ErrorKind::WouldBlock => {}
+                Err(err) => {
+                    error!("Reading Wayland events failed: {}", err);
+                    return Err(VkResult::VK_ERROR_SURFACE_LOST_KHR);
+                }
+            }
+        }
+        self.event_queue
+            .dispatch_pending(&mut WaylandState)
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+        Ok(())
+    }
+
+    /// Returns index of released buffer of `extent`, which is created if there are less than
+    /// [`WaylandShm::MAX_BUFFERS`], or waits for compositor to release one otherwise.
+    fn released_buffer(&mut self, extent: Extent2<u32>) -> Result<usize, VkResult> {
+        self.dispatch_events()?;
+        loop {
+            // Released buffers of other extent won't be attached anymore.
+            self.buffers.retain(|buffer| {
+                let used = buffer.extent == extent || buffer.busy.load(Ordering::Acquire);
+                if !used {
+                    buffer.buffer.destroy();
+                }
+                used
+            });
+            if let Some(index) = self
+                .buffers
+                .iter()
+                .position(|buffer| buffer.extent == extent && !buffer.busy.load(Ordering::Acquire))
+            {
+                return Ok(index);
+            }
+            if self.buffers.len() < Self::MAX_BUFFERS {
+                let buffer = ShmBuffer::new(&self.shm, &self.event_queue.handle(), extent)?;
+                self.buffers.push(buffer);
+                return Ok(self.buffers.len() - 1);
+            }
+            self.event_queue
+                .blocking_dispatch(&mut WaylandState)
+                .map_err(|err| {
+                    error!("Waiting for release of Wayland buffer failed: {}", err);
+                    VkResult::VK_ERROR_SURFACE_LOST_KHR
+                })?;
+        }
+    }
+}
+
+/// Dispatch state of surface event queues, which only track releases of buffers.
+struct WaylandState;
+
+impl Dispatch<WlRegistry, GlobalListContents> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WlRegistry,
+        _event: <WlRegistry as Proxy>::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlBuffer, Arc<AtomicBool>> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WlBuffer,
+        event: wl_buffer::Event,
+        busy: &Arc<AtomicBool>,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if matches!(event, wl_buffer::Event::Release) {
+            busy.store(false, Ordering::Release);
+        }
+    }
+}
+
+delegate_noop!(WaylandState: ignore WlShm);
+delegate_noop!(WaylandState: WlShmPool);
+
+/// Buffer of `XRGB8888` pixels in shared memory file, busy from attach until compositor releases
+/// it.
+struct ShmBuffer {
+    buffer: WlBuffer,
+    file: File,
+    extent: Extent2<u32>,
+    busy: Arc<AtomicBool>,
+}
+
+impl ShmBuffer {
+    const BYTES_PER_PIXEL: u32 = 4;
+
+    fn new(
+        shm: &WlShm,
+        queue_handle: &QueueHandle<WaylandState>,
+        extent: Extent2<u32>,
+    ) -> Result<Self, VkResult> {
+        let stride = extent.width * Self::BYTES_PER_PIXEL;
+        let size = stride as u64 * extent.height as u64;
+        let fd = unsafe { libc::memfd_create(c"WaylandBuffer".as_ptr(), libc::MFD_CLOEXEC) };
+        if fd < 0 {
+            error!("Creating memfd failed: {}", std::io::Error::last_os_error());
+            return Err(VkResult::VK_ERROR_OUT_OF_HOST_MEMORY);
+        }
+        let file = File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        if let Err(err) = file.set_len(size) {
+            error!("Resizing memfd to {} bytes failed: {}", size, err);
+            return Err(VkResult::VK_ERROR_OUT_OF_HOST_MEMORY);
+        }
+
+        let pool = shm.create_pool(file.as_fd(), size as i32, queue_handle, ());
+        let busy = Arc::new(AtomicBool::new(false));
+        let buffer = pool.create_buffer(
+            0,
+            extent.width as i32,
+            extent.height as i32,
+            stride as i32,
+            wl_shm::Format::Xrgb8888,
+            queue_handle,
+            busy.clone(),
+        );
+        // Buffer keeps memory of pool alive.
+        pool.destroy();
+        Ok(Self {
+            buffer,
+            file,
+            extent,
+            busy,
+        })
+    }
+
+    /// Converts texels of image into buffer pixels.
+    fn convert(data: &[u8], format: VkFormat) -> Vec<u8> {
+        let (r, g, b) = match format {
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM | VkFormat::VK_FORMAT_R8G8B8A8_SRGB => (0, 1, 2),
+            VkFormat::VK_FORMAT_B8G8R8A8_UNORM | VkFormat::VK_FORMAT_B8G8R8A8_SRGB => (2, 1, 0),
+            _ => unimplemented!("{:?}", format),
+        };
+        // Pixels are little-endian 32-bit words with unused top byte.
+        data.chunks_exact(format.bytes_per_pixel() as usize)
+            .flat_map(|texel| [texel[b], texel[g], texel[r], 0xFF])
+            .collect()
+    }
+}
+
+impl WaylandWindow {
+    fn new(create_info: &VkWaylandSurfaceCreateInfoKHR) -> Result<Self, RuntimeError> {
         let flags = create_info.flags;
-        let Some(connection) = create_info.connection else {
+        let (Some(display), Some(surface)) = (create_info.display, create_info.surface) else {
+            error!("Wayland display or surface is null");
+            return Err(RuntimeError::ValidationFailed);
+        };
+        // Display and surface are owned by application, surface must neither disconnect nor
+        // destroy them.
+        let connection = Connection::from_backend(unsafe {
+            Backend::from_foreign_display(display.as_ptr().cast())
+        });
+        let surface =
+            unsafe { ObjectId::from_ptr(WlSurface::interface(), surface.as_ptr().cast()) }
+                .and_then(|id| WlSurface::from_id(&connection, id))
+                .map_err(|_| {
+                    error!("Wayland surface {:?} is not wl_surface", surface);
+                    RuntimeError::ValidationFailed
+                })?;
+
+        Ok(Self {
+            flags,
+            connection,
+            surface,
+            extent: extent_from_env("ICD_WAYLAND_EXTENT"),
+            shm: None,
+        })
+    }
+
+    fn shm(&mut self) -> Result<&mut WaylandShm, VkResult> {
+        if self.shm.is_none() {
+            let (globals, event_queue) = registry_queue_init(&self.connection).map_err(|err| {
+                error!("Reading Wayland globals failed: {}", err);
+                VkResult::VK_ERROR_SURFACE_LOST_KHR
+            })?;
+            let shm = globals
+                .bind(&event_queue.handle(), 1..=1, ())
+                .map_err(|err| {
+                    error!("Binding wl_shm failed: {}", err);
+                    VkResult::VK_ERROR_SURFACE_LOST_KHR
+                })?;
+            self.shm = Some(WaylandShm {
+                event_queue,
+                shm,
+                buffers: Vec::new(),
+            });
+        }
+        let Some(shm) = self.shm.as_mut() else {
             unreachable!()
         };
+        Ok(shm)
+    }
+}
+
+impl PresentTarget for WaylandWindow {
+    fn extent(&self) -> Result<Extent2<u32>, VkResult> {
+        Ok(self.extent)
+    }
+
+    /// Attaches buffer with image to surface and commits it.
+    // NOTE: https://wayland.freedesktop.org/docs/html/apa.html#protocol-spec-wl_surface
+    fn present(
+        &mut self,
+        pixels: &[u8],
+        format: VkFormat,
+        extent: Extent2<u32>,
+    ) -> Result<VkResult, VkResult> {
+        let result = if extent == self.extent {
+            VkResult::VK_SUCCESS
+        } else {
+            VkResult::VK_SUBOPTIMAL_KHR
+        };
+        if extent.width == 0 || extent.height == 0 {
+            return Ok(result);
+        }
+        let data = ShmBuffer::convert(pixels, format);
+
+        let shm = self.shm()?;
+        let index = shm.released_buffer(extent)?;
+        let buffer = &shm.buffers[index];
+        buffer.file.write_all_at(&data, 0).map_err(|err| {
+            error!("Writing Wayland buffer failed: {}", err);
+            VkResult::VK_ERROR_OUT_OF_HOST_MEMORY
+        })?;
+        buffer.busy.store(true, Ordering::Release);
+        let buffer = buffer.buffer.clone();
+
+        self.surface.attach(Some(&buffer), 0, 0);
+        self.surface
+            .damage(0, 0, extent.width as i32, extent.height as i32);
+        self.surface.commit();
+        self.connection
+            .flush()
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+
+        Ok(result)
+    }
+
+    fn supported_formats(&self) -> &[VkFormat] {
+        &SURFACE_FORMATS
+    }
+}
+
+impl Drop for WaylandWindow {
+    fn drop(&mut self) {
+        // Free only resources created by surface itself.
+        if let Some(shm) = self.shm.take() {
+            for buffer in shm.buffers {
+                buffer.buffer.destroy();
+            }
+            let _ = self.connection.flush();
+        }
+    }
+}
+
+impl Debug for WaylandWindow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WaylandWindow")
+            .field("flags", &self.flags)
+            .field("display", &self.connection.backend().display_ptr())
+            .field("surface", &self.surface.id())
+            .field("extent", &self.extent)
+            .finish()
+    }
+}
+
+/// Window of XCB connection owned by application.
+struct XcbWindow {
+    flags: VkXcbSurfaceCreateFlagsKHR,
+    connection: ManuallyDrop<xcb::Connection>,
+    window: ManuallyDrop<x::Window>,
+
+    draw_state: Option<XcbDrawState>,
+}
+
+impl XcbWindow {
+    fn new(create_info: &VkXcbSurfaceCreateInfoKHR) -> Result<Self, RuntimeError> {
+        let flags = create_info.flags;
+        let Some(connection) = create_info.connection else {
+            error!("XCB connection is null");
+            return Err(RuntimeError::ValidationFailed);
+        };
+        if create_info.window == x::Window::none().resource_id() {
+            error!("XCB window is none");
+            return Err(RuntimeError::ValidationFailed);
+        }
+        // Connection and window are owned by application, surface must neither disconnect nor
+        // destroy them.
         let connection =
             unsafe { ManuallyDrop::new(xcb::Connection::from_raw_conn(connection.as_ptr())) };
         let window = unsafe { ManuallyDrop::new(xcb::XidNew::new(create_info.window)) };
 
-        let surface = Self {
-            handle,
-            instance,
+        let window = Self {
             flags,
             connection,
             window,
-            gc: None,
-            depth: None,
+            draw_state: None,
         };
-        surface.register_object()
+        if window.current_extent().is_err() {
+            error!("XCB window {:?} does not exist", *window.window);
+            return Err(RuntimeError::ValidationFailed);
+        }
+        Ok(window)
     }
 }
 
-impl Debug for Surface {
+impl Drop for XcbWindow {
+    fn drop(&mut self) {
+        // Free only resources created by surface itself.
+        if let Some(draw_state) = self.draw_state.take() {
+            self.connection
+                .send_request(&x::FreeGc { gc: draw_state.gc });
+            let _ = self.connection.flush();
+        }
+    }
+}
+
+impl Debug for XcbWindow {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Point")
-            .field("instance", &self.instance)
+        f.debug_struct("XcbWindow")
             .field("flags", &self.flags)
             .field("connection", &self.connection.get_raw_conn())
             .field("window", &self.window)
@@ -63,81 +560,532 @@ impl Debug for Surface {
     }
 }
 
-impl Surface {
-    pub fn present(
+impl XcbWindow {
+    fn current_extent(&self) -> Result<Extent2<u32>, VkResult> {
+        let cookie = self.connection.send_request(&x::GetGeometry {
+            drawable: x::Drawable::Window(*self.window),
+        });
+        let reply = self
+            .connection
+            .wait_for_reply(cookie)
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+        Ok(Extent2 {
+            width: reply.width() as u32,
+            height: reply.height() as u32,
+        })
+    }
+
+    /// Checks whether window manager iconified window.
+    // NOTE: https://x.org/releases/X11R7.6/doc/xorg-docs/specs/ICCCM/icccm.html#WM_STATE_Property
+    fn is_minimized(&self) -> Result<bool, VkResult> {
+        const ICONIC_STATE: u32 = 3;
+
+        let cookie = self.connection.send_request(&x::InternAtom {
+            only_if_exists: true,
+            name: b"WM_STATE",
+        });
+        let wm_state = self
+            .connection
+            .wait_for_reply(cookie)
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?
+            .atom();
+        if wm_state == x::ATOM_NONE {
+            // No window manager is running.
+            return Ok(false);
+        }
+
+        let cookie = self.connection.send_request(&x::GetProperty {
+            delete: false,
+            window: *self.window,
+            property: wm_state,
+            r#type: wm_state,
+            long_offset: 0,
+            long_length: 1,
+        });
+        let reply = self
+            .connection
+            .wait_for_reply(cookie)
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+        if reply.format() != 32 {
+            return Ok(false);
+        }
+        Ok(reply.value::<u32>().first() == Some(&ICONIC_STATE))
+    }
+
+    fn draw_state(&mut self) -> Result<XcbDrawState, VkResult> {
+        if let Some(draw_state) = self.draw_state {
+            return Ok(draw_state);
+        }
+
+        self.connection
+            .send_and_check_request(&x::ChangeWindowAttributes {
+                window: *self.window,
+                value_list: &[x::Cw::EventMask(x::EventMask::EXPOSURE)],
+            })
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+        let gc = self.connection.generate_id();
+        self.connection
+            .send_and_check_request(&x::CreateGc {
+                cid: gc,
+                drawable: x::Drawable::Window(*self.window),
+                value_list: &[x::Gc::GraphicsExposures(true)],
+            })
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+        self.connection
+            .send_and_check_request(&x::MapWindow {
+                window: *self.window,
+            })
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+        self.connection
+            .flush()
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+
+        let cookie = self.connection.send_request(&x::GetGeometry {
+            drawable: x::Drawable::Window(*self.window),
+        });
+        let reply = self
+            .connection
+            .wait_for_reply(cookie)
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+        let depth = reply.depth();
+
+        let cookie = self.connection.send_request(&x::GetWindowAttributes {
+            window: *self.window,
+        });
+        let reply = self
+            .connection
+            .wait_for_reply(cookie)
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+        let visual_id = reply.visual();
+
+        let setup = self.connection.get_setup();
+        let Some(visual) = setup
+            .roots()
+            .flat_map(|screen| screen.allowed_depths())
+            .flat_map(|depth| depth.visuals().iter())
+            .find(|visual| visual.visual_id() == visual_id)
+        else {
+            return Err(VkResult::VK_ERROR_SURFACE_LOST_KHR);
+        };
+        let Some(pixmap_format) = setup
+            .pixmap_formats()
+            .iter()
+            .find(|format| format.depth() == depth)
+        else {
+            return Err(VkResult::VK_ERROR_SURFACE_LOST_KHR);
+        };
+        if pixmap_format.bits_per_pixel() != 32 {
+            unimplemented!("{} bits per pixel", pixmap_format.bits_per_pixel());
+        }
+
+        let draw_state = XcbDrawState {
+            gc,
+            depth,
+            red_shift: visual.red_mask().trailing_zeros(),
+            green_shift: visual.green_mask().trailing_zeros(),
+            blue_shift: visual.blue_mask().trailing_zeros(),
+            lsb_first: setup.image_byte_order() == x::ImageOrder::LsbFirst,
+        };
+        self.draw_state = Some(draw_state);
+        Ok(draw_state)
+    }
+}
+
+impl PresentTarget for XcbWindow {
+    fn extent(&self) -> Result<Extent2<u32>, VkResult> {
+        // Minimized window has zero extent, so application knows to skip rendering.
+        if self.is_minimized()? {
+            return Ok(Extent2::default());
+        }
+        self.current_extent()
+    }
+
+    fn present(
         &mut self,
-        memory_allocation: Arc<Mutex<MemoryAllocation>>,
-        extent: Extent3<u32>,
+        pixels: &[u8],
+        format: VkFormat,
+        extent: Extent2<u32>,
     ) -> Result<VkResult, VkResult> {
-        let (gc, depth) = if let (Some(gc), Some(depth)) = (self.gc, self.depth) {
-            (gc, depth)
+        let draw_state = self.draw_state()?;
+
+        // Clip image to current window size.
+        let window_extent = self.current_extent()?;
+        let width = extent.width.min(window_extent.width);
+        let height = extent.height.min(window_extent.height);
+        let result = if width != window_extent.width || height != window_extent.height {
+            VkResult::VK_SUBOPTIMAL_KHR
         } else {
+            VkResult::VK_SUCCESS
+        };
+        if width == 0 || height == 0 {
+            return Ok(result);
+        }
+
+        let data = draw_state.convert(pixels, format, extent.width, width, height);
+
+        // Split image into strips that fit into single request.
+        const PUT_IMAGE_REQUEST_HEADER_SIZE: u32 = 24;
+        let max_request_size =
+            self.connection.get_maximum_request_length() * 4 - PUT_IMAGE_REQUEST_HEADER_SIZE;
+        let row_size = width * XcbDrawState::BYTES_PER_PIXEL;
+        let rows_per_request = (max_request_size / row_size).max(1);
+
+        // TODO: Use X Present Extension.
+        for (i, strip) in data
+            .chunks((rows_per_request * row_size) as usize)
+            .enumerate()
+        {
             self.connection
-                .send_and_check_request(&x::ChangeWindowAttributes {
-                    window: *self.window,
-                    value_list: &[x::Cw::EventMask(x::EventMask::EXPOSURE)],
-                })
-                .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
-            let gc = self.connection.generate_id();
-            self.connection
-                .send_and_check_request(&x::CreateGc {
-                    cid: gc,
+                .send_and_check_request(&x::PutImage {
+                    format: x::ImageFormat::ZPixmap,
                     drawable: x::Drawable::Window(*self.window),
-                    value_list: &[x::Gc::GraphicsExposures(true)],
+                    gc: draw_state.gc,
+                    width: width as u16,
+                    height: (strip.len() as u32 / row_size) as u16,
+                    dst_x: 0,
+                    dst_y: (i as u32 * rows_per_request) as i16,
+                    left_pad: 0,
+                    depth: draw_state.depth,
+                    data: strip,
                 })
                 .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
-            self.connection
-                .send_and_check_request(&x::MapWindow {
-                    window: *self.window,
-                })
-                .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
-            self.connection
-                .flush()
-                .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+        }
 
-            let cookie = self.connection.send_request(&x::GetGeometry {
-                drawable: x::Drawable::Window(*self.window),
-            });
-            let reply = self
-                .connection
-                .wait_for_reply(cookie)
-                .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
-            let depth = reply.depth();
+        self.connection
+            .flush()
+            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+
+        Ok(result)
+    }
+
+    fn supported_formats(&self) -> &[VkFormat] {
+        &SURFACE_FORMATS
+    }
+}
 
-            (gc, depth)
+/// Window drawing state and pixel layout expected by X server.
+#[derive(Debug, Copy, Clone)]
+struct XcbDrawState {
+    gc: x::Gcontext,
+    depth: u8,
+    red_shift: u32,
+    green_shift: u32,
+    blue_shift: u32,
+    lsb_first: bool,
+}
+
+impl XcbDrawState {
+    const BYTES_PER_PIXEL: u32 = 4;
+
+    /// Converts top-left `width`x`height` region of image into window pixels.
+    fn convert(
+        &self,
+        data: &[u8],
+        format: VkFormat,
+        image_width: u32,
+        width: u32,
+        height: u32,
+    ) -> Vec<u8> {
+        let (r, g, b) = match format {
+            VkFormat::VK_FORMAT_R8G8B8A8_UNORM | VkFormat::VK_FORMAT_R8G8B8A8_SRGB => (0, 1, 2),
+            VkFormat::VK_FORMAT_B8G8R8A8_UNORM | VkFormat::VK_FORMAT_B8G8R8A8_SRGB => (2, 1, 0),
+            _ => unimplemented!("{:?}", format),
         };
+        let bytes_per_pixel = format.bytes_per_pixel() as usize;
 
-        let mut memory_allocation = memory_allocation.lock();
-        let size = memory_allocation.gpu_memory_allocation.size;
-        assert!(size < self.connection.get_maximum_request_length() as u64 * 4);
-        let data = memory_allocation
-            .map_host(0, size)
-            .map_err(|_| VkResult::VK_ERROR_OUT_OF_DATE_KHR)?;
-        let data = unsafe { std::slice::from_raw_parts(data.as_ptr() as *mut u8, size as usize) };
+        let mut result = Vec::with_capacity((width * height * Self::BYTES_PER_PIXEL) as usize);
+        for y in 0..height as usize {
+            let row = &data[y * image_width as usize * bytes_per_pixel..];
+            for texel in row.chunks_exact(bytes_per_pixel).take(width as usize) {
+                let pixel = (texel[r] as u32) << self.red_shift
+                    | (texel[g] as u32) << self.green_shift
+                    | (texel[b] as u32) << self.blue_shift;
+                if self.lsb_first {
+                    result.extend_from_slice(&pixel.to_le_bytes());
+                } else {
+                    result.extend_from_slice(&pixel.to_be_bytes());
+                }
+            }
+        }
+        result
+    }
+}
 
-        // TODO: VK_ERROR_OUT_OF_DATE_KHR
-        // TODO: Use X Present Extension.
-        self.connection
-            .send_and_check_request(&x::PutImage {
-                format: x::ImageFormat::ZPixmap,
-                drawable: x::Drawable::Window(*self.window),
-                gc,
-                width: extent.width as u16,
-                height: extent.height as u16,
-                dst_x: 0,
-                dst_y: 0,
-                left_pad: 0,
-                depth,
-                data,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "wayland_tests")]
+    use crate::command_buffer::tests::record_and_submit;
+    use crate::context::Dispatchable;
+    #[cfg(feature = "wayland_tests")]
+    use crate::logical_device::tests::create_logical_device;
+    #[cfg(feature = "wayland_tests")]
+    use crate::swapchain::Swapchain;
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+    use wayland_client::protocol::wl_compositor::WlCompositor;
+
+    /// Dispatch state of test client owning display and surface.
+    struct ClientState;
+
+    impl Dispatch<WlRegistry, GlobalListContents> for ClientState {
+        fn event(
+            _state: &mut Self,
+            _proxy: &WlRegistry,
+            _event: <WlRegistry as Proxy>::Event,
+            _data: &GlobalListContents,
+            _conn: &Connection,
+            _qhandle: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    delegate_noop!(ClientState: WlCompositor);
+    delegate_noop!(ClientState: ignore WlSurface);
+
+    /// Creates `wl_surface` of client `connection` along with event queue of its events.
+    fn create_wl_surface(connection: &Connection) -> (WlSurface, EventQueue<ClientState>) {
+        let Ok((globals, event_queue)) = registry_queue_init::<ClientState>(connection) else {
+            unreachable!()
+        };
+        let Ok(compositor) = globals.bind::<WlCompositor, _, _>(&event_queue.handle(), 1..=4, ())
+        else {
+            unreachable!()
+        };
+        let wl_surface = compositor.create_surface(&event_queue.handle(), ());
+        (wl_surface, event_queue)
+    }
+
+    /// Serves client on other end of `stream` as compositor that advertises `wl_compositor` and
+    /// `wl_shm`, answers syncs and ignores other requests, so it never releases buffers.
+    ///
+    /// Returns number of `wl_shm_pool`s client created once it stopped sending requests, and closes
+    /// the connection.
+    fn serve_unreleasing_compositor(mut stream: UnixStream) -> usize {
+        const COMPOSITOR: u32 = 1;
+        const SHM: u32 = 2;
+        let word = |bytes: &[u8], index: usize| {
+            let bytes = &bytes[index * 4..index * 4 + 4];
+            u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        };
+        let send = |stream: &mut UnixStream, object: u32, opcode: u32, arguments: &[u32]| {
+            let size = (2 + arguments.len() as u32) * 4;
+            let words = [&[object, size << 16 | opcode], arguments].concat();
+            let bytes = words
+                .iter()
+                .flat_map(|word| word.to_ne_bytes())
+                .collect::<Vec<_>>();
+            assert!(stream.write_all(&bytes).is_ok());
+        };
+        let global = |name: u32, interface: &str, version: u32| {
+            // Strings are prefixed with their length including nul terminator and padded to words.
+            let mut bytes = interface.as_bytes().to_vec();
+            bytes.resize((interface.len() + 4) / 4 * 4, 0);
+            let mut arguments = vec![name, interface.len() as u32 + 1];
+            arguments.extend(bytes.chunks_exact(4).map(|bytes| word(bytes, 0)));
+            arguments.push(version);
+            arguments
+        };
+
+        assert!(stream
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .is_ok());
+        let (mut registries, mut shms, mut pools) = (vec![], vec![], 0);
+        let mut bytes = vec![];
+        let mut chunk = [0; 4096];
+        loop {
+            match stream.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(len) => bytes.extend_from_slice(&chunk[..len]),
+                // Client stopped sending requests after it started presenting.
+                Err(_) if pools > 0 => break,
+                Err(_) => continue,
+            }
+            while bytes.len() >= 8 && bytes.len() >= (word(&bytes, 1) >> 16) as usize {
+                let size = (word(&bytes, 1) >> 16) as usize;
+                let message = bytes.drain(..size).collect::<Vec<_>>();
+                let (object, opcode) = (word(&message, 0), word(&message, 1) & 0xFFFF);
+                match (object, opcode) {
+                    // wl_display.sync
+                    (1, 0) => {
+                        let callback = word(&message, 2);
+                        send(&mut stream, callback, 0, &[0]);
+                        send(&mut stream, 1, 1, &[callback]);
+                    }
+                    // wl_display.get_registry
+                    (1, 1) => {
+                        let registry = word(&message, 2);
+                        registries.push(registry);
+                        send(
+                            &mut stream,
+                            registry,
+                            0,
+                            &global(COMPOSITOR, "wl_compositor", 4),
+                        );
+                        send(&mut stream, registry, 0, &global(SHM, "wl_shm", 1));
+                    }
+                    // wl_registry.bind, whose new object is the last argument
+                    (object, 0) if registries.contains(&object) && word(&message, 2) == SHM => {
+                        shms.push(word(&message, size / 4 - 1));
+                    }
+                    // wl_shm.create_pool
+                    (object, 0) if shms.contains(&object) => pools += 1,
+                    _ => {}
+                }
+            }
+        }
+        pools
+    }
+
+    #[test]
+    fn wayland_surface_buffers_are_bounded_while_compositor_holds_them() {
+        let Ok((client_stream, compositor_stream)) = UnixStream::pair() else {
+            unreachable!()
+        };
+        let compositor =
+            std::thread::spawn(move || serve_unreleasing_compositor(compositor_stream));
+        let Ok(connection) = Connection::from_socket(client_stream) else {
+            unreachable!()
+        };
+        let (wl_surface, _event_queue) = create_wl_surface(&connection);
+
+        let Ok(instance) = Instance::create() else {
+            unreachable!()
+        };
+        let Some(instance) = Instance::from_handle(instance) else {
+            unreachable!()
+        };
+        let surface_create_info = VkWaylandSurfaceCreateInfoKHR {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_WAYLAND_SURFACE_CREATE_INFO_KHR,
+            pNext: None,
+            flags: 0,
+            display: NonNull::new(connection.backend().display_ptr().cast()),
+            surface: NonNull::new(wl_surface.id().as_ptr().cast()),
+        };
+        let Ok(surface) = Surface::create_wayland(instance, &surface_create_info) else {
+            unreachable!()
+        };
+        let Some(surface_object) = Surface::from_handle(surface) else {
+            unreachable!()
+        };
+
+        // Presents wait for compositor to release buffer once all of them are attached, until it
+        // disconnects.
+        let extent = Extent2 {
+            width: 4,
+            height: 4,
+        };
+        let pixels = [0x80; 64];
+        let results = (0..8)
+            .map(|_| {
+                surface_object.lock().target_mut().present(
+                    &pixels,
+                    VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+                    extent,
+                )
             })
-            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+            .take_while(Result::is_ok)
+            .count();
+        let Ok(pools) = compositor.join() else {
+            unreachable!()
+        };
+        assert_eq!(pools, WaylandShm::MAX_BUFFERS);
+        assert_eq!(results, WaylandShm::MAX_BUFFERS);
+    }
 
-        self.connection
-            .flush()
-            .map_err(|_| VkResult::VK_ERROR_SURFACE_LOST_KHR)?;
+    #[cfg(feature = "wayland_tests")]
+    #[test]
+    fn wayland_surface_commits_presented_buffer() {
+        let Ok(connection) = Connection::connect_to_env() else {
+            panic!("Wayland compositor is not running")
+        };
+        let (wl_surface, mut event_queue) = create_wl_surface(&connection);
+
+        let Ok(instance) = Instance::create() else {
+            unreachable!()
+        };
+        let Some(instance) = Instance::from_handle(instance) else {
+            unreachable!()
+        };
+        let surface_create_info = VkWaylandSurfaceCreateInfoKHR {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_WAYLAND_SURFACE_CREATE_INFO_KHR,
+            pNext: None,
+            flags: 0,
+            display: NonNull::new(connection.backend().display_ptr().cast()),
+            surface: NonNull::new(wl_surface.id().as_ptr().cast()),
+        };
+        let Ok(surface) = Surface::create_wayland(instance, &surface_create_info) else {
+            unreachable!()
+        };
+        let Some(surface_object) = Surface::from_handle(surface) else {
+            unreachable!()
+        };
+        let logical_device = create_logical_device();
+        let capabilities = logical_device
+            .lock()
+            .physical_device()
+            .surface_capabilities(&surface_object.lock());
+        let Ok(capabilities) = capabilities else {
+            unreachable!()
+        };
+
+        let swapchain_create_info = VkSwapchainCreateInfoKHR {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR,
+            pNext: None,
+            flags: 0,
+            surface,
+            minImageCount: 2,
+            imageFormat: VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+            imageColorSpace: VkColorSpaceKHR::VK_COLOR_SPACE_SRGB_NONLINEAR_KHR,
+            imageExtent: capabilities.currentExtent,
+            imageArrayLayers: 1,
+            imageUsage: VkImageUsageFlagBits::VK_IMAGE_USAGE_TRANSFER_DST_BIT.into(),
+            imageSharingMode: VkSharingMode::VK_SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: None,
+            preTransform: capabilities.currentTransform,
+            compositeAlpha: VkCompositeAlphaFlagBitsKHR::VK_COMPOSITE_ALPHA_OPAQUE_BIT_KHR,
+            presentMode: VkPresentModeKHR::VK_PRESENT_MODE_IMMEDIATE_KHR,
+            clipped: VK_TRUE,
+            oldSwapchain: VK_NULL_HANDLE,
+        };
+        let Ok(swapchain) = Swapchain::create(logical_device.clone(), &swapchain_create_info)
+        else {
+            unreachable!()
+        };
+        let Some(swapchain) = Swapchain::from_handle(swapchain) else {
+            unreachable!()
+        };
+        for _ in 0..2 {
+            let (image_index, result) = swapchain.lock().acquire_next_image(u64::MAX, None, None);
+            assert_eq!(result, VkResult::VK_SUCCESS);
+            let image = swapchain.lock().images[image_index as usize].clone();
+            let color = VkClearColorValue {
+                float32: ManuallyDrop::new([0.2, 0.4, 0.6, 1.0]),
+            };
+            let range = VkImageSubresourceRange {
+                aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+                baseMipLevel: 0,
+                levelCount: 1,
+                baseArrayLayer: 0,
+                layerCount: 1,
+            };
+            record_and_submit(&logical_device, |command_buffer| {
+                command_buffer.cmd_clear_color_image(
+                    image,
+                    VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                    color,
+                    &[range],
+                );
+            });
+            assert_eq!(
+                swapchain.lock().present(image_index),
+                Ok(VkResult::VK_SUCCESS)
+            );
+        }
 
-        memory_allocation.unmap_host();
-        drop(memory_allocation);
-        Ok(VkResult::VK_SUCCESS)
+        // Compositor raises protocol error on invalid buffers or commits.
+        assert!(event_queue.roundtrip(&mut ClientState).is_ok());
     }
 }
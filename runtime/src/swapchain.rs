@@ -1,45 +1,61 @@
 //! Swapchain
 
 use crate::context::NonDispatchable;
+use crate::error::RuntimeError;
 use crate::fence::Fence;
 use crate::image::*;
 use crate::logical_device::LogicalDevice;
 use crate::memory::MemoryAllocation;
 use crate::semaphore::Semaphore;
 use crate::surface::Surface;
-use common::math::Extent3;
+use common::math::{Extent2, Extent3};
 use headers::vk_decls::*;
 use log::*;
 use parking_lot::Mutex;
 use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Refresh rate of emulated display used when `ICD_REFRESH_RATE` is not set.
+const DEFAULT_REFRESH_RATE: u32 = 60;
 
 pub struct Swapchain {
     pub(crate) handle: VkNonDispatchableHandle,
-    logical_device: Arc<Mutex<LogicalDevice>>,
+    pub(crate) logical_device: Arc<Mutex<LogicalDevice>>,
     flags: VkSwapchainCreateFlagsKHR,
     surface: Arc<Mutex<Surface>>,
     extent: Extent3<u32>,
     pub images: Vec<Arc<Mutex<Image>>>,
     pub memory_allocations: Vec<Arc<Mutex<MemoryAllocation>>>,
+    image_ring: ImageRing,
+    present_queue: PresentQueue,
     #[allow(dead_code)]
     color_space: VkColorSpaceKHR,
-    #[allow(dead_code)]
-    present_mode: VkPresentModeKHR,
+    /// Whether swapchain was replaced by newer one, which owns presentation to surface since.
+    retired: bool,
 }
 
 impl Swapchain {
     pub fn create(
         logical_device: Arc<Mutex<LogicalDevice>>,
         create_info: &VkSwapchainCreateInfoKHR,
-    ) -> VkNonDispatchableHandle {
+    ) -> Result<VkNonDispatchableHandle, RuntimeError> {
         info!("new Swapchain");
         let handle = VK_NULL_HANDLE;
 
         let flags = create_info.flags;
-        let Some(surface) = Surface::from_handle(create_info.surface) else {
-            unreachable!()
-        };
+        let surface = Surface::lookup(create_info.surface)?;
+
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkSwapchainCreateInfoKHR-presentMode-01281
+        let present_mode = create_info.presentMode;
+        let present_modes = logical_device.lock().physical_device().present_modes();
+        if !present_modes.contains(&present_mode) {
+            error!(
+                "Present mode {:?} is not supported by surface",
+                present_mode
+            );
+            return Err(RuntimeError::InitializationFailed);
+        }
 
         let image_count = create_info.minImageCount;
         let extent = Extent3 {
@@ -50,19 +66,33 @@ impl Swapchain {
         let mut images = Vec::with_capacity(image_count as usize);
         let mut memory_allocations = Vec::with_capacity(image_count as usize);
         for _ in 0..image_count {
-            let image = Image::create(
-                logical_device.clone(),
-                create_info.imageFormat,
-                extent.width,
-                extent.height,
-                extent.depth,
-                create_info.imageUsage,
-            );
+            let image_create_info = VkImageCreateInfo {
+                sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_CREATE_INFO,
+                pNext: None,
+                flags: 0,
+                imageType: VkImageType::VK_IMAGE_TYPE_2D,
+                format: create_info.imageFormat,
+                extent: VkExtent3D {
+                    width: extent.width,
+                    height: extent.height,
+                    depth: 1,
+                },
+                mipLevels: 1,
+                arrayLayers: create_info.imageArrayLayers,
+                samples: VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+                tiling: VkImageTiling::VK_IMAGE_TILING_OPTIMAL,
+                usage: create_info.imageUsage,
+                sharingMode: create_info.imageSharingMode,
+                queueFamilyIndexCount: 0,
+                pQueueFamilyIndices: None,
+                initialLayout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+            };
+            let image = Image::create(logical_device.clone(), &image_create_info)?;
             let Some(image) = Image::from_handle(image) else {
                 unreachable!()
             };
             let memory_allocation =
-                MemoryAllocation::create(logical_device.clone(), image.lock().size_in_bytes(), 0);
+                MemoryAllocation::create(logical_device.clone(), image.lock().size_in_bytes(), 0)?;
             let Some(memory_allocation) = MemoryAllocation::from_handle(memory_allocation) else {
                 unreachable!()
             };
@@ -74,7 +104,10 @@ impl Swapchain {
         }
 
         let color_space = create_info.imageColorSpace;
-        let present_mode = create_info.presentMode;
+        let refresh_rate = std::env::var("ICD_REFRESH_RATE")
+            .ok()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(DEFAULT_REFRESH_RATE);
 
         warn!("TODO: Parse rest of swapchain create info");
         let _ = create_info.imageSharingMode;
@@ -86,7 +119,20 @@ impl Swapchain {
 
         let _ = create_info.clipped;
 
-        let _ = create_info.oldSwapchain;
+        let mut present_queue = PresentQueue::new(present_mode, refresh_rate);
+        if let Some(old_swapchain) = Self::from_handle(create_info.oldSwapchain) {
+            let mut old_swapchain = old_swapchain.lock();
+            // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkSwapchainCreateInfoKHR-oldSwapchain-01933
+            if !Arc::ptr_eq(&old_swapchain.surface, &surface) {
+                error!("Old swapchain is not associated with surface of new swapchain");
+            }
+            if old_swapchain.retired {
+                error!("Old swapchain is already retired");
+            }
+            // Presentation continues at the vblank old swapchain stopped at.
+            present_queue.vblank_clock = old_swapchain.retire();
+            drop(old_swapchain);
+        }
 
         let swapchain = Self {
             handle,
@@ -96,10 +142,45 @@ impl Swapchain {
             extent,
             images,
             memory_allocations,
+            image_ring: ImageRing::new(image_count),
+            present_queue,
             color_space,
-            present_mode,
+            retired: false,
         };
-        swapchain.register_object()
+        Ok(swapchain.register_object())
+    }
+
+    /// Returns handles of swapchain images ordered by image index.
+    pub fn images(&self) -> Vec<VkImage> {
+        self.images
+            .iter()
+            .map(|image| image.lock().get_handle())
+            .collect()
+    }
+
+    /// Unregisters images owned by swapchain, so their handles become invalid with the swapchain.
+    pub fn destroy(&self) {
+        for image in &self.images {
+            let handle = image.lock().get_handle();
+            Image::drop_handle(handle);
+        }
+        for memory_allocation in &self.memory_allocations {
+            let handle = memory_allocation.lock().get_handle();
+            MemoryAllocation::drop_handle(handle);
+        }
+    }
+
+    /// Hands surface over to swapchain replacing this one. Image waiting in mailbox is presented
+    /// first, so that the last frame isn't lost. Returns vblank clock to continue presenting with.
+    fn retire(&mut self) -> VblankClock {
+        if let Some(image_index) = self.present_queue.mailbox.take() {
+            let _ = self.present_image(image_index);
+        }
+        self.retired = true;
+        std::mem::replace(
+            &mut self.present_queue.vblank_clock,
+            VblankClock::new(DEFAULT_REFRESH_RATE),
+        )
     }
 
     pub fn acquire_next_image(
@@ -107,17 +188,152 @@ impl Swapchain {
         timeout: u64,
         semaphore: Option<Arc<Mutex<Semaphore>>>,
         fence: Option<Arc<Mutex<Fence>>>,
-    ) -> u32 {
-        warn!("TODO: Acquire next swapchain image");
-        let _ = timeout;
-        let _ = semaphore;
-        let _ = fence;
-        0
+    ) -> (u32, VkResult) {
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VkSwapchainCreateInfoKHR
+        if self.retired {
+            warn!("Acquiring image of retired swapchain");
+            return (0, VkResult::VK_ERROR_OUT_OF_DATE_KHR);
+        }
+
+        let status = match self.extent_status() {
+            Ok(status) => status,
+            Err(result) => return (0, result),
+        };
+
+        if let Some(image_index) = self.present_queue.poll(false) {
+            if let Err(result) = self.present_image(image_index) {
+                return (0, result);
+            }
+        }
+        if !self.image_ring.has_available() {
+            // Image waiting in mailbox becomes available after it is presented at next vblank.
+            if let Some(image_index) = self.present_queue.poll(timeout != 0) {
+                if let Err(result) = self.present_image(image_index) {
+                    return (0, result);
+                }
+            }
+        }
+
+        let Some(image_index) = self.image_ring.acquire() else {
+            warn!("All swapchain images are acquired by application");
+            // NOTE: Images are released only by presenting them, which can't happen while swapchain
+            // is locked, so waiting for timeout would not make any image available.
+            let result = if timeout == 0 {
+                VkResult::VK_NOT_READY
+            } else {
+                VkResult::VK_TIMEOUT
+            };
+            return (0, result);
+        };
+
+        // Presentation engine doesn't access image after present, so it is ready immediately.
+        if let Some(semaphore) = semaphore {
+            semaphore.lock().signal(0);
+        }
+        if let Some(fence) = fence {
+            Fence::signal_and_notify(&fence);
+        }
+
+        (image_index, status)
+    }
+
+    /// Compares extent of swapchain with current extent of window. Images are not scaled, so they
+    /// are out of date for window of any other size, except minimized window that shows nothing
+    /// until restored.
+    fn extent_status(&self) -> Result<VkResult, VkResult> {
+        let surface_extent = self.surface.lock().target().extent()?;
+        if surface_extent == Extent2::default() {
+            Ok(VkResult::VK_SUBOPTIMAL_KHR)
+        } else if surface_extent.width != self.extent.width
+            || surface_extent.height != self.extent.height
+        {
+            Err(VkResult::VK_ERROR_OUT_OF_DATE_KHR)
+        } else {
+            Ok(VkResult::VK_SUCCESS)
+        }
     }
 
     pub fn present(&mut self, image_index: u32) -> Result<VkResult, VkResult> {
-        let memory_allocation = self.memory_allocations[image_index as usize].clone();
-        self.surface.lock().present(memory_allocation, self.extent)
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VUID-VkPresentInfoKHR-pImageIndices-01430
+        if image_index as usize >= self.images.len() {
+            error!(
+                "Presenting swapchain image {image_index} of only {} images",
+                self.images.len()
+            );
+            return Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT);
+        }
+        if !self.image_ring.queue(image_index) {
+            warn!("Presenting swapchain image {image_index} that was not acquired");
+        }
+        // Images acquired before retirement are returned without being shown, as surface already
+        // shows images of the new swapchain.
+        if self.retired {
+            self.image_ring.release(image_index);
+            return Err(VkResult::VK_ERROR_OUT_OF_DATE_KHR);
+        }
+        let status = match self.extent_status() {
+            Ok(status) => status,
+            Err(result) => {
+                self.image_ring.release(image_index);
+                return Err(result);
+            }
+        };
+
+        // NOTE: Presentation is synchronous, so FIFO queue holds only the image being presented and
+        // acquire from other threads waits for it on swapchain lock. Global context lock is not held
+        // here, so sleeping doesn't block unrelated objects.
+        let presentation = self.present_queue.queue(image_index);
+        if let Some(discarded) = presentation.discarded {
+            trace!("Swapchain image {discarded} replaced in mailbox before presenting");
+            self.image_ring.release(discarded);
+        }
+        let result = presentation
+            .presented
+            .map_or(Ok(VkResult::VK_SUCCESS), |image_index| {
+                self.present_image(image_index)
+            })?;
+        if result == VkResult::VK_SUCCESS {
+            Ok(status)
+        } else {
+            Ok(result)
+        }
+    }
+
+    fn present_image(&mut self, image_index: u32) -> Result<VkResult, VkResult> {
+        let image = self.images[image_index as usize].lock();
+        if image.current_layout(0, 0) != VkImageLayout::VK_IMAGE_LAYOUT_PRESENT_SRC_KHR {
+            warn!(
+                "Presenting swapchain image {image_index} in {:?} instead of PRESENT_SRC_KHR",
+                image.current_layout(0, 0)
+            );
+        }
+        let format = image.format;
+        drop(image);
+        // Only the first array layer is presented.
+        let extent = Extent2 {
+            width: self.extent.width,
+            height: self.extent.height,
+        };
+        let size = extent.width as u64 * extent.height as u64 * format.bytes_per_pixel() as u64;
+        let memory_allocation = self.memory_allocations[image_index as usize].lock();
+        let pixels = memory_allocation
+            .logical_device
+            .lock()
+            .physical_device()
+            .gpu
+            .memory
+            .read_bytes(&memory_allocation.gpu_memory_allocation, 0, size)
+            .to_vec();
+        drop(memory_allocation);
+        let result = self
+            .surface
+            .lock()
+            .target_mut()
+            .present(&pixels, format, extent);
+
+        // Image is copied to window, so it can be acquired again.
+        self.image_ring.release(image_index);
+        result
     }
 }
 
@@ -130,3 +346,816 @@ impl Debug for Swapchain {
             .finish()
     }
 }
+
+/// Tracks which swapchain images are owned by application.
+#[derive(Debug)]
+struct ImageRing {
+    states: Vec<ImageState>,
+    next_index: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ImageState {
+    Available,
+    Acquired,
+    Queued,
+}
+
+impl ImageRing {
+    fn new(image_count: u32) -> Self {
+        Self {
+            states: vec![ImageState::Available; image_count as usize],
+            next_index: 0,
+        }
+    }
+
+    /// Returns index of the next available image in round-robin order.
+    fn acquire(&mut self) -> Option<u32> {
+        let image_count = self.states.len() as u32;
+        let index = (0..image_count)
+            .map(|i| (self.next_index + i) % image_count)
+            .find(|&i| self.states[i as usize] == ImageState::Available)?;
+        self.states[index as usize] = ImageState::Acquired;
+        self.next_index = (index + 1) % image_count;
+        Some(index)
+    }
+
+    fn has_available(&self) -> bool {
+        self.states.contains(&ImageState::Available)
+    }
+
+    /// Passes acquired image to presentation engine.
+    fn queue(&mut self, index: u32) -> bool {
+        let Some(state) = self.states.get_mut(index as usize) else {
+            return false;
+        };
+        let was_acquired = *state == ImageState::Acquired;
+        *state = ImageState::Queued;
+        was_acquired
+    }
+
+    /// Makes image available for acquiring again.
+    fn release(&mut self, index: u32) -> bool {
+        let Some(state) = self.states.get_mut(index as usize) else {
+            return false;
+        };
+        let was_owned = *state != ImageState::Available;
+        *state = ImageState::Available;
+        was_owned
+    }
+}
+
+/// Decides when queued images are presented according to present mode.
+#[derive(Debug)]
+struct PresentQueue {
+    present_mode: VkPresentModeKHR,
+    vblank_clock: VblankClock,
+    /// The newest image waiting for vblank in MAILBOX mode.
+    mailbox: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Presentation {
+    /// Image to be copied to surface now.
+    presented: Option<u32>,
+    /// Image replaced in mailbox before it was presented.
+    discarded: Option<u32>,
+}
+
+impl PresentQueue {
+    fn new(present_mode: VkPresentModeKHR, refresh_rate: u32) -> Self {
+        Self {
+            present_mode,
+            vblank_clock: VblankClock::new(refresh_rate),
+            mailbox: None,
+        }
+    }
+
+    fn queue(&mut self, image_index: u32) -> Presentation {
+        match self.present_mode {
+            VkPresentModeKHR::VK_PRESENT_MODE_IMMEDIATE_KHR => Presentation {
+                presented: Some(image_index),
+                discarded: None,
+            },
+            VkPresentModeKHR::VK_PRESENT_MODE_MAILBOX_KHR => {
+                let discarded = self.mailbox.replace(image_index);
+                Presentation {
+                    presented: self.poll(false),
+                    discarded,
+                }
+            }
+            // FIFO, as swapchains of other present modes fail creation.
+            _ => {
+                self.vblank_clock.wait_for_vblank();
+                Presentation {
+                    presented: Some(image_index),
+                    discarded: None,
+                }
+            }
+        }
+    }
+
+    /// Takes image from mailbox if vblank happened since the last presentation. Waits for the next
+    /// vblank otherwise if `wait` is set.
+    fn poll(&mut self, wait: bool) -> Option<u32> {
+        self.mailbox?;
+        let now = Instant::now();
+        if self.vblank_clock.is_vblank_due(now) {
+            self.vblank_clock.latch(now);
+        } else if wait {
+            self.vblank_clock.wait_for_vblank();
+        } else {
+            return None;
+        }
+        self.mailbox.take()
+    }
+}
+
+/// Emulates vertical blanking of display refreshing at fixed rate.
+#[derive(Debug)]
+struct VblankClock {
+    origin: Instant,
+    refresh_interval: Duration,
+    last_vblank: Option<u64>,
+}
+
+impl VblankClock {
+    fn new(refresh_rate: u32) -> Self {
+        Self {
+            origin: Instant::now(),
+            refresh_interval: Duration::from_secs(1) / refresh_rate.max(1),
+            last_vblank: None,
+        }
+    }
+
+    fn vblank_index(&self, now: Instant) -> u64 {
+        let elapsed = now.saturating_duration_since(self.origin).as_nanos();
+        (elapsed / self.refresh_interval.as_nanos()) as u64
+    }
+
+    /// Checks whether vblank happened since the last one waited for.
+    fn is_vblank_due(&self, now: Instant) -> bool {
+        self.last_vblank
+            .is_none_or(|last| self.vblank_index(now) > last)
+    }
+
+    /// Marks the most recent vblank as used without waiting.
+    fn latch(&mut self, now: Instant) {
+        self.last_vblank = Some(self.vblank_index(now));
+    }
+
+    /// Returns the first vblank at or after `now` that follows the last waited for vblank.
+    fn next_vblank(&self, now: Instant) -> (u64, Instant) {
+        let elapsed = now.saturating_duration_since(self.origin).as_nanos();
+        let interval = self.refresh_interval.as_nanos();
+        let vblank = elapsed.div_ceil(interval) as u64;
+        let vblank = self.last_vblank.map_or(vblank, |last| vblank.max(last + 1));
+        let time = self.origin + Duration::from_nanos((interval * vblank as u128) as u64);
+        (vblank, time)
+    }
+
+    /// Sleeps until the next vblank boundary.
+    fn wait_for_vblank(&mut self) {
+        let (vblank, time) = self.next_vblank(Instant::now());
+        let remaining = time.saturating_duration_since(Instant::now());
+        if !remaining.is_zero() {
+            trace!("waiting {remaining:?} for vblank {vblank}");
+            std::thread::sleep(remaining);
+        }
+        self.last_vblank = Some(vblank);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_buffer::tests::{
+        create_command_buffer, create_image_view, record_and_submit, render_triangle_into,
+    };
+    use crate::context::Dispatchable;
+    use crate::instance::Instance;
+    use crate::logical_device::tests::{create_logical_device, create_timeline_semaphore};
+    use crate::queue::SubmitInfo;
+    use crate::surface::{PresentTarget, PresentedImage};
+
+    #[test]
+    fn image_ring_acquire_present_fifo() {
+        let mut ring = ImageRing::new(2);
+        assert_eq!(ring.acquire(), Some(0));
+        assert!(ring.release(0));
+        assert_eq!(ring.acquire(), Some(1));
+        assert!(ring.release(1));
+        assert_eq!(ring.acquire(), Some(0));
+    }
+
+    #[test]
+    fn image_ring_all_acquired() {
+        let mut ring = ImageRing::new(2);
+        assert_eq!(ring.acquire(), Some(0));
+        assert_eq!(ring.acquire(), Some(1));
+        assert_eq!(ring.acquire(), None);
+        assert!(ring.release(1));
+        assert!(!ring.release(1));
+        assert_eq!(ring.acquire(), Some(1));
+    }
+
+    #[test]
+    fn vblank_clock_paces_presents() {
+        const REFRESH_RATE: u32 = 240;
+        const PRESENT_COUNT: u32 = 8;
+        let mut clock = VblankClock::new(REFRESH_RATE);
+        let start = Instant::now();
+        for _ in 0..PRESENT_COUNT {
+            clock.wait_for_vblank();
+        }
+        let elapsed = start.elapsed();
+        let expected = Duration::from_secs(1) * (PRESENT_COUNT - 1) / REFRESH_RATE;
+        let tolerance = Duration::from_micros(100);
+        assert!(
+            elapsed + tolerance >= expected,
+            "{elapsed:?} < {expected:?}"
+        );
+    }
+
+    #[test]
+    fn present_queue_mailbox_presents_newest() {
+        let mut queue = PresentQueue::new(VkPresentModeKHR::VK_PRESENT_MODE_MAILBOX_KHR, 10);
+        // Vblank just happened, the next one is 100ms away.
+        queue.vblank_clock.origin = Instant::now();
+        queue.vblank_clock.last_vblank = Some(0);
+
+        assert_eq!(queue.queue(0), Presentation::default());
+        assert_eq!(
+            queue.queue(1),
+            Presentation {
+                presented: None,
+                discarded: Some(0),
+            }
+        );
+        assert_eq!(
+            queue.queue(2),
+            Presentation {
+                presented: None,
+                discarded: Some(1),
+            }
+        );
+        assert_eq!(queue.poll(false), None);
+        assert_eq!(queue.poll(true), Some(2));
+        assert_eq!(queue.poll(true), None);
+    }
+
+    #[test]
+    fn present_queue_immediate_does_not_wait() {
+        let mut queue = PresentQueue::new(VkPresentModeKHR::VK_PRESENT_MODE_IMMEDIATE_KHR, 1);
+        let start = Instant::now();
+        for i in 0..3 {
+            assert_eq!(queue.queue(i).presented, Some(i));
+        }
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+
+    /// Returns create info of swapchain of two `extent` images presented to `surface`
+    /// immediately, replacing `old_swapchain`.
+    fn swapchain_create_info(
+        surface: VkSurfaceKHR,
+        format: VkFormat,
+        extent: Extent2<u32>,
+        old_swapchain: VkSwapchainKHR,
+    ) -> VkSwapchainCreateInfoKHR {
+        VkSwapchainCreateInfoKHR {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR,
+            pNext: None,
+            flags: 0,
+            surface,
+            minImageCount: 2,
+            imageFormat: format,
+            imageColorSpace: VkColorSpaceKHR::VK_COLOR_SPACE_SRGB_NONLINEAR_KHR,
+            imageExtent: VkExtent2D {
+                width: extent.width,
+                height: extent.height,
+            },
+            imageArrayLayers: 1,
+            imageUsage: VkImageUsageFlagBits::VK_IMAGE_USAGE_TRANSFER_DST_BIT.into(),
+            imageSharingMode: VkSharingMode::VK_SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: None,
+            preTransform: VkSurfaceTransformFlagBitsKHR::VK_SURFACE_TRANSFORM_IDENTITY_BIT_KHR,
+            compositeAlpha: VkCompositeAlphaFlagBitsKHR::VK_COMPOSITE_ALPHA_OPAQUE_BIT_KHR,
+            presentMode: VkPresentModeKHR::VK_PRESENT_MODE_IMMEDIATE_KHR,
+            clipped: VK_TRUE,
+            oldSwapchain: old_swapchain,
+        }
+    }
+
+    /// Creates swapchain of [`swapchain_create_info`].
+    fn create_swapchain(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        surface: VkSurfaceKHR,
+        format: VkFormat,
+        extent: Extent2<u32>,
+        old_swapchain: VkSwapchainKHR,
+    ) -> Arc<Mutex<Swapchain>> {
+        let swapchain_create_info = swapchain_create_info(surface, format, extent, old_swapchain);
+        let Ok(swapchain) = Swapchain::create(logical_device.clone(), &swapchain_create_info)
+        else {
+            unreachable!()
+        };
+        let Some(swapchain) = Swapchain::from_handle(swapchain) else {
+            unreachable!()
+        };
+        swapchain
+    }
+
+    fn create_instance() -> Arc<Mutex<Instance>> {
+        let Ok(instance) = Instance::create() else {
+            unreachable!()
+        };
+        let Some(instance) = Instance::from_handle(instance) else {
+            unreachable!()
+        };
+        instance
+    }
+
+    /// Creates headless surface whose window has `extent`.
+    fn create_headless_surface(extent: Extent2<u32>) -> Arc<Mutex<Surface>> {
+        let instance = create_instance();
+        let surface_create_info = VkHeadlessSurfaceCreateInfoEXT {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_HEADLESS_SURFACE_CREATE_INFO_EXT,
+            pNext: None,
+            flags: 0,
+        };
+        let surface = Surface::create_headless(instance, &surface_create_info);
+        let Some(surface) = Surface::from_handle(surface) else {
+            unreachable!()
+        };
+        surface.lock().set_headless_extent(extent);
+        surface
+    }
+
+    /// Creates swapchain of two `extent` images of `VK_FORMAT_R8G8B8A8_UNORM` presented to new
+    /// headless surface of the same extent in `present_mode`.
+    fn create_headless_swapchain(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        extent: Extent2<u32>,
+        present_mode: VkPresentModeKHR,
+    ) -> (Arc<Mutex<Surface>>, Arc<Mutex<Swapchain>>) {
+        let surface = create_headless_surface(extent);
+        let format = VkFormat::VK_FORMAT_R8G8B8A8_UNORM;
+        let surface_handle = surface.lock().handle;
+        let swapchain_create_info = VkSwapchainCreateInfoKHR {
+            presentMode: present_mode,
+            ..swapchain_create_info(surface_handle, format, extent, VK_NULL_HANDLE)
+        };
+        let Ok(swapchain) = Swapchain::create(logical_device.clone(), &swapchain_create_info)
+        else {
+            unreachable!()
+        };
+        let Some(swapchain) = Swapchain::from_handle(swapchain) else {
+            unreachable!()
+        };
+        (surface, swapchain)
+    }
+
+    /// Acquires image of `swapchain`, clears it to `color` and presents it.
+    fn clear_and_present(
+        logical_device: &Arc<Mutex<LogicalDevice>>,
+        swapchain: &Mutex<Swapchain>,
+        color: [f32; 4],
+    ) -> Result<VkResult, VkResult> {
+        let (image_index, result) = swapchain.lock().acquire_next_image(u64::MAX, None, None);
+        assert_eq!(result, VkResult::VK_SUCCESS);
+
+        let image = swapchain.lock().images[image_index as usize].clone();
+        let color = VkClearColorValue {
+            float32: std::mem::ManuallyDrop::new(color),
+        };
+        let range = VkImageSubresourceRange {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            baseMipLevel: 0,
+            levelCount: 1,
+            baseArrayLayer: 0,
+            layerCount: 1,
+        };
+        record_and_submit(logical_device, |command_buffer| {
+            command_buffer.cmd_clear_color_image(
+                image,
+                VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+                color,
+                &[range],
+            );
+        });
+        swapchain.lock().present(image_index)
+    }
+
+    #[test]
+    fn headless_surface_retains_presented_image() {
+        let extent = Extent2 {
+            width: 4,
+            height: 4,
+        };
+        let logical_device = create_logical_device();
+        let present_mode = VkPresentModeKHR::VK_PRESENT_MODE_IMMEDIATE_KHR;
+        let (surface_object, swapchain) =
+            create_headless_swapchain(&logical_device, extent, present_mode);
+        let capabilities = logical_device
+            .lock()
+            .physical_device()
+            .surface_capabilities(&surface_object.lock());
+        let Ok(capabilities) = capabilities else {
+            unreachable!()
+        };
+        assert_eq!(
+            (
+                capabilities.currentExtent.width,
+                capabilities.currentExtent.height
+            ),
+            (extent.width, extent.height)
+        );
+
+        let format = VkFormat::VK_FORMAT_R8G8B8A8_UNORM;
+        let (image_index, result) = swapchain.lock().acquire_next_image(u64::MAX, None, None);
+        assert_eq!(result, VkResult::VK_SUCCESS);
+        let image = swapchain.lock().images[image_index as usize].lock().handle;
+        let image_view = create_image_view(
+            logical_device.clone(),
+            image,
+            format,
+            VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT,
+        );
+        render_triangle_into(&logical_device, image_view);
+        assert_eq!(
+            swapchain.lock().present(image_index),
+            Ok(VkResult::VK_SUCCESS)
+        );
+
+        // Triangle covers texels above the diagonal, the rest keeps clear color.
+        let data = (0..4u32)
+            .flat_map(|y| (0..4u32).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                if x + y < 3 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 255, 0, 255]
+                }
+            })
+            .collect::<Vec<_>>();
+        let presented_image = surface_object.lock().presented_image().cloned();
+        assert_eq!(
+            presented_image,
+            Some(PresentedImage {
+                format,
+                extent,
+                data,
+            })
+        );
+    }
+
+    #[test]
+    fn fifo_swapchain_reacquires_presented_image() {
+        let logical_device = create_logical_device();
+        let extent = Extent2 {
+            width: 4,
+            height: 4,
+        };
+        let present_mode = VkPresentModeKHR::VK_PRESENT_MODE_FIFO_KHR;
+        let (_, swapchain) = create_headless_swapchain(&logical_device, extent, present_mode);
+        let image_count = swapchain.lock().images.len();
+        let mut image_indices = (0..image_count)
+            .map(|_| {
+                let (image_index, result) =
+                    swapchain.lock().acquire_next_image(u64::MAX, None, None);
+                assert_eq!(result, VkResult::VK_SUCCESS);
+                image_index
+            })
+            .collect::<Vec<_>>();
+        let (_, result) = swapchain.lock().acquire_next_image(0, None, None);
+        assert_eq!(result, VkResult::VK_NOT_READY);
+
+        let presented_index = image_indices[image_count - 1];
+        assert_eq!(
+            swapchain.lock().present(presented_index),
+            Ok(VkResult::VK_SUCCESS)
+        );
+        let (image_index, result) = swapchain.lock().acquire_next_image(u64::MAX, None, None);
+        assert_eq!(result, VkResult::VK_SUCCESS);
+        assert_eq!(image_index, presented_index);
+        image_indices.sort_unstable();
+        assert_eq!(image_indices, (0..image_count as u32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn present_waits_for_deferred_batch() {
+        let extent = Extent2 {
+            width: 4,
+            height: 4,
+        };
+        let logical_device = create_logical_device();
+        let present_mode = VkPresentModeKHR::VK_PRESENT_MODE_IMMEDIATE_KHR;
+        let (surface_object, swapchain) =
+            create_headless_swapchain(&logical_device, extent, present_mode);
+        let (image_index, result) = swapchain.lock().acquire_next_image(u64::MAX, None, None);
+        assert_eq!(result, VkResult::VK_SUCCESS);
+
+        let timeline_semaphore = create_timeline_semaphore(logical_device.clone());
+        let semaphore_create_info = VkSemaphoreCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SEMAPHORE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+        };
+        let Ok(rendered_semaphore) =
+            Semaphore::create(logical_device.clone(), &semaphore_create_info)
+        else {
+            unreachable!()
+        };
+        let Some(rendered_semaphore) = Semaphore::from_handle(rendered_semaphore) else {
+            unreachable!()
+        };
+        let image = swapchain.lock().images[image_index as usize].clone();
+        let color = VkClearColorValue {
+            float32: std::mem::ManuallyDrop::new([1.0, 0.0, 0.0, 1.0]),
+        };
+        let range = VkImageSubresourceRange {
+            aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.into(),
+            baseMipLevel: 0,
+            levelCount: 1,
+            baseArrayLayer: 0,
+            layerCount: 1,
+        };
+        let command_buffer = create_command_buffer(logical_device.clone());
+        let mut command_buffer_guard = command_buffer.lock();
+        assert_eq!(command_buffer_guard.begin(), Ok(()));
+        command_buffer_guard.cmd_clear_color_image(
+            image,
+            VkImageLayout::VK_IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+            color,
+            &[range],
+        );
+        assert_eq!(command_buffer_guard.end(), Ok(()));
+        drop(command_buffer_guard);
+
+        let Some(queue) = logical_device.lock().queue(0, 0) else {
+            unreachable!()
+        };
+        queue.lock().submit(
+            [SubmitInfo {
+                wait_semaphores: vec![timeline_semaphore.clone()],
+                wait_semaphore_values: vec![1],
+                command_buffers: vec![command_buffer],
+                signal_semaphores: vec![rendered_semaphore.clone()],
+                ..Default::default()
+            }],
+            None,
+        );
+        let mut result = VkResult::VK_ERROR_UNKNOWN;
+        let present_result = queue.lock().present(
+            [rendered_semaphore],
+            [swapchain],
+            [&image_index],
+            [&mut result],
+        );
+        assert_eq!(present_result, Ok(VkResult::VK_SUCCESS));
+        assert_eq!(result, VkResult::VK_SUCCESS);
+        assert_eq!(surface_object.lock().presented_image(), None);
+
+        Semaphore::signal_and_resume(&timeline_semaphore, 1);
+        assert!(queue.lock().is_idle());
+        let presented_image = surface_object.lock().presented_image().cloned();
+        assert_eq!(
+            presented_image,
+            Some(PresentedImage {
+                format: VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+                extent,
+                data: [255, 0, 0, 255].repeat(16),
+            })
+        );
+    }
+
+    type Frames = Arc<Mutex<Vec<PresentedImage>>>;
+
+    /// Window recording frames presented to it.
+    #[derive(Debug)]
+    struct RecordingTarget {
+        extent: Extent2<u32>,
+        frames: Frames,
+    }
+
+    impl PresentTarget for RecordingTarget {
+        fn extent(&self) -> Result<Extent2<u32>, VkResult> {
+            Ok(self.extent)
+        }
+
+        fn present(
+            &mut self,
+            pixels: &[u8],
+            format: VkFormat,
+            extent: Extent2<u32>,
+        ) -> Result<VkResult, VkResult> {
+            self.frames.lock().push(PresentedImage {
+                format,
+                extent,
+                data: pixels.to_vec(),
+            });
+            Ok(VkResult::VK_SUCCESS)
+        }
+
+        fn supported_formats(&self) -> &[VkFormat] {
+            &[VkFormat::VK_FORMAT_B8G8R8A8_UNORM]
+        }
+    }
+
+    #[test]
+    fn swapchain_presents_pixels_to_target() {
+        let instance = create_instance();
+        let extent = Extent2 {
+            width: 2,
+            height: 3,
+        };
+        let frames = Frames::default();
+        let target = RecordingTarget {
+            extent,
+            frames: frames.clone(),
+        };
+        let surface = Surface::create_with_target(instance, Box::new(target));
+        let Some(surface_object) = Surface::from_handle(surface) else {
+            unreachable!()
+        };
+        let logical_device = create_logical_device();
+        let surface_formats = logical_device
+            .lock()
+            .physical_device()
+            .surface_formats(&surface_object.lock());
+        assert_eq!(surface_formats.len(), 1);
+        let format = surface_formats[0].format;
+        assert_eq!(format, VkFormat::VK_FORMAT_B8G8R8A8_UNORM);
+
+        let swapchain = create_swapchain(&logical_device, surface, format, extent, VK_NULL_HANDLE);
+        for color in [[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 0.0]] {
+            assert_eq!(
+                clear_and_present(&logical_device, &swapchain, color),
+                Ok(VkResult::VK_SUCCESS)
+            );
+        }
+
+        let frame = |data: [u8; 4]| PresentedImage {
+            format,
+            extent,
+            data: data.repeat(6),
+        };
+        assert_eq!(
+            *frames.lock(),
+            [frame([0, 0, 255, 255]), frame([255, 0, 0, 0])]
+        );
+    }
+
+    #[test]
+    fn swapchain_of_unsupported_present_mode_fails() {
+        let extent = Extent2 {
+            width: 4,
+            height: 4,
+        };
+        let surface = create_headless_surface(extent).lock().handle;
+        let logical_device = create_logical_device();
+        let present_modes = logical_device.lock().physical_device().present_modes();
+        let present_mode = VkPresentModeKHR::VK_PRESENT_MODE_FIFO_RELAXED_KHR;
+        assert!(!present_modes.contains(&present_mode));
+
+        let swapchain_create_info = VkSwapchainCreateInfoKHR {
+            presentMode: present_mode,
+            ..swapchain_create_info(
+                surface,
+                VkFormat::VK_FORMAT_R8G8B8A8_UNORM,
+                extent,
+                VK_NULL_HANDLE,
+            )
+        };
+        assert_eq!(
+            Swapchain::create(logical_device, &swapchain_create_info),
+            Err(RuntimeError::InitializationFailed)
+        );
+    }
+
+    #[test]
+    fn presenting_out_of_range_image_fails() {
+        let instance = create_instance();
+        let extent = Extent2 {
+            width: 2,
+            height: 3,
+        };
+        let target = RecordingTarget {
+            extent,
+            frames: Frames::default(),
+        };
+        let surface = Surface::create_with_target(instance, Box::new(target));
+        let logical_device = create_logical_device();
+        let swapchain = create_swapchain(
+            &logical_device,
+            surface,
+            VkFormat::VK_FORMAT_B8G8R8A8_UNORM,
+            extent,
+            VK_NULL_HANDLE,
+        );
+        let image_count = swapchain.lock().images.len() as u32;
+        assert_eq!(
+            swapchain.lock().present(image_count),
+            Err(VkResult::VK_ERROR_VALIDATION_FAILED_EXT)
+        );
+    }
+
+    #[test]
+    fn recreated_swapchain_retires_old_one() {
+        let logical_device = create_logical_device();
+        let old_extent = Extent2 {
+            width: 2,
+            height: 2,
+        };
+        let present_mode = VkPresentModeKHR::VK_PRESENT_MODE_IMMEDIATE_KHR;
+        let (surface_object, old_swapchain) =
+            create_headless_swapchain(&logical_device, old_extent, present_mode);
+        let (old_image_index, result) =
+            old_swapchain
+                .lock()
+                .acquire_next_image(u64::MAX, None, None);
+        assert_eq!(result, VkResult::VK_SUCCESS);
+        let old_images = old_swapchain.lock().images();
+
+        let extent = Extent2 {
+            width: 4,
+            height: 2,
+        };
+        surface_object.lock().set_headless_extent(extent);
+        let old_handle = old_swapchain.lock().get_handle();
+        let surface = surface_object.lock().handle;
+        let format = VkFormat::VK_FORMAT_R8G8B8A8_UNORM;
+        let swapchain = create_swapchain(&logical_device, surface, format, extent, old_handle);
+
+        let (_, result) = old_swapchain
+            .lock()
+            .acquire_next_image(u64::MAX, None, None);
+        assert_eq!(result, VkResult::VK_ERROR_OUT_OF_DATE_KHR);
+        assert_eq!(
+            old_swapchain.lock().present(old_image_index),
+            Err(VkResult::VK_ERROR_OUT_OF_DATE_KHR)
+        );
+        assert_eq!(
+            clear_and_present(&logical_device, &swapchain, [0.2, 0.4, 0.6, 1.0]),
+            Ok(VkResult::VK_SUCCESS)
+        );
+        let presented_extent = surface_object
+            .lock()
+            .presented_image()
+            .map(|presented_image| presented_image.extent);
+        assert_eq!(presented_extent, Some(extent));
+
+        old_swapchain.lock().destroy();
+        Swapchain::drop_handle(old_handle);
+        for image in old_images {
+            assert!(Image::from_handle(image).is_none());
+        }
+        assert_eq!(
+            clear_and_present(&logical_device, &swapchain, [0.2, 0.4, 0.6, 1.0]),
+            Ok(VkResult::VK_SUCCESS)
+        );
+    }
+
+    #[test]
+    fn resized_window_makes_swapchain_out_of_date() {
+        let logical_device = create_logical_device();
+        let extent = Extent2 {
+            width: 4,
+            height: 4,
+        };
+        let present_mode = VkPresentModeKHR::VK_PRESENT_MODE_IMMEDIATE_KHR;
+        let (surface_object, swapchain) =
+            create_headless_swapchain(&logical_device, extent, present_mode);
+        let (image_index, result) = swapchain.lock().acquire_next_image(u64::MAX, None, None);
+        assert_eq!(result, VkResult::VK_SUCCESS);
+
+        surface_object.lock().set_headless_extent(Extent2 {
+            width: 8,
+            height: 4,
+        });
+        assert_eq!(
+            swapchain.lock().present(image_index),
+            Err(VkResult::VK_ERROR_OUT_OF_DATE_KHR)
+        );
+        let (_, result) = swapchain.lock().acquire_next_image(u64::MAX, None, None);
+        assert_eq!(result, VkResult::VK_ERROR_OUT_OF_DATE_KHR);
+
+        // Minimized window can't be matched by any swapchain.
+        surface_object
+            .lock()
+            .set_headless_extent(Extent2::default());
+        let (image_index, result) = swapchain.lock().acquire_next_image(u64::MAX, None, None);
+        assert_eq!(result, VkResult::VK_SUBOPTIMAL_KHR);
+        assert_eq!(
+            swapchain.lock().present(image_index),
+            Ok(VkResult::VK_SUBOPTIMAL_KHR)
+        );
+
+        surface_object.lock().set_headless_extent(extent);
+        assert_eq!(
+            clear_and_present(&logical_device, &swapchain, [0.2, 0.4, 0.6, 1.0]),
+            Ok(VkResult::VK_SUCCESS)
+        );
+    }
+}
@@ -1,7 +1,11 @@
 use crate::interpreter::Interpreter;
-use common::consts::MAX_CLIP_DISTANCES;
-use common::graphics::VertexInputState;
-use common::math::{Color, Fragment, Position, Vertex};
+use crate::spirv::Spirv;
+use anyhow::bail;
+use common::consts::{MAX_CLIP_DISTANCES, MAX_COLOR_ATTACHMENTS, MAX_VERTEX_ATTRIBUTES};
+use common::graphics::{Interpolation, VertexInputState};
+use common::math::{Color, Fragment, Position, Vector4, Vertex};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Default)]
 pub struct ShaderState {
@@ -9,27 +13,88 @@ pub struct ShaderState {
     pub fragment_shader: Option<Shader>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Shader {
     pub(crate) interpreter: Interpreter,
 }
 
 impl Shader {
     pub fn new(name: &str, code: Vec<u32>) -> anyhow::Result<Self> {
+        Self::with_specialization(name, code, &SpecializationInfo::default())
+    }
+
+    /// Creates shader whose specialization constants take values from `specialization` instead of
+    /// their defaults.
+    pub fn with_specialization(
+        name: &str,
+        code: Vec<u32>,
+        specialization: &SpecializationInfo,
+    ) -> anyhow::Result<Self> {
+        Self::from_spirv(name, &Spirv::new(&code)?, specialization)
+    }
+
+    /// Creates shader running entry point `name` of already parsed `spirv`, specialized with
+    /// `specialization`.
+    pub fn from_spirv(
+        name: &str,
+        spirv: &Spirv,
+        specialization: &SpecializationInfo,
+    ) -> anyhow::Result<Self> {
+        if spirv.entry_point.name != name {
+            bail!("spirv error: no entry point named {:?}", name);
+        }
         Ok(Self {
-            interpreter: Interpreter::new(name, code)?,
+            interpreter: Interpreter::new(spirv.specialize(specialization))?,
         })
     }
 }
 
+/// Values of specialization constants provided at pipeline creation.
+#[derive(Debug, Clone, Default)]
+pub struct SpecializationInfo {
+    /// Values of 32-bit scalar constants keyed by their `SpecId` decoration.
+    pub constants: HashMap<u32, u32>,
+}
+
+/// Resources bound to shader variables decorated with descriptor set and binding numbers.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderResources {
+    /// Contents of uniform and storage buffers keyed by descriptor set and binding numbers.
+    pub buffers: HashMap<(u32, u32), Vec<u8>>,
+    /// Contents of push constants.
+    pub push_constants: Vec<u8>,
+    /// Attachments of current subpass keyed by input attachment index.
+    pub input_attachments: HashMap<u32, InputAttachment>,
+}
+
+/// Texels of attachment read by fragment shader at its fragment position.
+#[derive(Debug, Clone, Default)]
+pub struct InputAttachment {
+    pub width: u32,
+    pub height: u32,
+    /// Bits of 32-bit texel components in row-major order, integers for integer formats and
+    /// floats for others.
+    pub texels: Vec<[u32; 4]>,
+}
+
+impl InputAttachment {
+    pub(crate) fn texel(&self, x: i32, y: i32) -> [u32; 4] {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return [0; 4];
+        }
+        self.texels[(y as u32 * self.width + x as u32) as usize]
+    }
+}
+
 impl Shader {
     pub fn execute_vertex_shader(
         &self,
         vertex_input_state: &VertexInputState,
+        resources: &ShaderResources,
         vertices: Vec<Vertex>,
     ) -> Vec<VertexShaderOutput> {
         self.interpreter
-            .execute_vertex_shader(vertex_input_state, vertices)
+            .execute_vertex_shader(vertex_input_state, resources, vertices)
     }
 }
 
@@ -43,6 +108,8 @@ pub struct VertexShaderOutput {
     pub vertex_index: u32,
     // gl_ClipDistances
     pub clip_distances: [f32; MAX_CLIP_DISTANCES as usize],
+    /// Outputs indexed by location, keeping raw bits of their components.
+    pub attributes: [Vector4; MAX_VERTEX_ATTRIBUTES as usize],
     // TODO: Determine shader output interface using OpEntryPoints and use it to initialize ShaderOutput
     //       https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#interfaces
 }
@@ -54,6 +121,7 @@ impl Default for VertexShaderOutput {
             point_size: 1.0,
             vertex_index: 0,
             clip_distances: [0.0f32, 0.0f32, 0.0f32, 0.0f32],
+            attributes: Default::default(),
         }
     }
 }
@@ -65,37 +133,69 @@ impl From<Vertex> for VertexShaderOutput {
             point_size: vertex.point_size,
             vertex_index: vertex.index,
             clip_distances: vertex.clip_distances,
+            attributes: Default::default(),
         }
     }
 }
 
 impl Shader {
-    pub fn execute_fragment_shader(&self, fragments: Vec<Fragment>) -> Vec<FragmentShaderOutput> {
-        self.interpreter.execute_fragment_shader(fragments)
+    /// Returns whether shader writes fragment depth, so that depth tests have to run after it.
+    pub const fn writes_frag_depth(&self) -> bool {
+        self.interpreter.writes_frag_depth()
+    }
+
+    /// Returns interpolation of fragment shader inputs indexed by location.
+    pub const fn input_interpolations(&self) -> &[Interpolation; MAX_VERTEX_ATTRIBUTES as usize] {
+        self.interpreter.input_interpolations()
+    }
+
+    pub fn execute_fragment_shader(
+        &self,
+        resources: &ShaderResources,
+        fragments: Vec<Fragment>,
+    ) -> Vec<FragmentShaderOutput> {
+        self.interpreter
+            .execute_fragment_shader(resources, fragments)
     }
 }
 
 #[derive(Debug, Copy, Clone, Default)]
 pub struct FragmentShaderOutput {
     pub position: Position,
-    pub color: Color,
+    /// Colors indexed by output location, `None` at locations shader doesn't declare.
+    pub colors: [Option<Color>; MAX_COLOR_ATTACHMENTS as usize],
 }
 
 impl From<Fragment> for FragmentShaderOutput {
     fn from(fragment: Fragment) -> Self {
+        let mut colors = [None; MAX_COLOR_ATTACHMENTS as usize];
+        colors[0] = Some(fragment.color);
         Self {
             position: fragment.position,
-            color: fragment.color,
+            colors,
         }
     }
 }
 
+impl Shader {
+    /// Runs compute shader for `workgroup_count` workgroups, leaving contents of storage buffers
+    /// it writes in `resources`.
+    pub fn execute_compute_shader(
+        &self,
+        resources: &mut ShaderResources,
+        workgroup_count: [u32; 3],
+    ) {
+        self.interpreter
+            .execute_compute_shader(resources, workgroup_count);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use common::{
         consts::{MAX_VERTEX_ATTRIBUTES, MAX_VERTEX_BINDINGS},
         graphics::{VertexAttribute, VertexBinding, VertexBindingNumber, VertexInputRate},
-        math::Format,
+        math::{Format, Vector4},
     };
 
     use super::*;
@@ -164,7 +264,9 @@ mod tests {
             position: Position::from_raw(10, 20, 30, 40).to_unorm8(),
             point_size: 1.0f32,
             index: 1,
+            instance_index: 0,
             clip_distances: [0.8f32, 0.4f32, 0.2f32, 0.1f32],
+            attributes: Default::default(),
         }];
         let expected = inputs
             .iter()
@@ -174,9 +276,11 @@ mod tests {
                 ..x.into()
             })
             .collect::<Vec<_>>();
-        let outputs = shader
-            .interpreter
-            .execute_vertex_shader(&vertex_input_state, inputs);
+        let outputs = shader.interpreter.execute_vertex_shader(
+            &vertex_input_state,
+            &ShaderResources::default(),
+            inputs,
+        );
         assert_eq!(outputs, expected);
     }
 
@@ -211,7 +315,9 @@ mod tests {
             position: Position::from_raw(10, 20, 30, 40).to_unorm8(),
             point_size: 1.0f32,
             index: 1,
+            instance_index: 0,
             clip_distances: [0.8f32, 0.4f32, 0.2f32, 0.1f32],
+            attributes: Default::default(),
         }];
         let expected = inputs
             .iter()
@@ -220,9 +326,11 @@ mod tests {
                 ..x.into()
             })
             .collect::<Vec<_>>();
-        let outputs = shader
-            .interpreter
-            .execute_vertex_shader(&vertex_input_state, inputs);
+        let outputs = shader.interpreter.execute_vertex_shader(
+            &vertex_input_state,
+            &ShaderResources::default(),
+            inputs,
+        );
         assert_eq!(outputs, expected);
     }
 
@@ -256,12 +364,16 @@ mod tests {
             position: Position::from_raw(10, 20, 30, 40).to_unorm8(),
             point_size: 1.0f32,
             index: 1,
+            instance_index: 0,
             clip_distances: [0.0f32, 0.0f32, 0.0f32, 0.0f32],
+            attributes: Default::default(),
         }];
         let expected = inputs.iter().map(|&x| x.into()).collect::<Vec<_>>();
-        let outputs = shader
-            .interpreter
-            .execute_vertex_shader(&vertex_input_state, inputs);
+        let outputs = shader.interpreter.execute_vertex_shader(
+            &vertex_input_state,
+            &ShaderResources::default(),
+            inputs,
+        );
         assert_eq!(outputs, expected);
     }
 
@@ -303,25 +415,36 @@ mod tests {
 
         // TODO: Test associating vertex input state to vertex input interface.
 
+        let vertex_attributes = |attribute| {
+            let mut attributes: [Vector4; MAX_VERTEX_ATTRIBUTES as usize] = Default::default();
+            attributes[0] = attribute;
+            attributes
+        };
         // R8G8 vec2
         let inputs = vec![
             Vertex {
-                position: Position::from_raw(101, 228, 0, 0).to_unorm8(),
+                position: Default::default(),
                 point_size: 1.0f32,
                 index: 0,
+                instance_index: 0,
                 clip_distances: Default::default(),
+                attributes: vertex_attributes(Position::from_raw(101, 228, 0, 0).to_unorm8()),
             },
             Vertex {
-                position: Position::from_raw(161, 201, 0, 0).to_unorm8(),
+                position: Default::default(),
                 point_size: 1.0f32,
                 index: 0,
+                instance_index: 0,
                 clip_distances: Default::default(),
+                attributes: vertex_attributes(Position::from_raw(161, 201, 0, 0).to_unorm8()),
             },
             Vertex {
-                position: Position::from_raw(243, 120, 0, 0).to_unorm8(),
+                position: Default::default(),
                 point_size: 1.0f32,
                 index: 0,
+                instance_index: 0,
                 clip_distances: Default::default(),
+                attributes: vertex_attributes(Position::from_raw(243, 120, 0, 0).to_unorm8()),
             },
         ];
 
@@ -341,7 +464,8 @@ mod tests {
             ),
         ];
 
-        let outputs = shader.execute_vertex_shader(&vertex_input_state, inputs);
+        let outputs =
+            shader.execute_vertex_shader(&vertex_input_state, &ShaderResources::default(), inputs);
 
         let eps = 0.00001f32; // TODO: Use ULP (units in the last place) as defined in Vulkan spec?
         for (output, (position, point_size)) in outputs.iter().zip(references) {
@@ -1,21 +1,37 @@
 use crate::spirv;
 use crate::spirv::Spirv;
 use anyhow::Context;
+use common::consts::MAX_VERTEX_ATTRIBUTES;
+use common::graphics::Interpolation;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Il {
     pub(crate) instructions: Vec<Instruction>,
+    /// Whether shader writes fragment depth, replacing the interpolated one.
+    pub(crate) writes_frag_depth: bool,
+    /// Interpolation of inputs indexed by location.
+    pub(crate) input_interpolations: [Interpolation; MAX_VERTEX_ATTRIBUTES as usize],
+    /// Number of invocations in workgroup of compute shader along each dimension.
+    pub(crate) local_size: [u32; 3],
 }
 
 impl Il {
-    pub fn new(name: &str, code: Vec<u32>) -> anyhow::Result<Self> {
-        let spirv = Spirv::new(name, code)?;
+    pub fn new(spirv: Spirv) -> anyhow::Result<Self> {
+        let writes_frag_depth = spirv.has_builtin(spirv::BuiltInDecoration::FragDepth);
+        let input_interpolations = spirv.input_interpolations();
+        let local_size = spirv.entry_point.local_size;
         let instructions = Self::parse_spirv(spirv)?;
-        Ok(Self { instructions })
+        Ok(Self {
+            instructions,
+            writes_frag_depth,
+            input_interpolations,
+            local_size,
+        })
     }
 }
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Variable {
     ObjectId(u32),
 }
@@ -26,7 +42,7 @@ impl Variable {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Instruction {
     Label {
         id: u32,
@@ -167,6 +183,36 @@ pub enum Instruction {
         false_label: u32,
     },
     Kill,
+    /// Fills variable pointed by `dst_pointer` with contents of buffer bound to `set` and
+    /// `binding`.
+    LoadDescriptorBuffer {
+        dst_pointer: Variable,
+        set: u32,
+        binding: u32,
+        decl: VariableDecl,
+    },
+    /// Fills variable pointed by `dst_pointer` with contents of push constants.
+    LoadPushConstants {
+        dst_pointer: Variable,
+        decl: VariableDecl,
+    },
+    /// Stores `index` of input attachment in image variable pointed by `dst_pointer`.
+    BindInputAttachment {
+        dst_pointer: Variable,
+        index: u32,
+    },
+    /// Reads texel of input attachment `image` at fragment position offset by `coordinate`.
+    ImageRead {
+        id: Variable,
+        image: Variable,
+        coordinate: Variable,
+    },
+    /// Makes variable pointed by `pointer` shared by all invocations of workgroup.
+    ShareWorkgroupVariable {
+        pointer: Variable,
+    },
+    /// Waits for all invocations of workgroup to reach barrier.
+    ControlBarrier,
 }
 
 impl Il {
@@ -216,10 +262,47 @@ impl Il {
                     let decl = Self::get_variable_decl(
                         &spirv,
                         &memory_object.type_,
-                        Self::from_spirv_decorations(&memory_object.decorations),
+                        Self::from_spirv_decorations(
+                            &memory_object.decorations,
+                            memory_object.storage_class == spirv::StorageClass::Output,
+                        ),
                     );
                     let id = Variable::from_spirv(id);
-                    pointer_variables.push(Instruction::VariableDecl { id, decl });
+                    if let Some(index) = memory_object.decorations.input_attachment_index {
+                        pointer_variables.push(Instruction::VariableDecl { id, decl });
+                        pointer_variables.push(Instruction::BindInputAttachment {
+                            dst_pointer: id,
+                            index,
+                        });
+                    } else if let (Some(set), Some(binding)) = (
+                        memory_object.decorations.descriptor_set,
+                        memory_object.decorations.binding_point,
+                    ) {
+                        pointer_variables.push(Instruction::VariableDecl {
+                            id,
+                            decl: decl.clone(),
+                        });
+                        pointer_variables.push(Instruction::LoadDescriptorBuffer {
+                            dst_pointer: id,
+                            set,
+                            binding,
+                            decl,
+                        });
+                    } else if memory_object.storage_class == spirv::StorageClass::PushConstant {
+                        pointer_variables.push(Instruction::VariableDecl {
+                            id,
+                            decl: decl.clone(),
+                        });
+                        pointer_variables.push(Instruction::LoadPushConstants {
+                            dst_pointer: id,
+                            decl,
+                        });
+                    } else if memory_object.storage_class == spirv::StorageClass::Workgroup {
+                        pointer_variables.push(Instruction::VariableDecl { id, decl });
+                        pointer_variables.push(Instruction::ShareWorkgroupVariable { pointer: id });
+                    } else {
+                        pointer_variables.push(Instruction::VariableDecl { id, decl });
+                    }
                 }
             }
         }
@@ -290,6 +373,22 @@ impl Il {
                         scalar: Variable::from_spirv(scalar),
                     });
                 }
+                spirv::Instruction::ImageRead {
+                    result_id,
+                    result_type,
+                    image,
+                    coordinate,
+                } => {
+                    let decl =
+                        Self::get_variable_decl(&spirv, result_type, VariableBacking::Memory);
+                    let id = Variable::from_spirv(result_id);
+                    instructions.push(Instruction::VariableDecl { id, decl });
+                    instructions.push(Instruction::ImageRead {
+                        id,
+                        image: Variable::from_spirv(image),
+                        coordinate: Variable::from_spirv(coordinate),
+                    });
+                }
                 spirv::Instruction::IAdd {
                     result_id,
                     result_type,
@@ -624,6 +723,11 @@ impl Il {
                 spirv::Instruction::Kill => {
                     instructions.push(Instruction::Kill);
                 }
+                spirv::Instruction::ControlBarrier => {
+                    instructions.push(Instruction::ControlBarrier);
+                }
+                // Invocations run one at a time, seeing each other's writes without barriers.
+                spirv::Instruction::MemoryBarrier => {}
             }
         }
         Ok(instructions)
@@ -651,19 +755,28 @@ impl Il {
         }
     }
 
-    const fn from_spirv_decorations(decorations: &spirv::Decorations) -> VariableBacking {
+    const fn from_spirv_decorations(
+        decorations: &spirv::Decorations,
+        output: bool,
+    ) -> VariableBacking {
         if let Some(builtin) = decorations.builtin {
             match builtin {
                 spirv::BuiltInDecoration::Position => VariableBacking::Position,
                 spirv::BuiltInDecoration::PointSize => VariableBacking::PointSize,
                 spirv::BuiltInDecoration::VertexIndex => VariableBacking::VertexIndex,
+                spirv::BuiltInDecoration::InstanceIndex => VariableBacking::InstanceIndex,
                 spirv::BuiltInDecoration::FragCoord => VariableBacking::FragCoord,
+                spirv::BuiltInDecoration::FragDepth => VariableBacking::FragDepth,
                 spirv::BuiltInDecoration::ClipDistance => VariableBacking::ClipDistance,
                 spirv::BuiltInDecoration::CullDistance => VariableBacking::CullDistance,
+                spirv::BuiltInDecoration::GlobalInvocationId => VariableBacking::GlobalInvocationId,
+                spirv::BuiltInDecoration::LocalInvocationId => VariableBacking::LocalInvocationId,
+                spirv::BuiltInDecoration::WorkgroupId => VariableBacking::WorkgroupId,
             }
         } else if let Some(location) = decorations.location {
             VariableBacking::Location {
                 number: location.number,
+                output,
             }
         } else {
             VariableBacking::Memory
@@ -720,6 +833,21 @@ impl Il {
                     },
                 )
             }
+            // Length is only known once bound to buffer.
+            spirv::Type::RuntimeArray {
+                element_type,
+                decorations,
+            } => {
+                let element_type = Self::get_variable_decl(spirv, element_type, backing);
+                (
+                    VariableKind::Array,
+                    0,
+                    VariableBacking::Array {
+                        element_kind: Box::new(element_type),
+                        array_stride: decorations.array_stride.unwrap_or_default(),
+                    },
+                )
+            }
             spirv::Type::Vector {
                 component_type,
                 component_count,
@@ -745,17 +873,26 @@ impl Il {
                         Self::get_variable_decl(
                             spirv,
                             type_,
-                            Self::from_spirv_decorations(decorations),
+                            Self::from_spirv_decorations(decorations, false),
                         )
                     })
                     .collect();
+                let member_offsets = member_types
+                    .iter()
+                    .map(|member_type| member_type.decorations.byte_offset)
+                    .collect();
                 assert!(decorations.block);
                 (
                     VariableKind::Struct,
                     member_types.len() as u32,
-                    VariableBacking::Struct { members },
+                    VariableBacking::Struct {
+                        members,
+                        member_offsets,
+                    },
                 )
             }
+            // Images are referred to by index of input attachment they are bound to.
+            spirv::Type::Image { sampled_type: _ } => (VariableKind::U32, 1, backing),
             spirv::Type::Pointer {
                 storage_class: _,
                 type_,
@@ -785,14 +922,21 @@ impl From<&VariableDecl> for Variable {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VariableDecl {
     pub(crate) kind: VariableKind,
     pub(crate) component_count: u32,
     pub(crate) backing: VariableBacking,
 }
 
-#[derive(Debug, Copy, Clone)]
+impl VariableDecl {
+    /// Whether variable is a single scalar held in memory of shader.
+    pub(crate) const fn is_scalar_memory(&self) -> bool {
+        matches!(self.backing, VariableBacking::Memory) && self.component_count == 1
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum VariableKind {
     F32,
     U32,
@@ -804,24 +948,33 @@ pub enum VariableKind {
     Pointer,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum VariableBacking {
     Memory,
     Location {
         number: u32,
+        /// Whether variable is an output of the shader, rather than an input.
+        output: bool,
     },
     Position,
     PointSize,
     VertexIndex,
+    InstanceIndex,
     FragCoord,
+    FragDepth,
     ClipDistance,
     CullDistance,
+    GlobalInvocationId,
+    LocalInvocationId,
+    WorkgroupId,
     Array {
         element_kind: Box<VariableDecl>,
         array_stride: u32,
     },
     Struct {
         members: Vec<VariableDecl>,
+        /// Byte offsets of members within buffer, if decorated.
+        member_offsets: Vec<Option<u32>>,
     },
     Pointer {
         kind: Box<VariableDecl>,
@@ -1,27 +1,41 @@
-use crate::glsl::{FragmentShaderOutput, VertexShaderOutput};
+use crate::glsl::{FragmentShaderOutput, ShaderResources, VertexShaderOutput};
 use crate::il;
-use common::consts::{MAX_CLIP_DISTANCES, MAX_CULL_DISTANCES};
-use common::graphics::VertexInputState;
+use crate::spirv::Spirv;
+use common::consts::{MAX_CLIP_DISTANCES, MAX_CULL_DISTANCES, MAX_VERTEX_ATTRIBUTES};
+use common::format::NumericFormat;
+use common::graphics::{Interpolation, VertexInputState};
 use common::math::{Format, Fragment, Vector4, Vertex};
 use hashbrown::HashMap;
 use log::warn;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Interpreter {
     il: il::Il,
 }
 
 impl Interpreter {
-    pub fn new(name: &str, code: Vec<u32>) -> anyhow::Result<Self> {
-        let il = il::Il::new(name, code)?;
+    pub fn new(spirv: Spirv) -> anyhow::Result<Self> {
+        let il = il::Il::new(spirv)?;
         Ok(Self { il })
     }
+
+    pub(crate) const fn writes_frag_depth(&self) -> bool {
+        self.il.writes_frag_depth
+    }
+
+    pub(crate) const fn input_interpolations(
+        &self,
+    ) -> &[Interpolation; MAX_VERTEX_ATTRIBUTES as usize] {
+        &self.il.input_interpolations
+    }
 }
 
 impl Interpreter {
     pub(crate) fn execute_vertex_shader(
         &self,
-        _vertex_input_state: &VertexInputState,
+        vertex_input_state: &VertexInputState,
+        resources: &ShaderResources,
         vertices: Vec<Vertex>,
     ) -> Vec<VertexShaderOutput> {
         warn!("TODO: Create shader input/output interfaces, check if match between stages");
@@ -30,11 +44,11 @@ impl Interpreter {
 
         for vertex in vertices {
             let mut state = State::new();
-            state.set_vertex_shader_input(vertex);
+            state.set_vertex_shader_input(vertex_input_state, vertex);
 
             loop {
                 let instruction = &self.il.instructions[state.pc];
-                let end = state.interpret_il_instruction(instruction);
+                let end = state.interpret_il_instruction(instruction, resources);
                 if end {
                     break;
                 }
@@ -47,6 +61,7 @@ impl Interpreter {
 
     pub(crate) fn execute_fragment_shader(
         &self,
+        resources: &ShaderResources,
         fragments: Vec<Fragment>,
     ) -> Vec<FragmentShaderOutput> {
         let mut outputs: Vec<FragmentShaderOutput> = vec![];
@@ -57,7 +72,7 @@ impl Interpreter {
 
             loop {
                 let instruction = &self.il.instructions[state.pc];
-                let end = state.interpret_il_instruction(instruction);
+                let end = state.interpret_il_instruction(instruction, resources);
                 if end {
                     break;
                 }
@@ -67,6 +82,69 @@ impl Interpreter {
         }
         outputs
     }
+
+    /// Runs all invocations of `workgroup_count` workgroups, leaving contents of storage
+    /// buffers they write in `resources`.
+    pub(crate) fn execute_compute_shader(
+        &self,
+        resources: &mut ShaderResources,
+        workgroup_count: [u32; 3],
+    ) {
+        let [size_x, size_y, size_z] = self.il.local_size;
+        let [count_x, count_y, count_z] = workgroup_count;
+        for (z, y, x) in itertools::iproduct!(0..count_z, 0..count_y, 0..count_x) {
+            let mut workgroup_memory = HashMap::new();
+            let mut invocations = itertools::iproduct!(0..size_z, 0..size_y, 0..size_x)
+                .map(|(local_z, local_y, local_x)| {
+                    let mut state = State::new();
+                    state.set_compute_shader_input(
+                        [x, y, z],
+                        [local_x, local_y, local_z],
+                        self.il.local_size,
+                    );
+                    state
+                })
+                .collect::<Vec<_>>();
+            // Invocations run one after another up to the next barrier, none passing it before
+            // all have reached it.
+            while !invocations.is_empty() {
+                invocations.retain_mut(|state| {
+                    !self.run_until_barrier(state, resources, &mut workgroup_memory)
+                });
+            }
+        }
+    }
+
+    /// Runs invocation until it reaches control barrier or returns, making memory it shares
+    /// with other invocations visible to them. Returns whether invocation has finished.
+    fn run_until_barrier(
+        &self,
+        state: &mut State,
+        resources: &mut ShaderResources,
+        workgroup_memory: &mut HashMap<il::Variable, Vec<u8>>,
+    ) -> bool {
+        state.load_shared_memory(resources, workgroup_memory);
+        let finished = loop {
+            let instruction = &self.il.instructions[state.pc];
+            match instruction {
+                il::Instruction::ControlBarrier => {
+                    state.pc += 1;
+                    break false;
+                }
+                il::Instruction::ShareWorkgroupVariable { pointer } => {
+                    state.il_share_workgroup_variable(pointer, workgroup_memory);
+                    state.pc += 1;
+                }
+                _ => {
+                    if state.interpret_il_instruction(instruction, resources) {
+                        break true;
+                    }
+                }
+            }
+        };
+        state.store_shared_memory(resources, workgroup_memory);
+        finished
+    }
 }
 
 #[derive(Debug)]
@@ -82,8 +160,14 @@ struct State {
 
     built_in_variables: HashMap<BuiltIn, Variable>,
     location_variables: HashMap<u32, Variable>,
+    output_location_variables: HashMap<u32, Variable>,
 
     il_variables: HashMap<il::Variable, Variable>,
+
+    /// Memory regions holding contents of buffers bound to set and binding.
+    descriptor_buffers: Vec<((u32, u32), MemoryRegion)>,
+    /// Memory regions of variables shared by invocations of workgroup.
+    workgroup_variables: Vec<(il::Variable, Vec<MemoryRegion>)>,
 }
 
 impl State {
@@ -98,7 +182,10 @@ impl State {
             pointer_variables: vec![],
             built_in_variables: Default::default(),
             location_variables: Default::default(),
+            output_location_variables: Default::default(),
             il_variables: Default::default(),
+            descriptor_buffers: vec![],
+            workgroup_variables: vec![],
         }
     }
 }
@@ -125,7 +212,7 @@ impl State {
 }
 
 impl State {
-    fn set_vertex_shader_input(&mut self, vertex: Vertex) {
+    fn set_vertex_shader_input(&mut self, vertex_input_state: &VertexInputState, vertex: Vertex) {
         let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32 * 4);
         let variable = self.add_array_variable(ArrayVariable {
             memory_region,
@@ -160,6 +247,18 @@ impl State {
             bytemuck::cast_slice(&[vertex.index]),
         );
 
+        let memory_region = self.allocate_memory(std::mem::size_of::<u32>() as u32);
+        let variable = self.add_array_variable(ArrayVariable {
+            memory_region,
+            stride: std::mem::size_of::<u32>() as u32,
+        });
+        self.built_in_variables
+            .insert(BuiltIn::InstanceIndex, variable);
+        self.store_imm32(
+            self.array_variable(self.built_in_variable(BuiltIn::InstanceIndex)),
+            bytemuck::cast_slice(&[vertex.instance_index]),
+        );
+
         let memory_region =
             self.allocate_memory(std::mem::size_of::<f32>() as u32 * MAX_CLIP_DISTANCES);
         let variable = self.add_array_variable(ArrayVariable {
@@ -186,17 +285,38 @@ impl State {
             bytemuck::cast_slice(&[0.0f32, 0.0f32, 0.0f32, 0.0f32]),
         );
 
-        let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32 * 4);
-        let variable = self.add_array_variable(ArrayVariable {
-            memory_region,
-            stride: std::mem::size_of::<f32>() as u32,
-        });
-        self.location_variables.insert(0, variable);
-        self.store_imm32(
-            self.array_variable(self.location_variable(0)),
-            bytemuck::cast_slice(vertex.position.get_as_f32_array().as_slice()),
-        );
-        warn!("TODO: use vertex bindings and vertex attributes?");
+        for (location, attribute) in vertex.attributes.iter().enumerate() {
+            let memory_region = self.allocate_memory(std::mem::size_of::<u32>() as u32 * 4);
+            let variable = self.add_array_variable(ArrayVariable {
+                memory_region,
+                stride: std::mem::size_of::<u32>() as u32,
+            });
+            self.location_variables.insert(location as u32, variable);
+            // Attributes of integer formats are read by shader as integer components.
+            let numeric_format = vertex_input_state.attributes[location]
+                .map(|attribute| attribute.format.info().numeric_format);
+            let components = attribute.get_as_f32_array();
+            let components = match numeric_format {
+                Some(NumericFormat::Uint) => components.map(|x| x as u32),
+                Some(NumericFormat::Sint) => components.map(|x| x as i32 as u32),
+                _ => components.map(f32::to_bits),
+            };
+            self.store_imm32(self.array_variable(variable), &components);
+        }
+    }
+
+    /// Returns raw components of output variables indexed by location, or `None` at locations
+    /// shader doesn't declare.
+    fn output_locations(&self) -> [Option<Vector4>; MAX_VERTEX_ATTRIBUTES as usize] {
+        std::array::from_fn(|location| {
+            let variable = self.output_location_variables.get(&(location as u32))?;
+            let Ok(&[r, g, b, a]) =
+                <&[u32; 4]>::try_from(self.load_imm32(self.array_variable(*variable)))
+            else {
+                unreachable!()
+            };
+            Some(Vector4::from_raw(r.into(), g.into(), b.into(), a.into()))
+        })
     }
 
     fn vertex_shader_output(&self) -> VertexShaderOutput {
@@ -221,6 +341,7 @@ impl State {
             point_size,
             vertex_index,
             clip_distances,
+            attributes: self.output_locations().map(Option::unwrap_or_default),
         }
     }
 
@@ -236,32 +357,71 @@ impl State {
             bytemuck::cast_slice(fragment.position.get_as_f32_array().as_slice()),
         );
 
-        let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32 * 4);
+        // Fragment depth not written by shader keeps the interpolated one.
+        let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32);
         let variable = self.add_array_variable(ArrayVariable {
             memory_region,
             stride: std::mem::size_of::<f32>() as u32,
         });
-        self.location_variables.insert(0, variable);
+        self.built_in_variables.insert(BuiltIn::FragDepth, variable);
         self.store_imm32(
-            self.array_variable(self.location_variable(0)),
-            bytemuck::cast_slice(fragment.color.get_as_f32_array().as_slice()),
+            self.array_variable(self.built_in_variable(BuiltIn::FragDepth)),
+            bytemuck::cast_slice(&[fragment.position.get_as_sfloat32(2)]),
         );
-        warn!("TODO: use descriptors");
+
+        // Inputs keep raw components of vertex shader outputs.
+        for (location, attribute) in fragment.attributes.iter().enumerate() {
+            let memory_region = self.allocate_memory(std::mem::size_of::<u32>() as u32 * 4);
+            let variable = self.add_array_variable(ArrayVariable {
+                memory_region,
+                stride: std::mem::size_of::<u32>() as u32,
+            });
+            self.location_variables.insert(location as u32, variable);
+            self.store_imm32(
+                self.array_variable(variable),
+                &attribute.components.map(|component| component as u32),
+            );
+        }
+    }
+
+    fn set_compute_shader_input(
+        &mut self,
+        workgroup_id: [u32; 3],
+        local_id: [u32; 3],
+        local_size: [u32; 3],
+    ) {
+        let global_id =
+            std::array::from_fn::<_, 3, _>(|i| workgroup_id[i] * local_size[i] + local_id[i]);
+        for (built_in, value) in [
+            (BuiltIn::GlobalInvocationId, global_id),
+            (BuiltIn::LocalInvocationId, local_id),
+            (BuiltIn::WorkgroupId, workgroup_id),
+        ] {
+            let memory_region = self.allocate_memory(std::mem::size_of::<u32>() as u32 * 3);
+            let variable = self.add_array_variable(ArrayVariable {
+                memory_region,
+                stride: std::mem::size_of::<u32>() as u32,
+            });
+            self.built_in_variables.insert(built_in, variable);
+            self.store_imm32(self.array_variable(variable), &value);
+        }
     }
 
     fn fragment_shader_output(&mut self) -> FragmentShaderOutput {
-        let position = Vector4::from_vertex_buffer_bytes(
+        let [x, y, _, w] = Vector4::from_vertex_buffer_bytes(
             Format::R32G32B32A32Sfloat,
             bytemuck::cast_slice(
                 self.load_imm32(self.array_variable(self.built_in_variable(BuiltIn::FragCoord))),
             ),
-        );
-        warn!("TODO: Determine color using fragment shader interface");
-        let color = Vector4::from_vertex_buffer_bytes(
-            Format::R32G32B32A32Sfloat,
-            bytemuck::cast_slice(self.load_imm32(self.array_variable(self.location_variable(0)))),
-        );
-        FragmentShaderOutput { position, color }
+        )
+        .get_as_f32_array();
+        let depth = *bytemuck::from_bytes::<f32>(bytemuck::cast_slice(
+            self.load_imm32(self.array_variable(self.built_in_variable(BuiltIn::FragDepth))),
+        ));
+        let position = Vector4::from_sfloat32_raw(x, y, depth, w);
+        let outputs = self.output_locations();
+        let colors = std::array::from_fn(|location| outputs[location]);
+        FragmentShaderOutput { position, colors }
     }
 }
 
@@ -269,10 +429,15 @@ impl State {
 enum BuiltIn {
     Position,
     FragCoord,
+    FragDepth,
     PointSize,
     VertexIndex,
+    InstanceIndex,
     ClipDistance,
     CullDistance,
+    GlobalInvocationId,
+    LocalInvocationId,
+    WorkgroupId,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -355,13 +520,38 @@ impl Variable {
                     stride: Self::size(decl),
                 })
             }
-            il::VariableBacking::Location { number } => state.location_variable(*number),
+            il::VariableBacking::Location {
+                number,
+                output: false,
+            } => state.location_variable(*number),
+            il::VariableBacking::Location {
+                number,
+                output: true,
+            } => state.output_location_variable(*number),
             il::VariableBacking::Position => state.built_in_variable(BuiltIn::Position),
             il::VariableBacking::PointSize => state.built_in_variable(BuiltIn::PointSize),
             il::VariableBacking::VertexIndex => state.built_in_variable(BuiltIn::VertexIndex),
+            il::VariableBacking::InstanceIndex => state.built_in_variable(BuiltIn::InstanceIndex),
             il::VariableBacking::FragCoord => state.built_in_variable(BuiltIn::FragCoord),
+            il::VariableBacking::FragDepth => state.built_in_variable(BuiltIn::FragDepth),
             il::VariableBacking::ClipDistance => state.built_in_variable(BuiltIn::ClipDistance),
             il::VariableBacking::CullDistance => state.built_in_variable(BuiltIn::CullDistance),
+            il::VariableBacking::GlobalInvocationId => {
+                state.built_in_variable(BuiltIn::GlobalInvocationId)
+            }
+            il::VariableBacking::LocalInvocationId => {
+                state.built_in_variable(BuiltIn::LocalInvocationId)
+            }
+            il::VariableBacking::WorkgroupId => state.built_in_variable(BuiltIn::WorkgroupId),
+            // Arrays of scalars not bound to buffer are tightly packed.
+            il::VariableBacking::Array { element_kind, .. } if element_kind.is_scalar_memory() => {
+                let stride = Self::size(element_kind);
+                let memory_region = state.allocate_memory(stride * decl.component_count.max(1));
+                state.add_array_variable(ArrayVariable {
+                    memory_region,
+                    stride,
+                })
+            }
             il::VariableBacking::Array {
                 element_kind,
                 array_stride,
@@ -377,7 +567,7 @@ impl Variable {
                     unimplemented!("{element_kind:#?} {array_stride:#?} {element:#?}")
                 }
             }
-            il::VariableBacking::Struct { members } => {
+            il::VariableBacking::Struct { members, .. } => {
                 let variable = StructVariable {
                     members: members.iter().map(|x| Self::from_il(x, state)).collect(),
                 };
@@ -454,14 +644,40 @@ impl State {
             .unwrap_or_else(|| unreachable!())
     }
 
+    /// Returns output variable at location, allocating a zeroed one on its declaration.
+    fn output_location_variable(&mut self, number: u32) -> Variable {
+        if let Some(variable) = self.output_location_variables.get(&number) {
+            return *variable;
+        }
+        let memory_region = self.allocate_memory(std::mem::size_of::<u32>() as u32 * 4);
+        let variable = self.add_array_variable(ArrayVariable {
+            memory_region,
+            stride: std::mem::size_of::<u32>() as u32,
+        });
+        self.output_location_variables.insert(number, variable);
+        variable
+    }
+
     fn allocate_memory(&mut self, size: u32) -> MemoryRegion {
-        MemoryRegion {
-            address: {
-                let address = self.memory_last_idx;
-                self.memory_last_idx += size;
-                address
-            },
-            size,
+        let address = self.memory_last_idx;
+        self.memory_last_idx += size;
+        if self.memory.len() < self.memory_last_idx as usize {
+            self.memory.resize(self.memory_last_idx as usize, 0);
+        }
+        MemoryRegion { address, size }
+    }
+
+    /// Returns memory regions holding components of variable.
+    fn memory_regions(&self, variable: Variable) -> Vec<MemoryRegion> {
+        match variable {
+            Variable::Array(_) => vec![self.array_variable(variable).memory_region],
+            Variable::Struct(_) => self
+                .struct_variable(variable)
+                .members
+                .iter()
+                .flat_map(|&member| self.memory_regions(member))
+                .collect(),
+            Variable::Pointer(_) => unreachable!(),
         }
     }
 
@@ -615,12 +831,269 @@ impl State {
         self.store_array(self.array_variable(dst), self.array_variable(src));
     }
 
+    /// Points variable at a copy of buffer bound to `set` and `binding`, contents of which are
+    /// stored back to the buffer by compute shaders.
+    pub(crate) fn il_load_descriptor_buffer(
+        &mut self,
+        dst_pointer: &il::Variable,
+        set: u32,
+        binding: u32,
+        decl: &il::VariableDecl,
+        resources: &ShaderResources,
+    ) {
+        let Some(bytes) = resources.buffers.get(&(set, binding)) else {
+            warn!("No buffer bound to set {set}, binding {binding}");
+            return;
+        };
+        let il::VariableBacking::Pointer { kind } = &decl.backing else {
+            unreachable!()
+        };
+        let memory_region = self.allocate_memory(bytes.len() as u32);
+        self.memory_mut(&memory_region).copy_from_slice(bytes);
+        let variable = self.bind_buffer(kind, memory_region);
+        self.pointer_variable_mut(self.il_variable(dst_pointer))
+            .pointer = Some(variable);
+        self.descriptor_buffers
+            .push(((set, binding), memory_region));
+    }
+
+    /// Creates variable laid out in memory region holding contents of buffer, placing struct
+    /// members at their decorated offsets and array elements at their stride.
+    fn bind_buffer(&mut self, decl: &il::VariableDecl, memory_region: MemoryRegion) -> Variable {
+        let region = |offset: u32, size: u32| {
+            (offset.saturating_add(size) <= memory_region.size).then_some(MemoryRegion {
+                address: memory_region.address + offset,
+                size,
+            })
+        };
+        match &decl.backing {
+            il::VariableBacking::Struct {
+                members,
+                member_offsets,
+            } => {
+                let members = std::iter::zip(members, member_offsets)
+                    .enumerate()
+                    .map(|(i, (member, offset))| {
+                        let Some(region) = offset.and_then(|offset| {
+                            region(offset, memory_region.size.saturating_sub(offset))
+                        }) else {
+                            warn!("Struct member {i} has no Offset decoration within buffer");
+                            return Variable::from_il(member, self);
+                        };
+                        self.bind_buffer(member, region)
+                    })
+                    .collect();
+                self.add_struct_variable(StructVariable { members })
+            }
+            il::VariableBacking::Array {
+                element_kind,
+                array_stride,
+            } if element_kind.is_scalar_memory() => {
+                let stride = match *array_stride {
+                    0 => Variable::size(element_kind),
+                    array_stride => array_stride,
+                };
+                // Runtime arrays have as many elements as fit in buffer.
+                let len = match decl.component_count {
+                    0 => memory_region.size / stride,
+                    len => len,
+                };
+                let Some(memory_region) = region(0, stride * len) else {
+                    warn!("Array of {len} elements doesn't fit in buffer");
+                    return Variable::from_il(decl, self);
+                };
+                self.add_array_variable(ArrayVariable {
+                    memory_region,
+                    stride,
+                })
+            }
+            il::VariableBacking::Memory => {
+                let stride = Variable::size(decl);
+                let Some(memory_region) = region(0, stride * decl.component_count) else {
+                    warn!("Variable doesn't fit in buffer");
+                    return Variable::from_il(decl, self);
+                };
+                self.add_array_variable(ArrayVariable {
+                    memory_region,
+                    stride,
+                })
+            }
+            // Other variables get a copy of buffer contents, not written back.
+            _ => {
+                let variable = Variable::from_il(decl, self);
+                let bytes = self.memory(&memory_region).to_vec();
+                self.store_buffer_bytes(variable, decl, &bytes);
+                variable
+            }
+        }
+    }
+
+    /// Makes variable pointed by `pointer` shared with other invocations of workgroup, taking
+    /// contents other invocations have already written.
+    fn il_share_workgroup_variable(
+        &mut self,
+        pointer: &il::Variable,
+        workgroup_memory: &HashMap<il::Variable, Vec<u8>>,
+    ) {
+        let variable = self
+            .pointer_variable(self.il_variable(pointer))
+            .pointer
+            .unwrap_or_else(|| unreachable!());
+        let memory_regions = self.memory_regions(variable);
+        if let Some(bytes) = workgroup_memory.get(pointer) {
+            self.store_memory_regions(&memory_regions, bytes);
+        }
+        self.workgroup_variables.push((*pointer, memory_regions));
+    }
+
+    /// Copies current contents of buffers and workgroup variables into memory of invocation.
+    fn load_shared_memory(
+        &mut self,
+        resources: &ShaderResources,
+        workgroup_memory: &HashMap<il::Variable, Vec<u8>>,
+    ) {
+        for (key, memory_region) in self.descriptor_buffers.clone() {
+            if let Some(bytes) = resources.buffers.get(&key) {
+                self.store_memory_regions(&[memory_region], bytes);
+            }
+        }
+        for (variable, memory_regions) in self.workgroup_variables.clone() {
+            if let Some(bytes) = workgroup_memory.get(&variable) {
+                self.store_memory_regions(&memory_regions, bytes);
+            }
+        }
+    }
+
+    /// Copies contents of buffers and workgroup variables out of memory of invocation, for
+    /// other invocations to see its writes.
+    fn store_shared_memory(
+        &self,
+        resources: &mut ShaderResources,
+        workgroup_memory: &mut HashMap<il::Variable, Vec<u8>>,
+    ) {
+        for (key, memory_region) in &self.descriptor_buffers {
+            if let Some(bytes) = resources.buffers.get_mut(key) {
+                let size = bytes.len().min(memory_region.size as usize);
+                bytes[..size].copy_from_slice(&self.memory(memory_region)[..size]);
+            }
+        }
+        for (variable, memory_regions) in &self.workgroup_variables {
+            let bytes = memory_regions
+                .iter()
+                .flat_map(|memory_region| self.memory(memory_region))
+                .copied()
+                .collect();
+            workgroup_memory.insert(*variable, bytes);
+        }
+    }
+
+    /// Fills consecutive memory regions with bytes.
+    fn store_memory_regions(&mut self, memory_regions: &[MemoryRegion], bytes: &[u8]) {
+        let mut bytes = bytes;
+        for memory_region in memory_regions {
+            let size = bytes.len().min(memory_region.size as usize);
+            self.memory_mut(memory_region)[..size].copy_from_slice(&bytes[..size]);
+            bytes = &bytes[size..];
+        }
+    }
+
+    pub(crate) fn il_load_push_constants(
+        &mut self,
+        dst_pointer: &il::Variable,
+        decl: &il::VariableDecl,
+        resources: &ShaderResources,
+    ) {
+        let il::VariableBacking::Pointer { kind } = &decl.backing else {
+            unreachable!()
+        };
+        let dst = *self
+            .pointer_variable(self.il_variable(dst_pointer))
+            .pointer
+            .as_ref()
+            .unwrap_or_else(|| unreachable!());
+        self.store_buffer_bytes(dst, kind, &resources.push_constants);
+    }
+
+    fn il_bind_input_attachment(&mut self, dst_pointer: &il::Variable, index: u32) {
+        let dst = *self
+            .pointer_variable(self.il_variable(dst_pointer))
+            .pointer
+            .as_ref()
+            .unwrap_or_else(|| unreachable!());
+        self.store_imm32(self.array_variable(dst), &[index]);
+    }
+
+    /// Reads texel of input attachment at pixel of fragment offset by `coordinate`, reading zeros
+    /// outside of attachment or if none is bound to index.
+    fn il_image_read(
+        &mut self,
+        result: &il::Variable,
+        image: &il::Variable,
+        coordinate: &il::Variable,
+        resources: &ShaderResources,
+    ) {
+        let index = self.load_imm32(self.array_variable(self.il_variable(image)))[0];
+        let &[dx, dy] = self.load_imm32(self.array_variable(self.il_variable(coordinate))) else {
+            unreachable!()
+        };
+        let &[x, y, ..] =
+            self.load_imm32(self.array_variable(self.built_in_variable(BuiltIn::FragCoord)))
+        else {
+            unreachable!()
+        };
+        let x = f32::from_bits(x).floor() as i32 + dx as i32;
+        let y = f32::from_bits(y).floor() as i32 + dy as i32;
+        let texel = resources
+            .input_attachments
+            .get(&index)
+            .map(|input_attachment| input_attachment.texel(x, y))
+            .unwrap_or_default();
+        let result = self.array_variable(self.il_variable(result));
+        self.store_imm32(result, &texel);
+    }
+
+    /// Copies buffer contents into variable, placing struct members at their decorated offsets.
+    fn store_buffer_bytes(&mut self, dst: Variable, decl: &il::VariableDecl, bytes: &[u8]) {
+        match (&decl.backing, dst) {
+            (
+                il::VariableBacking::Struct {
+                    members,
+                    member_offsets,
+                },
+                Variable::Struct(_),
+            ) => {
+                for (i, (member, offset)) in members.iter().zip(member_offsets).enumerate() {
+                    let Some(offset) = offset.map(|x| x as usize) else {
+                        warn!("Struct member {i} has no Offset decoration");
+                        continue;
+                    };
+                    let member_variable = self.struct_variable(dst).members[i];
+                    self.store_buffer_bytes(
+                        member_variable,
+                        member,
+                        bytes.get(offset..).unwrap_or_default(),
+                    );
+                }
+            }
+            (_, Variable::Array(_)) => {
+                let dst = self.array_variable(dst).memory_region;
+                let size = bytes.len().min(dst.size as usize);
+                self.memory_mut(&dst)[..size].copy_from_slice(&bytes[..size]);
+            }
+            _ => unimplemented!("{decl:#?}"),
+        }
+    }
+
     pub(crate) fn il_store_imm32(&mut self, variable: &il::Variable, imm: &[u32]) {
         warn!("TODO: Use variable stride");
-        let dst = self
-            .array_variable(self.il_variable(variable))
-            .memory_region;
+        let dst = self.array_variable(self.il_variable(variable));
+        let is_bool = dst.is_bool();
+        let dst = dst.memory_region;
         for (i, src) in imm.iter().enumerate() {
+            if is_bool {
+                self.memory[dst.address as usize + i] = u8::from(*src != 0);
+                continue;
+            }
             self.memory[dst.address as usize + 4 * i..dst.address as usize + 4 * (i + 1)]
                 .copy_from_slice(&src.to_ne_bytes());
         }
@@ -802,7 +1275,11 @@ impl State {
 }
 
 impl State {
-    fn interpret_il_instruction(&mut self, instruction: &il::Instruction) -> bool {
+    fn interpret_il_instruction(
+        &mut self,
+        instruction: &il::Instruction,
+        resources: &ShaderResources,
+    ) -> bool {
         match instruction {
             il::Instruction::Label { id } => {
                 self.labels.insert_unique_unchecked(*id, self.pc);
@@ -905,6 +1382,31 @@ impl State {
             il::Instruction::Kill => {
                 todo!()
             }
+            il::Instruction::LoadDescriptorBuffer {
+                dst_pointer,
+                set,
+                binding,
+                decl,
+            } => {
+                self.il_load_descriptor_buffer(dst_pointer, *set, *binding, decl, resources);
+            }
+            il::Instruction::LoadPushConstants { dst_pointer, decl } => {
+                self.il_load_push_constants(dst_pointer, decl, resources);
+            }
+            il::Instruction::BindInputAttachment { dst_pointer, index } => {
+                self.il_bind_input_attachment(dst_pointer, *index);
+            }
+            il::Instruction::ImageRead {
+                id,
+                image,
+                coordinate,
+            } => {
+                self.il_image_read(id, image, coordinate, resources);
+            }
+            // Handled when running compute shaders.
+            il::Instruction::ShareWorkgroupVariable { .. } | il::Instruction::ControlBarrier => {
+                unreachable!()
+            }
         };
         self.pc += 1;
         false
@@ -1,4 +1,7 @@
+use crate::glsl::SpecializationInfo;
 use anyhow::{bail, Context};
+use common::consts::MAX_VERTEX_ATTRIBUTES;
+use common::graphics::Interpolation;
 use hashbrown::HashMap;
 use log::debug;
 use rspirv::binary::Disassemble;
@@ -6,21 +9,36 @@ use rspirv::dr::{
     Block as Block_, Instruction as Instruction_, Module as Module_, Operand as Operand_, Operand,
 };
 use rspirv::spirv as spirv_;
+use std::cell::Cell;
 
+/// Parsed SPIR-V module, shared by shaders created from it with any specialization.
 #[derive(Debug, Clone)]
 pub struct Spirv {
     pub(crate) entry_point: EntryPoint,
+    /// Global objects, specialization constants holding their default values.
     pub(crate) objects: HashMap<ObjectId, Object>,
     pub(crate) functions: HashMap<ObjectId, Function>,
+    /// `SpecId` decorations of specialization constants.
+    pub(crate) spec_ids: HashMap<ObjectId, u32>,
+}
+
+thread_local! {
+    static PARSED_MODULES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Returns number of SPIR-V modules parsed on calling thread so far.
+pub fn parsed_modules() -> usize {
+    PARSED_MODULES.with(Cell::get)
 }
 
 impl Spirv {
-    pub(crate) fn new(name: &str, code: Vec<u32>) -> anyhow::Result<Self> {
+    pub fn new(code: &[u32]) -> anyhow::Result<Self> {
+        PARSED_MODULES.with(|count| count.set(count.get() + 1));
         let mut loader = rspirv::dr::Loader::new();
         assert_eq!(rspirv::spirv::MAGIC_NUMBER, code[0]);
 
-        rspirv::binary::parse_words(&code, &mut loader).map_or_else(
-            |e| bail!("spriv error: {:#?}\nname: {:?}\ncode: {:?}", e, name, code),
+        rspirv::binary::parse_words(code, &mut loader).map_or_else(
+            |e| bail!("spriv error: {:#?}\ncode: {:?}", e, code),
             |_| Ok(()),
         )?;
         let module = loader.module();
@@ -32,26 +50,86 @@ impl Spirv {
         Capability::parse(&module)?;
         MemoryModel::parse(&module)?;
 
-        let objects = Object::parse(&module)?;
+        let mut spec_ids = HashMap::default();
+        let objects = Object::parse(&module, &mut spec_ids)?;
         let functions = Function::parse(&module)?;
 
         Ok(Self {
             entry_point,
             objects,
             functions,
+            spec_ids,
+        })
+    }
+
+    /// Returns module whose specialization constants take values from `specialization` instead of
+    /// their defaults.
+    pub(crate) fn specialize(&self, specialization: &SpecializationInfo) -> Self {
+        let mut spirv = self.clone();
+        for (id, spec_id) in &self.spec_ids {
+            let Some(&specialized) = specialization.constants.get(spec_id) else {
+                continue;
+            };
+            let Some(Object::Constant(Constant::Scalar { type_: _, value })) =
+                spirv.objects.get_mut(id)
+            else {
+                unreachable!()
+            };
+            *value = specialized;
+        }
+        spirv
+    }
+
+    /// Returns whether any variable is decorated with `builtin`.
+    pub(crate) fn has_builtin(&self, builtin: BuiltInDecoration) -> bool {
+        self.objects.values().any(|object| {
+            matches!(
+                object,
+                Object::Variable(Variable::Pointer(Pointer::ToMemoryObject { memory_object }))
+                    if memory_object.decorations.builtin == Some(builtin)
+            )
         })
     }
+
+    /// Returns interpolation of input variables indexed by their locations.
+    pub(crate) fn input_interpolations(&self) -> [Interpolation; MAX_VERTEX_ATTRIBUTES as usize] {
+        let mut interpolations = [Interpolation::default(); MAX_VERTEX_ATTRIBUTES as usize];
+        for object in self.objects.values() {
+            let Object::Variable(Variable::Pointer(Pointer::ToMemoryObject { memory_object })) =
+                object
+            else {
+                continue;
+            };
+            let decorations = &memory_object.decorations;
+            let (StorageClass::Input, Some(location)) =
+                (memory_object.storage_class, decorations.location)
+            else {
+                continue;
+            };
+            interpolations[location.number as usize] = if decorations.flat {
+                Interpolation::Flat
+            } else if decorations.no_perspective {
+                Interpolation::NoPerspective
+            } else {
+                Interpolation::Smooth
+            };
+        }
+        interpolations
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct EntryPoint {
     pub(crate) entry_point: ObjectId,
+    pub(crate) name: String,
     #[allow(dead_code)]
     pub(crate) interfaces: Vec<ObjectId>,
+    /// Number of invocations in workgroup of compute shader along each dimension.
+    pub(crate) local_size: [u32; 3],
 }
 
 impl EntryPoint {
-    /// Parses OpEntryPoint.
+    /// Parses OpEntryPoint along with its OpExecutionMode declaring local size.
     fn parse(module: &Module_) -> anyhow::Result<Self> {
         let entry_point = module
             .entry_points
@@ -59,15 +137,31 @@ impl EntryPoint {
             .context("failed to get spirv entry point")?;
         match &entry_point.operands[..] {
             [Operand_::ExecutionModel(
-                spirv_::ExecutionModel::Vertex | spirv_::ExecutionModel::Fragment,
-            ), Operand_::IdRef(entry_point), _name, interfaces @ ..] => {
+                spirv_::ExecutionModel::Vertex
+                | spirv_::ExecutionModel::Fragment
+                | spirv_::ExecutionModel::GLCompute,
+            ), Operand_::IdRef(entry_point), Operand_::LiteralString(name), interfaces @ ..] => {
                 let interfaces = interfaces
                     .iter()
                     .map(|x| ObjectId(x.unwrap_id_ref()))
                     .collect::<Vec<_>>();
+                let local_size = module
+                    .execution_modes
+                    .iter()
+                    .find_map(|inst| match inst.operands[..] {
+                        [Operand_::IdRef(target), Operand_::ExecutionMode(spirv_::ExecutionMode::LocalSize), Operand_::LiteralInt32(x), Operand_::LiteralInt32(y), Operand_::LiteralInt32(z)]
+                            if target == *entry_point =>
+                        {
+                            Some([x, y, z])
+                        }
+                        _ => None,
+                    })
+                    .unwrap_or([1, 1, 1]);
                 Ok(Self {
                     entry_point: ObjectId(*entry_point),
+                    name: name.clone(),
                     interfaces,
+                    local_size,
                 })
             }
             invalid => {
@@ -148,11 +242,14 @@ pub struct ObjectId(pub u32);
 
 impl Object {
     /// Parses global types, constants and variables.
-    fn parse(module: &Module_) -> anyhow::Result<HashMap<ObjectId, Self>> {
+    fn parse(
+        module: &Module_,
+        spec_ids: &mut HashMap<ObjectId, u32>,
+    ) -> anyhow::Result<HashMap<ObjectId, Self>> {
         let mut types = Type::parse(module)?;
-        let mut constants = Constant::parse(module)?;
+        let constants = Constant::parse(module)?;
         let mut variables = Variable::parse(module)?;
-        Decorations::parse(module, &mut types, &mut constants, &mut variables);
+        Decorations::parse(module, &mut types, &constants, &mut variables, spec_ids);
 
         let mut data = HashMap::default();
         data.extend(types.iter().map(|(id, x)| (*id, Self::Type(x.clone()))));
@@ -187,6 +284,11 @@ pub enum Type {
         length: ObjectId,
         decorations: Decorations,
     },
+    /// Array whose length is determined by size of buffer it is stored in.
+    RuntimeArray {
+        element_type: ObjectId,
+        decorations: Decorations,
+    },
     Vector {
         component_type: ObjectId,
         component_count: u32,
@@ -199,6 +301,10 @@ pub enum Type {
         storage_class: StorageClass,
         type_: ObjectId,
     },
+    /// Image of subpass data dimensionality, read from input attachment.
+    Image {
+        sampled_type: ObjectId,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -267,6 +373,20 @@ impl Type {
                         },
                     );
                 }
+                (
+                    spirv_::Op::TypeRuntimeArray,
+                    None,
+                    &result_id,
+                    &[Operand_::IdRef(element_type)],
+                ) => {
+                    data.insert(
+                        ObjectId(result_id),
+                        Self::RuntimeArray {
+                            element_type: ObjectId(element_type),
+                            decorations: Default::default(),
+                        },
+                    );
+                }
                 (
                     spirv_::Op::TypeVector,
                     None,
@@ -310,9 +430,31 @@ impl Type {
                         },
                     );
                 }
+                (
+                    spirv_::Op::TypeImage,
+                    None,
+                    &result_id,
+                    &[Operand_::IdRef(sampled_type), Operand_::Dim(spirv_::Dim::DimSubpassData), ..],
+                ) => {
+                    data.insert(
+                        ObjectId(result_id),
+                        Self::Image {
+                            sampled_type: ObjectId(sampled_type),
+                        },
+                    );
+                }
                 (spirv_::Op::Variable, _, _, _) => continue,
                 (spirv_::Op::Constant, _, _, _) => continue,
                 (spirv_::Op::ConstantComposite, _, _, _) => continue,
+                (
+                    spirv_::Op::SpecConstantTrue
+                    | spirv_::Op::SpecConstantFalse
+                    | spirv_::Op::SpecConstant
+                    | spirv_::Op::SpecConstantComposite,
+                    _,
+                    _,
+                    _,
+                ) => continue,
                 _ => {
                     unimplemented!("{:#?}\n{:#?}", inst, data)
                 }
@@ -362,6 +504,10 @@ impl Type {
                 length: _,
                 decorations,
             }) => decorations,
+            Some(Self::RuntimeArray {
+                element_type: _,
+                decorations,
+            }) => decorations,
             None => return false,
             _ => unreachable!("{:?}, {:?}", target, type_),
         };
@@ -400,16 +546,32 @@ impl Constant {
                     | spirv_::Op::TypeFloat
                     | spirv_::Op::TypeInt
                     | spirv_::Op::TypeArray
+                    | spirv_::Op::TypeRuntimeArray
                     | spirv_::Op::TypeVector
                     | spirv_::Op::TypeStruct
                     | spirv_::Op::TypePointer
+                    | spirv_::Op::TypeImage
                     | spirv_::Op::Variable,
                     _,
                     _,
                     _,
                 ) => continue,
                 (
-                    spirv_::Op::Constant,
+                    spirv_::Op::SpecConstantTrue | spirv_::Op::SpecConstantFalse,
+                    &Some(result_type),
+                    &result_id,
+                    &[],
+                ) => {
+                    data.insert(
+                        ObjectId(result_id),
+                        Self::Scalar {
+                            type_: ObjectId(result_type),
+                            value: u32::from(*opcode == spirv_::Op::SpecConstantTrue),
+                        },
+                    );
+                }
+                (
+                    spirv_::Op::Constant | spirv_::Op::SpecConstant,
                     &Some(result_type),
                     &result_id,
                     &[Operand_::LiteralInt32(value)],
@@ -423,7 +585,7 @@ impl Constant {
                     );
                 }
                 (
-                    spirv_::Op::Constant,
+                    spirv_::Op::Constant | spirv_::Op::SpecConstant,
                     &Some(result_type),
                     &result_id,
                     &[Operand_::LiteralFloat32(value)],
@@ -436,7 +598,12 @@ impl Constant {
                         },
                     );
                 }
-                (spirv_::Op::ConstantComposite, &Some(result_type), &result_id, operands) => {
+                (
+                    spirv_::Op::ConstantComposite | spirv_::Op::SpecConstantComposite,
+                    &Some(result_type),
+                    &result_id,
+                    operands,
+                ) => {
                     data.insert(
                         ObjectId(result_id),
                         Self::Composite {
@@ -455,6 +622,37 @@ impl Constant {
         }
         Ok(data)
     }
+
+    /// Applies `SpecId` decoration, recording it in `spec_ids` so that default value of
+    /// specialization constant can be replaced when specializing module.
+    fn decorate(
+        data: &HashMap<ObjectId, Self>,
+        target: &spirv_::Word,
+        decoration: &spirv_::Decoration,
+        literals: &[Operand_],
+        spec_ids: &mut HashMap<ObjectId, u32>,
+    ) -> bool {
+        let Some(constant) = data.get(&ObjectId(*target)) else {
+            return false;
+        };
+        match (decoration, literals, constant) {
+            (
+                spirv_::Decoration::SpecId,
+                &[Operand_::LiteralInt32(spec_id)],
+                Self::Scalar { .. },
+            ) => {
+                spec_ids.insert(ObjectId(*target), spec_id);
+            }
+            // Constant already holds local size declared by execution mode.
+            (
+                spirv_::Decoration::BuiltIn,
+                &[Operand_::BuiltIn(spirv_::BuiltIn::WorkgroupSize)],
+                Self::Composite { .. },
+            ) => {}
+            (decoration, literals, _) => unimplemented!("{:?}, {:?}", decoration, literals),
+        }
+        true
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -464,6 +662,9 @@ pub enum StorageClass {
     Function,
     PushConstant,
     Uniform,
+    StorageBuffer,
+    Workgroup,
+    UniformConstant,
 }
 
 impl From<spirv_::StorageClass> for StorageClass {
@@ -474,6 +675,9 @@ impl From<spirv_::StorageClass> for StorageClass {
             spirv_::StorageClass::Function => Self::Function,
             spirv_::StorageClass::PushConstant => Self::PushConstant,
             spirv_::StorageClass::Uniform => Self::Uniform,
+            spirv_::StorageClass::StorageBuffer => Self::StorageBuffer,
+            spirv_::StorageClass::Workgroup => Self::Workgroup,
+            spirv_::StorageClass::UniformConstant => Self::UniformConstant,
             invalid => {
                 unimplemented!("{:#?}", invalid)
             }
@@ -499,11 +703,17 @@ impl Variable {
                     | spirv_::Op::TypeFloat
                     | spirv_::Op::TypeInt
                     | spirv_::Op::TypeArray
+                    | spirv_::Op::TypeRuntimeArray
                     | spirv_::Op::TypeVector
                     | spirv_::Op::TypeStruct
                     | spirv_::Op::TypePointer
+                    | spirv_::Op::TypeImage
                     | spirv_::Op::Constant
-                    | spirv_::Op::ConstantComposite,
+                    | spirv_::Op::ConstantComposite
+                    | spirv_::Op::SpecConstantTrue
+                    | spirv_::Op::SpecConstantFalse
+                    | spirv_::Op::SpecConstant
+                    | spirv_::Op::SpecConstantComposite,
                     _,
                     _,
                     _,
@@ -569,7 +779,6 @@ pub enum Pointer {
 #[derive(Debug, Clone)]
 pub struct MemoryObject {
     pub(crate) type_: ObjectId,
-    #[allow(dead_code)]
     pub(crate) storage_class: StorageClass,
     pub(crate) decorations: Decorations,
 }
@@ -582,8 +791,11 @@ pub struct Decorations {
     pub(crate) relaxed_precision: bool, // TODO: Implement RelaxedPrecision decoration.
     pub(crate) byte_offset: Option<u32>, // TODO: Implement Offset decoration for array member type.
     pub(crate) array_stride: Option<u32>, // TODO: Implement Offset decoration for array type.
-    pub(crate) descriptor_set: Option<u32>, // TODO: Implement DescriptorSet decoration for variable type.
-    pub(crate) binding_point: Option<u32>,  // TODO: Implement Binding decoration for variable type.
+    pub(crate) descriptor_set: Option<u32>,
+    pub(crate) binding_point: Option<u32>,
+    pub(crate) flat: bool,
+    pub(crate) no_perspective: bool,
+    pub(crate) input_attachment_index: Option<u32>,
 }
 
 impl Decorations {
@@ -593,7 +805,8 @@ impl Decorations {
                 assert!(self.builtin.is_none());
                 self.builtin = Some(BuiltInDecoration::new(literal));
             }
-            (spirv_::Decoration::Block, _) => self.block = true,
+            // Storage buffers of SPIR-V before 1.3 are decorated as buffer blocks.
+            (spirv_::Decoration::Block | spirv_::Decoration::BufferBlock, _) => self.block = true,
             (spirv_::Decoration::Location, [location]) => {
                 self.location = Some(LocationDecoration {
                     number: location.unwrap_literal_int32(),
@@ -612,19 +825,30 @@ impl Decorations {
             (spirv_::Decoration::Binding, &[Operand_::LiteralInt32(binding_point)]) => {
                 self.binding_point = Some(binding_point)
             }
+            (spirv_::Decoration::Flat, &[]) => self.flat = true,
+            (spirv_::Decoration::NoPerspective, &[]) => self.no_perspective = true,
+            (
+                spirv_::Decoration::InputAttachmentIndex,
+                &[Operand_::LiteralInt32(input_attachment_index)],
+            ) => self.input_attachment_index = Some(input_attachment_index),
             _ => unimplemented!("{:?}, {:?}", value, literals),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum BuiltInDecoration {
     Position,
     PointSize,
     VertexIndex,
+    InstanceIndex,
     FragCoord,
+    FragDepth,
     ClipDistance,
     CullDistance,
+    GlobalInvocationId,
+    LocalInvocationId,
+    WorkgroupId,
 }
 
 impl BuiltInDecoration {
@@ -633,9 +857,14 @@ impl BuiltInDecoration {
             Operand_::BuiltIn(spirv_::BuiltIn::Position) => Self::Position,
             Operand_::BuiltIn(spirv_::BuiltIn::PointSize) => Self::PointSize,
             Operand_::BuiltIn(spirv_::BuiltIn::VertexIndex) => Self::VertexIndex,
+            Operand_::BuiltIn(spirv_::BuiltIn::InstanceIndex) => Self::InstanceIndex,
             Operand_::BuiltIn(spirv_::BuiltIn::FragCoord) => Self::FragCoord,
+            Operand_::BuiltIn(spirv_::BuiltIn::FragDepth) => Self::FragDepth,
             Operand_::BuiltIn(spirv_::BuiltIn::ClipDistance) => Self::ClipDistance,
             Operand_::BuiltIn(spirv_::BuiltIn::CullDistance) => Self::CullDistance,
+            Operand_::BuiltIn(spirv_::BuiltIn::GlobalInvocationId) => Self::GlobalInvocationId,
+            Operand_::BuiltIn(spirv_::BuiltIn::LocalInvocationId) => Self::LocalInvocationId,
+            Operand_::BuiltIn(spirv_::BuiltIn::WorkgroupId) => Self::WorkgroupId,
             _ => unimplemented!("{operand:?}"),
         }
     }
@@ -651,8 +880,9 @@ impl Decorations {
     fn parse(
         module: &Module_,
         types: &mut HashMap<ObjectId, Type>,
-        _constants: &mut HashMap<ObjectId, Constant>,
+        constants: &HashMap<ObjectId, Constant>,
         variables: &mut HashMap<ObjectId, Variable>,
+        spec_ids: &mut HashMap<ObjectId, u32>,
     ) {
         for inst in &module.annotations {
             let (opcode, None, None, operands) = deconstruct_instruction(inst) else {
@@ -670,6 +900,7 @@ impl Decorations {
                     [Operand_::IdRef(target), Operand_::Decoration(decoration), literals @ ..],
                 ) => {
                     if Type::decorate(types, target, decoration, literals)
+                        || Constant::decorate(constants, target, decoration, literals, spec_ids)
                         || Variable::decorate(variables, target, decoration, literals)
                     {
                         continue;
@@ -916,6 +1147,12 @@ pub enum Instruction {
         result_type: ObjectId,
         storage_class: StorageClass,
     },
+    ImageRead {
+        result_id: ObjectId,
+        result_type: ObjectId,
+        image: ObjectId,
+        coordinate: ObjectId,
+    },
     Select {
         result_id: ObjectId,
         result_type: ObjectId,
@@ -940,6 +1177,9 @@ pub enum Instruction {
     },
     Return,
     Kill,
+    /// Waits for all invocations of workgroup to reach barrier.
+    ControlBarrier,
+    MemoryBarrier,
 }
 
 impl Instruction {
@@ -1199,6 +1439,17 @@ impl Instruction {
                 result_type: ObjectId(result_type),
                 storage_class: storage_class.into(),
             }),
+            (
+                spirv_::Op::ImageRead,
+                &Some(result_type),
+                &Some(result_id),
+                [Operand_::IdRef(image), Operand_::IdRef(coordinate), ..],
+            ) => Ok(Self::ImageRead {
+                result_id: ObjectId(result_id),
+                result_type: ObjectId(result_type),
+                image: ObjectId(*image),
+                coordinate: ObjectId(*coordinate),
+            }),
             (
                 spirv_::Op::Select,
                 &Some(result_type),
@@ -1243,6 +1494,18 @@ impl Instruction {
             }),
             (spirv_::Op::Return, None, None, &[]) => Ok(Self::Return),
             (spirv_::Op::Kill, None, None, &[]) => Ok(Self::Kill),
+            (
+                spirv_::Op::ControlBarrier,
+                None,
+                None,
+                &[Operand_::IdScope(_execution), Operand_::IdScope(_memory), Operand_::IdMemorySemantics(_semantics)],
+            ) => Ok(Self::ControlBarrier),
+            (
+                spirv_::Op::MemoryBarrier,
+                None,
+                None,
+                &[Operand_::IdScope(_memory), Operand_::IdMemorySemantics(_semantics)],
+            ) => Ok(Self::MemoryBarrier),
             _ => {
                 unimplemented!("{instruction:#?}")
             }
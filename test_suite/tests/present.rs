@@ -0,0 +1,758 @@
+#![cfg(feature = "x11")]
+#![allow(non_snake_case)]
+
+use headers::vk_decls::*;
+use std::ffi::c_char;
+use xcb::x;
+
+#[allow(dead_code)]
+mod common;
+
+const WIDTH: u16 = 64;
+const HEIGHT: u16 = 48;
+const EXTENT: VkExtent2D = VkExtent2D {
+    width: WIDTH as u32,
+    height: HEIGHT as u32,
+};
+const FORMAT: VkFormat = VkFormat::VK_FORMAT_R8G8B8A8_UNORM;
+const MIN_IMAGE_COUNT: u32 = 2;
+const CLEAR_COLOR: [f32; 4] = [1.0, 0.2, 0.6, 1.0];
+const EXPECTED_RGB: [u32; 3] = [255, 51, 153];
+
+type GetInstanceProcAddr = unsafe extern "C" fn(VkInstance, *const c_char) -> PFN_vkVoidFunction;
+type GetDeviceProcAddr =
+    unsafe extern "C" fn(VkDevice, Option<NonNull<c_char>>) -> PFN_vkVoidFunction;
+
+macro_rules! load {
+    ($get_proc_addr:expr, $name:literal: fn($($arg:ty),* $(,)?) $(-> $ret:ty)?) => {{
+        let function = $get_proc_addr(concat!($name, "\0").as_ptr().cast());
+        let function = function.unwrap_or_else(|| panic!("{} is not exported", $name));
+        std::mem::transmute::<unsafe extern "C" fn(), unsafe extern "C" fn($($arg),*) $(-> $ret)?>(
+            function,
+        )
+    }};
+}
+
+fn ptr<T>(value: &T) -> Option<NonNull<T>> {
+    Some(NonNull::from(value))
+}
+
+fn ptr_mut<T>(value: &mut T) -> Option<NonNull<T>> {
+    Some(NonNull::from(value))
+}
+
+fn check(result: VkResult) {
+    assert_eq!(result, VkResult::VK_SUCCESS);
+}
+
+fn create_window(connection: &xcb::Connection, screen_num: i32) -> x::Window {
+    let setup = connection.get_setup();
+    let screen = setup.roots().nth(screen_num as usize).unwrap();
+    let window = connection.generate_id();
+    connection
+        .send_and_check_request(&x::CreateWindow {
+            depth: x::COPY_FROM_PARENT as u8,
+            wid: window,
+            parent: screen.root(),
+            x: 0,
+            y: 0,
+            width: WIDTH,
+            height: HEIGHT,
+            border_width: 0,
+            class: x::WindowClass::InputOutput,
+            visual: screen.root_visual(),
+            value_list: &[x::Cw::EventMask(x::EventMask::STRUCTURE_NOTIFY)],
+        })
+        .unwrap();
+    connection
+        .send_and_check_request(&x::MapWindow { window })
+        .unwrap();
+    connection.flush().unwrap();
+    loop {
+        if let xcb::Event::X(x::Event::MapNotify(_)) = connection.wait_for_event().unwrap() {
+            break;
+        }
+    }
+    window
+}
+
+/// Reads back window contents as RGB triples.
+fn read_window(connection: &xcb::Connection, window: x::Window) -> Vec<[u32; 3]> {
+    let cookie = connection.send_request(&x::GetImage {
+        format: x::ImageFormat::ZPixmap,
+        drawable: x::Drawable::Window(window),
+        x: 0,
+        y: 0,
+        width: WIDTH,
+        height: HEIGHT,
+        plane_mask: u32::MAX,
+    });
+    let reply = connection.wait_for_reply(cookie).unwrap();
+
+    let setup = connection.get_setup();
+    let visual = setup
+        .roots()
+        .flat_map(|screen| screen.allowed_depths())
+        .flat_map(|depth| depth.visuals().iter())
+        .find(|visual| visual.visual_id() == reply.visual())
+        .unwrap();
+    let lsb_first = setup.image_byte_order() == x::ImageOrder::LsbFirst;
+    let channel = |pixel: u32, mask: u32| (pixel & mask) >> mask.trailing_zeros();
+
+    reply
+        .data()
+        .chunks_exact(4)
+        .map(|bytes| {
+            let bytes = bytes.try_into().unwrap();
+            let pixel = if lsb_first {
+                u32::from_le_bytes(bytes)
+            } else {
+                u32::from_be_bytes(bytes)
+            };
+            [
+                channel(pixel, visual.red_mask()),
+                channel(pixel, visual.green_mask()),
+                channel(pixel, visual.blue_mask()),
+            ]
+        })
+        .collect()
+}
+
+/// Swapchain created directly through ICD entry points, bypassing Vulkan loader.
+struct TestSwapchain {
+    _cdylib: libloading::Library,
+    get_instance_proc_addr: GetInstanceProcAddr,
+    get_device_proc_addr: GetDeviceProcAddr,
+    instance: VkInstance,
+    physical_device: VkPhysicalDevice,
+    surface: VkSurfaceKHR,
+    device: VkDevice,
+    queue: VkQueue,
+    swapchain: VkSwapchainKHR,
+}
+
+impl TestSwapchain {
+    unsafe fn new(
+        connection: &xcb::Connection,
+        window: x::Window,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let cdylib = libloading::Library::new(common::get_cdylib_path())?;
+        let get_instance_proc_addr =
+            *cdylib.get::<GetInstanceProcAddr>(b"vk_icdGetInstanceProcAddr")?;
+
+        let null_instance = VkDispatchableHandle(None);
+        let instance_proc = |name| get_instance_proc_addr(null_instance, name);
+        let vkCreateInstance = load!(instance_proc, "vkCreateInstance": fn(
+            Option<NonNull<VkInstanceCreateInfo>>,
+            Option<NonNull<VkAllocationCallbacks>>,
+            Option<NonNull<VkInstance>>,
+        ) -> VkResult);
+
+        let instance_create_info = VkInstanceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_INSTANCE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            pApplicationInfo: None,
+            enabledLayerCount: 0,
+            ppEnabledLayerNames: None,
+            enabledExtensionCount: 0,
+            ppEnabledExtensionNames: None,
+        };
+        let mut instance = VkDispatchableHandle(None);
+        check(vkCreateInstance(
+            ptr(&instance_create_info),
+            None,
+            ptr_mut(&mut instance),
+        ));
+
+        let instance_proc = |name| get_instance_proc_addr(instance, name);
+        let vkCreateXcbSurfaceKHR = load!(instance_proc, "vkCreateXcbSurfaceKHR": fn(
+            VkInstance,
+            Option<NonNull<VkXcbSurfaceCreateInfoKHR>>,
+            Option<NonNull<VkAllocationCallbacks>>,
+            Option<NonNull<VkSurfaceKHR>>,
+        ) -> VkResult);
+        let vkEnumeratePhysicalDevices = load!(instance_proc, "vkEnumeratePhysicalDevices": fn(
+            VkInstance,
+            Option<NonNull<u32>>,
+            Option<NonNull<VkPhysicalDevice>>,
+        ) -> VkResult);
+        let vkCreateDevice = load!(instance_proc, "vkCreateDevice": fn(
+            VkPhysicalDevice,
+            Option<NonNull<VkDeviceCreateInfo>>,
+            Option<NonNull<VkAllocationCallbacks>>,
+            Option<NonNull<VkDevice>>,
+        ) -> VkResult);
+        let get_device_proc_addr = load!(instance_proc, "vkGetDeviceProcAddr": fn(
+            VkDevice,
+            Option<NonNull<c_char>>,
+        ) -> PFN_vkVoidFunction);
+
+        let surface_create_info = VkXcbSurfaceCreateInfoKHR {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_XCB_SURFACE_CREATE_INFO_KHR,
+            pNext: None,
+            flags: 0,
+            connection: NonNull::new(connection.get_raw_conn()),
+            window: xcb::Xid::resource_id(&window),
+        };
+        let mut surface = VK_NULL_HANDLE;
+        check(vkCreateXcbSurfaceKHR(
+            instance,
+            ptr(&surface_create_info),
+            None,
+            ptr_mut(&mut surface),
+        ));
+
+        let mut physical_device_count = 1;
+        let mut physical_device = VkDispatchableHandle(None);
+        check(vkEnumeratePhysicalDevices(
+            instance,
+            ptr_mut(&mut physical_device_count),
+            ptr_mut(&mut physical_device),
+        ));
+
+        let queue_priority = 1.0f32;
+        let queue_create_info = VkDeviceQueueCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queueFamilyIndex: 0,
+            queueCount: 1,
+            pQueuePriorities: ptr(&queue_priority),
+        };
+        let device_create_info = VkDeviceCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_DEVICE_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queueCreateInfoCount: 1,
+            pQueueCreateInfos: ptr(&queue_create_info),
+            enabledLayerCount: 0,
+            ppEnabledLayerNames: None,
+            enabledExtensionCount: 0,
+            ppEnabledExtensionNames: None,
+            pEnabledFeatures: None,
+        };
+        let mut device = VkDispatchableHandle(None);
+        check(vkCreateDevice(
+            physical_device,
+            ptr(&device_create_info),
+            None,
+            ptr_mut(&mut device),
+        ));
+
+        let device_proc =
+            |name: *const c_char| get_device_proc_addr(device, NonNull::new(name.cast_mut()));
+        let vkGetDeviceQueue = load!(device_proc, "vkGetDeviceQueue": fn(
+            VkDevice,
+            u32,
+            u32,
+            Option<NonNull<VkQueue>>,
+        ));
+        let vkCreateSwapchainKHR = load!(device_proc, "vkCreateSwapchainKHR": fn(
+            VkDevice,
+            Option<NonNull<VkSwapchainCreateInfoKHR>>,
+            Option<NonNull<VkAllocationCallbacks>>,
+            Option<NonNull<VkSwapchainKHR>>,
+        ) -> VkResult);
+
+        let mut queue = VkDispatchableHandle(None);
+        vkGetDeviceQueue(device, 0, 0, ptr_mut(&mut queue));
+
+        let swapchain_create_info = VkSwapchainCreateInfoKHR {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SWAPCHAIN_CREATE_INFO_KHR,
+            pNext: None,
+            flags: 0,
+            surface,
+            minImageCount: MIN_IMAGE_COUNT,
+            imageFormat: FORMAT,
+            imageColorSpace: VkColorSpaceKHR::VK_COLOR_SPACE_SRGB_NONLINEAR_KHR,
+            imageExtent: EXTENT,
+            imageArrayLayers: 1,
+            imageUsage: VkImageUsageFlagBits::VK_IMAGE_USAGE_COLOR_ATTACHMENT_BIT.0,
+            imageSharingMode: VkSharingMode::VK_SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: None,
+            preTransform: VkSurfaceTransformFlagBitsKHR::VK_SURFACE_TRANSFORM_IDENTITY_BIT_KHR,
+            compositeAlpha: VkCompositeAlphaFlagBitsKHR::VK_COMPOSITE_ALPHA_OPAQUE_BIT_KHR,
+            presentMode: VkPresentModeKHR::VK_PRESENT_MODE_FIFO_KHR,
+            clipped: VK_TRUE,
+            oldSwapchain: VK_NULL_HANDLE,
+        };
+        let mut swapchain = VK_NULL_HANDLE;
+        check(vkCreateSwapchainKHR(
+            device,
+            ptr(&swapchain_create_info),
+            None,
+            ptr_mut(&mut swapchain),
+        ));
+
+        Ok(Self {
+            _cdylib: cdylib,
+            get_instance_proc_addr,
+            get_device_proc_addr,
+            instance,
+            physical_device,
+            surface,
+            device,
+            queue,
+            swapchain,
+        })
+    }
+
+    unsafe fn instance_proc(&self, name: *const c_char) -> PFN_vkVoidFunction {
+        (self.get_instance_proc_addr)(self.instance, name)
+    }
+
+    unsafe fn device_proc(&self, name: *const c_char) -> PFN_vkVoidFunction {
+        (self.get_device_proc_addr)(self.device, NonNull::new(name.cast_mut()))
+    }
+}
+
+#[test]
+fn present_solid_color_to_window() -> common::TestResult {
+    let (connection, screen_num) = xcb::Connection::connect(None)?;
+    let window = create_window(&connection, screen_num);
+
+    unsafe {
+        let test_swapchain = TestSwapchain::new(&connection, window)?;
+        let device_proc = |name| test_swapchain.device_proc(name);
+        let device = test_swapchain.device;
+        let queue = test_swapchain.queue;
+        let swapchain = test_swapchain.swapchain;
+        let vkGetSwapchainImagesKHR = load!(device_proc, "vkGetSwapchainImagesKHR": fn(
+            VkDevice,
+            VkSwapchainKHR,
+            Option<NonNull<u32>>,
+            Option<NonNull<VkImage>>,
+        ) -> VkResult);
+        let vkAcquireNextImageKHR = load!(device_proc, "vkAcquireNextImageKHR": fn(
+            VkDevice,
+            VkSwapchainKHR,
+            u64,
+            VkSemaphore,
+            VkFence,
+            Option<NonNull<u32>>,
+        ) -> VkResult);
+        let vkCreateImageView = load!(device_proc, "vkCreateImageView": fn(
+            VkDevice,
+            Option<NonNull<VkImageViewCreateInfo>>,
+            Option<NonNull<VkAllocationCallbacks>>,
+            Option<NonNull<VkImageView>>,
+        ) -> VkResult);
+        let vkCreateRenderPass = load!(device_proc, "vkCreateRenderPass": fn(
+            VkDevice,
+            Option<NonNull<VkRenderPassCreateInfo>>,
+            Option<NonNull<VkAllocationCallbacks>>,
+            Option<NonNull<VkRenderPass>>,
+        ) -> VkResult);
+        let vkCreateFramebuffer = load!(device_proc, "vkCreateFramebuffer": fn(
+            VkDevice,
+            Option<NonNull<VkFramebufferCreateInfo>>,
+            Option<NonNull<VkAllocationCallbacks>>,
+            Option<NonNull<VkFramebuffer>>,
+        ) -> VkResult);
+        let vkCreateCommandPool = load!(device_proc, "vkCreateCommandPool": fn(
+            VkDevice,
+            Option<NonNull<VkCommandPoolCreateInfo>>,
+            Option<NonNull<VkAllocationCallbacks>>,
+            Option<NonNull<VkCommandPool>>,
+        ) -> VkResult);
+        let vkAllocateCommandBuffers = load!(device_proc, "vkAllocateCommandBuffers": fn(
+            VkDevice,
+            Option<NonNull<VkCommandBufferAllocateInfo>>,
+            Option<NonNull<VkCommandBuffer>>,
+        ) -> VkResult);
+        let vkBeginCommandBuffer = load!(device_proc, "vkBeginCommandBuffer": fn(
+            VkCommandBuffer,
+            Option<NonNull<VkCommandBufferBeginInfo>>,
+        ) -> VkResult);
+        let vkCmdBeginRenderPass = load!(device_proc, "vkCmdBeginRenderPass": fn(
+            VkCommandBuffer,
+            Option<NonNull<VkRenderPassBeginInfo>>,
+            VkSubpassContents,
+        ));
+        let vkCmdEndRenderPass = load!(device_proc, "vkCmdEndRenderPass": fn(VkCommandBuffer));
+        let vkEndCommandBuffer =
+            load!(device_proc, "vkEndCommandBuffer": fn(VkCommandBuffer) -> VkResult);
+        let vkQueueSubmit = load!(device_proc, "vkQueueSubmit": fn(
+            VkQueue,
+            u32,
+            Option<NonNull<VkSubmitInfo>>,
+            VkFence,
+        ) -> VkResult);
+        let vkQueueWaitIdle = load!(device_proc, "vkQueueWaitIdle": fn(VkQueue) -> VkResult);
+        let vkQueuePresentKHR = load!(device_proc, "vkQueuePresentKHR": fn(
+            VkQueue,
+            Option<NonNull<VkPresentInfoKHR>>,
+        ) -> VkResult);
+
+        let mut image_count = 0;
+        check(vkGetSwapchainImagesKHR(
+            device,
+            swapchain,
+            ptr_mut(&mut image_count),
+            None,
+        ));
+        let mut images = vec![VK_NULL_HANDLE; image_count as usize];
+        check(vkGetSwapchainImagesKHR(
+            device,
+            swapchain,
+            ptr_mut(&mut image_count),
+            NonNull::new(images.as_mut_ptr()),
+        ));
+
+        let mut image_index = 0;
+        check(vkAcquireNextImageKHR(
+            device,
+            swapchain,
+            u64::MAX,
+            VK_NULL_HANDLE,
+            VK_NULL_HANDLE,
+            ptr_mut(&mut image_index),
+        ));
+
+        let image_view_create_info = VkImageViewCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_IMAGE_VIEW_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            image: images[image_index as usize],
+            viewType: VkImageViewType::VK_IMAGE_VIEW_TYPE_2D,
+            format: FORMAT,
+            components: VkComponentMapping {
+                r: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+                g: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+                b: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+                a: VkComponentSwizzle::VK_COMPONENT_SWIZZLE_IDENTITY,
+            },
+            subresourceRange: VkImageSubresourceRange {
+                aspectMask: VkImageAspectFlagBits::VK_IMAGE_ASPECT_COLOR_BIT.0,
+                baseMipLevel: 0,
+                levelCount: 1,
+                baseArrayLayer: 0,
+                layerCount: 1,
+            },
+        };
+        let mut image_view = VK_NULL_HANDLE;
+        check(vkCreateImageView(
+            device,
+            ptr(&image_view_create_info),
+            None,
+            ptr_mut(&mut image_view),
+        ));
+
+        let attachment = VkAttachmentDescription {
+            flags: 0,
+            format: FORMAT,
+            samples: VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
+            loadOp: VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR,
+            storeOp: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE,
+            stencilLoadOp: VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE,
+            stencilStoreOp: VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE,
+            initialLayout: VkImageLayout::VK_IMAGE_LAYOUT_UNDEFINED,
+            finalLayout: VkImageLayout::VK_IMAGE_LAYOUT_PRESENT_SRC_KHR,
+        };
+        let color_attachment = VkAttachmentReference {
+            attachment: 0,
+            layout: VkImageLayout::VK_IMAGE_LAYOUT_COLOR_ATTACHMENT_OPTIMAL,
+        };
+        let subpass = VkSubpassDescription {
+            flags: 0,
+            pipelineBindPoint: VkPipelineBindPoint::VK_PIPELINE_BIND_POINT_GRAPHICS,
+            inputAttachmentCount: 0,
+            pInputAttachments: None,
+            colorAttachmentCount: 1,
+            pColorAttachments: ptr(&color_attachment),
+            pResolveAttachments: None,
+            pDepthStencilAttachment: None,
+            preserveAttachmentCount: 0,
+            pPreserveAttachments: None,
+        };
+        let render_pass_create_info = VkRenderPassCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_RENDER_PASS_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            attachmentCount: 1,
+            pAttachments: ptr(&attachment),
+            subpassCount: 1,
+            pSubpasses: ptr(&subpass),
+            dependencyCount: 0,
+            pDependencies: None,
+        };
+        let mut render_pass = VK_NULL_HANDLE;
+        check(vkCreateRenderPass(
+            device,
+            ptr(&render_pass_create_info),
+            None,
+            ptr_mut(&mut render_pass),
+        ));
+
+        let framebuffer_create_info = VkFramebufferCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_FRAMEBUFFER_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            renderPass: render_pass,
+            attachmentCount: 1,
+            pAttachments: ptr(&image_view),
+            width: EXTENT.width,
+            height: EXTENT.height,
+            layers: 1,
+        };
+        let mut framebuffer = VK_NULL_HANDLE;
+        check(vkCreateFramebuffer(
+            device,
+            ptr(&framebuffer_create_info),
+            None,
+            ptr_mut(&mut framebuffer),
+        ));
+
+        let command_pool_create_info = VkCommandPoolCreateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_COMMAND_POOL_CREATE_INFO,
+            pNext: None,
+            flags: 0,
+            queueFamilyIndex: 0,
+        };
+        let mut command_pool = VK_NULL_HANDLE;
+        check(vkCreateCommandPool(
+            device,
+            ptr(&command_pool_create_info),
+            None,
+            ptr_mut(&mut command_pool),
+        ));
+
+        let command_buffer_allocate_info = VkCommandBufferAllocateInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
+            pNext: None,
+            commandPool: command_pool,
+            level: VkCommandBufferLevel::VK_COMMAND_BUFFER_LEVEL_PRIMARY,
+            commandBufferCount: 1,
+        };
+        let mut command_buffer = VkDispatchableHandle(None);
+        check(vkAllocateCommandBuffers(
+            device,
+            ptr(&command_buffer_allocate_info),
+            ptr_mut(&mut command_buffer),
+        ));
+
+        let begin_info = VkCommandBufferBeginInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO,
+            pNext: None,
+            flags: 0,
+            pInheritanceInfo: None,
+        };
+        check(vkBeginCommandBuffer(command_buffer, ptr(&begin_info)));
+        let clear_value = VkClearValue {
+            color: std::mem::ManuallyDrop::new(VkClearColorValue {
+                float32: std::mem::ManuallyDrop::new(CLEAR_COLOR),
+            }),
+        };
+        let render_pass_begin_info = VkRenderPassBeginInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_RENDER_PASS_BEGIN_INFO,
+            pNext: None,
+            renderPass: render_pass,
+            framebuffer,
+            renderArea: VkRect2D {
+                offset: VkOffset2D { x: 0, y: 0 },
+                extent: EXTENT,
+            },
+            clearValueCount: 1,
+            pClearValues: ptr(&clear_value),
+        };
+        vkCmdBeginRenderPass(
+            command_buffer,
+            ptr(&render_pass_begin_info),
+            VkSubpassContents::VK_SUBPASS_CONTENTS_INLINE,
+        );
+        vkCmdEndRenderPass(command_buffer);
+        check(vkEndCommandBuffer(command_buffer));
+
+        let submit_info = VkSubmitInfo {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_SUBMIT_INFO,
+            pNext: None,
+            waitSemaphoreCount: 0,
+            pWaitSemaphores: None,
+            pWaitDstStageMask: None,
+            commandBufferCount: 1,
+            pCommandBuffers: ptr(&command_buffer),
+            signalSemaphoreCount: 0,
+            pSignalSemaphores: None,
+        };
+        check(vkQueueSubmit(queue, 1, ptr(&submit_info), VK_NULL_HANDLE));
+        check(vkQueueWaitIdle(queue));
+
+        let present_info = VkPresentInfoKHR {
+            sType: VkStructureType::VK_STRUCTURE_TYPE_PRESENT_INFO_KHR,
+            pNext: None,
+            waitSemaphoreCount: 0,
+            pWaitSemaphores: None,
+            swapchainCount: 1,
+            pSwapchains: ptr(&swapchain),
+            pImageIndices: ptr(&image_index),
+            pResults: None,
+        };
+        check(vkQueuePresentKHR(queue, ptr(&present_info)));
+    }
+
+    let pixels = read_window(&connection, window);
+    assert_eq!(pixels.len(), WIDTH as usize * HEIGHT as usize);
+    for pixel in pixels {
+        for (actual, expected) in pixel.into_iter().zip(EXPECTED_RGB) {
+            assert!(
+                actual.abs_diff(expected) <= 1,
+                "{pixel:?} != {EXPECTED_RGB:?}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn swapchain_images_are_persistent() -> common::TestResult {
+    let (connection, screen_num) = xcb::Connection::connect(None)?;
+    let window = create_window(&connection, screen_num);
+
+    unsafe {
+        let test_swapchain = TestSwapchain::new(&connection, window)?;
+        let device_proc = |name| test_swapchain.device_proc(name);
+        let device = test_swapchain.device;
+        let swapchain = test_swapchain.swapchain;
+        let vkGetSwapchainImagesKHR = load!(device_proc, "vkGetSwapchainImagesKHR": fn(
+            VkDevice,
+            VkSwapchainKHR,
+            Option<NonNull<u32>>,
+            Option<NonNull<VkImage>>,
+        ) -> VkResult);
+
+        let mut image_count = 0;
+        check(vkGetSwapchainImagesKHR(
+            device,
+            swapchain,
+            ptr_mut(&mut image_count),
+            None,
+        ));
+        assert_eq!(image_count, MIN_IMAGE_COUNT);
+
+        let mut images = vec![VK_NULL_HANDLE; image_count as usize];
+        check(vkGetSwapchainImagesKHR(
+            device,
+            swapchain,
+            ptr_mut(&mut image_count),
+            NonNull::new(images.as_mut_ptr()),
+        ));
+        assert!(images.iter().all(|&image| image != VK_NULL_HANDLE));
+        assert_ne!(images[0], images[1]);
+
+        let mut images_again = vec![VK_NULL_HANDLE; image_count as usize];
+        check(vkGetSwapchainImagesKHR(
+            device,
+            swapchain,
+            ptr_mut(&mut image_count),
+            NonNull::new(images_again.as_mut_ptr()),
+        ));
+        assert_eq!(image_count, MIN_IMAGE_COUNT);
+        assert_eq!(images, images_again);
+
+        let mut image = VK_NULL_HANDLE;
+        image_count = 1;
+        assert_eq!(
+            vkGetSwapchainImagesKHR(
+                device,
+                swapchain,
+                ptr_mut(&mut image_count),
+                ptr_mut(&mut image),
+            ),
+            VkResult::VK_INCOMPLETE
+        );
+        assert_eq!(image_count, 1);
+        assert_eq!(image, images[0]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn surface_reports_window_extent() -> common::TestResult {
+    let (connection, screen_num) = xcb::Connection::connect(None)?;
+    let window = create_window(&connection, screen_num);
+
+    unsafe {
+        let test_swapchain = TestSwapchain::new(&connection, window)?;
+        let instance_proc = |name| test_swapchain.instance_proc(name);
+        let vkGetPhysicalDeviceSurfaceCapabilitiesKHR = load!(
+            instance_proc,
+            "vkGetPhysicalDeviceSurfaceCapabilitiesKHR": fn(
+                VkPhysicalDevice,
+                VkSurfaceKHR,
+                Option<NonNull<VkSurfaceCapabilitiesKHR>>,
+            ) -> VkResult
+        );
+
+        let mut surface_capabilities: VkSurfaceCapabilitiesKHR = std::mem::zeroed();
+        check(vkGetPhysicalDeviceSurfaceCapabilitiesKHR(
+            test_swapchain.physical_device,
+            test_swapchain.surface,
+            ptr_mut(&mut surface_capabilities),
+        ));
+        let current_extent = surface_capabilities.currentExtent;
+        assert_ne!(current_extent.width, u32::MAX);
+        assert_eq!(
+            (current_extent.width, current_extent.height),
+            (EXTENT.width, EXTENT.height)
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn surface_extent_follows_window_resize() -> common::TestResult {
+    let (connection, screen_num) = xcb::Connection::connect(None)?;
+    let window = create_window(&connection, screen_num);
+
+    unsafe {
+        let test_swapchain = TestSwapchain::new(&connection, window)?;
+        let instance_proc = |name| test_swapchain.instance_proc(name);
+        let vkGetPhysicalDeviceSurfaceCapabilitiesKHR = load!(
+            instance_proc,
+            "vkGetPhysicalDeviceSurfaceCapabilitiesKHR": fn(
+                VkPhysicalDevice,
+                VkSurfaceKHR,
+                Option<NonNull<VkSurfaceCapabilitiesKHR>>,
+            ) -> VkResult
+        );
+
+        connection.send_and_check_request(&x::ConfigureWindow {
+            window,
+            value_list: &[
+                x::ConfigWindow::Width(WIDTH as u32 / 2),
+                x::ConfigWindow::Height(HEIGHT as u32 / 2),
+            ],
+        })?;
+        connection.flush()?;
+        loop {
+            if let xcb::Event::X(x::Event::ConfigureNotify(_)) = connection.wait_for_event()? {
+                break;
+            }
+        }
+
+        let mut surface_capabilities: VkSurfaceCapabilitiesKHR = std::mem::zeroed();
+        check(vkGetPhysicalDeviceSurfaceCapabilitiesKHR(
+            test_swapchain.physical_device,
+            test_swapchain.surface,
+            ptr_mut(&mut surface_capabilities),
+        ));
+        let current_extent = surface_capabilities.currentExtent;
+        assert_eq!(
+            (current_extent.width, current_extent.height),
+            (EXTENT.width / 2, EXTENT.height / 2)
+        );
+        let min_image_extent = surface_capabilities.minImageExtent;
+        let max_image_extent = surface_capabilities.maxImageExtent;
+        assert!(min_image_extent.width <= current_extent.width);
+        assert!(min_image_extent.height <= current_extent.height);
+        assert!(max_image_extent.width >= current_extent.width);
+        assert!(max_image_extent.height >= current_extent.height);
+    }
+
+    Ok(())
+}